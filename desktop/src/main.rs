@@ -0,0 +1,34 @@
+//! Native shell around the migration app
+//!
+//! Runs the same `MigrationService` as the web build in a desktop webview.
+//! With `--features desktop`, HTTP goes through reqwest with rustls, and what
+//! the browser keeps in `localStorage` and OPFS is kept under the app's data
+//! directory instead (see `ui::utils::platform::desktop_data_dir`).
+
+#[cfg(feature = "desktop")]
+use dioxus::prelude::*;
+#[cfg(feature = "desktop")]
+use ui::MigrationService;
+
+#[cfg(feature = "desktop")]
+const MAIN_CSS: &str = include_str!("../../web/assets/main.css");
+
+#[cfg(feature = "desktop")]
+fn main() {
+    dioxus::launch(App);
+}
+
+#[cfg(not(feature = "desktop"))]
+fn main() {
+    eprintln!("Built without a renderer; run `cargo run -p desktop --features desktop`");
+    std::process::exit(1);
+}
+
+#[cfg(feature = "desktop")]
+#[component]
+fn App() -> Element {
+    rsx! {
+        document::Style { {MAIN_CSS} }
+        MigrationService {}
+    }
+}
@@ -0,0 +1,140 @@
+//! `/demo` page that migrates a throwaway account between two test PDSes
+
+use dioxus::prelude::*;
+
+use crate::migration::demo_mode::{demo_passed, run_demo, DemoCheck, DemoConfig};
+use crate::migration::types::{MigrationAction, MigrationState};
+use crate::utils::platform::current_page_url;
+
+const MIGRATION_SERVICE_CSS: Asset = asset!("/assets/styling/migration_service.css");
+
+/// Create, fill and migrate a throwaway account, listing each check
+#[component]
+pub fn DemoMode() -> Element {
+    let (initial, autorun) = use_hook(|| {
+        current_page_url()
+            .map(|url| DemoConfig::from_page_url(&url))
+            .unwrap_or_default()
+    });
+    let mut source_url = use_signal(|| initial.source_pds_url.clone());
+    let mut target_url = use_signal(|| initial.target_pds_url.clone());
+    let mut invite_code = use_signal(|| initial.invite_code.clone().unwrap_or_default());
+    let mut checks = use_signal(Vec::<DemoCheck>::new);
+    let mut running = use_signal(|| false);
+    let mut finished = use_signal(|| false);
+    // Progress of the migration plan, reduced like the real migration's state
+    let mut migration = use_signal(MigrationState::default);
+    let dispatch = EventHandler::new(move |action: MigrationAction| {
+        migration.with_mut(|state| state.reduce_in_place(action));
+    });
+
+    let mut start = move || {
+        let invite = invite_code().trim().to_string();
+        let config = DemoConfig {
+            source_pds_url: source_url(),
+            target_pds_url: target_url(),
+            invite_code: (!invite.is_empty()).then_some(invite),
+        };
+        checks.set(Vec::new());
+        migration.set(MigrationState::default());
+        finished.set(false);
+        running.set(true);
+        spawn(async move {
+            run_demo(&config, dispatch, |check| {
+                checks.write().push(check.clone())
+            })
+            .await;
+            running.set(false);
+            finished.set(true);
+        });
+    };
+
+    use_hook(move || {
+        if autorun && initial.validate().is_ok() {
+            start();
+        }
+    });
+
+    let passed = demo_passed(&checks());
+    let step = migration().migration_step;
+
+    rsx! {
+        document::Link { rel: "stylesheet", href: MIGRATION_SERVICE_CSS }
+
+        div {
+            class: "migration-service-container",
+            h1 {
+                class: "migration-title",
+                "Try a migration with a test account"
+            }
+            p {
+                class: "support-bundle-help",
+                "Creates a brand-new throwaway account on a test PDS, gives it a profile, a few posts, an image, a list and some preferences, then migrates it to a second test PDS and compares the two. Your real account is never touched. The final identity switch is skipped because it needs a code emailed to the account."
+            }
+            input {
+                class: "input-field",
+                placeholder: "Source test PDS URL (e.g. https://pds-a.example.com)",
+                value: "{source_url}",
+                oninput: move |event| source_url.set(event.value()),
+            }
+            input {
+                class: "input-field",
+                placeholder: "Target test PDS URL (e.g. https://pds-b.example.com)",
+                value: "{target_url}",
+                oninput: move |event| target_url.set(event.value()),
+            }
+            input {
+                class: "input-field",
+                placeholder: "Invite code, if the test PDSes need one",
+                value: "{invite_code}",
+                oninput: move |event| invite_code.set(event.value()),
+            }
+            button {
+                class: "validate-button",
+                disabled: running() || source_url().trim().is_empty() || target_url().trim().is_empty(),
+                onclick: move |_| start(),
+                if running() { "Running…" } else { "Run demo" }
+            }
+
+            if running() && !step.is_empty() {
+                p { class: "demo-mode-step", "{step}" }
+            }
+
+            if !checks().is_empty() {
+                ul {
+                    class: "self-test-checks",
+                    for check in checks() {
+                        li {
+                            class: if check.passed { "self-test-check passed" } else { "self-test-check failed" },
+                            span {
+                                class: "self-test-layer",
+                                "{check.stage.label()}"
+                            }
+                            strong {
+                                if check.passed { "✓ " } else { "✗ " }
+                                "{check.name}"
+                            }
+                            span {
+                                class: "self-test-detail",
+                                " {check.detail} ({check.duration_ms} ms)"
+                            }
+                        }
+                    }
+                }
+            }
+
+            if finished() {
+                div {
+                    id: "demo-result",
+                    "data-demo-result": if passed { "passed" } else { "failed" },
+                    class: if passed { "validation-result success" } else { "validation-result" },
+                    if passed {
+                        "✓ The throwaway account was migrated and both PDSes hold the same content."
+                    } else {
+                        "✗ The demo stopped at the failed check; checks after it were not run."
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,318 @@
+//! Public types for embedding `MigrationService` in a host Dioxus application
+
+use serde::Serialize;
+
+use crate::migration::timeline::{StepStatus, TimelineStep};
+use crate::migration::{FormStep, MigrationState};
+
+/// Lifecycle notifications delivered to the host through `MigrationService::on_event`
+#[derive(Debug, Clone, PartialEq)]
+pub enum MigrationLifecycleEvent {
+    /// The visible form step changed
+    StepChanged(FormStep),
+    /// The automated migration run started
+    MigrationStarted,
+    /// The automated migration run stopped (completed, paused for PLC, or failed)
+    MigrationStopped,
+    /// A migration error was reported to the user
+    MigrationFailed(String),
+    /// The whole migration, including the PLC update, finished
+    MigrationCompleted,
+    /// The run ended for good; sent once per outcome, after the events above
+    MigrationFinished(MigrationOutcome),
+}
+
+/// How a migration ended, for hosts that automate follow-up work
+///
+/// Serializes with an `outcome` tag (`success`, `partial_with_failed_blobs`,
+/// `aborted`, `failed_at_step`) so it can be forwarded as JSON unchanged.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum MigrationOutcome {
+    /// Everything moved and the identity points at the new PDS
+    Success { did: String, new_pds: String },
+    /// The identity moved, but some blobs could not be recovered from anywhere
+    PartialWithFailedBlobs {
+        did: String,
+        new_pds: String,
+        failed_blobs: Vec<String>,
+    },
+    /// The user stopped the run
+    Aborted { step: TimelineStep, reason: String },
+    /// A step failed and the run stopped
+    FailedAtStep { step: TimelineStep, error: String },
+}
+
+impl MigrationOutcome {
+    /// The outcome `state` represents, or `None` while a run is going or hasn't ended
+    pub fn from_state(state: &MigrationState) -> Option<Self> {
+        if state.migration_completed {
+            let (did, new_pds) = state
+                .new_pds_session
+                .as_ref()
+                .map(|session| (session.did.clone(), session.pds.clone()))
+                .unwrap_or_default();
+            return Some(if state.unrecoverable_blobs.is_empty() {
+                MigrationOutcome::Success { did, new_pds }
+            } else {
+                MigrationOutcome::PartialWithFailedBlobs {
+                    did,
+                    new_pds,
+                    failed_blobs: state.unrecoverable_blobs.clone(),
+                }
+            });
+        }
+
+        if state.is_migrating || state.form4.is_verifying {
+            return None;
+        }
+        let error = state.migration_error.clone()?;
+        // The first step that isn't done is the one that stopped
+        let step = state
+            .timeline_statuses()
+            .iter()
+            .position(|status| *status != StepStatus::Done)
+            .map_or(TimelineStep::Activation, |i| TimelineStep::ALL[i]);
+        Some(if state.migration_aborted {
+            MigrationOutcome::Aborted {
+                step,
+                reason: error,
+            }
+        } else {
+            MigrationOutcome::FailedAtStep { step, error }
+        })
+    }
+
+    /// The outcome as a JSON object, for hosts passing it on to scripts or webhooks
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// Visual overrides applied to the migration container as inline styles
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ThemeOverrides {
+    pub background_color: Option<String>,
+    pub text_color: Option<String>,
+    pub font_family: Option<String>,
+    pub max_width: Option<String>,
+}
+
+impl ThemeOverrides {
+    /// Build the inline `style` attribute for the container; empty when nothing is overridden
+    pub fn to_inline_style(&self) -> String {
+        [
+            ("background-color", &self.background_color),
+            ("color", &self.text_color),
+            ("font-family", &self.font_family),
+            ("max-width", &self.max_width),
+        ]
+        .iter()
+        .filter_map(|(property, value)| {
+            value
+                .as_ref()
+                .map(|value| format!("{}: {};", property, value))
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+    }
+}
+
+/// Optional behaviour toggles for embedded deployments
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EmbedFeatures {
+    /// Hide the tutorial video accordion
+    pub hide_video_accordion: bool,
+    /// Fix the target PDS to this URL and skip manual PDS selection
+    pub locked_pds_url: Option<String>,
+}
+
+/// The subset of state that lifecycle events are derived from
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LifecycleSnapshot {
+    current_step: FormStep,
+    is_migrating: bool,
+    migration_error: Option<String>,
+    migration_completed: bool,
+    is_verifying: bool,
+    outcome: Option<MigrationOutcome>,
+}
+
+impl LifecycleSnapshot {
+    pub(crate) fn capture(state: &MigrationState) -> Self {
+        Self {
+            current_step: state.current_step.clone(),
+            is_migrating: state.is_migrating,
+            migration_error: state.migration_error.clone(),
+            migration_completed: state.migration_completed,
+            is_verifying: state.form4.is_verifying,
+            outcome: MigrationOutcome::from_state(state),
+        }
+    }
+}
+
+/// Compute the lifecycle events caused by a transition between two snapshots
+pub(crate) fn lifecycle_events(
+    before: &LifecycleSnapshot,
+    after: &LifecycleSnapshot,
+) -> Vec<MigrationLifecycleEvent> {
+    let mut events = Vec::new();
+
+    if before.current_step != after.current_step {
+        events.push(MigrationLifecycleEvent::StepChanged(
+            after.current_step.clone(),
+        ));
+    }
+
+    match (before.is_migrating, after.is_migrating) {
+        (false, true) => events.push(MigrationLifecycleEvent::MigrationStarted),
+        (true, false) => events.push(MigrationLifecycleEvent::MigrationStopped),
+        _ => {}
+    }
+
+    if let Some(error) = &after.migration_error {
+        if before.migration_error.as_ref() != Some(error) {
+            events.push(MigrationLifecycleEvent::MigrationFailed(error.clone()));
+        }
+    }
+
+    if !before.migration_completed && after.migration_completed {
+        events.push(MigrationLifecycleEvent::MigrationCompleted);
+    }
+
+    // Only report an outcome when a run (or the form 4 verification) stops or
+    // completes, so errors shown before a run starts aren't mistaken for one
+    let was_running = before.is_migrating || before.is_verifying;
+    let running = after.is_migrating || after.is_verifying;
+    let ended = (was_running && !running) || after.migration_completed;
+    if let Some(outcome) = &after.outcome {
+        if ended && before.outcome.as_ref() != Some(outcome) {
+            events.push(MigrationLifecycleEvent::MigrationFinished(outcome.clone()));
+        }
+    }
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> LifecycleSnapshot {
+        LifecycleSnapshot::capture(&MigrationState::default())
+    }
+
+    #[test]
+    fn test_no_events_without_changes() {
+        assert!(lifecycle_events(&snapshot(), &snapshot()).is_empty());
+    }
+
+    #[test]
+    fn test_transition_events() {
+        let before = snapshot();
+        let mut after = snapshot();
+        after.current_step = FormStep::PlcVerification;
+        after.is_migrating = true;
+        after.migration_error = Some("boom".to_string());
+
+        assert_eq!(
+            lifecycle_events(&before, &after),
+            vec![
+                MigrationLifecycleEvent::StepChanged(FormStep::PlcVerification),
+                MigrationLifecycleEvent::MigrationStarted,
+                MigrationLifecycleEvent::MigrationFailed("boom".to_string()),
+            ]
+        );
+
+        let mut completed = after.clone();
+        completed.is_migrating = false;
+        completed.migration_completed = true;
+        assert_eq!(
+            lifecycle_events(&after, &completed),
+            vec![
+                MigrationLifecycleEvent::MigrationStopped,
+                MigrationLifecycleEvent::MigrationCompleted,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_outcome_reported_once_when_a_run_ends() {
+        use crate::migration::types::{MigrationAction, SessionCredentials};
+
+        let mut state = MigrationState::default();
+        state.form1.session_stored = true;
+        state.reduce_in_place(MigrationAction::SetMigrationError(Some(
+            "Handle is taken".to_string(),
+        )));
+        // An error before any run started isn't an outcome
+        assert!(
+            !lifecycle_events(&snapshot(), &LifecycleSnapshot::capture(&state))
+                .iter()
+                .any(|event| matches!(event, MigrationLifecycleEvent::MigrationFinished(_)))
+        );
+
+        state.reduce_in_place(MigrationAction::SetMigrating(true));
+        let running = LifecycleSnapshot::capture(&state);
+        state.reduce_in_place(MigrationAction::SetMigrating(false));
+        let stopped = LifecycleSnapshot::capture(&state);
+        let finished = MigrationOutcome::FailedAtStep {
+            step: TimelineStep::Account,
+            error: "Handle is taken".to_string(),
+        };
+        assert_eq!(
+            lifecycle_events(&running, &stopped).last(),
+            Some(&MigrationLifecycleEvent::MigrationFinished(
+                finished.clone()
+            ))
+        );
+        assert!(finished
+            .to_json()
+            .starts_with(r#"{"outcome":"failed_at_step","step":"Account""#));
+
+        state.reduce_in_place(MigrationAction::SetMigrationAborted(true));
+        assert!(matches!(
+            MigrationOutcome::from_state(&state),
+            Some(MigrationOutcome::Aborted { .. })
+        ));
+
+        state.reduce_in_place(MigrationAction::SetNewPdsSession(Some(
+            SessionCredentials {
+                did: "did:plc:alice".to_string(),
+                handle: "alice.new.example".to_string(),
+                pds: "https://new.example".to_string(),
+                access_jwt: String::new(),
+                refresh_jwt: String::new(),
+            },
+        )));
+        state.reduce_in_place(MigrationAction::SetUnrecoverableBlobs(vec![
+            "bafkreimissing".to_string(),
+        ]));
+        state.reduce_in_place(MigrationAction::SetMigrationCompleted(true));
+        let completed = LifecycleSnapshot::capture(&state);
+        let events = lifecycle_events(&stopped, &completed);
+        assert!(events.contains(&MigrationLifecycleEvent::MigrationFinished(
+            MigrationOutcome::PartialWithFailedBlobs {
+                did: "did:plc:alice".to_string(),
+                new_pds: "https://new.example".to_string(),
+                failed_blobs: vec!["bafkreimissing".to_string()],
+            }
+        )));
+        assert!(lifecycle_events(&completed, &completed).is_empty());
+    }
+
+    #[test]
+    fn test_theme_inline_style() {
+        assert_eq!(ThemeOverrides::default().to_inline_style(), "");
+
+        let theme = ThemeOverrides {
+            background_color: Some("#000".to_string()),
+            max_width: Some("100%".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            theme.to_inline_style(),
+            "background-color: #000; max-width: 100%;"
+        );
+    }
+}
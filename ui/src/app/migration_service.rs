@@ -1,13 +1,40 @@
-use crate::console_info;
+use crate::{console_info, console_warn};
 use dioxus::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
 
 // New import paths after refactoring
+#[cfg(feature = "session-transfer")]
+use crate::components::display::SessionTransferPanel;
+#[cfg(feature = "support-bundle")]
+use crate::components::display::SupportBundlePanel;
+use crate::components::display::{HelpModePanel, PanicRecoveryNotice};
+#[cfg(feature = "diagnostics-panel")]
+use crate::components::display::PipelineMetricsPanel;
+#[cfg(feature = "video-tutorial")]
 use crate::components::display::VideoAccordion;
 use crate::components::forms::{MigrationDetailsForm, PdsSelectionForm, PlcVerificationForm};
-use crate::migration::{FormStep, MigrationAction, MigrationState};
+use crate::migration::event_log::{write_spill_to_opfs, SPILL_BATCH_SIZE};
+use crate::migration::journal::{clear_journal, persist_journal, record_journal};
+use crate::migration::plc_token_delivery::load_plc_token_delivery;
+use crate::migration::progress::{is_live_progress, ProgressStore, ProgressThrottle};
+use crate::migration::{FormStep, MigrationAction, MigrationEventLog, MigrationState};
+use crate::services::config::{get_global_config, set_global_config, MigrationConfig};
+use crate::services::streaming::detect_storage_capabilities;
+use crate::utils::byte_format::set_byte_units;
+use crate::utils::log_filter::{set_debug_rate_limit, set_log_filter, LogFilter};
+use crate::utils::platform::{now_ms, now_secs, sleep_ms};
+
+use super::embed::{
+    lifecycle_events, EmbedFeatures, LifecycleSnapshot, MigrationLifecycleEvent, ThemeOverrides,
+};
+use super::panic_recovery::{clear_panic_report, install_panic_hook, pending_panic_report};
 
 #[cfg(feature = "web")]
-use crate::components::forms::ClientLoginFormComponent;
+use crate::components::forms::{
+    ClientLoginFormComponent, OldAccountDeactivationReminder, OldAccountDeletionReminder,
+    OrphanCleanupPanel,
+};
 
 // Feature will temporarily alias LocalStorageManager until we update it
 use crate::migration::storage::LocalStorageManager;
@@ -38,10 +65,161 @@ fn render_login_form(
     }
 }
 
+/// Render the tutorial video accordion when the video-tutorial feature is compiled in
+fn render_video_accordion(hidden: bool) -> Element {
+    #[cfg(feature = "video-tutorial")]
+    {
+        rsx! {
+            if !hidden {
+                VideoAccordion {}
+            }
+        }
+    }
+
+    #[cfg(not(feature = "video-tutorial"))]
+    {
+        let _ = hidden;
+        rsx! {}
+    }
+}
+
+/// Render the pipeline metrics panel in debug builds with the diagnostics-panel feature
+fn render_pipeline_metrics_panel() -> Element {
+    #[cfg(feature = "diagnostics-panel")]
+    {
+        rsx! {
+            if cfg!(debug_assertions) {
+                PipelineMetricsPanel {}
+            }
+        }
+    }
+
+    #[cfg(not(feature = "diagnostics-panel"))]
+    {
+        rsx! {}
+    }
+}
+
+/// Render the support bundle panel when the support-bundle feature is compiled in
+fn render_support_bundle_panel(state: Signal<MigrationState>) -> Element {
+    #[cfg(feature = "support-bundle")]
+    {
+        rsx! {
+            SupportBundlePanel { state: state }
+        }
+    }
+
+    #[cfg(not(feature = "support-bundle"))]
+    {
+        let _ = state;
+        rsx! {}
+    }
+}
+
+/// Render the hand-over to another device when the session-transfer feature is compiled in
+fn render_session_transfer_panel(
+    state: Signal<MigrationState>,
+    dispatch: EventHandler<MigrationAction>,
+) -> Element {
+    #[cfg(feature = "session-transfer")]
+    {
+        rsx! {
+            SessionTransferPanel { state: state, dispatch: dispatch }
+        }
+    }
+
+    #[cfg(not(feature = "session-transfer"))]
+    {
+        let _ = (state, dispatch);
+        rsx! {}
+    }
+}
+
+/// Render the orphaned account cleanup tool (web feature only)
+fn render_orphan_cleanup_panel() -> Element {
+    #[cfg(feature = "web")]
+    {
+        rsx! {
+            OrphanCleanupPanel {}
+        }
+    }
+
+    #[cfg(not(feature = "web"))]
+    {
+        rsx! {}
+    }
+}
+
+/// Render the reminder to deactivate an old account left active (web feature only)
+fn render_old_account_deactivation_reminder() -> Element {
+    #[cfg(feature = "web")]
+    {
+        rsx! {
+            OldAccountDeactivationReminder {}
+        }
+    }
+
+    #[cfg(not(feature = "web"))]
+    {
+        rsx! {}
+    }
+}
+
+/// Render the reminder for a scheduled old account deletion (web feature only)
+fn render_old_account_deletion_reminder() -> Element {
+    #[cfg(feature = "web")]
+    {
+        rsx! {
+            OldAccountDeletionReminder {}
+        }
+    }
+
+    #[cfg(not(feature = "web"))]
+    {
+        rsx! {}
+    }
+}
+
+/// Props for embedding the migration flow in a host application; all are optional
+#[derive(Props, PartialEq, Clone)]
+pub struct MigrationServiceProps {
+    /// Configuration installed as the global migration config on first render
+    #[props(default)]
+    pub config: Option<MigrationConfig>,
+    /// Called on step changes, migration start/stop, errors and completion
+    #[props(default)]
+    pub on_event: Option<EventHandler<MigrationLifecycleEvent>>,
+    #[props(default)]
+    pub theme: ThemeOverrides,
+    #[props(default)]
+    pub features: EmbedFeatures,
+}
+
 #[component]
-pub fn MigrationService() -> Element {
+pub fn MigrationService(props: MigrationServiceProps) -> Element {
     // Consolidated state management
     let mut state = use_signal(MigrationState::default);
+    // Append-only action log; the state signal above is derived from it and it is
+    // shared through context so debugging tools can replay earlier states
+    let mut event_log = use_context_provider(|| Signal::new(MigrationEventLog::default()));
+    let on_event = props.on_event;
+    let features = props.features.clone();
+    let container_style = props.theme.to_inline_style();
+
+    // Install host configuration once, before any migration reads the global config
+    let initial_config = props.config.clone();
+    use_hook(move || {
+        if let Some(config) = initial_config {
+            if let Err(e) = set_global_config(config) {
+                console_warn!("[Migration Service] Ignoring host configuration: {}", e);
+            }
+        }
+    });
+
+    // A panic shows a static error screen instead of freezing the page, and a
+    // report left by one before the last reload offers to resume from the journal
+    use_hook(install_panic_hook);
+    let mut panic_report = use_signal(pending_panic_report);
 
     // Check for incomplete migration on startup
     use_effect(move || {
@@ -53,10 +231,130 @@ pub fn MigrationService() -> Element {
         }
     });
 
-    // Dispatch function for actions - using in-place reduction to preserve Dioxus Signal reactivity
+    // Live progress for the progress displays, kept out of the state signal so
+    // byte counters don't re-render every form
+    let mut progress = use_context_provider(ProgressStore::new);
+    // Progress still reaches the state, just less often
+    let state_throttle = use_hook(|| {
+        Rc::new(RefCell::new(ProgressThrottle::new(
+            get_global_config().progress.state_updates_per_second,
+        )))
+    });
+    let trailing_flush = use_hook(|| Rc::new(Cell::new(false)));
+
+    // Records the action and reduces in place to preserve Dioxus Signal reactivity
+    let apply_to_state = move |action: MigrationAction| {
+        let (events, spill) = state.with_mut(|s| {
+            let before = LifecycleSnapshot::capture(s);
+            event_log.with_mut(|log| {
+                log.apply(s, action);
+                record_journal(s, now_secs());
+                let events = lifecycle_events(&before, &LifecycleSnapshot::capture(s));
+                // Lifecycle transitions are rare, so they're where the log gets checked
+                // and the journal written out
+                if !events.is_empty() {
+                    log.verify_or_rebuild(s);
+                    persist_journal();
+                }
+                let spill = (log.pending_spill_len() >= SPILL_BATCH_SIZE)
+                    .then(|| log.take_spill())
+                    .flatten();
+                (events, spill)
+            })
+        });
+
+        if let Some(spill) = spill {
+            spawn(async move {
+                if let Err(e) = write_spill_to_opfs(&spill).await {
+                    console_warn!("[Migration Service] Could not spill the action log: {}", e);
+                }
+            });
+        }
+
+        if let Some(on_event) = on_event {
+            for event in events {
+                on_event.call(event);
+            }
+        }
+    };
+
+    // Dispatch function for actions - progress goes to the progress store at once
+    // and to the state through the slower throttle
     let dispatch = EventHandler::new(move |action: MigrationAction| {
-        state.with_mut(|s| {
-            s.reduce_in_place(action);
+        let mut apply_to_state = apply_to_state;
+        let live = is_live_progress(&action);
+        if live {
+            progress.apply(&action);
+        }
+        let ready = state_throttle
+            .borrow_mut()
+            .push(action, now_ms() as u64);
+        for action in ready {
+            apply_to_state(action);
+        }
+
+        let pending = state_throttle.borrow().has_pending();
+        if !live && !pending {
+            // Resets and restores change progress without a progress action
+            progress.sync_from(&state.peek());
+        }
+
+        // The last value of a burst reaches the state once the interval has passed
+        if pending && !trailing_flush.get() {
+            trailing_flush.set(true);
+            let (throttle, scheduled) = (Rc::clone(&state_throttle), Rc::clone(&trailing_flush));
+            let delay_ms = throttle.borrow().min_interval_ms();
+            spawn(async move {
+                sleep_ms(delay_ms).await;
+                scheduled.set(false);
+                let ready = throttle.borrow_mut().flush();
+                for action in ready {
+                    apply_to_state(action);
+                }
+            });
+        }
+    });
+
+    // A PLC token email requested before a refresh is still worth waiting for
+    use_effect(move || {
+        if let Some(delivery) = load_plc_token_delivery() {
+            dispatch.call(MigrationAction::SetPlcTokenDelivery(Some(delivery)));
+        }
+    });
+
+    // The text-only progress choice applies to every later migration too
+    use_effect(move || {
+        if let Ok(true) = LocalStorageManager::get_text_only_progress() {
+            dispatch.call(MigrationAction::SetTextOnlyProgress(true));
+        }
+    });
+
+    // So is the choice of size units
+    use_effect(move || {
+        if let Ok(units) = LocalStorageManager::get_byte_units() {
+            set_byte_units(units);
+            dispatch.call(MigrationAction::SetByteUnits(units));
+        }
+    });
+
+    // Console verbosity: a filter saved in this browser, else the deployment's
+    use_effect(move || {
+        let logging = get_global_config().logging;
+        set_debug_rate_limit(logging.debug_logs_per_second);
+        let filter = LocalStorageManager::get_log_filter()
+            .ok()
+            .and_then(|saved| LogFilter::parse(&saved).ok())
+            .or_else(|| LogFilter::parse(&logging.filter).ok())
+            .unwrap_or_default();
+        dispatch.call(MigrationAction::SetLogFilter(filter.to_string()));
+        set_log_filter(filter);
+    });
+
+    // Probe persistent storage once so the form can warn about the minimal flow
+    use_effect(move || {
+        spawn(async move {
+            let capabilities = detect_storage_capabilities().await;
+            dispatch.call(MigrationAction::SetStorageCapabilities(Some(capabilities)));
         });
     });
 
@@ -65,6 +363,7 @@ pub fn MigrationService() -> Element {
 
         div {
             class: "migration-service-container",
+            style: "{container_style}",
 
             div {
                 class: "title-container",
@@ -80,7 +379,7 @@ pub fn MigrationService() -> Element {
             }
 
             // Video Tutorial Accordion
-            VideoAccordion {}
+            {render_video_accordion(features.hide_video_accordion)}
 
             // Recommendations Banner
             div {
@@ -108,6 +407,36 @@ pub fn MigrationService() -> Element {
                 }
             }
 
+            // The page crashed before the last reload
+            if let Some(report) = panic_report() {
+                PanicRecoveryNotice {
+                    report: report.clone(),
+                    on_resume: move |_| {
+                        if let Some(journal) = report.journal.clone() {
+                            for action in journal.actions() {
+                                dispatch.call(action);
+                            }
+                        }
+                        clear_panic_report();
+                        panic_report.set(None);
+                    },
+                    on_dismiss: move |_| {
+                        clear_panic_report();
+                        clear_journal();
+                        panic_report.set(None);
+                    },
+                }
+            }
+
+            // Reminder left by an earlier migration that kept the old account active
+            {render_old_account_deactivation_reminder()}
+
+            // Reminder left by an earlier migration to delete the old account
+            {render_old_account_deletion_reminder()}
+
+            // Move a half-filled migration between phone and desktop
+            {render_session_transfer_panel(state, dispatch)}
+
             // Form 1: Login to Current PDS - Using Client-side by default
             div {
                 class: if state().current_step == FormStep::PlcVerification { "form-frozen" } else { "" },
@@ -120,7 +449,8 @@ pub fn MigrationService() -> Element {
                     class: if state().current_step == FormStep::PlcVerification { "form-frozen" } else { "" },
                     PdsSelectionForm {
                         state: state,
-                        dispatch: dispatch
+                        dispatch: dispatch,
+                        locked_pds_url: features.locked_pds_url.clone()
                     }
                 }
             }
@@ -143,6 +473,17 @@ pub fn MigrationService() -> Element {
                     dispatch: dispatch
                 }
             }
+
+            // Cleanup for accounts left behind by abandoned attempts
+            {render_orphan_cleanup_panel()}
+
+            // Live status a community helper can load
+            HelpModePanel { state: state }
+
+            // Diagnostics for support escalation
+            {render_support_bundle_panel(state)}
+
+            {render_pipeline_metrics_panel()}
         }
     }
 }
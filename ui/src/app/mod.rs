@@ -1,3 +1,14 @@
+#[cfg(feature = "demo-mode")]
+pub mod demo_mode;
+pub mod embed;
 pub mod migration_service;
+pub mod panic_recovery;
+#[cfg(feature = "web")]
+pub mod self_test;
 
-pub use migration_service::MigrationService;
+#[cfg(feature = "demo-mode")]
+pub use demo_mode::DemoMode;
+pub use embed::{EmbedFeatures, MigrationLifecycleEvent, MigrationOutcome, ThemeOverrides};
+pub use migration_service::{MigrationService, MigrationServiceProps};
+#[cfg(feature = "web")]
+pub use self_test::SelfTest;
@@ -0,0 +1,145 @@
+//! Turning a WASM panic into something the user can act on
+//!
+//! After a panic the Dioxus runtime is gone, so nothing rendered through it
+//! updates again and the page just looks frozen. The hook installed here keeps
+//! the panic message, where it happened and the journal position in session
+//! storage, then writes a plain HTML overlay straight into the document with a
+//! link that reloads the page. On the next load `MigrationService` finds the
+//! report and offers to resume from the journal.
+
+use serde::{Deserialize, Serialize};
+
+use crate::migration::journal::MigrationJournal;
+use crate::utils::platform::{storage_delete, storage_get, StorageArea};
+
+/// Session storage key for the report of the last panic
+const PANIC_REPORT_STORAGE_KEY: &str = "panic_report";
+
+/// What went wrong, kept across the reload
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PanicReport {
+    pub message: String,
+    /// `file:line:column` of the panic, if known
+    pub location: Option<String>,
+    pub occurred_at_secs: u64,
+    pub journal: Option<MigrationJournal>,
+}
+
+impl PanicReport {
+    /// Where the migration was when it stopped
+    pub fn step_label(&self) -> String {
+        self.journal
+            .as_ref()
+            .map(MigrationJournal::step_label)
+            .unwrap_or_else(|| "the start".to_string())
+    }
+}
+
+/// Text of a panic payload, which is a `&str` or `String` for `panic!` and friends
+pub fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+/// The report left by a panic before the last reload, if any
+pub fn pending_panic_report() -> Option<PanicReport> {
+    storage_get(StorageArea::Session, PANIC_REPORT_STORAGE_KEY).ok()
+}
+
+pub fn clear_panic_report() {
+    storage_delete(StorageArea::Session, PANIC_REPORT_STORAGE_KEY);
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Static markup for the overlay shown in place of the frozen page
+pub fn overlay_html(report: &PanicReport, reload_url: &str) -> String {
+    format!(
+        r#"<div class="panic-overlay" role="alert"><div class="panic-overlay-box"><h2>Something went wrong</h2><p>The migration page hit an unexpected error and stopped responding. It stopped at <strong>{step}</strong>; that position is saved in this tab.</p><pre class="panic-overlay-message">{message}</pre><a class="validate-button" href="{url}">Reload and resume</a></div></div>"#,
+        step = escape_html(&report.step_label()),
+        message = escape_html(&report.message),
+        url = escape_html(reload_url),
+    )
+}
+
+/// Install the panic hook once; does nothing outside the browser
+pub fn install_panic_hook() {
+    #[cfg(target_arch = "wasm32")]
+    {
+        use crate::migration::journal::{load_journal, persist_journal};
+        use crate::utils::platform::storage_set;
+        use std::sync::Once;
+
+        static INSTALLED: Once = Once::new();
+        INSTALLED.call_once(|| {
+            std::panic::set_hook(Box::new(|info| {
+                persist_journal();
+                let report = PanicReport {
+                    message: panic_message(info.payload()),
+                    location: info.location().map(|location| location.to_string()),
+                    occurred_at_secs: crate::utils::platform::now_secs(),
+                    journal: load_journal(),
+                };
+                web_sys::console::error_1(
+                    &format!(
+                        "[Panic] {} at {}",
+                        report.message,
+                        report.location.as_deref().unwrap_or("unknown location")
+                    )
+                    .into(),
+                );
+                let _ = storage_set(StorageArea::Session, PANIC_REPORT_STORAGE_KEY, &report);
+                show_overlay(&report);
+            }));
+        });
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn show_overlay(report: &PanicReport) {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let reload_url = window
+        .location()
+        .href()
+        .ok()
+        .map(|href| href.split('#').next().unwrap_or(&href).to_string())
+        .unwrap_or_default();
+    if let Some(body) = window.document().and_then(|document| document.body()) {
+        let _ = body.insert_adjacent_html("beforeend", &overlay_html(report, &reload_url));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_message_and_overlay_markup() {
+        let payload: Box<dyn std::any::Any + Send> = Box::new("index out of bounds");
+        assert_eq!(panic_message(payload.as_ref()), "index out of bounds");
+        let payload: Box<dyn std::any::Any + Send> = Box::new(String::from("boom"));
+        assert_eq!(panic_message(payload.as_ref()), "boom");
+        assert_eq!(panic_message(&42u8), "unknown panic");
+
+        let report = PanicReport {
+            message: "bad <script>".to_string(),
+            location: Some("src/lib.rs:1:1".to_string()),
+            occurred_at_secs: 0,
+            journal: None,
+        };
+        let html = overlay_html(&report, "https://example.com/?a=1&b=2");
+        assert!(html.contains("bad &lt;script&gt;"));
+        assert!(html.contains("href=\"https://example.com/?a=1&amp;b=2\""));
+        assert!(html.contains("<strong>the start</strong>"));
+    }
+}
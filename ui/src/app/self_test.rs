@@ -0,0 +1,116 @@
+//! Hidden `/selftest` page for validating a deployment
+
+use dioxus::prelude::*;
+
+use crate::migration::self_test::{
+    pds_from_page_url, run_self_test, self_test_passed, SelfTestCheck, SelfTestConfig,
+};
+use crate::utils::platform::current_page_url;
+
+const MIGRATION_SERVICE_CSS: Asset = asset!("/assets/styling/migration_service.css");
+
+/// Run the scripted mini-migration against a reference PDS and list each check
+#[component]
+pub fn SelfTest() -> Element {
+    let mut pds_url = use_signal(|| {
+        current_page_url()
+            .and_then(|url| pds_from_page_url(&url))
+            .unwrap_or_default()
+    });
+    let mut handle = use_signal(String::new);
+    let mut password = use_signal(String::new);
+    let mut checks = use_signal(Vec::<SelfTestCheck>::new);
+    let mut running = use_signal(|| false);
+    let mut finished = use_signal(|| false);
+
+    let passed = self_test_passed(&checks());
+
+    rsx! {
+        document::Link { rel: "stylesheet", href: MIGRATION_SERVICE_CSS }
+
+        div {
+            class: "migration-service-container",
+            h1 {
+                class: "migration-title",
+                "Deployment self-test"
+            }
+            p {
+                class: "support-bundle-help",
+                "Runs a short scripted migration against a test PDS with a throwaway account: browser storage, handle and DID resolution, login, repository export, a blob upload and a preferences round trip. Use an account that holds nothing you care about."
+            }
+            input {
+                class: "input-field",
+                placeholder: "Test PDS URL (e.g. https://pds.example.com)",
+                value: "{pds_url}",
+                oninput: move |event| pds_url.set(event.value()),
+            }
+            input {
+                class: "input-field",
+                placeholder: "Throwaway account handle",
+                value: "{handle}",
+                oninput: move |event| handle.set(event.value()),
+            }
+            input {
+                class: "input-field",
+                r#type: "password",
+                placeholder: "Throwaway account password",
+                value: "{password}",
+                oninput: move |event| password.set(event.value()),
+            }
+            button {
+                class: "validate-button",
+                disabled: running() || pds_url().trim().is_empty() || handle().trim().is_empty() || password().is_empty(),
+                onclick: move |_| {
+                    let config = SelfTestConfig {
+                        pds_url: pds_url(),
+                        handle: handle(),
+                        password: password(),
+                    };
+                    checks.set(Vec::new());
+                    finished.set(false);
+                    running.set(true);
+                    spawn(async move {
+                        run_self_test(&config, |check| checks.write().push(check.clone())).await;
+                        running.set(false);
+                        finished.set(true);
+                    });
+                },
+                if running() { "Running…" } else { "Run self-test" }
+            }
+
+            if !checks().is_empty() {
+                ul {
+                    class: "self-test-checks",
+                    for check in checks() {
+                        li {
+                            class: if check.passed { "self-test-check passed" } else { "self-test-check failed" },
+                            span {
+                                class: "self-test-layer",
+                                "{check.layer.label()}"
+                            }
+                            strong {
+                                if check.passed { "✓ " } else { "✗ " }
+                                "{check.name}"
+                            }
+                            span {
+                                class: "self-test-detail",
+                                " {check.detail} ({check.duration_ms} ms)"
+                            }
+                        }
+                    }
+                }
+            }
+
+            if finished() {
+                div {
+                    class: if passed { "validation-result success" } else { "validation-result" },
+                    if passed {
+                        "✓ Every check passed; this deployment can run migrations."
+                    } else {
+                        "✗ Some checks failed. Checks that depend on a failed one were not run."
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use dioxus::prelude::*;
+
+use crate::console_warn;
+use crate::migration::app_passwords::passwords_to_recreate;
+use crate::migration::MigrationState;
+use crate::services::client::{ClientCreatedAppPassword, NewPds, PdsClient};
+
+#[derive(Props, PartialEq, Clone)]
+pub struct AppPasswordRecreationProps {
+    pub state: Signal<MigrationState>,
+}
+
+/// Re-create the old account's app passwords on the new PDS
+///
+/// The new secrets live only in this component; they are shown once and are
+/// gone when the page is left.
+#[component]
+pub fn AppPasswordRecreation(props: AppPasswordRecreationProps) -> Element {
+    let state = props.state;
+    let mut created = use_signal(Vec::<ClientCreatedAppPassword>::new);
+    let mut creating = use_signal(|| false);
+    let mut error = use_signal(|| None::<String>);
+
+    let Some(old_passwords) = state().app_passwords else {
+        return rsx! {};
+    };
+    if old_passwords.is_empty() || state().new_pds_session.is_none() {
+        return rsx! {};
+    }
+    let created_names = created()
+        .iter()
+        .map(|password| password.name.clone())
+        .collect::<Vec<_>>();
+    let remaining = passwords_to_recreate(&old_passwords, &created_names)
+        .into_iter()
+        .cloned()
+        .collect::<Vec<_>>();
+
+    rsx! {
+        div {
+            class: "app-password-recreation",
+            h4 {
+                class: "instructions-title",
+                "🔐 App passwords"
+            }
+            p {
+                "Your old account had {old_passwords.len()} app password(s). They stopped working with the old PDS; create new ones with the same names and paste each secret into the app or bot that used it."
+            }
+            if !created().is_empty() {
+                p {
+                    class: "app-password-warning",
+                    "⚠️ These secrets are shown only once. Copy them now; they are not saved anywhere."
+                }
+                ul {
+                    class: "app-password-list",
+                    for password in created() {
+                        li {
+                            key: "{password.name}",
+                            span { class: "app-password-name", "{password.name}" }
+                            code { class: "app-password-secret", "{password.password}" }
+                        }
+                    }
+                }
+            }
+            if !remaining.is_empty() {
+                ul {
+                    class: "app-password-list",
+                    for password in remaining.clone() {
+                        li {
+                            key: "{password.name}",
+                            span { class: "app-password-name", "{password.name}" }
+                            if password.privileged {
+                                span { class: "app-password-privileged", " (can read DMs)" }
+                            }
+                        }
+                    }
+                }
+                button {
+                    class: "validate-button",
+                    disabled: creating(),
+                    onclick: move |_| {
+                        let Some(session) = state().new_pds_session else {
+                            return;
+                        };
+                        let remaining = remaining.clone();
+                        creating.set(true);
+                        error.set(None);
+                        spawn(async move {
+                            let new_pds = NewPds::new(PdsClient::new(), (&session).into());
+                            for password in remaining {
+                                match new_pds.create_app_password(&password.name, password.privileged).await {
+                                    Ok(new_password) => created.write().push(new_password),
+                                    Err(e) => {
+                                        console_warn!("[Migration] Could not create app password {}: {}", password.name, e);
+                                        error.set(Some(format!("Could not create \"{}\": {}", password.name, e)));
+                                        break;
+                                    }
+                                }
+                            }
+                            creating.set(false);
+                        });
+                    },
+                    if creating() { "Creating..." } else { "Create on new PDS" }
+                }
+            }
+            if let Some(message) = error() {
+                p {
+                    class: "app-password-warning",
+                    "⚠️ {message}"
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use dioxus::prelude::*;
+
+use crate::migration::steps::blob_enumeration::BlobEnumerationSummary;
+use crate::services::config::BlobEnumerationMethod;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct BlobEnumerationSummaryViewProps {
+    pub summary: BlobEnumerationSummary,
+}
+
+/// Which blob enumeration method ran and what each listing returned, for
+/// comparing against goat or another tool's counts
+#[component]
+pub fn BlobEnumerationSummaryView(props: BlobEnumerationSummaryViewProps) -> Element {
+    let summary = props.summary;
+    let count = |value: Option<usize>| {
+        value
+            .map(|count| count.to_string())
+            .unwrap_or_else(|| "not asked".to_string())
+    };
+    let missing = count(summary.target_missing_count);
+    let inventory = count(summary.target_inventory_count);
+    let method = match summary.method {
+        BlobEnumerationMethod::SyncListBlobs => "full comparison (sync.listBlobs on both)",
+        _ => "missing blobs only (listMissingBlobs)",
+    };
+    let overridden =
+        summary.requested != BlobEnumerationMethod::Auto && summary.requested != summary.method;
+
+    rsx! {
+        div {
+            class: "blob-enumeration-summary",
+            h4 { class: "blob-progress-title", "Blob Enumeration" }
+            p {
+                class: "blob-enumeration-method",
+                "Used {method}: {summary.reason}."
+            }
+            if overridden {
+                p {
+                    class: "blob-enumeration-note",
+                    "You chose {summary.requested.label()}, but that wasn't possible for this pairing."
+                }
+            }
+            div {
+                class: "blob-stats",
+                div {
+                    class: "blob-stat-item",
+                    span { class: "stat-label", "Old PDS (listBlobs):" }
+                    span { class: "stat-value", "{summary.source_count}" }
+                }
+                div {
+                    class: "blob-stat-item",
+                    span { class: "stat-label", "New PDS missing:" }
+                    span { class: "stat-value", "{missing}" }
+                }
+                div {
+                    class: "blob-stat-item",
+                    span { class: "stat-label", "New PDS (listBlobs):" }
+                    span { class: "stat-value", "{inventory}" }
+                }
+                div {
+                    class: "blob-stat-item",
+                    span { class: "stat-label", "To transfer:" }
+                    span { class: "stat-value", "{summary.to_transfer}" }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use dioxus::prelude::*;
+
+use crate::migration::blob_audit::{
+    audit_blob_sample, BlobAuditReport, AUDIT_SAMPLE_CHOICES, DEFAULT_AUDIT_SAMPLE_SIZE,
+};
+use crate::migration::blob_manifest::{last_blob_manifest, BlobManifest};
+use crate::migration::SessionCredentials;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct BlobIntegrityAuditProps {
+    /// Session on the new PDS the blobs are downloaded from
+    pub session: SessionCredentials,
+}
+
+/// Re-download a random sample of the uploaded blobs and compare them with the
+/// checksum manifest; a manifest downloaded earlier can be pasted instead
+#[component]
+pub fn BlobIntegrityAudit(props: BlobIntegrityAuditProps) -> Element {
+    let mut sample_size = use_signal(|| DEFAULT_AUDIT_SAMPLE_SIZE);
+    let mut pasted = use_signal(String::new);
+    let mut progress = use_signal(|| None::<(usize, usize)>);
+    let mut report = use_signal(|| None::<BlobAuditReport>);
+    let mut error = use_signal(|| None::<String>);
+
+    let session = props.session;
+    let recorded = last_blob_manifest().filter(|manifest| manifest.did == session.did);
+    let running = progress().is_some_and(|(checked, total)| checked < total);
+
+    rsx! {
+        div {
+            class: "blob-manifest blob-audit",
+            h4 { class: "instructions-title", "🔍 Spot-check your media" }
+            p {
+                class: "blob-manifest-note",
+                "Downloads a random sample of the blobs back from the new PDS and compares each with the SHA-256 recorded when it was uploaded, without transferring everything again."
+            }
+
+            if let Some(manifest) = &recorded {
+                p { class: "blob-manifest-note", "Using the manifest from this session ({manifest.blobs.len()} blobs)." }
+            } else {
+                textarea {
+                    class: "input-field",
+                    rows: "4",
+                    placeholder: "Paste a blob checksum manifest (.json) downloaded earlier",
+                    value: "{pasted}",
+                    oninput: move |event| pasted.set(event.value()),
+                }
+            }
+
+            select {
+                class: "input-field",
+                value: "{sample_size}",
+                disabled: running,
+                onchange: move |event| {
+                    if let Ok(size) = event.value().parse() {
+                        sample_size.set(size);
+                    }
+                },
+                for size in AUDIT_SAMPLE_CHOICES {
+                    option { value: "{size}", "{size} blobs" }
+                }
+            }
+            button {
+                class: "validate-button",
+                disabled: running || (recorded.is_none() && pasted().trim().is_empty()),
+                onclick: move |_| {
+                    let manifest = match last_blob_manifest().filter(|manifest| manifest.did == session.did) {
+                        Some(manifest) => manifest,
+                        None => match serde_json::from_str::<BlobManifest>(pasted().trim()) {
+                            Ok(manifest) if manifest.did == session.did => manifest,
+                            Ok(manifest) => {
+                                error.set(Some(format!("That manifest is for {}, not {}", manifest.did, session.did)));
+                                return;
+                            }
+                            Err(e) => {
+                                error.set(Some(format!("Not a blob checksum manifest: {}", e)));
+                                return;
+                            }
+                        },
+                    };
+                    if manifest.blobs.is_empty() {
+                        error.set(Some("That manifest lists no blobs".to_string()));
+                        return;
+                    }
+                    error.set(None);
+                    report.set(None);
+                    progress.set(Some((0, sample_size().min(manifest.blobs.len()))));
+                    let client_session = (&session).into();
+                    spawn(async move {
+                        let result = audit_blob_sample(&client_session, &manifest, sample_size(), |checked, total| {
+                            progress.set(Some((checked, total)));
+                        })
+                        .await;
+                        report.set(Some(result));
+                    });
+                },
+                if running { "Checking…" } else { "Check a sample" }
+            }
+
+            if let (true, Some((checked, total))) = (running, progress()) {
+                div {
+                    role: "status",
+                    "aria-live": "polite",
+                    class: "blob-manifest-note",
+                    "Checked {checked} of {total}…"
+                }
+            }
+
+            if let Some(message) = error() {
+                div { class: "validation-result error", "✗ {message}" }
+            }
+
+            if let Some(result) = report() {
+                if let Some(bound) = result.max_damaged_fraction() {
+                    div {
+                        class: "validation-result success",
+                        if bound == 0.0 {
+                            "✓ All {result.sampled} blobs match the manifest."
+                        } else {
+                            "✓ All {result.sampled} sampled blobs match the manifest. With 95% confidence, fewer than {bound * 100.0:.1}% of the {result.population} blobs are damaged."
+                        }
+                    }
+                } else {
+                    div {
+                        class: "validation-result error",
+                        "✗ {result.matched} of {result.sampled} sampled blobs match the manifest."
+                    }
+                    ul {
+                        class: "blob-manifest-note",
+                        for cid in result.mismatched.iter() {
+                            li { key: "{cid}", "{cid}: the new PDS served different bytes" }
+                        }
+                        for (cid, message) in result.unreadable.iter() {
+                            li { key: "{cid}", "{cid}: could not be downloaded ({message})" }
+                        }
+                    }
+                    p {
+                        class: "blob-manifest-note",
+                        "Don't delete the old account until the affected blobs have been uploaded again; the old PDS still has the originals."
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use base64::Engine;
+use dioxus::prelude::*;
+
+use crate::migration::blob_manifest::{last_blob_manifest, BlobManifestInfo};
+use crate::utils::byte_format::format_size;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct BlobManifestDownloadProps {
+    pub manifest: BlobManifestInfo,
+}
+
+/// Download link for the checksum manifest of blobs uploaded to the new PDS
+#[component]
+pub fn BlobManifestDownload(props: BlobManifestDownloadProps) -> Element {
+    let info = props.manifest;
+    let Some(manifest) = last_blob_manifest() else {
+        return rsx! {};
+    };
+    let href = format!(
+        "data:application/json;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(manifest.to_json())
+    );
+    let size = format_size(info.total_bytes);
+
+    rsx! {
+        div {
+            class: "blob-manifest",
+            a {
+                class: "banner-link",
+                href: "{href}",
+                download: "{manifest.file_name()}",
+                "⬇️ Download blob checksum manifest ({info.blob_count} blobs, {size})"
+            }
+            span {
+                class: "blob-manifest-note",
+                " SHA-256 of every blob uploaded in this session, hashed in your browser."
+            }
+            if info.mismatched > 0 {
+                div {
+                    class: "blob-manifest-warning",
+                    "⚠️ {info.mismatched} uploaded blobs did not match their CID; they are flagged in the manifest."
+                }
+            }
+        }
+    }
+}
@@ -1,11 +1,25 @@
+use crate::utils::byte_format::format_size;
+use crate::utils::progress_text::{progress_announcement, progress_sentence};
 use crate::{console_debug, console_log, migration::*};
 use dioxus::prelude::*;
+use std::collections::VecDeque;
+
+/// Keeps the console log pinned to its newest entry
+const SCROLL_CONSOLE_LOG_JS: &str = r#"
+    const log = document.getElementById("blob-console-log");
+    if (log) { log.scrollTop = log.scrollHeight; }
+"#;
 
 // Main component that orchestrates all sub-components
 #[derive(Props, PartialEq, Clone, Debug)]
 pub struct BlobProgressDisplayProps {
     pub blob_progress: BlobProgress,
     pub migration_step: String,
+    #[props(default)]
+    pub console_messages: VecDeque<ConsoleEntry>,
+    /// Sentences instead of bars and the recent-blobs animation
+    #[props(default)]
+    pub text_only: bool,
 }
 
 #[component]
@@ -39,23 +53,47 @@ pub fn BlobProgressDisplay(props: BlobProgressDisplayProps) -> Element {
                 "Blob Transfer Progress"
             }
 
-            ProgressBar {
-                processed: blob_progress.processed_blobs,
-                total: blob_progress.total_blobs,
+            // Read out in 10% steps rather than on every tick
+            div {
+                class: "visually-hidden",
+                role: "status",
+                "aria-live": "polite",
+                "{progress_announcement(\"Blob transfer\", blob_progress.processed_blobs as u64, blob_progress.total_blobs as u64)}"
+            }
+
+            if props.text_only {
+                p {
+                    class: "blob-status-text",
+                    {progress_sentence(
+                        "blobs",
+                        blob_progress.processed_blobs as u64,
+                        blob_progress.total_blobs as u64,
+                        blob_progress.processed_bytes,
+                        blob_progress.total_bytes,
+                    )}
+                }
+            } else {
+                ProgressBar {
+                    processed: blob_progress.processed_blobs,
+                    total: blob_progress.total_blobs,
+                }
             }
 
             StatusText {
                 text: migration_step.clone(),
             }
 
-            DetailedStats {
-                blob_progress: blob_progress.clone(),
+            if !props.text_only {
+                DetailedStats {
+                    blob_progress: blob_progress.clone(),
+                }
             }
 
             if let Some(current_cid) = &blob_progress.current_blob_cid {
                 CurrentBlobDisplay {
                     cid: current_cid.clone(),
                     progress: blob_progress.current_blob_progress,
+                    text_only: props.text_only,
                 }
             }
 
@@ -65,11 +103,124 @@ pub fn BlobProgressDisplay(props: BlobProgressDisplayProps) -> Element {
                 }
             }
 
-            if blob_progress.processed_blobs > 0 {
+            if blob_progress.processed_blobs > 0 && !props.text_only {
                 RecentBlobsList {
                     processed_blobs: blob_progress.processed_blobs,
                 }
             }
+
+            if !props.console_messages.is_empty() {
+                ConsoleLog {
+                    messages: props.console_messages.clone(),
+                }
+            }
+        }
+    }
+}
+
+// Live console log with level filtering and auto-scroll
+#[derive(Props, PartialEq, Clone)]
+struct ConsoleLogProps {
+    messages: VecDeque<ConsoleEntry>,
+}
+
+#[component]
+fn ConsoleLog(props: ConsoleLogProps) -> Element {
+    let mut filter = use_signal(ConsoleFilter::default);
+    let mut auto_scroll = use_signal(|| true);
+
+    let visible: Vec<ConsoleEntry> = props
+        .messages
+        .iter()
+        .filter(|entry| filter().allows(entry.level))
+        .cloned()
+        .collect();
+    let newest_seq = visible.last().map(|entry| entry.seq);
+
+    use_effect(use_reactive!(|newest_seq| {
+        if auto_scroll() && newest_seq.is_some() {
+            document::eval(SCROLL_CONSOLE_LOG_JS);
+        }
+    }));
+
+    rsx! {
+        div {
+            class: "console-log-section",
+            div {
+                class: "console-log-controls",
+                h5 {
+                    class: "recent-blobs-title",
+                    "Migration Log"
+                }
+                select {
+                    class: "console-log-filter",
+                    onchange: move |evt| {
+                        if let Some(selected) = ConsoleFilter::parse(&evt.value()) {
+                            filter.set(selected);
+                        }
+                    },
+                    for option_filter in ConsoleFilter::ALL {
+                        option {
+                            value: option_filter.as_str(),
+                            selected: option_filter == filter(),
+                            "{option_filter.label()}"
+                        }
+                    }
+                }
+                label {
+                    class: "console-log-autoscroll",
+                    input {
+                        r#type: "checkbox",
+                        checked: auto_scroll(),
+                        onchange: move |evt| auto_scroll.set(evt.checked()),
+                    }
+                    "Auto-scroll"
+                }
+            }
+            div {
+                id: "blob-console-log",
+                class: "console-log",
+                if visible.is_empty() {
+                    div {
+                        class: "console-log-empty",
+                        "No messages at this level yet."
+                    }
+                }
+                for entry in visible {
+                    ConsoleLogLine {
+                        key: "{entry.seq}",
+                        entry: entry.clone(),
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Single console log line
+#[derive(Props, PartialEq, Clone)]
+struct ConsoleLogLineProps {
+    entry: ConsoleEntry,
+}
+
+#[component]
+fn ConsoleLogLine(props: ConsoleLogLineProps) -> Element {
+    let entry = &props.entry;
+    // HH:MM:SS in the browser's time zone; legacy entries have no time
+    let time = if entry.timestamp_ms > 0.0 {
+        crate::utils::time_format::format_local_clock(entry.timestamp_ms as u64)
+    } else {
+        String::new()
+    };
+    let level_class = entry.level.label().to_lowercase();
+
+    rsx! {
+        div {
+            class: "console-log-line {level_class}",
+            title: "{entry.source}",
+            span { class: "console-log-time", "{time}" }
+            span { class: "console-log-level", "{entry.level.label()}" }
+            span { class: "console-log-message", "{entry.message}" }
         }
     }
 }
@@ -99,6 +250,11 @@ fn ProgressBar(props: ProgressBarProps) -> Element {
     rsx! {
         div {
             class: "progress-bar-container",
+            role: "progressbar",
+            "aria-label": "Blobs transferred",
+            "aria-valuemin": "0",
+            "aria-valuemax": "{props.total}",
+            "aria-valuenow": "{props.processed}",
             div {
                 class: "progress-bar-background",
                 div {
@@ -138,9 +294,6 @@ struct DetailedStatsProps {
 
 #[component]
 fn DetailedStats(props: DetailedStatsProps) -> Element {
-    let total_mb = props.blob_progress.total_bytes as f64 / 1_048_576.0;
-    let processed_mb = props.blob_progress.processed_bytes as f64 / 1_048_576.0;
-
     rsx! {
         div {
             class: "blob-stats",
@@ -150,7 +303,11 @@ fn DetailedStats(props: DetailedStatsProps) -> Element {
             }
             StatItem {
                 label: "Size:".to_string(),
-                value: format!("{:.1}/{:.1} MB", processed_mb, total_mb),
+                value: format!(
+                    "{} / {}",
+                    format_size(props.blob_progress.processed_bytes),
+                    format_size(props.blob_progress.total_bytes)
+                ),
             }
         }
     }
@@ -179,6 +336,7 @@ fn StatItem(props: StatItemProps) -> Element {
 struct CurrentBlobDisplayProps {
     cid: String,
     progress: Option<f64>,
+    text_only: bool,
 }
 
 #[component]
@@ -195,8 +353,15 @@ fn CurrentBlobDisplay(props: CurrentBlobDisplayProps) -> Element {
                 "{props.cid}"
             }
             if let Some(current_progress) = props.progress {
-                MiniProgressBar {
-                    progress: current_progress,
+                if props.text_only {
+                    div {
+                        class: "mini-progress-text",
+                        "{current_progress:.0}% of this blob"
+                    }
+                } else {
+                    MiniProgressBar {
+                        progress: current_progress,
+                    }
                 }
             }
         }
@@ -214,6 +379,11 @@ fn MiniProgressBar(props: MiniProgressBarProps) -> Element {
     rsx! {
         div {
             class: "current-blob-progress",
+            role: "progressbar",
+            "aria-label": "Current blob",
+            "aria-valuemin": "0",
+            "aria-valuemax": "100",
+            "aria-valuenow": "{props.progress:.0}",
             div {
                 class: "mini-progress-bar",
                 div {
@@ -240,6 +410,7 @@ fn ErrorDisplay(props: ErrorDisplayProps) -> Element {
     rsx! {
         div {
             class: "blob-error",
+            role: "alert",
             "⚠️ {props.error}"
         }
     }
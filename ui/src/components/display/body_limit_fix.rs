@@ -0,0 +1,52 @@
+use dioxus::prelude::*;
+
+use crate::services::client::{BodyLimitReport, BodyProbeOutcome};
+use crate::utils::byte_format::format_size;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct BodyLimitFixViewProps {
+    pub report: BodyLimitReport,
+}
+
+/// The request body limit found on the new PDS, the test uploads that found
+/// it, and the configuration change its operator has to make
+#[component]
+pub fn BodyLimitFixView(props: BodyLimitFixViewProps) -> Element {
+    let report = props.report;
+    let Some(fix) = report.fix() else {
+        return rsx! {};
+    };
+    let title = if report.refusal().is_some() {
+        "The new PDS limits upload size"
+    } else {
+        "The new PDS may limit upload size"
+    };
+
+    rsx! {
+        div {
+            class: "blob-enumeration-summary",
+            h4 { class: "blob-progress-title", "{title}" }
+            p {
+                class: "blob-enumeration-note",
+                "This migration sends up to {format_size(report.needed_bytes)} in one request. Whoever runs the new PDS needs to make this change, then start the migration again:"
+            }
+            pre { class: "repo-import-failure-body", "{fix}" }
+            details {
+                summary { "Test uploads" }
+                ul {
+                    for probe in report.probes.iter() {
+                        li {
+                            key: "{probe.bytes}",
+                            "{format_size(probe.bytes)}: "
+                            match probe.outcome {
+                                BodyProbeOutcome::Accepted => "accepted".to_string(),
+                                BodyProbeOutcome::TooLarge => format!("refused as too large (HTTP {})", probe.status.unwrap_or(413)),
+                                BodyProbeOutcome::Failed => format!("failed ({})", probe.detail.as_deref().unwrap_or("no response")),
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
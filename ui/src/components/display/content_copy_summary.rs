@@ -0,0 +1,55 @@
+use dioxus::prelude::*;
+
+use crate::migration::content_copy::ContentCopyReport;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct ContentCopySummaryProps {
+    pub report: ContentCopyReport,
+}
+
+/// What the content copy wrote into the existing account, and what it couldn't
+#[component]
+pub fn ContentCopySummary(props: ContentCopySummaryProps) -> Element {
+    let report = props.report;
+    let more = (report.records_failed as usize).saturating_sub(report.failures.len());
+
+    rsx! {
+        div {
+            class: "blob-enumeration-summary",
+            h4 { class: "blob-progress-title", "Content Copy" }
+            div {
+                class: "blob-stats",
+                div {
+                    class: "blob-stat-item",
+                    span { class: "stat-label", "Records copied:" }
+                    span { class: "stat-value", "{report.records_copied}" }
+                }
+                div {
+                    class: "blob-stat-item",
+                    span { class: "stat-label", "Blobs copied:" }
+                    span { class: "stat-value", "{report.blobs_copied}" }
+                }
+                div {
+                    class: "blob-stat-item",
+                    span { class: "stat-label", "Records failed:" }
+                    span { class: "stat-value", "{report.records_failed}" }
+                }
+            }
+            if !report.failures.is_empty() {
+                ul {
+                    class: "content-copy-failures",
+                    for failure in report.failures.iter() {
+                        li { "{failure}" }
+                    }
+                    if more > 0 {
+                        li { "…and {more} more" }
+                    }
+                }
+            }
+            p {
+                class: "blob-enumeration-note",
+                "Your identity did not move: followers, replies from others and your DID stay with the old account."
+            }
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use dioxus::prelude::*;
+
+use crate::migration::continuous_sync::{request_sync_stop, ContinuousSyncStatus};
+use crate::utils::platform::now_ms;
+use crate::utils::time_format::format_relative;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct ContinuousSyncPanelProps {
+    pub status: ContinuousSyncStatus,
+}
+
+/// Rounds of the keep-in-sync loop so far, with the button that ends it
+#[component]
+pub fn ContinuousSyncPanel(props: ContinuousSyncPanelProps) -> Element {
+    let status = props.status;
+    let now = now_ms() as u64;
+    let last_check = status
+        .last_check_ms
+        .map(|at| format_relative(at, now))
+        .unwrap_or_else(|| "not yet".to_string());
+    let next_check = match status.next_check_ms {
+        Some(at) => format_relative(at, now),
+        None if status.stopping => "final round running".to_string(),
+        None => "running now".to_string(),
+    };
+    let imported_kb = status.imported_bytes / 1024;
+    let synced_rev = status
+        .synced_rev
+        .clone()
+        .unwrap_or_else(|| "unknown".to_string());
+
+    rsx! {
+        div {
+            class: "continuous-sync-panel",
+            h4 { class: "blob-progress-title", "Keeping Accounts in Sync" }
+            p {
+                class: "continuous-sync-note",
+                "Keep using your old account. New posts, follows and media are copied to the new PDS until you switch. Leave this tab open."
+            }
+            div {
+                class: "blob-stats",
+                div {
+                    class: "blob-stat-item",
+                    span { class: "stat-label", "Last check:" }
+                    span { class: "stat-value", "{last_check}" }
+                }
+                div {
+                    class: "blob-stat-item",
+                    span { class: "stat-label", "Next check:" }
+                    span { class: "stat-value", "{next_check}" }
+                }
+                div {
+                    class: "blob-stat-item",
+                    span { class: "stat-label", "Changes copied:" }
+                    span { class: "stat-value", "{status.deltas_applied} ({imported_kb} KB)" }
+                }
+                div {
+                    class: "blob-stat-item",
+                    span { class: "stat-label", "In sync with revision:" }
+                    span { class: "stat-value", "{synced_rev}" }
+                }
+            }
+            if let Some(error) = status.last_error.clone() {
+                p {
+                    class: "continuous-sync-error",
+                    "Last check failed ({status.consecutive_failures} in a row), retrying next round: {error}"
+                }
+            }
+            button {
+                class: "validate-button",
+                disabled: status.stopping,
+                onclick: move |_| request_sync_stop(),
+                if status.stopping {
+                    "Finishing the last sync..."
+                } else {
+                    "Stop syncing and switch identity"
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use dioxus::prelude::*;
+
+use crate::utils::handle::alternate_handle_form;
+use crate::utils::lookalike::domain_warnings;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct DomainSafetyNoteProps {
+    /// Handle or host, in Unicode or punycode
+    pub domain: String,
+}
+
+/// The other spelling of an internationalized handle or host, and any sign
+/// that it imitates a different one
+#[component]
+pub fn DomainSafetyNote(props: DomainSafetyNoteProps) -> Element {
+    let alternate = alternate_handle_form(&props.domain);
+    let warnings = domain_warnings(&props.domain);
+
+    rsx! {
+        if let Some(alternate) = alternate {
+            div {
+                class: "handle-normalization-note",
+                "Also written as {alternate}"
+            }
+        }
+        for warning in warnings {
+            div {
+                key: "{warning.message()}",
+                class: "domain-warning",
+                "⚠ {warning.message()}. Check it letter by letter before continuing."
+            }
+        }
+    }
+}
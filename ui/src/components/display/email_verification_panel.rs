@@ -0,0 +1,194 @@
+use dioxus::prelude::*;
+
+use crate::console_warn;
+use crate::migration::{
+    EmailVerification, EmailVerificationStatus, MigrationAction, MigrationState,
+};
+use crate::services::client::{NewPds, PdsClient};
+
+#[derive(Props, PartialEq, Clone)]
+pub struct EmailVerificationPanelProps {
+    pub state: Signal<MigrationState>,
+    pub dispatch: EventHandler<MigrationAction>,
+}
+
+fn new_pds(state: &MigrationState) -> Option<NewPds> {
+    state
+        .new_pds_session
+        .as_ref()
+        .map(|session| NewPds::new(PdsClient::new(), session.into()))
+}
+
+/// Confirm the new account's email, resend the code, or switch addresses
+#[component]
+pub fn EmailVerificationPanel(props: EmailVerificationPanelProps) -> Element {
+    let state = props.state;
+    let dispatch = props.dispatch;
+    let mut code = use_signal(String::new);
+    let mut new_email = use_signal(String::new);
+    let mut busy = use_signal(|| false);
+
+    let verification = state().email_verification;
+    if state().new_pds_session.is_none()
+        || verification.status == EmailVerificationStatus::NotChecked
+    {
+        return rsx! {};
+    }
+    let email = verification.email.clone();
+
+    // Runs one request and records the resulting status
+    let mut run =
+        move |task: std::pin::Pin<Box<dyn std::future::Future<Output = EmailVerification>>>| {
+            busy.set(true);
+            spawn(async move {
+                let verification = task.await;
+                if let EmailVerificationStatus::Failed(error) = &verification.status {
+                    console_warn!("[Migration] Email verification: {}", error);
+                }
+                dispatch.call(MigrationAction::SetEmailVerification(verification));
+                code.set(String::new());
+                busy.set(false);
+            });
+        };
+
+    rsx! {
+        div {
+            class: "email-verification-panel",
+            h4 {
+                class: "instructions-title",
+                "📧 Account email"
+            }
+            match verification.status.clone() {
+                EmailVerificationStatus::Confirmed => rsx! {
+                    p {
+                        class: "email-verification-status confirmed",
+                        "✓ {email} is confirmed on the new PDS."
+                    }
+                },
+                EmailVerificationStatus::ConfirmationSent => rsx! {
+                    p {
+                        class: "email-verification-status",
+                        "A confirmation code was sent to {email}. Enter it here so password resets and security codes reach you."
+                    }
+                },
+                EmailVerificationStatus::UpdateTokenSent { new_email: pending } => rsx! {
+                    p {
+                        class: "email-verification-status",
+                        "To switch to {pending}, enter the code the new PDS sent to {email}."
+                    }
+                },
+                EmailVerificationStatus::Failed(error) => rsx! {
+                    p {
+                        class: "email-verification-status failed",
+                        "⚠️ {error}"
+                    }
+                },
+                EmailVerificationStatus::NotChecked => rsx! {},
+            }
+            if !verification.is_confirmed() {
+                div {
+                    class: "email-verification-row",
+                    input {
+                        r#type: "text",
+                        class: "input-field",
+                        placeholder: "Code from the email",
+                        value: code(),
+                        oninput: move |event| code.set(event.value()),
+                    }
+                    button {
+                        class: "validate-button",
+                        disabled: busy() || code().trim().is_empty(),
+                        onclick: move |_| {
+                            let current = state();
+                            let Some(new_pds) = new_pds(&current) else {
+                                return;
+                            };
+                            let verification = current.email_verification.clone();
+                            let token = code().trim().to_string();
+                            run(Box::pin(async move {
+                                match verification.status {
+                                    EmailVerificationStatus::UpdateTokenSent { new_email } => {
+                                        match new_pds.update_email(&new_email, Some(&token)).await {
+                                            Ok(()) => match new_pds.request_email_confirmation().await {
+                                                Ok(()) => EmailVerification::new(new_email, EmailVerificationStatus::ConfirmationSent),
+                                                Err(e) => EmailVerification::new(new_email, EmailVerificationStatus::Failed(format!("Email changed, but no confirmation code was sent: {}", e))),
+                                            },
+                                            Err(e) => EmailVerification {
+                                                status: EmailVerificationStatus::Failed(format!("Could not change the email: {}", e)),
+                                                ..verification
+                                            },
+                                        }
+                                    }
+                                    _ => match new_pds.confirm_email(&verification.email, &token).await {
+                                        Ok(()) => EmailVerification::new(verification.email, EmailVerificationStatus::Confirmed),
+                                        Err(e) => EmailVerification::new(verification.email, EmailVerificationStatus::Failed(format!("Could not confirm the email: {}", e))),
+                                    },
+                                }
+                            }));
+                        },
+                        "Confirm"
+                    }
+                    button {
+                        class: "validate-button",
+                        disabled: busy(),
+                        onclick: move |_| {
+                            let current = state();
+                            let Some(new_pds) = new_pds(&current) else {
+                                return;
+                            };
+                            let email = current.email_verification.email.clone();
+                            run(Box::pin(async move {
+                                match new_pds.request_email_confirmation().await {
+                                    Ok(()) => EmailVerification::new(email, EmailVerificationStatus::ConfirmationSent),
+                                    Err(e) => EmailVerification::new(email, EmailVerificationStatus::Failed(format!("Could not send a new code: {}", e))),
+                                }
+                            }));
+                        },
+                        "Resend code"
+                    }
+                }
+            }
+            div {
+                class: "email-verification-row",
+                input {
+                    r#type: "email",
+                    class: "input-field",
+                    placeholder: "Use a different email",
+                    value: new_email(),
+                    oninput: move |event| new_email.set(event.value()),
+                }
+                button {
+                    class: "validate-button",
+                    disabled: busy() || !new_email().contains('@'),
+                    onclick: move |_| {
+                        let current = state();
+                        let Some(new_pds) = new_pds(&current) else {
+                            return;
+                        };
+                        let email = current.email_verification.email.clone();
+                        let wanted = new_email().trim().to_string();
+                        new_email.set(String::new());
+                        run(Box::pin(async move {
+                            let failed = |message: String| {
+                                EmailVerification::new(email.clone(), EmailVerificationStatus::Failed(message))
+                            };
+                            match new_pds.request_email_update().await {
+                                // A confirmed address must approve the change first
+                                Ok(true) => EmailVerification::new(email.clone(), EmailVerificationStatus::UpdateTokenSent { new_email: wanted }),
+                                Ok(false) => match new_pds.update_email(&wanted, None).await {
+                                    Ok(()) => match new_pds.request_email_confirmation().await {
+                                        Ok(()) => EmailVerification::new(wanted, EmailVerificationStatus::ConfirmationSent),
+                                        Err(e) => EmailVerification::new(wanted, EmailVerificationStatus::Failed(format!("Email changed, but no confirmation code was sent: {}", e))),
+                                    },
+                                    Err(e) => failed(format!("Could not change the email: {}", e)),
+                                },
+                                Err(e) => failed(format!("Could not start the email change: {}", e)),
+                            }
+                        }));
+                    },
+                    "Change email"
+                }
+            }
+        }
+    }
+}
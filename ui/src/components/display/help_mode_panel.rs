@@ -0,0 +1,139 @@
+use dioxus::prelude::*;
+
+use crate::components::display::MigrationTimeline;
+use crate::migration::help_status::{HelpStatus, HELP_STATUS_FRAGMENT, HELP_STATUS_TTL_SECS};
+use crate::migration::MigrationState;
+use crate::utils::platform::{current_page_url, current_url_fragment, now_ms};
+
+#[derive(Props, PartialEq, Clone)]
+pub struct HelpModePanelProps {
+    pub state: Signal<MigrationState>,
+}
+
+/// Opt-in sharing of a scrubbed live status, and a viewer for helpers loading one
+#[component]
+pub fn HelpModePanel(props: HelpModePanelProps) -> Element {
+    let state = props.state;
+    let mut sharing = use_signal(|| false);
+    // A shared link opens straight into the viewer
+    let mut pasted = use_signal(|| {
+        current_url_fragment()
+            .filter(|fragment| fragment.starts_with(HELP_STATUS_FRAGMENT))
+            .unwrap_or_default()
+    });
+    let opened_from_link = !pasted.peek().is_empty();
+
+    let shared = if sharing() {
+        let status = HelpStatus::capture(&state.read(), now_ms() as u64);
+        let link = current_page_url().map(|url| status.share_link(&url));
+        Some((status.encode(), link))
+    } else {
+        None
+    };
+    let loaded = if pasted().trim().is_empty() {
+        None
+    } else {
+        Some(HelpStatus::decode(&pasted(), now_ms() as u64 / 1000))
+    };
+
+    rsx! {
+        details {
+            class: "help-mode-panel",
+            open: opened_from_link,
+            summary { "🙋 Get help from a supporter" }
+
+            p {
+                class: "help-mode-help",
+                "Share a status that shows a helper which step you're on, errors and progress. It never includes your handle, DID, email, passwords or tokens, and it expires after {HELP_STATUS_TTL_SECS / 60} minutes. It updates as your migration moves, so share it again for a fresh view."
+            }
+
+            label {
+                class: "help-mode-toggle",
+                input {
+                    r#type: "checkbox",
+                    checked: sharing(),
+                    onchange: move |event| sharing.set(event.checked()),
+                }
+                " Share my progress"
+            }
+
+            if let Some((encoded, link)) = shared {
+                textarea {
+                    class: "input-field help-mode-status",
+                    readonly: true,
+                    rows: "3",
+                    value: "{encoded}",
+                }
+                if let Some(link) = link {
+                    a {
+                        class: "banner-link",
+                        href: "{link}",
+                        target: "_blank",
+                        "🔗 Status link (copy and send it to your helper)"
+                    }
+                }
+            }
+
+            div {
+                class: "help-mode-viewer",
+                label { class: "input-label", "Helping someone? Paste their status or link:" }
+                textarea {
+                    class: "input-field",
+                    rows: "2",
+                    value: "{pasted}",
+                    oninput: move |event| pasted.set(event.value()),
+                }
+                match loaded {
+                    Some(Ok(status)) => rsx! { HelpStatusView { status } },
+                    Some(Err(message)) => rsx! {
+                        div { class: "validation-result error", "✗ {message}" }
+                    },
+                    None => rsx! {},
+                }
+            }
+        }
+    }
+}
+
+/// Read-only rendering of a shared status
+#[component]
+fn HelpStatusView(status: HelpStatus) -> Element {
+    let blobs = &status.blob_progress;
+    let minutes_left = status
+        .expires_at_secs
+        .saturating_sub(now_ms() as u64 / 1000)
+        / 60;
+
+    rsx! {
+        div {
+            class: "help-status-view",
+            MigrationTimeline { entries: status.timeline.clone() }
+            ul {
+                li { "Form: {status.form_step:?} · plan: {status.plan_variant.label()}" }
+                li { "New PDS: {status.new_pds_host}" }
+                li { "Current step: {status.migration_step}" }
+                li { "Blobs: {blobs.processed_blobs}/{blobs.total_blobs}" }
+                if let (Some(step), Some(secs)) = (status.stalled_step, status.stalled_for_secs) {
+                    li { "⚠️ {step.label()} has made no progress for {secs / 60} min" }
+                }
+                if let Some(error) = &status.migration_error {
+                    li { class: "help-status-error", "Error: {error}" }
+                }
+            }
+            if !status.recent_problems.is_empty() {
+                details {
+                    summary { "Recent warnings and errors ({status.recent_problems.len()})" }
+                    ul {
+                        for problem in status.recent_problems.iter() {
+                            li { "{problem}" }
+                        }
+                    }
+                }
+            }
+            p {
+                class: "help-mode-help",
+                "Version {status.app_version} · expires in {minutes_left} min"
+            }
+        }
+    }
+}
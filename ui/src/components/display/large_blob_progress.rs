@@ -0,0 +1,90 @@
+use crate::migration::progress::{LargeBlobPhase, LargeBlobTransfer};
+use crate::utils::byte_format::format_size;
+use dioxus::prelude::*;
+
+#[derive(Props, PartialEq, Clone, Debug)]
+pub struct LargeBlobProgressProps {
+    pub transfers: Vec<LargeBlobTransfer>,
+}
+
+/// One bar per large blob in flight, so a long video transfer visibly moves
+#[component]
+pub fn LargeBlobProgress(props: LargeBlobProgressProps) -> Element {
+    if props.transfers.is_empty() {
+        return rsx! {};
+    }
+
+    rsx! {
+        div {
+            class: "large-blob-progress",
+
+            h4 {
+                class: "blob-progress-title",
+                "Large Files"
+            }
+
+            for transfer in props.transfers.iter() {
+                LargeBlobRow { key: "{transfer.cid}", transfer: transfer.clone() }
+            }
+        }
+    }
+}
+
+#[derive(Props, PartialEq, Clone, Debug)]
+struct LargeBlobRowProps {
+    transfer: LargeBlobTransfer,
+}
+
+#[component]
+fn LargeBlobRow(props: LargeBlobRowProps) -> Element {
+    let transfer = &props.transfer;
+    let short_cid = transfer.cid.chars().take(16).collect::<String>() + "...";
+    let total = transfer
+        .total_bytes
+        .map(format_size)
+        .unwrap_or_else(|| "unknown size".to_string());
+    let downloaded = format_size(transfer.downloaded_bytes);
+
+    let (status, fill_class, percentage) = match transfer.phase {
+        LargeBlobPhase::Downloading => (
+            format!("Downloading {} of {}", downloaded, total),
+            "progress-bar-fill",
+            transfer.download_percent(),
+        ),
+        // Uploads go out in one request, so there's no byte count until it finishes
+        LargeBlobPhase::Uploading => (
+            format!("Downloaded {}, uploading...", downloaded),
+            "progress-bar-fill large-blob-uploading",
+            None,
+        ),
+    };
+
+    rsx! {
+        div {
+            class: "large-blob-item",
+
+            div {
+                class: "large-blob-header",
+                span { class: "large-blob-cid", "{short_cid}" }
+                span { class: "large-blob-status", "{status}" }
+            }
+
+            div {
+                class: "progress-bar-container",
+                div {
+                    class: "progress-bar-background",
+                    div {
+                        class: "{fill_class}",
+                        style: format!("width: {}%", percentage.unwrap_or(100.0)),
+                    }
+                }
+                if let Some(percentage) = percentage {
+                    span {
+                        class: "progress-percentage",
+                        "{percentage:.1}%"
+                    }
+                }
+            }
+        }
+    }
+}
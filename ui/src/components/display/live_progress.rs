@@ -0,0 +1,31 @@
+use crate::components::display::{BlobProgressDisplay, LargeBlobProgress};
+use crate::migration::progress::use_progress_store;
+use crate::migration::ConsoleEntry;
+use dioxus::prelude::*;
+use std::collections::VecDeque;
+
+#[derive(Props, PartialEq, Clone, Debug)]
+pub struct LiveProgressProps {
+    #[props(default)]
+    pub console_messages: VecDeque<ConsoleEntry>,
+    #[props(default)]
+    pub text_only: bool,
+}
+
+/// Blob and large-file progress read from the progress store, so only this
+/// subtree re-renders when byte counts change
+#[component]
+pub fn LiveProgress(props: LiveProgressProps) -> Element {
+    let progress = use_progress_store().read();
+
+    rsx! {
+        BlobProgressDisplay {
+            blob_progress: progress.unified_blob_progress(),
+            migration_step: progress.step.clone(),
+            console_messages: props.console_messages,
+            text_only: props.text_only,
+        }
+
+        LargeBlobProgress { transfers: progress.large_blobs }
+    }
+}
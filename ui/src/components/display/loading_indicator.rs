@@ -5,12 +5,16 @@ pub struct LoadingIndicatorProps {
     pub message: String,
 }
 
+/// A short wait, announced politely to screen readers
 #[component]
 pub fn LoadingIndicator(props: LoadingIndicatorProps) -> Element {
     rsx! {
         div {
             class: "loading-indicator",
-            "⏳ {props.message}"
+            role: "status",
+            "aria-live": "polite",
+            span { class: "loading-indicator-icon", "aria-hidden": "true", "⏳ " }
+            "{props.message}"
         }
     }
 }
@@ -0,0 +1,49 @@
+use dioxus::prelude::*;
+
+use crate::migration::TimelineEntry;
+use crate::utils::time_format::format_duration_ms;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct MigrationTimelineProps {
+    pub entries: Vec<TimelineEntry>,
+    /// Finer-grained description of what the active step is doing
+    #[props(default)]
+    pub detail: String,
+}
+
+/// Horizontal stepper of migration phases with status, duration and error badges
+#[component]
+pub fn MigrationTimeline(props: MigrationTimelineProps) -> Element {
+    rsx! {
+        div {
+            class: "migration-timeline",
+            ol {
+                class: "timeline-steps",
+                for entry in props.entries.iter() {
+                    li {
+                        key: "{entry.step.label()}",
+                        class: "timeline-step {entry.status.css_class()}",
+                        span { class: "timeline-step-icon", "{entry.status.icon()}" }
+                        span { class: "timeline-step-label", "{entry.step.label()}" }
+                        if let Some(duration_ms) = entry.duration_ms {
+                            span { class: "timeline-step-duration", "{format_duration_ms(duration_ms)}" }
+                        }
+                        if let Some(error) = &entry.error {
+                            span {
+                                class: "timeline-error-badge",
+                                title: "{error}",
+                                "!"
+                            }
+                        }
+                    }
+                }
+            }
+            if !props.detail.is_empty() {
+                div {
+                    class: "timeline-detail",
+                    "{props.detail}"
+                }
+            }
+        }
+    }
+}
@@ -1,9 +1,95 @@
+pub mod app_password_recreation;
+pub mod blob_enumeration_summary;
+#[cfg(feature = "hash-verification")]
+pub mod blob_integrity_audit;
+pub mod blob_manifest_download;
 pub mod blob_progress_display;
+pub mod body_limit_fix;
+pub mod content_copy_summary;
+pub mod continuous_sync_panel;
+pub mod domain_safety_note;
+pub mod email_verification_panel;
+pub mod help_mode_panel;
+pub mod large_blob_progress;
+pub mod live_progress;
 pub mod loading_indicator;
+pub mod migration_timeline;
+pub mod panic_recovery_notice;
+pub mod password_save_offer;
+pub mod pds_endpoint_check;
+#[cfg(feature = "diagnostics-panel")]
+pub mod pipeline_metrics_panel;
+pub mod plc_token_expiry_wizard;
+pub mod plc_token_wait_panel;
+pub mod post_migration_checklist;
+pub mod preferences_backup_download;
+pub mod preferences_round_trip_summary;
+pub mod propagation_monitor;
 pub mod provider_display;
+pub mod repo_car_checksum;
+pub mod repo_import_failure;
+pub mod repo_import_progress;
+pub mod saved_feeds_check;
+pub mod service_account_notice;
+#[cfg(feature = "session-transfer")]
+pub mod session_transfer_panel;
+pub mod source_endpoint_notice;
+pub mod stall_notice;
+pub mod storage_usage_meter;
+#[cfg(feature = "support-bundle")]
+pub mod support_bundle_panel;
+#[cfg(feature = "web")]
+pub mod tab_activity_notice;
+pub mod target_cors_check;
+pub mod unrecoverable_blobs;
+pub mod upload_log_download;
+#[cfg(feature = "video-tutorial")]
 pub mod video_accordion;
 
+pub use app_password_recreation::*;
+pub use blob_enumeration_summary::*;
+#[cfg(feature = "hash-verification")]
+pub use blob_integrity_audit::*;
+pub use blob_manifest_download::*;
 pub use blob_progress_display::*;
+pub use body_limit_fix::*;
+pub use content_copy_summary::*;
+pub use continuous_sync_panel::*;
+pub use domain_safety_note::*;
+pub use email_verification_panel::*;
+pub use help_mode_panel::*;
+pub use large_blob_progress::*;
+pub use live_progress::*;
 pub use loading_indicator::*;
+pub use migration_timeline::*;
+pub use panic_recovery_notice::*;
+pub use password_save_offer::*;
+pub use pds_endpoint_check::*;
+#[cfg(feature = "diagnostics-panel")]
+pub use pipeline_metrics_panel::*;
+pub use plc_token_expiry_wizard::*;
+pub use plc_token_wait_panel::*;
+pub use post_migration_checklist::*;
+pub use preferences_backup_download::*;
+pub use preferences_round_trip_summary::*;
+pub use propagation_monitor::*;
 pub use provider_display::*;
+pub use repo_car_checksum::*;
+pub use repo_import_failure::*;
+pub use repo_import_progress::*;
+pub use saved_feeds_check::*;
+pub use service_account_notice::*;
+#[cfg(feature = "session-transfer")]
+pub use session_transfer_panel::*;
+pub use source_endpoint_notice::*;
+pub use stall_notice::*;
+pub use storage_usage_meter::*;
+#[cfg(feature = "support-bundle")]
+pub use support_bundle_panel::*;
+#[cfg(feature = "web")]
+pub use tab_activity_notice::*;
+pub use target_cors_check::*;
+pub use unrecoverable_blobs::*;
+pub use upload_log_download::*;
+#[cfg(feature = "video-tutorial")]
 pub use video_accordion::*;
@@ -0,0 +1,55 @@
+use dioxus::prelude::*;
+
+use crate::app::panic_recovery::PanicReport;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct PanicRecoveryNoticeProps {
+    pub report: PanicReport,
+    /// Re-fill the forms from the journal and go back to where it stopped
+    pub on_resume: EventHandler<()>,
+    /// Forget the crash and start from an empty form
+    pub on_dismiss: EventHandler<()>,
+}
+
+/// Shown after a reload that followed a crash of the page
+#[component]
+pub fn PanicRecoveryNotice(props: PanicRecoveryNoticeProps) -> Element {
+    let report = props.report;
+
+    rsx! {
+        div {
+            class: "panic-recovery-notice",
+            role: "alert",
+            p {
+                strong { "The page stopped unexpectedly during the last visit. " }
+                "It was at {report.step_label()}."
+            }
+            details {
+                summary { "Error details" }
+                pre {
+                    class: "panic-overlay-message",
+                    "{report.message}"
+                    if let Some(location) = &report.location {
+                        "\n at {location}"
+                    }
+                }
+            }
+            p {
+                class: "blob-manifest-note",
+                "Resuming fills the forms in again and reuses the sessions kept in this browser. An account already created on the new PDS is reused when the migration starts again."
+            }
+            if report.journal.is_some() {
+                button {
+                    class: "validate-button",
+                    onclick: move |_| props.on_resume.call(()),
+                    "Resume from journal"
+                }
+            }
+            button {
+                class: "validate-button",
+                onclick: move |_| props.on_dismiss.call(()),
+                "Start over"
+            }
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use dioxus::prelude::*;
+
+use crate::console_warn;
+use crate::migration::password_manager::{
+    credential_management_supported, credential_name, save_password_credential,
+    CredentialSaveStatus,
+};
+use crate::migration::{MigrationAction, MigrationState};
+
+#[derive(Props, PartialEq, Clone)]
+pub struct PasswordSaveOfferProps {
+    pub state: Signal<MigrationState>,
+    pub dispatch: EventHandler<MigrationAction>,
+}
+
+/// Offer to save the new account's login in the browser's password manager
+///
+/// Shown once the account exists on the new PDS, in browsers with the
+/// Credential Management API.
+#[component]
+pub fn PasswordSaveOffer(props: PasswordSaveOfferProps) -> Element {
+    let state = props.state;
+    let dispatch = props.dispatch;
+    let mut saving = use_signal(|| false);
+
+    let Some(session) = state().new_pds_session else {
+        return rsx! {};
+    };
+    if !credential_management_supported() || state().form3.password.is_empty() {
+        return rsx! {};
+    }
+    let name = credential_name(&session.handle, &session.pds);
+
+    rsx! {
+        div {
+            class: "password-save-offer",
+            match state().credential_save {
+                CredentialSaveStatus::Offered => rsx! {
+                    p {
+                        class: "password-save-status",
+                        "🔑 Your browser was asked to save the login for {name}. If you turned it down, write the password down before you leave this page."
+                    }
+                },
+                status => rsx! {
+                    p {
+                        "🔑 Your new account's password only lives in this form. Save it now so you can log in to the new PDS later."
+                    }
+                    button {
+                        class: "validate-button",
+                        disabled: saving(),
+                        onclick: move |_| {
+                            let current = state();
+                            let Some(session) = current.new_pds_session else {
+                                return;
+                            };
+                            let password = current.form3.password.clone();
+                            saving.set(true);
+                            spawn(async move {
+                                let name = credential_name(&session.handle, &session.pds);
+                                let status = match save_password_credential(&session.handle, &password, &name).await {
+                                    Ok(()) => CredentialSaveStatus::Offered,
+                                    Err(error) => {
+                                        console_warn!("[Migration] Could not save the new password: {}", error);
+                                        CredentialSaveStatus::Failed(error)
+                                    }
+                                };
+                                dispatch.call(MigrationAction::SetCredentialSave(status));
+                                saving.set(false);
+                            });
+                        },
+                        "Save to password manager"
+                    }
+                    if let CredentialSaveStatus::Failed(error) = status {
+                        p {
+                            class: "password-save-status failed",
+                            "⚠️ {error}. Write the password down instead."
+                        }
+                    }
+                },
+            }
+        }
+    }
+}
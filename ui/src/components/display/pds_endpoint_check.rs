@@ -0,0 +1,103 @@
+use dioxus::prelude::*;
+
+use crate::components::display::DomainSafetyNote;
+use crate::utils::handle::normalize_pds_url;
+use crate::utils::lookalike::url_host;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct PdsEndpointCheckProps {
+    /// PDS the handle's DID document lists, `None` when it couldn't be found
+    pub resolved: Option<String>,
+    /// PDS the user typed instead
+    pub pds_override: Option<String>,
+    pub disabled: bool,
+    pub on_override: EventHandler<Option<String>>,
+}
+
+/// The PDS the login and export will use, shown before the password is sent,
+/// with a way to enter another one
+#[component]
+pub fn PdsEndpointCheck(props: PdsEndpointCheckProps) -> Element {
+    let on_override = props.on_override;
+    let mut editing = use_signal(|| false);
+    let mut draft = use_signal(String::new);
+    let endpoint = props.pds_override.clone().or(props.resolved.clone());
+    let edit_from = endpoint.clone().unwrap_or_default();
+
+    rsx! {
+        div {
+            class: "blob-manifest pds-endpoint-check",
+            match (&endpoint, &props.pds_override) {
+                (Some(url), None) => rsx! {
+                    span { "We will export from " }
+                    code { "{url}" }
+                    span { " — is this right?" }
+                },
+                (Some(url), Some(_)) => rsx! {
+                    span { "We will export from " }
+                    code { "{url}" }
+                    span { ", the PDS you entered." }
+                    if let Some(resolved) = props.resolved.clone() {
+                        span {
+                            class: "blob-manifest-note",
+                            " Your DID document lists {resolved}; the login is refused if the account doesn't live at the PDS you entered."
+                        }
+                    }
+                },
+                (None, _) => rsx! {
+                    span {
+                        class: "preferences-attention",
+                        "We couldn't find your PDS from this handle. Enter its address if you know it."
+                    }
+                },
+            }
+
+            if let Some(host) = endpoint.as_deref().and_then(url_host) {
+                DomainSafetyNote { domain: host }
+            }
+
+            if editing() {
+                div {
+                    class: "pds-endpoint-edit",
+                    input {
+                        r#type: "url",
+                        class: "input-field",
+                        placeholder: "https://pds.example.com",
+                        value: "{draft}",
+                        oninput: move |event| draft.set(event.value()),
+                    }
+                    button {
+                        class: "validate-button",
+                        disabled: normalize_pds_url(&draft()).is_empty(),
+                        onclick: move |_| {
+                            on_override.call(Some(normalize_pds_url(&draft())));
+                            editing.set(false);
+                        },
+                        "Use this PDS"
+                    }
+                    button {
+                        class: "validate-button",
+                        onclick: move |_| editing.set(false),
+                        "Cancel"
+                    }
+                }
+            } else if !props.disabled {
+                button {
+                    class: "validate-button",
+                    onclick: move |_| {
+                        draft.set(edit_from.clone());
+                        editing.set(true);
+                    },
+                    if endpoint.is_some() { "Change" } else { "Enter PDS" }
+                }
+                if props.pds_override.is_some() && props.resolved.is_some() {
+                    button {
+                        class: "validate-button",
+                        onclick: move |_| on_override.call(None),
+                        "Use the resolved PDS"
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use dioxus::prelude::*;
+
+use crate::services::client::{clear_host_stats, host_stats};
+use crate::services::streaming::{pipeline_stats, reset_pipeline_stats};
+use crate::utils::byte_format::format_throughput;
+use crate::utils::platform::sleep_ms;
+
+/// Refresh interval for the metrics table while the panel is mounted
+const REFRESH_INTERVAL_MS: u64 = 2000;
+
+/// Debug panel with per-backend latency percentiles and throughput for the blob
+/// pipeline, and what each PDS host has answered so far
+#[component]
+pub fn PipelineMetricsPanel() -> Element {
+    let mut stats = use_signal(pipeline_stats);
+    let mut hosts = use_signal(host_stats);
+
+    use_future(move || async move {
+        loop {
+            sleep_ms(REFRESH_INTERVAL_MS).await;
+            stats.set(pipeline_stats());
+            hosts.set(host_stats());
+        }
+    });
+
+    rsx! {
+        details {
+            class: "pipeline-metrics-panel",
+            summary { "📊 Pipeline metrics (debug)" }
+
+            if stats().is_empty() {
+                p {
+                    class: "support-bundle-help",
+                    "No storage or network operations recorded yet."
+                }
+            } else {
+                table {
+                    class: "pipeline-metrics-table",
+                    thead {
+                        tr {
+                            th { "Operation" }
+                            th { "Count" }
+                            th { "p50" }
+                            th { "p95" }
+                            th { "Max" }
+                            th { "Throughput" }
+                        }
+                    }
+                    tbody {
+                        for stat in stats() {
+                            tr {
+                                key: "{stat.operation.label()}",
+                                td { "{stat.operation.label()}" }
+                                td { "{stat.count}" }
+                                td { "{stat.p50_ms:.0} ms" }
+                                td { "{stat.p95_ms:.0} ms" }
+                                td { "{stat.max_ms:.0} ms" }
+                                td { {format_throughput(stat.throughput_bytes_per_sec)} }
+                            }
+                        }
+                    }
+                }
+            }
+
+            if !hosts().is_empty() {
+                table {
+                    class: "pipeline-metrics-table",
+                    thead {
+                        tr {
+                            th { "Host" }
+                            th { "Requests" }
+                            th { "Success" }
+                            th { "429s" }
+                            th { "Median" }
+                        }
+                    }
+                    tbody {
+                        for (host, host_stat) in hosts() {
+                            tr {
+                                key: "{host}",
+                                td { "{host}" }
+                                td { "{host_stat.requests}" }
+                                td { {format!("{:.0}%", host_stat.success_rate() * 100.0)} }
+                                td { "{host_stat.rate_limited}" }
+                                td {
+                                    match host_stat.median_latency_ms() {
+                                        Some(ms) => format!("{} ms", ms),
+                                        None => "—".to_string(),
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            button {
+                class: "validate-button",
+                onclick: move |_| {
+                    reset_pipeline_stats();
+                    clear_host_stats();
+                    stats.set(Vec::new());
+                    hosts.set(Vec::new());
+                },
+                "Reset"
+            }
+        }
+    }
+}
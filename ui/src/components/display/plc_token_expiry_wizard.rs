@@ -0,0 +1,113 @@
+use dioxus::prelude::*;
+
+use crate::components::display::send_another_plc_token;
+use crate::migration::{MigrationAction, MigrationState};
+use crate::services::config::get_global_config;
+use crate::utils::time_format::humanize_duration;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct PlcTokenExpiryWizardProps {
+    pub state: Signal<MigrationState>,
+    pub dispatch: EventHandler<MigrationAction>,
+    /// False once a fresh email has been requested and the user only has to
+    /// enter the new code
+    pub expired: bool,
+    /// A fresh email was requested
+    pub on_resent: EventHandler<()>,
+}
+
+/// Step-by-step recovery from an expired PLC token: explains what happened,
+/// requests a fresh email and points back at the code field, without
+/// touching anything already copied to the new PDS
+#[component]
+pub fn PlcTokenExpiryWizard(props: PlcTokenExpiryWizardProps) -> Element {
+    let state = props.state;
+    let dispatch = props.dispatch;
+    let mut sending = use_signal(|| false);
+    let mut send_error = use_signal(|| None::<String>);
+
+    let config = get_global_config().plc_token;
+    let validity = humanize_duration(config.token_validity_secs);
+    let current_step = if props.expired { 2 } else { 3 };
+    let status = if sending() {
+        "Requesting a new code..."
+    } else if !props.expired {
+        "A new email is on its way. Step 3 of 3: enter the new code."
+    } else {
+        "Your code has expired. Step 2 of 3: request a new code."
+    };
+
+    rsx! {
+        section {
+            class: "blob-enumeration-summary plc-token-expiry",
+            aria_labelledby: "plc-token-expiry-title",
+            h3 {
+                id: "plc-token-expiry-title",
+                tabindex: "-1",
+                // Screen readers and keyboard users land on the explanation
+                onmounted: move |event| async move {
+                    let _ = event.set_focus(true).await;
+                },
+                if props.expired { "Your verification code has expired" } else { "A new verification code is on its way" }
+            }
+            p {
+                class: "blob-enumeration-note",
+                "Codes from the \"{config.email_subject}\" email only work for {validity}. "
+                "Nothing is lost: your posts, media and settings are already on the new PDS, and the migration carries on from this step once you have a new code."
+            }
+            ol {
+                class: "plc-token-expiry-steps",
+                li {
+                    strong { "Step 1: " }
+                    "The old code can't be used any more. Codes from earlier emails stop working too."
+                }
+                li {
+                    aria_current: if current_step == 2 { "step" } else { "false" },
+                    strong { "Step 2: " }
+                    "Ask your old PDS for a new code. "
+                    button {
+                        class: "validate-button",
+                        disabled: sending() || !props.expired,
+                        aria_busy: if sending() { "true" } else { "false" },
+                        onclick: move |_| {
+                            sending.set(true);
+                            send_error.set(None);
+                            // The stale code would only be refused again
+                            dispatch.call(MigrationAction::SetPlcVerificationCode(String::new()));
+                            let Some(delivery) = state().plc_token_delivery else {
+                                sending.set(false);
+                                return;
+                            };
+                            spawn(async move {
+                                match send_another_plc_token(&delivery, dispatch).await {
+                                    Ok(()) => props.on_resent.call(()),
+                                    Err(error) => send_error.set(Some(error)),
+                                }
+                                sending.set(false);
+                            });
+                        },
+                        if sending() { "Sending..." } else { "Email me a new code" }
+                    }
+                }
+                li {
+                    aria_current: if current_step == 3 { "step" } else { "false" },
+                    strong { "Step 3: " }
+                    "Paste the code from the newest email into the Email Verification Code field above, then select \"Verify and Complete Migration\" again."
+                }
+            }
+            p {
+                class: "blob-enumeration-note",
+                role: "status",
+                aria_live: "polite",
+                "{status}"
+            }
+            if let Some(error) = send_error() {
+                p {
+                    class: "validation-result error",
+                    role: "alert",
+                    "✗ {error}"
+                }
+            }
+        }
+    }
+}
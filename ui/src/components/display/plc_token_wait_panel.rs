@@ -0,0 +1,170 @@
+use dioxus::prelude::*;
+
+use crate::components::display::PlcTokenExpiryWizard;
+use crate::migration::did_method::did_method_for;
+use crate::migration::plc_rules::validate_signed_plc_operation;
+use crate::migration::plc_token_delivery::{
+    parse_externally_signed_operation, save_plc_token_delivery, PlcTokenDelivery, TokenWaitStage,
+};
+use crate::migration::storage::LocalStorageManager;
+use crate::migration::{MigrationAction, MigrationState};
+use crate::services::client::{OldPds, PdsApi, PdsClient};
+use crate::services::config::get_global_config;
+use crate::utils::platform::{now_ms, sleep_ms};
+use crate::utils::time_format::format_duration_ms;
+
+/// How often the elapsed time is redrawn
+const WAIT_TICK_MS: u64 = 5_000;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct PlcTokenWaitPanelProps {
+    pub state: Signal<MigrationState>,
+    pub dispatch: EventHandler<MigrationAction>,
+}
+
+/// Time since the PLC token email was requested, with spam-folder guidance and,
+/// once it's overdue, a resend and the recovery key alternative. An expired
+/// token hands over to [`PlcTokenExpiryWizard`]
+#[component]
+pub fn PlcTokenWaitPanel(props: PlcTokenWaitPanelProps) -> Element {
+    let state = props.state;
+    let dispatch = props.dispatch;
+    let mut now = use_signal(|| now_ms() as u64);
+    let mut resending = use_signal(|| false);
+    let mut resend_error = use_signal(|| None::<String>);
+    // Keeps the wizard up after the resend until the new code is entered
+    let mut recovering = use_signal(|| false);
+
+    use_future(move || async move {
+        loop {
+            sleep_ms(WAIT_TICK_MS).await;
+            now.set(now_ms() as u64);
+        }
+    });
+
+    let Some(delivery) = state().plc_token_delivery else {
+        return rsx! {};
+    };
+    let config = get_global_config().plc_token;
+    let stage = delivery.stage(now(), &config);
+    if stage == TokenWaitStage::Expired
+        || (recovering() && state().form4.verification_code.trim().is_empty())
+    {
+        return rsx! {
+            PlcTokenExpiryWizard {
+                state,
+                dispatch,
+                expired: stage == TokenWaitStage::Expired,
+                on_resent: move |_| recovering.set(true),
+            }
+        };
+    }
+    let elapsed = format_duration_ms(delivery.elapsed_ms(now()));
+    let audit_log = format!("https://plc.directory/{}/log/audit", delivery.did);
+    let signed_operation = state().form4.signed_operation;
+    let signed_problems: Vec<String> = if signed_operation.trim().is_empty() {
+        Vec::new()
+    } else {
+        match parse_externally_signed_operation(&signed_operation) {
+            Ok(compact) => serde_json::from_str(&compact)
+                .map(|operation| validate_signed_plc_operation(&operation))
+                .unwrap_or_default(),
+            Err(error) => vec![error],
+        }
+    };
+
+    rsx! {
+        div {
+            class: "blob-enumeration-summary plc-token-wait",
+            div {
+                class: "blob-stats",
+                div {
+                    class: "blob-stat-item",
+                    span { class: "stat-label", "Token email requested:" }
+                    span { class: "stat-value", "{elapsed} ago" }
+                }
+                if delivery.resend_count > 0 {
+                    div {
+                        class: "blob-stat-item",
+                        span { class: "stat-label", "Resent:" }
+                        span { class: "stat-value", "{delivery.resend_count}×" }
+                    }
+                }
+            }
+
+            if stage != TokenWaitStage::Waiting {
+                p {
+                    class: "preferences-attention",
+                    "Nothing yet? Search your spam, junk and promotions folders for \"{config.email_subject}\". "
+                    "The email goes to the address on your old account; if that is an alias or forwarding address (Hide My Email, SimpleLogin, a catch-all on your own domain), check the inbox it forwards to."
+                }
+            }
+
+            if stage == TokenWaitStage::Overdue {
+                div {
+                    class: "blob-enumeration-note",
+                    button {
+                        class: "validate-button",
+                        disabled: resending(),
+                        onclick: move |_| {
+                            resending.set(true);
+                            resend_error.set(None);
+                            let delivery = delivery.clone();
+                            spawn(async move {
+                                if let Err(error) = send_another_plc_token(&delivery, dispatch).await {
+                                    resend_error.set(Some(error));
+                                }
+                                resending.set(false);
+                            });
+                        },
+                        if resending() { "Sending..." } else { "Send another email" }
+                    }
+                    " A new email replaces the earlier token, so use the code from the newest one."
+                    if let Some(error) = resend_error() {
+                        p { class: "validation-result error", "✗ {error}" }
+                    }
+                }
+
+                details {
+                    summary { "Sign with your recovery key instead" }
+                    p {
+                        class: "blob-enumeration-note",
+                        "If you added a recovery (rotation) key to your identity, you can sign the update yourself and skip the email. "
+                        "Sign the operation below with that key in your own tool, setting \"prev\" to the CID of the newest entry in "
+                        a { href: "{audit_log}", target: "_blank", rel: "noopener noreferrer", "your PLC audit log" }
+                        ", then paste the signed operation here. Never paste the key itself into this page."
+                    }
+                    pre { class: "did-document", "{state().form4.plc_unsigned}" }
+                    textarea {
+                        class: "input-field plc-signed-operation",
+                        placeholder: "Signed operation JSON, including \"prev\" and \"sig\"",
+                        value: "{signed_operation}",
+                        oninput: move |event| {
+                            dispatch.call(MigrationAction::SetPlcSignedOperation(event.value()));
+                        }
+                    }
+                    for problem in signed_problems {
+                        p { key: "{problem}", class: "validation-result error", "✗ {problem}" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Ask the old PDS to email another PLC token and restart the wait
+pub async fn send_another_plc_token(
+    delivery: &PlcTokenDelivery,
+    dispatch: EventHandler<MigrationAction>,
+) -> Result<(), String> {
+    let session = LocalStorageManager::get_old_session()
+        .map_err(|_| "Failed to get old PDS session".to_string())?;
+    let client = PdsClient::new();
+    let api: &dyn PdsApi = &client;
+    let old = OldPds::new(api, (&session).into());
+    did_method_for(&delivery.did)?.request_token(&old).await?;
+    let resent = delivery.resent(now_ms() as u64);
+    save_plc_token_delivery(&resent);
+    dispatch.call(MigrationAction::SetPlcTokenDelivery(Some(resent)));
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use dioxus::prelude::*;
+
+use crate::migration::post_migration_checklist::{
+    build_checklist, load_checklist_progress, save_checklist_progress, ChecklistContext,
+};
+use crate::migration::MigrationState;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct PostMigrationChecklistProps {
+    pub state: Signal<MigrationState>,
+}
+
+/// Follow-up tasks for this account, with ticks remembered in this browser
+#[component]
+pub fn PostMigrationChecklist(props: PostMigrationChecklistProps) -> Element {
+    let state = props.state;
+    let context = ChecklistContext::from_state(&state());
+    let did = context.did.clone();
+    let mut progress = use_signal(move || load_checklist_progress(&did));
+
+    if context.did.is_empty() {
+        return rsx! {};
+    }
+    let items = build_checklist(&context);
+    let done = items
+        .iter()
+        .filter(|item| progress().is_checked(item.id))
+        .count();
+    let total = items.len();
+
+    rsx! {
+        div {
+            class: "post-migration-checklist",
+            h4 {
+                class: "instructions-title",
+                "✅ Your checklist ({done}/{total})"
+            }
+            ul {
+                class: "checklist-items",
+                for item in items {
+                    li {
+                        key: "{item.id:?}",
+                        class: if progress().is_checked(item.id) { "checklist-item done" } else { "checklist-item" },
+                        label {
+                            input {
+                                r#type: "checkbox",
+                                checked: progress().is_checked(item.id),
+                                onchange: move |event| {
+                                    let mut updated = progress();
+                                    updated.set(item.id, event.checked());
+                                    save_checklist_progress(&updated);
+                                    progress.set(updated);
+                                },
+                            }
+                            span {
+                                class: "checklist-title",
+                                " {item.title}"
+                            }
+                        }
+                        div {
+                            class: "checklist-detail",
+                            "{item.detail}"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use base64::Engine;
+use dioxus::prelude::*;
+
+use crate::migration::preferences_backup::{last_preferences_backup_json, PreferencesBackupInfo};
+
+#[derive(Props, PartialEq, Clone)]
+pub struct PreferencesBackupDownloadProps {
+    pub backup: PreferencesBackupInfo,
+}
+
+/// Download link for the preferences saved before they were imported
+#[component]
+pub fn PreferencesBackupDownload(props: PreferencesBackupDownloadProps) -> Element {
+    let backup = props.backup;
+    let Some(json) = last_preferences_backup_json() else {
+        return rsx! {};
+    };
+    let href = format!(
+        "data:application/json;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(json)
+    );
+    let stored = if backup.saved_to_opfs {
+        "A copy is also kept in this browser's storage."
+    } else {
+        "This browser could not keep a copy, so download it now if you want one."
+    };
+
+    rsx! {
+        div {
+            class: "preferences-backup",
+            a {
+                class: "banner-link",
+                href: "{href}",
+                download: "{backup.file_name}",
+                "⬇️ Download preferences backup ({backup.categories.len()} settings)"
+            }
+            span {
+                class: "preferences-backup-note",
+                " {stored}"
+            }
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use dioxus::prelude::*;
+
+use crate::migration::preferences_roundtrip::{PreferencesRoundTrip, RoundTripOutcome};
+
+#[derive(Props, PartialEq, Clone)]
+pub struct PreferencesRoundTripSummaryProps {
+    pub report: PreferencesRoundTrip,
+}
+
+/// Which preference categories the new PDS kept, changed or dropped, and what
+/// to redo by hand
+#[component]
+pub fn PreferencesRoundTripSummary(props: PreferencesRoundTripSummaryProps) -> Element {
+    let report = props.report;
+    let labels = |outcome: RoundTripOutcome| {
+        report
+            .with_outcome(outcome)
+            .map(|category| category.label.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let kept = labels(RoundTripOutcome::RoundTripped);
+    let changed = labels(RoundTripOutcome::Changed);
+    let dropped = labels(RoundTripOutcome::Dropped);
+
+    rsx! {
+        div {
+            class: "blob-enumeration-summary",
+            h4 { class: "blob-progress-title", "Preferences Check" }
+            if report.is_complete() {
+                p {
+                    class: "blob-enumeration-note",
+                    "✅ All {report.categories.len()} preference categories came back unchanged from the new PDS."
+                }
+            } else {
+                div {
+                    class: "blob-stats",
+                    if !kept.is_empty() {
+                        div {
+                            class: "blob-stat-item",
+                            span { class: "stat-label", "Carried over:" }
+                            span { class: "stat-value", "{kept}" }
+                        }
+                    }
+                    if !changed.is_empty() {
+                        div {
+                            class: "blob-stat-item",
+                            span { class: "stat-label", "Changed by the server:" }
+                            span { class: "stat-value", "{changed}" }
+                        }
+                    }
+                    if !dropped.is_empty() {
+                        div {
+                            class: "blob-stat-item",
+                            span { class: "stat-label", "Dropped by the server:" }
+                            span { class: "stat-value", "{dropped}" }
+                        }
+                    }
+                }
+                if report.needs_attention().next().is_some() {
+                    ul {
+                        class: "preferences-attention",
+                        for category in report.needs_attention() {
+                            li {
+                                key: "{category.category}",
+                                strong { "{category.label}: " }
+                                {category.attention.clone().unwrap_or_default()}
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
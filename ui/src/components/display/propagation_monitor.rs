@@ -0,0 +1,39 @@
+use dioxus::prelude::*;
+
+use crate::services::client::{PropagationReport, PropagationStatus};
+
+#[derive(Props, PartialEq, Clone)]
+pub struct PropagationMonitorProps {
+    pub report: PropagationReport,
+}
+
+/// Whether the relay and AppView have picked up the activated account
+#[component]
+pub fn PropagationMonitor(props: PropagationMonitorProps) -> Element {
+    let report = props.report;
+    let summary = if report.is_complete() {
+        "Your account is visible across the network.".to_string()
+    } else if report.finished {
+        "The network hasn't caught up yet. This usually resolves on its own within the hour."
+            .to_string()
+    } else {
+        format!("Checking the network (check {})...", report.polls + 1)
+    };
+
+    rsx! {
+        div {
+            class: "propagation-monitor",
+            h4 { class: "blob-progress-title", "Network Propagation" }
+            p { class: "propagation-summary", "{summary}" }
+            ul {
+                class: "propagation-checks",
+                for check in report.checks.iter() {
+                    li {
+                        class: if check.status == PropagationStatus::Done { "propagation-check done" } else { "propagation-check" },
+                        "{check.status.icon()} {check.kind.label()}: {check.detail}"
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use dioxus::prelude::*;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct RepoCarChecksumProps {
+    /// Hex sha-256 of the exported repository CAR
+    pub checksum: String,
+}
+
+/// Checksum of the repository as exported, for comparing with a CAR saved by
+/// another tool
+#[component]
+pub fn RepoCarChecksum(props: RepoCarChecksumProps) -> Element {
+    rsx! {
+        div {
+            class: "blob-manifest",
+            span { class: "stat-label", "Repository export SHA-256: " }
+            code { class: "repo-car-checksum", "{props.checksum}" }
+            span {
+                class: "blob-manifest-note",
+                " Hashed while downloading and checked again before the import, so the new PDS received exactly what the old one exported."
+            }
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use dioxus::prelude::*;
+
+use crate::services::client::{
+    body_limit_fix, identify_limit_source, last_body_limit_report, ImportDiagnosis,
+    ImportFailureCause,
+};
+use crate::services::config::get_global_config;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct RepoImportFailureViewProps {
+    pub diagnosis: ImportDiagnosis,
+}
+
+/// Why the new PDS refused the repository, what to try next, and the server's
+/// answer verbatim for whoever runs that PDS
+#[component]
+pub fn RepoImportFailureView(props: RepoImportFailureViewProps) -> Element {
+    let diagnosis = props.diagnosis;
+    let body = if diagnosis.body.trim().is_empty() {
+        "(empty response body)".to_string()
+    } else {
+        diagnosis.body.clone()
+    };
+
+    // The body usually names the proxy that refused the CAR
+    let size_fix = (diagnosis.cause == ImportFailureCause::CarTooLarge).then(|| {
+        let needed = last_body_limit_report()
+            .map(|report| report.needed_bytes)
+            .unwrap_or(get_global_config().blob.assumed_largest_blob_bytes);
+        body_limit_fix(identify_limit_source(None, &diagnosis.body), needed)
+    });
+
+    rsx! {
+        div {
+            class: "blob-enumeration-summary",
+            h4 { class: "blob-progress-title", "{diagnosis.cause.label()}" }
+            p {
+                class: "blob-enumeration-note",
+                "{diagnosis.cause.next_action()}"
+            }
+            if let Some(fix) = size_fix {
+                pre { class: "repo-import-failure-body", "{fix}" }
+            }
+            if let Some(offending) = diagnosis.offending.clone() {
+                div {
+                    class: "blob-stats",
+                    div {
+                        class: "blob-stat-item",
+                        span { class: "stat-label", "Server named:" }
+                        code { class: "stat-value", "{offending}" }
+                    }
+                }
+            }
+            details {
+                summary { "Server response (HTTP {diagnosis.status})" }
+                pre { class: "repo-import-failure-body", "{body}" }
+            }
+        }
+    }
+}
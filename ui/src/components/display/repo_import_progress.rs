@@ -0,0 +1,77 @@
+use crate::migration::steps::repo_import::RepoImportStatus;
+use dioxus::prelude::*;
+
+#[derive(Props, PartialEq, Clone, Debug)]
+pub struct RepoImportProgressProps {
+    pub status: RepoImportStatus,
+}
+
+/// Records and blocks indexed on the target, with a per-collection breakdown when known
+#[component]
+pub fn RepoImportProgress(props: RepoImportProgressProps) -> Element {
+    let status = &props.status;
+    let records = match status.expected_records {
+        Some(expected) => format!("{} / {}", status.indexed_records, expected),
+        None => status.indexed_records.to_string(),
+    };
+    let blocks = match status.expected_blocks {
+        Some(expected) => format!("{} / {}", status.repo_blocks, expected),
+        None => status.repo_blocks.to_string(),
+    };
+
+    rsx! {
+        div {
+            class: "repo-import-progress",
+
+            h4 {
+                class: "blob-progress-title",
+                "Repository Import"
+            }
+
+            if let Some(ratio) = status.record_ratio() {
+                div {
+                    class: "progress-bar-container",
+                    div {
+                        class: "progress-bar-background",
+                        div {
+                            class: "progress-bar-fill",
+                            style: format!("width: {}%", ratio * 100.0),
+                        }
+                    }
+                    span {
+                        class: "progress-percentage",
+                        "{ratio * 100.0:.1}%"
+                    }
+                }
+            }
+
+            div {
+                class: "blob-stats",
+                div {
+                    class: "blob-stat-item",
+                    span { class: "stat-label", "Indexed records:" }
+                    span { class: "stat-value", "{records}" }
+                }
+                div {
+                    class: "blob-stat-item",
+                    span { class: "stat-label", "Repo blocks:" }
+                    span { class: "stat-value", "{blocks}" }
+                }
+            }
+
+            if !status.collections.is_empty() {
+                ul {
+                    class: "repo-import-collections",
+                    for collection in status.collections.iter() {
+                        li {
+                            key: "{collection.nsid}",
+                            class: if collection.imported { "imported" } else { "pending" },
+                            if collection.imported { "✅ " } else { "○ " }
+                            "{collection.nsid}"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use dioxus::prelude::*;
+
+use crate::services::client::{ReferenceStatus, SavedFeedsReport};
+
+#[derive(Props, PartialEq, Clone)]
+pub struct SavedFeedsCheckProps {
+    pub report: SavedFeedsReport,
+}
+
+/// Saved feeds and lists that no longer resolve from the new account, so they
+/// can be re-added or replaced
+#[component]
+pub fn SavedFeedsCheck(props: SavedFeedsCheckProps) -> Element {
+    let report = props.report;
+    if report.checks.is_empty() {
+        return rsx! {};
+    }
+    let resolved = report.with_status(ReferenceStatus::Resolves).count();
+    let unchecked = report.with_status(ReferenceStatus::Unchecked).count();
+    let dead_count = report.dead().count();
+
+    rsx! {
+        div {
+            class: "blob-enumeration-summary",
+            h4 { class: "blob-progress-title", "Saved Feeds and Lists" }
+            if dead_count == 0 && unchecked == 0 {
+                p {
+                    class: "blob-enumeration-note",
+                    "✅ All {resolved} saved feeds and lists still work from the new account."
+                }
+            } else {
+                div {
+                    class: "blob-stats",
+                    div {
+                        class: "blob-stat-item",
+                        span { class: "stat-label", "Still working:" }
+                        span { class: "stat-value", "{resolved}" }
+                    }
+                    div {
+                        class: "blob-stat-item",
+                        span { class: "stat-label", "Dead:" }
+                        span { class: "stat-value", "{dead_count}" }
+                    }
+                    if unchecked > 0 {
+                        div {
+                            class: "blob-stat-item",
+                            span { class: "stat-label", "Couldn't check:" }
+                            span { class: "stat-value", "{unchecked}" }
+                        }
+                    }
+                }
+                if dead_count > 0 {
+                    p {
+                        class: "blob-enumeration-note",
+                        "These are still saved but show nothing. Remove them from the Feeds tab and find a replacement:"
+                    }
+                    ul {
+                        class: "preferences-attention",
+                        for check in report.dead() {
+                            li {
+                                key: "{check.reference.uri}",
+                                strong {
+                                    if check.reference.pinned {
+                                        "📌 {check.reference.kind.label()}: "
+                                    } else {
+                                        "{check.reference.kind.label()}: "
+                                    }
+                                }
+                                "{check.detail} "
+                                code { "{check.reference.uri}" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use dioxus::prelude::*;
+
+use crate::migration::service_accounts::ServiceAccountProfile;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct ServiceAccountNoticeProps {
+    pub profile: ServiceAccountProfile,
+    /// `plc` or `web`; did:web documents need the services copied by hand
+    #[props(into)]
+    pub did_method: String,
+}
+
+/// Labeler or feed generator services kept through the identity update, and what's left to do
+#[component]
+pub fn ServiceAccountNotice(props: ServiceAccountNoticeProps) -> Element {
+    let profile = props.profile;
+    let kinds = if profile.kinds.is_empty() {
+        "Service account".to_string()
+    } else {
+        profile
+            .kinds
+            .iter()
+            .map(|kind| kind.label())
+            .collect::<Vec<_>>()
+            .join(" and ")
+    };
+    let manual_steps = profile.manual_steps(&props.did_method);
+    let checks: Vec<(bool, String)> = profile
+        .endpoint_checks
+        .iter()
+        .map(|check| match &check.found {
+            _ if check.is_ok() => (
+                true,
+                format!("✅ {} still points at {}", check.id, check.expected),
+            ),
+            Some(found) => (
+                false,
+                format!(
+                    "⚠️ {} should point at {} but points at {}",
+                    check.id, check.expected, found
+                ),
+            ),
+            None => (
+                false,
+                format!("⚠️ {} is missing from the updated DID document", check.id),
+            ),
+        })
+        .collect();
+
+    rsx! {
+        div {
+            class: "service-account-notice",
+            h4 { class: "blob-progress-title", "{kinds} account" }
+            p {
+                class: "service-account-summary",
+                "These DID document entries are kept in the identity update:"
+            }
+            ul {
+                class: "service-account-entries",
+                for service in profile.services.iter() {
+                    li { "{service.id} ({service.service_type}): {service.endpoint}" }
+                }
+                for key in profile.extra_keys.iter() {
+                    li { "{key} signing key" }
+                }
+            }
+            if !checks.is_empty() {
+                ul {
+                    class: "service-account-checks",
+                    for (ok, line) in checks.iter() {
+                        li {
+                            class: if *ok { "service-account-check ok" } else { "service-account-check" },
+                            "{line}"
+                        }
+                    }
+                }
+            }
+            if !manual_steps.is_empty() {
+                p { class: "service-account-summary", strong { "You still need to:" } }
+                ul {
+                    class: "service-account-steps",
+                    for step in manual_steps.iter() {
+                        li { "{step}" }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use dioxus::prelude::*;
+
+use crate::migration::session_transfer::{
+    generate_transfer_code, SESSION_TRANSFER_FRAGMENT, SESSION_TRANSFER_TTL_SECS,
+};
+use crate::migration::storage::LocalStorageManager;
+use crate::migration::{MigrationAction, MigrationState, SessionTransfer};
+use crate::utils::platform::{current_page_url, current_url_fragment, now_secs};
+use crate::utils::qr::QrCode;
+use crate::{console_info, console_warn};
+
+#[derive(Props, PartialEq, Clone)]
+pub struct SessionTransferPanelProps {
+    pub state: Signal<MigrationState>,
+    pub dispatch: EventHandler<MigrationAction>,
+}
+
+/// An exported transfer as shown on the sending device
+#[derive(Clone, PartialEq)]
+struct ExportedTransfer {
+    code: String,
+    /// Link when the page URL is known, otherwise the bare transfer string
+    text: String,
+    qr_data_url: Option<String>,
+    includes_session: bool,
+}
+
+/// Hand the filled-in forms to another device, or pick them up from one
+#[component]
+pub fn SessionTransferPanel(props: SessionTransferPanelProps) -> Element {
+    let state = props.state;
+    let dispatch = props.dispatch;
+    let mut include_session = use_signal(|| false);
+    let mut exported = use_signal(|| None::<ExportedTransfer>);
+    let mut export_error = use_signal(|| None::<String>);
+    // A transfer link opens straight into the importer
+    let mut pasted = use_signal(|| {
+        current_url_fragment()
+            .filter(|fragment| fragment.starts_with(SESSION_TRANSFER_FRAGMENT))
+            .unwrap_or_default()
+    });
+    let opened_from_link = !pasted.peek().is_empty();
+    let mut code = use_signal(String::new);
+    let mut import_status = use_signal(|| None::<Result<String, String>>);
+
+    let has_session = state()
+        .form1
+        .login_response
+        .as_ref()
+        .is_some_and(|response| response.session.is_some());
+    let can_export = !state().form1.handle.trim().is_empty() && !state().is_migrating;
+
+    rsx! {
+        details {
+            class: "session-transfer-panel",
+            open: opened_from_link,
+            summary { "📲 Continue on another device" }
+
+            p {
+                class: "session-transfer-help",
+                "Started on your phone? Move what you've filled in to a computer for the transfer itself. Passwords, your email address and invite codes are never included; the transfer expires after {SESSION_TRANSFER_TTL_SECS / 60} minutes."
+            }
+
+            if can_export {
+                label {
+                    class: "session-transfer-consent",
+                    input {
+                        r#type: "checkbox",
+                        disabled: !has_session,
+                        checked: include_session(),
+                        onchange: move |event| include_session.set(event.checked()),
+                    }
+                    " Also send my login to the current PDS, so the other device doesn't need my password. Anyone with the QR code and the transfer code can act as me until it expires."
+                }
+
+                button {
+                    class: "validate-button",
+                    onclick: move |_| {
+                        let with_session = include_session() && has_session;
+                        let transfer = SessionTransfer::capture(&state.read(), with_session, now_secs());
+                        let transfer_code = generate_transfer_code();
+                        match transfer.encrypt(&transfer_code) {
+                            Ok(encrypted) => {
+                                let text = current_page_url()
+                                    .map(|url| SessionTransfer::link(&url, &encrypted))
+                                    .unwrap_or(encrypted);
+                                let qr_data_url = QrCode::encode(text.as_bytes()).map(|qr| qr.to_svg_data_url());
+                                if qr_data_url.is_none() {
+                                    console_warn!("[SessionTransfer] Transfer is too large for a QR code; copy it instead");
+                                }
+                                exported.set(Some(ExportedTransfer {
+                                    code: transfer_code,
+                                    text,
+                                    qr_data_url,
+                                    includes_session: with_session,
+                                }));
+                                export_error.set(None);
+                            }
+                            Err(e) => export_error.set(Some(e)),
+                        }
+                    },
+                    "Create transfer"
+                }
+            }
+
+            if let Some(transfer) = exported() {
+                div {
+                    class: "session-transfer-export",
+                    if let Some(src) = transfer.qr_data_url.clone() {
+                        img {
+                            class: "session-transfer-qr",
+                            src: "{src}",
+                            alt: "QR code of the transfer link",
+                        }
+                    }
+                    div {
+                        class: "session-transfer-code",
+                        "Transfer code: "
+                        strong { "{transfer.code}" }
+                    }
+                    p {
+                        class: "session-transfer-help",
+                        "Scan the QR code or copy the text below to the other device, then type the transfer code there."
+                        if transfer.includes_session {
+                            " Stop using this device for the migration once it has moved."
+                        }
+                    }
+                    textarea {
+                        class: "input-field session-transfer-text",
+                        readonly: true,
+                        rows: "3",
+                        value: "{transfer.text}",
+                    }
+                }
+            }
+
+            if let Some(message) = export_error() {
+                div { class: "validation-result error", "✗ {message}" }
+            }
+
+            div {
+                class: "session-transfer-import",
+                label { class: "input-label", "Continuing from another device? Paste the transfer or link:" }
+                textarea {
+                    class: "input-field",
+                    rows: "2",
+                    value: "{pasted}",
+                    oninput: move |event| pasted.set(event.value()),
+                }
+                input {
+                    class: "input-field",
+                    placeholder: "Transfer code",
+                    autocomplete: "off",
+                    value: "{code}",
+                    oninput: move |event| code.set(event.value()),
+                }
+                button {
+                    class: "validate-button",
+                    disabled: pasted().trim().is_empty() || code().trim().is_empty() || state().is_migrating,
+                    onclick: move |_| {
+                        let result = SessionTransfer::decrypt(&pasted(), &code(), now_secs()).and_then(|transfer| {
+                            if let Some(session) = &transfer.old_session {
+                                LocalStorageManager::store_old_session(session)
+                                    .map_err(|e| format!("Could not store the transferred login: {}", e))?;
+                            }
+                            for action in transfer.actions() {
+                                dispatch.call(action);
+                            }
+                            console_info!("[SessionTransfer] Imported transfer created {}", crate::utils::time_format::format_local_datetime(transfer.created_at_secs * 1000));
+                            Ok(if transfer.old_session.is_some() {
+                                "Picked up where the other device left off. Enter your email and any invite code again, then continue.".to_string()
+                            } else {
+                                "Details filled in. Log in to your current PDS, then enter your email and any invite code again.".to_string()
+                            })
+                        });
+                        if result.is_ok() {
+                            code.set(String::new());
+                        }
+                        import_status.set(Some(result));
+                    },
+                    "Continue here"
+                }
+                match import_status() {
+                    Some(Ok(message)) => rsx! {
+                        div { class: "validation-result success", "✓ {message}" }
+                    },
+                    Some(Err(message)) => rsx! {
+                        div { class: "validation-result error", "✗ {message}" }
+                    },
+                    None => rsx! {},
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use dioxus::prelude::*;
+
+use crate::migration::source_endpoint::SourceEndpointChange;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct SourceEndpointNoticeProps {
+    pub change: SourceEndpointChange,
+}
+
+/// The old PDS endpoint the migration switched to after the original stopped
+/// answering
+#[component]
+pub fn SourceEndpointNotice(props: SourceEndpointNoticeProps) -> Element {
+    rsx! {
+        div {
+            class: "blob-manifest source-endpoint-notice",
+            span { class: "stat-label", "Old PDS moved: " }
+            code { "{props.change.from}" }
+            " → "
+            code { "{props.change.to}" }
+            span {
+                class: "blob-manifest-note",
+                " {props.change.from} stopped answering and your DID document now lists {props.change.to}, so the migration continued from there."
+            }
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use dioxus::prelude::*;
+
+use crate::migration::watchdog::probe_connectivity;
+use crate::migration::{ConnectivityCheck, MigrationAction, MigrationState, StallAction};
+use crate::services::client::recent_http_traces;
+use crate::utils::platform::{now_ms, sleep_ms};
+use crate::utils::time_format::format_duration_ms;
+
+/// How often the watchdog re-evaluates the idle time while mounted
+const WATCHDOG_TICK_MS: u64 = 10_000;
+
+/// Requests shown when the user asks for recent traces
+const TRACES_SHOWN: usize = 10;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct StallNoticeProps {
+    pub state: Signal<MigrationState>,
+    pub dispatch: EventHandler<MigrationAction>,
+}
+
+/// Warning with suggested actions when the running step stops making progress
+#[component]
+pub fn StallNotice(props: StallNoticeProps) -> Element {
+    let state = props.state;
+    let dispatch = props.dispatch;
+    let mut now = use_signal(|| now_ms() as u64);
+    let mut checks = use_signal(|| None::<Vec<ConnectivityCheck>>);
+    let mut checking = use_signal(|| false);
+    let mut show_traces = use_signal(|| false);
+
+    use_future(move || async move {
+        loop {
+            sleep_ms(WATCHDOG_TICK_MS).await;
+            now.set(now_ms() as u64);
+        }
+    });
+
+    let Some(stall) = state().stall(now()) else {
+        return rsx! {};
+    };
+    let step_label = stall.step.label();
+    let idle = format_duration_ms(stall.idle_ms);
+
+    rsx! {
+        div {
+            class: "stall-notice",
+            div {
+                class: "stall-notice-title",
+                "⚠️ The {step_label} step has made no progress for {idle}"
+            }
+            p {
+                class: "stall-notice-help",
+                "It may still finish, but if nothing changes it's usually a dropped connection or a PDS that stopped responding."
+            }
+
+            div {
+                class: "stall-notice-actions",
+                for action in stall.actions.iter().copied() {
+                    button {
+                        key: "{action.label()}",
+                        class: "validate-button",
+                        disabled: action == StallAction::CheckNetwork && checking(),
+                        onclick: move |_| match action {
+                            StallAction::RetryStep => {
+                                dispatch.call(MigrationAction::SetMigrationAborted(true));
+                                dispatch.call(MigrationAction::SetMigrationError(Some(format!(
+                                    "The {} step was stopped after making no progress. Start the migration again to retry; an account already created on the new PDS is reused.",
+                                    step_label
+                                ))));
+                                dispatch.call(MigrationAction::SetMigrating(false));
+                            }
+                            StallAction::CheckNetwork => {
+                                checking.set(true);
+                                let snapshot = state();
+                                spawn(async move {
+                                    checks.set(Some(probe_connectivity(&snapshot).await));
+                                    checking.set(false);
+                                });
+                            }
+                            StallAction::ViewTraces => show_traces.set(!show_traces()),
+                        },
+                        "{action.label()}"
+                    }
+                }
+            }
+
+            if let Some(results) = checks() {
+                ul {
+                    class: "stall-notice-checks",
+                    for check in results {
+                        li {
+                            key: "{check.url}",
+                            match &check.result {
+                                Ok(ms) => rsx! { "✓ {check.label} ({check.url}) answered in {ms} ms" },
+                                Err(error) => rsx! { "✗ {check.label} ({check.url}): {error}" },
+                            }
+                        }
+                    }
+                }
+            }
+
+            if show_traces() {
+                ul {
+                    class: "stall-notice-traces",
+                    for trace in recent_http_traces().into_iter().rev().take(TRACES_SHOWN) {
+                        li {
+                            "{trace.method} {trace.url} → "
+                            match (trace.status, &trace.error) {
+                                (Some(status), _) => rsx! { "{status}" },
+                                (None, Some(error)) => rsx! { "{error}" },
+                                (None, None) => rsx! { "pending" },
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use crate::services::streaming::StorageUsage;
+use crate::utils::byte_format::format_size;
+use dioxus::prelude::*;
+
+#[derive(Props, PartialEq, Clone, Debug)]
+pub struct StorageUsageMeterProps {
+    pub usage: StorageUsage,
+}
+
+/// Live gauge of origin storage usage vs quota with a per-backend breakdown
+#[component]
+pub fn StorageUsageMeter(props: StorageUsageMeterProps) -> Element {
+    let usage = &props.usage;
+    let level = usage.level();
+    let percentage = (usage.usage_ratio() * 100.0).min(100.0);
+
+    rsx! {
+        div {
+            class: "storage-usage-meter {level.css_class()}",
+
+            h4 {
+                class: "blob-progress-title",
+                "Browser Storage"
+            }
+
+            div {
+                class: "progress-bar-container",
+                div {
+                    class: "progress-bar-background",
+                    div {
+                        class: "progress-bar-fill storage-usage-fill",
+                        style: format!("width: {}%", percentage),
+                    }
+                }
+                span {
+                    class: "progress-percentage",
+                    "{percentage:.1}%"
+                }
+            }
+
+            div {
+                class: "blob-stats",
+                BackendStat { label: "Origin:", value: format!("{} / {}", format_size(usage.origin_usage), format_size(usage.origin_quota)) }
+                BackendStat { label: "OPFS:", bytes: usage.backends.opfs_bytes }
+                BackendStat { label: "IndexedDB:", bytes: usage.backends.indexeddb_bytes }
+                BackendStat { label: "Memory:", bytes: usage.backends.memory_bytes }
+            }
+
+            if let Some(message) = level.message() {
+                div {
+                    class: "storage-usage-warning",
+                    "⚠️ {message}"
+                }
+            }
+        }
+    }
+}
+
+#[derive(Props, PartialEq, Clone)]
+struct BackendStatProps {
+    label: &'static str,
+    #[props(default)]
+    bytes: u64,
+    #[props(default)]
+    value: Option<String>,
+}
+
+#[component]
+fn BackendStat(props: BackendStatProps) -> Element {
+    let value = props
+        .value
+        .clone()
+        .unwrap_or_else(|| format_size(props.bytes));
+
+    rsx! {
+        div {
+            class: "blob-stat-item",
+            span { class: "stat-label", "{props.label}" }
+            span { class: "stat-value", "{value}" }
+        }
+    }
+}
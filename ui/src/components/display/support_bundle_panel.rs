@@ -0,0 +1,127 @@
+use base64::Engine;
+use dioxus::prelude::*;
+
+use crate::migration::{MigrationEventLog, MigrationState, SupportBundle};
+
+#[derive(Props, PartialEq, Clone)]
+pub struct SupportBundlePanelProps {
+    pub state: Signal<MigrationState>,
+}
+
+/// Export an encrypted support bundle for maintainers; debug builds can also load one
+#[component]
+pub fn SupportBundlePanel(props: SupportBundlePanelProps) -> Element {
+    let state = props.state;
+    let event_log = use_context::<Signal<MigrationEventLog>>();
+    let mut passphrase = use_signal(String::new);
+    let mut download = use_signal(|| None::<(String, String)>);
+    let mut error = use_signal(|| None::<String>);
+
+    rsx! {
+        details {
+            class: "support-bundle-panel",
+            summary { "🛟 Export support bundle" }
+
+            p {
+                class: "support-bundle-help",
+                "Creates an encrypted file with diagnostics (no passwords or tokens). Send the file and the passphrase to the maintainers separately."
+            }
+
+            input {
+                class: "input-field",
+                r#type: "password",
+                placeholder: "Passphrase",
+                value: "{passphrase}",
+                oninput: move |event| passphrase.set(event.value()),
+            }
+
+            button {
+                class: "validate-button",
+                disabled: passphrase().is_empty(),
+                onclick: move |_| {
+                    let bundle = SupportBundle::capture(&state.read(), &event_log.read());
+                    match bundle.encrypt(&passphrase()) {
+                        Ok(envelope) => {
+                            let encoded = base64::engine::general_purpose::STANDARD.encode(envelope);
+                            download.set(Some((
+                                format!("data:application/json;base64,{}", encoded),
+                                bundle.file_name(),
+                            )));
+                            error.set(None);
+                        }
+                        Err(e) => error.set(Some(e.to_string())),
+                    }
+                },
+                "Generate bundle"
+            }
+
+            if let Some((href, file_name)) = download() {
+                a {
+                    class: "banner-link",
+                    href: "{href}",
+                    download: "{file_name}",
+                    "⬇️ Download {file_name}"
+                }
+            }
+
+            if let Some(message) = error() {
+                div {
+                    class: "validation-result error",
+                    "✗ {message}"
+                }
+            }
+
+            if cfg!(debug_assertions) {
+                SupportBundleLoader { state: state }
+            }
+        }
+    }
+}
+
+/// Development-only loader that decrypts a bundle and replays its actions into the UI
+#[component]
+fn SupportBundleLoader(mut state: Signal<MigrationState>) -> Element {
+    let mut contents = use_signal(String::new);
+    let mut passphrase = use_signal(String::new);
+    let mut status = use_signal(|| None::<String>);
+
+    rsx! {
+        div {
+            class: "support-bundle-loader",
+            label { class: "input-label", "Load bundle (debug builds only):" }
+            textarea {
+                class: "input-field",
+                rows: "4",
+                value: "{contents}",
+                oninput: move |event| contents.set(event.value()),
+            }
+            input {
+                class: "input-field",
+                r#type: "password",
+                placeholder: "Passphrase",
+                value: "{passphrase}",
+                oninput: move |event| passphrase.set(event.value()),
+            }
+            button {
+                class: "validate-button",
+                onclick: move |_| {
+                    match SupportBundle::decrypt(&contents(), &passphrase()) {
+                        Ok(bundle) => {
+                            state.set(bundle.replay());
+                            status.set(Some(format!(
+                                "Replayed {} actions ({} dropped before export)",
+                                bundle.actions.len(),
+                                bundle.dropped_action_count
+                            )));
+                        }
+                        Err(e) => status.set(Some(e.to_string())),
+                    }
+                },
+                "Replay bundle"
+            }
+            if let Some(message) = status() {
+                div { class: "validation-result", "{message}" }
+            }
+        }
+    }
+}
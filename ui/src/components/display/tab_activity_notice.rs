@@ -0,0 +1,161 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::migration::tab_activity::{
+    is_page_hidden, release_wake_lock, request_wake_lock, DriftMeter, DRIFT_TICK_MS,
+};
+use crate::migration::{ConsoleEntry, MigrationAction, MigrationState, WakeLockStatus};
+use crate::utils::console_macros::ConsoleLevel;
+use crate::utils::platform::{now_ms, sleep_ms};
+use crate::utils::time_format::format_duration_ms;
+use crate::{console_info, console_warn};
+
+#[derive(Props, PartialEq, Clone)]
+pub struct TabActivityNoticeProps {
+    pub state: Signal<MigrationState>,
+    pub dispatch: EventHandler<MigrationAction>,
+}
+
+/// Keeps the screen awake while mounted and warns when a background tab gets throttled
+///
+/// Mounted only while a migration runs; unmounting releases the wake lock and
+/// removes the visibility listener.
+#[component]
+pub fn TabActivityNotice(props: TabActivityNoticeProps) -> Element {
+    let state = props.state;
+    let dispatch = props.dispatch;
+    let wake_lock = use_hook(|| Rc::new(RefCell::new(None::<JsValue>)));
+    let listener = use_hook(|| Rc::new(RefCell::new(None::<Closure<dyn FnMut()>>)));
+
+    // Visibility changes arrive on a DOM listener and are handled here, inside the runtime
+    let visibility_lock = wake_lock.clone();
+    let listener_slot = listener.clone();
+    use_future(move || {
+        let wake_lock = visibility_lock.clone();
+        let listener_slot = listener_slot.clone();
+        async move {
+            let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<bool>();
+            let closure = Closure::<dyn FnMut()>::new(move || {
+                let _ = tx.send(is_page_hidden());
+            });
+            if let Some(document) = web_sys::window().and_then(|w| w.document()) {
+                let _ = document.add_event_listener_with_callback(
+                    "visibilitychange",
+                    closure.as_ref().unchecked_ref(),
+                );
+            }
+            *listener_slot.borrow_mut() = Some(closure);
+
+            acquire_wake_lock(&wake_lock, dispatch).await;
+            while let Some(hidden) = rx.recv().await {
+                dispatch.call(MigrationAction::SetTabHidden(hidden));
+                if hidden {
+                    console_info!("[TabActivity] Tab hidden; stall detection paused");
+                    // The browser releases the lock on hide; drop our stale sentinel
+                    wake_lock.borrow_mut().take();
+                } else {
+                    console_info!("[TabActivity] Tab visible again; resuming");
+                    acquire_wake_lock(&wake_lock, dispatch).await;
+                }
+            }
+        }
+    });
+
+    // Timers in hidden tabs fire late when the browser throttles them
+    use_future(move || async move {
+        let mut meter = DriftMeter::new(DRIFT_TICK_MS);
+        let mut warned = false;
+        loop {
+            sleep_ms(DRIFT_TICK_MS.into()).await;
+            if !is_page_hidden() {
+                meter.reset();
+                continue;
+            }
+            if let Some(drift_ms) = meter.tick(now_ms() as u64) {
+                if !warned {
+                    warned = true;
+                    let message = format!(
+                        "Browser is throttling this background tab (timer {} late); keep it in front to avoid slowdowns",
+                        format_duration_ms(drift_ms)
+                    );
+                    console_warn!("[TabActivity] {}", message);
+                    dispatch.call(MigrationAction::AddConsoleMessage(ConsoleEntry::new(
+                        ConsoleLevel::Warn,
+                        module_path!(),
+                        message,
+                    )));
+                }
+                dispatch.call(MigrationAction::RecordTimerDrift(drift_ms));
+            }
+        }
+    });
+
+    use_drop(move || {
+        if let Some(closure) = listener.borrow_mut().take() {
+            if let Some(document) = web_sys::window().and_then(|w| w.document()) {
+                let _ = document.remove_event_listener_with_callback(
+                    "visibilitychange",
+                    closure.as_ref().unchecked_ref(),
+                );
+            }
+        }
+        if let Some(sentinel) = wake_lock.borrow_mut().take() {
+            release_wake_lock(&sentinel);
+            console_info!("[TabActivity] Wake lock released");
+        }
+    });
+
+    let activity = state().tab_activity;
+
+    rsx! {
+        if let Some(drift_ms) = activity.throttled_drift_ms {
+            div {
+                class: "tab-activity-notice",
+                div {
+                    class: "tab-activity-title",
+                    "⚠️ Your browser slowed this tab down while it was in the background"
+                }
+                p {
+                    "Timers ran up to {format_duration_ms(drift_ms)} late. Keep this tab in front and the screen on until the migration finishes; it carries on where it left off."
+                }
+            }
+        } else if activity.wake_lock == WakeLockStatus::Unavailable {
+            div {
+                class: "tab-activity-notice",
+                p {
+                    "This browser can't keep the screen awake. Keep this tab in front and the screen on until the migration finishes."
+                }
+            }
+        } else if activity.wake_lock == WakeLockStatus::Held {
+            div {
+                class: "tab-activity-status",
+                "🔆 Screen kept awake while migrating"
+            }
+        }
+    }
+}
+
+async fn acquire_wake_lock(
+    slot: &Rc<RefCell<Option<JsValue>>>,
+    dispatch: EventHandler<MigrationAction>,
+) {
+    match request_wake_lock().await {
+        Ok(sentinel) => {
+            if let Some(previous) = slot.borrow_mut().replace(sentinel) {
+                release_wake_lock(&previous);
+            }
+            console_info!("[TabActivity] Screen wake lock acquired");
+            dispatch.call(MigrationAction::SetWakeLockStatus(WakeLockStatus::Held));
+        }
+        Err(e) => {
+            console_warn!("[TabActivity] {}", e);
+            dispatch.call(MigrationAction::SetWakeLockStatus(
+                WakeLockStatus::Unavailable,
+            ));
+        }
+    }
+}
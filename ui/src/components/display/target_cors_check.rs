@@ -0,0 +1,84 @@
+use dioxus::prelude::*;
+
+use crate::components::display::LoadingIndicator;
+use crate::services::client::{mixed_content_problem, run_cors_preflight, CorsOutcome, CorsReport};
+use crate::utils::platform::current_page_url;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct TargetCorsCheckProps {
+    /// New PDS the migration will send everything to
+    pub pds_url: String,
+}
+
+/// Whether the browser may talk to the new PDS at all: mixed content is
+/// flagged straight away, CORS on each endpoint the migration needs on request
+#[component]
+pub fn TargetCorsCheck(props: TargetCorsCheckProps) -> Element {
+    let mut checking = use_signal(|| false);
+    let mut report = use_signal(|| None::<CorsReport>);
+    let pds_url = props.pds_url.clone();
+
+    let page_url = current_page_url();
+    let mixed_content = page_url
+        .as_deref()
+        .and_then(|page| mixed_content_problem(page, &pds_url));
+    // A report for a URL the user has since changed says nothing
+    let current = report().filter(|r| r.target == pds_url.trim().trim_end_matches('/'));
+
+    rsx! {
+        div {
+            class: "blob-manifest target-cors-check",
+            if let Some(problem) = mixed_content {
+                p {
+                    class: "validation-result error",
+                    role: "alert",
+                    "✗ {problem}"
+                }
+            } else if checking() {
+                LoadingIndicator { message: "Checking browser access to the PDS...".to_string() }
+            } else if let Some(found) = current {
+                if found.is_clear() {
+                    p {
+                        class: "validation-result success",
+                        "✓ The PDS allows every request this migration sends from the browser"
+                    }
+                } else {
+                    div {
+                        class: "validation-result error",
+                        role: "alert",
+                        p { "✗ The browser would be blocked. Fix these in the PDS or its reverse proxy before starting:" }
+                        ul {
+                            for problem in found.problems() {
+                                li { key: "{problem}", "{problem}" }
+                            }
+                        }
+                        for probe in found.probes.iter().filter(|p| p.outcome == CorsOutcome::Blocked) {
+                            if let Some(detail) = &probe.detail {
+                                p {
+                                    key: "{probe.endpoint}",
+                                    class: "blob-manifest-note",
+                                    "{probe.endpoint}: {detail}"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            button {
+                class: "validate-button",
+                title: "Send a harmless request of each kind the migration uses and report any the browser refuses",
+                disabled: checking() || pds_url.trim().is_empty(),
+                onclick: move |_| {
+                    let target = props.pds_url.clone();
+                    let page = page_url.clone();
+                    checking.set(true);
+                    spawn(async move {
+                        report.set(Some(run_cors_preflight(&target, page.as_deref()).await));
+                        checking.set(false);
+                    });
+                },
+                "Check browser access (CORS)"
+            }
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use dioxus::prelude::*;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct UnrecoverableBlobsReportProps {
+    pub cids: Vec<String>,
+}
+
+/// Images and videos the old PDS had already lost, so they can be told apart
+/// from anything the migration itself failed to copy
+#[component]
+pub fn UnrecoverableBlobsReport(props: UnrecoverableBlobsReportProps) -> Element {
+    if props.cids.is_empty() {
+        return rsx! {};
+    }
+    let count = props.cids.len();
+
+    rsx! {
+        div {
+            class: "blob-enumeration-summary",
+            h4 { class: "blob-progress-title", "Media Missing From the Old PDS" }
+            div {
+                class: "blob-stats",
+                div {
+                    class: "blob-stat-item",
+                    span { class: "stat-label", "Unrecoverable:" }
+                    span { class: "stat-value", "{count}" }
+                }
+            }
+            p {
+                class: "blob-enumeration-note",
+                "Your records still point at these blobs, but the old PDS answered \"not found\" for them before the migration started copying. They were skipped; the posts that use them will show missing images or videos on either server."
+            }
+            ul {
+                class: "preferences-attention",
+                for cid in props.cids {
+                    li {
+                        key: "{cid}",
+                        code { "{cid}" }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use base64::Engine;
+use dioxus::prelude::*;
+
+use crate::console_warn;
+use crate::migration::upload_log::{flush_upload_log, read_upload_log};
+
+#[derive(Props, PartialEq, Clone)]
+pub struct UploadLogDownloadProps {
+    /// Account whose upload log to offer
+    pub did: String,
+}
+
+/// Download link for the per-blob upload log, including runs that crashed
+#[component]
+pub fn UploadLogDownload(props: UploadLogDownloadProps) -> Element {
+    let did = props.did.clone();
+    let log = use_resource(move || {
+        let did = did.clone();
+        async move {
+            flush_upload_log().await;
+            read_upload_log(&did).await
+        }
+    });
+
+    let contents = match &*log.read() {
+        Some(Ok(contents)) if !contents.is_empty() => contents.clone(),
+        Some(Err(e)) => {
+            console_warn!("[UploadLog] Could not read the upload log: {}", e);
+            return rsx! {};
+        }
+        _ => return rsx! {},
+    };
+    let lines = contents.lines().count();
+    let href = format!(
+        "data:application/x-ndjson;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(&contents)
+    );
+    let file_name = format!("uploads-{}.ndjson", props.did.replace(':', "_"));
+
+    rsx! {
+        div {
+            class: "blob-manifest",
+            a {
+                class: "banner-link",
+                href: "{href}",
+                download: "{file_name}",
+                "⬇️ Download blob upload log ({lines} uploads)"
+            }
+            span {
+                class: "blob-manifest-note",
+                " One JSON line per uploaded blob with its size, upload time and attempts, written as the migration ran."
+            }
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use dioxus::prelude::*;
+
+use crate::migration::advanced_options::{highest_selected_risk, AdvancedOption, RiskLevel};
+
+/// A risky choice waiting for the user to confirm it
+#[derive(Clone, PartialEq)]
+struct PendingChoice {
+    id: &'static str,
+    value: String,
+    label: String,
+    warning: &'static str,
+}
+
+#[derive(Props, PartialEq, Clone)]
+pub struct AdvancedSectionProps {
+    pub title: String,
+    pub options: Vec<AdvancedOption>,
+    pub disabled: bool,
+    /// Called with the setting id and the chosen value once it is confirmed
+    pub on_select: EventHandler<(&'static str, String)>,
+}
+
+/// Collapsible group of settings with a risk badge per choice; risky choices
+/// ask for confirmation before they are applied
+#[component]
+pub fn AdvancedSection(props: AdvancedSectionProps) -> Element {
+    let changed = props
+        .options
+        .iter()
+        .filter(|option| option.is_changed())
+        .count();
+    // Open from the start when something was already changed
+    let mut open = use_signal(move || changed > 0);
+    let mut pending = use_signal(|| None::<PendingChoice>);
+    let highest = highest_selected_risk(&props.options);
+    let on_select = props.on_select;
+
+    rsx! {
+        div {
+            class: "advanced-section",
+            button {
+                class: "advanced-toggle",
+                r#type: "button",
+                onclick: move |_| open.toggle(),
+                if open() { "▾ " } else { "▸ " }
+                "{props.title}"
+                if changed > 0 {
+                    span {
+                        class: "advanced-changed",
+                        " ({changed} changed)"
+                    }
+                }
+                if highest != RiskLevel::Safe {
+                    span {
+                        class: highest.css_class(),
+                        "{highest.badge()}"
+                    }
+                }
+            }
+
+            if open() {
+                for option in props.options.clone() {
+                    div {
+                        key: "{option.id}",
+                        class: "input-section",
+                        label {
+                            class: "input-label",
+                            "{option.label}:"
+                            if let Some(choice) = option.selected_choice() {
+                                if choice.risk != RiskLevel::Safe {
+                                    span {
+                                        class: choice.risk.css_class(),
+                                        "{choice.risk.badge()}"
+                                    }
+                                }
+                            }
+                        }
+                        select {
+                            class: "input-field",
+                            disabled: props.disabled,
+                            onchange: {
+                                let option = option.clone();
+                                move |evt: Event<FormData>| {
+                                    let value = evt.value();
+                                    let Some(choice) = option.choices.iter().find(|choice| choice.value == value) else {
+                                        return;
+                                    };
+                                    match choice.warning.filter(|_| choice.needs_confirmation()) {
+                                        Some(warning) => pending.set(Some(PendingChoice {
+                                            id: option.id,
+                                            value,
+                                            label: choice.label.clone(),
+                                            warning,
+                                        })),
+                                        None => on_select.call((option.id, value)),
+                                    }
+                                }
+                            },
+                            for choice in option.choices.iter() {
+                                option {
+                                    value: "{choice.value}",
+                                    selected: choice.value == option.selected,
+                                    if choice.risk == RiskLevel::Safe {
+                                        "{choice.label}"
+                                    } else {
+                                        "{choice.label} · {choice.risk.badge()}"
+                                    }
+                                }
+                            }
+                        }
+                        if let Some(help) = option.help.clone() {
+                            div {
+                                class: "enumeration-method-explanation",
+                                "{help}"
+                            }
+                        }
+                        if let Some(warning) = option.selected_choice().and_then(|choice| choice.warning) {
+                            div {
+                                class: "risk-note",
+                                "{warning}"
+                            }
+                        }
+                    }
+                }
+            }
+
+            if let Some(choice) = pending() {
+                div {
+                    class: "risk-confirm",
+                    p {
+                        strong { "{choice.label}: " }
+                        "{choice.warning}"
+                    }
+                    button {
+                        class: "migrate-button",
+                        r#type: "button",
+                        onclick: move |_| {
+                            if let Some(choice) = pending.take() {
+                                on_select.call((choice.id, choice.value));
+                            }
+                        },
+                        "Use it anyway"
+                    }
+                    button {
+                        class: "validate-button",
+                        r#type: "button",
+                        onclick: move |_| pending.set(None),
+                        "Keep the current setting"
+                    }
+                }
+            }
+        }
+    }
+}
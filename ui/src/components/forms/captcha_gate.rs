@@ -174,21 +174,36 @@ pub fn CaptchaGate(props: CaptchaGateProps) -> Element {
 
 /// Generate a random hex state string for CSRF protection
 fn generate_random_state() -> String {
-    let window = web_sys::window().unwrap();
-    let crypto = window.crypto().unwrap();
-    let mut buf = [0u8; 32];
-    let _ = crypto.get_random_values_with_u8_array(&mut buf);
-    buf.iter().map(|b| format!("{:02x}", b)).collect()
+    crate::utils::platform::random_hex(32)
 }
 
 /// Get the current window origin
 fn get_origin() -> String {
+    if !crate::utils::platform::has_browser_window() {
+        return "https://tektite.cc".to_string();
+    }
+
     web_sys::window()
         .and_then(|w| w.location().origin().ok())
         .unwrap_or_else(|| "https://tektite.cc".to_string())
 }
 
 /// URL-encode a string
+#[cfg(target_arch = "wasm32")]
 fn url_encode(s: &str) -> String {
     js_sys::encode_uri_component(s).into()
 }
+
+/// URL-encode a string (matches `encodeURIComponent` for server-side rendering)
+#[cfg(not(target_arch = "wasm32"))]
+fn url_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' => (b as char).to_string(),
+            b'-' | b'_' | b'.' | b'!' | b'~' | b'*' | b'\'' | b'(' | b')' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
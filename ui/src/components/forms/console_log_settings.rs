@@ -0,0 +1,100 @@
+use dioxus::prelude::*;
+
+use crate::migration::storage::LocalStorageManager;
+use crate::migration::{MigrationAction, MigrationState};
+use crate::utils::log_filter::{set_log_filter, LogFilter, LEVEL_NAMES};
+
+#[derive(Props, PartialEq, Clone)]
+pub struct ConsoleLogSettingsProps {
+    pub state: Signal<MigrationState>,
+    pub dispatch: EventHandler<MigrationAction>,
+}
+
+/// Apply a filter right away and keep it for later visits
+fn save_log_filter(filter: LogFilter, dispatch: EventHandler<MigrationAction>) {
+    let spec = filter.to_string();
+    if let Err(e) = LocalStorageManager::store_log_filter(&spec) {
+        crate::console_warn!("[UI] Could not save the console log level: {}", e);
+    }
+    set_log_filter(filter);
+    dispatch.call(MigrationAction::SetLogFilter(spec));
+}
+
+/// How much goes to the browser console, changeable while a migration runs:
+/// a default level plus per-module overrides
+#[component]
+pub fn ConsoleLogSettings(props: ConsoleLogSettingsProps) -> Element {
+    let state = props.state;
+    let dispatch = props.dispatch;
+    let mut overrides_error = use_signal(|| None::<String>);
+
+    let filter = LogFilter::parse(&state().log_filter).unwrap_or_default();
+    let default_level = filter.default.label().to_ascii_lowercase();
+    let overrides = filter
+        .modules
+        .iter()
+        .map(|(module, level)| format!("{}={}", module, level.label().to_ascii_lowercase()))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    rsx! {
+        details {
+            class: "console-log-settings",
+            summary { "Console logging" }
+            label {
+                class: "progress-mode-toggle",
+                "Level "
+                select {
+                    onchange: move |evt| {
+                        let mut filter = LogFilter::parse(&state().log_filter).unwrap_or_default();
+                        if let Ok(chosen) = LogFilter::parse(&evt.value()) {
+                            filter.default = chosen.default;
+                            save_log_filter(filter, dispatch);
+                        }
+                    },
+                    for name in LEVEL_NAMES {
+                        option {
+                            key: "{name}",
+                            value: name,
+                            selected: name == default_level,
+                            "{name}"
+                        }
+                    }
+                }
+            }
+            label {
+                class: "progress-mode-toggle",
+                "Per-module levels "
+                input {
+                    r#type: "text",
+                    class: "input-field",
+                    placeholder: "services::streaming=debug",
+                    value: "{overrides}",
+                    onchange: move |evt| {
+                        let value = evt.value();
+                        if value.split(',').any(|entry| !entry.trim().is_empty() && !entry.contains('=')) {
+                            overrides_error.set(Some("Write each override as module=level".to_string()));
+                            return;
+                        }
+                        match LogFilter::parse(&value) {
+                            Ok(parsed) => {
+                                overrides_error.set(None);
+                                let mut filter = LogFilter::parse(&state().log_filter).unwrap_or_default();
+                                filter.modules = parsed.modules;
+                                save_log_filter(filter, dispatch);
+                            }
+                            Err(error) => overrides_error.set(Some(error)),
+                        }
+                    },
+                }
+            }
+            if let Some(error) = overrides_error() {
+                p { class: "validation-result error", "✗ {error}" }
+            }
+            p {
+                class: "blob-manifest-note",
+                "Debug messages are limited per line of code, so a busy transfer can't flood the console."
+            }
+        }
+    }
+}
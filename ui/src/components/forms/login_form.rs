@@ -1,6 +1,6 @@
 // Legacy server-side imports removed - using client-side now
 use dioxus::prelude::*;
-use gloo_storage::{LocalStorage, Storage};
+use crate::utils::platform::{storage_set, StorageArea};
 use serde_json;
 
 use crate::components::{
@@ -124,7 +124,7 @@ pub fn LoginFormComponent(props: LoginFormComponentProps) -> Element {
                                         // Store session data with key "old_pds_session"
                                         if let Some(session) = &response.session {
                                             if let Ok(session_json) = serde_json::to_string(session) {
-                                                let _ = LocalStorage::set("old_pds_session", session_json);
+                                                let _ = storage_set(StorageArea::Local, "old_pds_session", session_json);
                                                 dispatch.call(MigrationAction::SetSessionStored(true));
                                             }
                                         }
@@ -8,10 +8,12 @@ use dioxus::prelude::*;
 use crate::{console_error, console_info, console_log, console_warn};
 
 use crate::components::{
-    display::ProviderDisplay,
+    display::{DomainSafetyNote, PdsEndpointCheck, ProviderDisplay},
     inputs::{InputType, ValidatedInput},
 };
 use crate::migration::{storage::LocalStorageManager, *};
+use crate::utils::handle::{is_valid_handle_syntax, normalize_identifier};
+use crate::utils::platform::sleep_ms;
 
 #[derive(Props, PartialEq, Clone)]
 pub struct ClientLoginFormComponentProps {
@@ -22,13 +24,9 @@ pub struct ClientLoginFormComponentProps {
 #[cfg(feature = "web")]
 /// Check if a handle is potentially valid and worth resolving (prevents unnecessary network calls)
 fn should_resolve_handle(handle: &str) -> bool {
-    // Basic validation to prevent unnecessary network calls
-    handle.len() > 6 &&  // Minimum viable handle length (e.g., "a.b.co")
-    handle.contains('.') &&
-    handle.chars().last().is_some_and(|c| c.is_alphabetic()) &&
-    !handle.ends_with('.') &&  // Don't resolve incomplete handles like "torrho."
-    handle.split('.').count() >= 2 &&  // Must have at least domain.tld
-    !handle.contains(' ') // No spaces allowed
+    // Incomplete input like "torrho." or "alice" fails the syntax check;
+    // IDN and punycode domains with any well-formed TLD pass
+    is_valid_handle_syntax(handle)
 }
 
 #[cfg(feature = "web")]
@@ -66,10 +64,13 @@ pub fn ClientLoginFormComponent(props: ClientLoginFormComponentProps) -> Element
                     on_change: move |data: String| {
                         dispatch.call(MigrationAction::SetHandle(data.clone()));
 
-                        // Clear provider immediately when input changes
+                        // Clear provider and PDS endpoint immediately when input changes
                         dispatch.call(MigrationAction::SetProvider(ClientPdsProvider::None));
+                        dispatch.call(MigrationAction::SetResolvedPds(None));
+                        dispatch.call(MigrationAction::SetPdsOverride(None));
 
-                        let trimmed_data = data.trim();
+                        let normalized = normalize_identifier(&data);
+                        let trimmed_data = normalized.as_str();
 
                         // Handle DID inputs differently (no resolution needed)
                         if trimmed_data.starts_with("did:") {
@@ -105,11 +106,7 @@ pub fn ClientLoginFormComponent(props: ClientLoginFormComponentProps) -> Element
 
                         spawn(async move {
                             // Add a small delay to debounce rapid keystrokes
-                            #[cfg(target_arch = "wasm32")]
-                            {
-                                use gloo_timers::future::TimeoutFuture;
-                                TimeoutFuture::new(300).await; // 300ms debounce
-                            }
+                            sleep_ms(300).await;
 
                             // Check if this is still the most recent request
                             let current_counter = request_counter();
@@ -122,11 +119,18 @@ pub fn ClientLoginFormComponent(props: ClientLoginFormComponentProps) -> Element
 
                             let migration_client = MigrationClient::new();
                             let provider = migration_client.determine_provider(&data_for_async).await;
+                            let resolved_pds = migration_client
+                                .pds_client
+                                .resolve_identity(&data_for_async)
+                                .await
+                                .ok()
+                                .map(|(_, pds_url)| pds_url);
 
                             // Final check - only update if this is still the most recent request
                             if current_request_id == request_counter() {
-                                console_log!("Provider resolution completed for '{}': {:?} (request {})", data_for_async, provider, current_request_id);
+                                console_log!("Provider resolution completed for '{}': {:?} at {:?} (request {})", data_for_async, provider, resolved_pds, current_request_id);
                                 dispatch.call(MigrationAction::SetProvider(provider));
+                                dispatch.call(MigrationAction::SetResolvedPds(resolved_pds));
                                 dispatch.call(MigrationAction::SetLoading(false));
                             } else {
                                 console_log!("Discarding outdated provider resolution result for request {}", current_request_id);
@@ -134,6 +138,7 @@ pub fn ClientLoginFormComponent(props: ClientLoginFormComponentProps) -> Element
                         });
                     }
                 }
+                DomainSafetyNote { domain: state().form1.handle }
             }
 
             // Provider Display
@@ -146,6 +151,18 @@ pub fn ClientLoginFormComponent(props: ClientLoginFormComponentProps) -> Element
                 }
             }
 
+            // Where the login and export will go, before the password is sent
+            if should_resolve_handle(&normalize_identifier(&state().form1.handle)) && !state().form1.is_loading {
+                PdsEndpointCheck {
+                    resolved: state().form1.resolved_pds,
+                    pds_override: state().form1.pds_override,
+                    disabled: state().session_stored(),
+                    on_override: move |pds: Option<String>| {
+                        dispatch.call(MigrationAction::SetPdsOverride(pds));
+                    }
+                }
+            }
+
             // Password Input Section
             div {
                 class: "input-section",
@@ -174,8 +191,10 @@ pub fn ClientLoginFormComponent(props: ClientLoginFormComponentProps) -> Element
                     disabled: state().form1.is_authenticating || state().form1.handle.trim().is_empty() || state().form1.password.trim().is_empty() || state().session_stored(),
                     onclick: move |_| {
                         let current_state = state();
-                        let handle_value = current_state.form1.handle.trim().to_string();
+                        // Log in with the canonical punycode handle or DID
+                        let handle_value = normalize_identifier(&current_state.form1.handle);
                         let password_value = current_state.form1.password.trim().to_string();
+                        let pds_override = current_state.form1.pds_override.clone();
 
                         // Store the original handle for later use
                         dispatch.call(MigrationAction::SetOriginalHandle(handle_value.clone()));
@@ -184,7 +203,7 @@ pub fn ClientLoginFormComponent(props: ClientLoginFormComponentProps) -> Element
 
                         spawn(async move {
                             let migration_client = MigrationClient::new();
-                            match migration_client.pds_client.login(&handle_value, &password_value).await {
+                            match migration_client.pds_client.login_at(&handle_value, &password_value, pds_override.as_deref()).await {
                                 Ok(response) => {
                                     if response.success {
                                         if let Some(ref client_session) = response.session {
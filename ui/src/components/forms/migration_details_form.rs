@@ -1,8 +1,14 @@
 use dioxus::prelude::*;
 
 use crate::components::{
-    display::BlobProgressDisplay,
-    forms::DomainSelector,
+    display::{
+        BlobEnumerationSummaryView, BlobManifestDownload, BodyLimitFixView, ContentCopySummary,
+        ContinuousSyncPanel, DomainSafetyNote, LiveProgress, MigrationTimeline, PasswordSaveOffer,
+        PreferencesBackupDownload, PreferencesRoundTripSummary, RepoImportFailureView,
+        RepoImportProgress, SourceEndpointNotice, StallNotice, StorageUsageMeter,
+        UnrecoverableBlobsReport, UploadLogDownload,
+    },
+    forms::{AdvancedSection, ConsoleLogSettings, DomainSelector},
     inputs::{
         EmailValidationFeedback, HandleValidationFeedback, InputType, PasswordValidationFeedback,
         ValidatedInput,
@@ -10,11 +16,19 @@ use crate::components::{
 };
 
 #[cfg(feature = "web")]
-use crate::components::forms::CaptchaGate;
+use crate::components::display::TabActivityNotice;
+#[cfg(feature = "web")]
+use crate::components::forms::{CaptchaGate, PhoneVerificationForm};
 use crate::migration::{
+    advanced_options::{advanced_choice_action, migration_advanced_options},
     form_validation::{get_form3_validation_message, validate_form3_complete},
+    storage::LocalStorageManager,
     *,
 };
+use crate::services::streaming::MinimalFlowLimits;
+use crate::utils::byte_format::{set_byte_units, ByteUnits};
+use crate::utils::handle::is_valid_handle_syntax;
+use crate::utils::lookalike::url_host;
 use crate::utils::validation::{
     email_validation_class, email_validation_style, password_validation_class,
     password_validation_style, validation_class, validation_style,
@@ -27,6 +41,7 @@ use crate::services::client::WebIdentityResolver;
 // Import the appropriate migration logic based on feature flags
 #[cfg(feature = "web")]
 use crate::migration::logic::execute_migration_client_side;
+use crate::migration::steps::blob_repair::execute_cache_repair_client_side;
 
 #[cfg(not(feature = "web"))]
 use crate::migration::logic::execute_migration;
@@ -74,8 +89,8 @@ pub fn MigrationDetailsForm(props: MigrationDetailsFormProps) -> Element {
 
         let should_reset = !is_migrating_val && migration_completed_val;
 
-        console_debug!("[HOOK] use_effect triggered: is_migrating={}, migration_completed={}, will_reset_blob_progress={} - timestamp: {}", 
-            is_migrating_val, migration_completed_val, should_reset, js_sys::Date::now());
+        console_debug!("[HOOK] use_effect triggered: is_migrating={}, migration_completed={}, will_reset_blob_progress={}", 
+            is_migrating_val, migration_completed_val, should_reset);
 
         // When migration completes, ensure blob progress is cleared to prevent UI freeze
         if should_reset {
@@ -84,14 +99,24 @@ pub fn MigrationDetailsForm(props: MigrationDetailsFormProps) -> Element {
         }
     });
 
-    // Track whether we're showing the captcha gate
+    // Track whether we're showing the captcha gate, and whether the user chose
+    // to verify by text message instead
     let mut show_captcha = use_signal(|| false);
+    let use_phone = use_signal(|| false);
+    // A rejected createAccount brings verification back without another click
+    let verification_pending = move || {
+        let current_state = state();
+        (show_captcha() || current_state.form3.verification_rejected)
+            && current_state.form3.verification_code.is_none()
+            && !current_state.is_migrating
+    };
 
     // Extract handle validation logic into a reusable function
     let validate_handle_availability =
         move |full_handle: String, dispatch: EventHandler<MigrationAction>| {
-            // Validate handle availability if handle is not empty
-            if !full_handle.trim().is_empty() {
+            // Validate handle availability once the handle is well formed; a
+            // malformed one never resolves and would otherwise look available
+            if is_valid_handle_syntax(&full_handle) {
                 dispatch.call(MigrationAction::SetHandleValidation(
                     HandleValidation::Checking,
                 ));
@@ -150,6 +175,9 @@ pub fn MigrationDetailsForm(props: MigrationDetailsFormProps) -> Element {
                     class: "display-value",
                     "{state().form2.pds_url}"
                 }
+                if let Some(host) = url_host(&state().form2.pds_url) {
+                    DomainSafetyNote { domain: host }
+                }
             }
 
             div {
@@ -205,6 +233,8 @@ pub fn MigrationDetailsForm(props: MigrationDetailsFormProps) -> Element {
                     validation: state().validations.handle,
                     is_checking: state().form3.is_checking_handle
                 }
+
+                DomainSafetyNote { domain: state().form3.handle }
             }
 
             div {
@@ -274,28 +304,69 @@ pub fn MigrationDetailsForm(props: MigrationDetailsFormProps) -> Element {
                 }
             }
 
-            div {
-                class: "input-section",
-                label {
-                    class: "input-label",
-                    "Invite Code:"
+            if state().signup_gating().shows_invite_code() {
+                div {
+                    class: "input-section",
+                    label {
+                        class: "input-label",
+                        "Invite Code:"
+                    }
+                    ValidatedInput {
+                        value: state().form3.invite_code,
+                        placeholder: if state().signup_gating().requires_invite_code() {
+                            "Enter invite code".to_string()
+                        } else {
+                            "Enter invite code (if required)".to_string()
+                        },
+                        input_type: InputType::Text,
+                        input_class: "input-field".to_string(),
+                        input_style: "".to_string(),
+                        disabled: state().is_migrating || state().current_step == FormStep::PlcVerification,
+                        on_change: move |code: String| {
+                            dispatch.call(MigrationAction::SetInviteCode(code));
+                        }
+                    }
                 }
-                ValidatedInput {
-                    value: state().form3.invite_code,
-                    placeholder: "Enter invite code (if required)".to_string(),
-                    input_type: InputType::Text,
-                    input_class: "input-field".to_string(),
-                    input_style: "".to_string(),
-                    disabled: state().is_migrating || state().current_step == FormStep::PlcVerification,
-                    on_change: move |code: String| {
-                        dispatch.call(MigrationAction::SetInviteCode(code));
+            } else if state().signup_gating().uses_verification_gate() {
+                div {
+                    class: "input-section signup-gating-note",
+                    "No invite code needed: this PDS will ask you to verify the new account before it is created."
+                }
+            }
+
+            if state().minimal_flow_required() {
+                div {
+                    class: "input-section minimal-flow-note",
+                    "This browser has no persistent storage (OPFS or IndexedDB), so only small accounts can be migrated here: {MinimalFlowLimits::default().summary()}. Larger accounts will be refused before anything is created on the new PDS."
+                }
+            }
+
+            AdvancedSection {
+                title: "Advanced options".to_string(),
+                options: migration_advanced_options(&state()),
+                disabled: state().is_migrating || state().current_step == FormStep::PlcVerification,
+                on_select: move |(id, value): (&'static str, String)| {
+                    if let Some(action) = advanced_choice_action(id, &value) {
+                        dispatch.call(action);
+                    }
+                },
+            }
+
+            if state().form3.plan_variant.uses_existing_account() {
+                div {
+                    class: "migration-info",
+                    p {
+                        "Enter the handle and password of the account you already created on the new PDS. Your posts, media, follows and other records are copied into it, with mentions of your old DID rewritten to the new one."
+                    }
+                    p {
+                        "This is not a migration: your identity does not move. Followers keep following the old account, replies and likes from others stay attached to the old posts, and the old account keeps working until you delete it."
                     }
                 }
             }
 
-            // Show captcha gate when PDS requires verification and we don't have a code yet
-            if show_captcha() && state().form3.verification_code.is_none() {
-                {render_captcha_gate(state, dispatch, show_captcha)}
+            // Show signup verification when PDS requires it and we don't have a code yet
+            if verification_pending() {
+                {render_signup_verification(state, dispatch, show_captcha, use_phone)}
             }
 
             div {
@@ -304,13 +375,16 @@ pub fn MigrationDetailsForm(props: MigrationDetailsFormProps) -> Element {
                     class: "migrate-button",
                     disabled: {
                         let current_state = state();
-                        current_state.is_migrating || !validate_form3_complete(&current_state) || show_captcha()
+                        current_state.is_migrating || !validate_form3_complete(&current_state) || verification_pending()
                     },
                     onclick: move |_| {
                         let current_state = state();
 
                         // If captcha is required and we don't have a verification code, show captcha first
-                        if current_state.captcha_required() && current_state.form3.verification_code.is_none() {
+                        if current_state.captcha_required()
+                            && current_state.form3.verification_code.is_none()
+                            && !current_state.form3.plan_variant.uses_existing_account()
+                        {
                             console_info!("[Migration] Captcha required by target PDS - showing verification gate");
                             show_captcha.set(true);
                             return;
@@ -329,7 +403,7 @@ pub fn MigrationDetailsForm(props: MigrationDetailsFormProps) -> Element {
                     },
                     if state().is_migrating {
                         "Migrating..."
-                    } else if show_captcha() {
+                    } else if verification_pending() {
                         "Complete verification above..."
                     } else {
                         "Migrate"
@@ -342,36 +416,149 @@ pub fn MigrationDetailsForm(props: MigrationDetailsFormProps) -> Element {
                 if state().is_migrating {
                     div {
                         class: "migration-progress",
-                        "{state().migration_step}"
-
-                        // Show detailed blob progress using centralized logic
-                        {
-                            let current_state = state();
-                            let should_show = current_state.should_show_blob_progress();
-                            crate::console_info!("[UI] migration_details_form evaluating BlobProgressDisplay: should_show={}, is_migrating={}, migration_completed={}",
-                                should_show, current_state.is_migrating, current_state.migration_completed);
-
-                            if should_show {
-                                let blob_progress = current_state.unified_blob_progress();
-                                let migration_step = current_state.migration_step.clone();
-                                crate::console_info!("[UI] Rendering BlobProgressDisplay with step='{}'", migration_step);
-                                rsx! {
-                                    BlobProgressDisplay {
-                                        blob_progress,
-                                        migration_step,
+                        label {
+                            class: "progress-mode-toggle",
+                            input {
+                                r#type: "checkbox",
+                                checked: state().text_only_progress,
+                                onchange: move |evt| {
+                                    let enabled = evt.checked();
+                                    if let Err(e) = LocalStorageManager::store_text_only_progress(enabled) {
+                                        crate::console_warn!("[UI] Could not save the progress display choice: {}", e);
+                                    }
+                                    dispatch.call(MigrationAction::SetTextOnlyProgress(enabled));
+                                },
+                            }
+                            " Text-only progress"
+                        }
+                        label {
+                            class: "progress-mode-toggle",
+                            input {
+                                r#type: "checkbox",
+                                checked: state().byte_units == ByteUnits::Decimal,
+                                onchange: move |evt| {
+                                    let units = if evt.checked() { ByteUnits::Decimal } else { ByteUnits::Binary };
+                                    if let Err(e) = LocalStorageManager::store_byte_units(units) {
+                                        crate::console_warn!("[UI] Could not save the size unit choice: {}", e);
                                     }
-                                }
-                            } else {
-                                crate::console_info!("[UI] NOT rendering BlobProgressDisplay - conditions not met");
-                                rsx! {}
+                                    set_byte_units(units);
+                                    dispatch.call(MigrationAction::SetByteUnits(units));
+                                },
                             }
+                            " Decimal sizes (MB instead of MiB)"
+                        }
+
+                        ConsoleLogSettings { state, dispatch }
+
+                        MigrationTimeline {
+                            entries: state().timeline(crate::utils::platform::now_ms() as u64),
+                            detail: state().migration_step,
+                        }
+
+                        StallNotice { state, dispatch }
+
+                        {render_tab_activity_notice(state, dispatch)}
+
+                        PasswordSaveOffer { state, dispatch }
+
+                        if let Some(status) = state().repo_import_status.clone() {
+                            if !state().repo_progress.import_complete {
+                                RepoImportProgress { status }
+                            }
+                        }
+
+                        if let Some(change) = state().source_endpoint_change.clone() {
+                            SourceEndpointNotice { change }
+                        }
+
+                        // Byte counts come from the progress store; the form only re-renders
+                        // when the state itself changes
+                        LiveProgress {
+                            console_messages: state().console_messages.clone(),
+                            text_only: state().text_only_progress,
+                        }
+
+                        if let Some(summary) = state().blob_enumeration.clone() {
+                            BlobEnumerationSummaryView { summary }
+                        }
+
+                        if let Some(status) = state().continuous_sync.clone() {
+                            ContinuousSyncPanel { status }
+                        }
+
+                        if let Some(usage) = state().storage_usage.clone() {
+                            StorageUsageMeter { usage }
+                        }
+
+                        if let Some(backup) = state().preferences_backup.clone() {
+                            PreferencesBackupDownload { backup }
+                        }
+
+                        if let Some(report) = state().preferences_round_trip.clone() {
+                            PreferencesRoundTripSummary { report }
                         }
                     }
                 } else if let Some(error) = &state().migration_error {
+                    MigrationTimeline {
+                        entries: state().timeline(crate::utils::platform::now_ms() as u64),
+                    }
                     div {
                         class: "migration-error",
                         "Error: {error}"
                     }
+                    if let Some(diagnosis) = state().repo_import_failure.clone() {
+                        RepoImportFailureView { diagnosis }
+                    }
+                    if let Some(report) = state().body_limit_report.clone() {
+                        BodyLimitFixView { report }
+                    }
+                    if let Some(change) = state().source_endpoint_change.clone() {
+                        SourceEndpointNotice { change }
+                    }
+                    if state().form3.existing_did_rejected && !state().form3.plan_variant.uses_existing_account() {
+                        button {
+                            class: "validate-button",
+                            title: "Create an account on the new PDS yourself, then copy your content into it",
+                            onclick: move |_| {
+                                dispatch.call(MigrationAction::SetExistingDidRejected(false));
+                                dispatch.call(MigrationAction::SetMigrationError(None));
+                                dispatch.call(MigrationAction::SetPlanVariant(PlanVariant::ContentCopy));
+                            },
+                            "Copy content into a new account instead"
+                        }
+                    }
+                    PasswordSaveOffer { state, dispatch }
+                    if let Some(report) = state().content_copy_report.clone() {
+                        ContentCopySummary { report }
+                    }
+                    if let Some(backup) = state().preferences_backup.clone() {
+                        PreferencesBackupDownload { backup }
+                    }
+                    if let Some(report) = state().preferences_round_trip.clone() {
+                        PreferencesRoundTripSummary { report }
+                    }
+                    if let Some(manifest) = state().blob_manifest.clone() {
+                        BlobManifestDownload { manifest }
+                    }
+                    if let Some(session) = state().new_pds_session.clone() {
+                        UploadLogDownload { did: session.did }
+                    }
+                    if let Some(summary) = state().blob_enumeration.clone() {
+                        BlobEnumerationSummaryView { summary }
+                    }
+                    UnrecoverableBlobsReport { cids: state().unrecoverable_blobs.clone() }
+                    if state().new_pds_session.is_some() {
+                        button {
+                            class: "validate-button",
+                            title: "Upload blobs cached by earlier attempts without contacting the old PDS",
+                            onclick: move |_| {
+                                spawn(execute_cache_repair_client_side(state(), dispatch));
+                            },
+                            "Upload cached blobs only"
+                        }
+                    }
+                } else if let Some(report) = state().content_copy_report.clone() {
+                    ContentCopySummary { report }
                 } else if let Some(validation_msg) = get_form3_validation_message(&state()) {
                     div {
                         class: "validation-error",
@@ -393,44 +580,102 @@ pub fn MigrationDetailsForm(props: MigrationDetailsFormProps) -> Element {
     }
 }
 
-/// Render the captcha gate component (web feature only)
+/// Start the migration once signup verification produced a code
+#[cfg(feature = "web")]
+fn start_verified_migration(
+    state: Signal<MigrationState>,
+    dispatch: EventHandler<MigrationAction>,
+    mut show_captcha: Signal<bool>,
+    code: String,
+    phone: Option<String>,
+) {
+    dispatch.call(MigrationAction::SetVerificationCode(Some(code)));
+    dispatch.call(MigrationAction::SetVerificationPhone(phone));
+    dispatch.call(MigrationAction::SetVerificationRejected(false));
+    show_captcha.set(false);
+
+    // Auto-start migration now that we have the code
+    let current_state = state();
+    dispatch.call(MigrationAction::SetMigrating(true));
+    dispatch.call(MigrationAction::SetMigrationError(None));
+    dispatch.call(MigrationAction::SetMigrationStep(
+        "Starting migration...".to_string(),
+    ));
+    spawn(execute_migration_client_side(current_state, dispatch));
+}
+
+/// Render the wake lock and background throttling notice (web feature only)
+#[cfg(feature = "web")]
+fn render_tab_activity_notice(
+    state: Signal<MigrationState>,
+    dispatch: EventHandler<MigrationAction>,
+) -> Element {
+    rsx! {
+        TabActivityNotice { state, dispatch }
+    }
+}
+
+/// Render the wake lock and background throttling notice (non-web fallback - not supported)
+#[cfg(not(feature = "web"))]
+fn render_tab_activity_notice(
+    _state: Signal<MigrationState>,
+    _dispatch: EventHandler<MigrationAction>,
+) -> Element {
+    rsx! {}
+}
+
+/// Render the captcha gate, or the text message form, for signup verification (web feature only)
 #[cfg(feature = "web")]
-fn render_captcha_gate(
+fn render_signup_verification(
     state: Signal<MigrationState>,
     dispatch: EventHandler<MigrationAction>,
     mut show_captcha: Signal<bool>,
+    mut use_phone: Signal<bool>,
 ) -> Element {
     rsx! {
-        CaptchaGate {
-            pds_url: state().form2.pds_url.clone(),
-            handle: state().form3.handle.clone(),
-            on_success: move |code: String| {
-                console_info!("[Captcha] Verification code received, starting migration");
-                dispatch.call(MigrationAction::SetVerificationCode(Some(code)));
-                show_captcha.set(false);
-
-                // Auto-start migration now that we have the code
-                let current_state = state();
-                dispatch.call(MigrationAction::SetMigrating(true));
-                dispatch.call(MigrationAction::SetMigrationError(None));
-                dispatch.call(MigrationAction::SetMigrationStep("Starting migration...".to_string()));
-                spawn(execute_migration_client_side(current_state, dispatch));
-            },
-            on_error: move |error: String| {
-                console_info!("[Captcha] Verification failed: {}", error);
-                show_captcha.set(false);
-                dispatch.call(MigrationAction::SetMigrationError(Some(format!("Captcha verification failed: {}", error))));
+        if use_phone() {
+            PhoneVerificationForm {
+                pds_url: state().form2.pds_url.clone(),
+                on_success: move |(phone, code): (String, String)| {
+                    console_info!("[Phone] Verification code entered, starting migration");
+                    start_verified_migration(state, dispatch, show_captcha, code, Some(phone));
+                }
+            }
+            button {
+                class: "validate-button signup-verification-switch",
+                onclick: move |_| use_phone.set(false),
+                "Use the captcha instead"
+            }
+        } else {
+            CaptchaGate {
+                pds_url: state().form2.pds_url.clone(),
+                handle: state().form3.handle.clone(),
+                on_success: move |code: String| {
+                    console_info!("[Captcha] Verification code received, starting migration");
+                    start_verified_migration(state, dispatch, show_captcha, code, None);
+                },
+                on_error: move |error: String| {
+                    console_info!("[Captcha] Verification failed: {}", error);
+                    show_captcha.set(false);
+                    dispatch.call(MigrationAction::SetMigrationError(Some(format!("Captcha verification failed: {}", error))));
+                }
+            }
+            button {
+                class: "validate-button signup-verification-switch",
+                onclick: move |_| use_phone.set(true),
+                "This PDS verifies by text message instead"
             }
         }
     }
 }
 
-/// Render the captcha gate component (non-web fallback - not supported)
+/// Render the signup verification component (non-web fallback - not supported)
 #[cfg(not(feature = "web"))]
-fn render_captcha_gate(
+fn render_signup_verification(
     _state: Signal<MigrationState>,
     dispatch: EventHandler<MigrationAction>,
     mut show_captcha: Signal<bool>,
+    _use_phone: Signal<bool>,
 ) -> Element {
     show_captcha.set(false);
     dispatch.call(MigrationAction::SetMigrationError(Some(
@@ -1,3 +1,5 @@
+pub mod advanced_section;
+pub mod console_log_settings;
 pub mod domain_selector;
 pub mod migration_details_form;
 pub mod pds_selection_form;
@@ -8,7 +10,17 @@ pub mod plc_verification_form;
 pub mod captcha_gate;
 #[cfg(feature = "web")]
 pub mod login_form_client;
+#[cfg(feature = "web")]
+pub mod old_account_deletion_panel;
+#[cfg(feature = "web")]
+pub mod old_account_grace_panel;
+#[cfg(feature = "web")]
+pub mod orphan_cleanup_panel;
+#[cfg(feature = "web")]
+pub mod phone_verification_form;
 
+pub use advanced_section::*;
+pub use console_log_settings::*;
 pub use domain_selector::*;
 pub use migration_details_form::*;
 pub use pds_selection_form::*;
@@ -18,3 +30,11 @@ pub use plc_verification_form::*;
 pub use captcha_gate::*;
 #[cfg(feature = "web")]
 pub use login_form_client::ClientLoginFormComponent;
+#[cfg(feature = "web")]
+pub use old_account_deletion_panel::{OldAccountDeletionReminder, OldAccountDeletionScheduler};
+#[cfg(feature = "web")]
+pub use old_account_grace_panel::{OldAccountDeactivationReminder, OldAccountGraceOption};
+#[cfg(feature = "web")]
+pub use orphan_cleanup_panel::OrphanCleanupPanel;
+#[cfg(feature = "web")]
+pub use phone_verification_form::PhoneVerificationForm;
@@ -0,0 +1,254 @@
+use dioxus::prelude::*;
+
+use crate::migration::old_account_deletion::{
+    cancel_old_account_deletion, delete_old_account, find_old_account,
+    request_old_account_deletion, schedule_old_account_deletion, scheduled_old_account_deletion,
+    OldAccount, ScheduledDeletion, DEFAULT_SAFETY_WINDOW_DAYS, SAFETY_WINDOW_CHOICES,
+};
+use crate::migration::MigrationState;
+use crate::utils::platform::now_secs;
+
+/// Offer a reminder to delete the old account once a safety window has passed
+#[component]
+pub fn OldAccountDeletionScheduler(state: Signal<MigrationState>) -> Element {
+    let mut window_days = use_signal(|| DEFAULT_SAFETY_WINDOW_DAYS);
+    let mut scheduled = use_signal(scheduled_old_account_deletion);
+    let mut error = use_signal(|| None::<String>);
+
+    let old_session = state()
+        .form1
+        .login_response
+        .and_then(|response| response.session);
+    let Some(old_session) = old_session else {
+        return rsx! {};
+    };
+    let new_pds = state()
+        .new_pds_session
+        .map(|session| session.pds)
+        .unwrap_or_else(|| state().form2.pds_url.clone());
+
+    let current = scheduled().filter(|schedule| schedule.did == old_session.did);
+
+    rsx! {
+        div {
+            class: "old-account-deletion",
+            h4 {
+                class: "instructions-title",
+                "🗓️ Delete the old account later"
+            }
+            if let Some(schedule) = current {
+                p {
+                    "You'll be reminded to delete @{schedule.old_handle} on {schedule.old_pds} in {schedule.days_remaining(now_secs())} days when you come back to this page in this browser."
+                }
+                button {
+                    class: "validate-button",
+                    onclick: move |_| {
+                        cancel_old_account_deletion();
+                        scheduled.set(None);
+                    },
+                    "Cancel reminder"
+                }
+            } else {
+                p {
+                    "Your old account is only deactivated, so you can still go back. Once you're sure everything arrived, you can delete it. Pick how long to wait:"
+                }
+                select {
+                    class: "input-field",
+                    value: "{window_days}",
+                    onchange: move |event| {
+                        if let Ok(days) = event.value().parse() {
+                            window_days.set(days);
+                        }
+                    },
+                    for days in SAFETY_WINDOW_CHOICES {
+                        option { value: "{days}", "{days} days" }
+                    }
+                }
+                button {
+                    class: "validate-button",
+                    onclick: move |_| {
+                        let schedule = ScheduledDeletion::new(&old_session, &new_pds, now_secs(), window_days());
+                        match schedule_old_account_deletion(&schedule) {
+                            Ok(()) => {
+                                error.set(None);
+                                scheduled.set(Some(schedule));
+                            }
+                            Err(e) => error.set(Some(e)),
+                        }
+                    },
+                    "Remind me to delete it"
+                }
+            }
+
+            if let Some(message) = error() {
+                div {
+                    class: "validation-result error",
+                    "✗ {message}"
+                }
+            }
+        }
+    }
+}
+
+/// Surface a stored deletion reminder and, once due, walk through the deletion
+#[component]
+pub fn OldAccountDeletionReminder() -> Element {
+    let mut scheduled = use_signal(scheduled_old_account_deletion);
+    let mut password = use_signal(String::new);
+    let mut token = use_signal(String::new);
+    let mut confirmed = use_signal(|| false);
+    let mut account = use_signal(|| None::<OldAccount>);
+    let mut token_requested = use_signal(|| false);
+    let mut deleted = use_signal(|| false);
+    let mut busy = use_signal(|| false);
+    let mut error = use_signal(|| None::<String>);
+
+    if deleted() {
+        return rsx! {
+            div {
+                class: "old-account-reminder",
+                div {
+                    class: "validation-result success",
+                    "✓ Your old account was deleted. The migrated account on the new PDS is untouched."
+                }
+            }
+        };
+    }
+
+    let Some(schedule) = scheduled() else {
+        return rsx! {};
+    };
+    let now = now_secs();
+
+    rsx! {
+        div {
+            class: "old-account-reminder",
+            if !schedule.is_due(now) {
+                p {
+                    "🗓️ Your old account @{schedule.old_handle} on {schedule.old_pds} is due for deletion in {schedule.days_remaining(now)} days."
+                }
+                button {
+                    class: "validate-button",
+                    onclick: move |_| {
+                        cancel_old_account_deletion();
+                        scheduled.set(None);
+                    },
+                    "Cancel reminder"
+                }
+            } else {
+                details {
+                    open: true,
+                    summary { "🗓️ Time to delete your old account @{schedule.old_handle}" }
+                    p {
+                        class: "support-bundle-help",
+                        "The safety window you chose has passed. Log into the old account on {schedule.old_pds} to delete it. This is only allowed if your identity now points at another PDS and the old account is still deactivated."
+                    }
+
+                    if let Some(found) = account() {
+                        div {
+                            class: "validation-result",
+                            "Old account {found.session.did} on {found.session.pds} is deactivated and your identity points elsewhere."
+                        }
+
+                        if !token_requested() {
+                            button {
+                                class: "validate-button",
+                                disabled: busy(),
+                                onclick: move |_| {
+                                    let Some(found) = account() else { return };
+                                    busy.set(true);
+                                    error.set(None);
+                                    spawn(async move {
+                                        match request_old_account_deletion(&found).await {
+                                            Ok(()) => token_requested.set(true),
+                                            Err(e) => error.set(Some(e)),
+                                        }
+                                        busy.set(false);
+                                    });
+                                },
+                                "Email me a deletion code"
+                            }
+                        } else {
+                            input {
+                                class: "input-field",
+                                placeholder: "Deletion code from the email",
+                                value: "{token}",
+                                oninput: move |event| token.set(event.value()),
+                            }
+                            label {
+                                class: "input-label",
+                                input {
+                                    r#type: "checkbox",
+                                    checked: confirmed(),
+                                    onchange: move |event| confirmed.set(event.checked()),
+                                }
+                                " I understand this permanently deletes my old account and everything left on the old PDS"
+                            }
+                            button {
+                                class: "validate-button",
+                                disabled: busy() || !confirmed() || token().trim().is_empty(),
+                                onclick: move |_| {
+                                    let Some(found) = account() else { return };
+                                    busy.set(true);
+                                    error.set(None);
+                                    spawn(async move {
+                                        match delete_old_account(&found, &password(), &token()).await {
+                                            Ok(()) => {
+                                                password.set(String::new());
+                                                deleted.set(true);
+                                            }
+                                            Err(e) => error.set(Some(e)),
+                                        }
+                                        busy.set(false);
+                                    });
+                                },
+                                "Delete old account permanently"
+                            }
+                        }
+                    } else {
+                        input {
+                            class: "input-field",
+                            r#type: "password",
+                            placeholder: "Password for the old account",
+                            value: "{password}",
+                            oninput: move |event| password.set(event.value()),
+                        }
+                        button {
+                            class: "validate-button",
+                            disabled: busy() || password().is_empty(),
+                            onclick: move |_| {
+                                let Some(schedule) = scheduled() else { return };
+                                busy.set(true);
+                                error.set(None);
+                                spawn(async move {
+                                    match find_old_account(&schedule, &password(), now_secs()).await {
+                                        Ok(found) => account.set(Some(found)),
+                                        Err(e) => error.set(Some(e)),
+                                    }
+                                    busy.set(false);
+                                });
+                            },
+                            "Log in and check"
+                        }
+                        button {
+                            class: "validate-button",
+                            disabled: busy(),
+                            onclick: move |_| {
+                                cancel_old_account_deletion();
+                                scheduled.set(None);
+                            },
+                            "Keep the old account"
+                        }
+                    }
+
+                    if let Some(message) = error() {
+                        div {
+                            class: "validation-result error",
+                            "✗ {message}"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use dioxus::prelude::*;
+
+use crate::migration::old_account_grace::{
+    cancel_old_account_deactivation, deactivate_old_account, scheduled_old_account_deactivation,
+    DEFAULT_GRACE_PERIOD_DAYS, GRACE_PERIOD_CHOICES,
+};
+use crate::migration::{MigrationAction, MigrationState};
+use crate::utils::platform::now_secs;
+
+/// Let the user keep the old account active for a while instead of deactivating it
+#[component]
+pub fn OldAccountGraceOption(
+    state: Signal<MigrationState>,
+    dispatch: EventHandler<MigrationAction>,
+) -> Element {
+    let grace_days = state().form4.old_account_grace_days;
+    let disabled = state().form4.is_verifying || state().migration_completed;
+
+    rsx! {
+        div {
+            class: "old-account-grace",
+            label {
+                class: "input-label",
+                input {
+                    r#type: "checkbox",
+                    checked: grace_days.is_some(),
+                    disabled,
+                    onchange: move |event| {
+                        let days = event.checked().then_some(DEFAULT_GRACE_PERIOD_DAYS);
+                        dispatch.call(MigrationAction::SetOldAccountGrace(days));
+                    },
+                }
+                " Keep my old account active for a while instead of deactivating it"
+            }
+            if let Some(days) = grace_days {
+                select {
+                    class: "input-field",
+                    value: "{days}",
+                    disabled,
+                    onchange: move |event| {
+                        if let Ok(days) = event.value().parse() {
+                            dispatch.call(MigrationAction::SetOldAccountGrace(Some(days)));
+                        }
+                    },
+                    for choice in GRACE_PERIOD_CHOICES {
+                        option { value: "{choice}", "{choice} days" }
+                    }
+                }
+                p {
+                    class: "blob-manifest-note",
+                    "Your identity still moves to the new PDS. The old PDS, though, keeps serving your account as it is right now, so some services and apps may show stale posts or an old profile until it is deactivated. New posts made through the old PDS won't reach the new one. You'll be reminded on this page, in this browser, to deactivate it after {days} days."
+                }
+            }
+        }
+    }
+}
+
+/// Surface a stored deactivation reminder and deactivate the old account on request
+#[component]
+pub fn OldAccountDeactivationReminder() -> Element {
+    let mut scheduled = use_signal(scheduled_old_account_deactivation);
+    let mut password = use_signal(String::new);
+    let mut deactivated = use_signal(|| false);
+    let mut busy = use_signal(|| false);
+    let mut error = use_signal(|| None::<String>);
+
+    if deactivated() {
+        return rsx! {
+            div {
+                class: "old-account-reminder",
+                div {
+                    class: "validation-result success",
+                    "✓ Your old account was deactivated. Services now only see the account on your new PDS."
+                }
+            }
+        };
+    }
+
+    let Some(schedule) = scheduled() else {
+        return rsx! {};
+    };
+    let now = now_secs();
+
+    rsx! {
+        div {
+            class: "old-account-reminder",
+            details {
+                open: schedule.is_due(now),
+                summary {
+                    if schedule.is_due(now) {
+                        "⏳ Time to deactivate your old account @{schedule.old_handle}"
+                    } else {
+                        "⏳ Your old account @{schedule.old_handle} stays active for {schedule.days_remaining(now)} more days"
+                    }
+                }
+                p {
+                    class: "support-bundle-help",
+                    "The old account on {schedule.old_pds} is still active, so services that read from it may show stale data. Log in with its password to deactivate it. This is only allowed once your identity points at another PDS."
+                }
+                input {
+                    class: "input-field",
+                    r#type: "password",
+                    placeholder: "Password for the old account",
+                    value: "{password}",
+                    oninput: move |event| password.set(event.value()),
+                }
+                button {
+                    class: "validate-button",
+                    disabled: busy() || password().is_empty(),
+                    onclick: move |_| {
+                        let Some(schedule) = scheduled() else { return };
+                        busy.set(true);
+                        error.set(None);
+                        spawn(async move {
+                            match deactivate_old_account(&schedule, &password()).await {
+                                Ok(()) => {
+                                    password.set(String::new());
+                                    deactivated.set(true);
+                                }
+                                Err(e) => error.set(Some(e)),
+                            }
+                            busy.set(false);
+                        });
+                    },
+                    if schedule.is_due(now) { "Deactivate old account" } else { "Deactivate now" }
+                }
+                button {
+                    class: "validate-button",
+                    disabled: busy(),
+                    onclick: move |_| {
+                        cancel_old_account_deactivation();
+                        scheduled.set(None);
+                    },
+                    "Dismiss reminder"
+                }
+
+                if let Some(message) = error() {
+                    div {
+                        class: "validation-result error",
+                        "✗ {message}"
+                    }
+                }
+            }
+        }
+    }
+}
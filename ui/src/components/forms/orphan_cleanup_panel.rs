@@ -0,0 +1,143 @@
+use dioxus::prelude::*;
+
+use crate::migration::orphan_cleanup::{
+    delete_orphaned_account, find_orphaned_account, request_orphan_deletion, OrphanedAccount,
+};
+
+/// Log into a never-activated account left on a target PDS and delete it
+#[component]
+pub fn OrphanCleanupPanel() -> Element {
+    let mut pds_url = use_signal(String::new);
+    let mut identifier = use_signal(String::new);
+    let mut password = use_signal(String::new);
+    let mut token = use_signal(String::new);
+    let mut confirmed = use_signal(|| false);
+    let mut account = use_signal(|| None::<OrphanedAccount>);
+    let mut token_requested = use_signal(|| false);
+    let mut deleted = use_signal(|| false);
+    let mut busy = use_signal(|| false);
+    let mut error = use_signal(|| None::<String>);
+
+    rsx! {
+        details {
+            class: "orphan-cleanup-panel",
+            summary { "🧹 Delete an unfinished account on a new PDS" }
+
+            p {
+                class: "support-bundle-help",
+                "If a failed migration left an account on a PDS you no longer want to move to, log into it here to delete it and free the handle. Only accounts that were never activated and that your DID does not point to can be deleted."
+            }
+
+            if deleted() {
+                div {
+                    class: "validation-result success",
+                    "✓ The account was deleted. You can now start a migration to a different PDS."
+                }
+            } else if let Some(found) = account() {
+                div {
+                    class: "validation-result",
+                    "Account {found.session.did} on {found.session.pds}: {found.indexed_records} records, {found.imported_blobs} blobs imported, never activated."
+                }
+
+                if !token_requested() {
+                    button {
+                        class: "validate-button",
+                        disabled: busy(),
+                        onclick: move |_| {
+                            let Some(found) = account() else { return };
+                            busy.set(true);
+                            error.set(None);
+                            spawn(async move {
+                                match request_orphan_deletion(&found).await {
+                                    Ok(()) => token_requested.set(true),
+                                    Err(e) => error.set(Some(e)),
+                                }
+                                busy.set(false);
+                            });
+                        },
+                        "Email me a deletion code"
+                    }
+                } else {
+                    input {
+                        class: "input-field",
+                        placeholder: "Deletion code from the email",
+                        value: "{token}",
+                        oninput: move |event| token.set(event.value()),
+                    }
+                    label {
+                        class: "input-label",
+                        input {
+                            r#type: "checkbox",
+                            checked: confirmed(),
+                            onchange: move |event| confirmed.set(event.checked()),
+                        }
+                        " I understand this permanently deletes the account and everything imported into it"
+                    }
+                    button {
+                        class: "validate-button",
+                        disabled: busy() || !confirmed() || token().trim().is_empty(),
+                        onclick: move |_| {
+                            let Some(found) = account() else { return };
+                            busy.set(true);
+                            error.set(None);
+                            spawn(async move {
+                                match delete_orphaned_account(&found, &password(), &token()).await {
+                                    Ok(()) => {
+                                        password.set(String::new());
+                                        deleted.set(true);
+                                    }
+                                    Err(e) => error.set(Some(e)),
+                                }
+                                busy.set(false);
+                            });
+                        },
+                        "Delete account permanently"
+                    }
+                }
+            } else {
+                input {
+                    class: "input-field",
+                    placeholder: "New PDS URL (e.g. https://pds.example.com)",
+                    value: "{pds_url}",
+                    oninput: move |event| pds_url.set(event.value()),
+                }
+                input {
+                    class: "input-field",
+                    placeholder: "Handle or DID",
+                    value: "{identifier}",
+                    oninput: move |event| identifier.set(event.value()),
+                }
+                input {
+                    class: "input-field",
+                    r#type: "password",
+                    placeholder: "Password for the new PDS account",
+                    value: "{password}",
+                    oninput: move |event| password.set(event.value()),
+                }
+                button {
+                    class: "validate-button",
+                    disabled: busy() || pds_url().trim().is_empty() || identifier().trim().is_empty() || password().is_empty(),
+                    onclick: move |_| {
+                        busy.set(true);
+                        error.set(None);
+                        spawn(async move {
+                            match find_orphaned_account(&pds_url(), &identifier(), &password()).await {
+                                Ok(found) => account.set(Some(found)),
+                                Err(e) => error.set(Some(e)),
+                            }
+                            busy.set(false);
+                        });
+                    },
+                    "Log in"
+                }
+            }
+
+            if let Some(message) = error() {
+                div {
+                    class: "validation-result error",
+                    "✗ {message}"
+                }
+            }
+        }
+    }
+}
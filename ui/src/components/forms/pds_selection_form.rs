@@ -5,21 +5,55 @@ use dioxus::prelude::*;
 use crate::services::client::compat::{describe_server, resolve_handle_shared};
 
 use crate::components::{
-    display::LoadingIndicator,
+    display::{DomainSafetyNote, LoadingIndicator, TargetCorsCheck},
     inputs::{InputType, ValidatedInput},
 };
 use crate::migration::*;
+use crate::services::client::{invalidate_server_info, server_info_age_secs};
+use crate::services::config::get_global_config;
+#[cfg(feature = "web")]
+use crate::utils::handle::normalize_pds_url;
+use crate::utils::lookalike::url_host;
+use crate::utils::time_format::format_duration_ms;
 
 #[derive(Props, PartialEq, Clone)]
 pub struct PdsSelectionFormProps {
     pub state: Signal<MigrationState>,
     pub dispatch: EventHandler<MigrationAction>,
+    /// When set, the target PDS is fixed to this URL and cannot be changed
+    #[props(default)]
+    pub locked_pds_url: Option<String>,
 }
 
 #[component]
 pub fn PdsSelectionForm(props: PdsSelectionFormProps) -> Element {
     let state = props.state;
     let dispatch = props.dispatch;
+    let locked_pds_url = props.locked_pds_url.clone();
+    let is_locked = locked_pds_url.is_some();
+    let host_policy = get_global_config().target_hosts;
+    let blacksky_allowed = host_policy.check_url("https://blacksky.app").is_ok();
+    let host_rejection = Some(state().form2.pds_url)
+        .filter(|url| !url.trim().is_empty())
+        .and_then(|url| host_policy.check_url(&url).err());
+
+    // Describe the locked PDS once so the flow continues without user input
+    use_hook(move || {
+        if let Some(url) = locked_pds_url {
+            if state.peek().form2.pds_url != url {
+                dispatch.call(MigrationAction::SetNewPdsUrl(url.clone()));
+                dispatch.call(MigrationAction::SetDescribingPds(true));
+                spawn(describe_and_suggest_handle(url, state, dispatch));
+            }
+        } else {
+            // A URL filled in by a session transfer still needs describing
+            let prefilled = state.peek().form2.pds_url.clone();
+            if !prefilled.trim().is_empty() && state.peek().form2.describe_response.is_none() {
+                dispatch.call(MigrationAction::SetDescribingPds(true));
+                spawn(describe_and_suggest_handle(prefilled, state, dispatch));
+            }
+        }
+    });
 
     rsx! {
         div {
@@ -30,73 +64,22 @@ pub fn PdsSelectionForm(props: PdsSelectionFormProps) -> Element {
                 "Step 2: New PDS Host"
             }
 
-            div {
-                class: "button-section",
-                button {
-                    class: "validate-button",
-                    style: "margin-bottom: 16px; background-color: #7c3aed;",
-                    disabled: state().form2_submitted(),
-                    onclick: move |_| {
-                        dispatch.call(MigrationAction::SetNewPdsUrl("https://blacksky.app".to_string()));
-                        // Trigger PDS describe for Blacksky
-                        let url = "https://blacksky.app".to_string();
-                        dispatch.call(MigrationAction::SetDescribingPds(true));
-                        spawn(async move {
-                            #[cfg(feature = "web")]
-                            {
-                                match describe_server(url).await {
-                                    Ok(server_info) => {
-                                        // Parse the JSON response to PdsDescribeResponse
-                                        match serde_json::from_value::<PdsDescribeResponse>(server_info) {
-                                            Ok(response) => {
-                                                dispatch.call(MigrationAction::SetPdsDescribeResponse(Some(response.clone())));
-                                                dispatch.call(MigrationAction::SetForm2Submitted(true));
-
-                                                // Auto-populate smart handle suggestion if available
-                                                let current_state = state();
-                                                if let Some(suggested_handle) = current_state.suggest_handle() {
-                                                    // Check if the suggested handle is available
-                                                    match resolve_handle_shared(suggested_handle.clone()).await {
-                                                        Ok(provider) => {
-                                                            match provider {
-                                                                crate::services::client::ClientPdsProvider::None => {
-                                                                    // Handle is available, auto-populate it
-                                                                    dispatch.call(MigrationAction::SetNewHandle(suggested_handle));
-                                                                    dispatch.call(MigrationAction::SetHandleValidation(HandleValidation::Available));
-                                                                }
-                                                                _ => {
-                                                                    // Handle is unavailable, leave empty
-                                                                    // User will see it as placeholder with unavailable styling
-                                                                }
-                                                            }
-                                                        }
-                                                        Err(_) => {
-                                                            // Error checking, leave empty
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                            Err(_) => {
-                                                dispatch.call(MigrationAction::SetPdsDescribeResponse(None));
-                                            }
-                                        }
-                                    }
-                                    Err(_) => {
-                                        dispatch.call(MigrationAction::SetPdsDescribeResponse(None));
-                                    }
-                                }
-                            }
-
-                            #[cfg(not(feature = "web"))]
-                            {
-                                // Fallback - set error state
-                                dispatch.call(MigrationAction::SetPdsDescribeResponse(None));
-                            }
-
-                            dispatch.call(MigrationAction::SetDescribingPds(false));
-                        });
-                    },
-                    "Migrate to Blacksky"
+            if !is_locked && blacksky_allowed {
+                div {
+                    class: "button-section",
+                    button {
+                        class: "validate-button",
+                        style: "margin-bottom: 16px; background-color: #7c3aed;",
+                        disabled: state().form2_submitted(),
+                        onclick: move |_| {
+                            dispatch.call(MigrationAction::SetNewPdsUrl("https://blacksky.app".to_string()));
+                            // Trigger PDS describe for Blacksky
+                            let url = "https://blacksky.app".to_string();
+                            dispatch.call(MigrationAction::SetDescribingPds(true));
+                            spawn(describe_and_suggest_handle(url, state, dispatch));
+                        },
+                        "Migrate to Blacksky"
+                    }
                 }
             }
 
@@ -112,7 +95,7 @@ pub fn PdsSelectionForm(props: PdsSelectionFormProps) -> Element {
                     input_type: InputType::Text,
                     input_class: "input-field".to_string(),
                     input_style: "".to_string(),
-                    disabled: state().form2_submitted(),
+                    disabled: is_locked || state().form2_submitted(),
                     on_change: move |url: String| {
                         dispatch.call(MigrationAction::SetNewPdsUrl(url.clone()));
 
@@ -123,67 +106,22 @@ pub fn PdsSelectionForm(props: PdsSelectionFormProps) -> Element {
                         // Trigger PDS describe if URL is not empty
                         if !url.trim().is_empty() {
                             dispatch.call(MigrationAction::SetDescribingPds(true));
-                            spawn(async move {
-                                #[cfg(feature = "web")]
-                                {
-                                    match describe_server(url).await {
-                                        Ok(server_info) => {
-                                            // Parse the JSON response to PdsDescribeResponse
-                                            match serde_json::from_value::<PdsDescribeResponse>(server_info) {
-                                                Ok(response) => {
-                                                    dispatch.call(MigrationAction::SetPdsDescribeResponse(Some(response.clone())));
-                                                    dispatch.call(MigrationAction::SetForm2Submitted(true));
-
-                                                    // Auto-populate smart handle suggestion if available
-                                                    let current_state = state();
-                                                    if let Some(suggested_handle) = current_state.suggest_handle() {
-                                                        // Check if the suggested handle is available
-                                                        match resolve_handle_shared(suggested_handle.clone()).await {
-                                                            Ok(provider) => {
-                                                                match provider {
-                                                                    crate::services::client::ClientPdsProvider::None => {
-                                                                        // Handle is available, auto-populate it
-                                                                        dispatch.call(MigrationAction::SetNewHandle(suggested_handle));
-                                                                        dispatch.call(MigrationAction::SetHandleValidation(HandleValidation::Available));
-                                                                    }
-                                                                    _ => {
-                                                                        // Handle is unavailable, leave empty
-                                                                        // User will see it as placeholder with unavailable styling
-                                                                    }
-                                                                }
-                                                            }
-                                                            Err(_) => {
-                                                                // Error checking, leave empty
-                                                            }
-                                                        }
-                                                    }
-                                                }
-                                                Err(_) => {
-                                                    dispatch.call(MigrationAction::SetPdsDescribeResponse(None));
-                                                }
-                                            }
-                                        }
-                                        Err(_) => {
-                                            dispatch.call(MigrationAction::SetPdsDescribeResponse(None));
-                                        }
-                                    }
-                                }
-
-                                #[cfg(not(feature = "web"))]
-                                {
-                                    // Fallback - set error state
-                                    dispatch.call(MigrationAction::SetPdsDescribeResponse(None));
-                                }
-
-                                dispatch.call(MigrationAction::SetDescribingPds(false));
-                            });
+                            spawn(describe_and_suggest_handle(url, state, dispatch));
                         }
                     }
                 }
+                if let Some(host) = url_host(&state().form2.pds_url) {
+                    DomainSafetyNote { domain: host }
+                }
             }
 
             // Show PDS describe status
-            if state().form2.is_describing {
+            if let Some(message) = host_rejection {
+                div {
+                    class: "validation-result error",
+                    "✗ {message}"
+                }
+            } else if state().form2.is_describing {
                 LoadingIndicator { message: "Describing PDS server...".to_string() }
             } else if let Some(describe_response) = &state().form2.describe_response {
                 div {
@@ -196,13 +134,14 @@ pub fn PdsSelectionForm(props: PdsSelectionFormProps) -> Element {
                         style: "margin-bottom: 4px;",
                         "Available Domains: {describe_response.available_user_domains.join(\", \")}"
                     }
-                    if let Some(invite_required) = describe_response.invite_code_required {
-                        div {
-                            style: "margin-bottom: 4px;",
-                            if invite_required {
-                                "⚠️ Invite code required"
-                            } else {
-                                "✓ No invite code required"
+                    {
+                        let gating = SignupGating::from_describe(describe_response);
+                        rsx! {
+                            if gating != SignupGating::Unknown {
+                                div {
+                                    style: "margin-bottom: 4px;",
+                                    "{gating.description()}"
+                                }
                             }
                         }
                     }
@@ -242,6 +181,24 @@ pub fn PdsSelectionForm(props: PdsSelectionFormProps) -> Element {
                             }
                         }
                     }
+                    div {
+                        class: "server-info-refresh",
+                        if let Some(age) = server_info_age_secs(&state().form2.pds_url) {
+                            span { "Server details fetched {format_duration_ms(age * 1000)} ago and reused for the migration. " }
+                        }
+                        button {
+                            class: "validate-button",
+                            title: "Ask the server again, e.g. after its domains or signup rules changed",
+                            disabled: state().form2.is_describing,
+                            onclick: move |_| {
+                                let url = state().form2.pds_url.clone();
+                                invalidate_server_info(&url);
+                                dispatch.call(MigrationAction::SetDescribingPds(true));
+                                spawn(describe_and_suggest_handle(url, state, dispatch));
+                            },
+                            "Refresh"
+                        }
+                    }
                 }
             } else if !state().form2.pds_url.trim().is_empty() && !state().form2.is_describing {
                 div {
@@ -249,6 +206,96 @@ pub fn PdsSelectionForm(props: PdsSelectionFormProps) -> Element {
                     "✗ Unable to describe PDS server. Please check the URL."
                 }
             }
+
+            // Self-hosted PDSes often sit behind a proxy without CORS headers
+            if !state().form2.pds_url.trim().is_empty() && !state().form2.is_describing {
+                TargetCorsCheck { pds_url: state().form2.pds_url.clone() }
+            }
         }
     }
 }
+
+/// Describe the given PDS and, on success, pre-fill an available handle suggestion
+async fn describe_and_suggest_handle(
+    url: String,
+    state: Signal<MigrationState>,
+    dispatch: EventHandler<MigrationAction>,
+) {
+    #[cfg(feature = "web")]
+    if get_global_config()
+        .target_hosts
+        .check_url(&normalize_pds_url(&url))
+        .is_err()
+    {
+        // The form explains why; nothing is sent to a host the deployment rules out
+        dispatch.call(MigrationAction::SetPdsDescribeResponse(None));
+    } else {
+        let url = normalize_pds_url(&url);
+        match describe_server(url.clone()).await {
+            Ok(server_info) => {
+                // Show the canonical URL once it is known to reach a PDS,
+                // unless the user has typed something else meanwhile
+                let typed = state.peek().form2.pds_url.clone();
+                if typed != url && normalize_pds_url(&typed) == url {
+                    dispatch.call(MigrationAction::SetNewPdsUrl(url));
+                }
+
+                // Parse the JSON response to PdsDescribeResponse
+                match serde_json::from_value::<PdsDescribeResponse>(server_info) {
+                    Ok(response) => {
+                        dispatch.call(MigrationAction::SetPdsDescribeResponse(Some(
+                            response.clone(),
+                        )));
+                        dispatch.call(MigrationAction::SetForm2Submitted(true));
+
+                        // Auto-populate smart handle suggestion if available, unless a
+                        // handle was already chosen (e.g. on another device)
+                        let current_state = state();
+                        let suggestion = current_state
+                            .suggest_handle()
+                            .filter(|_| current_state.form3.handle.trim().is_empty());
+                        if let Some(suggested_handle) = suggestion {
+                            // Check if the suggested handle is available
+                            match resolve_handle_shared(suggested_handle.clone()).await {
+                                Ok(provider) => {
+                                    match provider {
+                                        crate::services::client::ClientPdsProvider::None => {
+                                            // Handle is available, auto-populate it
+                                            dispatch.call(MigrationAction::SetNewHandle(
+                                                suggested_handle,
+                                            ));
+                                            dispatch.call(MigrationAction::SetHandleValidation(
+                                                HandleValidation::Available,
+                                            ));
+                                        }
+                                        _ => {
+                                            // Handle is unavailable, leave empty
+                                            // User will see it as placeholder with unavailable styling
+                                        }
+                                    }
+                                }
+                                Err(_) => {
+                                    // Error checking, leave empty
+                                }
+                            }
+                        }
+                    }
+                    Err(_) => {
+                        dispatch.call(MigrationAction::SetPdsDescribeResponse(None));
+                    }
+                }
+            }
+            Err(_) => {
+                dispatch.call(MigrationAction::SetPdsDescribeResponse(None));
+            }
+        }
+    }
+
+    #[cfg(not(feature = "web"))]
+    {
+        // Fallback - set error state
+        dispatch.call(MigrationAction::SetPdsDescribeResponse(None));
+    }
+
+    dispatch.call(MigrationAction::SetDescribingPds(false));
+}
@@ -0,0 +1,104 @@
+//! Text message verification for PDS account creation
+//!
+//! PDSes that advertise `phoneVerificationRequired` without a captcha gate text a
+//! code through `com.atproto.temp.requestPhoneVerification`. The code and the
+//! phone number are then passed to `createAccount` as `verificationCode` and
+//! `verificationPhone`.
+
+use dioxus::prelude::*;
+
+use crate::console_info;
+use crate::services::client::PdsClient;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct PhoneVerificationFormProps {
+    /// The PDS URL (e.g., "https://pds.example.com")
+    pub pds_url: String,
+    /// Called with (phone number, code) once the user has entered the code
+    pub on_success: EventHandler<(String, String)>,
+}
+
+#[component]
+pub fn PhoneVerificationForm(props: PhoneVerificationFormProps) -> Element {
+    let mut phone = use_signal(String::new);
+    let mut code = use_signal(String::new);
+    let mut code_sent = use_signal(|| false);
+    let mut busy = use_signal(|| false);
+    let mut error = use_signal(|| None::<String>);
+
+    let pds_url = props.pds_url.clone();
+    let on_success = props.on_success;
+
+    rsx! {
+        div {
+            class: "phone-verification",
+            div {
+                class: "phone-verification-help",
+                "Verification required by the target PDS. Enter your phone number in international format (e.g. +15551234567) to receive a code by text message:"
+            }
+
+            input {
+                class: "input-field",
+                r#type: "tel",
+                placeholder: "+15551234567",
+                value: "{phone}",
+                disabled: code_sent(),
+                oninput: move |event| phone.set(event.value()),
+            }
+
+            if !code_sent() {
+                button {
+                    class: "validate-button",
+                    disabled: busy() || phone().trim().len() < 8,
+                    onclick: move |_| {
+                        let pds_url = pds_url.clone();
+                        busy.set(true);
+                        error.set(None);
+                        spawn(async move {
+                            match PdsClient::new().request_phone_verification(&pds_url, phone().trim()).await {
+                                Ok(response) if response.success => {
+                                    console_info!("[Phone] Verification code requested");
+                                    code_sent.set(true);
+                                }
+                                Ok(response) => error.set(Some(response.message)),
+                                Err(e) => error.set(Some(e.to_string())),
+                            }
+                            busy.set(false);
+                        });
+                    },
+                    "Text me a code"
+                }
+            } else {
+                input {
+                    class: "input-field",
+                    placeholder: "Code from the text message",
+                    value: "{code}",
+                    oninput: move |event| code.set(event.value()),
+                }
+                button {
+                    class: "validate-button",
+                    disabled: code().trim().is_empty(),
+                    onclick: move |_| {
+                        on_success.call((phone().trim().to_string(), code().trim().to_string()));
+                    },
+                    "Verify and start migration"
+                }
+                button {
+                    class: "validate-button",
+                    onclick: move |_| {
+                        code.set(String::new());
+                        code_sent.set(false);
+                    },
+                    "Use a different number"
+                }
+            }
+
+            if let Some(message) = error() {
+                div {
+                    class: "validation-result error",
+                    "✗ {message}"
+                }
+            }
+        }
+    }
+}
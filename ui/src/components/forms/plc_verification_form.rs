@@ -1,12 +1,31 @@
 use dioxus::prelude::*;
 // Import console macros from our crate
-use crate::{console_error, console_info, console_warn};
-
+use crate::{console_error, console_info, console_warn, console_warn_with_dispatch};
+
+use crate::components::display::{
+    AppPasswordRecreation, BlobManifestDownload, DomainSafetyNote, EmailVerificationPanel,
+    MigrationTimeline, PasswordSaveOffer, PlcTokenWaitPanel, PostMigrationChecklist,
+    PreferencesBackupDownload, PreferencesRoundTripSummary, PropagationMonitor, RepoCarChecksum,
+    SavedFeedsCheck, ServiceAccountNotice, UnrecoverableBlobsReport, UploadLogDownload,
+};
 use crate::components::inputs::{InputType, ValidatedInput};
 use crate::migration::*;
 
+use crate::migration::did_method::{did_method_for, did_web_document_url};
+use crate::migration::plc_token_delivery::{
+    is_expired_token_error, parse_externally_signed_operation, save_plc_token_delivery,
+    PlcTokenDelivery,
+};
+use crate::migration::preferences_backup::load_preferences_backup;
+use crate::migration::service_accounts::verify_service_endpoints;
 use crate::migration::storage::LocalStorageManager;
-use crate::services::client::PdsClient;
+use crate::services::client::{
+    check_saved_feeds, run_propagation_monitor, NewPds, OldPds, PdsClient,
+};
+use crate::services::config::get_global_config;
+use crate::utils::handle::display_handle;
+use crate::utils::lookalike::url_host;
+use crate::utils::platform::sleep_ms;
 
 #[derive(Props, PartialEq, Clone)]
 pub struct PlcVerificationFormProps {
@@ -14,6 +33,96 @@ pub struct PlcVerificationFormProps {
     pub dispatch: EventHandler<MigrationAction>,
 }
 
+/// Offer to keep the old account active for a while after the switch (web feature only)
+fn render_old_account_grace_option(
+    state: Signal<MigrationState>,
+    dispatch: EventHandler<MigrationAction>,
+) -> Element {
+    #[cfg(feature = "web")]
+    {
+        rsx! {
+            super::OldAccountGraceOption { state, dispatch }
+        }
+    }
+
+    #[cfg(not(feature = "web"))]
+    {
+        let _ = (state, dispatch);
+        rsx! {}
+    }
+}
+
+/// Store the reminder to deactivate an old account left active; without it
+/// the old account is deactivated as usual
+fn schedule_old_account_grace(state: &MigrationState, days: u32) -> Result<(), String> {
+    #[cfg(feature = "web")]
+    {
+        use crate::migration::old_account_grace::{
+            schedule_old_account_deactivation, ScheduledDeactivation,
+        };
+
+        let old_session = state
+            .form1
+            .login_response
+            .as_ref()
+            .and_then(|response| response.session.as_ref())
+            .ok_or_else(|| "No old session to remember".to_string())?;
+        let new_pds = state
+            .new_pds_session
+            .as_ref()
+            .map(|session| session.pds.clone())
+            .unwrap_or_else(|| state.form2.pds_url.clone());
+        let schedule = ScheduledDeactivation::new(
+            old_session,
+            &new_pds,
+            crate::utils::platform::now_secs(),
+            days,
+        );
+        schedule_old_account_deactivation(&schedule)
+    }
+
+    #[cfg(not(feature = "web"))]
+    {
+        let _ = (state, days);
+        Err("Reminders need the web build".to_string())
+    }
+}
+
+/// Offer a sampled re-check of the uploaded blobs (hash-verification feature only)
+fn render_blob_integrity_audit(session: Option<SessionCredentials>) -> Element {
+    #[cfg(feature = "hash-verification")]
+    {
+        match session {
+            Some(session) => rsx! {
+                crate::components::display::BlobIntegrityAudit { session }
+            },
+            None => rsx! {},
+        }
+    }
+
+    #[cfg(not(feature = "hash-verification"))]
+    {
+        let _ = session;
+        rsx! {}
+    }
+}
+
+/// Offer deferred deletion of the old account (web feature only)
+fn render_old_account_deletion_scheduler(state: Signal<MigrationState>) -> Element {
+    #[cfg(feature = "web")]
+    {
+        rsx! {
+            super::OldAccountDeletionScheduler { state: state }
+        }
+    }
+
+    #[cfg(not(feature = "web"))]
+    {
+        let _ = state;
+        rsx! {}
+    }
+}
+
 #[component]
 pub fn PlcVerificationForm(props: PlcVerificationFormProps) -> Element {
     let state = props.state;
@@ -23,6 +132,16 @@ pub fn PlcVerificationForm(props: PlcVerificationFormProps) -> Element {
         state().get_handle_prefix(),
         state().get_domain_suffix()
     );
+    let new_did = state()
+        .new_pds_session
+        .map(|session| session.did)
+        .unwrap_or_default();
+    // did:web has no emailed token; the user publishes the document instead
+    let requires_token = did_method_for(&new_did).map_or(true, |method| method.requires_token());
+    let email_subject = get_global_config().plc_token.email_subject;
+    // An operation signed with a recovery key stands in for the emailed token
+    let signed_operation = state().form4.signed_operation;
+    let signed_externally = !signed_operation.trim().is_empty();
 
     rsx! {
         div {
@@ -30,7 +149,7 @@ pub fn PlcVerificationForm(props: PlcVerificationFormProps) -> Element {
 
             h2 {
                 class: "form-title",
-                "Step 4: PLC Token Verification"
+                if requires_token { "Step 4: PLC Token Verification" } else { "Step 4: Publish Your DID Document" }
             }
 
             div {
@@ -45,66 +164,138 @@ pub fn PlcVerificationForm(props: PlcVerificationFormProps) -> Element {
                 }
             }
 
+            // Last look at where the identity will point before it moves
             div {
-                class: "instruction-section",
-                p {
-                    class: "instruction-text",
-                    strong { "Check the email for " }
-                    strong {
-                        style: "color: #8b5cf6;",
-                        "{state().form1.original_handle}"
+                class: "display-section",
+                label {
+                    class: "input-label",
+                    "Moving to:"
+                }
+                div {
+                    class: "display-value",
+                    "@{display_handle(&handle)} on {state().form2.pds_url}"
+                }
+                DomainSafetyNote { domain: handle.clone() }
+                if let Some(host) = url_host(&state().form2.pds_url) {
+                    DomainSafetyNote { domain: host }
+                }
+            }
+
+            PasswordSaveOffer { state, dispatch }
+
+            if let Some(profile) = state().service_account.clone() {
+                ServiceAccountNotice {
+                    profile,
+                    did_method: if requires_token { "plc" } else { "web" },
+                }
+            }
+
+            if requires_token {
+                div {
+                    class: "instruction-section",
+                    p {
+                        class: "instruction-text",
+                        strong { "Check the email for " }
+                        strong {
+                            style: "color: #8b5cf6;",
+                            "{state().form1.original_handle}"
+                        }
+                        br {}
+                        br {}
+                        "📧 Look for an email with the subject: "
+                        strong { "\"{email_subject}\"" }
+                        br {}
+                        "🔍 Check your spam/junk folder if you don't see it"
+                        br {}
+                        "🎯 The verification token will look something like: "
+                        code {
+                            style: "background-color: #f3f4f6; padding: 2px 6px; border-radius: 4px; font-family: monospace;",
+                            "A1B2C-3D4E5"
+                        }
+                        br {}
+                        br {}
+                        "⏰ "
+                        em { "Copy and paste it below to complete your migration." }
+                    }
+                }
+
+                div {
+                    class: "input-section",
+                    label {
+                        class: "input-label",
+                        "Email Verification Code:"
                     }
-                    br {}
-                    br {}
-                    "📧 Look for an email with the subject: "
-                    strong { "\"PLC Update Operation Requested\"" }
-                    br {}
-                    "🔍 Check your spam/junk folder if you don't see it"
-                    br {}
-                    "🎯 The verification token will look something like: "
-                    code {
-                        style: "background-color: #f3f4f6; padding: 2px 6px; border-radius: 4px; font-family: monospace;",
-                        "A1B2C-3D4E5"
+                    ValidatedInput {
+                        value: state().form4.verification_code,
+                        placeholder: "Enter verification code from email".to_string(),
+                        input_type: InputType::Text,
+                        input_class: "input-field".to_string(),
+                        input_style: "".to_string(),
+                        disabled: state().form4.is_verifying,
+                        on_change: move |code: String| {
+                            dispatch.call(MigrationAction::SetPlcVerificationCode(code));
+                        }
                     }
-                    br {}
-                    br {}
-                    "⏰ "
-                    em { "Copy and paste it below to complete your migration." }
                 }
-            }
 
-            div {
-                class: "input-section",
-                label {
-                    class: "input-label",
-                    "Email Verification Code:"
+                PlcTokenWaitPanel { state, dispatch }
+            } else {
+                div {
+                    class: "instruction-section",
+                    p {
+                        class: "instruction-text",
+                        "Your identity is a did:web, so the new PDS can't update it for you. Replace the document at "
+                        code { "{did_web_document_url(&new_did).unwrap_or_default()}" }
+                        " with the one below, then confirm."
+                    }
+                    pre {
+                        class: "did-document",
+                        "{state().form4.plc_unsigned}"
+                    }
                 }
-                ValidatedInput {
-                    value: state().form4.verification_code,
-                    placeholder: "Enter verification code from email".to_string(),
-                    input_type: InputType::Text,
-                    input_class: "input-field".to_string(),
-                    input_style: "".to_string(),
-                    disabled: state().form4.is_verifying,
-                    on_change: move |code: String| {
-                        dispatch.call(MigrationAction::SetPlcVerificationCode(code));
+            }
+
+            if let Some(notice) = state().form4.update_confirmation.clone() {
+                div {
+                    class: "instruction-section",
+                    p {
+                        class: "instruction-text",
+                        "{notice}"
+                    }
+                    label {
+                        class: "input-label",
+                        input {
+                            r#type: "checkbox",
+                            checked: state().form4.update_confirmed,
+                            disabled: state().form4.is_verifying,
+                            onchange: move |event| dispatch.call(MigrationAction::SetPlcUpdateConfirmed(event.checked())),
+                        }
+                        " I understand who will be able to change my identity after this update"
                     }
                 }
             }
 
+            {render_old_account_grace_option(state, dispatch)}
+
             div {
                 class: "button-section",
                 button {
                     class: "verify-button",
                     disabled: {
                         state().form4.is_verifying ||
-                        state().form4.verification_code.trim().is_empty() ||
+                        state().form4.awaiting_confirmation() ||
+                        (requires_token && !signed_externally && state().form4.verification_code.trim().is_empty()) ||
+                        (signed_externally && parse_externally_signed_operation(&signed_operation).is_err()) ||
                         state().form4.plc_unsigned.trim().is_empty()
                     },
                     onclick: move |_| {
                         let current_state = state();
+                        if current_state.form4.awaiting_confirmation() {
+                            return;
+                        }
                         let verification_code = current_state.form4.verification_code.clone();
                         let plc_unsigned = current_state.form4.plc_unsigned.clone();
+                        let signed_operation = current_state.form4.signed_operation.clone();
 
                         dispatch.call(MigrationAction::SetPlcVerifying(true));
                         dispatch.call(MigrationAction::SetMigrationError(None));
@@ -112,9 +303,6 @@ pub fn PlcVerificationForm(props: PlcVerificationFormProps) -> Element {
                         spawn(async move {
                             console_info!("[Form4] Starting PLC operation signing with verification code");
 
-                            // Create PDS client for API calls
-                            let pds_client = PdsClient::new();
-
                             // Get old and new sessions from localStorage
                             let old_session_result = LocalStorageManager::get_old_session()
                                 .map_err(|_| "Failed to get old PDS session")
@@ -124,8 +312,8 @@ pub fn PlcVerificationForm(props: PlcVerificationFormProps) -> Element {
                                 .map_err(|_| "Failed to get new PDS session")
                                 .map(|session| (&session).into());
 
-                            let old_session = match old_session_result {
-                                Ok(session) => session,
+                            let old_pds = match old_session_result {
+                                Ok(session) => OldPds::new(PdsClient::new(), session),
                                 Err(error) => {
                                     console_error!("{}", format!("[Form4] Failed to get old session: {}", error));
                                     dispatch.call(MigrationAction::SetMigrationError(Some(error.to_string())));
@@ -134,8 +322,8 @@ pub fn PlcVerificationForm(props: PlcVerificationFormProps) -> Element {
                                 }
                             };
 
-                            let new_session = match new_session_result {
-                                Ok(session) => session,
+                            let new_pds = match new_session_result {
+                                Ok(session) => NewPds::new(PdsClient::new(), session),
                                 Err(error) => {
                                     console_error!("{}", format!("[Form4] Failed to get new session: {}", error));
                                     dispatch.call(MigrationAction::SetMigrationError(Some(error.to_string())));
@@ -148,22 +336,42 @@ pub fn PlcVerificationForm(props: PlcVerificationFormProps) -> Element {
                             console_info!("[Form4] Step 17: Signing PLC operation");
                             dispatch.call(MigrationAction::SetMigrationStep("Signing PLC operation...".to_string()));
 
-                            let plc_signed = match pds_client.sign_plc_operation(&old_session, plc_unsigned, verification_code).await {
-                                Ok(response) => {
-                                    if response.success {
-                                        console_info!("[Form4] PLC operation signed successfully");
-                                        response.plc_signed.unwrap_or_default()
-                                    } else {
-                                        let error_msg = response.message.clone();
-                                        console_error!("{}", format!("[Form4] PLC signing failed: {}", error_msg));
-                                        dispatch.call(MigrationAction::SetMigrationError(Some(response.message)));
-                                        dispatch.call(MigrationAction::SetPlcVerifying(false));
-                                        return;
-                                    }
+                            let method = match did_method_for(new_pds.did()) {
+                                Ok(method) => method,
+                                Err(error) => {
+                                    console_error!("{}", format!("[Form4] {}", error));
+                                    dispatch.call(MigrationAction::SetMigrationError(Some(error)));
+                                    dispatch.call(MigrationAction::SetPlcVerifying(false));
+                                    return;
                                 }
-                                Err(e) => {
-                                    console_error!("{}", format!("[Form4] PLC signing client operation failed: {}", e));
-                                    dispatch.call(MigrationAction::SetMigrationError(Some(format!("Failed to sign PLC operation: {}", e))));
+                            };
+
+                            let signed = if !signed_operation.trim().is_empty() {
+                                parse_externally_signed_operation(&signed_operation)
+                            } else {
+                                method.sign(&old_pds, plc_unsigned, verification_code).await
+                            };
+                            let plc_signed = match signed {
+                                Ok(plc_signed) => {
+                                    console_info!("[Form4] PLC operation signed successfully");
+                                    plc_signed
+                                }
+                                Err(error) if is_expired_token_error(&error) => {
+                                    // The expiry wizard explains this and gets a fresh code
+                                    console_warn!("[Form4] PLC token expired: {}", error);
+                                    let delivery = current_state
+                                        .plc_token_delivery
+                                        .clone()
+                                        .unwrap_or_else(|| PlcTokenDelivery::new(new_pds.did(), crate::utils::platform::now_ms() as u64))
+                                        .expired();
+                                    save_plc_token_delivery(&delivery);
+                                    dispatch.call(MigrationAction::SetPlcTokenDelivery(Some(delivery)));
+                                    dispatch.call(MigrationAction::SetPlcVerifying(false));
+                                    return;
+                                }
+                                Err(error) => {
+                                    console_error!("{}", format!("[Form4] PLC signing failed: {}", error));
+                                    dispatch.call(MigrationAction::SetMigrationError(Some(error)));
                                     dispatch.call(MigrationAction::SetPlcVerifying(false));
                                     return;
                                 }
@@ -174,42 +382,75 @@ pub fn PlcVerificationForm(props: PlcVerificationFormProps) -> Element {
                             plc_progress.operation_signed = true;
                             dispatch.call(MigrationAction::SetPlcProgress(plc_progress.clone()));
 
+                            // Posts made since the initial export would be stranded on the old PDS
+                            #[cfg(feature = "web")]
+                            if let Err(error) = crate::migration::continuous_sync::catch_up_before_switch(
+                                old_pds.session(),
+                                new_pds.session(),
+                                &dispatch,
+                                &current_state,
+                            )
+                            .await
+                            {
+                                console_warn_with_dispatch!(
+                                    dispatch,
+                                    "[Form4] Catch-up before the identity switch failed, continuing: {}",
+                                    error
+                                );
+                            }
+
                             // Step 18: Submit PLC operation to new PDS
                             console_info!("[Form4] Step 18: Submitting PLC operation");
                             dispatch.call(MigrationAction::SetMigrationStep("Submitting PLC operation...".to_string()));
 
-                            match pds_client.submit_plc_operation(&new_session, plc_signed).await {
-                                Ok(response) => {
-                                    if response.success {
-                                        console_info!("[Form4] PLC operation submitted successfully");
-                                    } else {
-                                        let error_msg = response.message.clone();
-                                        console_error!("{}", format!("[Form4] PLC submission failed: {}", error_msg));
-                                        dispatch.call(MigrationAction::SetMigrationError(Some(response.message)));
-                                        dispatch.call(MigrationAction::SetPlcVerifying(false));
-                                        return;
-                                    }
-                                }
-                                Err(e) => {
-                                    console_error!("{}", format!("[Form4] PLC submission client operation failed: {}", e));
-                                    dispatch.call(MigrationAction::SetMigrationError(Some(format!("Failed to submit PLC operation: {}", e))));
-                                    dispatch.call(MigrationAction::SetPlcVerifying(false));
-                                    return;
-                                }
-                            };
+                            if let Err(error) = method.submit(&new_pds, plc_signed).await {
+                                console_error!("{}", format!("[Form4] PLC submission failed: {}", error));
+                                dispatch.call(MigrationAction::SetMigrationError(Some(error)));
+                                dispatch.call(MigrationAction::SetPlcVerifying(false));
+                                return;
+                            }
+                            console_info!("[Form4] PLC operation submitted successfully");
 
                             // Update PLC progress
                             plc_progress.operation_submitted = true;
                             dispatch.call(MigrationAction::SetPlcProgress(plc_progress.clone()));
 
+                            // Labeler and feed services must still point where they did
+                            if let Some(profile) = current_state.service_account.clone() {
+                                if !profile.services.is_empty() && new_pds.did().starts_with("did:plc:") {
+                                    match verify_service_endpoints(&new_pds, profile).await {
+                                        Ok(profile) => {
+                                            if !profile.endpoints_verified() {
+                                                console_warn_with_dispatch!(
+                                                    dispatch,
+                                                    "[Form4] Some labeler or feed generator services no longer point at their old endpoints"
+                                                );
+                                            }
+                                            dispatch.call(MigrationAction::SetServiceAccount(Some(profile)));
+                                        }
+                                        Err(error) => {
+                                            console_warn_with_dispatch!(dispatch, "[Form4] {}", error);
+                                        }
+                                    }
+                                }
+                            }
+
                             // Step 19: Activate account on new PDS
                             console_info!("[Form4] Step 19: Activating account on new PDS");
                             dispatch.call(MigrationAction::SetMigrationStep("Activating account on new PDS...".to_string()));
 
-                            match pds_client.activate_account(&new_session).await {
+                            // A target that refused to deactivate its auto-activated account is already active
+                            let already_active = matches!(
+                                current_state.target_auto_activation,
+                                Some(TargetAutoActivation::LeftActive { .. })
+                            );
+
+                            match new_pds.activate_account().await {
                                 Ok(response) => {
                                     if response.success {
                                         console_info!("[Form4] New account activated successfully");
+                                    } else if already_active {
+                                        console_warn!("{}", format!("[Form4] Activation call failed on an already-active account, continuing: {}", response.message));
                                     } else {
                                         let error_msg = response.message.clone();
                                         console_error!("{}", format!("[Form4] Account activation failed: {}", error_msg));
@@ -218,6 +459,9 @@ pub fn PlcVerificationForm(props: PlcVerificationFormProps) -> Element {
                                         return;
                                     }
                                 }
+                                Err(e) if already_active => {
+                                    console_warn!("{}", format!("[Form4] Activation call failed on an already-active account, continuing: {}", e));
+                                }
                                 Err(e) => {
                                     console_error!("{}", format!("[Form4] Account activation client operation failed: {}", e));
                                     dispatch.call(MigrationAction::SetMigrationError(Some(format!("Failed to activate new account: {}", e))));
@@ -226,52 +470,76 @@ pub fn PlcVerificationForm(props: PlcVerificationFormProps) -> Element {
                                 }
                             };
 
+                            // Ask the relay to crawl the new PDS and watch the account show up
+                            let propagation_session = new_pds.session().clone();
+                            spawn(async move {
+                                run_propagation_monitor(&propagation_session, |report| {
+                                    dispatch.call(MigrationAction::SetPropagation(Some(report)))
+                                })
+                                .await;
+                            });
+
+                            // Look up the saved feeds and lists as the new account
+                            let saved_feeds_session = new_pds.session().clone();
+                            spawn(async move {
+                                match load_preferences_backup(&saved_feeds_session.did).await {
+                                    Ok(backup) => {
+                                        let report = check_saved_feeds(&saved_feeds_session, &backup.export).await;
+                                        dispatch.call(MigrationAction::SetSavedFeeds(Some(report)));
+                                    }
+                                    Err(e) => {
+                                        console_warn!("{}", format!("[Form4] No preferences backup to check saved feeds against: {}", e));
+                                    }
+                                }
+                            });
+
                             // Update migration progress
                             let mut migration_progress = current_state.migration_progress.clone();
                             migration_progress.new_account_activated = true;
                             dispatch.call(MigrationAction::SetMigrationProgress(migration_progress.clone()));
 
-                            // Step 20: Deactivate account on old PDS
-                            console_info!("[Form4] Step 20: Deactivating account on old PDS");
-                            dispatch.call(MigrationAction::SetMigrationStep("Deactivating account on old PDS...".to_string()));
-
-                            // Get old session again for deactivation
-                            let old_session_for_deactivation = match LocalStorageManager::get_old_session()
-                                .map_err(|_| "Failed to get old PDS session")
-                                .map(|session| session.into()) {
-                                Ok(session) => session,
-                                Err(error) => {
-                                    console_warn!("{}", format!("[Form4] Failed to get old session for deactivation: {}", error));
-                                    // This is not critical - migration is essentially complete
-                                    dispatch.call(MigrationAction::SetMigrationStep("Migration completed! (Note: Could not deactivate old account - please do this manually)".to_string()));
-                                    dispatch.call(MigrationAction::SetPlcVerifying(false));
-                                    return;
-                                }
-                            };
-
-                            match pds_client.deactivate_account(&old_session_for_deactivation).await {
-                                Ok(response) => {
-                                    if response.success {
-                                        console_info!("[Form4] Old account deactivated successfully");
-
-                                        // Update final migration progress
-                                        migration_progress.old_account_deactivated = true;
-                                        dispatch.call(MigrationAction::SetMigrationProgress(migration_progress));
-
-                                        dispatch.call(MigrationAction::SetMigrationStep("Migration completed successfully! Your account has been migrated to the new PDS.".to_string()));
-                                    } else {
-                                        let error_msg = response.message.clone();
-                                        console_warn!("{}", format!("[Form4] Old account deactivation failed: {}", error_msg));
-                                        dispatch.call(MigrationAction::SetMigrationStep(format!("Migration completed! New account activated, but old account deactivation failed: {}. Please deactivate it manually.", response.message)));
+                            // Step 20: Deactivate account on old PDS, unless the user chose to keep it active for a while
+                            let grace_days = current_state.form4.old_account_grace_days.and_then(|days| {
+                                match schedule_old_account_grace(&current_state, days) {
+                                    Ok(()) => Some(days),
+                                    Err(e) => {
+                                        console_warn!("{}", format!("[Form4] Could not keep a reminder for the old account, deactivating it instead: {}", e));
+                                        dispatch.call(MigrationAction::SetOldAccountGrace(None));
+                                        None
                                     }
                                 }
-                                Err(e) => {
-                                    console_warn!("{}", format!("[Form4] Old account deactivation client operation failed: {}", e));
-                                    dispatch.call(MigrationAction::SetMigrationStep("Migration completed! New account activated, but could not deactivate old account. Please deactivate it manually.".to_string()));
-                                }
-                            };
+                            });
+                            if let Some(days) = grace_days {
+                                console_info!("{}", format!("[Form4] Step 20: Leaving old account active for {} days", days));
+                                dispatch.call(MigrationAction::SetMigrationStep(format!("Migration completed! Your old account stays active for {} days.", days)));
+                            } else {
+                                console_info!("[Form4] Step 20: Deactivating account on old PDS");
+                                dispatch.call(MigrationAction::SetMigrationStep("Deactivating account on old PDS...".to_string()));
+
+                                match old_pds.deactivate_account().await {
+                                    Ok(response) => {
+                                        if response.success {
+                                            console_info!("[Form4] Old account deactivated successfully");
+
+                                            // Update final migration progress
+                                            migration_progress.old_account_deactivated = true;
+                                            dispatch.call(MigrationAction::SetMigrationProgress(migration_progress));
+
+                                            dispatch.call(MigrationAction::SetMigrationStep("Migration completed successfully! Your account has been migrated to the new PDS.".to_string()));
+                                        } else {
+                                            let error_msg = response.message.clone();
+                                            console_warn!("{}", format!("[Form4] Old account deactivation failed: {}", error_msg));
+                                            dispatch.call(MigrationAction::SetMigrationStep(format!("Migration completed! New account activated, but old account deactivation failed: {}. Please deactivate it manually.", response.message)));
+                                        }
+                                    }
+                                    Err(e) => {
+                                        console_warn!("{}", format!("[Form4] Old account deactivation client operation failed: {}", e));
+                                        dispatch.call(MigrationAction::SetMigrationStep("Migration completed! New account activated, but could not deactivate old account. Please deactivate it manually.".to_string()));
+                                    }
+                                };
+                            }
 
-                            console_info!("[MILESTONE] Form4 PLC operations completed successfully - timestamp: {}", js_sys::Date::now());
+                            console_info!("[MILESTONE] Form4 PLC operations completed successfully");
                             console_info!("[Form4] Migration process completed!");
 
                             // Complete migration state management with sequential dispatch and verification
@@ -279,43 +547,33 @@ pub fn PlcVerificationForm(props: PlcVerificationFormProps) -> Element {
                             dispatch.call(MigrationAction::SetPlcVerifying(false));
 
                             // Small delay between dispatches to prevent queue conflicts
-                            let dispatch_copy1 = dispatch;
-                            gloo_timers::callback::Timeout::new(10, move || {
-                                console_info!("[DISPATCH] About to call SetMigrationCompleted(true)");
-                                dispatch_copy1.call(MigrationAction::SetMigrationCompleted(true));
-                            }).forget();
-
-                            let dispatch_copy2 = dispatch;
-                            gloo_timers::callback::Timeout::new(20, move || {
-                                console_info!("[DISPATCH] About to call SetMigrating(false) - THIS IS CRITICAL");
-                                dispatch_copy2.call(MigrationAction::SetMigrating(false));
-                            }).forget();
-
-                            let dispatch_copy3 = dispatch;
-                            gloo_timers::callback::Timeout::new(30, move || {
-                                console_info!("[DISPATCH] About to call SetBlobProgress(default)");
-                                dispatch_copy3.call(MigrationAction::SetBlobProgress(BlobProgress::default()));
-                            }).forget();
-
-                            let dispatch_copy4 = dispatch;
-                            gloo_timers::callback::Timeout::new(40, move || {
-                                console_info!("[DISPATCH] About to call SetMigrationStep");
-                                dispatch_copy4.call(MigrationAction::SetMigrationStep("🎉 Migration completed successfully!".to_string()));
-                            }).forget();
+                            sleep_ms(10).await;
+                            console_info!("[DISPATCH] About to call SetMigrationCompleted(true)");
+                            dispatch.call(MigrationAction::SetMigrationCompleted(true));
+
+                            sleep_ms(10).await;
+                            console_info!("[DISPATCH] About to call SetMigrating(false) - THIS IS CRITICAL");
+                            dispatch.call(MigrationAction::SetMigrating(false));
+
+                            sleep_ms(10).await;
+                            console_info!("[DISPATCH] About to call SetBlobProgress(default)");
+                            dispatch.call(MigrationAction::SetBlobProgress(BlobProgress::default()));
+
+                            sleep_ms(10).await;
+                            console_info!("[DISPATCH] About to call SetMigrationStep");
+                            dispatch.call(MigrationAction::SetMigrationStep("🎉 Migration completed successfully!".to_string()));
 
                             // Verify state after all dispatches complete
-                            let state_copy = state;
-                            gloo_timers::callback::Timeout::new(100, move || {
-                                let final_state = state_copy();
-                                console_info!("[VERIFICATION] Final state verification - is_migrating={}, migration_completed={}, step='{}'",
-                                    final_state.is_migrating, final_state.migration_completed, final_state.migration_step);
-
-                                if final_state.is_migrating {
-                                    console_error!("[VERIFICATION] ERROR: is_migrating is still true after completion! This explains the frozen UI.");
-                                } else {
-                                    console_info!("[VERIFICATION] SUCCESS: is_migrating is now false, UI should update properly.");
-                                }
-                            }).forget();
+                            sleep_ms(60).await;
+                            let final_state = state();
+                            console_info!("[VERIFICATION] Final state verification - is_migrating={}, migration_completed={}, step='{}'",
+                                final_state.is_migrating, final_state.migration_completed, final_state.migration_step);
+
+                            if final_state.is_migrating {
+                                console_error!("[VERIFICATION] ERROR: is_migrating is still true after completion! This explains the frozen UI.");
+                            } else {
+                                console_info!("[VERIFICATION] SUCCESS: is_migrating is now false, UI should update properly.");
+                            }
 
                             console_info!("[STATE] Migration completion sequence initiated with sequential dispatches");
                         });
@@ -352,13 +610,20 @@ pub fn PlcVerificationForm(props: PlcVerificationFormProps) -> Element {
                                 class: "instructions-title",
                                 "📋 Post-Migration Steps"
                             }
+                            PostMigrationChecklist { state }
+                            EmailVerificationPanel { state, dispatch }
+                            AppPasswordRecreation { state }
                             ul {
                                 class: "general-steps",
-                                li { "Update your handle in any external applications" }
-                                li { "Verify your posts and follows are intact" }
-                                li { "Your old account has been deactivated" }
+                                if let Some(days) = state().form4.old_account_grace_days {
+                                    li { "Your old account stays active for {days} days. Services that still read from the old PDS may show stale data until you deactivate it; you'll be reminded here" }
+                                } else {
+                                    li { "Your old account has been deactivated" }
+                                }
                                 li { "If you see an invalid handle error, make a post/skeet with your new handle @{handle}" }
-                                li { "Re-enable 2FA (recommended)" }
+                                if let Some(handling) = &state().target_auto_activation {
+                                    li { "Compatibility note: {handling.compatibility_note()}" }
+                                }
                                 li { "Note: Invalid handle warnings may appear for ~20 minutes (this is normal)" }
                                 li {
                                     "If you have migration questions, reach out to "
@@ -372,6 +637,36 @@ pub fn PlcVerificationForm(props: PlcVerificationFormProps) -> Element {
                                 }
                             }
 
+                            if let Some(backup) = state().preferences_backup.clone() {
+                                PreferencesBackupDownload { backup }
+                            }
+                            if let Some(report) = state().preferences_round_trip.clone() {
+                                PreferencesRoundTripSummary { report }
+                            }
+
+                            if let Some(checksum) = state().repo_car_checksum.clone() {
+                                RepoCarChecksum { checksum }
+                            }
+                            if let Some(manifest) = state().blob_manifest.clone() {
+                                BlobManifestDownload { manifest }
+                            }
+                            {render_blob_integrity_audit(state().new_pds_session.clone())}
+                            if let Some(session) = state().new_pds_session.clone() {
+                                UploadLogDownload { did: session.did }
+                            }
+                            UnrecoverableBlobsReport { cids: state().unrecoverable_blobs.clone() }
+
+                            if let Some(report) = state().propagation.clone() {
+                                PropagationMonitor { report }
+                            }
+                            if let Some(report) = state().saved_feeds.clone() {
+                                SavedFeedsCheck { report }
+                            }
+
+                            if state().form4.old_account_grace_days.is_none() {
+                                {render_old_account_deletion_scheduler(state)}
+                            }
+
                             // Add PDS policy links if available
                             if let Some(describe_response) = &state().form2.describe_response {
                                 if let Some(links) = &describe_response.links {
@@ -416,7 +711,10 @@ pub fn PlcVerificationForm(props: PlcVerificationFormProps) -> Element {
                 } else if state().form4.is_verifying {
                     div {
                         class: "verification-progress",
-                        "{state().migration_step}"
+                        MigrationTimeline {
+                            entries: state().timeline(crate::utils::platform::now_ms() as u64),
+                            detail: state().migration_step,
+                        }
                     }
                 } else if let Some(error) = &state().migration_error {
                     div {
@@ -1,7 +1,14 @@
 //! This crate contains all shared UI components for the migration service.
 
 pub mod app;
-pub use app::MigrationService;
+pub use app::{
+    EmbedFeatures, MigrationLifecycleEvent, MigrationOutcome, MigrationService,
+    MigrationServiceProps, ThemeOverrides,
+};
+#[cfg(feature = "demo-mode")]
+pub use app::DemoMode;
+#[cfg(feature = "web")]
+pub use app::SelfTest;
 
 pub mod components;
 pub mod migration;
@@ -5,28 +5,53 @@
 
 #[cfg(feature = "web")]
 use crate::services::client::{
-    ClientAccountStatusResponse, ClientCreateAccountRequest, ClientSessionCredentials,
+    ClientAccountStatusResponse, ClientCreateAccountRequest, ClientError, ClientSessionCredentials,
     MigrationClient, PdsClient,
 };
+#[cfg(feature = "web")]
+use crate::services::config::{get_global_config, RetryPhase};
+#[cfg(feature = "web")]
+use crate::services::retry::with_retries_when;
+#[cfg(feature = "web")]
+use crate::services::streaming::{check_minimal_flow_fits, AccountSizeEstimate, MinimalFlowLimits};
+
+use crate::migration::types::TargetAutoActivation;
+use crate::{console_info, console_warn};
 
-use crate::console_info;
+/// Session for the new account, and whether this request created it rather
+/// than resuming one that already existed
+#[cfg(feature = "web")]
+pub struct CreatedAccount {
+    pub session: ClientSessionCredentials,
+    pub newly_created: bool,
+}
 
 /// NEWBOLD.md Step: goat account create --pds-host $NEWPDSHOST --existing-did $ACCOUNTDID --handle $NEWHANDLE --password $NEWPASSWORD --email $NEWEMAIL --invite-code $INVITECODE --service-auth $SERVICEAUTH (line 40-47)
 /// Create account using client-side operations (with fallback resumption logic)
 #[cfg(feature = "web")]
 pub async fn create_account_client_side(
     migration_client: &MigrationClient,
+    pds_url: &str,
     request: ClientCreateAccountRequest,
-) -> Result<ClientSessionCredentials, String> {
+) -> Result<CreatedAccount, String> {
     // Implements: goat account create --pds-host $NEWPDSHOST --existing-did $ACCOUNTDID --handle $NEWHANDLE --password $NEWPASSWORD --email $NEWEMAIL --invite-code $INVITECODE --service-auth $SERVICEAUTH
-    match migration_client
-        .create_account_new_pds(request.clone())
-        .await
-    {
+    // Only network failures and 5xx responses are retried. If the account was
+    // created but the response was lost, the retry gets AlreadyExists: resumed
+    // below when the PDS returns a session, reported otherwise
+    let retry = get_global_config().retry;
+    let created = with_retries_when(&retry, RetryPhase::Auth, ClientError::is_transient, || {
+        migration_client.create_account_new_pds(pds_url, request.clone())
+    })
+    .await;
+    match created {
         Ok(response) => {
             if response.success {
                 response
                     .session
+                    .map(|session| CreatedAccount {
+                        session,
+                        newly_created: true,
+                    })
                     .ok_or_else(|| "No session returned from account creation".to_string())
             } else if response.resumable
                 && response
@@ -43,7 +68,10 @@ pub async fn create_account_client_side(
                 // Even if account creation "failed" due to AlreadyExists, check if we got session credentials
                 if let Some(session) = response.session {
                     console_info!("[Migration] AlreadyExists response included session credentials for existing account");
-                    Ok(session)
+                    Ok(CreatedAccount {
+                        session,
+                        newly_created: false,
+                    })
                 } else {
                     // True failure - no session provided for existing account
                     Err(format!("Account creation failed with AlreadyExists but no session provided for resumption: {}", response.message))
@@ -73,3 +101,47 @@ pub async fn check_account_status_client_side(
         Err(error) => Err(format!("Account status check failed: {}", error)),
     }
 }
+
+/// Deactivate an account the target PDS activated at creation, so content isn't
+/// served from it before the PLC update points the DID here
+#[cfg(feature = "web")]
+pub async fn handle_target_auto_activation(
+    session: &ClientSessionCredentials,
+) -> TargetAutoActivation {
+    console_warn!("[Migration] Target PDS activated the account on creation; deactivating it until the PLC update");
+    let reason = match PdsClient::new().deactivate_account(session).await {
+        Ok(response) if response.success => {
+            console_info!("[Migration] Auto-activated account deactivated");
+            return TargetAutoActivation::Deactivated;
+        }
+        Ok(response) => response.message,
+        Err(error) => error.to_string(),
+    };
+    console_warn!(
+        "[Migration] Could not deactivate auto-activated account ({}); continuing with it active",
+        reason
+    );
+    TargetAutoActivation::LeftActive { reason }
+}
+
+/// Up-front size check for browsers limited to the minimal storage flow, run
+/// before anything is created on the target
+#[cfg(feature = "web")]
+pub async fn check_minimal_flow_capacity(
+    old_session: &ClientSessionCredentials,
+) -> Result<(), String> {
+    let status = check_account_status_client_side(old_session)
+        .await
+        .map_err(|e| format!("Could not estimate the account size: {}", e))?;
+    let estimate = AccountSizeEstimate {
+        repo_blocks: status.repo_blocks.unwrap_or_default().max(0) as u64,
+        blobs: status.expected_blobs.unwrap_or_default().max(0) as u64,
+    };
+    console_info!(
+        "[Migration] Minimal flow estimate: {} blocks, {} blobs, ~{} bytes",
+        estimate.repo_blocks,
+        estimate.blobs,
+        estimate.total_bytes()
+    );
+    check_minimal_flow_fits(&estimate, &MinimalFlowLimits::default())
+}
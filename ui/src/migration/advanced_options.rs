@@ -0,0 +1,292 @@
+//! Settings shown behind the "Advanced options" disclosure
+//!
+//! Most people should migrate with the defaults, so the migration form keeps
+//! the tuning knobs folded away. Each knob is described here as data: its
+//! choices, how risky each choice is, and a warning the user has to confirm
+//! before a risky one takes effect. The `AdvancedSection` component renders
+//! whatever this module returns, so adding a setting means adding an entry
+//! here and a case in [`advanced_choice_action`].
+
+use crate::migration::plan::PlanVariant;
+use crate::migration::types::{MigrationAction, MigrationState};
+use crate::services::config::{
+    BlobEnumerationMethod, BlobNotFoundPolicy, VerificationLevel, BLOB_RETRY_CHOICES,
+};
+
+/// How much a choice can hurt if the user doesn't know what it does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RiskLevel {
+    Safe,
+    /// Slower, or changes what the migration does, but loses nothing
+    Caution,
+    /// Can leave the new account incomplete after the identity moves
+    Risky,
+}
+
+impl RiskLevel {
+    pub fn badge(&self) -> &'static str {
+        match self {
+            RiskLevel::Safe => "Safe",
+            RiskLevel::Caution => "Caution",
+            RiskLevel::Risky => "Risky",
+        }
+    }
+
+    pub fn css_class(&self) -> &'static str {
+        match self {
+            RiskLevel::Safe => "risk-badge safe",
+            RiskLevel::Caution => "risk-badge caution",
+            RiskLevel::Risky => "risk-badge risky",
+        }
+    }
+}
+
+/// One value an advanced setting can take
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdvancedChoice {
+    pub value: String,
+    pub label: String,
+    pub risk: RiskLevel,
+    /// Shown for confirmation before a risky choice is applied
+    pub warning: Option<&'static str>,
+}
+
+impl AdvancedChoice {
+    fn new(value: impl Into<String>, label: impl Into<String>) -> Self {
+        Self {
+            value: value.into(),
+            label: label.into(),
+            risk: RiskLevel::Safe,
+            warning: None,
+        }
+    }
+
+    fn risk(mut self, risk: RiskLevel, warning: &'static str) -> Self {
+        self.risk = risk;
+        self.warning = Some(warning);
+        self
+    }
+
+    /// Risky choices must be confirmed before they are applied
+    pub fn needs_confirmation(&self) -> bool {
+        self.risk == RiskLevel::Risky && self.warning.is_some()
+    }
+}
+
+/// One setting in the advanced section
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdvancedOption {
+    pub id: &'static str,
+    pub label: &'static str,
+    pub help: Option<String>,
+    pub choices: Vec<AdvancedChoice>,
+    pub selected: String,
+    pub default: String,
+}
+
+impl AdvancedOption {
+    pub fn selected_choice(&self) -> Option<&AdvancedChoice> {
+        self.choices
+            .iter()
+            .find(|choice| choice.value == self.selected)
+    }
+
+    pub fn is_changed(&self) -> bool {
+        self.selected != self.default
+    }
+}
+
+/// Highest risk among the current selections, for the collapsed header
+pub fn highest_selected_risk(options: &[AdvancedOption]) -> RiskLevel {
+    options
+        .iter()
+        .filter_map(|option| option.selected_choice().map(|choice| choice.risk))
+        .max()
+        .unwrap_or(RiskLevel::Safe)
+}
+
+/// The advanced settings of the migration form for the current state
+pub fn migration_advanced_options(state: &MigrationState) -> Vec<AdvancedOption> {
+    let configured = MigrationState::default();
+    vec![
+        AdvancedOption {
+            id: "verification",
+            label: "Verification before switching",
+            help: None,
+            choices: VerificationLevel::ALL
+                .into_iter()
+                .filter(|level| level.is_available())
+                .map(|level| {
+                    let choice = AdvancedChoice::new(level.as_str(), level.label());
+                    match level {
+                        VerificationLevel::None => choice.risk(
+                            RiskLevel::Risky,
+                            "Nothing is checked before your identity moves. Missing posts or media would only show up after the switch, when the old account is already deactivated.",
+                        ),
+                        VerificationLevel::FullRehash => choice.risk(
+                            RiskLevel::Caution,
+                            "Downloads every blob again from the new PDS; large accounts take much longer.",
+                        ),
+                        _ => choice,
+                    }
+                })
+                .collect(),
+            selected: state.verification_level().as_str().to_string(),
+            default: configured.verification_level().as_str().to_string(),
+        },
+        AdvancedOption {
+            id: "blob-enumeration",
+            label: "Finding blobs to transfer",
+            help: Some(state.blob_enumeration_method().explanation().to_string()),
+            choices: BlobEnumerationMethod::ALL
+                .into_iter()
+                .map(|method| {
+                    let choice = AdvancedChoice::new(method.as_str(), method.label());
+                    match method {
+                        BlobEnumerationMethod::MissingBlobs => choice.risk(
+                            RiskLevel::Caution,
+                            "Relies on the new PDS's own list of missing media.",
+                        ),
+                        _ => choice,
+                    }
+                })
+                .collect(),
+            selected: state.blob_enumeration_method().as_str().to_string(),
+            default: configured.blob_enumeration_method().as_str().to_string(),
+        },
+        AdvancedOption {
+            id: "blob-retries",
+            label: "Retries per blob",
+            help: Some(
+                "Raise this on a flaky connection. It only affects image and video transfers; identity changes are never retried more."
+                    .to_string(),
+            ),
+            choices: BLOB_RETRY_CHOICES
+                .into_iter()
+                .map(|retries| {
+                    let choice = AdvancedChoice::new(retries.to_string(), retries.to_string());
+                    if retries == 1 {
+                        choice.risk(
+                            RiskLevel::Caution,
+                            "A single network hiccup fails the blob.",
+                        )
+                    } else {
+                        choice
+                    }
+                })
+                .collect(),
+            selected: state
+                .retry_config()
+                .blob_download
+                .max_retries
+                .to_string(),
+            default: configured
+                .retry_config()
+                .blob_download
+                .max_retries
+                .to_string(),
+        },
+        AdvancedOption {
+            id: "blob-not-found",
+            label: "Media the old PDS has lost",
+            help: Some(
+                "Posts can still point at images or videos the old PDS no longer has. No retry brings those back, so by default they are skipped and listed at the end."
+                    .to_string(),
+            ),
+            choices: BlobNotFoundPolicy::ALL
+                .into_iter()
+                .map(|policy| {
+                    let choice = AdvancedChoice::new(policy.as_str(), policy.label());
+                    match policy {
+                        BlobNotFoundPolicy::Fail => choice.risk(
+                            RiskLevel::Caution,
+                            "A single image the old PDS already lost stops the migration.",
+                        ),
+                        _ => choice,
+                    }
+                })
+                .collect(),
+            selected: state.blob_not_found_policy().as_str().to_string(),
+            default: configured.blob_not_found_policy().as_str().to_string(),
+        },
+        AdvancedOption {
+            id: "plan",
+            label: "Migration plan",
+            help: None,
+            choices: PlanVariant::ALL
+                .into_iter()
+                .map(|variant| {
+                    let choice = AdvancedChoice::new(variant.as_str(), variant.label());
+                    match variant {
+                        PlanVariant::Emergency => choice.risk(
+                            RiskLevel::Risky,
+                            "Skips every verification pass and moves your identity even if some media or preferences failed to copy. Only use this when the old PDS is about to disappear.",
+                        ),
+                        PlanVariant::ContentCopy => choice.risk(
+                            RiskLevel::Caution,
+                            "Your identity does not move; followers stay with the old account.",
+                        ),
+                        _ => choice,
+                    }
+                })
+                .collect(),
+            selected: state.form3.plan_variant.as_str().to_string(),
+            default: configured.form3.plan_variant.as_str().to_string(),
+        },
+    ]
+}
+
+/// The state change for choosing `value` in the setting `id`
+pub fn advanced_choice_action(id: &str, value: &str) -> Option<MigrationAction> {
+    match id {
+        "verification" => {
+            VerificationLevel::parse(value).map(MigrationAction::SetVerificationLevel)
+        }
+        "blob-enumeration" => {
+            BlobEnumerationMethod::parse(value).map(MigrationAction::SetBlobEnumerationMethod)
+        }
+        "blob-retries" => value.parse().ok().map(MigrationAction::SetBlobRetries),
+        "blob-not-found" => {
+            BlobNotFoundPolicy::parse(value).map(MigrationAction::SetBlobNotFoundPolicy)
+        }
+        "plan" => PlanVariant::parse(value).map(MigrationAction::SetPlanVariant),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_advanced_options_flag_risky_choices() {
+        let mut state = MigrationState::default();
+        let options = migration_advanced_options(&state);
+        assert!(options.iter().all(|option| !option.is_changed()));
+        assert_eq!(highest_selected_risk(&options), RiskLevel::Safe);
+
+        let verification = &options[0];
+        let none = verification
+            .choices
+            .iter()
+            .find(|choice| choice.value == "none")
+            .unwrap();
+        assert!(none.needs_confirmation());
+
+        for option in &options {
+            for choice in &option.choices {
+                let action = advanced_choice_action(option.id, &choice.value)
+                    .unwrap_or_else(|| panic!("{} has no action for {}", option.id, choice.value));
+                state.reduce_in_place(action);
+            }
+        }
+        // Last choices: full re-hash and the content-copy plan
+        let options = migration_advanced_options(&state);
+        assert!(options.iter().any(AdvancedOption::is_changed));
+        assert_eq!(highest_selected_risk(&options), RiskLevel::Caution);
+
+        state.reduce_in_place(advanced_choice_action("plan", "emergency").unwrap());
+        let options = migration_advanced_options(&state);
+        assert_eq!(highest_selected_risk(&options), RiskLevel::Risky);
+    }
+}
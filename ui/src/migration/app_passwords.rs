@@ -0,0 +1,109 @@
+//! Re-creating app passwords on the new PDS
+//!
+//! App passwords belong to the PDS that issued them, so every client and bot
+//! logged in with one stops working once the old account is deactivated. While
+//! the old account is still active, an optional plan step records the names
+//! (never the secrets, which a PDS doesn't return after creation) of its app
+//! passwords. After the identity update the user can create equivalently named
+//! ones on the new PDS; the new secrets are shown once and not kept anywhere.
+
+use async_trait::async_trait;
+
+use crate::console_info;
+use crate::migration::plan::PlanStep;
+use crate::migration::steps::traits::{MigrationStep, StepContext};
+use crate::migration::types::MigrationAction;
+use crate::services::client::ClientAppPassword;
+
+/// App passwords from the old account still missing on the new one, by name
+pub fn passwords_to_recreate<'a>(
+    old: &'a [ClientAppPassword],
+    created: &[String],
+) -> Vec<&'a ClientAppPassword> {
+    old.iter()
+        .filter(|password| !created.contains(&password.name))
+        .collect()
+}
+
+/// Records the names of the old account's app passwords
+pub struct AppPasswordInventoryStep;
+
+#[async_trait(?Send)]
+impl MigrationStep for AppPasswordInventoryStep {
+    fn plan_step(&self) -> PlanStep {
+        PlanStep::AppPasswordInventory
+    }
+
+    fn describe(&self) -> &'static str {
+        "List the old account's app passwords so they can be re-created"
+    }
+
+    async fn execute(&self, ctx: &mut StepContext<'_>) -> Result<(), String> {
+        let passwords = ctx
+            .old
+            .list_app_passwords()
+            .await
+            .map_err(|e| format!("Could not list app passwords: {}", e))?;
+        console_info!(
+            "[Migration] Old account has {} app password(s)",
+            passwords.len()
+        );
+        (ctx.dispatch)(MigrationAction::SetAppPasswords(Some(passwords)));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migration::steps::mock_pds::{session, MockPds};
+    use crate::migration::types::MigrationState;
+    use crate::services::client::{OldPds, PdsApi};
+    use std::cell::RefCell;
+
+    fn app_password(name: &str, privileged: bool) -> ClientAppPassword {
+        ClientAppPassword {
+            name: name.to_string(),
+            privileged,
+        }
+    }
+
+    #[test]
+    fn test_inventory_records_names_and_skips_created_ones() {
+        let pds = MockPds {
+            app_passwords: Some(vec![
+                app_password("Graysky", false),
+                app_password("dm-bot", true),
+            ]),
+            ..MockPds::default()
+        };
+        let state = MigrationState::default();
+        let actions = RefCell::new(Vec::new());
+        let dispatch = |action: MigrationAction| actions.borrow_mut().push(action);
+        let api: &dyn PdsApi = &pds;
+        let mut ctx = StepContext {
+            old: OldPds::new(api, session("https://old.example")),
+            new: None,
+            state: &state,
+            dispatch: &dispatch,
+            handler: None,
+            preferences: None,
+        };
+
+        let result = futures::executor::block_on(AppPasswordInventoryStep.execute(&mut ctx));
+        assert_eq!(result, Ok(()));
+        let passwords = match actions.borrow_mut().pop() {
+            Some(MigrationAction::SetAppPasswords(Some(passwords))) => passwords,
+            other => panic!("expected the app password list, got {:?}", other),
+        };
+        assert_eq!(passwords.len(), 2);
+
+        let remaining = passwords_to_recreate(&passwords, &["Graysky".to_string()]);
+        assert_eq!(remaining, vec![&app_password("dm-bot", true)]);
+
+        let failing = MockPds::default();
+        let api: &dyn PdsApi = &failing;
+        ctx.old = OldPds::new(api, session("https://old.example"));
+        assert!(futures::executor::block_on(AppPasswordInventoryStep.execute(&mut ctx)).is_err());
+    }
+}
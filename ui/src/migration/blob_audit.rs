@@ -0,0 +1,211 @@
+//! Spot-checking migrated blobs against the checksum manifest
+//!
+//! Re-downloading every blob to prove it arrived intact costs as much as the
+//! transfer itself. Instead a random sample of the manifest is fetched back
+//! from the new PDS with `getBlob`, re-hashed, and compared to the SHA-256
+//! recorded when it was uploaded. If none of `n` sampled blobs is damaged, the
+//! damaged share of the whole set is below `1 - 0.05^(1/n)` with 95%
+//! confidence (about 3/n), which is what the report states. Needs the
+//! `hash-verification` feature for hashing.
+
+use cid::Cid;
+use sha2::{Digest, Sha256};
+use std::str::FromStr;
+
+use crate::console_info;
+use crate::migration::blob_manifest::{BlobManifest, BlobManifestEntry};
+use crate::services::client::{ClientSessionCredentials, PdsClient};
+use crate::utils::platform::random_f64;
+
+/// Sample size offered first
+pub const DEFAULT_AUDIT_SAMPLE_SIZE: usize = 20;
+
+/// Sample sizes the user can choose from
+pub const AUDIT_SAMPLE_CHOICES: [usize; 4] = [10, 20, 50, 100];
+
+/// What re-downloading one sampled blob showed
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlobAuditOutcome {
+    Matches,
+    /// The new PDS served different bytes than were uploaded
+    Mismatch {
+        expected: String,
+        actual: String,
+    },
+    /// The blob couldn't be downloaded from the new PDS
+    Unreadable(String),
+}
+
+/// Result of auditing a sample of the manifest
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BlobAuditReport {
+    /// Blobs in the manifest
+    pub population: usize,
+    pub sampled: usize,
+    pub matched: usize,
+    pub mismatched: Vec<String>,
+    /// CID and error for blobs that couldn't be downloaded
+    pub unreadable: Vec<(String, String)>,
+}
+
+impl BlobAuditReport {
+    pub fn record(&mut self, cid: &str, outcome: BlobAuditOutcome) {
+        self.sampled += 1;
+        match outcome {
+            BlobAuditOutcome::Matches => self.matched += 1,
+            BlobAuditOutcome::Mismatch { .. } => self.mismatched.push(cid.to_string()),
+            BlobAuditOutcome::Unreadable(error) => self.unreadable.push((cid.to_string(), error)),
+        }
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.mismatched.is_empty() && self.unreadable.is_empty()
+    }
+
+    /// Upper bound on the damaged share of all blobs at 95% confidence, for a
+    /// clean sample; zero when every blob was checked
+    pub fn max_damaged_fraction(&self) -> Option<f64> {
+        if !self.is_clean() || self.sampled == 0 {
+            return None;
+        }
+        if self.sampled >= self.population {
+            return Some(0.0);
+        }
+        Some(1.0 - 0.05f64.powf(1.0 / self.sampled as f64))
+    }
+}
+
+/// Pick up to `n` distinct entries, uniformly, using `random` for values in `[0, 1)`
+pub fn sample_entries(
+    blobs: &[BlobManifestEntry],
+    n: usize,
+    mut random: impl FnMut() -> f64,
+) -> Vec<BlobManifestEntry> {
+    // Partial Fisher-Yates over indices, so the manifest itself is untouched
+    let mut indices: Vec<usize> = (0..blobs.len()).collect();
+    let n = n.min(blobs.len());
+    for i in 0..n {
+        let remaining = indices.len() - i;
+        let j = i + ((random() * remaining as f64) as usize).min(remaining - 1);
+        indices.swap(i, j);
+    }
+    indices[..n].iter().map(|&i| blobs[i].clone()).collect()
+}
+
+/// Compare downloaded bytes with the hash recorded at upload
+pub fn check_blob(entry: &BlobManifestEntry, data: &[u8]) -> BlobAuditOutcome {
+    let actual: String = Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    if actual.eq_ignore_ascii_case(&entry.sha256) {
+        BlobAuditOutcome::Matches
+    } else {
+        BlobAuditOutcome::Mismatch {
+            expected: entry.sha256.clone(),
+            actual,
+        }
+    }
+}
+
+async fn audit_entry(
+    client: &PdsClient,
+    session: &ClientSessionCredentials,
+    entry: &BlobManifestEntry,
+) -> BlobAuditOutcome {
+    let cid = match Cid::from_str(&entry.cid) {
+        Ok(cid) => cid,
+        Err(e) => return BlobAuditOutcome::Unreadable(format!("invalid CID: {}", e)),
+    };
+    match client.export_blob(session, &cid).await {
+        Ok(response) => match response.blob_data {
+            Some(data) if response.success => check_blob(entry, &data),
+            _ => BlobAuditOutcome::Unreadable(response.message),
+        },
+        Err(e) => BlobAuditOutcome::Unreadable(e.to_string()),
+    }
+}
+
+/// Download a random sample of the manifest from the new PDS and re-hash it,
+/// reporting progress as (checked, sample size)
+pub async fn audit_blob_sample(
+    session: &ClientSessionCredentials,
+    manifest: &BlobManifest,
+    sample_size: usize,
+    mut on_progress: impl FnMut(usize, usize),
+) -> BlobAuditReport {
+    let client = PdsClient::new();
+    let sample = sample_entries(&manifest.blobs, sample_size, random_f64);
+    let mut report = BlobAuditReport {
+        population: manifest.blobs.len(),
+        ..BlobAuditReport::default()
+    };
+
+    for entry in &sample {
+        on_progress(report.sampled, sample.len());
+        let outcome = audit_entry(&client, session, entry).await;
+        report.record(&entry.cid, outcome);
+    }
+    on_progress(report.sampled, sample.len());
+
+    console_info!(
+        "[Audit] {} of {} sampled blobs matched the manifest ({} in total)",
+        report.matched,
+        report.sampled,
+        report.population
+    );
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(cid: &str, data: &[u8]) -> BlobManifestEntry {
+        BlobManifestEntry {
+            cid: cid.to_string(),
+            size: data.len() as u64,
+            sha256: Sha256::digest(data)
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect(),
+            url: String::new(),
+            cid_matches: true,
+        }
+    }
+
+    #[test]
+    fn test_sample_check_and_confidence() {
+        let blobs: Vec<_> = (0..10)
+            .map(|i| entry(&format!("cid{}", i), format!("blob {}", i).as_bytes()))
+            .collect();
+
+        let sample = sample_entries(&blobs, 4, || 0.999);
+        assert_eq!(sample.len(), 4);
+        let mut cids: Vec<_> = sample.iter().map(|blob| blob.cid.clone()).collect();
+        cids.sort();
+        cids.dedup();
+        assert_eq!(cids.len(), 4);
+        assert_eq!(sample_entries(&blobs, 50, || 0.0).len(), 10);
+
+        assert_eq!(check_blob(&blobs[0], b"blob 0"), BlobAuditOutcome::Matches);
+        assert!(matches!(
+            check_blob(&blobs[0], b"blob 1"),
+            BlobAuditOutcome::Mismatch { .. }
+        ));
+
+        let mut report = BlobAuditReport {
+            population: 1_000,
+            ..BlobAuditReport::default()
+        };
+        for blob in blobs.iter().take(3) {
+            report.record(&blob.cid, BlobAuditOutcome::Matches);
+        }
+        let bound = report.max_damaged_fraction().unwrap();
+        assert!(bound > 0.6 && bound < 0.64);
+
+        report.record("cid9", BlobAuditOutcome::Unreadable("404".to_string()));
+        assert!(!report.is_clean());
+        assert_eq!(report.max_damaged_fraction(), None);
+    }
+}
@@ -0,0 +1,203 @@
+//! Checksum manifest of the blobs uploaded to the new PDS
+//!
+//! While blobs stream, the bytes handed to `uploadBlob` are hashed in the
+//! browser and recorded with their CID, size and `getBlob` URL on the new PDS.
+//! The resulting manifest can be downloaded so archivists can check their
+//! media later with ordinary tools (`sha256sum`) without trusting either PDS.
+//! Blobs that were already on the new PDS are never downloaded, so only the
+//! ones uploaded in this session appear. Hashing needs the
+//! `hash-verification` feature; without it nothing is recorded.
+//!
+//! Like the preferences backup, the manifest itself stays out of
+//! [`MigrationState`](super::MigrationState); state only carries
+//! [`BlobManifestInfo`].
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::services::client::types::current_time_secs;
+
+/// Current manifest file schema
+pub const BLOB_MANIFEST_VERSION: u32 = 1;
+
+/// Blobs recorded for the account currently being migrated
+static RECORDER: Mutex<Option<ManifestRecorder>> = Mutex::new(None);
+
+struct ManifestRecorder {
+    did: String,
+    pds: String,
+    entries: BTreeMap<String, BlobManifestEntry>,
+}
+
+/// One uploaded blob
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlobManifestEntry {
+    pub cid: String,
+    pub size: u64,
+    /// Hex sha256 of the uploaded bytes
+    pub sha256: String,
+    /// `com.atproto.sync.getBlob` URL on the new PDS
+    pub url: String,
+    /// Whether the bytes hash to the digest in the CID
+    pub cid_matches: bool,
+}
+
+/// Downloadable manifest document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlobManifest {
+    pub version: u32,
+    pub generated_at_secs: u64,
+    pub did: String,
+    pub pds: String,
+    pub blobs: Vec<BlobManifestEntry>,
+}
+
+/// What the UI needs to know about the manifest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlobManifestInfo {
+    pub blob_count: usize,
+    pub total_bytes: u64,
+    /// Blobs whose bytes didn't match their CID
+    pub mismatched: usize,
+}
+
+impl BlobManifest {
+    pub fn file_name(&self) -> String {
+        format!(
+            "blobs-{}-{}.json",
+            self.did.replace(':', "_"),
+            self.generated_at_secs
+        )
+    }
+
+    pub fn info(&self) -> BlobManifestInfo {
+        BlobManifestInfo {
+            blob_count: self.blobs.len(),
+            total_bytes: self.blobs.iter().map(|blob| blob.size).sum(),
+            mismatched: self.blobs.iter().filter(|blob| !blob.cid_matches).count(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// `getBlob` URL for a blob on the given PDS
+pub fn blob_url(pds: &str, did: &str, cid: &str) -> String {
+    format!(
+        "{}/xrpc/com.atproto.sync.getBlob?did={}&cid={}",
+        pds.trim_end_matches('/'),
+        did,
+        cid
+    )
+}
+
+/// Start recording for an account; keeps earlier entries when the same account
+/// and PDS are already being recorded, so verification re-uploads add to them
+pub fn begin_blob_manifest(did: &str, pds: &str) {
+    if let Ok(mut recorder) = RECORDER.lock() {
+        let same_target = recorder
+            .as_ref()
+            .is_some_and(|current| current.did == did && current.pds == pds);
+        if !same_target {
+            *recorder = Some(ManifestRecorder {
+                did: did.to_string(),
+                pds: pds.to_string(),
+                entries: BTreeMap::new(),
+            });
+        }
+    }
+}
+
+/// Hash and record bytes that were just uploaded
+#[cfg(feature = "hash-verification")]
+pub fn record_uploaded_blob(cid: &str, data: &[u8]) {
+    use crate::migration::integrity::verify_blob_bytes;
+    use sha2::{Digest, Sha256};
+    use std::str::FromStr;
+
+    let sha256: String = Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    let cid_matches = cid::Cid::from_str(cid)
+        .map(|parsed| verify_blob_bytes(&parsed, data).is_ok())
+        .unwrap_or(false);
+    if !cid_matches {
+        crate::console_warn!("[Manifest] Uploaded bytes for {} do not match the CID", cid);
+    }
+
+    if let Ok(mut recorder) = RECORDER.lock() {
+        if let Some(recorder) = recorder.as_mut() {
+            let entry = BlobManifestEntry {
+                cid: cid.to_string(),
+                size: data.len() as u64,
+                sha256,
+                url: blob_url(&recorder.pds, &recorder.did, cid),
+                cid_matches,
+            };
+            recorder.entries.insert(cid.to_string(), entry);
+        }
+    }
+}
+
+#[cfg(not(feature = "hash-verification"))]
+pub fn record_uploaded_blob(_cid: &str, _data: &[u8]) {}
+
+/// Snapshot of everything recorded so far, if any blob was uploaded
+pub fn last_blob_manifest() -> Option<BlobManifest> {
+    let recorder = RECORDER.lock().ok()?;
+    let recorder = recorder.as_ref()?;
+    if recorder.entries.is_empty() {
+        return None;
+    }
+    Some(BlobManifest {
+        version: BLOB_MANIFEST_VERSION,
+        generated_at_secs: current_time_secs(),
+        did: recorder.did.clone(),
+        pds: recorder.pds.clone(),
+        blobs: recorder.entries.values().cloned().collect(),
+    })
+}
+
+#[cfg(all(test, feature = "hash-verification"))]
+mod tests {
+    use super::*;
+    use cid::multihash::Multihash;
+    use cid::Cid;
+    use sha2::{Digest, Sha256};
+
+    #[test]
+    fn test_manifest_records_hash_size_and_url() {
+        const RAW: u64 = 0x55;
+        let data = b"a picture of a cat";
+        let digest = Sha256::digest(data);
+        let cid = Cid::new_v1(RAW, Multihash::wrap(0x12, &digest).unwrap()).to_string();
+
+        begin_blob_manifest("did:plc:manifest", "https://new.example/");
+        record_uploaded_blob(&cid, data);
+        record_uploaded_blob("bafkreinotarealcid", b"other bytes");
+        // Re-uploading the same CID replaces its entry rather than duplicating it
+        record_uploaded_blob(&cid, data);
+
+        let manifest = last_blob_manifest().unwrap();
+        assert_eq!(manifest.blobs.len(), 2);
+        let entry = manifest.blobs.iter().find(|blob| blob.cid == cid).unwrap();
+        assert_eq!(entry.size, data.len() as u64);
+        assert_eq!(entry.sha256.len(), 64);
+        assert!(entry.cid_matches);
+        assert_eq!(
+            entry.url,
+            format!(
+                "https://new.example/xrpc/com.atproto.sync.getBlob?did=did:plc:manifest&cid={}",
+                cid
+            )
+        );
+        assert_eq!(manifest.info().mismatched, 1);
+        assert!(manifest.file_name().starts_with("blobs-did_plc_manifest-"));
+    }
+}
@@ -0,0 +1,374 @@
+//! Copying content into an account that already exists on the new PDS
+//!
+//! Some people create an account on the new PDS before they find this tool,
+//! so the target already has a DID of its own and `createAccount` with the old
+//! DID is no longer possible. The content-copy plan logs into that account
+//! instead and writes every record from the old repository into it with
+//! `putRecord`, uploading the blobs they reference first.
+//!
+//! This is not a migration: the identity stays where it is. Followers keep
+//! following the old DID, mentions and replies by others still point at the
+//! old account, and the old account keeps working. Where a record mentions the
+//! old DID (AT URIs, mention facets, self-replies), it is rewritten to the new
+//! one so threads stay connected inside the copy; strong references still
+//! carry the old record's CID, which the AppView tolerates for display. Post
+//! text is never touched, since facets index into it by byte offset.
+
+use async_trait::async_trait;
+use cid::Cid;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+
+use crate::console_info;
+use crate::console_warn;
+use crate::migration::plan::PlanStep;
+use crate::migration::steps::traits::{MigrationStep, StepContext};
+use crate::migration::types::MigrationAction;
+
+/// Failures listed in the report; the count keeps going past this
+pub const MAX_REPORTED_FAILURES: usize = 20;
+
+/// What the content copy wrote into the existing account
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContentCopyReport {
+    pub records_copied: u32,
+    pub records_failed: u32,
+    pub blobs_copied: u32,
+    /// First failures as `at://` URI and reason
+    pub failures: Vec<String>,
+}
+
+impl ContentCopyReport {
+    fn fail(&mut self, uri: &str, reason: impl std::fmt::Display) {
+        self.records_failed += 1;
+        if self.failures.len() < MAX_REPORTED_FAILURES {
+            self.failures.push(format!("{}: {}", uri, reason));
+        }
+    }
+}
+
+/// Record key of an `at://did/collection/rkey` URI
+pub fn rkey_of(uri: &str) -> Option<&str> {
+    uri.strip_prefix("at://")?
+        .splitn(3, '/')
+        .nth(2)
+        .filter(|rkey| !rkey.is_empty() && !rkey.contains('/'))
+}
+
+/// Replace the old DID with the new one wherever a record uses it as a whole
+/// value: fields equal to the DID and the authority of `at://` URIs. `text`
+/// fields are skipped so facet byte offsets keep matching.
+pub fn rewrite_did(value: &mut Value, old_did: &str, new_did: &str) {
+    match value {
+        Value::String(s) => {
+            if let Some(rewritten) = rewritten_did(s, old_did, new_did) {
+                *s = rewritten;
+            }
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| rewrite_did(item, old_did, new_did)),
+        Value::Object(map) => map
+            .iter_mut()
+            .filter(|(key, _)| key.as_str() != "text")
+            .for_each(|(_, item)| rewrite_did(item, old_did, new_did)),
+        _ => {}
+    }
+}
+
+fn rewritten_did(s: &str, old_did: &str, new_did: &str) -> Option<String> {
+    if s == old_did {
+        return Some(new_did.to_string());
+    }
+    // The authority ends at the path, query or fragment, so a longer DID
+    // sharing the prefix doesn't match
+    let rest = s.strip_prefix("at://")?.strip_prefix(old_did)?;
+    if rest.is_empty() || rest.starts_with(['/', '?', '#']) {
+        Some(format!("at://{}{}", new_did, rest))
+    } else {
+        None
+    }
+}
+
+/// CIDs of every `{"$type": "blob", "ref": {"$link": …}}` in a record
+pub fn blob_refs(value: &Value) -> Vec<String> {
+    let mut refs = Vec::new();
+    collect_blob_refs(value, &mut refs);
+    refs
+}
+
+fn collect_blob_refs(value: &Value, refs: &mut Vec<String>) {
+    match value {
+        Value::Array(items) => items.iter().for_each(|item| collect_blob_refs(item, refs)),
+        Value::Object(map) => {
+            if map.get("$type").and_then(Value::as_str) == Some("blob") {
+                if let Some(link) = map
+                    .get("ref")
+                    .and_then(|r| r.get("$link"))
+                    .and_then(Value::as_str)
+                {
+                    refs.push(link.to_string());
+                }
+            }
+            map.values().for_each(|item| collect_blob_refs(item, refs));
+        }
+        _ => {}
+    }
+}
+
+/// Writes the old repository's records and blobs into the existing account
+pub struct ContentCopyStep;
+
+impl ContentCopyStep {
+    /// Copy a blob unless an earlier record already did
+    async fn copy_blob(
+        ctx: &StepContext<'_>,
+        cid: &str,
+        copied: &mut HashSet<String>,
+    ) -> Result<bool, String> {
+        if copied.contains(cid) {
+            return Ok(false);
+        }
+        let parsed = Cid::try_from(cid).map_err(|e| format!("bad blob CID {}: {}", cid, e))?;
+        let export = ctx
+            .old
+            .export_blob(&parsed)
+            .await
+            .map_err(|e| format!("blob {}: {}", cid, e))?;
+        let data = export
+            .blob_data
+            .filter(|_| export.success)
+            .ok_or_else(|| format!("blob {}: {}", cid, export.message))?;
+        let upload = ctx
+            .target(PlanStep::CopyRecords)?
+            .upload_blob(&parsed, data)
+            .await
+            .map_err(|e| format!("blob {}: {}", cid, e))?;
+        if !upload.success {
+            return Err(format!("blob {}: {}", cid, upload.message));
+        }
+        copied.insert(cid.to_string());
+        Ok(true)
+    }
+}
+
+#[async_trait(?Send)]
+impl MigrationStep for ContentCopyStep {
+    fn plan_step(&self) -> PlanStep {
+        PlanStep::CopyRecords
+    }
+
+    fn describe(&self) -> &'static str {
+        "Copy records and their blobs into an existing account, rewriting the DID"
+    }
+
+    async fn execute(&self, ctx: &mut StepContext<'_>) -> Result<(), String> {
+        let old_did = ctx.old.session().did.clone();
+        let new_did = ctx.target(PlanStep::CopyRecords)?.session().did.clone();
+        if old_did == new_did {
+            return Err(
+                "The account on the new PDS has the same DID; use a regular migration plan"
+                    .to_string(),
+            );
+        }
+
+        let collections = ctx
+            .old
+            .describe_repo_collections()
+            .await
+            .map_err(|e| format!("Could not list the old repository's collections: {}", e))?;
+        let mut report = ContentCopyReport::default();
+        let mut copied_blobs = HashSet::new();
+
+        for collection in &collections {
+            let mut cursor: Option<String> = None;
+            loop {
+                let page = ctx
+                    .old
+                    .list_records(collection, cursor.as_deref())
+                    .await
+                    .map_err(|e| format!("Could not list {}: {}", collection, e))?;
+                for record in page.records {
+                    let Some(rkey) = rkey_of(&record.uri) else {
+                        report.fail(&record.uri, "no record key in URI");
+                        continue;
+                    };
+                    let mut value = record.value;
+                    let mut blob_error = None;
+                    for cid in blob_refs(&value) {
+                        match Self::copy_blob(ctx, &cid, &mut copied_blobs).await {
+                            Ok(true) => report.blobs_copied += 1,
+                            Ok(false) => {}
+                            Err(e) => {
+                                blob_error = Some(e);
+                                break;
+                            }
+                        }
+                    }
+                    if let Some(e) = blob_error {
+                        report.fail(&record.uri, e);
+                        continue;
+                    }
+                    rewrite_did(&mut value, &old_did, &new_did);
+                    match ctx
+                        .target(PlanStep::CopyRecords)?
+                        .put_record(collection, rkey, value)
+                        .await
+                    {
+                        Ok(()) => report.records_copied += 1,
+                        Err(e) => report.fail(&record.uri, e),
+                    }
+                }
+                (ctx.dispatch)(MigrationAction::SetMigrationStep(format!(
+                    "Copying content: {} records, {} blobs",
+                    report.records_copied, report.blobs_copied
+                )));
+                match page.cursor {
+                    Some(next) => cursor = Some(next),
+                    None => break,
+                }
+            }
+        }
+
+        console_info!(
+            "[Migration] Content copy: {} records copied, {} failed, {} blobs",
+            report.records_copied,
+            report.records_failed,
+            report.blobs_copied
+        );
+        if report.records_failed > 0 {
+            console_warn!(
+                "[Migration] {} record(s) could not be copied",
+                report.records_failed
+            );
+        }
+        let nothing_copied = report.records_copied == 0 && report.records_failed > 0;
+        let failures = report.records_failed;
+        (ctx.dispatch)(MigrationAction::SetContentCopyReport(Some(report)));
+        if nothing_copied {
+            return Err(format!(
+                "None of the {} records could be copied into the existing account",
+                failures
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migration::steps::mock_pds::{session, MockPds};
+    use crate::migration::types::MigrationState;
+    use crate::services::client::{ClientRecord, NewPds, OldPds, PdsApi};
+    use serde_json::json;
+    use std::cell::RefCell;
+
+    const BLOB: &str = "bafkreifgv7bnt3bfogfvzuz5l76uhrojeswno5gjgjsbakwdofioabm6k4";
+
+    fn record(rkey: &str, value: Value) -> ClientRecord {
+        ClientRecord {
+            uri: format!("at://did:plc:alice/app.bsky.feed.post/{}", rkey),
+            cid: "bafyreia".to_string(),
+            value,
+        }
+    }
+
+    #[test]
+    fn test_content_copy_rewrites_did_and_copies_blobs_once() {
+        let image =
+            json!({"$type": "blob", "ref": {"$link": BLOB}, "mimeType": "image/png", "size": 34});
+        let pds = MockPds {
+            collections: Some(vec!["app.bsky.feed.post".to_string()]),
+            records: [(
+                "app.bsky.feed.post".to_string(),
+                vec![
+                    record("3k1", json!({"text": "hi", "embed": {"images": [{"image": image}]}})),
+                    record(
+                        "3k2",
+                        json!({"text": "again", "reply": {"root": {"uri": "at://did:plc:alice/app.bsky.feed.post/3k1"}}, "embed": {"images": [{"image": image}]}}),
+                    ),
+                ],
+            )]
+            .into(),
+            blobs: [(BLOB.to_string(), b"png".to_vec())].into(),
+            ..MockPds::default()
+        };
+        let state = MigrationState::default();
+        let actions = RefCell::new(Vec::new());
+        let dispatch = |action: MigrationAction| actions.borrow_mut().push(action);
+        let api: &dyn PdsApi = &pds;
+        let mut target = session("https://new.example");
+        target.did = "did:plc:bob".to_string();
+        let mut ctx = StepContext {
+            old: OldPds::new(api, session("https://old.example")),
+            new: Some(NewPds::new(api, target)),
+            state: &state,
+            dispatch: &dispatch,
+            handler: None,
+            preferences: None,
+        };
+
+        let result = futures::executor::block_on(ContentCopyStep.execute(&mut ctx));
+        assert_eq!(result, Ok(()));
+        assert_eq!(*pds.uploaded_blobs.borrow(), vec![BLOB.to_string()]);
+        let written = pds.written_records.borrow();
+        assert_eq!(written.len(), 2);
+        assert_eq!(written[1].1, "3k2");
+        assert_eq!(
+            written[1].2["reply"]["root"]["uri"],
+            "at://did:plc:bob/app.bsky.feed.post/3k1"
+        );
+        match actions.borrow_mut().pop() {
+            Some(MigrationAction::SetContentCopyReport(Some(report))) => {
+                assert_eq!(report.records_copied, 2);
+                assert_eq!(report.blobs_copied, 1);
+            }
+            other => panic!("expected the content copy report, got {:?}", other),
+        }
+
+        assert_eq!(rkey_of("at://did:plc:alice/app.bsky.feed.post"), None);
+        assert_eq!(
+            rkey_of("at://did:plc:alice/app.bsky.actor.profile/self"),
+            Some("self")
+        );
+    }
+
+    #[test]
+    fn test_rewrite_did_leaves_longer_dids_alone() {
+        let mut value = json!({
+            "subject": "did:web:example.com",
+            "other": "did:web:example.com.au",
+            "uri": "at://did:web:example.com/app.bsky.feed.post/3k1",
+            "elsewhere": "at://did:web:example.com.au/app.bsky.feed.post/3k1",
+        });
+        rewrite_did(&mut value, "did:web:example.com", "did:plc:bob");
+        assert_eq!(value["subject"], "did:plc:bob");
+        assert_eq!(value["other"], "did:web:example.com.au");
+        assert_eq!(value["uri"], "at://did:plc:bob/app.bsky.feed.post/3k1");
+        assert_eq!(
+            value["elsewhere"],
+            "at://did:web:example.com.au/app.bsky.feed.post/3k1"
+        );
+    }
+
+    #[test]
+    fn test_rewrite_did_keeps_facet_offsets_valid() {
+        let text = "hi did:plc:alice and @alice.test";
+        let mut post = json!({
+            "text": text,
+            "facets": [{
+                "index": {"byteStart": 21, "byteEnd": 32},
+                "features": [{"$type": "app.bsky.richtext.facet#mention", "did": "did:plc:alice"}]
+            }]
+        });
+        rewrite_did(&mut post, "did:plc:alice", "did:plc:bobby-longer");
+        assert_eq!(post["text"], text);
+        assert_eq!(
+            post["facets"][0]["features"][0]["did"],
+            "did:plc:bobby-longer"
+        );
+        assert_eq!(&text[21..32], "@alice.test");
+    }
+}
@@ -0,0 +1,416 @@
+//! Keeping the new account in step with the old one until the identity switch
+//!
+//! With the "keep in sync" plan the initial copy is followed by a loop instead
+//! of going straight to PLC setup. Every interval both PDSes are asked for
+//! their latest commit; when the old one has moved on, `getRepo` with `since`
+//! set to the new PDS's revision returns only the blocks written since, and
+//! importing that CAR applies them on top of the repository already there.
+//! New records can bring new blobs, so a delta is followed by a blob pass,
+//! which only transfers what `listMissingBlobs` reports.
+//!
+//! The user keeps posting from the old account meanwhile, for as long as they
+//! like. Stopping runs one last round so nothing written since the previous
+//! check is left behind, then the plan continues with preferences and PLC
+//! setup. Sessions are refreshed every round, since access tokens expire long
+//! before a multi-day sync ends.
+//!
+//! Every migration, whatever its plan, also runs a single catch-up round just
+//! before the signed identity update is submitted.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+#[cfg(feature = "web")]
+use async_trait::async_trait;
+#[cfg(feature = "web")]
+use dioxus::prelude::*;
+
+#[cfg(feature = "web")]
+use crate::migration::{
+    plan::PlanStep,
+    steps::{blob::execute_streaming_blob_migration, MigrationStep, StepContext},
+    types::{MigrationAction, MigrationState},
+};
+#[cfg(feature = "web")]
+use crate::services::client::{ClientSessionCredentials, NewPds, OldPds, PdsClient};
+#[cfg(feature = "web")]
+use crate::utils::platform::{now_ms, sleep_ms};
+#[cfg(feature = "web")]
+use crate::{console_info, console_warn};
+
+/// Time between checks of the old PDS
+pub const DEFAULT_SYNC_INTERVAL_SECS: u64 = 15 * 60;
+
+/// How often the wait between rounds looks for a stop request
+#[cfg(feature = "web")]
+const STOP_POLL_MS: u64 = 1000;
+
+static STOP_REQUESTED: Mutex<bool> = Mutex::new(false);
+
+/// Ask the sync loop to run its final round and hand over to the identity switch
+pub fn request_sync_stop() {
+    if let Ok(mut stop) = STOP_REQUESTED.lock() {
+        *stop = true;
+    }
+}
+
+pub fn sync_stop_requested() -> bool {
+    STOP_REQUESTED.lock().map(|stop| *stop).unwrap_or(false)
+}
+
+#[cfg(feature = "web")]
+fn reset_sync_stop() {
+    if let Ok(mut stop) = STOP_REQUESTED.lock() {
+        *stop = false;
+    }
+}
+
+/// What one round has to copy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncDecision {
+    /// Both PDSes report the same commit
+    UpToDate,
+    /// Export the blocks written after the new PDS's revision
+    Delta { since: String },
+    /// The new PDS reports no revision to diff from; export everything
+    Full,
+}
+
+/// Compare the latest commits of both PDSes
+pub fn decide_sync(
+    source_commit: Option<&str>,
+    target_commit: Option<&str>,
+    target_rev: Option<&str>,
+) -> SyncDecision {
+    if source_commit.is_some() && source_commit == target_commit {
+        return SyncDecision::UpToDate;
+    }
+    match target_rev {
+        Some(rev) => SyncDecision::Delta {
+            since: rev.to_string(),
+        },
+        None => SyncDecision::Full,
+    }
+}
+
+/// Outcome of one successful round
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncRound {
+    /// Revision of the old PDS that the new one now matches
+    pub source_rev: Option<String>,
+    /// Size of the CAR imported, zero when nothing had changed
+    pub imported_bytes: u64,
+}
+
+/// Progress of the sync loop, for the panel and the support bundle
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContinuousSyncStatus {
+    pub interval_secs: u64,
+    pub rounds: u32,
+    /// Rounds that found changes and imported them
+    pub deltas_applied: u32,
+    pub imported_bytes: u64,
+    pub synced_rev: Option<String>,
+    pub last_check_ms: Option<u64>,
+    /// Set while waiting for the next round
+    pub next_check_ms: Option<u64>,
+    pub last_error: Option<String>,
+    pub consecutive_failures: u32,
+    /// The user asked to stop; the final round is running
+    pub stopping: bool,
+}
+
+impl ContinuousSyncStatus {
+    pub fn new(interval_secs: u64) -> Self {
+        Self {
+            interval_secs,
+            ..Self::default()
+        }
+    }
+
+    /// Fold a round's outcome into the status
+    pub fn record(&mut self, result: Result<SyncRound, String>, now_ms: u64) {
+        self.rounds += 1;
+        self.last_check_ms = Some(now_ms);
+        match result {
+            Ok(round) => {
+                if round.imported_bytes > 0 {
+                    self.deltas_applied += 1;
+                    self.imported_bytes += round.imported_bytes;
+                }
+                if round.source_rev.is_some() {
+                    self.synced_rev = round.source_rev;
+                }
+                self.last_error = None;
+                self.consecutive_failures = 0;
+            }
+            Err(e) => {
+                self.last_error = Some(e);
+                self.consecutive_failures += 1;
+            }
+        }
+    }
+
+    /// Whether the loop is sleeping between rounds, which isn't a stall
+    pub fn is_waiting(&self) -> bool {
+        self.next_check_ms.is_some()
+    }
+}
+
+/// Bring the new PDS up to the old one's latest commit, returning what was copied
+#[cfg(feature = "web")]
+pub async fn sync_round(
+    pds_client: &PdsClient,
+    old_session: &ClientSessionCredentials,
+    new_session: &ClientSessionCredentials,
+    dispatch: &EventHandler<MigrationAction>,
+    state: &MigrationState,
+) -> Result<SyncRound, String> {
+    let source = pds_client
+        .check_account_status(old_session)
+        .await
+        .map_err(|e| format!("Failed to check old account status: {}", e))?;
+    let target = pds_client
+        .check_account_status(new_session)
+        .await
+        .map_err(|e| format!("Failed to check new account status: {}", e))?;
+
+    let decision = decide_sync(
+        source.repo_commit.as_deref(),
+        target.repo_commit.as_deref(),
+        target.repo_rev.as_deref(),
+    );
+    let export = match &decision {
+        SyncDecision::UpToDate => {
+            return Ok(SyncRound {
+                source_rev: source.repo_rev,
+                imported_bytes: 0,
+            });
+        }
+        SyncDecision::Delta { since } => {
+            dispatch.call(MigrationAction::SetMigrationStep(format!(
+                "Copying changes made on the old PDS since revision {}...",
+                since
+            )));
+            pds_client.export_repository_since(old_session, since).await
+        }
+        SyncDecision::Full => {
+            dispatch.call(MigrationAction::SetMigrationStep(
+                "Copying the repository from the old PDS...".to_string(),
+            ));
+            pds_client.export_repository(old_session).await
+        }
+    }
+    .map_err(|e| format!("Failed to export changes from old PDS: {}", e))?;
+    let car = export.car_data.ok_or(export.message)?;
+    let imported_bytes = car.len() as u64;
+
+    let import = pds_client
+        .import_repository(new_session, car)
+        .await
+        .map_err(|e| format!("Failed to import changes to new PDS: {}", e))?;
+    if !import.success {
+        return Err(import.message);
+    }
+    console_info!(
+        "[Migration] Continuous sync imported {} bytes ({:?})",
+        imported_bytes,
+        decision
+    );
+
+    // New records may reference blobs the new PDS hasn't seen yet
+    let old = OldPds::new(PdsClient::new(), old_session.clone());
+    let new = NewPds::new(PdsClient::new(), new_session.clone());
+    execute_streaming_blob_migration(&old, &new, dispatch, state).await?;
+
+    Ok(SyncRound {
+        source_rev: source.repo_rev,
+        imported_bytes,
+    })
+}
+
+/// Keep a refreshed session, or the current one if refreshing failed
+#[cfg(feature = "web")]
+async fn refreshed(
+    pds_client: &PdsClient,
+    session: &ClientSessionCredentials,
+) -> ClientSessionCredentials {
+    match pds_client.refresh_session(session).await {
+        Ok(fresh) => fresh,
+        Err(e) => {
+            console_warn!(
+                "[Migration] Could not refresh session for {}: {}",
+                session.pds,
+                e
+            );
+            session.clone()
+        }
+    }
+}
+
+/// One last round right before the identity switch, so posts made while the
+/// migration ran (or while the PLC token was in the mail) reach the new PDS
+///
+/// The new PDS's revision is the one the initial export was taken at, unless
+/// a keep-in-sync loop has moved it on since, so only newer blocks are copied.
+#[cfg(feature = "web")]
+pub async fn catch_up_before_switch(
+    old_session: &ClientSessionCredentials,
+    new_session: &ClientSessionCredentials,
+    dispatch: &EventHandler<MigrationAction>,
+    state: &MigrationState,
+) -> Result<SyncRound, String> {
+    dispatch.call(MigrationAction::SetMigrationStep(
+        "Copying anything posted on the old account during the migration...".to_string(),
+    ));
+    let pds_client = PdsClient::new();
+    let old_session = refreshed(&pds_client, old_session).await;
+    let new_session = refreshed(&pds_client, new_session).await;
+    let round = sync_round(&pds_client, &old_session, &new_session, dispatch, state).await?;
+    if round.imported_bytes > 0 {
+        console_info!(
+            "[Migration] Catch-up copied {} bytes of changes up to revision {:?}",
+            round.imported_bytes,
+            round.source_rev
+        );
+    } else {
+        console_info!("[Migration] Catch-up found no changes since the initial copy");
+    }
+    Ok(round)
+}
+
+/// Sleep until the next round, returning early if the user asks to stop
+#[cfg(feature = "web")]
+async fn wait_for_next_round(interval_secs: u64) {
+    let deadline = now_ms() + (interval_secs * 1000) as f64;
+    while now_ms() < deadline && !sync_stop_requested() {
+        sleep_ms(STOP_POLL_MS).await;
+    }
+}
+
+/// Re-checks the old PDS and copies what changed until the user stops it
+#[cfg(feature = "web")]
+pub struct ContinuousSyncStep;
+
+#[cfg(feature = "web")]
+#[async_trait(?Send)]
+impl MigrationStep for ContinuousSyncStep {
+    fn plan_step(&self) -> PlanStep {
+        PlanStep::ContinuousSync
+    }
+
+    fn describe(&self) -> &'static str {
+        "Copy new records and blobs from the old PDS until the identity switch"
+    }
+
+    async fn execute(&self, ctx: &mut StepContext<'_>) -> Result<(), String> {
+        let target = ctx.target(self.plan_step())?;
+        let handler = ctx.handler(self.plan_step())?;
+        let pds_client = PdsClient::new();
+        let mut old_session = ctx.old.session().clone();
+        let mut new_session = target.session().clone();
+
+        reset_sync_stop();
+        let mut status = ContinuousSyncStatus::new(DEFAULT_SYNC_INTERVAL_SECS);
+        loop {
+            let stopping = sync_stop_requested();
+            status.stopping = stopping;
+            status.next_check_ms = None;
+            handler.call(MigrationAction::SetContinuousSync(Some(status.clone())));
+
+            old_session = refreshed(&pds_client, &old_session).await;
+            new_session = refreshed(&pds_client, &new_session).await;
+            let result =
+                sync_round(&pds_client, &old_session, &new_session, &handler, ctx.state).await;
+            if let Err(e) = &result {
+                console_warn!("[Migration] Continuous sync round failed: {}", e);
+            }
+            let final_error = result.as_ref().err().cloned();
+            status.record(result, now_ms() as u64);
+
+            if stopping {
+                handler.call(MigrationAction::SetContinuousSync(Some(status)));
+                // The last round has to succeed, or recent posts would be left behind
+                return match final_error {
+                    Some(e) => Err(format!(
+                        "Final sync before the identity switch failed: {}",
+                        e
+                    )),
+                    None => Ok(()),
+                };
+            }
+
+            status.next_check_ms = Some(now_ms() as u64 + status.interval_secs * 1000);
+            handler.call(MigrationAction::SetMigrationStep(format!(
+                "Keeping the new account in sync ({} checks so far)",
+                status.rounds
+            )));
+            handler.call(MigrationAction::SetContinuousSync(Some(status.clone())));
+            wait_for_next_round(status.interval_secs).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decide_sync_from_commits() {
+        assert_eq!(
+            decide_sync(Some("bafyA"), Some("bafyA"), Some("3k2a")),
+            SyncDecision::UpToDate
+        );
+        assert_eq!(
+            decide_sync(Some("bafyB"), Some("bafyA"), Some("3k2a")),
+            SyncDecision::Delta {
+                since: "3k2a".to_string()
+            }
+        );
+        assert_eq!(decide_sync(Some("bafyB"), None, None), SyncDecision::Full);
+        // An unknown source commit is never assumed to match
+        assert_eq!(
+            decide_sync(None, None, Some("3k2a")),
+            SyncDecision::Delta {
+                since: "3k2a".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_status_records_rounds() {
+        let mut status = ContinuousSyncStatus::new(60);
+        status.record(
+            Ok(SyncRound {
+                source_rev: Some("3k2a".to_string()),
+                imported_bytes: 0,
+            }),
+            1_000,
+        );
+        status.record(Err("timeout".to_string()), 2_000);
+        assert_eq!(status.consecutive_failures, 1);
+        assert_eq!(status.synced_rev.as_deref(), Some("3k2a"));
+
+        status.record(
+            Ok(SyncRound {
+                source_rev: Some("3k2b".to_string()),
+                imported_bytes: 512,
+            }),
+            3_000,
+        );
+        assert_eq!(status.rounds, 3);
+        assert_eq!(status.deltas_applied, 1);
+        assert_eq!(status.imported_bytes, 512);
+        assert_eq!(status.consecutive_failures, 0);
+        assert_eq!(status.last_error, None);
+        assert_eq!(status.last_check_ms, Some(3_000));
+    }
+
+    #[cfg(feature = "web")]
+    #[tokio::test]
+    async fn test_wait_for_next_round_sleeps_without_a_browser() {
+        let started = now_ms();
+        wait_for_next_round(1).await;
+        assert!(now_ms() - started >= 900.0);
+    }
+}
@@ -0,0 +1,599 @@
+//! Throwaway test-account demo of the whole pipeline
+//!
+//! Prospective users want to see a migration run before pointing the tool at
+//! their real identity. The hidden `/demo` page signs a brand-new account up
+//! on a test PDS, fills it with a profile, a few posts, an image, a list and
+//! some preferences, creates the matching account on a second test PDS and
+//! runs the migration plan between them. The PLC update is left out: it needs a token
+//! emailed to the account, and a throwaway account has no inbox. Both accounts
+//! stay behind on the test PDSes.
+//!
+//! A headless browser can drive the page with
+//! `/demo?source=<pds>&target=<pds>&invite=<code>&run=1`; the result element
+//! carries `data-demo-result="passed"` or `"failed"` once the run ends.
+
+use cid::Cid;
+use dioxus::prelude::EventHandler;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::future::Future;
+
+use crate::migration::orchestrator::{run_steps, step_for};
+use crate::migration::plan::{MigrationPlan, PlanStep, PlanVariant};
+use crate::migration::steps::{MigrationStep, StepContext};
+use crate::migration::types::{MigrationAction, MigrationState};
+use crate::services::client::clock_skew::server_now_secs;
+use crate::services::client::{
+    ClientAccountStatusResponse, ClientCreateAccountRequest, ClientSessionCredentials, NewPds,
+    OldPds, PdsApi, PdsClient,
+};
+use crate::utils::handle::normalize_pds_url;
+use crate::utils::platform::{format_iso8601, now_ms, random_hex};
+
+/// Image uploaded to the throwaway account, used as avatar and post image
+pub const SAMPLE_IMAGE: &[u8] = include_bytes!("../../assets/demo-sample.png");
+
+/// Raw sha2-256 CID of [`SAMPLE_IMAGE`]
+pub const SAMPLE_IMAGE_CID: &str = "bafkreieo4yvxlubq5chpiiak6t5ahpeskbkvjzzvtdwngc32xr7fvqh5u4";
+
+/// Alphabet of AT Protocol record keys built from timestamps
+const TID_ALPHABET: &[u8; 32] = b"234567abcdefghijklmnopqrstuvwxyz";
+
+/// What a demo check exercised
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DemoStage {
+    SourceAccount,
+    SampleContent,
+    TargetAccount,
+    Migration,
+    Comparison,
+}
+
+impl DemoStage {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DemoStage::SourceAccount => "Throwaway account",
+            DemoStage::SampleContent => "Sample content",
+            DemoStage::TargetAccount => "Target account",
+            DemoStage::Migration => "Migration",
+            DemoStage::Comparison => "Comparison",
+        }
+    }
+}
+
+/// Outcome of one demo check
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DemoCheck {
+    pub stage: DemoStage,
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+    pub duration_ms: u64,
+}
+
+/// Test PDSes to run the demo between
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DemoConfig {
+    pub source_pds_url: String,
+    pub target_pds_url: String,
+    /// Used on both PDSes when they ask for one
+    pub invite_code: Option<String>,
+}
+
+impl DemoConfig {
+    /// Settings from the demo page URL, and whether `run=1` asks to start right away
+    pub fn from_page_url(page_url: &str) -> (Self, bool) {
+        let Ok(url) = reqwest::Url::parse(page_url) else {
+            return (Self::default(), false);
+        };
+        let param = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        let config = Self {
+            source_pds_url: param("source")
+                .map(|pds| normalize_pds_url(&pds))
+                .unwrap_or_default(),
+            target_pds_url: param("target")
+                .map(|pds| normalize_pds_url(&pds))
+                .unwrap_or_default(),
+            invite_code: param("invite"),
+        };
+        let autorun = param("run").is_some_and(|run| run == "1" || run == "true");
+        (config, autorun)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let source = normalize_pds_url(&self.source_pds_url);
+        let target = normalize_pds_url(&self.target_pds_url);
+        if source.is_empty() || target.is_empty() {
+            return Err("Both a source and a target test PDS are needed".to_string());
+        }
+        if source == target {
+            return Err("The source and target test PDS must be different servers".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Sign-up details of a throwaway account
+#[derive(Debug, Clone, PartialEq)]
+pub struct DemoAccount {
+    pub handle: String,
+    pub password: String,
+    pub email: String,
+}
+
+impl DemoAccount {
+    /// `demo-<suffix>` under the first user domain a PDS offers
+    pub fn new(user_domain: &str, suffix: &str, password: &str) -> Self {
+        let domain = user_domain.trim().trim_start_matches('.');
+        Self {
+            handle: format!("demo-{}.{}", suffix, domain),
+            password: password.to_string(),
+            email: format!("demo-{}@example.com", suffix),
+        }
+    }
+}
+
+/// First user domain from a `describeServer` answer
+pub fn user_domain(description: &Value) -> Option<String> {
+    description
+        .get("availableUserDomains")?
+        .as_array()?
+        .iter()
+        .find_map(Value::as_str)
+        .map(str::to_string)
+}
+
+/// The full plan without the identity switch, which needs an emailed token
+pub fn demo_plan() -> MigrationPlan {
+    let mut plan = MigrationPlan::for_variant(PlanVariant::Full);
+    plan.steps
+        .retain(|descriptor| !matches!(descriptor.step, PlanStep::EmailSetup | PlanStep::PlcSetup));
+    plan
+}
+
+/// Timestamp record key: microseconds since the epoch and a clock id
+pub fn tid(micros: u64, clock_id: u16) -> String {
+    let value = ((micros & ((1 << 53) - 1)) << 10) | (clock_id as u64 & 0x3ff);
+    (0..13)
+        .rev()
+        .map(|i| TID_ALPHABET[((value >> (i * 5)) & 31) as usize] as char)
+        .collect()
+}
+
+/// Blob reference to [`SAMPLE_IMAGE`] for use inside a record
+fn sample_image_ref() -> Value {
+    json!({
+        "$type": "blob",
+        "ref": { "$link": SAMPLE_IMAGE_CID },
+        "mimeType": "image/png",
+        "size": SAMPLE_IMAGE.len(),
+    })
+}
+
+/// Records written to the throwaway account, as `(collection, rkey, record)`
+pub fn sample_records(did: &str, now_ms: u64) -> Vec<(&'static str, String, Value)> {
+    let created_at = format_iso8601(now_ms);
+    let key = |n: u64| tid(now_ms * 1000 + n, 0);
+    let list_key = key(4);
+    vec![
+        (
+            "app.bsky.actor.profile",
+            "self".to_string(),
+            json!({
+                "$type": "app.bsky.actor.profile",
+                "displayName": "Migration demo",
+                "description": "Throwaway account created to try out the migration service.",
+                "avatar": sample_image_ref(),
+                "createdAt": created_at,
+            }),
+        ),
+        (
+            "app.bsky.feed.post",
+            key(1),
+            json!({
+                "$type": "app.bsky.feed.post",
+                "text": "Hello from a throwaway demo account!",
+                "langs": ["en"],
+                "createdAt": created_at,
+            }),
+        ),
+        (
+            "app.bsky.feed.post",
+            key(2),
+            json!({
+                "$type": "app.bsky.feed.post",
+                "text": "Images come along too.",
+                "embed": {
+                    "$type": "app.bsky.embed.images",
+                    "images": [{
+                        "alt": "A small colour gradient",
+                        "image": sample_image_ref(),
+                        "aspectRatio": { "width": 8, "height": 8 },
+                    }],
+                },
+                "createdAt": created_at,
+            }),
+        ),
+        (
+            "app.bsky.feed.post",
+            key(3),
+            json!({
+                "$type": "app.bsky.feed.post",
+                "text": "So do #hashtags.",
+                "facets": [{
+                    "index": { "byteStart": 6, "byteEnd": 15 },
+                    "features": [{ "$type": "app.bsky.richtext.facet#tag", "tag": "hashtags" }],
+                }],
+                "createdAt": created_at,
+            }),
+        ),
+        (
+            "app.bsky.graph.list",
+            list_key.clone(),
+            json!({
+                "$type": "app.bsky.graph.list",
+                "purpose": "app.bsky.graph.defs#curatelist",
+                "name": "Demo list",
+                "createdAt": created_at,
+            }),
+        ),
+        (
+            "app.bsky.graph.listitem",
+            key(5),
+            json!({
+                "$type": "app.bsky.graph.listitem",
+                "subject": did,
+                "list": format!("at://{}/app.bsky.graph.list/{}", did, list_key),
+                "createdAt": created_at,
+            }),
+        ),
+    ]
+}
+
+/// Preferences written to the throwaway account before the migration
+pub fn sample_preferences() -> Value {
+    json!({"preferences": [
+        {"$type": "app.bsky.actor.defs#adultContentPref", "enabled": false},
+        {"$type": "app.bsky.actor.defs#savedFeedsPrefV2", "items": [
+            {"id": "3l6ovcmm2vd2j", "type": "timeline", "value": "following", "pinned": true},
+        ]},
+        {"$type": "app.bsky.actor.defs#mutedWordsPref", "items": [
+            {"value": "spoilers", "targets": ["content", "tag"]},
+        ]},
+    ]})
+}
+
+/// Whether the target holds everything the source does, by the PDSes' own counts
+pub fn compare_account_status(
+    source: &ClientAccountStatusResponse,
+    target: &ClientAccountStatusResponse,
+) -> Result<String, String> {
+    let source_records = source.indexed_records.unwrap_or(0);
+    let target_records = target.indexed_records.unwrap_or(0);
+    if source_records != target_records {
+        return Err(format!(
+            "The source has {} records, the target {}",
+            source_records, target_records
+        ));
+    }
+    let expected = target.expected_blobs.unwrap_or(0);
+    let imported = target.imported_blobs.unwrap_or(0);
+    if imported < expected {
+        return Err(format!("The target has {} of {} blobs", imported, expected));
+    }
+    Ok(format!(
+        "{} records and {} blobs on both",
+        target_records, imported
+    ))
+}
+
+/// Whether every check that ran passed
+pub fn demo_passed(checks: &[DemoCheck]) -> bool {
+    !checks.is_empty() && checks.iter().all(|check| check.passed)
+}
+
+/// Runs each check and reports it as soon as it finishes
+struct Runner<F: FnMut(&DemoCheck)> {
+    checks: Vec<DemoCheck>,
+    on_check: F,
+}
+
+impl<F: FnMut(&DemoCheck)> Runner<F> {
+    async fn check<T>(
+        &mut self,
+        stage: DemoStage,
+        name: &str,
+        run: impl Future<Output = Result<(T, String), String>>,
+    ) -> Option<T> {
+        let started = now_ms();
+        let result = run.await;
+        let check = DemoCheck {
+            stage,
+            name: name.to_string(),
+            passed: result.is_ok(),
+            detail: match &result {
+                Ok((_, detail)) => detail.clone(),
+                Err(error) => error.clone(),
+            },
+            duration_ms: (now_ms() - started).max(0.0) as u64,
+        };
+        (self.on_check)(&check);
+        self.checks.push(check);
+        result.ok().map(|(value, _)| value)
+    }
+}
+
+/// Describe a PDS and pick the user domain to sign up under
+async fn server_domain(client: &PdsClient, pds_url: &str) -> Result<(Value, String), String> {
+    let description = client
+        .describe_server(pds_url)
+        .await
+        .map_err(|e| e.to_string())?;
+    let domain =
+        user_domain(&description).ok_or_else(|| format!("{} offers no user domains", pds_url))?;
+    Ok((description, domain))
+}
+
+async fn create_account(
+    client: &PdsClient,
+    pds_url: &str,
+    request: ClientCreateAccountRequest,
+) -> Result<ClientSessionCredentials, String> {
+    let response = client
+        .create_account(pds_url, request)
+        .await
+        .map_err(|e| e.to_string())?;
+    response
+        .session
+        .filter(|_| response.success)
+        .ok_or(response.message)
+}
+
+async fn account_status(
+    client: &PdsClient,
+    session: &ClientSessionCredentials,
+) -> Result<ClientAccountStatusResponse, String> {
+    let status = client
+        .check_account_status(session)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !status.success {
+        return Err(status.message);
+    }
+    Ok(status)
+}
+
+/// Create, fill and migrate a throwaway account; later checks that depend
+/// on a failed one are left out
+pub async fn run_demo(
+    config: &DemoConfig,
+    handler: EventHandler<MigrationAction>,
+    on_check: impl FnMut(&DemoCheck),
+) -> Vec<DemoCheck> {
+    let mut runner = Runner {
+        checks: Vec::new(),
+        on_check,
+    };
+    let client = PdsClient::new();
+    let source_url = normalize_pds_url(&config.source_pds_url);
+    let target_url = normalize_pds_url(&config.target_pds_url);
+    let suffix = random_hex(4);
+
+    let Some(source) = runner
+        .check(
+            DemoStage::SourceAccount,
+            "Create throwaway account",
+            async {
+                config.validate()?;
+                let (_, domain) = server_domain(&client, &source_url).await?;
+                let account = DemoAccount::new(&domain, &suffix, &random_hex(16));
+                let request = ClientCreateAccountRequest {
+                    did: String::new(),
+                    handle: account.handle.clone(),
+                    password: account.password,
+                    email: account.email,
+                    invite_code: config.invite_code.clone(),
+                    service_auth_token: None,
+                    verification_code: None,
+                    verification_phone: None,
+                    idempotency_key: None,
+                };
+                let session = create_account(&client, &source_url, request).await?;
+                let detail = format!("@{} ({})", session.handle, session.did);
+                Ok((session, detail))
+            },
+        )
+        .await
+    else {
+        return runner.checks;
+    };
+
+    let seeded = runner
+        .check(DemoStage::SampleContent, "Upload image", async {
+            let cid = Cid::try_from(SAMPLE_IMAGE_CID).map_err(|e| e.to_string())?;
+            let upload = client
+                .upload_blob(&source, &cid, SAMPLE_IMAGE.to_vec())
+                .await
+                .map_err(|e| e.to_string())?;
+            if !upload.success {
+                return Err(upload.message);
+            }
+            Ok(((), format!("{} bytes as {}", SAMPLE_IMAGE.len(), cid)))
+        })
+        .await
+        .is_some();
+    let seeded = seeded
+        && runner
+            .check(DemoStage::SampleContent, "Write records", async {
+                let records = sample_records(&source.did, now_ms() as u64);
+                for (collection, rkey, record) in &records {
+                    client
+                        .put_record(&source, collection, rkey, record.clone())
+                        .await
+                        .map_err(|e| format!("{}/{}: {}", collection, rkey, e))?;
+                }
+                Ok((
+                    (),
+                    format!("Profile, posts and a list ({} records)", records.len()),
+                ))
+            })
+            .await
+            .is_some();
+    let seeded = seeded
+        && runner
+            .check(DemoStage::SampleContent, "Write preferences", async {
+                let preferences = sample_preferences();
+                let import = client
+                    .import_preferences(&source, preferences.to_string())
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if !import.success {
+                    return Err(import.message);
+                }
+                let count = preferences["preferences"].as_array().map_or(0, Vec::len);
+                Ok(((), format!("{} preference categories", count)))
+            })
+            .await
+            .is_some();
+    if !seeded {
+        return runner.checks;
+    }
+
+    let Some(target) = runner
+        .check(
+            DemoStage::TargetAccount,
+            "Create account on the target",
+            async {
+                let (description, domain) = server_domain(&client, &target_url).await?;
+                let server_did = description
+                    .get("did")
+                    .and_then(Value::as_str)
+                    .ok_or("The target PDS reports no DID")?;
+                let auth = client
+                    .get_service_auth(
+                        &source,
+                        server_did,
+                        Some("com.atproto.server.createAccount"),
+                        Some(server_now_secs() + 600),
+                    )
+                    .await
+                    .map_err(|e| e.to_string())?;
+                let token = auth.token.filter(|_| auth.success).ok_or(auth.message)?;
+                let account = DemoAccount::new(&domain, &suffix, &random_hex(16));
+                let request = ClientCreateAccountRequest {
+                    did: source.did.clone(),
+                    handle: account.handle,
+                    password: account.password,
+                    email: account.email,
+                    invite_code: config.invite_code.clone(),
+                    service_auth_token: Some(token),
+                    verification_code: None,
+                    verification_phone: None,
+                    idempotency_key: None,
+                };
+                let session = create_account(&client, &target_url, request).await?;
+                let detail = format!("@{} on {}", session.handle, target_url);
+                Ok((session, detail))
+            },
+        )
+        .await
+    else {
+        return runner.checks;
+    };
+
+    let migrated = runner
+        .check(DemoStage::Migration, "Run the migration plan", async {
+            let plan = demo_plan();
+            let pds: &dyn PdsApi = &client;
+            let state = MigrationState::default();
+            let emit = move |action: MigrationAction| handler.call(action);
+            let steps: Vec<Box<dyn MigrationStep>> =
+                plan.steps.iter().map(|d| step_for(d.step)).collect();
+            let mut ctx = StepContext {
+                old: OldPds::new(pds, source.clone()),
+                new: Some(NewPds::new(pds, target.clone())),
+                state: &state,
+                dispatch: &emit,
+                handler: Some(handler),
+                preferences: None,
+            };
+            run_steps(&plan, &steps, &mut ctx).await?;
+            let labels: Vec<&str> = plan.steps.iter().map(|d| d.step.label()).collect();
+            Ok(((), labels.join(", ")))
+        })
+        .await
+        .is_some();
+    if !migrated {
+        return runner.checks;
+    }
+
+    runner
+        .check(DemoStage::Comparison, "Compare the accounts", async {
+            let source_status = account_status(&client, &source).await?;
+            let target_status = account_status(&client, &target).await?;
+            let detail = compare_account_status(&source_status, &target_status)?;
+            Ok(((), detail))
+        })
+        .await;
+
+    runner.checks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_demo_inputs_and_sample_content() {
+        let (config, autorun) = DemoConfig::from_page_url(
+            "https://migrate.example/demo?source=https%3A%2F%2Fa.test%2F&target=b.test&invite=abc&run=1",
+        );
+        assert_eq!(config.source_pds_url, "https://a.test");
+        assert_eq!(config.invite_code.as_deref(), Some("abc"));
+        assert!(autorun);
+        assert!(config.validate().is_ok());
+        let (same, autorun) =
+            DemoConfig::from_page_url("https://migrate.example/demo?source=a.test&target=a.test");
+        assert!(!autorun);
+        assert!(same.validate().is_err());
+
+        let account = DemoAccount::new(".pds.test", "1a2b3c4d", "secret");
+        assert_eq!(account.handle, "demo-1a2b3c4d.pds.test");
+        assert_eq!(
+            user_domain(&json!({"availableUserDomains": [".pds.test"]})),
+            Some(".pds.test".to_string())
+        );
+
+        assert_eq!(tid(0, 0), "2222222222222");
+        assert!(tid(1_700_000_000_000_000, 0) < tid(1_700_000_000_000_001, 0));
+        assert_eq!(tid(1_700_000_000_000_000, 7).len(), 13);
+
+        let plan = demo_plan();
+        assert!(plan.validate().is_ok());
+        assert!(plan.contains(PlanStep::VerifyBlobs));
+        assert!(!plan.contains(PlanStep::PlcSetup));
+
+        let records = sample_records("did:plc:demo", 1_700_000_000_000);
+        let rkeys: Vec<&str> = records.iter().map(|(_, rkey, _)| rkey.as_str()).collect();
+        let mut unique = rkeys.clone();
+        unique.dedup();
+        assert_eq!(unique.len(), rkeys.len());
+        let listitem = &records.last().unwrap().2;
+        assert_eq!(
+            listitem["list"],
+            format!("at://did:plc:demo/app.bsky.graph.list/{}", records[4].1)
+        );
+    }
+
+    #[cfg(feature = "hash-verification")]
+    #[test]
+    fn test_sample_image_cid_matches_payload() {
+        let cid = Cid::try_from(SAMPLE_IMAGE_CID).unwrap();
+        crate::migration::integrity::verify_blob_bytes(&cid, SAMPLE_IMAGE).unwrap();
+    }
+}
@@ -0,0 +1,399 @@
+//! DID method operations behind one trait
+//!
+//! Moving an identity means pointing its DID document at the new PDS, and how
+//! that happens depends on the DID method. did:plc updates are signed by the
+//! old PDS (after an emailed confirmation token) and submitted to the PLC
+//! directory through the new PDS. did:web documents live on the user's own
+//! domain, so the update is the new document itself: the user publishes it and
+//! submission checks that it now resolves to the new PDS.
+//!
+//! The PLC setup step and Form 4 only talk to [`DidMethod`], so supporting
+//! another method means adding an implementation and a match arm in
+//! [`did_method_for`].
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::migration::plc_rules::validate_signed_plc_operation;
+use crate::migration::service_accounts::preserve_service_entries;
+use crate::migration::steps::plc_fallback::construct_plc_operation_fallback;
+use crate::services::client::{invalidate_identity, NewPds, OldPds, PdsApi, PdsClient};
+use crate::services::config::{get_global_config, RetryPhase};
+use crate::services::retry::with_retries;
+use crate::{console_info, console_warn};
+
+/// Unsigned update, and anything the user has to agree to before it is signed
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnsignedUpdate {
+    pub operation: String,
+    /// Shown in Form 4; signing waits until the user confirms it
+    pub confirmation: Option<String>,
+}
+
+impl From<String> for UnsignedUpdate {
+    fn from(operation: String) -> Self {
+        Self {
+            operation,
+            confirmation: None,
+        }
+    }
+}
+
+/// Identity update operations for one DID method
+#[async_trait(?Send)]
+pub trait DidMethod {
+    /// Method name as it appears in the DID, e.g. `plc`
+    fn method(&self) -> &'static str;
+
+    /// Whether signing needs a confirmation token emailed by the old PDS
+    fn requires_token(&self) -> bool;
+
+    /// PDS endpoint the DID document currently points at
+    async fn resolve(&self, did: &str) -> Result<String, String>;
+
+    /// Unsigned update that points the DID at the new PDS. `reserved_signing_key`
+    /// is the repo signing key reserved on the target before account creation.
+    async fn build_update(
+        &self,
+        new: &NewPds<&dyn PdsApi>,
+        reserved_signing_key: Option<&str>,
+    ) -> Result<UnsignedUpdate, String>;
+
+    /// Ask the old PDS for the confirmation token, if the method uses one
+    async fn request_token(&self, old: &OldPds<&dyn PdsApi>) -> Result<(), String>;
+
+    /// Turn the unsigned update into one the method will accept
+    async fn sign(&self, old: &OldPds, unsigned: String, token: String) -> Result<String, String>;
+
+    /// Publish the signed update, or confirm it has been published
+    async fn submit(&self, new: &NewPds, signed: String) -> Result<(), String>;
+}
+
+/// Pick the implementation for a DID
+pub fn did_method_for(did: &str) -> Result<Box<dyn DidMethod>, String> {
+    match did.split(':').nth(1) {
+        Some("plc") if did.starts_with("did:") => Ok(Box::new(PlcMethod)),
+        Some("web") if did.starts_with("did:") => Ok(Box::new(WebMethod)),
+        _ => Err(format!("Unsupported DID method: {}", did)),
+    }
+}
+
+async fn resolve_endpoint(did: &str) -> Result<String, String> {
+    PdsClient::new()
+        .resolve_pds_from_did(did)
+        .await
+        .map_err(|e| format!("Could not resolve {}: {}", did, e))
+}
+
+/// did:plc, updated through signed operations in the PLC directory
+pub struct PlcMethod;
+
+#[async_trait(?Send)]
+impl DidMethod for PlcMethod {
+    fn method(&self) -> &'static str {
+        "plc"
+    }
+
+    fn requires_token(&self) -> bool {
+        true
+    }
+
+    async fn resolve(&self, did: &str) -> Result<String, String> {
+        resolve_endpoint(did).await
+    }
+
+    async fn build_update(
+        &self,
+        new: &NewPds<&dyn PdsApi>,
+        reserved_signing_key: Option<&str>,
+    ) -> Result<UnsignedUpdate, String> {
+        let retry = get_global_config().retry;
+        let recommendation =
+            with_retries(&retry, RetryPhase::Plc, || new.get_plc_recommendation()).await;
+        match recommendation {
+            Ok(response) if !response.success && response.endpoint_missing => {
+                // The PDS doesn't offer recommendations; build the operation ourselves
+                console_warn!(
+                    "[Migration] getRecommendedDidCredentials not implemented; constructing PLC operation client-side"
+                );
+                construct_plc_operation_fallback(new, reserved_signing_key)
+                    .await
+                    .map_err(|e| format!("Failed to construct PLC operation: {}", e))
+            }
+            Ok(response) if response.success => {
+                console_info!("[Migration] {}", response.message);
+                let plc_unsigned = response.plc_unsigned.unwrap_or_default();
+                // Recommendations only describe the PDS; keep labeler and feed services
+                match (
+                    serde_json::from_str::<Value>(&plc_unsigned),
+                    new.fetch_plc_data().await,
+                ) {
+                    (Ok(mut operation), Ok(current)) => {
+                        let restored = preserve_service_entries(&mut operation, &current);
+                        if restored.is_empty() {
+                            return Ok(plc_unsigned.into());
+                        }
+                        console_info!(
+                            "[Migration] Carried over from the current PLC data: {}",
+                            restored.join(", ")
+                        );
+                        Ok(operation.to_string().into())
+                    }
+                    (_, Err(e)) => {
+                        console_warn!(
+                            "[Migration] Could not read current PLC data to preserve extra services: {}",
+                            e
+                        );
+                        Ok(plc_unsigned.into())
+                    }
+                    (Err(_), _) => Ok(plc_unsigned.into()),
+                }
+            }
+            Ok(response) => Err(response.message),
+            Err(e) => Err(format!("Failed to get PLC recommendation: {}", e)),
+        }
+    }
+
+    async fn request_token(&self, old: &OldPds<&dyn PdsApi>) -> Result<(), String> {
+        match old.request_plc_token().await {
+            Ok(response) if response.success => Ok(()),
+            Ok(response) => Err(response.message),
+            Err(e) => Err(format!("Failed to request PLC token: {}", e)),
+        }
+    }
+
+    async fn sign(&self, old: &OldPds, unsigned: String, token: String) -> Result<String, String> {
+        match old.sign_plc_operation(unsigned, token).await {
+            Ok(response) if response.success => Ok(response.plc_signed.unwrap_or_default()),
+            Ok(response) => Err(response.message),
+            Err(e) => Err(format!("Failed to sign PLC operation: {}", e)),
+        }
+    }
+
+    async fn submit(&self, new: &NewPds, signed: String) -> Result<(), String> {
+        let operation: Value = serde_json::from_str(&signed)
+            .map_err(|e| format!("The signed PLC operation is not valid JSON: {}", e))?;
+        let problems = validate_signed_plc_operation(&operation);
+        if !problems.is_empty() {
+            return Err(format!(
+                "The PLC directory would reject this operation: {}",
+                problems.join("; ")
+            ));
+        }
+
+        // Only retried if the host raised the PLC budget; every attempt carries the
+        // same idempotency key
+        let retry = get_global_config().retry;
+        let submitted = with_retries(&retry, RetryPhase::Plc, || {
+            new.submit_plc_operation(signed.clone())
+        })
+        .await;
+        let error = match submitted {
+            Ok(response) if response.success => None,
+            Ok(response) => Some(response.message),
+            Err(e) => Some(format!("Failed to submit PLC operation: {}", e)),
+        };
+        // The DID document now points at the new PDS, or may do so already
+        invalidate_identity(new.did());
+        let Some(error) = error else {
+            return Ok(());
+        };
+        // A lost response, or a retry rejected because the first attempt went
+        // through, shouldn't fail a migration whose identity has moved
+        match self.resolve(new.did()).await {
+            Ok(endpoint) if same_endpoint(&endpoint, new.host()) => {
+                console_warn!(
+                    "[Migration] PLC submission reported an error ({}) but the DID already points at the new PDS",
+                    error
+                );
+                Ok(())
+            }
+            _ => Err(error),
+        }
+    }
+}
+
+/// did:web, whose document the user hosts at `/.well-known/did.json`
+pub struct WebMethod;
+
+/// Where a did:web document has to be published
+pub fn did_web_document_url(did: &str) -> Result<String, String> {
+    let domain = did
+        .strip_prefix("did:web:")
+        .filter(|domain| !domain.is_empty())
+        .ok_or_else(|| format!("{} is not a did:web", did))?;
+    // Paths after the domain are colon-separated; the atproto profile allows none
+    if domain.contains(':') {
+        return Err(format!(
+            "{} has a path, which atproto does not support for did:web",
+            did
+        ));
+    }
+    Ok(format!("https://{}/.well-known/did.json", domain))
+}
+
+/// DID document pointing at the new PDS, from the target's recommended credentials
+pub fn build_did_web_document(
+    did: &str,
+    recommended: &Value,
+    pds_endpoint: &str,
+) -> Result<Value, String> {
+    did_web_document_url(did)?;
+    let signing_key = recommended
+        .pointer("/verificationMethods/atproto")
+        .and_then(Value::as_str)
+        .ok_or("Target PDS did not recommend an atproto signing key")?;
+    let public_key = signing_key
+        .strip_prefix("did:key:")
+        .ok_or_else(|| format!("Signing key is not a did:key: {}", signing_key))?;
+    let also_known_as = recommended
+        .get("alsoKnownAs")
+        .cloned()
+        .unwrap_or_else(|| json!([]));
+
+    Ok(json!({
+        "@context": [
+            "https://www.w3.org/ns/did/v1",
+            "https://w3id.org/security/multikey/v1",
+        ],
+        "id": did,
+        "alsoKnownAs": also_known_as,
+        "verificationMethod": [{
+            "id": format!("{}#atproto", did),
+            "type": "Multikey",
+            "controller": did,
+            "publicKeyMultibase": public_key,
+        }],
+        "service": [{
+            "id": "#atproto_pds",
+            "type": "AtprotoPersonalDataServer",
+            "serviceEndpoint": pds_endpoint.trim_end_matches('/'),
+        }],
+    }))
+}
+
+fn same_endpoint(a: &str, b: &str) -> bool {
+    a.trim_end_matches('/')
+        .eq_ignore_ascii_case(b.trim_end_matches('/'))
+}
+
+#[async_trait(?Send)]
+impl DidMethod for WebMethod {
+    fn method(&self) -> &'static str {
+        "web"
+    }
+
+    fn requires_token(&self) -> bool {
+        false
+    }
+
+    async fn resolve(&self, did: &str) -> Result<String, String> {
+        resolve_endpoint(did).await
+    }
+
+    async fn build_update(
+        &self,
+        new: &NewPds<&dyn PdsApi>,
+        _reserved_signing_key: Option<&str>,
+    ) -> Result<UnsignedUpdate, String> {
+        let response = new
+            .get_plc_recommendation()
+            .await
+            .map_err(|e| format!("Failed to get recommended DID credentials: {}", e))?;
+        if !response.success {
+            return Err(response.message);
+        }
+        let recommended: Value =
+            serde_json::from_str(response.plc_unsigned.as_deref().unwrap_or_default())
+                .map_err(|e| format!("Recommended DID credentials are not valid JSON: {}", e))?;
+        let document = build_did_web_document(new.did(), &recommended, new.host())?;
+        serde_json::to_string_pretty(&document)
+            .map(UnsignedUpdate::from)
+            .map_err(|e| e.to_string())
+    }
+
+    async fn request_token(&self, _old: &OldPds<&dyn PdsApi>) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// The document is authenticated by the domain serving it, so there is nothing to sign
+    async fn sign(
+        &self,
+        _old: &OldPds,
+        unsigned: String,
+        _token: String,
+    ) -> Result<String, String> {
+        Ok(unsigned)
+    }
+
+    async fn submit(&self, new: &NewPds, _signed: String) -> Result<(), String> {
+        invalidate_identity(new.did());
+        let endpoint = self.resolve(new.did()).await?;
+        if same_endpoint(&endpoint, new.host()) {
+            Ok(())
+        } else {
+            Err(format!(
+                "{} still points at {}. Publish the new document at {} and try again.",
+                new.did(),
+                endpoint,
+                did_web_document_url(new.did())?
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_did_method_selection() {
+        assert_eq!(did_method_for("did:plc:abc123").unwrap().method(), "plc");
+        assert!(did_method_for("did:plc:abc123").unwrap().requires_token());
+        assert_eq!(
+            did_method_for("did:web:alice.example").unwrap().method(),
+            "web"
+        );
+        assert!(!did_method_for("did:web:alice.example")
+            .unwrap()
+            .requires_token());
+        assert!(did_method_for("did:key:z6Mk").is_err());
+        assert!(did_method_for("plc:abc").is_err());
+    }
+
+    #[test]
+    fn test_did_web_document_points_at_new_pds() {
+        let recommended = json!({
+            "alsoKnownAs": ["at://alice.example"],
+            "verificationMethods": {"atproto": "did:key:zQ3shexample"},
+            "rotationKeys": ["did:key:zQ3shrotation"],
+        });
+        let document = build_did_web_document(
+            "did:web:alice.example",
+            &recommended,
+            "https://new.example/",
+        )
+        .unwrap();
+
+        assert_eq!(document["id"], "did:web:alice.example");
+        assert_eq!(document["alsoKnownAs"][0], "at://alice.example");
+        assert_eq!(
+            document["verificationMethod"][0]["publicKeyMultibase"],
+            "zQ3shexample"
+        );
+        assert_eq!(
+            document["service"][0]["serviceEndpoint"],
+            "https://new.example"
+        );
+
+        assert_eq!(
+            did_web_document_url("did:web:alice.example").unwrap(),
+            "https://alice.example/.well-known/did.json"
+        );
+        assert!(did_web_document_url("did:web:alice.example:user").is_err());
+        assert!(
+            build_did_web_document("did:web:alice.example", &json!({}), "https://new.example")
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,188 @@
+//! Making sure the new account ends up with a confirmed email
+//!
+//! `createAccount` stores the address from Form 3 but leaves it unconfirmed,
+//! and password resets and emailed PLC or deletion codes go to that address.
+//! An optional plan step reads the address back from the new PDS, corrects it
+//! if it differs from the one entered (possible without a token while it is
+//! unconfirmed) and asks the PDS to send a confirmation code. Form 4 then lets
+//! the user enter that code, resend it, or switch to a different address; the
+//! status is kept in [`EmailVerification`] so the page reflects what was sent.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::migration::plan::PlanStep;
+use crate::migration::steps::traits::{MigrationStep, StepContext};
+use crate::migration::types::MigrationAction;
+use crate::{console_info, console_warn};
+
+/// Where the new account's email stands
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EmailVerificationStatus {
+    #[default]
+    NotChecked,
+    Confirmed,
+    /// The PDS emailed a confirmation code to the address
+    ConfirmationSent,
+    /// Changing a confirmed address needs the code the PDS sent to it
+    UpdateTokenSent {
+        new_email: String,
+    },
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmailVerification {
+    /// Address the new PDS has on file
+    pub email: String,
+    pub status: EmailVerificationStatus,
+}
+
+impl EmailVerification {
+    pub fn new(email: impl Into<String>, status: EmailVerificationStatus) -> Self {
+        Self {
+            email: email.into(),
+            status,
+        }
+    }
+
+    pub fn is_confirmed(&self) -> bool {
+        self.status == EmailVerificationStatus::Confirmed
+    }
+}
+
+/// Sets the email entered on Form 3 and requests its confirmation
+pub struct EmailSetupStep;
+
+#[async_trait(?Send)]
+impl MigrationStep for EmailSetupStep {
+    fn plan_step(&self) -> PlanStep {
+        PlanStep::EmailSetup
+    }
+
+    fn describe(&self) -> &'static str {
+        "Set the new account's email and request its confirmation"
+    }
+
+    async fn execute(&self, ctx: &mut StepContext<'_>) -> Result<(), String> {
+        let new = ctx.target(PlanStep::EmailSetup)?;
+        let fail = |message: String| {
+            (ctx.dispatch)(MigrationAction::SetEmailVerification(
+                EmailVerification::new(
+                    ctx.state.form3.email.trim(),
+                    EmailVerificationStatus::Failed(message.clone()),
+                ),
+            ));
+            message
+        };
+
+        let current = new
+            .email_status()
+            .await
+            .map_err(|e| fail(format!("Could not read the account's email: {}", e)))?;
+        let mut email = current.email.unwrap_or_default();
+        let mut confirmed = current.confirmed;
+        let wanted = ctx.state.form3.email.trim();
+
+        if !wanted.is_empty() && !email.eq_ignore_ascii_case(wanted) {
+            if confirmed {
+                // Replacing a confirmed address needs a token; Form 4 offers that
+                console_warn!(
+                    "[Migration] New PDS has a confirmed email other than the one entered"
+                );
+            } else {
+                new.update_email(wanted, None)
+                    .await
+                    .map_err(|e| fail(format!("Could not set the email: {}", e)))?;
+                email = wanted.to_string();
+                confirmed = false;
+            }
+        }
+
+        if confirmed {
+            (ctx.dispatch)(MigrationAction::SetEmailVerification(
+                EmailVerification::new(email, EmailVerificationStatus::Confirmed),
+            ));
+            return Ok(());
+        }
+
+        new.request_email_confirmation()
+            .await
+            .map_err(|e| fail(format!("Could not request email confirmation: {}", e)))?;
+        console_info!("[Migration] Email confirmation code requested");
+        (ctx.dispatch)(MigrationAction::SetEmailVerification(
+            EmailVerification::new(email, EmailVerificationStatus::ConfirmationSent),
+        ));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migration::steps::mock_pds::{session, MockPds};
+    use crate::migration::types::MigrationState;
+    use crate::services::client::{ClientEmailStatus, NewPds, OldPds, PdsApi};
+    use std::cell::RefCell;
+
+    fn run(pds: &MockPds, entered: &str) -> Option<EmailVerification> {
+        let mut state = MigrationState::default();
+        state.form3.email = entered.to_string();
+        let actions = RefCell::new(Vec::new());
+        let dispatch = |action: MigrationAction| actions.borrow_mut().push(action);
+        let api: &dyn PdsApi = pds;
+        let mut ctx = StepContext {
+            old: OldPds::new(api, session("https://old.example")),
+            new: Some(NewPds::new(api, session("https://new.example"))),
+            state: &state,
+            dispatch: &dispatch,
+            handler: None,
+            preferences: None,
+        };
+        let _ = futures::executor::block_on(EmailSetupStep.execute(&mut ctx));
+        let last = actions.borrow_mut().pop();
+        match last {
+            Some(MigrationAction::SetEmailVerification(verification)) => Some(verification),
+            _ => None,
+        }
+    }
+
+    fn email(address: &str, confirmed: bool) -> RefCell<Option<ClientEmailStatus>> {
+        RefCell::new(Some(ClientEmailStatus {
+            email: Some(address.to_string()),
+            confirmed,
+        }))
+    }
+
+    #[test]
+    fn test_email_setup_corrects_unconfirmed_address_and_requests_code() {
+        let pds = MockPds {
+            email: email("typo@example.com", false),
+            ..MockPds::default()
+        };
+        let verification = run(&pds, "alice@example.com").unwrap();
+        assert_eq!(
+            verification,
+            EmailVerification::new(
+                "alice@example.com",
+                EmailVerificationStatus::ConfirmationSent
+            )
+        );
+        assert_eq!(pds.email_confirmation_requests.get(), 1);
+
+        let confirmed = MockPds {
+            email: email("old@example.com", true),
+            ..MockPds::default()
+        };
+        let verification = run(&confirmed, "alice@example.com").unwrap();
+        assert_eq!(verification.email, "old@example.com");
+        assert!(verification.is_confirmed());
+        assert_eq!(confirmed.email_confirmation_requests.get(), 0);
+
+        let unreachable = MockPds::default();
+        assert!(matches!(
+            run(&unreachable, "alice@example.com").unwrap().status,
+            EmailVerificationStatus::Failed(_)
+        ));
+    }
+}
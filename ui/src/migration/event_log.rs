@@ -0,0 +1,457 @@
+//! Append-only log of dispatched migration actions
+//!
+//! The current `MigrationState` is derived by folding actions through
+//! `MigrationState::reduce_in_place`. Keeping the log alongside the live state lets us
+//! rebuild the state at any earlier point when debugging a reported issue.
+//!
+//! A migration of a large account dispatches a progress snapshot for every few
+//! blobs, so the log is kept small in three ways:
+//!
+//! - Compaction: a progress snapshot replaces the previous snapshot of the same
+//!   kind when only other snapshots were recorded in between. The surviving entry
+//!   keeps the sequence range it stands for. Replaying the log still ends in the
+//!   same state; only intermediate progress values are lost.
+//! - Size caps: once over the entry or byte budget, the oldest entries are folded
+//!   into the base state. Their redacted JSON is queued as a spill chunk that
+//!   [`write_spill_to_opfs`] persists, so nothing is lost for support.
+//! - Corruption checks: [`MigrationEventLog::verify`] replays the log and compares
+//!   it with the live state. The live state reflects what the servers reported, so
+//!   on a mismatch [`MigrationEventLog::rebuild_from`] restarts the log from it.
+//!
+//! The log is only ever mutated through the owning signal's exclusive borrow. Spill
+//! chunks are named after their first sequence number, so overlapping writes from
+//! concurrent tasks never touch the same file.
+
+use std::collections::VecDeque;
+use std::mem::discriminant;
+
+use opfs::persistent::app_specific_dir;
+use opfs::{CreateWritableOptions, GetDirectoryHandleOptions, GetFileHandleOptions};
+use opfs::{DirectoryHandle as _, FileHandle as _, WritableFileStream as _};
+
+use super::types::{MigrationAction, MigrationState};
+
+/// Default number of actions retained before the oldest are folded into the base state
+pub const DEFAULT_EVENT_LOG_CAPACITY: usize = 2000;
+
+/// Default budget for the serialized size of the retained actions
+pub const DEFAULT_EVENT_LOG_MAX_BYTES: usize = 2 * 1024 * 1024;
+
+/// Folded actions queued before the dispatcher should write a spill chunk
+pub const SPILL_BATCH_SIZE: usize = 500;
+
+/// Queued spill lines kept when nobody drains them; older lines are dropped
+const MAX_PENDING_SPILL: usize = 4 * SPILL_BATCH_SIZE;
+
+/// OPFS directory spill chunks are written to
+const SPILL_DIR: &str = "event_log_spill";
+
+/// A retained action and the recorded sequence numbers it stands for
+#[derive(Clone)]
+struct LoggedAction {
+    first_seq: u64,
+    last_seq: u64,
+    /// Serialized size of the redacted action, counted against the byte budget
+    bytes: usize,
+    action: MigrationAction,
+}
+
+/// Redacted JSON lines of folded actions, ready to be written out
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventLogSpill {
+    pub first_seq: u64,
+    pub lines: Vec<String>,
+}
+
+impl EventLogSpill {
+    pub fn file_name(&self) -> String {
+        format!("actions-{:012}.jsonl", self.first_seq)
+    }
+}
+
+/// Bounded, append-only action log with a base snapshot for replay
+#[derive(Clone)]
+pub struct MigrationEventLog {
+    /// State before the first retained action
+    base: MigrationState,
+    actions: VecDeque<LoggedAction>,
+    capacity: usize,
+    max_bytes: usize,
+    bytes: usize,
+    /// Total actions ever recorded, including those folded into `base`
+    total_recorded: u64,
+    /// Actions absorbed by a later snapshot of the same kind
+    compacted: u64,
+    pending_spill: VecDeque<(u64, String)>,
+    /// Folded actions whose spill line was dropped before it could be written
+    spill_dropped: u64,
+    rebuilds: u32,
+}
+
+/// Progress snapshots that fully overwrite one field of the state
+fn is_snapshot(action: &MigrationAction) -> bool {
+    matches!(
+        action,
+        MigrationAction::SetMigrationStep(_)
+            | MigrationAction::SetMigrationProgress(_)
+            | MigrationAction::SetRepoProgress(_)
+            | MigrationAction::SetBlobProgress(_)
+            | MigrationAction::SetLargeBlobTransfers(_)
+            | MigrationAction::SetPreferencesProgress(_)
+            | MigrationAction::SetPlcProgress(_)
+            | MigrationAction::SetStorageUsage(_)
+            | MigrationAction::SetContinuousSync(_)
+            | MigrationAction::SetPropagation(_)
+            | MigrationAction::SetServiceAccount(_)
+    )
+}
+
+fn serialized(action: &MigrationAction) -> String {
+    serde_json::to_string(&action.redacted()).unwrap_or_default()
+}
+
+/// Fields a replay must reproduce exactly; timings stamped by the reducer are left out
+fn state_digest(state: &MigrationState) -> String {
+    format!(
+        "{:?}|{}|{}|{:?}|{}|{}|{}|{:?}|{:?}|{}",
+        state.current_step,
+        state.is_migrating,
+        state.migration_completed,
+        state.migration_error,
+        state.migration_step,
+        state.form1.handle,
+        state.form2.pds_url,
+        state.repo_progress,
+        state.blob_progress,
+        state.console_messages_total,
+    )
+}
+
+impl MigrationEventLog {
+    pub fn new(initial: MigrationState) -> Self {
+        Self::with_capacity(initial, DEFAULT_EVENT_LOG_CAPACITY)
+    }
+
+    pub fn with_capacity(initial: MigrationState, capacity: usize) -> Self {
+        Self::with_limits(initial, capacity, DEFAULT_EVENT_LOG_MAX_BYTES)
+    }
+
+    pub fn with_limits(initial: MigrationState, capacity: usize, max_bytes: usize) -> Self {
+        Self {
+            base: initial,
+            actions: VecDeque::new(),
+            capacity: capacity.max(1),
+            max_bytes: max_bytes.max(1),
+            bytes: 0,
+            total_recorded: 0,
+            compacted: 0,
+            pending_spill: VecDeque::new(),
+            spill_dropped: 0,
+            rebuilds: 0,
+        }
+    }
+
+    /// Append an action, compacting superseded snapshots and folding the oldest
+    /// actions into the base state once over the entry or byte budget
+    pub fn record(&mut self, action: MigrationAction) {
+        self.total_recorded += 1;
+        let seq = self.total_recorded;
+        let mut first_seq = seq;
+
+        if is_snapshot(&action) {
+            let superseded = self
+                .actions
+                .iter()
+                .rev()
+                .take_while(|entry| is_snapshot(&entry.action))
+                .position(|entry| discriminant(&entry.action) == discriminant(&action));
+            if let Some(offset) = superseded {
+                let index = self.actions.len() - 1 - offset;
+                if let Some(old) = self.actions.remove(index) {
+                    self.bytes -= old.bytes;
+                    self.compacted += 1;
+                    first_seq = old.first_seq;
+                }
+            }
+        }
+
+        let bytes = serialized(&action).len();
+        self.bytes += bytes;
+        self.actions.push_back(LoggedAction {
+            first_seq,
+            last_seq: seq,
+            bytes,
+            action,
+        });
+
+        while self.actions.len() > self.capacity
+            || (self.bytes > self.max_bytes && self.actions.len() > 1)
+        {
+            if let Some(oldest) = self.actions.pop_front() {
+                self.bytes -= oldest.bytes;
+                self.queue_spill(oldest.last_seq, serialized(&oldest.action));
+                self.base.reduce_in_place(oldest.action);
+            }
+        }
+    }
+
+    fn queue_spill(&mut self, seq: u64, line: String) {
+        self.pending_spill.push_back((seq, line));
+        while self.pending_spill.len() > MAX_PENDING_SPILL {
+            self.pending_spill.pop_front();
+            self.spill_dropped += 1;
+        }
+    }
+
+    /// Apply an action to the live state and record it in one step
+    pub fn apply(&mut self, state: &mut MigrationState, action: MigrationAction) {
+        state.reduce_in_place(action.clone());
+        self.record(action);
+    }
+
+    /// Actions currently retained, oldest first
+    pub fn actions(&self) -> impl Iterator<Item = &MigrationAction> {
+        self.actions.iter().map(|entry| &entry.action)
+    }
+
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    pub fn total_recorded(&self) -> u64 {
+        self.total_recorded
+    }
+
+    /// Actions absorbed by compaction
+    pub fn compacted_count(&self) -> u64 {
+        self.compacted
+    }
+
+    /// Serialized size of the retained actions
+    pub fn retained_bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Times the log was restarted after failing verification
+    pub fn rebuild_count(&self) -> u32 {
+        self.rebuilds
+    }
+
+    /// Folded actions waiting to be written out
+    pub fn pending_spill_len(&self) -> usize {
+        self.pending_spill.len()
+    }
+
+    /// Folded actions that were dropped before being written out
+    pub fn spill_dropped(&self) -> u64 {
+        self.spill_dropped
+    }
+
+    /// Drain the queued spill lines as one chunk
+    pub fn take_spill(&mut self) -> Option<EventLogSpill> {
+        let first_seq = self.pending_spill.front()?.0;
+        let lines = self.pending_spill.drain(..).map(|(_, line)| line).collect();
+        Some(EventLogSpill { first_seq, lines })
+    }
+
+    /// Rebuild the state after the first `count` retained actions (time-travel debugging)
+    pub fn replay(&self, count: usize) -> MigrationState {
+        self.actions
+            .iter()
+            .take(count)
+            .map(|entry| entry.action.clone())
+            .fold(self.base.clone(), MigrationState::reduce)
+    }
+
+    /// Rebuild the state after all retained actions
+    pub fn replay_all(&self) -> MigrationState {
+        self.replay(self.actions.len())
+    }
+
+    /// Whether replaying the log reproduces the live state
+    pub fn verify(&self, live: &MigrationState) -> bool {
+        state_digest(&self.replay_all()) == state_digest(live)
+    }
+
+    /// Restart the log from the live state, spilling what was retained
+    pub fn rebuild_from(&mut self, live: &MigrationState) {
+        while let Some(entry) = self.actions.pop_front() {
+            self.queue_spill(entry.last_seq, serialized(&entry.action));
+        }
+        self.base = live.clone();
+        self.bytes = 0;
+        self.rebuilds += 1;
+    }
+
+    /// Verify against the live state and rebuild on a mismatch; returns whether it rebuilt
+    pub fn verify_or_rebuild(&mut self, live: &MigrationState) -> bool {
+        if self.verify(live) {
+            return false;
+        }
+        crate::console_warn!(
+            "[EventLog] Replay no longer matches the live state after {} actions; rebuilding from it",
+            self.total_recorded
+        );
+        self.rebuild_from(live);
+        true
+    }
+}
+
+impl Default for MigrationEventLog {
+    fn default() -> Self {
+        Self::new(MigrationState::default())
+    }
+}
+
+/// Persist a spill chunk to OPFS
+pub async fn write_spill_to_opfs(spill: &EventLogSpill) -> Result<(), String> {
+    let file_name = spill.file_name();
+    let app_dir = app_specific_dir()
+        .await
+        .map_err(|e| format!("OPFS unavailable: {:?}", e))?;
+    let dir = app_dir
+        .get_directory_handle_with_options(SPILL_DIR, &GetDirectoryHandleOptions { create: true })
+        .await
+        .map_err(|e| format!("Failed to open {}: {:?}", SPILL_DIR, e))?;
+    let mut file = dir
+        .get_file_handle_with_options(&file_name, &GetFileHandleOptions { create: true })
+        .await
+        .map_err(|e| format!("Failed to create {}: {:?}", file_name, e))?;
+    let mut writer = file
+        .create_writable_with_options(&CreateWritableOptions {
+            keep_existing_data: false,
+        })
+        .await
+        .map_err(|e| format!("Failed to open {} for writing: {:?}", file_name, e))?;
+    let mut contents = spill.lines.join("\n");
+    contents.push('\n');
+    writer
+        .write_at_cursor_pos(contents.into_bytes())
+        .await
+        .map_err(|e| format!("Failed to write {}: {:?}", file_name, e))?;
+    writer
+        .close()
+        .await
+        .map_err(|e| format!("Failed to finish {}: {:?}", file_name, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migration::types::{BlobProgress, ConsoleEntry};
+    use crate::utils::console_macros::ConsoleLevel;
+
+    #[test]
+    fn test_replay_matches_live_state() {
+        let mut log = MigrationEventLog::default();
+        let mut state = MigrationState::default();
+
+        log.apply(
+            &mut state,
+            MigrationAction::SetHandle("alice.test".to_string()),
+        );
+        log.apply(
+            &mut state,
+            MigrationAction::SetNewPdsUrl("https://pds.test".to_string()),
+        );
+        log.apply(
+            &mut state,
+            MigrationAction::SetHandle("bob.test".to_string()),
+        );
+
+        assert_eq!(log.len(), 3);
+        assert_eq!(log.replay_all().form1.handle, state.form1.handle);
+        assert_eq!(log.replay_all().form2.pds_url, "https://pds.test");
+
+        let earlier = log.replay(1);
+        assert_eq!(earlier.form1.handle, "alice.test");
+        assert!(earlier.form2.pds_url.is_empty());
+    }
+
+    #[test]
+    fn test_capacity_folds_oldest_actions_into_base() {
+        let mut log = MigrationEventLog::with_capacity(MigrationState::default(), 2);
+        for i in 0..5 {
+            log.record(MigrationAction::AddConsoleMessage(ConsoleEntry::new(
+                ConsoleLevel::Info,
+                "test",
+                format!("message {}", i),
+            )));
+        }
+
+        assert_eq!(log.len(), 2);
+        assert_eq!(log.total_recorded(), 5);
+
+        let base = log.replay(0);
+        assert_eq!(base.console_messages.len(), 3);
+        assert_eq!(log.replay_all().console_messages.len(), 5);
+
+        let spill = log.take_spill().expect("folded actions are spilled");
+        assert_eq!(spill.first_seq, 1);
+        assert_eq!(spill.lines.len(), 3);
+        assert!(log.take_spill().is_none());
+    }
+
+    #[test]
+    fn test_snapshots_compact_and_replay_to_the_same_state() {
+        let mut log = MigrationEventLog::default();
+        let mut state = MigrationState::default();
+        for processed in 1..=50 {
+            log.apply(
+                &mut state,
+                MigrationAction::SetBlobProgress(BlobProgress {
+                    total_blobs: 50,
+                    processed_blobs: processed,
+                    ..BlobProgress::default()
+                }),
+            );
+            log.apply(
+                &mut state,
+                MigrationAction::SetMigrationStep(format!("Blob {} of 50", processed)),
+            );
+        }
+        log.apply(
+            &mut state,
+            MigrationAction::AddConsoleMessage(ConsoleEntry::new(
+                ConsoleLevel::Info,
+                "test",
+                "blobs done",
+            )),
+        );
+        log.apply(&mut state, MigrationAction::SetMigrationStep("PLC".into()));
+
+        // A console message separates runs, so the last step is kept separately
+        assert_eq!(log.len(), 4);
+        assert_eq!(log.compacted_count(), 98);
+        assert_eq!(log.total_recorded(), 102);
+        assert!(log.verify(&state));
+        assert_eq!(log.replay_all().blob_progress.processed_blobs, 50);
+    }
+
+    #[test]
+    fn test_byte_budget_and_corruption_rebuild() {
+        let mut log = MigrationEventLog::with_limits(MigrationState::default(), 100, 200);
+        let mut state = MigrationState::default();
+        for i in 0..10 {
+            log.apply(
+                &mut state,
+                MigrationAction::SetHandle(format!("user{}.example.com", i)),
+            );
+        }
+        assert!(log.retained_bytes() <= 200);
+        assert!(log.len() < 10);
+        assert!(log.verify(&state));
+
+        // The live state moved on without the log seeing it
+        state.reduce_in_place(MigrationAction::SetMigrationStep("lost".into()));
+        assert!(log.verify_or_rebuild(&state));
+        assert_eq!(log.rebuild_count(), 1);
+        assert!(log.is_empty());
+        assert!(log.verify(&state));
+        assert_eq!(log.take_spill().map(|spill| spill.lines.len()), Some(10));
+    }
+}
@@ -2,10 +2,16 @@ use crate::migration::types::*;
 
 /// Validates that all required Form 3 fields are filled and passwords match
 pub fn validate_form3_complete(state: &MigrationState) -> bool {
+    // Copying into an existing account only needs its login
+    if state.form3.plan_variant.uses_existing_account() {
+        return !state.form3.handle.trim().is_empty() && !state.form3.password.trim().is_empty();
+    }
     !state.form3.handle.trim().is_empty()
         && !state.form3.password.trim().is_empty()
         && !state.form3.password_confirm.trim().is_empty()
         && !state.form3.email.trim().is_empty()
+        && (!state.signup_gating().requires_invite_code()
+            || !state.form3.invite_code.trim().is_empty())
         && state.validate_passwords() == PasswordValidation::Match
 }
 
@@ -38,6 +44,15 @@ pub fn get_form3_validation_message(state: &MigrationState) -> Option<String> {
         return Some("Please enter a handle for the new PDS".to_string());
     }
 
+    if state.form3.plan_variant.uses_existing_account() {
+        return state
+            .form3
+            .password
+            .trim()
+            .is_empty()
+            .then(|| "Please enter the password of the existing account".to_string());
+    }
+
     if state.form3.password.trim().is_empty() {
         return Some("Please enter a new password".to_string());
     }
@@ -50,6 +65,10 @@ pub fn get_form3_validation_message(state: &MigrationState) -> Option<String> {
         return Some("Please enter an email address".to_string());
     }
 
+    if state.signup_gating().requires_invite_code() && state.form3.invite_code.trim().is_empty() {
+        return Some("This PDS requires an invite code".to_string());
+    }
+
     match state.validate_passwords() {
         PasswordValidation::NoMatch => Some("Passwords do not match".to_string()),
         PasswordValidation::Match => None,
@@ -71,6 +90,7 @@ pub fn get_handle_validation_message(state: &MigrationState) -> Option<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::migration::plan::PlanVariant;
 
     #[test]
     fn test_validate_form3_complete() {
@@ -93,6 +113,40 @@ mod tests {
         assert!(!validate_form3_complete(&state));
     }
 
+    #[test]
+    fn test_invite_code_required_only_when_advertised() {
+        let mut state = MigrationState::default();
+        state.form3.handle = "testuser".to_string();
+        state.form3.password = "testpassword123".to_string();
+        state.form3.password_confirm = "testpassword123".to_string();
+        state.form3.email = "test@example.com".to_string();
+        state.form2.describe_response = Some(PdsDescribeResponse::success(
+            vec![],
+            None,
+            "did:web:pds.example.com".to_string(),
+            Some(true),
+            None,
+            None,
+        ));
+
+        assert!(!validate_form3_complete(&state));
+        assert_eq!(
+            get_form3_validation_message(&state).as_deref(),
+            Some("This PDS requires an invite code")
+        );
+
+        state.form3.invite_code = "pds-example-com-abcde".to_string();
+        assert!(validate_form3_complete(&state));
+
+        // An existing account needs neither an invite code nor an email
+        state.form3.invite_code.clear();
+        state.form3.email.clear();
+        state.form3.password_confirm.clear();
+        state.form3.plan_variant = PlanVariant::ContentCopy;
+        assert!(validate_form3_complete(&state));
+        assert_eq!(get_form3_validation_message(&state), None);
+    }
+
     #[test]
     fn test_validate_handle_availability() {
         let mut state = MigrationState::default();
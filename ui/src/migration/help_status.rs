@@ -0,0 +1,278 @@
+//! Shareable, privacy-scrubbed migration status for community helpers
+//!
+//! In "get help" mode the user shares a short string (or a link carrying it in
+//! the URL fragment) that a helper pastes into their own copy of the app to see
+//! where the migration is stuck: the current step, the timeline, progress
+//! counters, the latest errors and whether the watchdog thinks a step stalled.
+//! Handles, DIDs, email addresses, tokens and passwords are never included,
+//! and free text (errors, console lines) is scrubbed of them. Each status
+//! expires after [`HELP_STATUS_TTL_SECS`], so an old link stops working.
+//!
+//! Nothing is uploaded anywhere: the fragment part of a link never reaches a
+//! server, and the string is regenerated from the live state on every render.
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use super::plan::PlanVariant;
+use super::timeline::{TimelineEntry, TimelineStep};
+use super::types::{BlobProgress, FormStep, MigrationState, RepoProgress};
+use crate::utils::console_macros::ConsoleLevel;
+
+/// Marks a help status string and its format version
+pub const HELP_STATUS_PREFIX: &str = "tektite-help-v1.";
+
+/// How long a shared status stays loadable
+pub const HELP_STATUS_TTL_SECS: u64 = 30 * 60;
+
+/// URL fragment key carrying a status in shared links
+pub const HELP_STATUS_FRAGMENT: &str = "help=";
+
+/// Warning and error console lines included, newest last
+const RECENT_PROBLEM_LIMIT: usize = 10;
+
+const REDACTED: &str = "[redacted]";
+
+/// What a helper gets to see
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelpStatus {
+    pub created_at_secs: u64,
+    pub expires_at_secs: u64,
+    pub app_version: String,
+    pub form_step: FormStep,
+    pub plan_variant: PlanVariant,
+    pub is_migrating: bool,
+    pub migration_completed: bool,
+    pub migration_step: String,
+    pub migration_error: Option<String>,
+    /// Host of the PDS being migrated to; the account itself is not identified
+    pub new_pds_host: String,
+    pub timeline: Vec<TimelineEntry>,
+    pub repo_progress: RepoProgress,
+    pub blob_progress: BlobProgress,
+    pub stalled_step: Option<TimelineStep>,
+    pub stalled_for_secs: Option<u64>,
+    pub recent_problems: Vec<String>,
+}
+
+impl HelpStatus {
+    pub fn capture(state: &MigrationState, now_ms: u64) -> Self {
+        let scrubber = Scrubber::for_state(state);
+        let created_at_secs = now_ms / 1000;
+        let stall = state.stall(now_ms);
+
+        let mut timeline = state.timeline(now_ms);
+        for entry in &mut timeline {
+            entry.error = entry.error.as_deref().map(|e| scrubber.scrub(e));
+        }
+
+        let mut blob_progress = state.blob_progress.clone();
+        blob_progress.error = blob_progress.error.as_deref().map(|e| scrubber.scrub(e));
+        let mut repo_progress = state.repo_progress.clone();
+        repo_progress.error = repo_progress.error.as_deref().map(|e| scrubber.scrub(e));
+
+        let mut recent_problems: Vec<String> = state
+            .console_messages
+            .iter()
+            .rev()
+            .filter(|entry| matches!(entry.level, ConsoleLevel::Warn | ConsoleLevel::Error))
+            .take(RECENT_PROBLEM_LIMIT)
+            .map(|entry| scrubber.scrub(&entry.message))
+            .collect();
+        recent_problems.reverse();
+
+        Self {
+            created_at_secs,
+            expires_at_secs: created_at_secs + HELP_STATUS_TTL_SECS,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            form_step: state.current_step.clone(),
+            plan_variant: state.form3.plan_variant,
+            is_migrating: state.is_migrating,
+            migration_completed: state.migration_completed,
+            migration_step: scrubber.scrub(&state.migration_step),
+            migration_error: state.migration_error.as_deref().map(|e| scrubber.scrub(e)),
+            new_pds_host: host_of(&state.form2.pds_url),
+            timeline,
+            repo_progress,
+            blob_progress,
+            stalled_step: stall.as_ref().map(|info| info.step),
+            stalled_for_secs: stall.as_ref().map(|info| info.idle_ms / 1000),
+            recent_problems,
+        }
+    }
+
+    /// Compact string form: prefix plus URL-safe base64 of the JSON
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        format!(
+            "{}{}",
+            HELP_STATUS_PREFIX,
+            base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(json)
+        )
+    }
+
+    /// Parse a pasted string or link, refusing expired statuses
+    pub fn decode(input: &str, now_secs: u64) -> Result<Self, String> {
+        let input = input.trim();
+        let input = input
+            .rsplit_once(HELP_STATUS_FRAGMENT)
+            .map_or(input, |(_, encoded)| encoded);
+        let encoded = input
+            .strip_prefix(HELP_STATUS_PREFIX)
+            .ok_or("This is not a help status from this tool (or it's from a newer version)")?;
+        let json = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(encoded)
+            .map_err(|e| format!("The help status is damaged: {}", e))?;
+        let status: Self = serde_json::from_slice(&json)
+            .map_err(|e| format!("The help status is damaged: {}", e))?;
+        if now_secs > status.expires_at_secs {
+            return Err("This help status has expired; ask for a fresh one".to_string());
+        }
+        Ok(status)
+    }
+
+    /// Link to the app that opens this status, e.g. `https://example.com/#help=...`
+    pub fn share_link(&self, page_url: &str) -> String {
+        let base = page_url.split('#').next().unwrap_or(page_url);
+        format!("{}#{}{}", base, HELP_STATUS_FRAGMENT, self.encode())
+    }
+}
+
+/// Replaces identifying values in free text
+struct Scrubber {
+    identifiers: Vec<(String, &'static str)>,
+}
+
+impl Scrubber {
+    fn for_state(state: &MigrationState) -> Self {
+        let mut identifiers = vec![
+            (state.form1.handle.clone(), "<old-handle>"),
+            (state.form1.original_handle.clone(), "<old-handle>"),
+            (state.form3.handle.clone(), "<new-handle>"),
+            (state.form3.email.clone(), "<email>"),
+        ];
+        if let Some(session) = &state.new_pds_session {
+            identifiers.push((session.did.clone(), "<did>"));
+            identifiers.push((session.handle.clone(), "<new-handle>"));
+        }
+        if let Some(session) = state
+            .form1
+            .login_response
+            .as_ref()
+            .and_then(|response| response.session.as_ref())
+        {
+            identifiers.push((session.did.clone(), "<did>"));
+        }
+        identifiers.retain(|(value, _)| value.trim().len() > 2);
+        // Longest first so a handle isn't half-replaced by a shorter value
+        identifiers.sort_by_key(|(value, _)| std::cmp::Reverse(value.len()));
+        Self { identifiers }
+    }
+
+    fn scrub(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for (value, placeholder) in &self.identifiers {
+            text = text.replace(value.as_str(), placeholder);
+        }
+        text.split(' ')
+            .map(scrub_word)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Replace a DID, email or secret-looking word, keeping surrounding punctuation
+fn scrub_word(word: &str) -> String {
+    let is_punctuation =
+        |c: char| matches!(c, '(' | ')' | '[' | ']' | ',' | ';' | ':' | '"' | '\'');
+    let core = word.trim_matches(is_punctuation);
+    let replacement = if looks_like_secret(core) {
+        REDACTED
+    } else if core.starts_with("did:") {
+        "<did>"
+    } else if core.contains('@') && core.contains('.') {
+        "<email>"
+    } else {
+        return word.to_string();
+    };
+    let start = word.find(core).unwrap_or(0);
+    format!(
+        "{}{}{}",
+        &word[..start],
+        replacement,
+        &word[start + core.len()..]
+    )
+}
+
+/// JWTs and long opaque strings (tokens, codes) that could grant access
+fn looks_like_secret(word: &str) -> bool {
+    let word = word.trim_matches(|c: char| !c.is_ascii_alphanumeric());
+    let is_jwt = word.starts_with("eyJ") && word.matches('.').count() == 2;
+    let is_opaque = word.len() >= 32
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && word.chars().any(|c| c.is_ascii_digit())
+        && !word.starts_with("baf");
+    is_jwt || is_opaque
+}
+
+fn host_of(url: &str) -> String {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    without_scheme
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migration::types::MigrationAction;
+
+    fn state_with_secrets() -> MigrationState {
+        let mut state = MigrationState::default();
+        for action in [
+            MigrationAction::SetHandle("alice.bsky.social".to_string()),
+            MigrationAction::SetEmailAddress("alice@example.com".to_string()),
+            MigrationAction::SetNewPdsUrl("https://pds.example.net/".to_string()),
+            MigrationAction::SetMigrationError(Some(
+                "alice.bsky.social (did:plc:abc123) rejected token eyJhbGci.eyJzdWIi.c2ln"
+                    .to_string(),
+            )),
+        ] {
+            state.reduce_in_place(action);
+        }
+        state
+    }
+
+    #[test]
+    fn test_capture_scrubs_identifiers_and_tokens() {
+        let status = HelpStatus::capture(&state_with_secrets(), 1_000_000);
+        let json = serde_json::to_string(&status).unwrap();
+
+        assert!(!json.contains("alice"));
+        assert!(!json.contains("did:plc:abc123"));
+        assert!(!json.contains("eyJhbGci"));
+        assert_eq!(status.new_pds_host, "pds.example.net");
+        assert_eq!(
+            status.migration_error.as_deref(),
+            Some("<old-handle> (<did>) rejected token [redacted]")
+        );
+    }
+
+    #[test]
+    fn test_round_trip_through_link_and_expiry() {
+        let status = HelpStatus::capture(&state_with_secrets(), 1_000_000);
+        let link = status.share_link("https://migrate.example/?x=1#old");
+        assert!(link.starts_with("https://migrate.example/?x=1#help=tektite-help-v1."));
+
+        assert_eq!(HelpStatus::decode(&link, 1_000).unwrap(), status);
+        assert_eq!(HelpStatus::decode(&status.encode(), 1_000).unwrap(), status);
+        assert!(HelpStatus::decode(&link, 1_000 + HELP_STATUS_TTL_SECS + 1).is_err());
+        assert!(HelpStatus::decode("tektite-help-v1.%%%", 1_000).is_err());
+        assert!(HelpStatus::decode("something else", 1_000).is_err());
+    }
+}
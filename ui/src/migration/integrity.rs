@@ -0,0 +1,114 @@
+//! Content integrity checks for migrated data
+//!
+//! Re-hashes blobs and CAR blocks against their CIDs. Only compiled with the
+//! `hash-verification` feature, which keeps sha2 out of lean builds.
+
+use cid::Cid;
+use sha2::{Digest, Sha256};
+
+/// Check data against the sha2-256 digest in its CID
+pub fn verify_blob_bytes(cid: &Cid, data: &[u8]) -> Result<(), String> {
+    const SHA2_256: u64 = 0x12;
+    if cid.hash().code() != SHA2_256 {
+        return Err(format!(
+            "unsupported multihash code 0x{:x}",
+            cid.hash().code()
+        ));
+    }
+    if Sha256::digest(data).as_slice() != cid.hash().digest() {
+        return Err("content hash does not match CID".to_string());
+    }
+    Ok(())
+}
+
+/// Re-hash every block in a CARv1 file; returns the number of verified blocks
+pub fn verify_car_blocks(car: &[u8]) -> Result<usize, String> {
+    let mut pos = 0;
+    let header_len = read_uvarint(car, &mut pos)? as usize;
+    pos = pos
+        .checked_add(header_len)
+        .filter(|end| *end <= car.len())
+        .ok_or("truncated CAR header")?;
+
+    let mut blocks = 0;
+    while pos < car.len() {
+        let section_len = read_uvarint(car, &mut pos)? as usize;
+        let end = pos
+            .checked_add(section_len)
+            .filter(|end| *end <= car.len())
+            .ok_or("truncated CAR section")?;
+
+        let mut section = &car[pos..end];
+        let cid = Cid::read_bytes(&mut section).map_err(|e| format!("invalid block CID: {}", e))?;
+        verify_blob_bytes(&cid, section).map_err(|e| format!("block {}: {}", cid, e))?;
+
+        blocks += 1;
+        pos = end;
+    }
+    Ok(blocks)
+}
+
+fn read_uvarint(data: &[u8], pos: &mut usize) -> Result<u64, String> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *data.get(*pos).ok_or("truncated varint")?;
+        *pos += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err("varint overflow".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cid::multihash::Multihash;
+
+    const DAG_CBOR: u64 = 0x71;
+
+    fn cid_for(data: &[u8]) -> Cid {
+        let digest = Sha256::digest(data);
+        Cid::new_v1(DAG_CBOR, Multihash::wrap(0x12, &digest).unwrap())
+    }
+
+    fn push_uvarint(out: &mut Vec<u8>, mut value: u64) {
+        while value >= 0x80 {
+            out.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    fn car_with(blocks: &[(Cid, &[u8])]) -> Vec<u8> {
+        let header = b"header";
+        let mut car = Vec::new();
+        push_uvarint(&mut car, header.len() as u64);
+        car.extend_from_slice(header);
+        for (cid, data) in blocks {
+            let cid_bytes = cid.to_bytes();
+            push_uvarint(&mut car, (cid_bytes.len() + data.len()) as u64);
+            car.extend_from_slice(&cid_bytes);
+            car.extend_from_slice(data);
+        }
+        car
+    }
+
+    #[test]
+    fn test_verify_blob_bytes() {
+        let cid = cid_for(b"hello");
+        assert!(verify_blob_bytes(&cid, b"hello").is_ok());
+        assert!(verify_blob_bytes(&cid, b"tampered").is_err());
+    }
+
+    #[test]
+    fn test_verify_car_blocks() {
+        let good = car_with(&[(cid_for(b"a"), b"a"), (cid_for(b"bb"), b"bb")]);
+        assert_eq!(verify_car_blocks(&good), Ok(2));
+
+        let bad = car_with(&[(cid_for(b"a"), b"a"), (cid_for(b"bb"), b"xx")]);
+        assert!(verify_car_blocks(&bad).is_err());
+        assert!(verify_car_blocks(&good[..good.len() - 1]).is_err());
+    }
+}
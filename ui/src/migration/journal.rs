@@ -0,0 +1,386 @@
+//! Where the migration was, for picking it back up after the page dies
+//!
+//! A panic in the WASM module leaves the page frozen, and a reload starts from
+//! an empty form. The journal keeps the last known position — the form step,
+//! the timeline step that was running and its progress text — and the form
+//! inputs needed to get back there. It is updated in memory on every dispatch,
+//! written to `sessionStorage` (it survives a reload of the same tab) on
+//! lifecycle transitions and by the panic hook, and never holds passwords or
+//! tokens: the sessions themselves are already kept by [`LocalStorageManager`]
+//! and are read back from there when resuming.
+//!
+//! Stored positions form a short chain: each entry carries the MAC of the one
+//! before it and an HMAC-SHA256 over that link and its own contents, keyed by
+//! a random key that lives in memory and in the tab's session storage. Loading
+//! keeps the entries up to the first one that fails to check and discards the
+//! rest, so a corrupted or hand-edited tail can't resume the migration from a
+//! position it never reached. Without the `hash-verification` feature the
+//! entries carry no MAC and only the chain links are checked.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+use super::plan::PlanVariant;
+use super::storage::LocalStorageManager;
+use super::timeline::{StepStatus, TimelineStep};
+use super::types::{FormStep, MigrationAction, MigrationState, PdsLoginResponse};
+use crate::console_warn;
+use crate::utils::platform::{random_hex, storage_delete, storage_get, storage_set, StorageArea};
+
+/// Session storage key for the journal chain
+const JOURNAL_STORAGE_KEY: &str = "migration_journal";
+
+/// Session storage key for the key the chain's MACs are computed with
+const JOURNAL_KEY_STORAGE_KEY: &str = "migration_journal_key";
+
+/// Entries kept in the chain; older ones are dropped
+const JOURNAL_CHAIN_LEN: usize = 8;
+
+/// Latest entry, kept in memory so recording doesn't touch storage on every dispatch
+static LATEST: Mutex<Option<MigrationJournal>> = Mutex::new(None);
+
+/// MAC key for this tab, once read or created
+static JOURNAL_KEY: Mutex<Option<String>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MigrationJournal {
+    pub recorded_at_secs: u64,
+    pub form_step: FormStep,
+    /// Timeline step that was running or had failed
+    pub timeline_step: Option<TimelineStep>,
+    /// Progress text shown at the time
+    pub detail: String,
+    pub handle: String,
+    pub new_pds_url: String,
+    pub new_handle: String,
+    pub email: String,
+    pub invite_code: String,
+    pub plan_variant: PlanVariant,
+}
+
+impl MigrationJournal {
+    pub fn capture(state: &MigrationState, now_secs: u64) -> Self {
+        let handle = if state.form1.original_handle.is_empty() {
+            state.form1.handle.clone()
+        } else {
+            state.form1.original_handle.clone()
+        };
+        let timeline_step = state
+            .timeline_statuses()
+            .iter()
+            .zip(TimelineStep::ALL)
+            .find(|(status, _)| matches!(status, StepStatus::Active | StepStatus::Failed))
+            .map(|(_, step)| step);
+
+        Self {
+            recorded_at_secs: now_secs,
+            form_step: state.current_step.clone(),
+            timeline_step,
+            detail: state.migration_step.clone(),
+            handle,
+            new_pds_url: state.form2.pds_url.clone(),
+            new_handle: state.form3.handle.clone(),
+            email: state.form3.email.clone(),
+            invite_code: state.form3.invite_code.clone(),
+            plan_variant: state.form3.plan_variant,
+        }
+    }
+
+    /// Human-readable position, e.g. "Migration details, Blobs: Uploading blob 12 of 300"
+    pub fn step_label(&self) -> String {
+        let form = match self.form_step {
+            FormStep::Login => "Login",
+            FormStep::SelectPds => "Choose a PDS",
+            FormStep::MigrationDetails => "Migration details",
+            FormStep::PlcVerification => "Identity update",
+        };
+        let mut label = match self.timeline_step {
+            Some(step) => format!("{}, {}", form, step.label()),
+            None => form.to_string(),
+        };
+        if !self.detail.is_empty() {
+            label.push_str(": ");
+            label.push_str(&self.detail);
+        }
+        label
+    }
+
+    /// Furthest form step that can be shown again with what was kept; the PDS
+    /// has to be confirmed again (which re-reads its domains) unless the
+    /// migration had already reached the identity update
+    pub fn resume_step(&self, has_old_session: bool, has_plc_operation: bool) -> FormStep {
+        match self.form_step {
+            _ if !has_old_session => FormStep::Login,
+            FormStep::PlcVerification if has_plc_operation => FormStep::PlcVerification,
+            FormStep::Login => FormStep::Login,
+            _ => FormStep::SelectPds,
+        }
+    }
+
+    /// Actions that re-fill the forms and return to [`Self::resume_step`],
+    /// using the sessions and PLC operation kept in local storage
+    pub fn actions(&self) -> Vec<MigrationAction> {
+        let mut actions = vec![
+            MigrationAction::SetHandle(self.handle.clone()),
+            MigrationAction::SetOriginalHandle(self.handle.clone()),
+        ];
+        let old_session = LocalStorageManager::get_old_session().ok();
+        if let Some(session) = &old_session {
+            actions.push(MigrationAction::SetLoginResponse(Some(PdsLoginResponse {
+                success: true,
+                message: "Restored after a reload".to_string(),
+                did: Some(session.did.clone()),
+                session: Some(session.clone()),
+            })));
+            actions.push(MigrationAction::SetSessionStored(true));
+        }
+        actions.extend([
+            MigrationAction::SetNewPdsUrl(self.new_pds_url.clone()),
+            MigrationAction::SetNewHandle(self.new_handle.clone()),
+            MigrationAction::SetEmailAddress(self.email.clone()),
+            MigrationAction::SetInviteCode(self.invite_code.clone()),
+            MigrationAction::SetPlanVariant(self.plan_variant),
+        ]);
+        if let Ok(session) = LocalStorageManager::get_new_session() {
+            actions.push(MigrationAction::SetNewPdsSession(Some(session)));
+        }
+        let plc_operation = LocalStorageManager::get_plc_operation().ok();
+        if let Some(operation) = &plc_operation {
+            actions.push(MigrationAction::SetPlcUnsigned(operation.unsigned.clone()));
+        }
+        actions.push(MigrationAction::SetCurrentStep(
+            self.resume_step(old_session.is_some(), plc_operation.is_some()),
+        ));
+        actions
+    }
+}
+
+/// Note the current position in memory; cheap enough to call on every dispatch
+pub fn record_journal(state: &MigrationState, now_secs: u64) {
+    if let Ok(mut latest) = LATEST.lock() {
+        *latest = Some(MigrationJournal::capture(state, now_secs));
+    }
+}
+
+/// One stored position, linked to the entry before it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub seq: u64,
+    /// MAC of the previous entry, empty for the first
+    pub prev: String,
+    pub journal: MigrationJournal,
+    /// HMAC-SHA256 over `seq`, `prev` and the journal; `None` when built without hashing
+    pub mac: Option<String>,
+}
+
+impl JournalEntry {
+    fn new(key: &str, previous: Option<&JournalEntry>, journal: MigrationJournal) -> Self {
+        let seq = previous.map(|entry| entry.seq + 1).unwrap_or(0);
+        let prev = previous.map(JournalEntry::link).unwrap_or_default();
+        let mac = entry_mac(key, seq, &prev, &journal);
+        Self {
+            seq,
+            prev,
+            journal,
+            mac,
+        }
+    }
+
+    /// What the next entry records as `prev`
+    fn link(&self) -> String {
+        self.mac
+            .clone()
+            .unwrap_or_else(|| format!("seq:{}", self.seq))
+    }
+
+    fn follows(&self, previous: Option<&JournalEntry>) -> bool {
+        match previous {
+            Some(previous) => self.seq == previous.seq + 1 && self.prev == previous.link(),
+            None => true,
+        }
+    }
+
+    fn is_authentic(&self, key: &str) -> bool {
+        self.mac == entry_mac(key, self.seq, &self.prev, &self.journal)
+    }
+}
+
+#[cfg(feature = "hash-verification")]
+fn entry_mac(key: &str, seq: u64, prev: &str, journal: &MigrationJournal) -> Option<String> {
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).ok()?;
+    mac.update(&seq.to_be_bytes());
+    mac.update(prev.as_bytes());
+    mac.update(&serde_json::to_vec(journal).ok()?);
+    Some(
+        mac.finalize()
+            .into_bytes()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect(),
+    )
+}
+
+#[cfg(not(feature = "hash-verification"))]
+fn entry_mac(_key: &str, _seq: u64, _prev: &str, _journal: &MigrationJournal) -> Option<String> {
+    None
+}
+
+/// Longest prefix of `entries` that is linked in order and carries valid MACs
+pub fn verified_prefix(entries: &[JournalEntry], key: &str) -> usize {
+    let mut previous = None;
+    for (index, entry) in entries.iter().enumerate() {
+        if !entry.follows(previous) || !entry.is_authentic(key) {
+            return index;
+        }
+        previous = Some(entry);
+    }
+    entries.len()
+}
+
+/// This tab's MAC key, created on first use and kept in session storage so
+/// entries written before a reload still check
+fn journal_key() -> Option<String> {
+    let mut key = JOURNAL_KEY.try_lock().ok()?;
+    if key.is_none() {
+        *key = Some(
+            storage_get(StorageArea::Session, JOURNAL_KEY_STORAGE_KEY).unwrap_or_else(|_| {
+                let created = random_hex(32);
+                let _ = storage_set(StorageArea::Session, JOURNAL_KEY_STORAGE_KEY, &created);
+                created
+            }),
+        );
+    }
+    key.clone()
+}
+
+/// Stored chain cut to its verified entries, rewriting storage if a tail was dropped
+fn verified_chain(key: &str) -> Vec<JournalEntry> {
+    let mut entries: Vec<JournalEntry> =
+        storage_get(StorageArea::Session, JOURNAL_STORAGE_KEY).unwrap_or_default();
+    let valid = verified_prefix(&entries, key);
+    if valid < entries.len() {
+        console_warn!(
+            "[Journal] Discarding {} journal entries that failed their integrity check",
+            entries.len() - valid
+        );
+        entries.truncate(valid);
+        let _ = storage_set(StorageArea::Session, JOURNAL_STORAGE_KEY, &entries);
+    }
+    entries
+}
+
+/// Append the latest position to the chain in session storage
+///
+/// Called from the panic hook too, so it never waits on a lock.
+pub fn persist_journal() {
+    let Ok(latest) = LATEST.try_lock() else {
+        return;
+    };
+    let (Some(journal), Some(key)) = (latest.as_ref(), journal_key()) else {
+        return;
+    };
+    let mut entries = verified_chain(&key);
+    if entries.last().map(|entry| &entry.journal) == Some(journal) {
+        return;
+    }
+    let entry = JournalEntry::new(&key, entries.last(), journal.clone());
+    entries.push(entry);
+    if entries.len() > JOURNAL_CHAIN_LEN {
+        entries.drain(..entries.len() - JOURNAL_CHAIN_LEN);
+    }
+    let _ = storage_set(StorageArea::Session, JOURNAL_STORAGE_KEY, &entries);
+}
+
+/// The latest position, from memory or, after a reload, the last entry of the
+/// stored chain that passes its integrity check
+pub fn load_journal() -> Option<MigrationJournal> {
+    LATEST
+        .try_lock()
+        .ok()
+        .and_then(|latest| latest.clone())
+        .or_else(|| {
+            let key = journal_key()?;
+            verified_chain(&key).pop().map(|entry| entry.journal)
+        })
+}
+
+pub fn clear_journal() {
+    if let Ok(mut latest) = LATEST.lock() {
+        *latest = None;
+    }
+    storage_delete(StorageArea::Session, JOURNAL_STORAGE_KEY);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_captures_position_and_picks_resume_step() {
+        let mut state = MigrationState::default();
+        state.reduce_in_place(MigrationAction::SetHandle("alice.old.example".to_string()));
+        state.reduce_in_place(MigrationAction::SetNewPdsUrl(
+            "https://new.example".to_string(),
+        ));
+        state.reduce_in_place(MigrationAction::SetSessionStored(true));
+        state.reduce_in_place(MigrationAction::SetCurrentStep(FormStep::MigrationDetails));
+        state.reduce_in_place(MigrationAction::SetMigrating(true));
+        state.reduce_in_place(MigrationAction::SetMigrationStep(
+            "Creating account".to_string(),
+        ));
+
+        let journal = MigrationJournal::capture(&state, 1_000);
+        assert_eq!(journal.handle, "alice.old.example");
+        assert_eq!(journal.timeline_step, Some(TimelineStep::Account));
+        assert_eq!(
+            journal.step_label(),
+            "Migration details, Account: Creating account"
+        );
+
+        assert_eq!(journal.resume_step(false, false), FormStep::Login);
+        assert_eq!(journal.resume_step(true, false), FormStep::SelectPds);
+        let at_plc = MigrationJournal {
+            form_step: FormStep::PlcVerification,
+            ..journal.clone()
+        };
+        assert_eq!(at_plc.resume_step(true, true), FormStep::PlcVerification);
+        assert_eq!(at_plc.resume_step(true, false), FormStep::SelectPds);
+
+        record_journal(&state, 1_000);
+        persist_journal();
+        assert_eq!(load_journal(), Some(journal.clone()));
+        assert!(matches!(
+            journal.actions().last(),
+            Some(MigrationAction::SetCurrentStep(_))
+        ));
+        clear_journal();
+        assert_eq!(load_journal(), None);
+    }
+
+    #[cfg(feature = "hash-verification")]
+    #[test]
+    fn test_chain_drops_tampered_tail() {
+        let journal = MigrationJournal::capture(&MigrationState::default(), 1);
+        let later = MigrationJournal {
+            recorded_at_secs: 2,
+            ..journal.clone()
+        };
+        let first = JournalEntry::new("key", None, journal.clone());
+        let second = JournalEntry::new("key", Some(&first), later.clone());
+        let third = JournalEntry::new("key", Some(&second), later.clone());
+        let mut chain = vec![first, second, third];
+        assert_eq!(verified_prefix(&chain, "key"), 3);
+        assert_eq!(verified_prefix(&chain, "other key"), 0);
+
+        chain[1].journal.form_step = FormStep::PlcVerification;
+        assert_eq!(verified_prefix(&chain, "key"), 1);
+
+        chain[1].journal = later;
+        chain.remove(2);
+        chain.push(JournalEntry::new("key", None, journal));
+        assert_eq!(verified_prefix(&chain, "key"), 2);
+    }
+}
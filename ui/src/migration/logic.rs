@@ -1,24 +1,35 @@
 //! Client-side migration logic using DNS-over-HTTPS and direct PDS operations
 //! This replaces server-side functions with browser-based implementations
-use crate::migration::steps::blob::execute_streaming_blob_migration;
+#[cfg(feature = "web")]
+use crate::services::client::auth::check_session_identity;
 #[cfg(feature = "web")]
 use crate::services::client::{
-    ClientCreateAccountRequest, ClientSessionCredentials, JwtUtils, MigrationClient,
+    clock_skew, idempotency_key, run_bandwidth_preflight, run_body_limit_preflight,
+    run_route_preflight, run_target_health_check, server_now_secs, ClientCreateAccountRequest,
+    ClientSessionCredentials, JwtUtils, MigrationClient, PdsApi, TokenStatus, TransferDirection,
 };
+#[cfg(feature = "web")]
+use crate::services::config::{get_global_config, TargetHostPolicy};
+#[cfg(feature = "web")]
+use crate::services::streaming::{detect_storage_capabilities, AccountSizeEstimate};
 // use reqwest::Client;
 use dioxus::prelude::*;
 // Import console macros from our crate
-use crate::{console_error, console_info, console_warn};
+use crate::utils::handle::{normalize_handle, normalize_identifier, normalize_pds_url};
+use crate::{console_error, console_info, console_warn, console_warn_with_dispatch};
 
 use crate::migration::{
-    account_operations::{check_account_status_client_side, create_account_client_side},
-    steps::{
-        plc::setup_plc_transition_client_side, preferences::migrate_preferences_client_side,
-        repository::migrate_repository_client_side,
+    account_operations::{
+        check_account_status_client_side, check_minimal_flow_capacity, create_account_client_side,
+        handle_target_auto_activation,
     },
+    orchestrator::execute_plan,
+    plan::{MigrationPlan, PlanStep, PlanVariant},
+    resume_reconcile::reconcile_with_server,
     storage::LocalStorageManager,
-    types::{MigrationAction, MigrationState},
-    validation::verify_and_complete_blob_migration,
+    types::{
+        is_existing_did_rejected, is_signup_verification_error, MigrationAction, MigrationState,
+    },
 };
 
 /// Client-side migration execution
@@ -29,6 +40,19 @@ pub async fn execute_migration_client_side(
 ) {
     console_info!("[Migration] Starting client-side migration process");
 
+    // Nothing may reach a target the deployment rules out, including the
+    // preflight probes and logins below; the URL can come from a transfer or a
+    // resumed journal without passing the PDS form's check
+    let plan = MigrationPlan::for_variant(state.form3.plan_variant);
+    if plan.needs_target_account() {
+        if let Err(error) = allowed_target_url(&get_global_config().target_hosts, &state) {
+            console_error!("[Migration] Refusing to start: {}", error);
+            dispatch.call(MigrationAction::SetMigrationError(Some(error)));
+            dispatch.call(MigrationAction::SetMigrating(false));
+            return;
+        }
+    }
+
     let migration_client = MigrationClient::new();
 
     // Step 1: Get old PDS session from localStorage
@@ -64,22 +88,138 @@ pub async fn execute_migration_client_side(
     let old_session = LocalStorageManager::session_to_client(&old_session_api);
 
     // Check if token is expired or needs refresh
-    if JwtUtils::is_expired(&old_session.access_jwt) {
-        console_error!("[Migration] Old PDS session token is expired");
-        dispatch.call(MigrationAction::SetMigrationError(Some(
-            "Session token has expired. Please log in again.".to_string(),
-        )));
+    match JwtUtils::status(&old_session.access_jwt) {
+        TokenStatus::Valid => {}
+        TokenStatus::NeedsRefresh => {
+            console_warn!(
+                "[Migration] Old PDS session token needs refresh, but continuing with migration"
+            );
+        }
+        status @ (TokenStatus::Expired | TokenStatus::Unknown) => {
+            // Some PDS forks issue tokens we cannot judge locally; let the PDS decide
+            if !get_global_config().session.server_side_validation {
+                console_error!("[Migration] Old PDS session token is expired or unreadable");
+                dispatch.call(MigrationAction::SetMigrationError(Some(
+                    "Session token has expired. Please log in again.".to_string(),
+                )));
+                dispatch.call(MigrationAction::SetMigrating(false));
+                return;
+            }
+
+            console_warn!(
+                "{}",
+                format!(
+                    "[Migration] Old PDS session token status is {:?}, validating with PDS",
+                    status
+                )
+            );
+            match migration_client
+                .pds_client
+                .validate_session(&old_session)
+                .await
+            {
+                Ok(true) => {
+                    console_info!("[Migration] Old PDS accepted the session token");
+                }
+                Ok(false) => {
+                    console_error!("[Migration] Old PDS session token is expired");
+                    dispatch.call(MigrationAction::SetMigrationError(Some(
+                        "Session token has expired. Please log in again.".to_string(),
+                    )));
+                    dispatch.call(MigrationAction::SetMigrating(false));
+                    return;
+                }
+                Err(e) => {
+                    console_error!(
+                        "{}",
+                        format!("[Migration] Failed to validate old PDS session: {}", e)
+                    );
+                    dispatch.call(MigrationAction::SetMigrationError(Some(format!(
+                        "Could not validate session with the PDS: {}",
+                        e
+                    ))));
+                    dispatch.call(MigrationAction::SetMigrating(false));
+                    return;
+                }
+            }
+        }
+    }
+
+    // Make sure the session is the account the user entered, on the PDS its DID lists
+    let identifier = if state.form1.original_handle.is_empty() {
+        normalize_identifier(&state.form1.handle)
+    } else {
+        state.form1.original_handle.clone()
+    };
+    if let Err(e) = verify_old_session_identity(&migration_client, &identifier, &old_session).await
+    {
+        console_error!("{}", format!("[Migration] Refusing to start: {}", e));
+        dispatch.call(MigrationAction::SetMigrationError(Some(e)));
         dispatch.call(MigrationAction::SetMigrating(false));
         return;
-    } else if JwtUtils::needs_refresh(&old_session.access_jwt) {
-        console_warn!(
-            "[Migration] Old PDS session token needs refresh, but continuing with migration"
+    }
+
+    // Plans that only read from the old PDS skip account setup on the target
+    if !plan.needs_target_account() {
+        console_info!(
+            "[Migration] {:?} plan writes nothing to the new PDS; skipping account setup",
+            plan.variant
         );
+        run_content_plan(&plan, &state, &dispatch, &old_session, None).await;
+        return;
+    }
+
+    // Content copy writes into an account the user already created; no
+    // service auth or createAccount, and the identity stays where it is
+    if plan.variant.uses_existing_account() {
+        match login_to_existing_account(
+            &migration_client.pds_client,
+            &get_global_config().target_hosts,
+            &state,
+            &old_session,
+        )
+        .await
+        {
+            Ok(session) => {
+                if let Err(error) = LocalStorageManager::store_client_session_as_new(&session) {
+                    console_warn!(
+                        "{}",
+                        format!(
+                            "[Migration] Failed to store existing session: {}",
+                            error.to_string()
+                        )
+                    );
+                }
+                dispatch.call(MigrationAction::SetNewPdsSession(Some((&session).into())));
+                run_content_plan(&plan, &state, &dispatch, &old_session, Some(&session)).await;
+            }
+            Err(error) => {
+                console_error!("[Migration] {}", error);
+                dispatch.call(MigrationAction::SetMigrationError(Some(error)));
+                dispatch.call(MigrationAction::SetMigrating(false));
+            }
+        }
+        return;
+    }
+
+    // Browsers without OPFS or IndexedDB can only take small accounts; refuse
+    // before creating anything on the target
+    if detect_storage_capabilities().await.requires_minimal_flow() {
+        console_warn!("[Migration] No OPFS or IndexedDB available; using the minimal flow");
+        dispatch.call(MigrationAction::SetMigrationStep(
+            "Checking whether this account fits the limited storage of this browser...".to_string(),
+        ));
+        if let Err(error) = check_minimal_flow_capacity(&old_session).await {
+            console_error!("[Migration] {}", error);
+            dispatch.call(MigrationAction::SetMigrationError(Some(error)));
+            dispatch.call(MigrationAction::SetMigrating(false));
+            return;
+        }
     }
 
     // Step 2: Get target PDS DID from form2 (via describe server)
     console_info!("[Migration] Step 2: Getting target PDS DID");
-    let target_pds_url = state.form2.pds_url.clone();
+    let target_pds_url = normalize_pds_url(&state.form2.pds_url);
     if target_pds_url.is_empty() {
         console_error!("[Migration] No target PDS URL specified");
         dispatch.call(MigrationAction::SetMigrationError(Some(
@@ -89,6 +229,26 @@ pub async fn execute_migration_client_side(
         return;
     }
 
+    // Preflight: time each route so DNS and blob fetches use the fastest ones
+    dispatch.call(MigrationAction::SetMigrationStep(
+        "Measuring latency to the PDSes and DNS providers...".to_string(),
+    ));
+    let route_report = run_route_preflight(
+        &old_session.pds,
+        &target_pds_url,
+        &get_global_config().blob.mirror_urls,
+    )
+    .await;
+    console_info!(
+        "[Migration] Preflight: DoH order {:?}, blob source order {:?}",
+        route_report.doh_order,
+        route_report.blob_source_order
+    );
+    // Logins and the preflight requests have shown us the servers' clock by now
+    if let Some(warning) = clock_skew().and_then(|skew| skew.warning()) {
+        console_warn_with_dispatch!(dispatch, "[Migration] {}", warning);
+    }
+
     // NEWBOLD.md Step: goat pds describe $NEWPDSHOST (line 11)
     // Get target PDS DID by calling the describe server endpoint
     // This implements: goat pds describe https://bsky.social
@@ -96,23 +256,16 @@ pub async fn execute_migration_client_side(
         "Getting target PDS information...".to_string(),
     ));
 
-    let target_pds_did = match migration_client
+    // Shares the answer the PDS form showed, so the DID used for service auth
+    // is the one the user saw
+    let (target_pds_did, target_describe) = match migration_client
         .pds_client
-        .describe_server(&target_pds_url)
+        .server_info(&target_pds_url)
         .await
     {
-        Ok(response) => {
-            if let Some(did) = response.get("did").and_then(|d| d.as_str()) {
-                console_info!("{}", format!("[Migration] Target PDS DID: {}", did));
-                did.to_string()
-            } else {
-                console_error!("[Migration] No DID found in PDS describe response");
-                dispatch.call(MigrationAction::SetMigrationError(Some(
-                    "Target PDS does not provide DID information".to_string(),
-                )));
-                dispatch.call(MigrationAction::SetMigrating(false));
-                return;
-            }
+        Ok(info) => {
+            console_info!("{}", format!("[Migration] Target PDS DID: {}", info.did));
+            (info.did, info.response)
         }
         Err(e) => {
             console_error!(
@@ -165,16 +318,32 @@ pub async fn execute_migration_client_side(
     ));
 
     // Use the PDS URL from form 2 (user already provided it)
-    let new_pds_url = state.form2.pds_url.clone();
+    let new_pds_url = normalize_pds_url(&state.form2.pds_url);
+
+    // The new PDS expects the canonical form; a Unicode handle is registered in punycode
+    let new_handle = match normalize_handle(&state.form3.handle) {
+        Ok(handle) => handle,
+        Err(e) => {
+            console_error!("{}", format!("[Migration] Invalid new handle: {}", e));
+            dispatch.call(MigrationAction::SetMigrationError(Some(format!(
+                "Invalid new handle: {}",
+                e
+            ))));
+            dispatch.call(MigrationAction::SetMigrating(false));
+            return;
+        }
+    };
 
     // NEWBOLD.md Step: goat account login --pds-host $NEWPDSHOST -u $ACCOUNTDID -p $NEWPASSWORD (line 52)
     // Try to login first to check if account already exists
     // Implements: goat account login --pds-host $NEWPDSHOST -u $ACCOUNTDID -p $NEWPASSWORD
     let login_result = migration_client
         .pds_client
-        .try_login_before_creation(&state.form3.handle, &state.form3.password, &new_pds_url)
+        .try_login_before_creation(&new_handle, &state.form3.password, &new_pds_url)
         .await;
 
+    // Set when the account is left over from an earlier attempt
+    let mut resuming = false;
     let new_session = match login_result {
         Ok(login_response) => {
             if login_response.success && login_response.session.is_some() {
@@ -185,6 +354,28 @@ pub async fn execute_migration_client_side(
                 ));
 
                 let existing_session = login_response.session.unwrap();
+                dispatch.call(MigrationAction::SetReservedSigningKey(None));
+
+                // An account created by hand has a DID of its own; importing the
+                // repository into it would fail, and PLC would point at the wrong place
+                if existing_session.did != old_session.did {
+                    console_error!(
+                        "[Migration] Existing account {} has a different DID than {}",
+                        existing_session.did,
+                        old_session.did
+                    );
+                    dispatch.call(MigrationAction::SetMigrationError(Some(format!(
+                        "An account for {} already exists on the new PDS with a different DID ({}). \
+                         A full migration needs an account created with your current DID. Either \
+                         delete that account, or choose the \"{}\" plan to copy your posts and \
+                         media into it; your followers and identity stay with the old account.",
+                        new_handle,
+                        existing_session.did,
+                        PlanVariant::ContentCopy.label()
+                    ))));
+                    dispatch.call(MigrationAction::SetMigrating(false));
+                    return;
+                }
 
                 // Store the existing session for use in migration
                 if let Err(error) =
@@ -201,6 +392,7 @@ pub async fn execute_migration_client_side(
                 dispatch.call(MigrationAction::SetNewPdsSession(Some(
                     (&existing_session).into(),
                 )));
+                resuming = true;
 
                 existing_session
             } else {
@@ -212,30 +404,65 @@ pub async fn execute_migration_client_side(
                     "Creating account on new PDS...".to_string(),
                 ));
 
+                // A client-built PLC operation needs the key the account is created
+                // with, and the PDS only hands that out before createAccount
+                let reserved_signing_key = if old_session.did.starts_with("did:plc:") {
+                    match migration_client
+                        .pds_client
+                        .reserve_signing_key(&new_pds_url, &old_session.did)
+                        .await
+                    {
+                        Ok(key) => Some(key),
+                        Err(e) => {
+                            console_warn!("[Migration] Could not reserve a signing key: {}", e);
+                            None
+                        }
+                    }
+                } else {
+                    None
+                };
+
                 let create_account_request = ClientCreateAccountRequest {
                     did: old_session.did.clone(),
-                    handle: state.form3.handle.clone(),
+                    handle: new_handle.clone(),
                     password: state.form3.password.clone(),
                     email: state.form3.email.clone(),
-                    invite_code: if state.form3.invite_code.trim().is_empty() {
+                    // Gate-verified servers don't take invite codes; don't send a stale one
+                    invite_code: if !state.signup_gating().shows_invite_code()
+                        || state.form3.invite_code.trim().is_empty()
+                    {
                         None
                     } else {
                         Some(state.form3.invite_code.clone())
                     },
                     service_auth_token: Some(service_auth_token),
                     verification_code: state.form3.verification_code.clone(),
+                    verification_phone: state.form3.verification_phone.clone(),
+                    idempotency_key: Some(idempotency_key(
+                        "createAccount",
+                        &[&old_session.did, &new_pds_url, &new_handle],
+                    )),
                 };
 
-                match create_account_client_side(&migration_client, create_account_request.clone())
-                    .await
+                match create_account_client_side(
+                    &migration_client,
+                    &new_pds_url,
+                    create_account_request.clone(),
+                )
+                .await
                 {
-                    Ok(session) => {
+                    Ok(created) => {
                         console_info!("[Migration] Account created successfully on new PDS");
-                        session
+                        // An account that already existed wasn't created with the key just reserved
+                        dispatch.call(MigrationAction::SetReservedSigningKey(
+                            reserved_signing_key.filter(|_| created.newly_created),
+                        ));
+                        created.session
                     }
                     Err(error) => {
                         // Check if this is the specific "AlreadyExists without session" error
                         if error.contains("Account creation failed with AlreadyExists but no session provided for resumption") {
+                            dispatch.call(MigrationAction::SetReservedSigningKey(None));
                             console_info!("[Migration] Account exists - attempting direct login (equivalent to JavaScript createNewAccount=false)");
                             dispatch.call(MigrationAction::SetMigrationStep(
                                 "Account already exists. Logging in to existing account...".to_string(),
@@ -291,6 +518,7 @@ pub async fn execute_migration_client_side(
                             }
 
                             if login_succeeded {
+                                resuming = true;
                                 recovered_session.unwrap()
                             } else {
                                 console_error!("[Migration] All login attempts failed for existing account");
@@ -305,6 +533,24 @@ pub async fn execute_migration_client_side(
                                 dispatch.call(MigrationAction::SetMigrating(false));
                                 return;
                             }
+                        } else if is_existing_did_rejected(&error) {
+                            console_warn!("[Migration] Target PDS refuses accounts with an existing DID: {}", error);
+                            report_existing_did_rejected(&dispatch, &error);
+                            return;
+                        } else if is_signup_verification_error(&error) {
+                            // The PDS wants a captcha or text message code first; send
+                            // the user back to verification instead of a dead end
+                            console_warn!("[Migration] Target PDS requires signup verification: {}", error);
+                            dispatch.call(MigrationAction::SetVerificationCode(None));
+                            dispatch.call(MigrationAction::SetVerificationPhone(None));
+                            dispatch.call(MigrationAction::SetVerificationRejected(true));
+                            dispatch.call(MigrationAction::SetMigrationError(Some(
+                                "The new PDS needs to verify you before it creates the account. \
+                                Complete the verification above and the migration will start again."
+                                    .to_string(),
+                            )));
+                            dispatch.call(MigrationAction::SetMigrating(false));
+                            return;
                         } else {
                             // Other errors - fail as before
                             console_error!(
@@ -356,17 +602,20 @@ pub async fn execute_migration_client_side(
 
     match check_account_status_client_side(&new_session).await {
         Ok(status_response) => {
-            if let Some(activated) = status_response.activated {
-                if activated {
-                    console_error!("[Migration] Account is already activated before migration");
-                    dispatch.call(MigrationAction::SetMigrationError(Some("Account is already activated before migration. Try again with a different handle.".to_string())));
-                    dispatch.call(MigrationAction::SetMigrating(false));
-                    return;
-                }
+            if status_response.activated == Some(true) {
+                // Some PDS implementations activate accounts on creation instead of
+                // waiting for activateAccount; adapt rather than failing the migration
+                dispatch.call(MigrationAction::SetMigrationStep(
+                    "Target PDS activated the account on creation, deactivating until the identity update...".to_string(),
+                ));
+                let handling = handle_target_auto_activation(&new_session).await;
+                console_info!("[Migration] {}", handling.compatibility_note());
+                dispatch.call(MigrationAction::SetTargetAutoActivation(Some(handling)));
+            } else {
+                console_info!(
+                    "[Migration] Account status verification successful - account is not activated"
+                );
             }
-            console_info!(
-                "[Migration] Account status verification successful - account is not activated"
-            );
         }
         Err(error) => {
             console_error!(
@@ -382,58 +631,290 @@ pub async fn execute_migration_client_side(
         }
     }
 
-    // Phase 2: Content migration
-    console_info!("[Migration] Starting Phase 2: Content and Identity Migration");
-
-    // Execute repository migration
-    if let Err(error) = migrate_repository_client_side(&old_session, &new_session, &dispatch).await
-    {
-        dispatch.call(MigrationAction::SetMigrationError(Some(error)));
+    // Readiness: catch a blobstore or relay misconfiguration before hours of transfer
+    dispatch.call(MigrationAction::SetMigrationStep(
+        "Checking that the new PDS can store blobs and is reachable by the relay...".to_string(),
+    ));
+    let health = run_target_health_check(&new_session, &target_describe).await;
+    for check in health.warnings() {
+        console_warn_with_dispatch!(
+            dispatch,
+            "[Migration] {}: {}",
+            check.kind.label(),
+            check.detail
+        );
+    }
+    if let Some(check) = health.failure() {
+        console_error!("[Migration] {}: {}", check.kind.label(), check.detail);
+        dispatch.call(MigrationAction::SetMigrationError(Some(format!(
+            "The new PDS is not ready for a migration. {}",
+            check.detail
+        ))));
         dispatch.call(MigrationAction::SetMigrating(false));
         return;
     }
 
-    // Execute blob migration using streaming architecture
-    if let Err(error) =
-        execute_streaming_blob_migration(&old_session, &new_session, &dispatch, &state).await
-    {
-        dispatch.call(MigrationAction::SetMigrationError(Some(error)));
-        dispatch.call(MigrationAction::SetMigrating(false));
-        return;
+    // Throughput to both hosts, for the ETA and a warning on links too slow to finish comfortably
+    dispatch.call(MigrationAction::SetMigrationStep(
+        "Measuring the connection speed to both PDSes...".to_string(),
+    ));
+    let size_estimate = match check_account_status_client_side(&old_session).await {
+        Ok(status) => AccountSizeEstimate {
+            repo_blocks: status.repo_blocks.unwrap_or_default().max(0) as u64,
+            blobs: status.expected_blobs.unwrap_or_default().max(0) as u64,
+        },
+        Err(error) => {
+            console_warn!("[Migration] Could not estimate the account size: {}", error);
+            AccountSizeEstimate::default()
+        }
+    };
+    let estimated_bytes = size_estimate.total_bytes();
+    let bandwidth = run_bandwidth_preflight(
+        &old_session,
+        &new_session,
+        estimated_bytes,
+        get_global_config().concurrency.max_concurrent_transfers,
+    )
+    .await;
+    console_info!(
+        "[Migration] Preflight bandwidth: {:?} bytes/s bottleneck, ~{} bytes, ~{:?} s, suggested concurrency {}",
+        bandwidth.bottleneck_bytes_per_sec().map(|rate| rate as u64),
+        bandwidth.estimated_bytes,
+        bandwidth.estimated_secs,
+        bandwidth.suggested_concurrency
+    );
+    if let Some(warning) = bandwidth.slow_link_warning() {
+        console_warn_with_dispatch!(dispatch, "[Migration] {}", warning);
     }
 
-    // Verify blob migration completion and automatically retry missing blobs
-    if let Err(error) =
-        verify_and_complete_blob_migration(&old_session, &new_session, &dispatch, &state).await
-    {
-        dispatch.call(MigrationAction::SetMigrationError(Some(error)));
+    // Proxy body limits: the largest blob or the repository import would be
+    // refused late, so find the limit with test uploads now
+    dispatch.call(MigrationAction::SetMigrationStep(
+        "Checking how large an upload the new PDS accepts...".to_string(),
+    ));
+    let largest_blob = health
+        .limits
+        .max_blob_bytes
+        .unwrap_or(get_global_config().blob.assumed_largest_blob_bytes);
+    let body_limit = run_body_limit_preflight(
+        &new_session,
+        largest_blob.max(size_estimate.repo_bytes()),
+        bandwidth
+            .samples
+            .iter()
+            .find(|sample| sample.direction == TransferDirection::Upload)
+            .and_then(|sample| sample.bytes_per_sec()),
+    )
+    .await;
+    dispatch.call(MigrationAction::SetBodyLimitReport(Some(body_limit.clone())));
+    if let Some(problem) = body_limit.problem() {
+        console_error!("[Migration] {}", problem);
+        dispatch.call(MigrationAction::SetMigrationError(Some(format!(
+            "The new PDS would refuse part of this migration. {}. {}",
+            problem,
+            body_limit.fix().unwrap_or_default()
+        ))));
         dispatch.call(MigrationAction::SetMigrating(false));
         return;
     }
+    if let Some(warning) = body_limit.warning() {
+        console_warn_with_dispatch!(dispatch, "[Migration] {}", warning);
+    }
 
-    // Execute preferences migration
-    if let Err(error) =
-        migrate_preferences_client_side(&old_session, &new_session, &dispatch, &state).await
-    {
-        dispatch.call(MigrationAction::SetMigrationError(Some(error)));
-        dispatch.call(MigrationAction::SetMigrating(false));
+    // A leftover account may hold much of an interrupted attempt; ask the new
+    // PDS what it already has so only the missing work is done again
+    let state = if resuming {
+        reconcile_resumed_migration(state, &dispatch, &old_session, &new_session).await
+    } else {
+        state
+    };
+
+    // Phase 2: Content migration
+    console_info!("[Migration] Starting Phase 2: Content and Identity Migration");
+    if !run_content_plan(&plan, &state, &dispatch, &old_session, Some(&new_session)).await {
         return;
     }
 
-    // Execute PLC setup and transition to Form 4
-    if let Err(error) =
-        setup_plc_transition_client_side(&old_session, &new_session, &dispatch, &state).await
-    {
+    console_info!("[MILESTONE] Client-side migration data phase completed successfully");
+    console_info!("[Migration] ⚠️  Migration continues with PLC operations in Form4 - NOT setting is_migrating=false yet");
+}
+
+/// Record what the new PDS already holds from an earlier attempt, returning
+/// the state the content phase should run with
+#[cfg(feature = "web")]
+async fn reconcile_resumed_migration(
+    state: MigrationState,
+    dispatch: &EventHandler<MigrationAction>,
+    old_session: &ClientSessionCredentials,
+    new_session: &ClientSessionCredentials,
+) -> MigrationState {
+    dispatch.call(MigrationAction::SetMigrationStep(
+        "Checking what the new PDS already has from the earlier attempt...".to_string(),
+    ));
+    match reconcile_with_server(old_session, new_session).await {
+        Ok(reconciliation) if reconciliation.is_resume() => {
+            dispatch.call(MigrationAction::SetMigrationStep(reconciliation.summary()));
+            let action = MigrationAction::SetResumeReconciliation(Some(reconciliation));
+            dispatch.call(action.clone());
+            state.reduce(action)
+        }
+        Ok(_) => state,
+        Err(e) => {
+            console_warn!(
+                "[Migration] Could not compare the earlier attempt with the new PDS: {}",
+                e
+            );
+            state
+        }
+    }
+}
+
+/// Run the content phase of the plan, reporting failures to the UI
+///
+/// Returns true when the plan ends in PLC setup, which hands over to Form 4;
+/// plans without it are finished here.
+#[cfg(feature = "web")]
+async fn run_content_plan(
+    plan: &MigrationPlan,
+    state: &MigrationState,
+    dispatch: &EventHandler<MigrationAction>,
+    old_session: &ClientSessionCredentials,
+    new_session: Option<&ClientSessionCredentials>,
+) -> bool {
+    if let Err(error) = execute_plan(plan, state, dispatch, old_session, new_session).await {
+        // A PDS can create the account and still refuse the repository import
+        if plan.contains(PlanStep::Repository) && is_existing_did_rejected(&error) {
+            report_existing_did_rejected(dispatch, &error);
+            return false;
+        }
         dispatch.call(MigrationAction::SetMigrationError(Some(error)));
         dispatch.call(MigrationAction::SetMigrating(false));
-        return;
+        return false;
+    }
+
+    if plan.hands_over_to_plc() {
+        return true;
+    }
+
+    console_info!("[Migration] {:?} plan finished", plan.variant);
+    dispatch.call(MigrationAction::SetMigrationStep(format!(
+        "Done: {}. Your identity was not changed.",
+        plan.variant.label()
+    )));
+    dispatch.call(MigrationAction::SetMigrating(false));
+    false
+}
+
+/// Explain a PDS that won't take in an account with its existing DID, and
+/// offer the content-copy plan instead of a generic account creation failure
+#[cfg(feature = "web")]
+fn report_existing_did_rejected(dispatch: &EventHandler<MigrationAction>, error: &str) {
+    dispatch.call(MigrationAction::SetExistingDidRejected(true));
+    dispatch.call(MigrationAction::SetMigrationError(Some(format!(
+        "The new PDS does not accept accounts migrating in with their existing DID ({}). \
+         Its operator has to allow account imports; on the reference Bluesky PDS that is \
+         PDS_ACCEPTING_REPO_IMPORTS=true in pds.env, followed by a restart. If that isn't \
+         possible, create a fresh account there and choose \"{}\" to copy your posts and \
+         media into it; your DID and followers stay with the old account.",
+        error,
+        PlanVariant::ContentCopy.label()
+    ))));
+    dispatch.call(MigrationAction::SetMigrating(false));
+}
+
+/// The target PDS URL from Form 2, refused when the deployment's host policy
+/// rules its host out
+#[cfg(feature = "web")]
+fn allowed_target_url(policy: &TargetHostPolicy, state: &MigrationState) -> Result<String, String> {
+    let target_pds_url = normalize_pds_url(&state.form2.pds_url);
+    if target_pds_url.is_empty() {
+        return Err("No target PDS URL specified".to_string());
     }
+    policy.check_url(&target_pds_url)?;
+    Ok(target_pds_url)
+}
 
+/// Log in to the account the user created on the new PDS for a content copy
+#[cfg(feature = "web")]
+async fn login_to_existing_account(
+    pds: &dyn PdsApi,
+    policy: &TargetHostPolicy,
+    state: &MigrationState,
+    old_session: &ClientSessionCredentials,
+) -> Result<ClientSessionCredentials, String> {
+    // Checked before the password goes anywhere
+    let target_pds_url = allowed_target_url(policy, state)?;
+    let handle = normalize_handle(&state.form3.handle)
+        .map_err(|e| format!("Invalid handle of the existing account: {}", e))?;
+    let response = pds
+        .try_login_before_creation(&handle, &state.form3.password, &target_pds_url)
+        .await
+        .map_err(|e| format!("Could not log in to the existing account: {}", e))?;
+    let session = response.session.filter(|_| response.success).ok_or_else(|| {
+        format!(
+            "Could not log in to {} on the new PDS: {}. The content-copy plan needs an account you already created there.",
+            handle, response.message
+        )
+    })?;
+    if session.did == old_session.did {
+        return Err(
+            "That account already has your current DID; choose a regular migration plan instead of a content copy"
+                .to_string(),
+        );
+    }
     console_info!(
-        "[MILESTONE] Client-side migration data phase completed successfully - timestamp: {}",
-        js_sys::Date::now()
+        "[Migration] Copying content from {} into existing account {}",
+        old_session.did,
+        session.did
     );
-    console_info!("[Migration] ⚠️  Migration continues with PLC operations in Form4 - NOT setting is_migrating=false yet");
+    Ok(session)
+}
+
+/// Check the old session against a fresh resolution of the entered identifier
+/// and of the session DID's document; lookups that fail are logged and skipped,
+/// only a real divergence stops the migration
+#[cfg(feature = "web")]
+async fn verify_old_session_identity(
+    migration_client: &MigrationClient,
+    identifier: &str,
+    old_session: &ClientSessionCredentials,
+) -> Result<(), String> {
+    let resolver = &migration_client.identity_resolver;
+    let resolved_did = if identifier.is_empty() {
+        old_session.did.clone()
+    } else if identifier.starts_with("did:") {
+        identifier.to_string()
+    } else {
+        match resolver.resolve_handle(identifier).await {
+            Ok(did) => did,
+            Err(e) => {
+                console_warn!(
+                    "{}",
+                    format!("[Migration] Could not re-resolve {}: {}", identifier, e)
+                );
+                old_session.did.clone()
+            }
+        }
+    };
+    let document_pds = match resolver.resolve_did_to_pds_endpoint(&old_session.did).await {
+        Ok(pds) => Some(pds),
+        Err(e) => {
+            console_warn!(
+                "{}",
+                format!(
+                    "[Migration] Could not resolve the DID document of {}: {}",
+                    old_session.did, e
+                )
+            );
+            None
+        }
+    };
+    check_session_identity(
+        identifier,
+        &resolved_did,
+        old_session,
+        document_pds.as_deref(),
+    )
 }
 
 /// Request a service auth token from the old PDS for migration
@@ -454,7 +935,8 @@ async fn request_service_auth_token(
 
     // FIX: https://github.com/blacksky-algorithms/tektite-cc-migration-service/issues/3
     // There might be a discrepancy between client and PDS clock - set max expiry to 59 minutes
-    let exp_timestamp = (js_sys::Date::now() / 1000.0) as u64 + 3540; // 59 minute expiry
+    // on the PDS's clock, as far as responses so far have told us
+    let exp_timestamp = server_now_secs() + 3540; // 59 minute expiry
 
     match migration_client
         .pds_client
@@ -490,3 +972,52 @@ async fn request_service_auth_token(
         }
     }
 }
+
+#[cfg(all(test, feature = "web"))]
+mod tests {
+    use super::*;
+    use crate::migration::steps::mock_pds::{session, MockPds};
+
+    fn content_copy_state(pds_url: &str) -> MigrationState {
+        let mut state = MigrationState::default();
+        state.reduce_in_place(MigrationAction::SetNewPdsUrl(pds_url.to_string()));
+        state.reduce_in_place(MigrationAction::SetNewHandle(
+            "alice.new.example".to_string(),
+        ));
+        state
+    }
+
+    #[test]
+    fn test_denied_target_fails_before_any_request() {
+        let policy = TargetHostPolicy {
+            allowed_hosts: vec![],
+            denied_hosts: vec!["new.example".to_string()],
+        };
+        let pds = MockPds {
+            login_session: Some(ClientSessionCredentials {
+                did: "did:plc:existing".to_string(),
+                ..session("https://new.example")
+            }),
+            ..Default::default()
+        };
+        let old_session = session("https://old.example");
+
+        let denied = futures::executor::block_on(login_to_existing_account(
+            &pds,
+            &policy,
+            &content_copy_state("https://pds.new.example"),
+            &old_session,
+        ));
+        assert!(denied.unwrap_err().contains("blocked"));
+        assert_eq!(pds.logins.get(), 0);
+
+        let allowed = futures::executor::block_on(login_to_existing_account(
+            &pds,
+            &policy,
+            &content_copy_state("https://other.example"),
+            &old_session,
+        ));
+        assert_eq!(allowed.unwrap().did, "did:plc:existing");
+        assert_eq!(pds.logins.get(), 1);
+    }
+}
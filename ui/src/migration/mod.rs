@@ -14,6 +14,10 @@
 //! - **Preferences Migration**: Transfers user preferences between PDS instances
 //! - **Identity Migration**: Updates DID documents to point to new PDS
 //!
+//! The order of these steps is a [`MigrationPlan`]: a list of step descriptors
+//! with dependencies that the orchestrator interprets, so variants like
+//! skipping preferences or an emergency move are different plans.
+//!
 //! # Usage
 //!
 //! ```rust
@@ -24,20 +28,81 @@
 //! ```
 
 pub mod account_operations;
+pub mod advanced_options;
+pub mod app_passwords;
+#[cfg(feature = "hash-verification")]
+pub mod blob_audit;
+pub mod blob_manifest;
+pub mod car_diff;
+pub mod content_copy;
+pub mod continuous_sync;
+#[cfg(feature = "demo-mode")]
+pub mod demo_mode;
+pub mod did_method;
+pub mod email_verification;
+pub mod event_log;
 pub mod form_validation;
+pub mod help_status;
+#[cfg(feature = "hash-verification")]
+pub mod integrity;
+pub mod journal;
 pub mod logic;
+#[cfg(feature = "web")]
+pub mod old_account_deletion;
+#[cfg(feature = "web")]
+pub mod old_account_grace;
 pub mod orchestrator;
+#[cfg(feature = "web")]
+pub mod orphan_cleanup;
+pub mod password_manager;
+pub mod plan;
+#[cfg(test)]
+pub(crate) mod plc_fixtures;
+pub mod plc_rules;
+pub mod plc_token_delivery;
+pub mod post_migration_checklist;
+pub mod preferences_backup;
+pub mod preferences_roundtrip;
 pub mod progress;
+pub mod resume_reconcile;
+#[cfg(feature = "web")]
+pub mod self_test;
+pub mod service_accounts;
 pub mod session_management;
+pub mod session_prewarm;
+#[cfg(feature = "session-transfer")]
+pub mod session_transfer;
+pub mod source_endpoint;
 pub mod steps;
 pub mod storage;
+#[cfg(feature = "support-bundle")]
+pub mod support_bundle;
+pub mod tab_activity;
+pub mod timeline;
 pub mod types;
+pub mod unrecoverable_blobs;
+pub mod upload_log;
 pub mod validation;
+pub mod watchdog;
 
+pub use did_method::{did_method_for, DidMethod};
+pub use email_verification::{EmailVerification, EmailVerificationStatus};
+pub use event_log::MigrationEventLog;
 pub use form_validation::*;
+pub use help_status::HelpStatus;
+#[cfg(feature = "web")]
 pub use orchestrator::execute_migration_client_side;
+pub use password_manager::CredentialSaveStatus;
+pub use plan::{MigrationPlan, PlanStep, PlanVariant, StepDescriptor};
 pub use progress::*;
+#[cfg(feature = "session-transfer")]
+pub use session_transfer::SessionTransfer;
+#[cfg(feature = "support-bundle")]
+pub use support_bundle::{SupportBundle, SupportBundleError};
+pub use tab_activity::{TabActivity, WakeLockStatus};
+pub use timeline::{StepStatus, StepTimings, TimelineEntry, TimelineStep};
 pub use types::*;
+pub use watchdog::{ConnectivityCheck, StallAction, StallInfo};
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,270 @@
+//! Deleting the old account after a safety window instead of right away
+//!
+//! Once the PLC switch is done the old account is only deactivated, which
+//! keeps a way back if something turns out to be missing. The user can ask to
+//! be reminded to delete it after a window of their choosing: the reminder is
+//! kept in localStorage (no credentials) and surfaced on every later visit.
+//! When the window has passed, a guided flow logs into the old PDS, checks that
+//! the DID no longer points there and that the old account is deactivated, and
+//! deletes it through the standard `requestAccountDelete` / `deleteAccount` pair.
+
+use serde::{Deserialize, Serialize};
+
+use crate::migration::storage::LocalStorageManager;
+use crate::migration::types::SessionCredentials;
+use crate::services::client::{ClientAccountStatusResponse, ClientSessionCredentials, PdsClient};
+use crate::{console_info, console_warn};
+
+/// Window offered first on the completion screen
+pub const DEFAULT_SAFETY_WINDOW_DAYS: u32 = 30;
+
+/// Windows the user can choose from
+pub const SAFETY_WINDOW_CHOICES: [u32; 4] = [7, 30, 60, 90];
+
+pub(crate) const DAY_SECS: u64 = 86_400;
+
+/// A stored reminder to delete the old account
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledDeletion {
+    pub did: String,
+    pub old_pds: String,
+    pub old_handle: String,
+    pub new_pds: String,
+    pub scheduled_at_secs: u64,
+    pub due_at_secs: u64,
+}
+
+impl ScheduledDeletion {
+    pub fn new(
+        old_session: &SessionCredentials,
+        new_pds: &str,
+        now_secs: u64,
+        window_days: u32,
+    ) -> Self {
+        Self {
+            did: old_session.did.clone(),
+            old_pds: old_session.pds.clone(),
+            old_handle: old_session.handle.clone(),
+            new_pds: new_pds.to_string(),
+            scheduled_at_secs: now_secs,
+            due_at_secs: now_secs + u64::from(window_days) * DAY_SECS,
+        }
+    }
+
+    pub fn is_due(&self, now_secs: u64) -> bool {
+        now_secs >= self.due_at_secs
+    }
+
+    /// Whole days left in the window, rounded up
+    pub fn days_remaining(&self, now_secs: u64) -> u64 {
+        self.due_at_secs.saturating_sub(now_secs).div_ceil(DAY_SECS)
+    }
+}
+
+/// The old account, logged into and confirmed safe to delete
+#[derive(Debug, Clone)]
+pub struct OldAccount {
+    pub session: ClientSessionCredentials,
+}
+
+pub fn schedule_old_account_deletion(schedule: &ScheduledDeletion) -> Result<(), String> {
+    LocalStorageManager::store_scheduled_deletion(schedule)
+        .map_err(|e| format!("Could not save the reminder: {}", e))?;
+    console_info!(
+        "[Deletion] Old account {} on {} scheduled for deletion at {}",
+        schedule.did,
+        schedule.old_pds,
+        schedule.due_at_secs
+    );
+    Ok(())
+}
+
+pub fn scheduled_old_account_deletion() -> Option<ScheduledDeletion> {
+    LocalStorageManager::get_scheduled_deletion().ok()
+}
+
+pub fn cancel_old_account_deletion() {
+    LocalStorageManager::clear_scheduled_deletion();
+}
+
+pub(crate) fn same_pds(a: &str, b: &str) -> bool {
+    a.trim()
+        .trim_end_matches('/')
+        .eq_ignore_ascii_case(b.trim().trim_end_matches('/'))
+}
+
+/// Refuse deletion before the window ends, while the identity still points at
+/// the old PDS, or while the old account is active
+pub fn check_old_account_deletable(
+    schedule: &ScheduledDeletion,
+    current_pds: &str,
+    status: &ClientAccountStatusResponse,
+    now_secs: u64,
+) -> Result<(), String> {
+    if !schedule.is_due(now_secs) {
+        return Err(format!(
+            "The safety window ends in {} days",
+            schedule.days_remaining(now_secs)
+        ));
+    }
+    if same_pds(current_pds, &schedule.old_pds) {
+        return Err(
+            "Your identity still points at the old PDS, so the old account is still your live one"
+                .to_string(),
+        );
+    }
+    match status.activated {
+        Some(false) => Ok(()),
+        Some(true) => Err(
+            "The old account is active again. Deactivate it first if you still want to delete it."
+                .to_string(),
+        ),
+        None => Err("The old PDS did not report whether the account is active".to_string()),
+    }
+}
+
+/// Log into the old account and check that it is safe to delete
+pub async fn find_old_account(
+    schedule: &ScheduledDeletion,
+    password: &str,
+    now_secs: u64,
+) -> Result<OldAccount, String> {
+    let pds_client = PdsClient::new();
+
+    let login = pds_client
+        .login_with_explicit_pds(&schedule.did, password, &schedule.old_pds)
+        .await
+        .map_err(|e| format!("Login to the old PDS failed: {}", e))?;
+    let session = match login.session {
+        Some(session) if login.success => session,
+        _ => return Err(format!("Login to the old PDS failed: {}", login.message)),
+    };
+
+    let current_pds = pds_client
+        .resolve_pds_from_did(&schedule.did)
+        .await
+        .map_err(|e| format!("Could not check where your identity points: {}", e))?;
+    let status = pds_client
+        .check_account_status(&session)
+        .await
+        .map_err(|e| format!("Account status check failed: {}", e))?;
+    if !status.success {
+        return Err(status.message);
+    }
+    check_old_account_deletable(schedule, &current_pds, &status, now_secs)?;
+
+    Ok(OldAccount { session })
+}
+
+/// Have the old PDS email the token required for deletion
+pub async fn request_old_account_deletion(account: &OldAccount) -> Result<(), String> {
+    let response = PdsClient::new()
+        .request_account_delete(&account.session)
+        .await
+        .map_err(|e| e.to_string())?;
+    if response.success {
+        Ok(())
+    } else {
+        Err(response.message)
+    }
+}
+
+/// Delete the old account and drop the reminder
+pub async fn delete_old_account(
+    account: &OldAccount,
+    password: &str,
+    token: &str,
+) -> Result<(), String> {
+    let response = PdsClient::new()
+        .delete_account(&account.session, password, token.trim())
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.success {
+        return Err(response.message);
+    }
+
+    console_info!(
+        "[Deletion] Deleted old account {} on {}",
+        account.session.did,
+        account.session.pds
+    );
+    cancel_old_account_deletion();
+    if let Ok(stored) = LocalStorageManager::get_old_session() {
+        if stored.did == account.session.did && stored.pds == account.session.pds {
+            if let Err(e) = LocalStorageManager::clear_migration_data() {
+                console_warn!("[Deletion] Failed to clear stored migration data: {}", e);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(now_secs: u64) -> ScheduledDeletion {
+        let session = SessionCredentials {
+            did: "did:plc:alice".to_string(),
+            handle: "alice.old.example".to_string(),
+            pds: "https://old.example".to_string(),
+            access_jwt: String::new(),
+            refresh_jwt: String::new(),
+        };
+        ScheduledDeletion::new(&session, "https://new.example", now_secs, 30)
+    }
+
+    #[test]
+    fn test_window_rounds_remaining_days_up() {
+        let scheduled = schedule(1_000);
+        assert!(!scheduled.is_due(1_000));
+        assert_eq!(scheduled.days_remaining(1_000), 30);
+        assert_eq!(scheduled.days_remaining(1_000 + DAY_SECS + 1), 29);
+        assert!(scheduled.is_due(1_000 + 30 * DAY_SECS));
+        assert_eq!(scheduled.days_remaining(1_000 + 31 * DAY_SECS), 0);
+    }
+
+    #[test]
+    fn test_deletion_requires_window_moved_identity_and_deactivation() {
+        let scheduled = schedule(0);
+        let due = 30 * DAY_SECS;
+        let status = |activated| ClientAccountStatusResponse {
+            success: true,
+            activated,
+            ..ClientAccountStatusResponse::default()
+        };
+
+        assert!(check_old_account_deletable(
+            &scheduled,
+            "https://new.example",
+            &status(Some(false)),
+            due
+        )
+        .is_ok());
+        assert!(check_old_account_deletable(
+            &scheduled,
+            "https://new.example",
+            &status(Some(false)),
+            due - 1
+        )
+        .is_err());
+        assert!(check_old_account_deletable(
+            &scheduled,
+            "https://OLD.example/",
+            &status(Some(false)),
+            due
+        )
+        .is_err());
+        assert!(check_old_account_deletable(
+            &scheduled,
+            "https://new.example",
+            &status(Some(true)),
+            due
+        )
+        .is_err());
+        assert!(
+            check_old_account_deletable(&scheduled, "https://new.example", &status(None), due)
+                .is_err()
+        );
+    }
+}
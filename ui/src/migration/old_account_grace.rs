@@ -0,0 +1,173 @@
+//! Leaving the old account active for a grace period after the switch
+//!
+//! Normally the last step of the migration deactivates the old account. Some
+//! users want it to stay readable for a few days while apps, feeds and
+//! AppViews catch up. The catch is that the old PDS keeps serving the account
+//! as it was at the switch, so services that still read from it see stale
+//! posts and profile data until it is deactivated. When the user picks a
+//! grace period, deactivation is skipped and a reminder (no credentials) is
+//! kept in localStorage like the deletion reminder. It shows up in the
+//! post-migration checklist and on every later visit, and offers to log into
+//! the old PDS and deactivate the account, refusing while the identity still
+//! points there.
+
+use serde::{Deserialize, Serialize};
+
+use crate::migration::old_account_deletion::{same_pds, DAY_SECS};
+use crate::migration::storage::LocalStorageManager;
+use crate::migration::types::SessionCredentials;
+use crate::services::client::PdsClient;
+use crate::{console_info, console_warn};
+
+/// Grace period offered first in form 4
+pub const DEFAULT_GRACE_PERIOD_DAYS: u32 = 7;
+
+/// Grace periods the user can choose from
+pub const GRACE_PERIOD_CHOICES: [u32; 3] = [3, 7, 14];
+
+/// A stored reminder to deactivate the old account
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledDeactivation {
+    pub did: String,
+    pub old_pds: String,
+    pub old_handle: String,
+    pub new_pds: String,
+    pub scheduled_at_secs: u64,
+    pub due_at_secs: u64,
+}
+
+impl ScheduledDeactivation {
+    pub fn new(
+        old_session: &SessionCredentials,
+        new_pds: &str,
+        now_secs: u64,
+        grace_days: u32,
+    ) -> Self {
+        Self {
+            did: old_session.did.clone(),
+            old_pds: old_session.pds.clone(),
+            old_handle: old_session.handle.clone(),
+            new_pds: new_pds.to_string(),
+            scheduled_at_secs: now_secs,
+            due_at_secs: now_secs + u64::from(grace_days) * DAY_SECS,
+        }
+    }
+
+    pub fn is_due(&self, now_secs: u64) -> bool {
+        now_secs >= self.due_at_secs
+    }
+
+    /// Whole days left in the grace period, rounded up
+    pub fn days_remaining(&self, now_secs: u64) -> u64 {
+        self.due_at_secs.saturating_sub(now_secs).div_ceil(DAY_SECS)
+    }
+}
+
+pub fn schedule_old_account_deactivation(schedule: &ScheduledDeactivation) -> Result<(), String> {
+    LocalStorageManager::store_scheduled_deactivation(schedule)
+        .map_err(|e| format!("Could not save the reminder: {}", e))?;
+    console_info!(
+        "[Grace] Old account {} on {} left active until {}",
+        schedule.did,
+        schedule.old_pds,
+        schedule.due_at_secs
+    );
+    Ok(())
+}
+
+pub fn scheduled_old_account_deactivation() -> Option<ScheduledDeactivation> {
+    LocalStorageManager::get_scheduled_deactivation().ok()
+}
+
+pub fn cancel_old_account_deactivation() {
+    LocalStorageManager::clear_scheduled_deactivation();
+}
+
+/// Refuse to deactivate while the identity still points at the old PDS, since
+/// that would take the user's only live account offline
+pub fn check_old_account_deactivatable(
+    schedule: &ScheduledDeactivation,
+    current_pds: &str,
+) -> Result<(), String> {
+    if same_pds(current_pds, &schedule.old_pds) {
+        return Err(
+            "Your identity still points at the old PDS, so the old account is still your live one"
+                .to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// Log into the old account, check the identity has moved, deactivate it and
+/// drop the reminder
+pub async fn deactivate_old_account(
+    schedule: &ScheduledDeactivation,
+    password: &str,
+) -> Result<(), String> {
+    let pds_client = PdsClient::new();
+
+    let login = pds_client
+        .login_with_explicit_pds(&schedule.did, password, &schedule.old_pds)
+        .await
+        .map_err(|e| format!("Login to the old PDS failed: {}", e))?;
+    let session = match login.session {
+        Some(session) if login.success => session,
+        _ => return Err(format!("Login to the old PDS failed: {}", login.message)),
+    };
+
+    let current_pds = pds_client
+        .resolve_pds_from_did(&schedule.did)
+        .await
+        .map_err(|e| format!("Could not check where your identity points: {}", e))?;
+    check_old_account_deactivatable(schedule, &current_pds)?;
+
+    let response = pds_client
+        .deactivate_account(&session)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.success {
+        console_warn!(
+            "[Grace] Deactivating {} on {} failed: {}",
+            schedule.did,
+            schedule.old_pds,
+            response.message
+        );
+        return Err(response.message);
+    }
+
+    console_info!(
+        "[Grace] Deactivated old account {} on {}",
+        schedule.did,
+        schedule.old_pds
+    );
+    cancel_old_account_deactivation();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grace_period_and_moved_identity() {
+        let session = SessionCredentials {
+            did: "did:plc:alice".to_string(),
+            handle: "alice.old.example".to_string(),
+            pds: "https://old.example".to_string(),
+            access_jwt: String::new(),
+            refresh_jwt: String::new(),
+        };
+        let schedule = ScheduledDeactivation::new(&session, "https://new.example", 1_000, 7);
+        assert!(!schedule.is_due(1_000));
+        assert_eq!(schedule.days_remaining(1_000), 7);
+        assert!(schedule.is_due(1_000 + 7 * DAY_SECS));
+
+        assert!(check_old_account_deactivatable(&schedule, "https://new.example").is_ok());
+        assert!(check_old_account_deactivatable(&schedule, "https://OLD.example/").is_err());
+
+        schedule_old_account_deactivation(&schedule).unwrap();
+        assert_eq!(scheduled_old_account_deactivation(), Some(schedule));
+        cancel_old_account_deactivation();
+        assert_eq!(scheduled_old_account_deactivation(), None);
+    }
+}
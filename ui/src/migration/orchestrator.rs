@@ -1,21 +1,43 @@
-//! Migration orchestrator - coordinates the execution of migration steps
+//! Migration orchestrator - interprets a [`MigrationPlan`] step by step
 
 #[cfg(feature = "web")]
-use crate::services::client::ClientSessionCredentials;
+use crate::console_error;
+#[cfg(feature = "web")]
+use crate::services::client::{ClientSessionCredentials, NewPds, OldPds, PdsApi, PdsClient};
+#[cfg(feature = "web")]
 use crate::services::config::get_global_config;
-use crate::{console_error, console_info, console_warn};
+#[cfg(feature = "web")]
+use crate::services::retry::reset_retry_clock;
+use crate::{console_info, console_warn};
+#[cfg(feature = "web")]
 use dioxus::prelude::*;
 
+#[cfg(feature = "web")]
 use crate::migration::{
+    app_passwords::AppPasswordInventoryStep,
+    content_copy::ContentCopyStep,
+    continuous_sync::ContinuousSyncStep,
+    email_verification::EmailSetupStep,
     steps::{
-        blob::execute_streaming_blob_migration, plc::setup_plc_transition_client_side,
-        preferences::migrate_preferences_client_side, repository::migrate_repository_client_side,
+        blob::BlobStep,
+        plc::PlcSetupStep,
+        preferences::{ExportPreferencesStep, ImportPreferencesStep, RestorePreferencesBackupStep},
+        repository::RepositoryStep,
     },
     storage::LocalStorageManager,
     types::*,
+    validation::{VerifyBlobsStep, VerifyRepositoryStep},
+};
+use crate::migration::{
+    plan::{MigrationPlan, PlanStep},
+    session_prewarm::{prewarm_sessions, prewarms_sessions},
+    source_endpoint::{is_connection_failure, relocated_source, save_source_endpoint},
+    steps::{MigrationStep, StepContext},
+    types::MigrationAction,
 };
 
-/// Main migration orchestrator that coordinates all migration steps
+/// Main migration orchestrator that runs the selected plan with stored sessions
+#[cfg(feature = "web")]
 pub async fn execute_migration_client_side(
     state: MigrationState,
     dispatch: EventHandler<MigrationAction>,
@@ -45,25 +67,22 @@ pub async fn execute_migration_client_side(
         }
     };
 
-    // Get new PDS session from state
-    let new_session_api = match state.new_pds_session.as_ref() {
-        Some(session) => session.clone(),
-        None => {
-            console_error!("[Migration] Missing new PDS session");
-            dispatch.call(MigrationAction::SetMigrationError(Some(
-                "Missing new PDS session credentials".to_string(),
-            )));
-            return;
-        }
-    };
-
-    let new_session = (&new_session_api).into();
+    let plan = MigrationPlan::for_variant(state.form3.plan_variant);
+    reset_retry_clock();
 
-    // Execute migration with retry logic (no complex resume capability)
-    console_info!("[Migration] Starting fresh migration with retry capabilities");
+    // Get new PDS session from state, if the plan writes anything there
+    let new_session: Option<ClientSessionCredentials> =
+        state.new_pds_session.as_ref().map(|session| session.into());
+    if plan.needs_target_account() && new_session.is_none() {
+        console_error!("[Migration] Missing new PDS session");
+        dispatch.call(MigrationAction::SetMigrationError(Some(
+            "Missing new PDS session credentials".to_string(),
+        )));
+        return;
+    }
 
-    // Execute the full migration pipeline
-    if let Err(e) = execute_full_migration(&state, &dispatch, &old_session, &new_session).await {
+    if let Err(e) = execute_plan(&plan, &state, &dispatch, &old_session, new_session.as_ref()).await
+    {
         console_error!("{}", format!("[Migration] Migration failed: {}", &e));
         dispatch.call(MigrationAction::SetMigrationError(Some(e)));
         return;
@@ -73,149 +92,370 @@ pub async fn execute_migration_client_side(
     dispatch.call(MigrationAction::SetMigrationCompleted(true));
 }
 
-async fn execute_full_migration(
+/// Run the plan against the live PDS client and the UI dispatcher
+#[cfg(feature = "web")]
+pub async fn execute_plan(
+    plan: &MigrationPlan,
     state: &MigrationState,
     dispatch: &EventHandler<MigrationAction>,
     old_session: &ClientSessionCredentials,
-    new_session: &ClientSessionCredentials,
+    new_session: Option<&ClientSessionCredentials>,
 ) -> Result<(), String> {
-    // Get configuration to determine architecture choice
-    let config = get_global_config();
-
     console_info!(
-        "[Migration] Using {} architecture for migration",
-        match config.architecture {
-            crate::services::config::MigrationArchitecture::Traditional => "traditional",
-            crate::services::config::MigrationArchitecture::Streaming => "streaming",
-        }
+        "[Migration] Running {:?} plan: {:?}",
+        plan.variant,
+        plan.steps.iter().map(|d| d.step).collect::<Vec<_>>()
     );
 
-    // Step 1: Repository migration (always uses new streaming architecture)
-    console_info!("[Migration] Phase 1: Repository Migration");
-    migrate_repository_client_side(old_session, new_session, dispatch).await?;
+    // A session restored from storage or another device reaches here without
+    // the checks that ran when the account was created
+    if let Some(session) = new_session {
+        get_global_config().target_hosts.check_url(&session.pds)?;
+    }
+
+    let pds_client = PdsClient::new();
+    let pds: &dyn PdsApi = &pds_client;
+    let handler = *dispatch;
+    let emit = move |action: MigrationAction| handler.call(action);
+    let steps: Vec<Box<dyn MigrationStep>> = plan.steps.iter().map(|d| step_for(d.step)).collect();
 
-    // Step 2: Blob migration - choose based on configuration
-    console_info!("[Migration] Phase 2: Blob Migration");
-    match config.architecture {
-        crate::services::config::MigrationArchitecture::Traditional => {
-            console_info!("[Migration] Using traditional blob migration with smart strategies");
-            execute_streaming_blob_migration(old_session, new_session, dispatch, state).await?;
-        }
-        crate::services::config::MigrationArchitecture::Streaming => {
-            console_info!("[Migration] Using streaming blob migration with channel-tee pattern");
-            execute_streaming_blob_migration(old_session, new_session, dispatch, state).await?;
-        }
+    let mut ctx = StepContext {
+        old: OldPds::new(pds, old_session.clone()),
+        new: new_session.map(|session| NewPds::new(pds, session.clone())),
+        state,
+        dispatch: &emit,
+        handler: Some(handler),
+        preferences: None,
+    };
+    run_steps(plan, &steps, &mut ctx).await
+}
+
+/// The production implementation of a plan step
+#[cfg(feature = "web")]
+pub fn step_for(step: PlanStep) -> Box<dyn MigrationStep> {
+    match step {
+        PlanStep::Repository => Box::new(RepositoryStep),
+        PlanStep::VerifyRepository => Box::new(VerifyRepositoryStep),
+        PlanStep::Blobs => Box::new(BlobStep),
+        PlanStep::VerifyBlobs => Box::new(VerifyBlobsStep),
+        PlanStep::ExportPreferences => Box::new(ExportPreferencesStep),
+        PlanStep::RestorePreferencesBackup => Box::new(RestorePreferencesBackupStep),
+        PlanStep::ImportPreferences => Box::new(ImportPreferencesStep),
+        PlanStep::ContinuousSync => Box::new(ContinuousSyncStep),
+        PlanStep::AppPasswordInventory => Box::new(AppPasswordInventoryStep),
+        PlanStep::EmailSetup => Box::new(EmailSetupStep),
+        PlanStep::CopyRecords => Box::new(ContentCopyStep),
+        PlanStep::PlcSetup => Box::new(PlcSetupStep),
     }
+}
 
-    // Step 3: Preferences migration
-    console_info!("[Migration] Phase 3: Preferences Migration");
-    migrate_preferences_client_side(old_session, new_session, dispatch, state).await?;
+/// Run the plan's steps in order using the given implementations
+///
+/// A step whose dependencies didn't all complete is skipped. Each step is
+/// verified right after it executes. A failing required step rolls back the
+/// steps completed so far, newest first, and ends the run with its error; a
+/// failing optional step is logged and only blocks the steps that depend on it.
+/// A step that failed because the old PDS couldn't be reached runs once more
+/// if the DID document now lists a different endpoint for it.
+pub async fn run_steps(
+    plan: &MigrationPlan,
+    steps: &[Box<dyn MigrationStep>],
+    ctx: &mut StepContext<'_>,
+) -> Result<(), String> {
+    plan.validate()?;
 
-    // Step 4: Verification and retry before Form 4 loads
-    console_info!("[Migration] Phase 4: Account and Blob Verification");
-    let max_retries = 3;
-    let mut retry_count = 0;
+    let mut completed: Vec<&dyn MigrationStep> = Vec::new();
 
-    while retry_count < max_retries {
-        match verify_migration_completeness(old_session, new_session).await {
-            Ok(true) => {
-                console_info!("[Migration] Migration verification successful");
-                break;
-            }
-            Ok(false) => {
-                retry_count += 1;
-                console_warn!(
-                    "[Migration] Verification failed, attempt {}/{}",
-                    retry_count,
-                    max_retries
-                );
+    for descriptor in &plan.steps {
+        let step = descriptor.step;
+        if let Some(missing) = descriptor.depends_on.iter().find(|dependency| {
+            !completed
+                .iter()
+                .any(|done| done.plan_step() == **dependency)
+        }) {
+            console_warn!(
+                "[Migration] Skipping {} because {} did not complete",
+                step.label(),
+                missing.label()
+            );
+            continue;
+        }
 
-                if retry_count < max_retries {
-                    console_info!("[Migration] Retrying repository and blob migration...");
+        let implementation = steps
+            .iter()
+            .find(|candidate| candidate.plan_step() == step)
+            .ok_or_else(|| format!("No implementation for plan step {}", step.label()))?;
 
-                    // Retry repository migration
-                    if let Err(e) =
-                        migrate_repository_client_side(old_session, new_session, dispatch).await
-                    {
-                        console_error!("[Migration] Repository retry failed: {}", e);
-                        continue;
-                    }
+        console_info!(
+            "[Migration] Plan step: {} ({})",
+            step.label(),
+            implementation.describe()
+        );
+        if prewarms_sessions(step) {
+            prewarm_sessions(ctx).await;
+        }
+        let mut result = execute_and_verify(implementation.as_ref(), ctx).await;
+        if result.as_ref().is_err_and(|e| is_connection_failure(e))
+            && follow_relocated_source(ctx).await
+        {
+            console_info!(
+                "[Migration] Retrying {} against the old PDS's new endpoint",
+                step.label()
+            );
+            result = execute_and_verify(implementation.as_ref(), ctx).await;
+        }
 
-                    // Retry blob migration based on configuration
-                    let retry_result = match config.architecture {
-                        crate::services::config::MigrationArchitecture::Traditional => {
-                            execute_streaming_blob_migration(
-                                old_session,
-                                new_session,
-                                dispatch,
-                                state,
-                            )
-                            .await
-                        }
-                        crate::services::config::MigrationArchitecture::Streaming => {
-                            execute_streaming_blob_migration(
-                                old_session,
-                                new_session,
-                                dispatch,
-                                state,
-                            )
-                            .await
-                        }
-                    };
-
-                    if let Err(e) = retry_result {
-                        console_error!("[Migration] Blob migration retry failed: {}", e);
-                        continue;
+        match result {
+            Ok(()) => completed.push(implementation.as_ref()),
+            Err(e) if descriptor.required => {
+                for done in completed.iter().rev() {
+                    if let Err(rollback_error) = done.rollback(ctx).await {
+                        console_warn!(
+                            "[Migration] Rolling back {} failed: {}",
+                            done.plan_step().label(),
+                            rollback_error
+                        );
                     }
-                } else {
-                    return Err(format!(
-                        "Migration verification failed after {} attempts",
-                        max_retries
-                    ));
                 }
+                return Err(e);
             }
             Err(e) => {
-                return Err(format!("Migration verification error: {}", e));
+                console_warn!(
+                    "[Migration] Optional step {} failed, continuing: {}",
+                    step.label(),
+                    e
+                );
             }
         }
     }
 
-    // Step 5: PLC transition setup (prepares for Form 4)
-    console_info!("[Migration] Phase 5: PLC Transition Setup");
-    setup_plc_transition_client_side(old_session, new_session, dispatch, state).await?;
+    Ok(())
+}
 
-    console_info!("[Migration] Migration completed successfully - ready for Form 4");
+async fn execute_and_verify(
+    step: &dyn MigrationStep,
+    ctx: &mut StepContext<'_>,
+) -> Result<(), String> {
+    step.execute(ctx).await?;
+    step.verify(ctx).await
+}
 
-    Ok(())
+/// Re-resolve the DID document after the old PDS stopped answering and, if it
+/// lists a different endpoint, switch the run to it
+async fn follow_relocated_source(ctx: &mut StepContext<'_>) -> bool {
+    let Some(change) = relocated_source(&ctx.old).await else {
+        return false;
+    };
+    console_warn!(
+        "[Migration] Old PDS moved from {} to {}",
+        change.from,
+        change.to
+    );
+    ctx.old = ctx.old.with_host(&change.to);
+    save_source_endpoint(&change);
+    (ctx.dispatch)(MigrationAction::SetSourceEndpointChange(Some(change)));
+    true
 }
 
-/// Verify account status and blob completeness before Form 4
-async fn verify_migration_completeness(
-    _old_session: &ClientSessionCredentials,
-    new_session: &ClientSessionCredentials,
-) -> Result<bool, String> {
-    let client = crate::services::client::PdsClient::new();
-
-    // Check if there are any missing blobs on new PDS
-    match client.get_missing_blobs(new_session, None, None).await {
-        Ok(response) => {
-            let missing_count = response.missing_blobs.unwrap_or_default().len();
-            if missing_count > 0 {
-                console_warn!(
-                    "[Migration] Found {} missing blobs on target PDS",
-                    missing_count
-                );
-                return Ok(false);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migration::plan::{PlanVariant, StepDescriptor};
+    use crate::migration::steps::mock_pds::{session, MockPds};
+    use crate::migration::types::{MigrationAction, MigrationState};
+    use crate::services::client::{NewPds, OldPds, PdsApi};
+    use async_trait::async_trait;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Records calls into a shared log and fails `execute` on demand
+    struct FakeStep {
+        step: PlanStep,
+        fail: bool,
+        log: Rc<RefCell<Vec<String>>>,
+    }
+
+    #[async_trait(?Send)]
+    impl MigrationStep for FakeStep {
+        fn plan_step(&self) -> PlanStep {
+            self.step
+        }
+
+        fn describe(&self) -> &'static str {
+            "fake"
+        }
+
+        async fn execute(&self, _ctx: &mut StepContext<'_>) -> Result<(), String> {
+            self.log
+                .borrow_mut()
+                .push(format!("execute {:?}", self.step));
+            if self.fail {
+                Err(format!("{:?} failed", self.step))
+            } else {
+                Ok(())
             }
         }
-        Err(e) => {
-            console_warn!("[Migration] Failed to check missing blobs: {}", e);
-            return Err(format!("Failed to verify blob migration: {}", e));
+
+        async fn rollback(&self, _ctx: &StepContext<'_>) -> Result<(), String> {
+            self.log
+                .borrow_mut()
+                .push(format!("rollback {:?}", self.step));
+            Ok(())
+        }
+    }
+
+    fn run(plan: &MigrationPlan, failing: &[PlanStep]) -> (Result<(), String>, Vec<String>) {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let steps: Vec<Box<dyn MigrationStep>> = plan
+            .steps
+            .iter()
+            .map(|d| {
+                Box::new(FakeStep {
+                    step: d.step,
+                    fail: failing.contains(&d.step),
+                    log: Rc::clone(&log),
+                }) as Box<dyn MigrationStep>
+            })
+            .collect();
+
+        let pds = MockPds::default();
+        let old = session("https://old.example");
+        let new = session("https://new.example");
+        let state = MigrationState::default();
+        let dispatch = |_: MigrationAction| {};
+        let pds: &dyn PdsApi = &pds;
+        let mut ctx = StepContext {
+            old: OldPds::new(pds, old),
+            new: Some(NewPds::new(pds, new)),
+            state: &state,
+            dispatch: &dispatch,
+            handler: None,
+            preferences: None,
+        };
+        let result = futures::executor::block_on(run_steps(plan, &steps, &mut ctx));
+        let calls = log.borrow().clone();
+        (result, calls)
+    }
+
+    #[test]
+    fn test_required_failure_rolls_back_completed_steps_in_reverse() {
+        let plan = MigrationPlan {
+            variant: PlanVariant::Full,
+            steps: vec![
+                StepDescriptor {
+                    step: PlanStep::Repository,
+                    depends_on: vec![],
+                    required: true,
+                },
+                StepDescriptor {
+                    step: PlanStep::Blobs,
+                    depends_on: vec![PlanStep::Repository],
+                    required: true,
+                },
+                StepDescriptor {
+                    step: PlanStep::PlcSetup,
+                    depends_on: vec![PlanStep::Blobs],
+                    required: true,
+                },
+            ],
+        };
+        let (result, calls) = run(&plan, &[PlanStep::PlcSetup]);
+
+        assert_eq!(result, Err("PlcSetup failed".to_string()));
+        assert_eq!(
+            calls,
+            [
+                "execute Repository",
+                "execute Blobs",
+                "execute PlcSetup",
+                "rollback Blobs",
+                "rollback Repository",
+            ]
+        );
+    }
+
+    /// Can't connect until the old session points at the moved host
+    struct HostBoundStep;
+
+    #[async_trait(?Send)]
+    impl MigrationStep for HostBoundStep {
+        fn plan_step(&self) -> PlanStep {
+            PlanStep::Repository
+        }
+
+        fn describe(&self) -> &'static str {
+            "host-bound"
+        }
+
+        async fn execute(&self, ctx: &mut StepContext<'_>) -> Result<(), String> {
+            if ctx.old.host() == "https://moved.example" {
+                Ok(())
+            } else {
+                Err(format!(
+                    "Failed to fetch repo stream: error sending request for url ({})",
+                    ctx.old.host()
+                ))
+            }
         }
     }
 
-    // Check account status on both PDSs
-    // TODO: Add account status verification
-    console_info!("[Migration] Account and blob verification completed successfully");
-    Ok(true)
+    #[test]
+    fn test_connection_failure_follows_moved_source() {
+        let plan = MigrationPlan {
+            variant: PlanVariant::Full,
+            steps: vec![StepDescriptor {
+                step: PlanStep::Repository,
+                depends_on: vec![],
+                required: true,
+            }],
+        };
+        let steps: Vec<Box<dyn MigrationStep>> = vec![Box::new(HostBoundStep)];
+        let state = MigrationState::default();
+        let actions = RefCell::new(Vec::new());
+        let dispatch = |action: MigrationAction| actions.borrow_mut().push(action);
+
+        let run_against = |moved_to: Option<&str>| {
+            let pds = MockPds {
+                did_pds_endpoint: moved_to.map(str::to_string),
+                ..Default::default()
+            };
+            let pds: &dyn PdsApi = &pds;
+            let mut ctx = StepContext {
+                old: OldPds::new(pds, session("https://old.example")),
+                new: None,
+                state: &state,
+                dispatch: &dispatch,
+                handler: None,
+                preferences: None,
+            };
+            let result = futures::executor::block_on(run_steps(&plan, &steps, &mut ctx));
+            (result, ctx.old.host().to_string())
+        };
+
+        // The DID document still lists the dead host, so there is nothing to follow
+        let (result, host) = run_against(Some("https://old.example/"));
+        assert!(result.unwrap_err().contains("error sending request"));
+        assert_eq!(host, "https://old.example");
+        assert!(actions.borrow().is_empty());
+
+        let (result, host) = run_against(Some("https://moved.example"));
+        assert_eq!(result, Ok(()));
+        assert_eq!(host, "https://moved.example");
+        assert!(matches!(
+            &actions.borrow()[..],
+            [MigrationAction::SetSourceEndpointChange(Some(change))]
+                if change.from == "https://old.example" && change.to == "https://moved.example"
+        ));
+    }
+
+    #[test]
+    fn test_optional_failure_skips_only_its_dependents() {
+        let plan = MigrationPlan::for_variant(PlanVariant::Emergency);
+        let (result, calls) = run(&plan, &[PlanStep::ExportPreferences]);
+
+        assert_eq!(result, Ok(()));
+        assert!(calls.contains(&"execute PlcSetup".to_string()));
+        assert!(!calls.contains(&"execute ImportPreferences".to_string()));
+        assert!(!calls.iter().any(|call| call.starts_with("rollback")));
+    }
 }
@@ -0,0 +1,336 @@
+//! Self-service removal of half-created accounts on a target PDS
+//!
+//! A failed attempt can leave a never-activated account on the target holding
+//! the handle and the DID association. If the user decides to migrate somewhere
+//! else, they log into that account here and delete it through the standard
+//! `requestAccountDelete` / `deleteAccount` pair.
+//!
+//! Being deactivated isn't enough: the old account after a finished migration
+//! is deactivated too, and so is a target whose PLC update went through but
+//! whose activation failed. Neither `validDid` nor the current DID document
+//! tells those apart from an orphan, since the old account also reports
+//! `validDid` false and the document names the new PDS. What an orphan has that
+//! they don't is a PLC history that never named its PDS, so an account is only
+//! deletable when the PDS reports it deactivated with `validDid` false, the DID
+//! document resolved fresh names a different PDS, and no operation in the PLC
+//! audit log ever set this PDS as the `atproto_pds` endpoint. did:web has no
+//! such history, so those accounts are always refused.
+
+use crate::migration::storage::LocalStorageManager;
+use crate::services::client::{
+    invalidate_identity, ClientAccountStatusResponse, ClientSessionCredentials, PdsClient,
+    WebIdentityResolver,
+};
+use crate::utils::handle::{normalize_identifier, normalize_pds_url};
+use crate::{console_info, console_warn};
+use serde_json::Value;
+
+/// A target account confirmed safe to delete
+#[derive(Debug, Clone)]
+pub struct OrphanedAccount {
+    pub session: ClientSessionCredentials,
+    pub indexed_records: i64,
+    pub imported_blobs: i64,
+}
+
+/// Every PDS endpoint named by an operation in a PLC audit log, including
+/// nullified ones and the legacy `create` format's `service` field
+pub fn pds_endpoints_in_audit_log(log: &[Value]) -> Vec<String> {
+    log.iter()
+        .filter_map(|entry| {
+            let operation = entry.get("operation")?;
+            operation
+                .pointer("/services/atproto_pds/endpoint")
+                .or_else(|| operation.get("service"))
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        })
+        .collect()
+}
+
+/// Refuse anything that looks like a live account. `document_pds` is the PDS
+/// the DID document names, or `None` if it couldn't be resolved. `pds_history`
+/// is every PDS the DID's PLC audit log has named, or `None` if there is no
+/// log to read.
+pub fn check_deletable(
+    status: &ClientAccountStatusResponse,
+    pds_url: &str,
+    document_pds: Option<&str>,
+    pds_history: Option<&[String]>,
+) -> Result<(), String> {
+    if status.activated == Some(true) {
+        return Err(
+            "This account is active, so it may be the live copy of your identity. \
+             Only accounts that were never activated can be deleted here."
+                .to_string(),
+        );
+    }
+    if status.activated.is_none() {
+        return Err("The PDS did not report whether this account is active".to_string());
+    }
+    match status.valid_did {
+        Some(false) => {}
+        Some(true) => {
+            return Err(
+                "The PDS reports that your DID points to this account, so it may be \
+                 the live copy of your identity."
+                    .to_string(),
+            )
+        }
+        None => {
+            return Err(
+                "The PDS did not report whether your DID points to this account".to_string(),
+            )
+        }
+    }
+    let Some(document_pds) = document_pds else {
+        return Err("Could not resolve your DID document to check where it points".to_string());
+    };
+    if normalize_pds_url(document_pds) == normalize_pds_url(pds_url) {
+        return Err(format!(
+            "Your DID document names {} as your PDS, so this account may be the live copy \
+             of your identity.",
+            document_pds
+        ));
+    }
+    let Some(pds_history) = pds_history else {
+        return Err(
+            "Could not read your DID's PLC history to confirm this account was never \
+             activated. Accounts without a did:plc can't be checked this way."
+                .to_string(),
+        );
+    };
+    if pds_history
+        .iter()
+        .any(|endpoint| normalize_pds_url(endpoint) == normalize_pds_url(pds_url))
+    {
+        return Err(
+            "Your DID's PLC history shows it has pointed at this PDS before, so this \
+             may be the account you migrated away from. Only accounts that were never \
+             activated can be deleted here."
+                .to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// Log into the partial account and check that it was never activated
+pub async fn find_orphaned_account(
+    pds_url: &str,
+    identifier: &str,
+    password: &str,
+) -> Result<OrphanedAccount, String> {
+    let pds_client = PdsClient::new();
+    let pds_url = normalize_pds_url(pds_url);
+
+    let login = pds_client
+        .login_with_explicit_pds(&normalize_identifier(identifier), password, &pds_url)
+        .await
+        .map_err(|e| format!("Login failed: {}", e))?;
+    let session = match login.session {
+        Some(session) if login.success => session,
+        _ => return Err(format!("Login failed: {}", login.message)),
+    };
+
+    let status = pds_client
+        .check_account_status(&session)
+        .await
+        .map_err(|e| format!("Account status check failed: {}", e))?;
+    if !status.success {
+        return Err(status.message);
+    }
+
+    // A cached document could predate a PLC update pointing the DID here
+    invalidate_identity(&session.did);
+    let document_pds = match WebIdentityResolver::new()
+        .resolve_did_to_pds_endpoint(&session.did)
+        .await
+    {
+        Ok(pds) => Some(pds),
+        Err(e) => {
+            console_warn!(
+                "[Cleanup] Could not resolve the DID document of {}: {}",
+                session.did,
+                e
+            );
+            None
+        }
+    };
+
+    // The old account after a migration looks the same as an orphan on every
+    // other check; only the history shows the DID once pointed here
+    let pds_history = if session.did.starts_with("did:plc:") {
+        match pds_client.fetch_plc_audit_log(&session.did).await {
+            Ok(log) => Some(pds_endpoints_in_audit_log(&log)),
+            Err(e) => {
+                console_warn!(
+                    "[Cleanup] Could not read the PLC audit log of {}: {}",
+                    session.did,
+                    e
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+    check_deletable(
+        &status,
+        &pds_url,
+        document_pds.as_deref(),
+        pds_history.as_deref(),
+    )?;
+
+    console_info!(
+        "[Cleanup] Found never-activated account {} on {}",
+        session.did,
+        session.pds
+    );
+    Ok(OrphanedAccount {
+        indexed_records: status.indexed_records.unwrap_or_default(),
+        imported_blobs: status.imported_blobs.unwrap_or_default(),
+        session,
+    })
+}
+
+/// Have the PDS email the token required for deletion
+pub async fn request_orphan_deletion(account: &OrphanedAccount) -> Result<(), String> {
+    let response = PdsClient::new()
+        .request_account_delete(&account.session)
+        .await
+        .map_err(|e| e.to_string())?;
+    if response.success {
+        Ok(())
+    } else {
+        Err(response.message)
+    }
+}
+
+/// Delete the account and forget any local state pointing at it
+pub async fn delete_orphaned_account(
+    account: &OrphanedAccount,
+    password: &str,
+    token: &str,
+) -> Result<(), String> {
+    let response = PdsClient::new()
+        .delete_account(&account.session, password, token.trim())
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.success {
+        return Err(response.message);
+    }
+
+    console_info!(
+        "[Cleanup] Deleted account {} on {}",
+        account.session.did,
+        account.session.pds
+    );
+
+    // A stored "new PDS" session for this account would resume a migration into it
+    if let Ok(stored) = LocalStorageManager::get_new_session() {
+        if stored.did == account.session.did && stored.pds == account.session.pds {
+            if let Err(e) = LocalStorageManager::clear_migration_data() {
+                console_warn!("[Cleanup] Failed to clear stored migration data: {}", e);
+            }
+        }
+    }
+    invalidate_identity(&account.session.did);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PDS: &str = "https://target.example";
+    const ELSEWHERE: Option<&str> = Some("https://other.example");
+
+    fn history(endpoints: &[&str]) -> Vec<String> {
+        endpoints.iter().map(|e| e.to_string()).collect()
+    }
+
+    fn status(activated: Option<bool>, valid_did: Option<bool>) -> ClientAccountStatusResponse {
+        ClientAccountStatusResponse {
+            success: true,
+            activated,
+            valid_did,
+            ..ClientAccountStatusResponse::default()
+        }
+    }
+
+    #[test]
+    fn test_only_never_activated_accounts_are_deletable() {
+        let never_here = history(&["https://other.example"]);
+        let orphan = status(Some(false), Some(false));
+        assert!(check_deletable(&orphan, PDS, ELSEWHERE, Some(&never_here)).is_ok());
+        assert!(check_deletable(
+            &status(Some(true), Some(false)),
+            PDS,
+            ELSEWHERE,
+            Some(&never_here)
+        )
+        .is_err());
+        assert!(check_deletable(
+            &status(None, Some(false)),
+            PDS,
+            ELSEWHERE,
+            Some(&never_here)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_refuses_account_the_did_points_to() {
+        // PLC update submitted, activation failed: deactivated, but the DID is here
+        let orphan = status(Some(false), Some(false));
+        assert!(check_deletable(&orphan, PDS, Some("https://target.example/"), None).is_err());
+        assert!(check_deletable(&orphan, "target.example", Some(PDS), None).is_err());
+        assert!(check_deletable(&orphan, PDS, None, None).is_err());
+    }
+
+    #[test]
+    fn test_refuses_account_with_valid_did() {
+        // Deactivated, but the PDS still considers the DID its own
+        let never_here = history(&["https://other.example"]);
+        assert!(check_deletable(
+            &status(Some(false), Some(true)),
+            PDS,
+            ELSEWHERE,
+            Some(&never_here)
+        )
+        .is_err());
+        assert!(check_deletable(
+            &status(Some(false), None),
+            PDS,
+            ELSEWHERE,
+            Some(&never_here)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_refuses_source_account_after_migration() {
+        // Moved from target.example to other.example: the old account is
+        // deactivated, invalid and not in the document, but the history names it
+        let source = status(Some(false), Some(false));
+        let moved = history(&[PDS, "https://other.example"]);
+        assert!(check_deletable(&source, PDS, ELSEWHERE, Some(&moved)).is_err());
+        assert!(check_deletable(&source, PDS, ELSEWHERE, None).is_err());
+    }
+
+    #[test]
+    fn test_pds_endpoints_in_audit_log() {
+        let log: Vec<Value> = serde_json::from_value(serde_json::json!([
+            {"operation": {"type": "create", "service": "https://bsky.social"}},
+            {"operation": {"type": "plc_operation", "services": {
+                "atproto_pds": {"type": "AtprotoPersonalDataServer", "endpoint": "https://target.example"}
+            }}, "nullified": true},
+            {"operation": {"type": "plc_tombstone"}}
+        ]))
+        .unwrap();
+        assert_eq!(
+            pds_endpoints_in_audit_log(&log),
+            vec!["https://bsky.social", "https://target.example"]
+        );
+    }
+}
@@ -0,0 +1,121 @@
+//! Offering the new account's password to the browser's password manager
+//!
+//! The new PDS password is typed once, on Form 3, and a user who didn't write
+//! it down is locked out as soon as the old session is gone. Once the account
+//! exists the page offers to hand the handle and password to the browser through
+//! the Credential Management API (`navigator.credentials.store` with a
+//! `PasswordCredential`). Browsers file the entry under this site's origin, so
+//! it is named after the handle and the new PDS to be findable later.
+//!
+//! Chromium-based browsers implement `PasswordCredential`; Firefox and Safari
+//! don't, and the offer isn't shown there.
+
+use serde::{Deserialize, Serialize};
+
+/// What happened to the offer in this migration
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CredentialSaveStatus {
+    #[default]
+    NotOffered,
+    /// Handed to the browser; it shows its own prompt, and doesn't say whether
+    /// the user accepted it
+    Offered,
+    Failed(String),
+}
+
+/// Label shown in the password manager, e.g. `@alice.example (new.example)`
+pub fn credential_name(handle: &str, pds_url: &str) -> String {
+    let host = pds_url
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_end_matches('/');
+    format!("@{} ({})", handle.trim_start_matches('@'), host)
+}
+
+/// Whether the browser can store password credentials
+#[cfg(feature = "web")]
+pub fn credential_management_supported() -> bool {
+    use wasm_bindgen::JsValue;
+
+    let Some(window) = web_sys::window() else {
+        return false;
+    };
+    let defined = |target: &JsValue, name: &str| {
+        js_sys::Reflect::get(target, &JsValue::from_str(name))
+            .map(|value| !value.is_undefined() && !value.is_null())
+            .unwrap_or(false)
+    };
+    defined(&window, "PasswordCredential") && defined(&window.navigator(), "credentials")
+}
+
+#[cfg(not(feature = "web"))]
+pub fn credential_management_supported() -> bool {
+    false
+}
+
+/// Ask the browser to save a login for the new account
+///
+/// `PasswordCredential` isn't in stable `web_sys`, so it's reached via `Reflect`.
+#[cfg(feature = "web")]
+pub async fn save_password_credential(id: &str, password: &str, name: &str) -> Result<(), String> {
+    use wasm_bindgen::{JsCast, JsValue};
+
+    let unsupported = "This browser can't save passwords from web pages";
+    let window = web_sys::window().ok_or("No browser window")?;
+    let constructor: js_sys::Function =
+        js_sys::Reflect::get(&window, &JsValue::from_str("PasswordCredential"))
+            .map_err(|_| unsupported)?
+            .dyn_into()
+            .map_err(|_| unsupported)?;
+    let credentials = js_sys::Reflect::get(&window.navigator(), &JsValue::from_str("credentials"))
+        .ok()
+        .filter(|value| !value.is_undefined() && !value.is_null())
+        .ok_or(unsupported)?;
+    let store: js_sys::Function = js_sys::Reflect::get(&credentials, &JsValue::from_str("store"))
+        .map_err(|_| unsupported)?
+        .dyn_into()
+        .map_err(|_| unsupported)?;
+
+    let data = js_sys::Object::new();
+    for (key, value) in [("id", id), ("password", password), ("name", name)] {
+        js_sys::Reflect::set(&data, &JsValue::from_str(key), &JsValue::from_str(value))
+            .map_err(|e| format!("Could not build the credential: {:?}", e))?;
+    }
+    let credential = js_sys::Reflect::construct(&constructor, &js_sys::Array::of1(&data))
+        .map_err(|e| format!("Browser rejected the credential: {:?}", e))?;
+    let promise: js_sys::Promise = store
+        .call1(&credentials, &credential)
+        .map_err(|e| format!("Saving the password failed: {:?}", e))?
+        .dyn_into()
+        .map_err(|_| "Saving the password did not return a promise")?;
+    wasm_bindgen_futures::JsFuture::from(promise)
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("Saving the password failed: {:?}", e))
+}
+
+#[cfg(not(feature = "web"))]
+pub async fn save_password_credential(
+    _id: &str,
+    _password: &str,
+    _name: &str,
+) -> Result<(), String> {
+    Err("Saving passwords needs a browser".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_credential_name_shows_handle_and_pds_host() {
+        assert_eq!(
+            credential_name("alice.example", "https://new.example/"),
+            "@alice.example (new.example)"
+        );
+        assert_eq!(
+            credential_name("@alice.example", "new.example"),
+            "@alice.example (new.example)"
+        );
+    }
+}
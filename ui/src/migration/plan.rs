@@ -0,0 +1,337 @@
+//! Declarative migration plans
+//!
+//! The content phase of a migration (everything after the account exists on
+//! the new PDS) is described as an ordered list of [`StepDescriptor`]s rather
+//! than hard-coded calls. The orchestrator walks the list, skipping a step when
+//! one of its dependencies didn't complete and stopping when a required step
+//! fails. Variants such as "skip preferences" or "emergency" are just different
+//! lists built by [`MigrationPlan::for_variant`].
+
+use serde::{Deserialize, Serialize};
+
+/// One unit of work the orchestrator knows how to run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PlanStep {
+    /// Stream the repository CAR from the old PDS into the new one
+    Repository,
+    /// Check the imported repository at the selected verification level
+    VerifyRepository,
+    /// Copy blobs to the new PDS
+    Blobs,
+    /// Compare blob counts and re-upload anything missing
+    VerifyBlobs,
+    /// Export preferences from the old PDS and save a backup
+    ExportPreferences,
+    /// Load preferences from a backup saved earlier instead of exporting them
+    RestorePreferencesBackup,
+    /// Write the exported or restored preferences to the new PDS
+    ImportPreferences,
+    /// Copy new records and blobs from the old PDS until the user is ready to switch
+    ContinuousSync,
+    /// Note the names of the old account's app passwords for re-creating later
+    AppPasswordInventory,
+    /// Set the new account's email and have the PDS send a confirmation code
+    EmailSetup,
+    /// Fetch the PLC recommendation and request the PLC token (hands over to Form 4)
+    PlcSetup,
+    /// Copy records and their blobs into an existing account with a different DID
+    CopyRecords,
+}
+
+impl PlanStep {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PlanStep::Repository => "Repository",
+            PlanStep::VerifyRepository => "Repository verification",
+            PlanStep::Blobs => "Blobs",
+            PlanStep::VerifyBlobs => "Blob verification",
+            PlanStep::ExportPreferences => "Preferences export",
+            PlanStep::RestorePreferencesBackup => "Preferences backup restore",
+            PlanStep::ImportPreferences => "Preferences import",
+            PlanStep::ContinuousSync => "Continuous sync",
+            PlanStep::AppPasswordInventory => "App password inventory",
+            PlanStep::EmailSetup => "Email setup",
+            PlanStep::PlcSetup => "PLC setup",
+            PlanStep::CopyRecords => "Content copy",
+        }
+    }
+
+    /// Whether the step needs a session on the new PDS
+    pub fn needs_target_account(&self) -> bool {
+        !matches!(
+            self,
+            PlanStep::ExportPreferences
+                | PlanStep::RestorePreferencesBackup
+                | PlanStep::AppPasswordInventory
+        )
+    }
+}
+
+/// A step, what it depends on, and whether its failure ends the migration
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepDescriptor {
+    pub step: PlanStep,
+    /// Steps that must have completed for this one to run
+    pub depends_on: Vec<PlanStep>,
+    /// Optional steps are skipped on failure, along with anything depending on them
+    pub required: bool,
+}
+
+impl StepDescriptor {
+    fn required(step: PlanStep, depends_on: &[PlanStep]) -> Self {
+        Self {
+            step,
+            depends_on: depends_on.to_vec(),
+            required: true,
+        }
+    }
+
+    fn optional(step: PlanStep, depends_on: &[PlanStep]) -> Self {
+        Self {
+            required: false,
+            ..Self::required(step, depends_on)
+        }
+    }
+}
+
+/// Named plan shapes offered to the user
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlanVariant {
+    /// Repository, blobs, preferences and identity, each verified
+    #[default]
+    Full,
+    /// As `Full`, leaving preferences on the old PDS
+    SkipPreferences,
+    /// Only back up preferences from the old PDS; nothing is written to the new one
+    ExportOnly,
+    /// Import preferences from the backup saved by an earlier attempt
+    ImportFromBackup,
+    /// As `Full`, but keep copying new records and blobs from the old account
+    /// until the user chooses to switch identity
+    KeepInSync,
+    /// Move data and identity as fast as possible when the old PDS is going away:
+    /// no verification passes, and blob or preference failures don't block PLC setup
+    Emergency,
+    /// Copy posts, media and preferences into an account that already exists on
+    /// the new PDS under a different DID; the identity stays where it is
+    ContentCopy,
+}
+
+impl PlanVariant {
+    pub const ALL: [PlanVariant; 7] = [
+        PlanVariant::Full,
+        PlanVariant::SkipPreferences,
+        PlanVariant::KeepInSync,
+        PlanVariant::ExportOnly,
+        PlanVariant::ImportFromBackup,
+        PlanVariant::Emergency,
+        PlanVariant::ContentCopy,
+    ];
+
+    /// Stable identifier used in form values
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlanVariant::Full => "full",
+            PlanVariant::SkipPreferences => "skip-preferences",
+            PlanVariant::KeepInSync => "keep-in-sync",
+            PlanVariant::ExportOnly => "export-only",
+            PlanVariant::ImportFromBackup => "import-from-backup",
+            PlanVariant::Emergency => "emergency",
+            PlanVariant::ContentCopy => "content-copy",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|variant| variant.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PlanVariant::Full => "Full migration (recommended)",
+            PlanVariant::SkipPreferences => "Full migration without preferences",
+            PlanVariant::KeepInSync => "Keep accounts in sync until I switch identity",
+            PlanVariant::ExportOnly => "Back up preferences only",
+            PlanVariant::ImportFromBackup => "Import preferences from a saved backup",
+            PlanVariant::Emergency => "Emergency (skip verification, don't stop for blobs)",
+            PlanVariant::ContentCopy => "Copy content into an account I already created",
+        }
+    }
+
+    /// Whether the plan logs into an existing account instead of creating one
+    pub fn uses_existing_account(&self) -> bool {
+        matches!(self, PlanVariant::ContentCopy)
+    }
+}
+
+/// Ordered steps the orchestrator runs for one variant
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigrationPlan {
+    pub variant: PlanVariant,
+    pub steps: Vec<StepDescriptor>,
+}
+
+impl MigrationPlan {
+    pub fn for_variant(variant: PlanVariant) -> Self {
+        use PlanStep::*;
+
+        let steps = match variant {
+            PlanVariant::Full => vec![
+                StepDescriptor::required(Repository, &[]),
+                StepDescriptor::required(VerifyRepository, &[Repository]),
+                StepDescriptor::required(Blobs, &[Repository]),
+                StepDescriptor::required(VerifyBlobs, &[Blobs]),
+                StepDescriptor::required(ExportPreferences, &[]),
+                StepDescriptor::required(ImportPreferences, &[ExportPreferences]),
+                StepDescriptor::optional(AppPasswordInventory, &[]),
+                StepDescriptor::optional(EmailSetup, &[]),
+                StepDescriptor::required(PlcSetup, &[VerifyRepository, VerifyBlobs]),
+            ],
+            PlanVariant::SkipPreferences => vec![
+                StepDescriptor::required(Repository, &[]),
+                StepDescriptor::required(VerifyRepository, &[Repository]),
+                StepDescriptor::required(Blobs, &[Repository]),
+                StepDescriptor::required(VerifyBlobs, &[Blobs]),
+                StepDescriptor::optional(AppPasswordInventory, &[]),
+                StepDescriptor::optional(EmailSetup, &[]),
+                StepDescriptor::required(PlcSetup, &[VerifyRepository, VerifyBlobs]),
+            ],
+            // Preferences are copied after the sync so changes made meanwhile come along
+            PlanVariant::KeepInSync => vec![
+                StepDescriptor::required(Repository, &[]),
+                StepDescriptor::required(VerifyRepository, &[Repository]),
+                StepDescriptor::required(Blobs, &[Repository]),
+                StepDescriptor::required(VerifyBlobs, &[Blobs]),
+                StepDescriptor::required(ContinuousSync, &[VerifyRepository, VerifyBlobs]),
+                StepDescriptor::required(ExportPreferences, &[]),
+                StepDescriptor::required(ImportPreferences, &[ExportPreferences]),
+                StepDescriptor::optional(AppPasswordInventory, &[]),
+                StepDescriptor::optional(EmailSetup, &[]),
+                StepDescriptor::required(PlcSetup, &[ContinuousSync]),
+            ],
+            PlanVariant::ExportOnly => vec![StepDescriptor::required(ExportPreferences, &[])],
+            PlanVariant::ImportFromBackup => vec![
+                StepDescriptor::required(RestorePreferencesBackup, &[]),
+                StepDescriptor::required(ImportPreferences, &[RestorePreferencesBackup]),
+            ],
+            PlanVariant::Emergency => vec![
+                StepDescriptor::required(Repository, &[]),
+                StepDescriptor::optional(Blobs, &[Repository]),
+                StepDescriptor::optional(ExportPreferences, &[]),
+                StepDescriptor::optional(ImportPreferences, &[ExportPreferences]),
+                StepDescriptor::optional(AppPasswordInventory, &[]),
+                StepDescriptor::optional(EmailSetup, &[]),
+                StepDescriptor::required(PlcSetup, &[Repository]),
+            ],
+            PlanVariant::ContentCopy => vec![
+                StepDescriptor::required(CopyRecords, &[]),
+                StepDescriptor::optional(ExportPreferences, &[]),
+                StepDescriptor::optional(ImportPreferences, &[ExportPreferences]),
+            ],
+        };
+
+        Self { variant, steps }
+    }
+
+    /// Check that every dependency is planned earlier and no step appears twice
+    pub fn validate(&self) -> Result<(), String> {
+        for (index, descriptor) in self.steps.iter().enumerate() {
+            let earlier = &self.steps[..index];
+            if earlier.iter().any(|other| other.step == descriptor.step) {
+                return Err(format!(
+                    "{} appears more than once in the plan",
+                    descriptor.step.label()
+                ));
+            }
+            for dependency in &descriptor.depends_on {
+                if !earlier.iter().any(|other| other.step == *dependency) {
+                    return Err(format!(
+                        "{} depends on {}, which isn't planned before it",
+                        descriptor.step.label(),
+                        dependency.label()
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, step: PlanStep) -> bool {
+        self.steps.iter().any(|descriptor| descriptor.step == step)
+    }
+
+    /// Whether an account has to be created on (or logged into) the new PDS first
+    pub fn needs_target_account(&self) -> bool {
+        self.steps
+            .iter()
+            .any(|descriptor| descriptor.step.needs_target_account())
+    }
+
+    /// Whether the plan ends by handing over to PLC verification in Form 4
+    pub fn hands_over_to_plc(&self) -> bool {
+        self.contains(PlanStep::PlcSetup)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_variant_builds_a_valid_plan() {
+        for variant in PlanVariant::ALL {
+            let plan = MigrationPlan::for_variant(variant);
+            assert_eq!(plan.validate(), Ok(()), "{:?}", variant);
+            assert_eq!(PlanVariant::parse(variant.as_str()), Some(variant));
+        }
+
+        let full = MigrationPlan::for_variant(PlanVariant::Full);
+        assert!(full.contains(PlanStep::ImportPreferences));
+        assert!(full.hands_over_to_plc());
+        assert!(!MigrationPlan::for_variant(PlanVariant::SkipPreferences)
+            .contains(PlanStep::ExportPreferences));
+        assert!(!MigrationPlan::for_variant(PlanVariant::ExportOnly).needs_target_account());
+        assert!(!MigrationPlan::for_variant(PlanVariant::Emergency).contains(PlanStep::VerifyBlobs));
+        assert!(!full.contains(PlanStep::ContinuousSync));
+        assert!(MigrationPlan::for_variant(PlanVariant::KeepInSync).hands_over_to_plc());
+        let copy = MigrationPlan::for_variant(PlanVariant::ContentCopy);
+        assert!(copy.needs_target_account() && !copy.hands_over_to_plc());
+        assert!(!copy.contains(PlanStep::Repository));
+        for variant in PlanVariant::ALL {
+            let plan = MigrationPlan::for_variant(variant);
+            assert_eq!(
+                plan.contains(PlanStep::AppPasswordInventory),
+                plan.hands_over_to_plc()
+            );
+            assert_eq!(
+                plan.contains(PlanStep::EmailSetup),
+                plan.hands_over_to_plc()
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_out_of_order_dependencies() {
+        let plan = MigrationPlan {
+            variant: PlanVariant::Full,
+            steps: vec![
+                StepDescriptor::required(
+                    PlanStep::ImportPreferences,
+                    &[PlanStep::ExportPreferences],
+                ),
+                StepDescriptor::required(PlanStep::ExportPreferences, &[]),
+            ],
+        };
+        assert!(plan.validate().is_err());
+
+        let duplicate = MigrationPlan {
+            variant: PlanVariant::Full,
+            steps: vec![
+                StepDescriptor::required(PlanStep::Repository, &[]),
+                StepDescriptor::required(PlanStep::Repository, &[]),
+            ],
+        };
+        assert!(duplicate.validate().is_err());
+    }
+}
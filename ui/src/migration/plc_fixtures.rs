@@ -0,0 +1,218 @@
+//! Golden PLC operations for tests
+//!
+//! A wrong PLC operation is the one mistake a migration can't take back: a
+//! dropped rotation key locks the user out of their identity, and a bad
+//! endpoint points it somewhere the account doesn't live. The JSON files in
+//! `fixtures/plc` pin down what the crate builds from known inputs, unsigned
+//! and signed, for plain accounts and for accounts holding a recovery key of
+//! their own, plus the did:web document that replaces an operation for web
+//! DIDs. The property tests below check that any operation the fallback
+//! builds survives serialization unchanged and passes the directory's rules
+//! once signed.
+
+use serde_json::Value;
+
+/// Rotation key the old PDS holds
+pub const PDS_ROTATION_KEY: &str = "did:key:zQ3shhCGUqDKjStzuDxPkTxN6ujddP4RkEKJJouJGRRkaLGbg";
+/// Recovery key the user added ahead of the PDS's
+pub const RECOVERY_KEY: &str = "did:key:zDnaeWgbpcUat3VPa1GtRwXt5SeGMbFNpz5NcaD3yqkbQJHkE";
+/// Repo signing key the new PDS reserved
+pub const SIGNING_KEY: &str = "did:key:zQ3shXjHeiBuRCKmM36cuYnm7YEMzhGnCmCyW92sRJ9pribSF";
+pub const NEW_HANDLE: &str = "alice.new.example";
+pub const NEW_ENDPOINT: &str = "https://new.example";
+pub const PREV_CID: &str = "bafyreigdxwlq5ltwlvz5bfbrecbaxjj7i5elfhh4vmaudvrpmyhr6fzbim";
+
+/// Parse one of the files in `fixtures/plc`
+pub fn golden(name: &str) -> Value {
+    let text = match name {
+        "current" => include_str!("fixtures/plc/current.json"),
+        "current_with_recovery_key" => include_str!("fixtures/plc/current_with_recovery_key.json"),
+        "unsigned" => include_str!("fixtures/plc/unsigned.json"),
+        "unsigned_with_recovery_key" => {
+            include_str!("fixtures/plc/unsigned_with_recovery_key.json")
+        }
+        "signed" => include_str!("fixtures/plc/signed.json"),
+        "recommended" => include_str!("fixtures/plc/recommended.json"),
+        "did_web_document" => include_str!("fixtures/plc/did_web_document.json"),
+        other => panic!("no PLC fixture named {}", other),
+    };
+    serde_json::from_str(text).unwrap_or_else(|e| panic!("fixture {} is not JSON: {}", name, e))
+}
+
+/// `unsigned` as the old PDS would return it after signing
+pub fn sign(unsigned: &Value, prev: &str, sig: &str) -> Value {
+    let mut signed = unsigned.clone();
+    signed["type"] = Value::from("plc_operation");
+    signed["prev"] = Value::from(prev);
+    signed["sig"] = Value::from(sig);
+    signed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migration::did_method::{build_did_web_document, did_method_for};
+    use crate::migration::plc_rules::{validate_signed_plc_operation, MAX_ROTATION_KEYS};
+    use crate::migration::plc_token_delivery::parse_externally_signed_operation;
+    use crate::migration::service_accounts::preserve_service_entries;
+    use crate::migration::steps::mock_pds::{session, MockPds};
+    use crate::migration::steps::plc_fallback::{
+        build_fallback_plc_operation, construct_plc_operation_fallback,
+    };
+    use crate::services::client::{ClientSessionCredentials, NewPds, PdsApi};
+    use proptest::prelude::*;
+    use serde_json::json;
+
+    fn build(current: &Value) -> Result<Value, String> {
+        build_fallback_plc_operation(current, SIGNING_KEY, NEW_HANDLE, NEW_ENDPOINT, None)
+    }
+
+    #[test]
+    fn test_operations_match_golden_files() {
+        assert_eq!(build(&golden("current")).unwrap(), golden("unsigned"));
+        assert_eq!(
+            build(&golden("current_with_recovery_key")).unwrap(),
+            golden("unsigned_with_recovery_key")
+        );
+
+        let signed = sign(
+            &golden("unsigned"),
+            PREV_CID,
+            golden("signed")["sig"].as_str().unwrap(),
+        );
+        assert_eq!(signed, golden("signed"));
+        assert!(validate_signed_plc_operation(&golden("signed")).is_empty());
+        let compact = parse_externally_signed_operation(
+            &serde_json::to_string_pretty(&golden("signed")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&compact).unwrap(),
+            golden("signed")
+        );
+
+        // Recommendations describe only the PDS; the labeler comes from the current data
+        let mut recommended = golden("recommended");
+        let restored = preserve_service_entries(&mut recommended, &golden("current"));
+        assert_eq!(restored, vec!["atproto_labeler"]);
+        assert_eq!(
+            recommended["services"]["atproto_labeler"],
+            golden("current")["services"]["atproto_labeler"]
+        );
+        assert_eq!(recommended["verificationMethods"]["atproto"], SIGNING_KEY);
+    }
+
+    #[test]
+    fn test_rotation_keys_are_never_injected_or_dropped() {
+        // The user's recovery key keeps its place ahead of the PDS's key
+        let operation = build(&golden("current_with_recovery_key")).unwrap();
+        assert_eq!(
+            operation["rotationKeys"],
+            json!([RECOVERY_KEY, PDS_ROTATION_KEY])
+        );
+        assert!(!operation["rotationKeys"]
+            .as_array()
+            .unwrap()
+            .contains(&json!(SIGNING_KEY)));
+
+        // Something that isn't a did:key can't ride along into the operation
+        let mut injected = golden("current");
+        injected["rotationKeys"] = json!([PDS_ROTATION_KEY, "https://attacker.example/key"]);
+        assert!(build(&injected).is_err());
+        injected["rotationKeys"] = json!([PDS_ROTATION_KEY, 42]);
+        assert!(build(&injected).is_err());
+
+        // Carried over as-is, so duplicates and overflows surface at validation
+        let mut duplicated = golden("current");
+        duplicated["rotationKeys"] = json!([PDS_ROTATION_KEY, PDS_ROTATION_KEY]);
+        let signed = sign(&build(&duplicated).unwrap(), PREV_CID, "c2ln");
+        assert!(validate_signed_plc_operation(&signed)
+            .iter()
+            .any(|problem| problem.contains("listed twice")));
+        let mut overflowing = golden("current");
+        overflowing["rotationKeys"] = json!(vec![PDS_ROTATION_KEY; MAX_ROTATION_KEYS + 1]);
+        let signed = sign(&build(&overflowing).unwrap(), PREV_CID, "c2ln");
+        assert!(validate_signed_plc_operation(&signed)
+            .iter()
+            .any(|problem| problem.contains("at most 5")));
+    }
+
+    #[test]
+    fn test_did_web_skips_the_plc_operation() {
+        let method = did_method_for("did:web:alice.example").unwrap();
+        assert!(!method.requires_token());
+
+        let pds = MockPds::default();
+        let api: &dyn PdsApi = &pds;
+        let web_session = ClientSessionCredentials {
+            did: "did:web:alice.example".to_string(),
+            ..session(NEW_ENDPOINT)
+        };
+        let refused = futures::executor::block_on(construct_plc_operation_fallback(
+            &NewPds::new(api, web_session),
+            Some(SIGNING_KEY),
+        ));
+        assert!(refused.unwrap_err().contains("not a did:plc"));
+
+        let recommended = json!({
+            "alsoKnownAs": ["at://alice.example"],
+            "verificationMethods": { "atproto": SIGNING_KEY },
+        });
+        let document = build_did_web_document(
+            "did:web:alice.example",
+            &recommended,
+            "https://new.example/",
+        )
+        .unwrap();
+        assert_eq!(document, golden("did_web_document"));
+    }
+
+    fn did_key() -> impl Strategy<Value = String> {
+        "[1-9A-HJ-NP-Za-km-z]{44}".prop_map(|key| format!("did:key:zQ3sh{}", key))
+    }
+
+    proptest! {
+        #[test]
+        fn constructed_operations_round_trip_and_validate(
+            rotation_keys in prop::collection::btree_set(did_key(), 1..=MAX_ROTATION_KEYS),
+            signing_key in did_key(),
+            name in "[a-z][a-z0-9]{0,15}",
+            host in "[a-z][a-z0-9]{0,10}",
+            aliases in prop::collection::vec("https://[a-z]{1,12}\\.example", 0..3),
+            services in prop::collection::btree_map("[a-z_]{1,12}", "[a-z]{1,20}", 0..4),
+            sig in "[A-Za-z0-9_-]{20,90}",
+        ) {
+            let rotation_keys: Vec<String> = rotation_keys.into_iter().collect();
+            let mut current = golden("current");
+            current["rotationKeys"] = json!(rotation_keys);
+            current["alsoKnownAs"] = json!(aliases);
+            for (id, service_type) in &services {
+                current["services"][id] = json!({
+                    "type": service_type,
+                    "endpoint": format!("https://{}.example", id.replace('_', "-")),
+                });
+            }
+            let handle = format!("{}.{}.example", name, host);
+            let endpoint = format!("https://{}.example", host);
+
+            let operation =
+                build_fallback_plc_operation(&current, &signing_key, &handle, &endpoint, None)
+                    .unwrap();
+            prop_assert_eq!(&operation["rotationKeys"], &json!(rotation_keys));
+            prop_assert_eq!(&operation["verificationMethods"]["atproto"], &json!(signing_key));
+            prop_assert_eq!(&operation["alsoKnownAs"][0], &json!(format!("at://{}", handle)));
+            prop_assert_eq!(&operation["services"]["atproto_pds"]["endpoint"], &json!(endpoint));
+
+            let compact: Value = serde_json::from_str(&operation.to_string()).unwrap();
+            prop_assert_eq!(&compact, &operation);
+            let pretty: Value =
+                serde_json::from_str(&serde_json::to_string_pretty(&operation).unwrap()).unwrap();
+            prop_assert_eq!(&pretty, &operation);
+
+            let signed = sign(&operation, PREV_CID, &sig);
+            prop_assert_eq!(validate_signed_plc_operation(&signed), Vec::<String>::new());
+            let submitted = parse_externally_signed_operation(&signed.to_string()).unwrap();
+            prop_assert_eq!(serde_json::from_str::<Value>(&submitted).unwrap(), signed);
+        }
+    }
+}
@@ -0,0 +1,341 @@
+//! Checking a signed PLC operation against the directory's rules before submitting it
+//!
+//! plc.directory rejects operations it considers malformed with a terse 400,
+//! and by then the emailed token has been spent on the signature. The rules
+//! that can be checked without the audit log are applied here first: the
+//! operation's shape, at most [`MAX_ROTATION_KEYS`] distinct did:key rotation
+//! keys, the directory's limits on aliases and services, canonical handles, an
+//! https origin for the PDS, and the encoded size. Each problem is reported
+//! with what to change, so the user knows whether to fix the operation or to
+//! ask their PDS. Signature and `prev` checks need the log and stay with the
+//! directory.
+
+use serde_json::{Map, Value};
+
+use crate::migration::steps::plc_fallback::{
+    check_did_key, check_handle, endpoint_host, ATPROTO_PDS_SERVICE, ATPROTO_PDS_TYPE,
+};
+
+/// Rotation keys the PLC method allows
+pub const MAX_ROTATION_KEYS: usize = 5;
+/// Limits plc.directory applies on top of the method's own rules
+pub const MAX_ALSO_KNOWN_AS: usize = 10;
+pub const MAX_ALSO_KNOWN_AS_LENGTH: usize = 256;
+pub const MAX_SERVICES: usize = 10;
+pub const MAX_VERIFICATION_METHODS: usize = 10;
+pub const MAX_ENTRY_ID_LENGTH: usize = 32;
+pub const MAX_SERVICE_TYPE_LENGTH: usize = 256;
+pub const MAX_SERVICE_ENDPOINT_LENGTH: usize = 512;
+/// Largest operation the directory accepts, measured as DAG-CBOR
+pub const MAX_OPERATION_BYTES: usize = 4000;
+
+/// Everything wrong with a signed operation, or nothing
+pub fn validate_signed_plc_operation(operation: &Value) -> Vec<String> {
+    let mut problems = Vec::new();
+    let Some(object) = operation.as_object() else {
+        return vec!["The operation must be a JSON object".to_string()];
+    };
+
+    match object.get("type").and_then(Value::as_str) {
+        Some("plc_operation") => {}
+        Some("plc_tombstone") => problems.push(
+            "This is a tombstone, which would deactivate the DID permanently; the migration only submits plc_operation updates".to_string(),
+        ),
+        Some(other) => problems.push(format!(
+            "Operation type \"{}\" is not accepted; it must be \"plc_operation\"",
+            other
+        )),
+        None => problems.push("The operation has no \"type\"".to_string()),
+    }
+    match object.get("sig").and_then(Value::as_str) {
+        Some(sig) if is_base64url(sig) => {}
+        Some(_) => problems.push(
+            "The signature must be unpadded base64url; re-export it from your signing tool without \"=\" padding".to_string(),
+        ),
+        None => problems.push("The operation is not signed (no \"sig\")".to_string()),
+    }
+    if !object.get("prev").is_some_and(Value::is_string) {
+        problems.push(
+            "\"prev\" must be the CID of the newest operation in the DID's audit log".to_string(),
+        );
+    }
+
+    check_rotation_keys(object, &mut problems);
+    check_also_known_as(object, &mut problems);
+    check_verification_methods(object, &mut problems);
+    check_services(object, &mut problems);
+
+    let size = dag_cbor_len(operation);
+    if size > MAX_OPERATION_BYTES {
+        problems.push(format!(
+            "The operation is about {} bytes encoded, over the directory's {} byte limit; drop unused aliases or services",
+            size, MAX_OPERATION_BYTES
+        ));
+    }
+    problems
+}
+
+fn check_rotation_keys(object: &Map<String, Value>, problems: &mut Vec<String>) {
+    let Some(keys) = object.get("rotationKeys").and_then(Value::as_array) else {
+        problems.push("\"rotationKeys\" must be a list of did:key strings".to_string());
+        return;
+    };
+    if keys.is_empty() {
+        problems.push(
+            "At least one rotation key is required, or nobody could update the DID again"
+                .to_string(),
+        );
+    }
+    if keys.len() > MAX_ROTATION_KEYS {
+        problems.push(format!(
+            "{} rotation keys listed; the PLC method allows at most {}. Remove keys you no longer hold",
+            keys.len(),
+            MAX_ROTATION_KEYS
+        ));
+    }
+    for (i, key) in keys.iter().enumerate() {
+        match key.as_str() {
+            Some(key) => {
+                if let Err(e) = check_did_key(key, "Rotation key") {
+                    problems.push(e);
+                }
+                if keys[..i]
+                    .iter()
+                    .any(|earlier| earlier.as_str() == Some(key))
+                {
+                    problems.push(format!("Rotation key {} is listed twice", key));
+                }
+            }
+            None => problems.push("Every rotation key must be a string".to_string()),
+        }
+    }
+}
+
+fn check_also_known_as(object: &Map<String, Value>, problems: &mut Vec<String>) {
+    let Some(aliases) = object.get("alsoKnownAs").and_then(Value::as_array) else {
+        problems.push("\"alsoKnownAs\" must be a list".to_string());
+        return;
+    };
+    if aliases.len() > MAX_ALSO_KNOWN_AS {
+        problems.push(format!(
+            "{} alsoKnownAs entries; the directory accepts at most {}",
+            aliases.len(),
+            MAX_ALSO_KNOWN_AS
+        ));
+    }
+    for alias in aliases {
+        let Some(alias) = alias.as_str() else {
+            problems.push("Every alsoKnownAs entry must be a string".to_string());
+            continue;
+        };
+        if alias.len() > MAX_ALSO_KNOWN_AS_LENGTH {
+            problems.push(format!(
+                "alsoKnownAs entry is longer than {} characters",
+                MAX_ALSO_KNOWN_AS_LENGTH
+            ));
+        }
+        if let Some(handle) = alias.strip_prefix("at://") {
+            if let Err(e) = check_handle(handle) {
+                problems.push(format!("{}; use the lowercase punycode form", e));
+            }
+        }
+    }
+}
+
+fn check_verification_methods(object: &Map<String, Value>, problems: &mut Vec<String>) {
+    let Some(methods) = object.get("verificationMethods").and_then(Value::as_object) else {
+        problems.push("\"verificationMethods\" must be an object".to_string());
+        return;
+    };
+    if methods.len() > MAX_VERIFICATION_METHODS {
+        problems.push(format!(
+            "{} verification methods; the directory accepts at most {}",
+            methods.len(),
+            MAX_VERIFICATION_METHODS
+        ));
+    }
+    if !methods.contains_key("atproto") {
+        problems.push(
+            "No \"atproto\" verification method, so the new PDS's commits could not be verified"
+                .to_string(),
+        );
+    }
+    for (id, key) in methods {
+        check_entry_id(id, problems);
+        match key.as_str() {
+            Some(key) => {
+                if let Err(e) = check_did_key(key, &format!("Verification method {}", id)) {
+                    problems.push(e);
+                }
+            }
+            None => problems.push(format!(
+                "Verification method {} must be a did:key string",
+                id
+            )),
+        }
+    }
+}
+
+fn check_services(object: &Map<String, Value>, problems: &mut Vec<String>) {
+    let Some(services) = object.get("services").and_then(Value::as_object) else {
+        problems.push("\"services\" must be an object".to_string());
+        return;
+    };
+    if services.len() > MAX_SERVICES {
+        problems.push(format!(
+            "{} services; the directory accepts at most {}",
+            services.len(),
+            MAX_SERVICES
+        ));
+    }
+    for (id, service) in services {
+        check_entry_id(id, problems);
+        let service_type = service.get("type").and_then(Value::as_str);
+        let endpoint = service.get("endpoint").and_then(Value::as_str);
+        let (Some(service_type), Some(endpoint)) = (service_type, endpoint) else {
+            problems.push(format!(
+                "Service {} needs string \"type\" and \"endpoint\" fields",
+                id
+            ));
+            continue;
+        };
+        if service_type.len() > MAX_SERVICE_TYPE_LENGTH {
+            problems.push(format!("Service {} has an over-long type", id));
+        }
+        if endpoint.len() > MAX_SERVICE_ENDPOINT_LENGTH {
+            problems.push(format!(
+                "Service {} endpoint is longer than {} characters",
+                id, MAX_SERVICE_ENDPOINT_LENGTH
+            ));
+        }
+    }
+
+    match services.get(ATPROTO_PDS_SERVICE) {
+        Some(pds) => {
+            if pds.get("type").and_then(Value::as_str) != Some(ATPROTO_PDS_TYPE) {
+                problems.push(format!(
+                    "The {} service must have type {}",
+                    ATPROTO_PDS_SERVICE, ATPROTO_PDS_TYPE
+                ));
+            }
+            if let Some(endpoint) = pds.get("endpoint").and_then(Value::as_str) {
+                if let Err(e) = endpoint_host(endpoint) {
+                    problems.push(e);
+                }
+            }
+        }
+        None => problems.push(format!(
+            "No {} service, so the DID would not point at any PDS",
+            ATPROTO_PDS_SERVICE
+        )),
+    }
+}
+
+fn check_entry_id(id: &str, problems: &mut Vec<String>) {
+    if id.is_empty() || id.len() > MAX_ENTRY_ID_LENGTH || id.starts_with('#') {
+        problems.push(format!(
+            "Entry id \"{}\" must be 1 to {} characters without a leading #",
+            id, MAX_ENTRY_ID_LENGTH
+        ));
+    }
+}
+
+fn is_base64url(text: &str) -> bool {
+    !text.is_empty()
+        && text
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+/// Length of the CBOR head for a major type and argument
+fn cbor_head_len(argument: u64) -> usize {
+    match argument {
+        0..=23 => 1,
+        24..=0xff => 2,
+        0x100..=0xffff => 3,
+        0x1_0000..=0xffff_ffff => 5,
+        _ => 9,
+    }
+}
+
+/// Size of `value` encoded as DAG-CBOR, which is what the directory measures
+fn dag_cbor_len(value: &Value) -> usize {
+    match value {
+        Value::Null | Value::Bool(_) => 1,
+        Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+            (Some(u), _) => cbor_head_len(u),
+            (None, Some(i)) => cbor_head_len((-1 - i) as u64),
+            _ => 9,
+        },
+        Value::String(s) => cbor_head_len(s.len() as u64) + s.len(),
+        Value::Array(items) => {
+            cbor_head_len(items.len() as u64) + items.iter().map(dag_cbor_len).sum::<usize>()
+        }
+        Value::Object(map) => {
+            cbor_head_len(map.len() as u64)
+                + map
+                    .iter()
+                    .map(|(key, item)| {
+                        cbor_head_len(key.len() as u64) + key.len() + dag_cbor_len(item)
+                    })
+                    .sum::<usize>()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const KEY: &str = "did:key:zQ3shXjHeiBuRCKmM36cuYnm7YEMzhGnCmCyW92sRJ9pribSF";
+    const OTHER_KEY: &str = "did:key:zQ3shhCGUqDKjStzuDxPkTxN6ujddP4RkEKJJouJGRRkaLGbg";
+
+    fn operation() -> Value {
+        json!({
+            "type": "plc_operation",
+            "rotationKeys": [KEY],
+            "verificationMethods": { "atproto": OTHER_KEY },
+            "alsoKnownAs": ["at://alice.example.com"],
+            "services": {
+                "atproto_pds": {
+                    "type": "AtprotoPersonalDataServer",
+                    "endpoint": "https://pds.example.com"
+                }
+            },
+            "prev": "bafyreigdxwlq5ltwlvz5bfbrecbaxjj7i5elfhh4vmaudvrpmyhr6fzbim",
+            "sig": "c2lnbmF0dXJlX2J5dGVz-_"
+        })
+    }
+
+    #[test]
+    fn test_signed_operation_rules() {
+        assert!(validate_signed_plc_operation(&operation()).is_empty());
+
+        let mut too_many = operation();
+        too_many["rotationKeys"] = json!([KEY, KEY, KEY, KEY, KEY, KEY]);
+        too_many["alsoKnownAs"] = json!(["at://Alice.Example.com"]);
+        too_many["services"]["atproto_pds"]["endpoint"] = json!("http://pds.example.com");
+        too_many["sig"] = json!("c2ln==");
+        let problems = validate_signed_plc_operation(&too_many);
+        assert!(problems.iter().any(|p| p.contains("at most 5")));
+        assert!(problems.iter().any(|p| p.contains("listed twice")));
+        assert!(problems.iter().any(|p| p.contains("punycode")));
+        assert!(problems.iter().any(|p| p.contains("https")));
+        assert!(problems.iter().any(|p| p.contains("base64url")));
+
+        let mut oversized = operation();
+        oversized["services"]["bulk"] = json!({
+            "type": "x".repeat(200),
+            "endpoint": format!("https://{}.example", "a".repeat(500))
+        });
+        for i in 0..8 {
+            oversized["services"][format!("extra{}", i)] = json!({ "type": "Extra", "endpoint": format!("https://{}.example", "b".repeat(480)) });
+        }
+        let problems = validate_signed_plc_operation(&oversized);
+        assert!(problems.iter().any(|p| p.contains("byte limit")));
+
+        // Map head, then a one-character key and a 23-byte string, each with a one-byte head
+        assert_eq!(dag_cbor_len(&json!({ "a": "x".repeat(23) })), 1 + 2 + 24);
+    }
+}
@@ -0,0 +1,210 @@
+//! Waiting for the PLC token email
+//!
+//! `requestPlcOperationSignature` only says the email was queued; whether it
+//! arrives, lands in spam, or goes to an alias the user forgot about is out of
+//! our hands. The request time is kept per DID in localStorage so Form 4 can
+//! say how long the email has been on its way, point at the spam folder early,
+//! and after [`PlcTokenConfig::resend_after_secs`] offer a resend or signing
+//! with a recovery key instead. Each request replaces the token the PDS sent
+//! before, so a migration restarted after a page refresh reuses a recent
+//! request rather than invalidating the email the user is waiting for.
+//!
+//! The PDS only honours a token for [`PlcTokenConfig::token_validity_secs`].
+//! A user who leaves Form 4 open longer than that, or whose code the PDS
+//! refuses as expired, is walked through requesting a fresh one; everything
+//! already copied to the new PDS stays where it is.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::console_warn;
+use crate::migration::storage::LocalStorageManager;
+use crate::services::config::PlcTokenConfig;
+
+/// A PLC token request and how often it was repeated
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlcTokenDelivery {
+    pub did: String,
+    /// When the latest email was requested, ms since the epoch
+    pub requested_at_ms: u64,
+    pub resend_count: u32,
+    /// The PDS refused a code from this request as expired
+    #[serde(default)]
+    pub rejected_as_expired: bool,
+}
+
+/// How long the user has been waiting, in terms of what to show them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenWaitStage {
+    /// The email is probably still on its way
+    Waiting,
+    /// Long enough that spam filters or aliases are the likely culprit
+    CheckSpam,
+    /// Offer a resend and the recovery key path
+    Overdue,
+    /// The token can no longer be used; a fresh one has to be requested
+    Expired,
+}
+
+impl PlcTokenDelivery {
+    pub fn new(did: &str, now_ms: u64) -> Self {
+        Self {
+            did: did.to_string(),
+            requested_at_ms: now_ms,
+            resend_count: 0,
+            rejected_as_expired: false,
+        }
+    }
+
+    /// The same request after asking the PDS for another email
+    pub fn resent(&self, now_ms: u64) -> Self {
+        Self {
+            did: self.did.clone(),
+            requested_at_ms: now_ms,
+            resend_count: self.resend_count + 1,
+            rejected_as_expired: false,
+        }
+    }
+
+    /// The same request after the PDS refused its token as expired
+    pub fn expired(&self) -> Self {
+        Self {
+            rejected_as_expired: true,
+            ..self.clone()
+        }
+    }
+
+    pub fn elapsed_ms(&self, now_ms: u64) -> u64 {
+        now_ms.saturating_sub(self.requested_at_ms)
+    }
+
+    pub fn stage(&self, now_ms: u64, config: &PlcTokenConfig) -> TokenWaitStage {
+        let elapsed_secs = self.elapsed_ms(now_ms) / 1000;
+        if self.rejected_as_expired || elapsed_secs >= config.token_validity_secs {
+            TokenWaitStage::Expired
+        } else if elapsed_secs >= config.resend_after_secs {
+            TokenWaitStage::Overdue
+        } else if elapsed_secs >= config.spam_hint_after_secs {
+            TokenWaitStage::CheckSpam
+        } else {
+            TokenWaitStage::Waiting
+        }
+    }
+
+    /// Whether a migration for `did` can wait for this email instead of
+    /// requesting a new one
+    pub fn reusable_for(&self, did: &str, now_ms: u64, config: &PlcTokenConfig) -> bool {
+        self.did == did
+            && matches!(
+                self.stage(now_ms, config),
+                TokenWaitStage::Waiting | TokenWaitStage::CheckSpam
+            )
+    }
+}
+
+/// Whether a `signPlcOperation` failure means the token ran out, as opposed
+/// to a typo or a network problem
+pub fn is_expired_token_error(error: &str) -> bool {
+    error.contains("ExpiredToken") || error.to_lowercase().contains("token is expired")
+}
+
+/// The request saved before a page refresh, if any
+pub fn load_plc_token_delivery() -> Option<PlcTokenDelivery> {
+    LocalStorageManager::get_plc_token_delivery().ok()
+}
+
+pub fn save_plc_token_delivery(delivery: &PlcTokenDelivery) {
+    if let Err(e) = LocalStorageManager::store_plc_token_delivery(delivery) {
+        console_warn!("[PLC Token] Could not save the request time: {}", e);
+    }
+}
+
+/// Check an operation signed outside the app with a rotation key and return
+/// it compacted for submission
+pub fn parse_externally_signed_operation(text: &str) -> Result<String, String> {
+    let operation: Value = serde_json::from_str(text.trim())
+        .map_err(|e| format!("The signed operation is not valid JSON: {}", e))?;
+    let object = operation
+        .as_object()
+        .ok_or("The signed operation must be a JSON object")?;
+    if !object.get("sig").is_some_and(Value::is_string) {
+        return Err(
+            "The operation has no \"sig\" field; sign it with your recovery key first".into(),
+        );
+    }
+    if !object.get("prev").is_some_and(Value::is_string) {
+        return Err(
+            "The operation has no \"prev\" field; it must name the latest operation in your PLC log"
+                .into(),
+        );
+    }
+    Ok(operation.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_wait_stages_and_signed_operation() {
+        let config = PlcTokenConfig {
+            email_subject: "PLC Update Operation Requested".to_string(),
+            spam_hint_after_secs: 60,
+            resend_after_secs: 300,
+            token_validity_secs: 900,
+        };
+        let delivery = PlcTokenDelivery::new("did:plc:alice", 1_000_000);
+
+        assert_eq!(delivery.stage(1_000_000, &config), TokenWaitStage::Waiting);
+        assert_eq!(
+            delivery.stage(1_060_000, &config),
+            TokenWaitStage::CheckSpam
+        );
+        assert_eq!(delivery.stage(1_300_000, &config), TokenWaitStage::Overdue);
+        assert_eq!(delivery.stage(1_900_000, &config), TokenWaitStage::Expired);
+        assert_eq!(delivery.stage(0, &config), TokenWaitStage::Waiting);
+
+        assert!(delivery.reusable_for("did:plc:alice", 1_100_000, &config));
+        assert!(!delivery.reusable_for("did:plc:bob", 1_100_000, &config));
+        assert!(!delivery.reusable_for("did:plc:alice", 1_300_000, &config));
+
+        let resent = delivery.resent(1_400_000);
+        assert_eq!(resent.resend_count, 1);
+        assert_eq!(resent.stage(1_400_000, &config), TokenWaitStage::Waiting);
+        assert_eq!(
+            resent.expired().stage(1_400_000, &config),
+            TokenWaitStage::Expired
+        );
+        assert!(!resent
+            .expired()
+            .reusable_for("did:plc:alice", 1_400_000, &config));
+        assert!(!resent.expired().resent(1_500_000).rejected_as_expired);
+
+        // Older saves have no expiry flag
+        let saved: PlcTokenDelivery = serde_json::from_str(
+            r#"{"did":"did:plc:alice","requested_at_ms":1000000,"resend_count":0}"#,
+        )
+        .unwrap();
+        assert_eq!(saved, delivery);
+
+        assert!(is_expired_token_error(
+            r#"PLC signing failed: {"error":"ExpiredToken","message":"Token is expired"}"#
+        ));
+        assert!(!is_expired_token_error(
+            r#"PLC signing failed: {"error":"InvalidToken","message":"Token is invalid"}"#
+        ));
+
+        assert!(
+            parse_externally_signed_operation(r#"{"type":"plc_operation","prev":"bafyabc"}"#)
+                .unwrap_err()
+                .contains("sig")
+        );
+        assert!(parse_externally_signed_operation("not json").is_err());
+        assert_eq!(
+            parse_externally_signed_operation(
+                " {\"type\": \"plc_operation\", \"prev\": \"bafyabc\", \"sig\": \"c2ln\"}\n"
+            ),
+            Ok(r#"{"prev":"bafyabc","sig":"c2ln","type":"plc_operation"}"#.to_string())
+        );
+    }
+}
@@ -0,0 +1,352 @@
+//! What's left for the user once the identity update is done
+//!
+//! The migration can't finish everything: app passwords aren't carried over,
+//! apps stay logged in to the old PDS, and a handle on the user's own domain
+//! may depend on records that still point at the old host. The checklist is
+//! built from what the migration saw about the account (handle domain, DID
+//! method, labeler or feed services, whether the password was saved) and the
+//! items the user ticks off are kept in localStorage per DID, so coming back
+//! to the page later shows where they left off.
+
+use serde::{Deserialize, Serialize};
+
+use crate::console_warn;
+use crate::migration::password_manager::CredentialSaveStatus;
+use crate::migration::service_accounts::ServiceAccountProfile;
+use crate::migration::storage::LocalStorageManager;
+use crate::migration::types::MigrationState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ChecklistItemId {
+    SavePassword,
+    HandleDns,
+    Relogin,
+    AppPasswords,
+    ConfirmEmail,
+    TwoFactor,
+    RotationKey,
+    DidWebDocument,
+    ServiceAccount,
+    VerifyContent,
+    DeactivateOldAccount,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChecklistItem {
+    pub id: ChecklistItemId,
+    pub title: String,
+    pub detail: String,
+}
+
+impl ChecklistItem {
+    fn new(id: ChecklistItemId, title: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            id,
+            title: title.into(),
+            detail: detail.into(),
+        }
+    }
+}
+
+/// What the migration observed that decides which items apply
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChecklistContext {
+    pub did: String,
+    pub handle: String,
+    pub new_pds: String,
+    pub email: String,
+    pub email_confirmed: bool,
+    /// Handle isn't under one of the new PDS's user domains
+    pub custom_domain: bool,
+    pub password_saved: bool,
+    pub service_account: Option<ServiceAccountProfile>,
+    /// Names of the old account's app passwords, if they were listed
+    pub app_passwords: Option<Vec<String>>,
+    /// Days the old account was left active instead of being deactivated
+    pub old_account_grace_days: Option<u32>,
+}
+
+impl ChecklistContext {
+    pub fn from_state(state: &MigrationState) -> Self {
+        let (did, handle, new_pds) = state
+            .new_pds_session
+            .as_ref()
+            .map(|session| {
+                (
+                    session.did.clone(),
+                    session.handle.clone(),
+                    session.pds.clone(),
+                )
+            })
+            .unwrap_or_default();
+        let domains = state
+            .form2
+            .describe_response
+            .as_ref()
+            .map(|describe| describe.available_user_domains.clone())
+            .unwrap_or_default();
+        Self {
+            custom_domain: is_custom_domain(&handle, &domains),
+            did,
+            handle,
+            new_pds,
+            email: if state.email_verification.email.is_empty() {
+                state.form3.email.clone()
+            } else {
+                state.email_verification.email.clone()
+            },
+            email_confirmed: state.email_verification.is_confirmed(),
+            password_saved: state.credential_save == CredentialSaveStatus::Offered,
+            service_account: state.service_account.clone(),
+            app_passwords: state.app_passwords.as_ref().map(|passwords| {
+                passwords
+                    .iter()
+                    .map(|password| password.name.clone())
+                    .collect()
+            }),
+            old_account_grace_days: state
+                .form4
+                .old_account_grace_days
+                .filter(|_| !state.migration_progress.old_account_deactivated),
+        }
+    }
+
+    fn is_did_web(&self) -> bool {
+        self.did.starts_with("did:web:")
+    }
+}
+
+/// Whether a handle is on the user's own domain rather than one the PDS hands out
+pub fn is_custom_domain(handle: &str, available_user_domains: &[String]) -> bool {
+    let handle = handle.trim_start_matches('@').to_ascii_lowercase();
+    if handle.is_empty() {
+        return false;
+    }
+    !available_user_domains.iter().any(|domain| {
+        let suffix = domain.trim_start_matches('.').to_ascii_lowercase();
+        handle.ends_with(&format!(".{}", suffix))
+    })
+}
+
+/// Items that apply to this account, most urgent first
+pub fn build_checklist(context: &ChecklistContext) -> Vec<ChecklistItem> {
+    use ChecklistItemId::*;
+
+    let mut items = Vec::new();
+    if !context.password_saved {
+        items.push(ChecklistItem::new(
+            SavePassword,
+            "Store your new password",
+            "It isn't in a password manager yet. Without it you can't log in to the new PDS.",
+        ));
+    }
+    if context.custom_domain {
+        items.push(ChecklistItem::new(
+            HandleDns,
+            format!("Check the handle records for {}", context.handle),
+            format!(
+                "The _atproto.{} TXT record should still read did={}. If the domain's /.well-known/atproto-did was served by the old PDS, serve it from {} or switch to the DNS record.",
+                context.handle, context.did, context.new_pds
+            ),
+        ));
+    }
+    if let Some(days) = context.old_account_grace_days {
+        items.push(ChecklistItem::new(
+            DeactivateOldAccount,
+            "Deactivate your old account",
+            format!(
+                "It stays active for {} days as you asked, and services that still read from the old PDS may show stale posts meanwhile. You'll be reminded on this page to deactivate it.",
+                days
+            ),
+        ));
+    }
+    items.push(ChecklistItem::new(
+        Relogin,
+        "Log in again on your phone and other apps",
+        format!(
+            "Apps still hold a session with the old PDS. Log out and back in as @{} so they find {}.",
+            context.handle, context.new_pds
+        ),
+    ));
+    match &context.app_passwords {
+        // The old account had none, so there is nothing to re-create
+        Some(names) if names.is_empty() => {}
+        Some(names) => items.push(ChecklistItem::new(
+            AppPasswords,
+            "Re-create app passwords",
+            format!(
+                "App passwords aren't migrated. Create new ones for {} and update the apps that used them.",
+                names.join(", ")
+            ),
+        )),
+        None => items.push(ChecklistItem::new(
+            AppPasswords,
+            "Re-create app passwords",
+            "App passwords aren't migrated. Create new ones on the new PDS for every client and bot that used one.",
+        )),
+    }
+    if !context.email_confirmed {
+        items.push(ChecklistItem::new(
+            ConfirmEmail,
+            "Confirm your email on the new PDS",
+            format!(
+                "Look for a confirmation message sent to {}; password resets go there now.",
+                context.email
+            ),
+        ));
+    }
+    items.push(ChecklistItem::new(
+        TwoFactor,
+        "Turn email two-factor back on",
+        "Two-factor sign-in is a setting of each PDS and starts off on the new one.",
+    ));
+    if context.is_did_web() {
+        items.push(ChecklistItem::new(
+            DidWebDocument,
+            "Keep your did.json published",
+            "Your identity now depends on the new document staying at /.well-known/did.json on your domain.",
+        ));
+    } else {
+        items.push(ChecklistItem::new(
+            RotationKey,
+            "Back up a rotation key",
+            "The new PDS holds the keys to your did:plc. Adding a rotation key of your own, and keeping it offline, lets you move again if that PDS disappears.",
+        ));
+    }
+    if let Some(profile) = &context.service_account {
+        let kinds = profile
+            .kinds
+            .iter()
+            .map(|kind| kind.label())
+            .collect::<Vec<_>>()
+            .join(" and ");
+        items.push(ChecklistItem::new(
+            ServiceAccount,
+            format!("Reconnect your {} services", kinds.to_lowercase()),
+            "Follow the labeler and feed generator steps shown during the identity update.",
+        ));
+    }
+    items.push(ChecklistItem::new(
+        VerifyContent,
+        "Check your posts, follows and images",
+        "Open your profile on the new PDS and spot-check older posts and media.",
+    ));
+    items
+}
+
+/// Ticked items, kept across visits
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChecklistProgress {
+    pub did: String,
+    pub checked: Vec<ChecklistItemId>,
+}
+
+impl ChecklistProgress {
+    pub fn set(&mut self, id: ChecklistItemId, checked: bool) {
+        self.checked.retain(|item| *item != id);
+        if checked {
+            self.checked.push(id);
+        }
+    }
+
+    pub fn is_checked(&self, id: ChecklistItemId) -> bool {
+        self.checked.contains(&id)
+    }
+}
+
+/// Progress saved for a DID; a different DID starts empty
+pub fn load_checklist_progress(did: &str) -> ChecklistProgress {
+    LocalStorageManager::get_checklist_progress()
+        .ok()
+        .filter(|progress| progress.did == did)
+        .unwrap_or_else(|| ChecklistProgress {
+            did: did.to_string(),
+            checked: Vec::new(),
+        })
+}
+
+pub fn save_checklist_progress(progress: &ChecklistProgress) {
+    if let Err(e) = LocalStorageManager::store_checklist_progress(progress) {
+        console_warn!("[Checklist] Could not save progress: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> ChecklistContext {
+        ChecklistContext {
+            did: "did:plc:abc".to_string(),
+            handle: "alice.example".to_string(),
+            new_pds: "https://new.example".to_string(),
+            email: "alice@example.com".to_string(),
+            email_confirmed: false,
+            custom_domain: true,
+            password_saved: false,
+            service_account: None,
+            app_passwords: None,
+            old_account_grace_days: None,
+        }
+    }
+
+    fn ids(context: &ChecklistContext) -> Vec<ChecklistItemId> {
+        build_checklist(context)
+            .into_iter()
+            .map(|item| item.id)
+            .collect()
+    }
+
+    #[test]
+    fn test_checklist_follows_observed_setup() {
+        let items = ids(&context());
+        assert!(items.contains(&ChecklistItemId::SavePassword));
+        assert!(items.contains(&ChecklistItemId::HandleDns));
+        assert!(items.contains(&ChecklistItemId::RotationKey));
+        assert!(!items.contains(&ChecklistItemId::DidWebDocument));
+        assert!(!items.contains(&ChecklistItemId::ServiceAccount));
+        assert!(items.contains(&ChecklistItemId::AppPasswords));
+        assert!(!items.contains(&ChecklistItemId::DeactivateOldAccount));
+
+        let grace = ChecklistContext {
+            old_account_grace_days: Some(7),
+            ..context()
+        };
+        assert!(ids(&grace).contains(&ChecklistItemId::DeactivateOldAccount));
+
+        let hosted = ChecklistContext {
+            did: "did:web:alice.example".to_string(),
+            custom_domain: false,
+            password_saved: true,
+            service_account: Some(ServiceAccountProfile::default()),
+            app_passwords: Some(Vec::new()),
+            email_confirmed: true,
+            ..context()
+        };
+        let items = ids(&hosted);
+        assert!(!items.contains(&ChecklistItemId::SavePassword));
+        assert!(!items.contains(&ChecklistItemId::HandleDns));
+        assert!(items.contains(&ChecklistItemId::DidWebDocument));
+        assert!(!items.contains(&ChecklistItemId::RotationKey));
+        assert!(items.contains(&ChecklistItemId::ServiceAccount));
+        assert!(!items.contains(&ChecklistItemId::AppPasswords));
+        assert!(!items.contains(&ChecklistItemId::ConfirmEmail));
+    }
+
+    #[test]
+    fn test_custom_domain_detection_and_progress() {
+        let domains = vec![".new.example".to_string()];
+        assert!(!is_custom_domain("alice.new.example", &domains));
+        assert!(is_custom_domain("alice.example", &domains));
+        assert!(!is_custom_domain("", &domains));
+
+        let mut progress = load_checklist_progress("did:plc:abc");
+        progress.set(ChecklistItemId::Relogin, true);
+        progress.set(ChecklistItemId::Relogin, true);
+        save_checklist_progress(&progress);
+
+        let loaded = load_checklist_progress("did:plc:abc");
+        assert_eq!(loaded.checked, vec![ChecklistItemId::Relogin]);
+        assert!(load_checklist_progress("did:plc:other").checked.is_empty());
+    }
+}
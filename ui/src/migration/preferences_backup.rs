@@ -0,0 +1,266 @@
+//! Versioned backup of exported preferences, kept before importing them
+//!
+//! `putPreferences` replaces the whole preference set, so a target that drops
+//! or rejects a category loses it for good. The exported document is wrapped
+//! with a schema version and timestamp, written to OPFS and offered as a
+//! download before the import runs. The preferences themselves stay out of
+//! [`MigrationState`](super::MigrationState) and the action log, since both end
+//! up in support bundles; state only carries [`PreferencesBackupInfo`]. Saved
+//! backups can be read back for the import-from-backup migration plan.
+
+use opfs::persistent::app_specific_dir;
+use opfs::{
+    CreateWritableOptions, DirectoryEntry, GetDirectoryHandleOptions, GetFileHandleOptions,
+};
+use opfs::{DirectoryHandle as _, FileHandle as _, WritableFileStream as _};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Mutex;
+
+use crate::services::client::types::current_time_secs;
+
+/// Current backup file schema
+pub const PREFERENCES_BACKUP_VERSION: u32 = 1;
+
+/// OPFS directory the backups are written to
+const BACKUP_DIR: &str = "preferences_backups";
+
+/// Serialized form of the most recent backup, for the download link
+static LAST_BACKUP_JSON: Mutex<Option<String>> = Mutex::new(None);
+
+/// On-disk backup document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreferencesBackup {
+    pub version: u32,
+    pub created_at_secs: u64,
+    pub did: String,
+    pub source_pds: String,
+    /// The `app.bsky.actor.getPreferences` output, unchanged
+    pub export: Value,
+}
+
+/// What the UI needs to know about a saved backup
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PreferencesBackupInfo {
+    pub file_name: String,
+    pub created_at_secs: u64,
+    /// `$type` of each preference, in export order
+    pub categories: Vec<String>,
+    pub saved_to_opfs: bool,
+}
+
+impl PreferencesBackup {
+    /// Wrap an export response body
+    pub fn from_export(did: &str, source_pds: &str, export_json: &str) -> Result<Self, String> {
+        let export: Value = serde_json::from_str(export_json)
+            .map_err(|e| format!("Exported preferences are not valid JSON: {}", e))?;
+        if !export.get("preferences").is_some_and(Value::is_array) {
+            return Err("Exported preferences have no preferences array".to_string());
+        }
+        Ok(Self {
+            version: PREFERENCES_BACKUP_VERSION,
+            created_at_secs: current_time_secs(),
+            did: did.to_string(),
+            source_pds: source_pds.to_string(),
+            export,
+        })
+    }
+
+    /// Read a backup file, refusing versions newer than this build understands
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let backup: Self =
+            serde_json::from_str(json).map_err(|e| format!("Not a preferences backup: {}", e))?;
+        if backup.version > PREFERENCES_BACKUP_VERSION {
+            return Err(format!(
+                "Backup version {} is newer than this tool supports ({})",
+                backup.version, PREFERENCES_BACKUP_VERSION
+            ));
+        }
+        Ok(backup)
+    }
+
+    pub fn file_name(&self) -> String {
+        format!(
+            "prefs-{}-{}.json",
+            self.did.replace(':', "_"),
+            self.created_at_secs
+        )
+    }
+
+    pub fn categories(&self) -> Vec<String> {
+        self.export
+            .get("preferences")
+            .and_then(Value::as_array)
+            .map(|prefs| {
+                prefs
+                    .iter()
+                    .map(|pref| {
+                        pref.get("$type")
+                            .and_then(Value::as_str)
+                            .unwrap_or("unknown")
+                            .to_string()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The export in the form `import_preferences` expects
+    pub fn export_json(&self) -> String {
+        self.export.to_string()
+    }
+}
+
+/// Pretty-printed JSON of the latest backup, if one was made this session
+pub fn last_preferences_backup_json() -> Option<String> {
+    LAST_BACKUP_JSON.lock().ok().and_then(|last| last.clone())
+}
+
+async fn write_to_opfs(file_name: &str, contents: &str) -> Result<(), String> {
+    let app_dir = app_specific_dir()
+        .await
+        .map_err(|e| format!("OPFS unavailable: {:?}", e))?;
+    let dir = app_dir
+        .get_directory_handle_with_options(BACKUP_DIR, &GetDirectoryHandleOptions { create: true })
+        .await
+        .map_err(|e| format!("Failed to open {}: {:?}", BACKUP_DIR, e))?;
+    let mut file = dir
+        .get_file_handle_with_options(file_name, &GetFileHandleOptions { create: true })
+        .await
+        .map_err(|e| format!("Failed to create {}: {:?}", file_name, e))?;
+    let mut writer = file
+        .create_writable_with_options(&CreateWritableOptions {
+            keep_existing_data: false,
+        })
+        .await
+        .map_err(|e| format!("Failed to open {} for writing: {:?}", file_name, e))?;
+    writer
+        .write_at_cursor_pos(contents.as_bytes().to_vec())
+        .await
+        .map_err(|e| format!("Failed to write {}: {:?}", file_name, e))?;
+    writer
+        .close()
+        .await
+        .map_err(|e| format!("Failed to finish {}: {:?}", file_name, e))
+}
+
+/// Newest backup saved for `did`, read back from OPFS
+async fn read_newest_from_opfs(did: &str) -> Result<String, String> {
+    use futures_util::StreamExt;
+
+    let app_dir = app_specific_dir()
+        .await
+        .map_err(|e| format!("OPFS unavailable: {:?}", e))?;
+    let dir = app_dir
+        .get_directory_handle_with_options(BACKUP_DIR, &GetDirectoryHandleOptions { create: false })
+        .await
+        .map_err(|e| format!("No saved preferences backups: {:?}", e))?;
+    let entries: Vec<_> = dir
+        .entries()
+        .await
+        .map_err(|e| format!("Failed to list {}: {:?}", BACKUP_DIR, e))?
+        .collect()
+        .await;
+
+    let prefix = format!("prefs-{}-", did.replace(':', "_"));
+    let newest = entries
+        .into_iter()
+        .filter_map(|entry| match entry {
+            Ok((name, DirectoryEntry::File(file))) => {
+                let secs = name
+                    .strip_prefix(&prefix)?
+                    .strip_suffix(".json")?
+                    .parse::<u64>()
+                    .ok()?;
+                Some((secs, name, file))
+            }
+            _ => None,
+        })
+        .max_by_key(|(secs, _, _)| *secs);
+    let (_, file_name, file) =
+        newest.ok_or_else(|| format!("No preferences backup saved for {}", did))?;
+
+    let bytes = file
+        .read()
+        .await
+        .map_err(|e| format!("Failed to read {}: {:?}", file_name, e))?;
+    String::from_utf8(bytes).map_err(|e| format!("Backup {} is not UTF-8: {}", file_name, e))
+}
+
+/// Most recent backup for `did`: this session's if there is one, otherwise the newest in OPFS
+pub async fn load_preferences_backup(did: &str) -> Result<PreferencesBackup, String> {
+    if let Some(json) = last_preferences_backup_json() {
+        let backup = PreferencesBackup::parse(&json)?;
+        if backup.did == did {
+            return Ok(backup);
+        }
+    }
+    PreferencesBackup::parse(&read_newest_from_opfs(did).await?)
+}
+
+/// Keep the backup in memory for download and try to persist it to OPFS
+pub async fn save_preferences_backup(backup: &PreferencesBackup) -> PreferencesBackupInfo {
+    let file_name = backup.file_name();
+    let contents = serde_json::to_string_pretty(backup).unwrap_or_else(|_| backup.export_json());
+
+    if let Ok(mut last) = LAST_BACKUP_JSON.lock() {
+        *last = Some(contents.clone());
+    }
+
+    let saved_to_opfs = match write_to_opfs(&file_name, &contents).await {
+        Ok(()) => {
+            crate::console_info!("[Migration] Saved preferences backup {} to OPFS", file_name);
+            true
+        }
+        Err(e) => {
+            crate::console_warn!("[Migration] Preferences backup not persisted: {}", e);
+            false
+        }
+    };
+
+    PreferencesBackupInfo {
+        file_name,
+        created_at_secs: backup.created_at_secs,
+        categories: backup.categories(),
+        saved_to_opfs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"{"preferences":[
+        {"$type":"app.bsky.actor.defs#adultContentPref","enabled":false},
+        {"$type":"app.bsky.actor.defs#savedFeedsPrefV2","items":[]}
+    ]}"#;
+
+    #[test]
+    fn test_backup_round_trips_and_lists_categories() {
+        let backup =
+            PreferencesBackup::from_export("did:plc:alice", "https://old.example", EXPORT).unwrap();
+        assert_eq!(backup.version, PREFERENCES_BACKUP_VERSION);
+        assert_eq!(
+            backup.categories(),
+            vec![
+                "app.bsky.actor.defs#adultContentPref",
+                "app.bsky.actor.defs#savedFeedsPrefV2"
+            ]
+        );
+        assert!(backup.file_name().starts_with("prefs-did_plc_alice-"));
+
+        let parsed = PreferencesBackup::parse(&serde_json::to_string(&backup).unwrap()).unwrap();
+        assert_eq!(parsed, backup);
+    }
+
+    #[test]
+    fn test_rejects_invalid_exports_and_future_versions() {
+        assert!(PreferencesBackup::from_export("did:plc:alice", "", "{}").is_err());
+
+        let mut backup =
+            PreferencesBackup::from_export("did:plc:alice", "https://old.example", EXPORT).unwrap();
+        backup.version = PREFERENCES_BACKUP_VERSION + 1;
+        assert!(PreferencesBackup::parse(&serde_json::to_string(&backup).unwrap()).is_err());
+    }
+}
@@ -0,0 +1,231 @@
+//! Category-by-category check that imported preferences survived
+//!
+//! After `putPreferences` the new PDS is asked for the preferences again and
+//! each `$type` is compared with the source export. A category either came
+//! back unchanged, came back different (the server normalised or trimmed it),
+//! or is missing. Categories people set up by hand and would not notice losing
+//! — muted words, saved feeds, labeler subscriptions — carry a hint on how to
+//! restore them, so the summary can point at exactly what needs redoing.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// How a preference category fared on the new PDS
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RoundTripOutcome {
+    RoundTripped,
+    /// Present, but not what was exported
+    Changed,
+    /// Missing from the new PDS's preferences
+    Dropped,
+}
+
+/// One preference category in the round-trip report
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CategoryRoundTrip {
+    /// The preference `$type`, e.g. `app.bsky.actor.defs#mutedWordsPref`
+    pub category: String,
+    pub label: String,
+    pub outcome: RoundTripOutcome,
+    /// How to restore the category by hand, when it didn't round-trip
+    pub attention: Option<String>,
+}
+
+/// Every exported category and whether the new PDS kept it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PreferencesRoundTrip {
+    pub categories: Vec<CategoryRoundTrip>,
+}
+
+impl PreferencesRoundTrip {
+    pub fn with_outcome(
+        &self,
+        outcome: RoundTripOutcome,
+    ) -> impl Iterator<Item = &CategoryRoundTrip> {
+        self.categories
+            .iter()
+            .filter(move |category| category.outcome == outcome)
+    }
+
+    pub fn needs_attention(&self) -> impl Iterator<Item = &CategoryRoundTrip> {
+        self.categories
+            .iter()
+            .filter(|category| category.attention.is_some())
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.categories
+            .iter()
+            .all(|category| category.outcome == RoundTripOutcome::RoundTripped)
+    }
+}
+
+/// Friendly name of a preference `$type`
+pub fn category_label(category: &str) -> String {
+    let name = category.rsplit('#').next().unwrap_or(category);
+    match name {
+        "adultContentPref" => "Adult content",
+        "contentLabelPref" => "Content filters",
+        "savedFeedsPref" => "Saved feeds (legacy)",
+        "savedFeedsPrefV2" => "Saved and pinned feeds",
+        "personalDetailsPref" => "Birth date",
+        "feedViewPref" => "Following feed settings",
+        "threadViewPref" => "Thread settings",
+        "interestsPref" => "Interests",
+        "mutedWordsPref" => "Muted words and tags",
+        "hiddenPostsPref" => "Hidden posts",
+        "labelersPref" => "Subscribed labelers",
+        "bskyAppStatePref" => "App state",
+        "postInteractionSettingsPref" => "Default reply settings",
+        "verificationPrefs" => "Verification badges",
+        other => other,
+    }
+    .to_string()
+}
+
+/// Where to redo a category by hand in the Bluesky app
+fn attention_hint(category: &str) -> Option<&'static str> {
+    match category.rsplit('#').next()? {
+        "mutedWordsPref" => {
+            Some("Add your muted words again under Settings → Moderation → Muted words & tags.")
+        }
+        "savedFeedsPrefV2" | "savedFeedsPref" => {
+            Some("Pin your feeds again from the Feeds tab; the preferences backup lists them.")
+        }
+        "labelersPref" => Some("Subscribe to your labelers again from each labeler's profile."),
+        "contentLabelPref" | "adultContentPref" => {
+            Some("Check Settings → Moderation → Content filters.")
+        }
+        "hiddenPostsPref" => Some("Posts you hid will show again; hide them from the post menu."),
+        "personalDetailsPref" => Some("Set your birth date under Settings → Account."),
+        _ => None,
+    }
+}
+
+/// Compact JSON with object keys sorted, so equal preferences compare equal
+fn canonical(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let sorted: BTreeMap<&String, String> =
+                map.iter().map(|(k, v)| (k, canonical(v))).collect();
+            let fields: Vec<String> = sorted
+                .into_iter()
+                .map(|(k, v)| format!("{}:{}", Value::String(k.clone()), v))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonical).collect();
+            format!("[{}]", items.join(","))
+        }
+        other => other.to_string(),
+    }
+}
+
+/// Preferences of an export grouped by `$type`, each group sorted
+fn by_category(export: &Value) -> BTreeMap<String, Vec<String>> {
+    let mut grouped: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let prefs = export
+        .get("preferences")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    for pref in prefs {
+        let category = pref
+            .get("$type")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown");
+        grouped
+            .entry(category.to_string())
+            .or_default()
+            .push(canonical(pref));
+    }
+    grouped.values_mut().for_each(|items| items.sort());
+    grouped
+}
+
+/// Compare the source export with what the new PDS returns after the import
+pub fn compare_preferences(source: &Value, target: &Value) -> PreferencesRoundTrip {
+    let stored = by_category(target);
+    let categories = by_category(source)
+        .into_iter()
+        .map(|(category, exported)| {
+            let outcome = match stored.get(&category) {
+                None => RoundTripOutcome::Dropped,
+                Some(items) if *items == exported => RoundTripOutcome::RoundTripped,
+                Some(_) => RoundTripOutcome::Changed,
+            };
+            let attention = (outcome != RoundTripOutcome::RoundTripped)
+                .then(|| attention_hint(&category))
+                .flatten()
+                .map(str::to_string);
+            CategoryRoundTrip {
+                label: category_label(&category),
+                category,
+                outcome,
+                attention,
+            }
+        })
+        .collect();
+    PreferencesRoundTrip { categories }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_compare_preferences_by_category() {
+        let source = json!({"preferences": [
+            {"$type": "app.bsky.actor.defs#adultContentPref", "enabled": false},
+            {"$type": "app.bsky.actor.defs#mutedWordsPref", "items": [{"value": "spoilers", "targets": ["content"]}]},
+            {"$type": "app.bsky.actor.defs#savedFeedsPrefV2", "items": [{"id": "1", "type": "timeline", "value": "following", "pinned": true}]},
+            {"$type": "app.bsky.actor.defs#bskyAppStatePref", "queuedNudges": []},
+            {"$type": "app.bsky.actor.defs#contentLabelPref", "label": "gore", "visibility": "hide"},
+            {"$type": "app.bsky.actor.defs#contentLabelPref", "label": "nudity", "visibility": "warn"},
+        ]});
+        // Keys reordered and content labels swapped: still the same preferences
+        let target = json!({"preferences": [
+            {"enabled": false, "$type": "app.bsky.actor.defs#adultContentPref"},
+            {"$type": "app.bsky.actor.defs#contentLabelPref", "label": "nudity", "visibility": "warn"},
+            {"$type": "app.bsky.actor.defs#contentLabelPref", "visibility": "hide", "label": "gore"},
+            {"$type": "app.bsky.actor.defs#savedFeedsPrefV2", "items": []},
+        ]});
+
+        let report = compare_preferences(&source, &target);
+        let outcome = |label: &str| {
+            report
+                .categories
+                .iter()
+                .find(|c| c.label == label)
+                .map(|c| c.outcome)
+        };
+        assert_eq!(
+            outcome("Adult content"),
+            Some(RoundTripOutcome::RoundTripped)
+        );
+        assert_eq!(
+            outcome("Content filters"),
+            Some(RoundTripOutcome::RoundTripped)
+        );
+        assert_eq!(
+            outcome("Saved and pinned feeds"),
+            Some(RoundTripOutcome::Changed)
+        );
+        assert_eq!(
+            outcome("Muted words and tags"),
+            Some(RoundTripOutcome::Dropped)
+        );
+        assert_eq!(outcome("App state"), Some(RoundTripOutcome::Dropped));
+        assert!(!report.is_complete());
+
+        let attention: Vec<&str> = report.needs_attention().map(|c| c.label.as_str()).collect();
+        assert_eq!(
+            attention,
+            ["Muted words and tags", "Saved and pinned feeds"]
+        );
+        assert_eq!(report.with_outcome(RoundTripOutcome::Dropped).count(), 2);
+    }
+}
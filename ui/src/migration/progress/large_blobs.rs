@@ -0,0 +1,223 @@
+//! Per-item progress for large blob transfers
+//!
+//! The aggregate blob bar barely moves while a single video of a few hundred
+//! megabytes streams, which looks like a hang. Blobs at or above
+//! `ProgressConfig::large_blob_threshold_bytes` get their own entry with bytes
+//! downloaded and uploaded, fed from the orchestrator's progress callback.
+//! Small blobs never show up here, and an entry disappears once its blob is done.
+
+use serde::{Deserialize, Serialize};
+
+use crate::services::streaming::{ProgressEvent, ProgressPhase, ProgressUpdate};
+
+/// Which leg of the transfer a large blob is in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LargeBlobPhase {
+    Downloading,
+    Uploading,
+}
+
+/// Progress of one large blob
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LargeBlobTransfer {
+    pub cid: String,
+    /// Size from `Content-Length`, or the stored size once uploading
+    pub total_bytes: Option<u64>,
+    pub downloaded_bytes: u64,
+    pub uploaded_bytes: u64,
+    pub phase: LargeBlobPhase,
+}
+
+impl LargeBlobTransfer {
+    /// Download completion as a percentage, when the size is known
+    pub fn download_percent(&self) -> Option<f64> {
+        self.total_bytes
+            .filter(|total| *total > 0)
+            .map(|total| (self.downloaded_bytes as f64 / total as f64 * 100.0).min(100.0))
+    }
+}
+
+/// Turns orchestrator progress updates into the list of large transfers in flight
+#[derive(Debug, Clone)]
+pub struct LargeBlobTracker {
+    threshold_bytes: u64,
+    transfers: Vec<LargeBlobTransfer>,
+}
+
+impl LargeBlobTracker {
+    pub fn new(threshold_bytes: u64) -> Self {
+        Self {
+            threshold_bytes,
+            transfers: Vec::new(),
+        }
+    }
+
+    /// Apply an update; returns whether the visible list changed
+    pub fn observe(&mut self, update: &ProgressUpdate) -> bool {
+        let Some(cid) = update.item_id.as_deref() else {
+            return false;
+        };
+        let position = self.transfers.iter().position(|t| t.cid == cid);
+
+        match (&update.phase, &update.event) {
+            (ProgressPhase::Completing, _) => {
+                let Some(index) = position else {
+                    return false;
+                };
+                self.transfers.remove(index);
+                true
+            }
+            (ProgressPhase::Downloading, _) => {
+                // Without Content-Length the estimate is a guess, so only trust
+                // bytes actually received to decide whether the blob is large
+                let known_total = update.total_known.then_some(update.total_bytes_estimate);
+                let size_hint = known_total.unwrap_or(update.bytes_processed);
+                let transfer = match position {
+                    Some(index) => &mut self.transfers[index],
+                    None if size_hint >= self.threshold_bytes => {
+                        self.transfers.push(LargeBlobTransfer {
+                            cid: cid.to_string(),
+                            total_bytes: None,
+                            downloaded_bytes: 0,
+                            uploaded_bytes: 0,
+                            phase: LargeBlobPhase::Downloading,
+                        });
+                        self.transfers.last_mut().expect("just pushed")
+                    }
+                    None => return false,
+                };
+                transfer.downloaded_bytes = update.bytes_processed;
+                if known_total.is_some() {
+                    transfer.total_bytes = known_total;
+                }
+                true
+            }
+            (ProgressPhase::Uploading, event) => {
+                let transfer = match position {
+                    Some(index) => &mut self.transfers[index],
+                    None if update.total_bytes_estimate >= self.threshold_bytes => {
+                        self.transfers.push(LargeBlobTransfer {
+                            cid: cid.to_string(),
+                            total_bytes: None,
+                            downloaded_bytes: update.total_bytes_estimate,
+                            uploaded_bytes: 0,
+                            phase: LargeBlobPhase::Uploading,
+                        });
+                        self.transfers.last_mut().expect("just pushed")
+                    }
+                    None => return false,
+                };
+                transfer.phase = LargeBlobPhase::Uploading;
+                transfer.total_bytes = Some(update.total_bytes_estimate);
+                transfer.downloaded_bytes = update.total_bytes_estimate;
+                transfer.uploaded_bytes = if *event == ProgressEvent::Completed {
+                    update.total_bytes_estimate
+                } else {
+                    update.bytes_processed
+                };
+                true
+            }
+            (ProgressPhase::Starting, _) => false,
+        }
+    }
+
+    pub fn transfers(&self) -> &[LargeBlobTransfer] {
+        &self.transfers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    fn update(phase: ProgressPhase, event: ProgressEvent, done: u64, total: u64) -> ProgressUpdate {
+        ProgressUpdate {
+            item_id: Some("bafylarge".to_string()),
+            phase,
+            bytes_processed: done,
+            total_bytes_estimate: total,
+            total_known: true,
+            event,
+        }
+    }
+
+    #[test]
+    fn test_tracks_large_blob_through_download_upload_and_completion() {
+        let mut tracker = LargeBlobTracker::new(20 * MB);
+
+        // Small blob never appears
+        let small = ProgressUpdate {
+            item_id: Some("bafysmall".to_string()),
+            ..update(
+                ProgressPhase::Downloading,
+                ProgressEvent::Progress,
+                MB,
+                2 * MB,
+            )
+        };
+        assert!(!tracker.observe(&small));
+        assert!(tracker.transfers().is_empty());
+
+        // Known size from Content-Length puts the blob on the list immediately
+        assert!(tracker.observe(&update(
+            ProgressPhase::Downloading,
+            ProgressEvent::Progress,
+            MB,
+            50 * MB
+        )));
+        let transfer = &tracker.transfers()[0];
+        assert_eq!(transfer.total_bytes, Some(50 * MB));
+        assert_eq!(transfer.download_percent(), Some(2.0));
+
+        tracker.observe(&update(
+            ProgressPhase::Uploading,
+            ProgressEvent::Started,
+            0,
+            50 * MB,
+        ));
+        let transfer = &tracker.transfers()[0];
+        assert_eq!(transfer.phase, LargeBlobPhase::Uploading);
+        assert_eq!(transfer.downloaded_bytes, 50 * MB);
+        assert_eq!(transfer.uploaded_bytes, 0);
+
+        tracker.observe(&update(
+            ProgressPhase::Uploading,
+            ProgressEvent::Completed,
+            50 * MB,
+            50 * MB,
+        ));
+        assert_eq!(tracker.transfers()[0].uploaded_bytes, 50 * MB);
+
+        assert!(tracker.observe(&update(
+            ProgressPhase::Completing,
+            ProgressEvent::Completed,
+            50 * MB,
+            50 * MB
+        )));
+        assert!(tracker.transfers().is_empty());
+    }
+
+    #[test]
+    fn test_unknown_size_waits_for_received_bytes() {
+        let mut tracker = LargeBlobTracker::new(20 * MB);
+
+        // Orchestrator's guess without Content-Length: received + 1MB
+        let guessed = |done| ProgressUpdate {
+            total_known: false,
+            ..update(
+                ProgressPhase::Downloading,
+                ProgressEvent::Progress,
+                done,
+                done + MB,
+            )
+        };
+        assert!(!tracker.observe(&guessed(19 * MB)));
+        assert!(tracker.observe(&guessed(21 * MB)));
+        let transfer = &tracker.transfers()[0];
+        assert_eq!(transfer.total_bytes, None);
+        assert_eq!(transfer.downloaded_bytes, 21 * MB);
+        assert_eq!(transfer.download_percent(), None);
+    }
+}
@@ -1,7 +1,15 @@
 pub mod events;
+pub mod large_blobs;
 pub mod metrics;
+pub mod openmetrics;
 pub mod reporter;
+pub mod store;
+pub mod throttle;
 
 pub use events::*;
+pub use large_blobs::*;
 pub use metrics::*;
+pub use openmetrics::*;
 pub use reporter::*;
+pub use store::*;
+pub use throttle::*;
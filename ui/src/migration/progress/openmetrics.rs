@@ -0,0 +1,288 @@
+//! Migration metrics in OpenMetrics text format
+//!
+//! Operators embedding the tool can scrape or forward these alongside their own
+//! metrics. The exposition covers what the migration state already tracks (step
+//! statuses and durations, repository and blob transfer counts, failures) plus
+//! the pipeline latency histograms from `services::streaming::metrics`. No
+//! handles, DIDs or hosts appear in labels.
+
+use std::fmt::Write;
+
+use crate::migration::timeline::{StepStatus, TimelineStep};
+use crate::migration::types::MigrationState;
+use crate::services::streaming::{pipeline_histograms, LatencyHistogram, PipelineOperation};
+
+/// `Content-Type` to serve the rendered text with
+pub const OPENMETRICS_CONTENT_TYPE: &str =
+    "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Render the current migration and pipeline metrics
+pub fn render_openmetrics(state: &MigrationState, now_ms: u64) -> String {
+    render_openmetrics_with(state, &pipeline_histograms(), now_ms)
+}
+
+/// Render metrics from explicit pipeline histograms
+pub fn render_openmetrics_with(
+    state: &MigrationState,
+    histograms: &[(PipelineOperation, LatencyHistogram)],
+    now_ms: u64,
+) -> String {
+    let mut out = String::new();
+
+    gauge(
+        &mut out,
+        "tektite_migration_in_progress",
+        "Whether a migration is running",
+        state.is_migrating as u8,
+    );
+    gauge(
+        &mut out,
+        "tektite_migration_completed",
+        "Whether the migration finished",
+        state.migration_completed as u8,
+    );
+    gauge(
+        &mut out,
+        "tektite_migration_failed",
+        "Whether the migration stopped with an error",
+        state.migration_error.is_some() as u8,
+    );
+
+    let timeline = state.timeline(now_ms);
+    let _ = writeln!(out, "# TYPE tektite_step stateset");
+    let _ = writeln!(out, "# HELP tektite_step Status of each migration step");
+    for entry in &timeline {
+        for status in [
+            StepStatus::Pending,
+            StepStatus::Active,
+            StepStatus::Done,
+            StepStatus::Failed,
+        ] {
+            let _ = writeln!(
+                out,
+                "tektite_step{{step=\"{}\",tektite_step=\"{}\"}} {}",
+                step_label(entry.step),
+                status.css_class(),
+                (entry.status == status) as u8
+            );
+        }
+    }
+    let _ = writeln!(out, "# TYPE tektite_step_duration_seconds gauge");
+    let _ = writeln!(out, "# UNIT tektite_step_duration_seconds seconds");
+    let _ = writeln!(
+        out,
+        "# HELP tektite_step_duration_seconds Time spent in each step so far"
+    );
+    for entry in &timeline {
+        if let Some(duration_ms) = entry.duration_ms {
+            let _ = writeln!(
+                out,
+                "tektite_step_duration_seconds{{step=\"{}\"}} {:?}",
+                step_label(entry.step),
+                duration_ms as f64 / 1000.0
+            );
+        }
+    }
+
+    let car_bytes = state
+        .repo_progress
+        .car_size
+        .max(state.migration_progress.repo_car_size);
+    let _ = writeln!(out, "# TYPE tektite_repo_car_bytes gauge");
+    let _ = writeln!(out, "# UNIT tektite_repo_car_bytes bytes");
+    let _ = writeln!(
+        out,
+        "# HELP tektite_repo_car_bytes Size of the exported repository CAR"
+    );
+    let _ = writeln!(out, "tektite_repo_car_bytes {}", car_bytes);
+
+    let blobs = state.unified_blob_progress();
+    gauge(
+        &mut out,
+        "tektite_blobs_expected",
+        "Blobs the migration plans to transfer",
+        blobs.total_blobs,
+    );
+    counter(
+        &mut out,
+        "tektite_blobs_transferred",
+        "Blobs transferred to the new PDS",
+        blobs.processed_blobs,
+    );
+    let _ = writeln!(out, "# TYPE tektite_blob_transferred_bytes counter");
+    let _ = writeln!(out, "# UNIT tektite_blob_transferred_bytes bytes");
+    let _ = writeln!(
+        out,
+        "# HELP tektite_blob_transferred_bytes Blob bytes transferred to the new PDS"
+    );
+    let _ = writeln!(
+        out,
+        "tektite_blob_transferred_bytes_total {}",
+        blobs.processed_bytes
+    );
+    let failed_blobs = if state.migration_progress.blobs_imported {
+        state
+            .migration_progress
+            .total_blob_count
+            .saturating_sub(state.migration_progress.imported_blob_count)
+    } else {
+        0
+    };
+    counter(
+        &mut out,
+        "tektite_blobs_failed",
+        "Blobs that could not be transferred",
+        failed_blobs,
+    );
+
+    let _ = writeln!(
+        out,
+        "# TYPE tektite_pipeline_operation_duration_seconds histogram"
+    );
+    let _ = writeln!(
+        out,
+        "# UNIT tektite_pipeline_operation_duration_seconds seconds"
+    );
+    let _ = writeln!(
+        out,
+        "# HELP tektite_pipeline_operation_duration_seconds Latency of network and storage operations in the blob pipeline"
+    );
+    for (operation, histogram) in histograms {
+        let name = operation_label(*operation);
+        for (bound_ms, count) in histogram.cumulative_buckets() {
+            let le = bound_ms.map_or_else(|| "+Inf".to_string(), |ms| format!("{:?}", ms / 1000.0));
+            let _ = writeln!(
+                out,
+                "tektite_pipeline_operation_duration_seconds_bucket{{operation=\"{}\",le=\"{}\"}} {}",
+                name, le, count
+            );
+        }
+        let _ = writeln!(
+            out,
+            "tektite_pipeline_operation_duration_seconds_count{{operation=\"{}\"}} {}",
+            name,
+            histogram.count()
+        );
+        let _ = writeln!(
+            out,
+            "tektite_pipeline_operation_duration_seconds_sum{{operation=\"{}\"}} {:?}",
+            name,
+            histogram.total_ms() / 1000.0
+        );
+    }
+    let _ = writeln!(out, "# TYPE tektite_pipeline_operation_bytes counter");
+    let _ = writeln!(out, "# UNIT tektite_pipeline_operation_bytes bytes");
+    let _ = writeln!(
+        out,
+        "# HELP tektite_pipeline_operation_bytes Bytes moved by each pipeline operation"
+    );
+    for (operation, histogram) in histograms {
+        let _ = writeln!(
+            out,
+            "tektite_pipeline_operation_bytes_total{{operation=\"{}\"}} {}",
+            operation_label(*operation),
+            histogram.total_bytes()
+        );
+    }
+
+    out.push_str("# EOF\n");
+    out
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, value: impl std::fmt::Display) {
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+fn counter(out: &mut String, name: &str, help: &str, value: impl std::fmt::Display) {
+    let _ = writeln!(out, "# TYPE {} counter", name);
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "{}_total {}", name, value);
+}
+
+fn step_label(step: TimelineStep) -> &'static str {
+    match step {
+        TimelineStep::Login => "login",
+        TimelineStep::Account => "account",
+        TimelineStep::Repository => "repository",
+        TimelineStep::Blobs => "blobs",
+        TimelineStep::Preferences => "preferences",
+        TimelineStep::Plc => "plc",
+        TimelineStep::Activation => "activation",
+    }
+}
+
+fn operation_label(operation: PipelineOperation) -> &'static str {
+    match operation {
+        PipelineOperation::NetworkDownload => "network_download",
+        PipelineOperation::NetworkUpload => "network_upload",
+        PipelineOperation::OpfsWrite => "opfs_write",
+        PipelineOperation::OpfsRead => "opfs_read",
+        PipelineOperation::IndexedDbWrite => "indexeddb_write",
+        PipelineOperation::IndexedDbRead => "indexeddb_read",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migration::types::{BlobProgress, MigrationAction};
+
+    #[test]
+    fn test_renders_state_and_histograms_as_openmetrics() {
+        let mut state = MigrationState::default();
+        state.reduce_in_place(MigrationAction::SetMigrating(true));
+        state.reduce_in_place(MigrationAction::SetBlobProgress(BlobProgress {
+            total_blobs: 10,
+            processed_blobs: 4,
+            processed_bytes: 4096,
+            ..BlobProgress::default()
+        }));
+        let mut histogram = LatencyHistogram::default();
+        histogram.record(8.0, 1000);
+        histogram.record(400.0, 2000);
+
+        let text = render_openmetrics_with(
+            &state,
+            &[(PipelineOperation::NetworkUpload, histogram)],
+            1_000_000,
+        );
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(lines.last(), Some(&"# EOF"));
+        assert!(lines.contains(&"tektite_migration_in_progress 1"));
+        assert!(lines.contains(&"tektite_blobs_expected 10"));
+        assert!(lines.contains(&"tektite_blobs_transferred_total 4"));
+        assert!(lines.contains(&"tektite_blob_transferred_bytes_total 4096"));
+        assert!(lines.contains(
+            &"tektite_pipeline_operation_duration_seconds_bucket{operation=\"network_upload\",le=\"0.01\"} 1"
+        ));
+        assert!(lines.contains(
+            &"tektite_pipeline_operation_duration_seconds_bucket{operation=\"network_upload\",le=\"+Inf\"} 2"
+        ));
+        assert!(lines.contains(
+            &"tektite_pipeline_operation_duration_seconds_sum{operation=\"network_upload\"} 0.408"
+        ));
+        assert!(lines.contains(
+            &"tektite_pipeline_operation_bytes_total{operation=\"network_upload\"} 3000"
+        ));
+
+        // Every sample belongs to a family declared before it
+        let mut declared = Vec::new();
+        for line in &lines {
+            if let Some(rest) = line.strip_prefix("# TYPE ") {
+                declared.push(rest.split(' ').next().unwrap().to_string());
+            } else if !line.starts_with('#') {
+                let name = line.split(['{', ' ']).next().unwrap();
+                assert!(
+                    declared
+                        .iter()
+                        .any(|family| name.starts_with(family.as_str())),
+                    "undeclared sample {}",
+                    name
+                );
+            }
+        }
+    }
+}
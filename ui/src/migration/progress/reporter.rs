@@ -1,6 +1,7 @@
 //! Progress reporting abstraction for migration operations
 
 use crate::migration::types::*;
+use crate::utils::byte_format::format_size;
 use crate::{console_error, console_info};
 
 /// Trait for reporting migration progress
@@ -93,9 +94,9 @@ impl ProgressReporter for ConsoleProgressReporter {
     fn report_completion(&self, result: MigrationResult) {
         if result.success {
             console_info!(
-                "[Progress] ✅ Migration Complete: {} blobs migrated ({:.1} MB) in {}s",
+                "[Progress] ✅ Migration Complete: {} blobs migrated ({}) in {}s",
                 result.total_blobs_migrated,
-                result.total_bytes_processed as f64 / 1_048_576.0,
+                format_size(result.total_bytes_processed),
                 result.duration_seconds
             );
         } else {
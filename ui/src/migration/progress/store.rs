@@ -0,0 +1,173 @@
+//! Live progress kept apart from `MigrationState`
+//!
+//! Every write to the `MigrationState` signal re-renders the whole form tree,
+//! and during a large migration most writes are byte counters. Progress
+//! actions therefore land in a [`ProgressStore`] first: a signal of its own,
+//! read only by the progress displays, updated as often as the step's
+//! [`ThrottledDispatch`](super::ThrottledDispatch) releases them. The state
+//! still receives them, but through a second, slower throttle
+//! (`ProgressConfig::state_updates_per_second`) with a trailing flush, so
+//! the journal, the timeline and the stall watchdog see the final values
+//! without redrawing every form on each chunk.
+
+use dioxus::prelude::*;
+
+use crate::migration::progress::LargeBlobTransfer;
+use crate::migration::types::{BlobProgress, MigrationAction, MigrationState, RepoProgress};
+
+/// Whether an action only carries live progress
+pub fn is_live_progress(action: &MigrationAction) -> bool {
+    matches!(
+        action,
+        MigrationAction::SetRepoProgress(_)
+            | MigrationAction::SetBlobProgress(_)
+            | MigrationAction::SetLargeBlobTransfers(_)
+            | MigrationAction::SetMigrationStep(_)
+    )
+}
+
+/// Blob progress for display; while only the repository has streamed, blobs
+/// are estimated from the CAR size so the bar isn't empty
+pub fn unify_blob_progress(blobs: &BlobProgress, repo: &RepoProgress) -> BlobProgress {
+    let mut unified = blobs.clone();
+    if unified.total_blobs == 0 && unified.processed_blobs == 0 && repo.car_size > 0 {
+        let estimated_blobs = std::cmp::max(1, (repo.car_size / 10_000) as u32);
+        unified.total_blobs = estimated_blobs;
+        unified.processed_blobs = estimated_blobs;
+        unified.total_bytes = repo.car_size;
+        unified.processed_bytes = repo.car_size;
+    }
+    unified
+}
+
+/// The progress fields of `MigrationState`, at full update rate
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProgressSnapshot {
+    pub repo: RepoProgress,
+    pub blobs: BlobProgress,
+    pub large_blobs: Vec<LargeBlobTransfer>,
+    pub step: String,
+}
+
+impl ProgressSnapshot {
+    pub fn from_state(state: &MigrationState) -> Self {
+        Self {
+            repo: state.repo_progress.clone(),
+            blobs: state.blob_progress.clone(),
+            large_blobs: state.large_blob_transfers.clone(),
+            step: state.migration_step.clone(),
+        }
+    }
+
+    /// Take the value a progress action carries; false for any other action
+    pub fn apply(&mut self, action: &MigrationAction) -> bool {
+        match action {
+            MigrationAction::SetRepoProgress(progress) => self.repo = progress.clone(),
+            MigrationAction::SetBlobProgress(progress) => self.blobs = progress.clone(),
+            MigrationAction::SetLargeBlobTransfers(transfers) => {
+                self.large_blobs = transfers.clone()
+            }
+            MigrationAction::SetMigrationStep(step) => self.step = step.clone(),
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn unified_blob_progress(&self) -> BlobProgress {
+        unify_blob_progress(&self.blobs, &self.repo)
+    }
+}
+
+/// Live progress shared through context by `MigrationService`
+#[derive(Clone, Copy, PartialEq)]
+pub struct ProgressStore {
+    snapshot: Signal<ProgressSnapshot>,
+}
+
+impl ProgressStore {
+    pub fn new() -> Self {
+        Self {
+            snapshot: Signal::new(ProgressSnapshot::default()),
+        }
+    }
+
+    /// Current progress; subscribes the calling component
+    pub fn read(&self) -> ProgressSnapshot {
+        (self.snapshot)()
+    }
+
+    pub fn apply(&mut self, action: &MigrationAction) {
+        if is_live_progress(action) {
+            self.snapshot.with_mut(|snapshot| snapshot.apply(action));
+        }
+    }
+
+    /// Catch up with the state after actions that reset or replace progress
+    pub fn sync_from(&mut self, state: &MigrationState) {
+        let fresh = ProgressSnapshot::from_state(state);
+        if *self.snapshot.peek() != fresh {
+            self.snapshot.set(fresh);
+        }
+    }
+}
+
+impl Default for ProgressStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The store provided by `MigrationService`
+pub fn use_progress_store() -> ProgressStore {
+    use_context::<ProgressStore>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_takes_only_progress_actions() {
+        let mut snapshot = ProgressSnapshot::default();
+        assert!(
+            snapshot.apply(&MigrationAction::SetRepoProgress(RepoProgress {
+                car_size: 25_000,
+                ..RepoProgress::default()
+            }))
+        );
+        assert!(snapshot.apply(&MigrationAction::SetMigrationStep(
+            "Streaming repository...".to_string()
+        )));
+        assert!(!snapshot.apply(&MigrationAction::SetMigrationError(Some("boom".into()))));
+        assert!(!is_live_progress(&MigrationAction::SetMigrationError(None)));
+        assert_eq!(snapshot.step, "Streaming repository...");
+
+        // Only the repository so far: blobs are estimated from the CAR
+        let unified = snapshot.unified_blob_progress();
+        assert_eq!((unified.processed_blobs, unified.total_bytes), (2, 25_000));
+
+        assert!(
+            snapshot.apply(&MigrationAction::SetBlobProgress(BlobProgress {
+                total_blobs: 10,
+                processed_blobs: 4,
+                ..BlobProgress::default()
+            }))
+        );
+        assert_eq!(snapshot.unified_blob_progress().processed_blobs, 4);
+
+        // The state reduces the same actions to the same values
+        let mut state = MigrationState::default();
+        for action in [
+            MigrationAction::SetRepoProgress(snapshot.repo.clone()),
+            MigrationAction::SetBlobProgress(snapshot.blobs.clone()),
+            MigrationAction::SetMigrationStep(snapshot.step.clone()),
+        ] {
+            state.reduce_in_place(action);
+        }
+        assert_eq!(ProgressSnapshot::from_state(&state), snapshot);
+        assert_eq!(
+            state.unified_blob_progress(),
+            snapshot.unified_blob_progress()
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! Coalescing of high-frequency progress actions
+//!
+//! Streaming callbacks fire per chunk, and dispatching every one of them
+//! re-renders the progress UI far more often than anyone can see. The throttle
+//! keeps only the newest pending action of each progress kind and releases
+//! them at most `max_updates_per_second` times a second. Any other action
+//! flushes what's pending first, so ordering is preserved, and `flush` at the
+//! end of a step guarantees the final values are rendered.
+
+use std::cell::RefCell;
+use std::mem::{discriminant, Discriminant};
+use std::rc::Rc;
+
+use crate::console_debug;
+use crate::migration::types::MigrationAction;
+
+/// Whether later actions of the same kind fully replace earlier ones
+fn is_coalescable(action: &MigrationAction) -> bool {
+    matches!(
+        action,
+        MigrationAction::SetRepoProgress(_)
+            | MigrationAction::SetBlobProgress(_)
+            | MigrationAction::SetLargeBlobTransfers(_)
+            | MigrationAction::SetMigrationStep(_)
+            | MigrationAction::SetStorageUsage(_)
+    )
+}
+
+/// Time-based coalescing state, independent of any clock or dispatcher
+#[derive(Debug)]
+pub struct ProgressThrottle {
+    min_interval_ms: u64,
+    last_release_ms: Option<u64>,
+    pending: Vec<(Discriminant<MigrationAction>, MigrationAction)>,
+    received: u64,
+    released: u64,
+}
+
+impl ProgressThrottle {
+    pub fn new(max_updates_per_second: u32) -> Self {
+        Self {
+            min_interval_ms: 1000 / u64::from(max_updates_per_second.max(1)),
+            last_release_ms: None,
+            pending: Vec::new(),
+            received: 0,
+            released: 0,
+        }
+    }
+
+    /// Queue an action; returns the actions that should be dispatched now
+    pub fn push(&mut self, action: MigrationAction, now_ms: u64) -> Vec<MigrationAction> {
+        self.received += 1;
+
+        if !is_coalescable(&action) {
+            let mut ready = self.drain();
+            ready.push(action);
+            self.released += 1;
+            return ready;
+        }
+
+        let kind = discriminant(&action);
+        match self
+            .pending
+            .iter_mut()
+            .find(|(pending, _)| *pending == kind)
+        {
+            Some(slot) => slot.1 = action,
+            None => self.pending.push((kind, action)),
+        }
+
+        let due = self
+            .last_release_ms
+            .is_none_or(|last| now_ms.saturating_sub(last) >= self.min_interval_ms);
+        if due {
+            self.last_release_ms = Some(now_ms);
+            self.drain()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Release everything still pending, e.g. when a step finishes
+    pub fn flush(&mut self) -> Vec<MigrationAction> {
+        self.drain()
+    }
+
+    /// Whether anything is held back waiting for the next interval
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    pub fn min_interval_ms(&self) -> u64 {
+        self.min_interval_ms
+    }
+
+    /// (actions received, actions released)
+    pub fn counts(&self) -> (u64, u64) {
+        (self.received, self.released)
+    }
+
+    fn drain(&mut self) -> Vec<MigrationAction> {
+        self.released += self.pending.len() as u64;
+        self.pending.drain(..).map(|(_, action)| action).collect()
+    }
+}
+
+/// A dispatch function wrapped in a [`ProgressThrottle`]
+///
+/// Cloning shares the throttle, so a progress callback can own one clone while
+/// the step keeps another to flush once streaming ends.
+pub struct ThrottledDispatch<F>
+where
+    F: Fn(MigrationAction),
+{
+    dispatch: Rc<F>,
+    throttle: Rc<RefCell<ProgressThrottle>>,
+}
+
+impl<F> Clone for ThrottledDispatch<F>
+where
+    F: Fn(MigrationAction),
+{
+    fn clone(&self) -> Self {
+        Self {
+            dispatch: Rc::clone(&self.dispatch),
+            throttle: Rc::clone(&self.throttle),
+        }
+    }
+}
+
+impl<F> ThrottledDispatch<F>
+where
+    F: Fn(MigrationAction),
+{
+    pub fn new(dispatch: F, max_updates_per_second: u32) -> Self {
+        Self {
+            dispatch: Rc::new(dispatch),
+            throttle: Rc::new(RefCell::new(ProgressThrottle::new(max_updates_per_second))),
+        }
+    }
+
+    pub fn dispatch(&self, action: MigrationAction) {
+        let now_ms = crate::utils::platform::now_ms() as u64;
+        let ready = self.throttle.borrow_mut().push(action, now_ms);
+        for action in ready {
+            (self.dispatch)(action);
+        }
+    }
+
+    /// Dispatch whatever is still pending so the UI shows the final values
+    pub fn flush(&self) {
+        let ready = self.throttle.borrow_mut().flush();
+        for action in ready {
+            (self.dispatch)(action);
+        }
+        let (received, released) = self.throttle.borrow().counts();
+        console_debug!(
+            "[Progress] Coalesced {} progress actions into {} dispatches",
+            received,
+            released
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migration::types::BlobProgress;
+
+    fn blob_progress(processed_blobs: u32) -> MigrationAction {
+        MigrationAction::SetBlobProgress(BlobProgress {
+            processed_blobs,
+            ..Default::default()
+        })
+    }
+
+    fn processed(action: &MigrationAction) -> Option<u32> {
+        match action {
+            MigrationAction::SetBlobProgress(progress) => Some(progress.processed_blobs),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_updates_within_interval_coalesce_to_latest() {
+        let mut throttle = ProgressThrottle::new(10);
+
+        // The first update goes out immediately
+        assert_eq!(throttle.push(blob_progress(1), 0).len(), 1);
+        // Updates inside the 100ms window are held back
+        for (i, now) in [10, 20, 30].into_iter().enumerate() {
+            assert!(throttle.push(blob_progress(2 + i as u32), now).is_empty());
+        }
+        // Only the newest held value is released once the window passes
+        let released = throttle.push(blob_progress(5), 100);
+        assert_eq!(
+            released.iter().filter_map(processed).collect::<Vec<_>>(),
+            [5]
+        );
+
+        // A trailing value is never lost
+        assert!(throttle.push(blob_progress(6), 120).is_empty());
+        let flushed = throttle.flush();
+        assert_eq!(
+            flushed.iter().filter_map(processed).collect::<Vec<_>>(),
+            [6]
+        );
+        assert_eq!(throttle.counts(), (6, 3));
+    }
+
+    #[test]
+    fn test_other_actions_flush_pending_progress_first() {
+        let mut throttle = ProgressThrottle::new(1);
+        throttle.push(blob_progress(1), 0);
+        assert!(throttle.push(blob_progress(2), 10).is_empty());
+
+        let released = throttle.push(MigrationAction::SetMigrationError(Some("boom".into())), 20);
+        assert_eq!(released.len(), 2);
+        assert_eq!(processed(&released[0]), Some(2));
+        assert!(matches!(released[1], MigrationAction::SetMigrationError(_)));
+        assert!(throttle.flush().is_empty());
+    }
+}
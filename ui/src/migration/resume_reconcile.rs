@@ -0,0 +1,195 @@
+//! Checking an interrupted attempt against what the new PDS already holds
+//!
+//! When the tab was closed or crashed in the middle of the content phase, the
+//! local records of that attempt can't be trusted on their own: an upload the
+//! browser never saw finish may have landed, and one the upload log recorded
+//! may still be missing if the new PDS didn't keep it. So before a resumed run
+//! starts copying again, the new PDS is asked directly. Its latest commit is
+//! compared with the old PDS's, and every blob in the upload log is looked up
+//! in `listMissingBlobs`. A repository the new PDS already has at the source's
+//! commit isn't imported again, logged blobs the server has count as done, and
+//! only the ones it still reports missing are uploaded again.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::migration::continuous_sync::{decide_sync, SyncDecision};
+use crate::migration::upload_log::{read_upload_log, UploadLogEntry};
+use crate::services::client::{ClientSessionCredentials, PdsClient};
+use crate::{console_info, console_warn};
+
+/// Page size for `listMissingBlobs`
+const MISSING_BLOBS_PAGE_SIZE: i64 = 1000;
+
+/// Upper bound on pages read, so a server that keeps returning a cursor can't
+/// hold the resume forever
+const MAX_MISSING_BLOBS_PAGES: usize = 500;
+
+/// Local records of an earlier attempt, checked against the new PDS
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResumeReconciliation {
+    /// The new PDS already has the old PDS's latest commit
+    pub repo_already_imported: bool,
+    /// Distinct blobs the upload log recorded in earlier attempts
+    pub logged_uploads: usize,
+    /// Logged blobs the new PDS has
+    pub confirmed_uploads: usize,
+    /// Logged blobs the new PDS still reports missing
+    pub redo_uploads: Vec<String>,
+    /// Blobs the new PDS reports missing in total
+    pub missing_blobs: usize,
+}
+
+impl ResumeReconciliation {
+    /// Whether anything from an earlier attempt was found
+    pub fn is_resume(&self) -> bool {
+        self.repo_already_imported || self.logged_uploads > 0
+    }
+
+    pub fn summary(&self) -> String {
+        let repo = if self.repo_already_imported {
+            "the repository is already imported"
+        } else {
+            "the repository will be imported again"
+        };
+        format!(
+            "Resuming: {}; {} of {} blobs uploaded earlier are on the new PDS, {} need uploading again, {} missing in total",
+            repo,
+            self.confirmed_uploads,
+            self.logged_uploads,
+            self.redo_uploads.len(),
+            self.missing_blobs
+        )
+    }
+}
+
+/// Distinct CIDs in an upload log, in the order they were first logged;
+/// lines that don't parse are skipped
+pub fn logged_cids(log: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    log.lines()
+        .filter_map(|line| serde_json::from_str::<UploadLogEntry>(line).ok())
+        .map(|entry| entry.cid)
+        .filter(|cid| seen.insert(cid.clone()))
+        .collect()
+}
+
+/// Split logged CIDs into the number the server has and the ones it is
+/// still missing
+pub fn reconcile_uploads(logged: &[String], missing: &HashSet<String>) -> (usize, Vec<String>) {
+    let redo: Vec<String> = logged
+        .iter()
+        .filter(|cid| missing.contains(*cid))
+        .cloned()
+        .collect();
+    (logged.len() - redo.len(), redo)
+}
+
+/// Every CID the new PDS reports missing
+async fn missing_blob_cids(
+    client: &PdsClient,
+    session: &ClientSessionCredentials,
+) -> Result<HashSet<String>, String> {
+    let mut missing = HashSet::new();
+    let mut cursor = None;
+    for _ in 0..MAX_MISSING_BLOBS_PAGES {
+        let response = client
+            .get_missing_blobs(session, cursor, Some(MISSING_BLOBS_PAGE_SIZE))
+            .await
+            .map_err(|e| e.to_string())?;
+        if !response.success {
+            return Err(response.message);
+        }
+        let page = response.missing_blobs.unwrap_or_default();
+        let page_len = page.len();
+        missing.extend(page.into_iter().map(|blob| blob.cid.to_string()));
+        cursor = response.cursor;
+        if cursor.is_none() || page_len == 0 {
+            break;
+        }
+    }
+    Ok(missing)
+}
+
+/// Compare the upload log and both repositories with the new PDS
+pub async fn reconcile_with_server(
+    old_session: &ClientSessionCredentials,
+    new_session: &ClientSessionCredentials,
+) -> Result<ResumeReconciliation, String> {
+    let client = PdsClient::new();
+    let source = client
+        .check_account_status(old_session)
+        .await
+        .map_err(|e| format!("Old PDS status: {}", e))?;
+    let target = client
+        .check_account_status(new_session)
+        .await
+        .map_err(|e| format!("New PDS status: {}", e))?;
+    // Only an exact commit match counts; a fresh account has a rev of its own
+    let repo_already_imported = matches!(
+        decide_sync(
+            source.repo_commit.as_deref(),
+            target.repo_commit.as_deref(),
+            target.repo_rev.as_deref(),
+        ),
+        SyncDecision::UpToDate
+    );
+
+    let logged = match read_upload_log(&old_session.did).await {
+        Ok(log) => logged_cids(&log),
+        Err(e) => {
+            console_warn!("[Resume] Upload log unavailable: {}", e);
+            Vec::new()
+        }
+    };
+    if !repo_already_imported && logged.is_empty() {
+        return Ok(ResumeReconciliation::default());
+    }
+
+    let missing = missing_blob_cids(&client, new_session).await?;
+    let (confirmed_uploads, redo_uploads) = reconcile_uploads(&logged, &missing);
+    let reconciliation = ResumeReconciliation {
+        repo_already_imported,
+        logged_uploads: logged.len(),
+        confirmed_uploads,
+        redo_uploads,
+        missing_blobs: missing.len(),
+    };
+    console_info!("[Resume] {}", reconciliation.summary());
+    Ok(reconciliation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_logged_uploads_are_split_by_server_state() {
+        let log = [
+            r#"{"cid":"bafy1","bytes":10,"durationMs":5,"attempts":1,"uploadedAtMs":1}"#,
+            "not json",
+            r#"{"cid":"bafy2","bytes":20,"durationMs":5,"attempts":2,"uploadedAtMs":2}"#,
+            r#"{"cid":"bafy1","bytes":10,"durationMs":5,"attempts":1,"uploadedAtMs":3}"#,
+            r#"{"cid":"bafy3","bytes":30,"durationMs":5,"attempts":1,"uploadedAtMs":4}"#,
+        ]
+        .join("\n");
+        let logged = logged_cids(&log);
+        assert_eq!(logged, vec!["bafy1", "bafy2", "bafy3"]);
+
+        let missing: HashSet<String> = ["bafy2", "bafy9"].iter().map(|s| s.to_string()).collect();
+        let (confirmed, redo) = reconcile_uploads(&logged, &missing);
+        assert_eq!(confirmed, 2);
+        assert_eq!(redo, vec!["bafy2"]);
+
+        let reconciliation = ResumeReconciliation {
+            logged_uploads: logged.len(),
+            confirmed_uploads: confirmed,
+            redo_uploads: redo,
+            missing_blobs: missing.len(),
+            ..ResumeReconciliation::default()
+        };
+        assert!(reconciliation.is_resume());
+        assert!(!ResumeReconciliation::default().is_resume());
+        assert!(reconciliation.summary().contains("2 of 3 blobs"));
+    }
+}
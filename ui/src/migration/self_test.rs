@@ -0,0 +1,340 @@
+//! Deployment self-test against a reference PDS
+//!
+//! Operators re-hosting the frontend have no quick way to tell whether the
+//! build they deployed can still resolve identities, talk to a PDS and use
+//! browser storage; CSP headers, blocked DoH endpoints and a missing OPFS all
+//! fail silently until a real user tries to migrate. The hidden `/selftest`
+//! page runs a scripted mini-migration against a test PDS and a throwaway
+//! account the maintainer provides: storage round trips, handle and DID
+//! resolution, then log in, export the repository, upload a blob and write the
+//! preferences back unchanged. Nothing is written to the PDS that the account
+//! did not already have, apart from one small unreferenced blob the PDS
+//! garbage collects.
+
+use cid::Cid;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+
+use crate::migration::car_diff::CarStreamReader;
+use crate::services::client::{ClientSessionCredentials, PdsClient, WebIdentityResolver};
+use crate::services::streaming::BrowserStorage;
+use crate::utils::handle::{normalize_identifier, normalize_pds_url};
+use crate::utils::platform::{
+    now_ms, random_hex, storage_delete, storage_get, storage_set, StorageArea,
+};
+
+/// Payload uploaded by the blob check
+pub const SELF_TEST_BLOB: &[u8] = b"tektite deployment self-test blob\n";
+
+/// Raw sha2-256 CID of [`SELF_TEST_BLOB`]
+pub const SELF_TEST_BLOB_CID: &str = "bafkreifgv7bnt3bfogfvzuz5l76uhrojeswno5gjgjsbakwdofioabm6k4";
+
+const STORAGE_KEY: &str = "selftest_probe";
+
+/// Layer of the deployed build a check exercises
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SelfTestLayer {
+    Storage,
+    Resolver,
+    Client,
+}
+
+impl SelfTestLayer {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SelfTestLayer::Storage => "Storage",
+            SelfTestLayer::Resolver => "Resolver",
+            SelfTestLayer::Client => "Client",
+        }
+    }
+}
+
+/// Outcome of one check
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SelfTestCheck {
+    pub layer: SelfTestLayer,
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+    pub duration_ms: u64,
+}
+
+/// Reference PDS and throwaway account to test against
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SelfTestConfig {
+    pub pds_url: String,
+    pub handle: String,
+    pub password: String,
+}
+
+/// Whether every check that ran passed
+pub fn self_test_passed(checks: &[SelfTestCheck]) -> bool {
+    !checks.is_empty() && checks.iter().all(|check| check.passed)
+}
+
+/// Reference PDS given as `?pds=` on the self-test page URL
+pub fn pds_from_page_url(page_url: &str) -> Option<String> {
+    let url = reqwest::Url::parse(page_url).ok()?;
+    let pds = url
+        .query_pairs()
+        .find(|(key, _)| key == "pds")
+        .map(|(_, value)| normalize_pds_url(&value))?;
+    (!pds.is_empty()).then_some(pds)
+}
+
+/// Runs each check and reports it as soon as it finishes
+struct Runner<F: FnMut(&SelfTestCheck)> {
+    checks: Vec<SelfTestCheck>,
+    on_check: F,
+}
+
+impl<F: FnMut(&SelfTestCheck)> Runner<F> {
+    async fn check<T>(
+        &mut self,
+        layer: SelfTestLayer,
+        name: &str,
+        run: impl Future<Output = Result<(T, String), String>>,
+    ) -> Option<T> {
+        let started = now_ms();
+        let result = run.await;
+        let check = SelfTestCheck {
+            layer,
+            name: name.to_string(),
+            passed: result.is_ok(),
+            detail: match &result {
+                Ok((_, detail)) => detail.clone(),
+                Err(error) => error.clone(),
+            },
+            duration_ms: (now_ms() - started).max(0.0) as u64,
+        };
+        (self.on_check)(&check);
+        self.checks.push(check);
+        result.ok().map(|(value, _)| value)
+    }
+}
+
+/// Run every check; later checks that depend on a failed one are left out
+pub async fn run_self_test(
+    config: &SelfTestConfig,
+    on_check: impl FnMut(&SelfTestCheck),
+) -> Vec<SelfTestCheck> {
+    let mut runner = Runner {
+        checks: Vec::new(),
+        on_check,
+    };
+    let pds_url = normalize_pds_url(&config.pds_url);
+    let handle = normalize_identifier(&config.handle);
+
+    runner
+        .check(SelfTestLayer::Storage, "Local storage round trip", async {
+            let token = random_hex(8);
+            storage_set(StorageArea::Local, STORAGE_KEY, &token).map_err(|e| e.to_string())?;
+            let read: Result<String, _> = storage_get(StorageArea::Local, STORAGE_KEY);
+            storage_delete(StorageArea::Local, STORAGE_KEY);
+            match read {
+                Ok(read) if read == token => Ok(((), "Wrote, read back and removed a key".into())),
+                Ok(_) => Err("Read back a different value than was written".to_string()),
+                Err(e) => Err(e.to_string()),
+            }
+        })
+        .await;
+
+    runner
+        .check(SelfTestLayer::Storage, "Blob storage round trip", async {
+            let storage = BrowserStorage::new().await?;
+            let id = format!("selftest-{}", random_hex(4));
+            storage.write_chunk(&id, 0, SELF_TEST_BLOB).await?;
+            let read = storage.read_data(&id).await;
+            storage.delete(&id).await?;
+            if read? != SELF_TEST_BLOB {
+                return Err("Read back different bytes than were written".to_string());
+            }
+            Ok((
+                (),
+                format!("Stored and read back {} bytes", SELF_TEST_BLOB.len()),
+            ))
+        })
+        .await;
+
+    let resolver = WebIdentityResolver::new();
+    let did = runner
+        .check(SelfTestLayer::Resolver, "Resolve handle", async {
+            let did = resolver
+                .resolve_handle(&handle)
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok((did.clone(), format!("{} → {}", handle, did)))
+        })
+        .await;
+    if let Some(did) = &did {
+        runner
+            .check(
+                SelfTestLayer::Resolver,
+                "Resolve PDS from DID document",
+                async {
+                    let endpoint = resolver
+                        .resolve_did_to_pds_endpoint(did)
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    if endpoint.trim_end_matches('/') != pds_url {
+                        return Err(format!(
+                            "DID document points to {}, expected {}",
+                            endpoint, pds_url
+                        ));
+                    }
+                    Ok(((), endpoint))
+                },
+            )
+            .await;
+    }
+
+    let client = PdsClient::new();
+    runner
+        .check(SelfTestLayer::Client, "Describe server", async {
+            let description = client
+                .describe_server(&pds_url)
+                .await
+                .map_err(|e| e.to_string())?;
+            let server_did = description
+                .get("did")
+                .and_then(|did| did.as_str())
+                .unwrap_or("no DID reported");
+            Ok(((), server_did.to_string()))
+        })
+        .await;
+
+    let Some(session) = runner
+        .check(SelfTestLayer::Client, "Log in", async {
+            let response = client
+                .try_login_before_creation(&handle, &config.password, &pds_url)
+                .await
+                .map_err(|e| e.to_string())?;
+            match response.session {
+                Some(session) if response.success => {
+                    let detail = format!("Session for {}", session.did);
+                    Ok((session, detail))
+                }
+                _ => Err(response.message),
+            }
+        })
+        .await
+    else {
+        return runner.checks;
+    };
+
+    run_session_checks(&mut runner, &client, &session).await;
+    runner.checks
+}
+
+async fn run_session_checks<F: FnMut(&SelfTestCheck)>(
+    runner: &mut Runner<F>,
+    client: &PdsClient,
+    session: &ClientSessionCredentials,
+) {
+    runner
+        .check(SelfTestLayer::Client, "Export repository", async {
+            let export = client
+                .export_repository(session)
+                .await
+                .map_err(|e| e.to_string())?;
+            let car = export
+                .car_data
+                .filter(|_| export.success)
+                .ok_or(export.message)?;
+            let mut reader = CarStreamReader::new();
+            reader.push(&car, |_, _| Ok(()))?;
+            reader.finish()?;
+            let root = reader.root().ok_or("CAR export has no root")?;
+            Ok((
+                (),
+                format!(
+                    "{} blocks, {} bytes, root {}",
+                    reader.blocks(),
+                    reader.bytes(),
+                    root
+                ),
+            ))
+        })
+        .await;
+
+    runner
+        .check(SelfTestLayer::Client, "Upload blob", async {
+            let cid = Cid::try_from(SELF_TEST_BLOB_CID).map_err(|e| e.to_string())?;
+            let upload = client
+                .upload_blob(session, &cid, SELF_TEST_BLOB.to_vec())
+                .await
+                .map_err(|e| e.to_string())?;
+            if !upload.success {
+                return Err(upload.message);
+            }
+            Ok(((), format!("Uploaded {}", cid)))
+        })
+        .await;
+
+    runner
+        .check(SelfTestLayer::Client, "Preferences round trip", async {
+            let export = client
+                .export_preferences(session)
+                .await
+                .map_err(|e| e.to_string())?;
+            let preferences = export
+                .preferences_json
+                .filter(|_| export.success)
+                .ok_or(export.message)?;
+            let import = client
+                .import_preferences(session, preferences)
+                .await
+                .map_err(|e| e.to_string())?;
+            if !import.success {
+                return Err(import.message);
+            }
+            Ok(((), "Read the preferences and wrote them back".to_string()))
+        })
+        .await;
+
+    runner
+        .check(SelfTestLayer::Client, "Session still valid", async {
+            match client.validate_session(session).await {
+                Ok(true) => Ok(((), "Access token accepted".to_string())),
+                Ok(false) => Err("The PDS rejected the access token".to_string()),
+                Err(e) => Err(e.to_string()),
+            }
+        })
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_self_test_inputs_and_verdict() {
+        assert_eq!(
+            pds_from_page_url("https://migrate.example/selftest?pds=https%3A%2F%2Fpds.test%2F"),
+            Some("https://pds.test".to_string())
+        );
+        assert_eq!(pds_from_page_url("https://migrate.example/selftest"), None);
+        assert_eq!(
+            pds_from_page_url("https://migrate.example/selftest?pds="),
+            None
+        );
+
+        let check = |passed| SelfTestCheck {
+            layer: SelfTestLayer::Client,
+            name: "Log in".to_string(),
+            passed,
+            detail: String::new(),
+            duration_ms: 12,
+        };
+        assert!(!self_test_passed(&[]));
+        assert!(self_test_passed(&[check(true), check(true)]));
+        assert!(!self_test_passed(&[check(true), check(false)]));
+    }
+
+    #[cfg(feature = "hash-verification")]
+    #[test]
+    fn test_self_test_blob_cid_matches_payload() {
+        let cid = Cid::try_from(SELF_TEST_BLOB_CID).unwrap();
+        crate::migration::integrity::verify_blob_bytes(&cid, SELF_TEST_BLOB).unwrap();
+    }
+}
@@ -0,0 +1,384 @@
+//! Labeler and feed-generator accounts
+//!
+//! Some accounts do more than post: a labeler's DID document carries an
+//! `atproto_labeler` service and an `atproto_label` signing key, and a feed
+//! generator account may declare a `bsky_fg` service next to its
+//! `app.bsky.feed.generator` records. The target's recommended credentials only
+//! describe the PDS, so an operation built from them alone would silently drop
+//! those entries and take the labeler or feed offline.
+//!
+//! These accounts are detected before the PLC operation is built, their extra
+//! services and keys are carried into the operation, the endpoints are checked
+//! again once it's submitted, and the operator is told what has to be updated
+//! by hand on the services themselves.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::services::client::{NewPds, OldPds, PdsApi};
+use crate::{console_info, console_warn};
+
+const ATPROTO_PDS_SERVICE: &str = "atproto_pds";
+const ATPROTO_SIGNING_KEY: &str = "atproto";
+const LABELER_SERVICE: &str = "atproto_labeler";
+const LABELER_SERVICE_TYPE: &str = "AtprotoLabeler";
+const LABELER_KEY: &str = "atproto_label";
+const LABELER_COLLECTION: &str = "app.bsky.labeler.service";
+const FEED_GENERATOR_SERVICE: &str = "bsky_fg";
+const FEED_GENERATOR_SERVICE_TYPE: &str = "BskyFeedGenerator";
+const FEED_GENERATOR_COLLECTION: &str = "app.bsky.feed.generator";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ServiceAccountKind {
+    Labeler,
+    FeedGenerator,
+}
+
+impl ServiceAccountKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ServiceAccountKind::Labeler => "Labeler",
+            ServiceAccountKind::FeedGenerator => "Feed generator",
+        }
+    }
+}
+
+/// A non-PDS service entry from the DID document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceEntry {
+    pub id: String,
+    pub service_type: String,
+    pub endpoint: String,
+}
+
+/// Where a preserved service points after the identity update
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceEndpointCheck {
+    pub id: String,
+    pub expected: String,
+    /// `None` when the entry is missing from the updated document
+    pub found: Option<String>,
+}
+
+impl ServiceEndpointCheck {
+    pub fn is_ok(&self) -> bool {
+        self.found
+            .as_deref()
+            .is_some_and(|found| same_endpoint(found, &self.expected))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServiceAccountProfile {
+    pub kinds: Vec<ServiceAccountKind>,
+    /// Services besides the PDS that must survive the identity update
+    pub services: Vec<ServiceEntry>,
+    /// Verification methods besides the repo signing key, e.g. `atproto_label`
+    pub extra_keys: Vec<String>,
+    /// Filled in once the identity update has been submitted
+    pub endpoint_checks: Vec<ServiceEndpointCheck>,
+}
+
+impl ServiceAccountProfile {
+    /// Whether every preserved service was found at its old endpoint
+    pub fn endpoints_verified(&self) -> bool {
+        !self.endpoint_checks.is_empty() && self.endpoint_checks.iter().all(|c| c.is_ok())
+    }
+
+    /// What the migration can't do for the operator
+    pub fn manual_steps(&self, did_method: &str) -> Vec<String> {
+        let mut steps = Vec::new();
+        if self.kinds.contains(&ServiceAccountKind::Labeler) {
+            steps.push(
+                "Point your labeler service (e.g. Ozone) at the new PDS and give it a new app password; app passwords are not migrated."
+                    .to_string(),
+            );
+            steps.push(
+                "Keep the labeler's signing key where it is. Labels stay valid only while the atproto_label key in the DID document is unchanged."
+                    .to_string(),
+            );
+        }
+        if self.kinds.contains(&ServiceAccountKind::FeedGenerator) {
+            steps.push(
+                "Update any scripts or servers that publish or edit your feed records to log in to the new PDS."
+                    .to_string(),
+            );
+            steps.push(
+                "Keep your feed generator server running at the same address; feed records still point at it."
+                    .to_string(),
+            );
+        }
+        if did_method == "web" && !(self.services.is_empty() && self.extra_keys.is_empty()) {
+            steps.push(
+                "Your did:web document is published by you. Copy the labeler and feed generator services and keys from your current did.json into the new one."
+                    .to_string(),
+            );
+        }
+        steps
+    }
+}
+
+fn same_endpoint(a: &str, b: &str) -> bool {
+    a.trim_end_matches('/')
+        .eq_ignore_ascii_case(b.trim_end_matches('/'))
+}
+
+/// Non-PDS services in PLC-data form, in name order
+fn extra_services(current: &Value) -> Vec<ServiceEntry> {
+    let Some(services) = current.get("services").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    services
+        .iter()
+        .filter(|(id, _)| id.as_str() != ATPROTO_PDS_SERVICE)
+        .map(|(id, service)| ServiceEntry {
+            id: id.clone(),
+            service_type: service
+                .get("type")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            endpoint: service
+                .get("endpoint")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+        })
+        .collect()
+}
+
+fn extra_keys(current: &Value) -> Vec<String> {
+    current
+        .get("verificationMethods")
+        .and_then(Value::as_object)
+        .map(|keys| {
+            keys.keys()
+                .filter(|name| name.as_str() != ATPROTO_SIGNING_KEY)
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Classify an account from its current PLC data and its repo's collections
+pub fn detect_service_account(
+    current: Option<&Value>,
+    collections: &[String],
+) -> Option<ServiceAccountProfile> {
+    let services = current.map(extra_services).unwrap_or_default();
+    let extra_keys = current.map(extra_keys).unwrap_or_default();
+    let has_service = |id: &str, service_type: &str| {
+        services
+            .iter()
+            .any(|service| service.id == id || service.service_type == service_type)
+    };
+    let has_collection = |nsid: &str| collections.iter().any(|c| c == nsid);
+
+    let mut kinds = Vec::new();
+    if has_service(LABELER_SERVICE, LABELER_SERVICE_TYPE)
+        || extra_keys.iter().any(|key| key == LABELER_KEY)
+        || has_collection(LABELER_COLLECTION)
+    {
+        kinds.push(ServiceAccountKind::Labeler);
+    }
+    if has_service(FEED_GENERATOR_SERVICE, FEED_GENERATOR_SERVICE_TYPE)
+        || has_collection(FEED_GENERATOR_COLLECTION)
+    {
+        kinds.push(ServiceAccountKind::FeedGenerator);
+    }
+
+    if kinds.is_empty() && services.is_empty() && extra_keys.is_empty() {
+        return None;
+    }
+    Some(ServiceAccountProfile {
+        kinds,
+        services,
+        extra_keys,
+        endpoint_checks: Vec::new(),
+    })
+}
+
+/// Copy the current non-PDS services and extra keys into an unsigned operation
+/// that lacks them, returning the names that were added
+pub fn preserve_service_entries(unsigned: &mut Value, current: &Value) -> Vec<String> {
+    let mut restored = Vec::new();
+    let Some(operation) = unsigned.as_object_mut() else {
+        return restored;
+    };
+
+    for (field, skip) in [
+        ("services", ATPROTO_PDS_SERVICE),
+        ("verificationMethods", ATPROTO_SIGNING_KEY),
+    ] {
+        let Some(existing) = current.get(field).and_then(Value::as_object) else {
+            continue;
+        };
+        let target = operation
+            .entry(field)
+            .or_insert_with(|| Value::Object(Map::new()));
+        let Some(target) = target.as_object_mut() else {
+            continue;
+        };
+        for (name, entry) in existing {
+            if name != skip && !target.contains_key(name) {
+                target.insert(name.clone(), entry.clone());
+                restored.push(name.clone());
+            }
+        }
+    }
+    restored
+}
+
+/// Compare the preserved services with the PLC data after the update
+pub fn check_service_endpoints(
+    expected: &[ServiceEntry],
+    current: &Value,
+) -> Vec<ServiceEndpointCheck> {
+    let found = extra_services(current);
+    expected
+        .iter()
+        .map(|service| ServiceEndpointCheck {
+            id: service.id.clone(),
+            expected: service.endpoint.clone(),
+            found: found
+                .iter()
+                .find(|entry| entry.id == service.id)
+                .map(|entry| entry.endpoint.clone()),
+        })
+        .collect()
+}
+
+/// Look at the account before the identity update is built
+pub async fn detect_for_migration(
+    old: &OldPds<&dyn PdsApi>,
+    new: &NewPds<&dyn PdsApi>,
+) -> Option<ServiceAccountProfile> {
+    let current = if new.did().starts_with("did:plc:") {
+        match new.fetch_plc_data().await {
+            Ok(current) => Some(current),
+            Err(e) => {
+                console_warn!(
+                    "[Migration] Could not read PLC data to check for labeler or feed generator services: {}",
+                    e
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let collections = old.describe_repo_collections().await.unwrap_or_else(|e| {
+        console_warn!(
+            "[Migration] Could not list collections to check for labeler or feed generator records: {}",
+            e
+        );
+        Vec::new()
+    });
+
+    let profile = detect_service_account(current.as_ref(), &collections)?;
+    console_info!(
+        "[Migration] Service account detected ({:?}); preserving services {:?} and keys {:?}",
+        profile.kinds,
+        profile
+            .services
+            .iter()
+            .map(|service| service.id.as_str())
+            .collect::<Vec<_>>(),
+        profile.extra_keys
+    );
+    Some(profile)
+}
+
+/// Re-read the PLC data after submission and record where each service points
+pub async fn verify_service_endpoints<C: PdsApi>(
+    new: &NewPds<C>,
+    mut profile: ServiceAccountProfile,
+) -> Result<ServiceAccountProfile, String> {
+    let current = new
+        .fetch_plc_data()
+        .await
+        .map_err(|e| format!("Could not read the updated PLC data: {}", e))?;
+    profile.endpoint_checks = check_service_endpoints(&profile.services, &current);
+    Ok(profile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn labeler_plc_data() -> Value {
+        json!({
+            "rotationKeys": ["did:key:zrotation"],
+            "verificationMethods": {
+                "atproto": "did:key:zold",
+                "atproto_label": "did:key:zlabel"
+            },
+            "services": {
+                "atproto_pds": { "type": "AtprotoPersonalDataServer", "endpoint": "https://old.example" },
+                "atproto_labeler": { "type": "AtprotoLabeler", "endpoint": "https://labels.example" }
+            }
+        })
+    }
+
+    #[test]
+    fn test_detects_labelers_and_feed_generators() {
+        let profile = detect_service_account(Some(&labeler_plc_data()), &[]).unwrap();
+        assert_eq!(profile.kinds, vec![ServiceAccountKind::Labeler]);
+        assert_eq!(profile.services[0].id, "atproto_labeler");
+        assert_eq!(profile.extra_keys, vec!["atproto_label".to_string()]);
+
+        let feeds = detect_service_account(None, &["app.bsky.feed.generator".to_string()]).unwrap();
+        assert_eq!(feeds.kinds, vec![ServiceAccountKind::FeedGenerator]);
+        assert!(!feeds.manual_steps("plc").is_empty());
+
+        let plain = json!({
+            "verificationMethods": { "atproto": "did:key:zold" },
+            "services": { "atproto_pds": { "type": "AtprotoPersonalDataServer", "endpoint": "https://old.example" } }
+        });
+        assert_eq!(
+            detect_service_account(Some(&plain), &["app.bsky.feed.post".to_string()]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_preserves_services_missing_from_recommendation() {
+        let mut recommended = json!({
+            "verificationMethods": { "atproto": "did:key:znew" },
+            "services": {
+                "atproto_pds": { "type": "AtprotoPersonalDataServer", "endpoint": "https://new.example" }
+            }
+        });
+        let restored = preserve_service_entries(&mut recommended, &labeler_plc_data());
+
+        assert_eq!(restored, vec!["atproto_labeler", "atproto_label"]);
+        assert_eq!(recommended["verificationMethods"]["atproto"], "did:key:znew");
+        assert_eq!(
+            recommended["verificationMethods"]["atproto_label"],
+            "did:key:zlabel"
+        );
+        assert_eq!(
+            recommended["services"]["atproto_pds"]["endpoint"],
+            "https://new.example"
+        );
+        assert_eq!(
+            recommended["services"]["atproto_labeler"]["endpoint"],
+            "https://labels.example"
+        );
+        assert!(preserve_service_entries(&mut recommended, &labeler_plc_data()).is_empty());
+    }
+
+    #[test]
+    fn test_endpoint_checks_after_update() {
+        let profile = detect_service_account(Some(&labeler_plc_data()), &[]).unwrap();
+
+        let kept = check_service_endpoints(&profile.services, &labeler_plc_data());
+        assert!(kept.iter().all(ServiceEndpointCheck::is_ok));
+
+        let dropped = json!({ "services": { "atproto_pds": { "endpoint": "https://new.example" } } });
+        let checks = check_service_endpoints(&profile.services, &dropped);
+        assert_eq!(checks[0].found, None);
+        assert!(!checks[0].is_ok());
+    }
+}
@@ -0,0 +1,139 @@
+//! Refreshing both sessions right before the long phases
+//!
+//! The repository export and the blob uploads each run for a long time, partly
+//! in single large requests. A token that expires in the middle of one of those
+//! fails a request that is expensive to repeat, and the refresh-on-expiry in
+//! the client only helps the request after it. So just before those steps
+//! start, each session with less than `session.prewarm_min_lifetime_secs` left
+//! is refreshed. The refresh is cut off after `session.prewarm_timeout_secs`;
+//! if it fails or runs out of time the step starts with the session it had.
+
+use futures::future::{select, Either};
+
+use crate::migration::plan::PlanStep;
+use crate::migration::steps::StepContext;
+use crate::migration::storage::LocalStorageManager;
+use crate::migration::types::MigrationAction;
+use crate::services::client::{ClientError, ClientSessionCredentials};
+use crate::services::config::get_global_config;
+use crate::services::retry::sleep_ms;
+use crate::{console_info, console_warn};
+
+/// Steps long enough that both sessions are refreshed before they start
+pub fn prewarms_sessions(step: PlanStep) -> bool {
+    matches!(step, PlanStep::Repository | PlanStep::Blobs)
+}
+
+/// Whether the session has less than `min_lifetime_secs` left; sessions with no
+/// known expiry are left alone
+pub fn needs_prewarm(session: &ClientSessionCredentials, min_lifetime_secs: u64) -> bool {
+    min_lifetime_secs > 0
+        && session
+            .remaining_lifetime_secs()
+            .is_some_and(|left| left < min_lifetime_secs)
+}
+
+/// Run a refresh, giving up after `timeout_secs`
+async fn time_boxed<T>(
+    refresh: impl std::future::Future<Output = Result<T, ClientError>>,
+    timeout_secs: u64,
+) -> Result<T, String> {
+    match select(Box::pin(refresh), Box::pin(sleep_ms(timeout_secs * 1000))).await {
+        Either::Left((result, _)) => result.map_err(|e| e.to_string()),
+        Either::Right(_) => Err(format!("no answer within {}s", timeout_secs)),
+    }
+}
+
+/// Refresh the sessions in `ctx` that would expire soon, keeping the stored
+/// copies and the state in step
+pub async fn prewarm_sessions(ctx: &mut StepContext<'_>) {
+    let config = get_global_config().session;
+    let min_lifetime = config.prewarm_min_lifetime_secs;
+
+    if needs_prewarm(ctx.old.session(), min_lifetime) {
+        match time_boxed(ctx.old.refreshed(), config.prewarm_timeout_secs).await {
+            Ok(old) => {
+                console_info!("[Migration] Refreshed the old PDS session before a long step");
+                let _ = LocalStorageManager::store_old_session(&old.session().into());
+                ctx.old = old;
+            }
+            Err(e) => console_warn!(
+                "[Migration] Could not refresh the old PDS session ahead of time: {}",
+                e
+            ),
+        }
+    }
+
+    let refreshed_new = match ctx.new.as_ref() {
+        Some(new) if needs_prewarm(new.session(), min_lifetime) => {
+            Some(time_boxed(new.refreshed(), config.prewarm_timeout_secs).await)
+        }
+        _ => None,
+    };
+    match refreshed_new {
+        Some(Ok(new)) => {
+            console_info!("[Migration] Refreshed the new PDS session before a long step");
+            let session = new.session().into();
+            let _ = LocalStorageManager::store_new_session(&session);
+            (ctx.dispatch)(MigrationAction::SetNewPdsSession(Some(session)));
+            ctx.new = Some(new);
+        }
+        Some(Err(e)) => console_warn!(
+            "[Migration] Could not refresh the new PDS session ahead of time: {}",
+            e
+        ),
+        None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migration::steps::mock_pds::{session, MockPds};
+    use crate::migration::types::MigrationState;
+    use crate::services::client::{NewPds, OldPds, PdsApi};
+    use crate::utils::platform::now_secs;
+    use std::cell::RefCell;
+
+    #[test]
+    fn test_only_sessions_about_to_expire_are_refreshed() {
+        let now = now_secs();
+        let pds = MockPds {
+            refreshed_expires_at: Some(now + 7200),
+            ..MockPds::default()
+        };
+        let api: &dyn PdsApi = &pds;
+        let expiring = ClientSessionCredentials {
+            expires_at: Some(now + 60),
+            ..session("https://old.example")
+        };
+        let lasting = ClientSessionCredentials {
+            expires_at: Some(now + 3600),
+            ..session("https://new.example")
+        };
+        assert!(needs_prewarm(&expiring, 1200));
+        assert!(!needs_prewarm(&lasting, 1200));
+        assert!(!needs_prewarm(&session("https://old.example"), 1200));
+        assert!(!needs_prewarm(&expiring, 0));
+
+        let state = MigrationState::default();
+        let actions = RefCell::new(Vec::new());
+        let dispatch = |action: MigrationAction| actions.borrow_mut().push(action);
+        let mut ctx = StepContext {
+            old: OldPds::new(api, expiring),
+            new: Some(NewPds::new(api, lasting)),
+            state: &state,
+            dispatch: &dispatch,
+            handler: None,
+            preferences: None,
+        };
+
+        futures::executor::block_on(prewarm_sessions(&mut ctx));
+        assert_eq!(ctx.old.session().access_jwt, "refreshed");
+        assert_eq!(ctx.new.as_ref().unwrap().session().access_jwt, "");
+        assert_eq!(pds.session_refreshes.get(), 1);
+        assert!(actions.borrow().is_empty());
+        assert!(prewarms_sessions(PlanStep::Blobs));
+        assert!(!prewarms_sessions(PlanStep::PlcSetup));
+    }
+}
@@ -0,0 +1,355 @@
+//! Handing a migration over to another device
+//!
+//! Checking the account and picking the new PDS works fine on a phone, but the
+//! transfer itself is best left to a desktop. The phone exports a short-lived
+//! transfer string (shown as a QR code of a link, or copied by hand) and the
+//! desktop imports it to continue with the forms already filled in.
+//!
+//! The string carries the plan: old handle, new PDS, new handle and plan
+//! options. Passwords, the email address and the invite code are never
+//! included and are asked for again on the desktop. The old PDS session is
+//! only added when the user explicitly opts in, so the desktop can skip logging
+//! in. Everything is encrypted with AES-256-GCM under a key derived from a
+//! one-time transfer code that the phone displays separately and the user types
+//! on the desktop, and the payload stops being accepted after
+//! [`SESSION_TRANSFER_TTL_SECS`].
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use super::plan::PlanVariant;
+use super::types::{MigrationAction, MigrationState, PdsLoginResponse, SessionCredentials};
+use crate::services::config::{BlobEnumerationMethod, BlobNotFoundPolicy, VerificationLevel};
+use crate::utils::platform::fill_random;
+
+/// Marks a transfer string and its format version
+pub const SESSION_TRANSFER_PREFIX: &str = "tektite-transfer-v1.";
+
+/// URL fragment key carrying a transfer string in links
+pub const SESSION_TRANSFER_FRAGMENT: &str = "transfer=";
+
+/// How long an exported transfer can be imported
+pub const SESSION_TRANSFER_TTL_SECS: u64 = 15 * 60;
+
+/// PBKDF2-HMAC-SHA256 rounds; the code is short, so make each guess expensive
+pub const SESSION_TRANSFER_KDF_ITERATIONS: u32 = 300_000;
+
+/// Characters of a transfer code, without look-alikes (0/O, 1/I/L, U/V)
+const CODE_ALPHABET: &[u8] = b"23456789ABCDEFGHJKMNPQRSTWXYZ";
+const CODE_LEN: usize = 10;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// What moves to the other device
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionTransfer {
+    pub created_at_secs: u64,
+    pub expires_at_secs: u64,
+    pub app_version: String,
+    pub handle: String,
+    pub new_pds_url: String,
+    pub new_handle: String,
+    pub plan_variant: PlanVariant,
+    pub verification_level: Option<VerificationLevel>,
+    #[serde(default)]
+    pub blob_enumeration_method: Option<BlobEnumerationMethod>,
+    #[serde(default)]
+    pub blob_retries: Option<u32>,
+    #[serde(default)]
+    pub blob_not_found_policy: Option<BlobNotFoundPolicy>,
+    /// Old PDS session, only when the user agreed to send it
+    pub old_session: Option<SessionCredentials>,
+}
+
+impl SessionTransfer {
+    pub fn capture(state: &MigrationState, include_session: bool, now_secs: u64) -> Self {
+        let handle = if state.form1.original_handle.is_empty() {
+            state.form1.handle.clone()
+        } else {
+            state.form1.original_handle.clone()
+        };
+        let old_session = include_session
+            .then(|| {
+                state
+                    .form1
+                    .login_response
+                    .as_ref()
+                    .and_then(|response| response.session.clone())
+            })
+            .flatten();
+
+        Self {
+            created_at_secs: now_secs,
+            expires_at_secs: now_secs + SESSION_TRANSFER_TTL_SECS,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            handle,
+            new_pds_url: state.form2.pds_url.clone(),
+            new_handle: state.form3.handle.clone(),
+            plan_variant: state.form3.plan_variant,
+            verification_level: state.form3.verification_level,
+            blob_enumeration_method: state.form3.blob_enumeration_method,
+            blob_retries: state.form3.blob_retries,
+            blob_not_found_policy: state.form3.blob_not_found_policy,
+            old_session,
+        }
+    }
+
+    /// Encrypt into a transfer string under `code`
+    pub fn encrypt(&self, code: &str) -> Result<String, String> {
+        self.encrypt_with_iterations(code, SESSION_TRANSFER_KDF_ITERATIONS)
+    }
+
+    pub(crate) fn encrypt_with_iterations(
+        &self,
+        code: &str,
+        iterations: u32,
+    ) -> Result<String, String> {
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        fill_random(&mut salt);
+        fill_random(&mut nonce);
+
+        let plaintext = serde_json::to_vec(self).map_err(|e| e.to_string())?;
+        let ciphertext = cipher_for(code, &salt, iterations)?
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_ref())
+            .map_err(|_| "Could not encrypt the transfer".to_string())?;
+
+        let mut packed = Vec::with_capacity(4 + SALT_LEN + NONCE_LEN + ciphertext.len());
+        packed.extend_from_slice(&iterations.to_be_bytes());
+        packed.extend_from_slice(&salt);
+        packed.extend_from_slice(&nonce);
+        packed.extend_from_slice(&ciphertext);
+        Ok(format!(
+            "{}{}",
+            SESSION_TRANSFER_PREFIX,
+            base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(packed)
+        ))
+    }
+
+    /// Parse a pasted string or link, refusing a wrong code or an expired transfer
+    pub fn decrypt(input: &str, code: &str, now_secs: u64) -> Result<Self, String> {
+        let input = input.trim();
+        let input = input
+            .rsplit_once(SESSION_TRANSFER_FRAGMENT)
+            .map_or(input, |(_, encoded)| encoded);
+        let encoded = input
+            .strip_prefix(SESSION_TRANSFER_PREFIX)
+            .ok_or("This is not a transfer from this tool (or it's from a newer version)")?;
+        let packed = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(encoded)
+            .map_err(|e| format!("The transfer is damaged: {}", e))?;
+        if packed.len() <= 4 + SALT_LEN + NONCE_LEN {
+            return Err("The transfer is damaged: too short".to_string());
+        }
+
+        let (iterations, rest) = packed.split_at(4);
+        let (salt, rest) = rest.split_at(SALT_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let iterations = u32::from_be_bytes(iterations.try_into().expect("4 bytes"));
+        if iterations == 0 || iterations > SESSION_TRANSFER_KDF_ITERATIONS {
+            return Err("The transfer is damaged: unexpected key derivation".to_string());
+        }
+        let plaintext = cipher_for(&normalize_code(code), salt, iterations)?
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "Wrong transfer code, or the transfer is damaged".to_string())?;
+        let transfer: Self = serde_json::from_slice(&plaintext)
+            .map_err(|e| format!("The transfer is damaged: {}", e))?;
+        if now_secs > transfer.expires_at_secs {
+            return Err(
+                "This transfer has expired; create a new one on the other device".to_string(),
+            );
+        }
+        Ok(transfer)
+    }
+
+    /// Link to the app that opens this transfer, e.g. `https://example.com/#transfer=...`
+    pub fn link(page_url: &str, encrypted: &str) -> String {
+        let base = page_url.split('#').next().unwrap_or(page_url);
+        format!("{}#{}{}", base, SESSION_TRANSFER_FRAGMENT, encrypted)
+    }
+
+    /// Actions that fill in the forms on the importing device
+    ///
+    /// Storing the old session for the migration itself is left to the caller.
+    pub fn actions(&self) -> Vec<MigrationAction> {
+        let mut actions = vec![
+            MigrationAction::SetHandle(self.handle.clone()),
+            MigrationAction::SetOriginalHandle(self.handle.clone()),
+        ];
+        if let Some(session) = &self.old_session {
+            actions.push(MigrationAction::SetLoginResponse(Some(PdsLoginResponse {
+                success: true,
+                message: "Signed in on another device".to_string(),
+                did: Some(session.did.clone()),
+                session: Some(session.clone()),
+            })));
+            actions.push(MigrationAction::SetSessionStored(true));
+        }
+        actions.extend([
+            MigrationAction::SetNewPdsUrl(self.new_pds_url.clone()),
+            MigrationAction::SetNewHandle(self.new_handle.clone()),
+            MigrationAction::SetPlanVariant(self.plan_variant),
+        ]);
+        if let Some(level) = self.verification_level {
+            actions.push(MigrationAction::SetVerificationLevel(level));
+        }
+        if let Some(method) = self.blob_enumeration_method {
+            actions.push(MigrationAction::SetBlobEnumerationMethod(method));
+        }
+        if let Some(retries) = self.blob_retries {
+            actions.push(MigrationAction::SetBlobRetries(retries));
+        }
+        if let Some(policy) = self.blob_not_found_policy {
+            actions.push(MigrationAction::SetBlobNotFoundPolicy(policy));
+        }
+        actions
+    }
+}
+
+/// New random transfer code, grouped for reading aloud, e.g. `K7QX2-MP9RT`
+pub fn generate_transfer_code() -> String {
+    let mut chars = String::with_capacity(CODE_LEN);
+    while chars.len() < CODE_LEN {
+        let mut bytes = [0u8; CODE_LEN];
+        fill_random(&mut bytes);
+        chars.extend(
+            bytes
+                .iter()
+                .filter_map(|byte| code_char(*byte))
+                .take(CODE_LEN - chars.len()),
+        );
+    }
+    format!("{}-{}", &chars[..CODE_LEN / 2], &chars[CODE_LEN / 2..])
+}
+
+/// Map a random byte onto the alphabet, rejecting bytes past the largest
+/// multiple of its length so every character is equally likely
+fn code_char(byte: u8) -> Option<char> {
+    let len = CODE_ALPHABET.len();
+    let limit = 256 - 256 % len;
+    ((byte as usize) < limit).then(|| CODE_ALPHABET[byte as usize % len] as char)
+}
+
+/// Uppercase without separators, so typing `k7qx2 mp9rt` works
+fn normalize_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+fn cipher_for(code: &str, salt: &[u8], iterations: u32) -> Result<Aes256Gcm, String> {
+    let code = normalize_code(code);
+    if code.is_empty() {
+        return Err("Enter the transfer code shown on the other device".to_string());
+    }
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(code.as_bytes(), salt, iterations, &mut key);
+    Aes256Gcm::new_from_slice(&key).map_err(|_| "Could not derive the transfer key".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_with_login() -> MigrationState {
+        let mut state = MigrationState::default();
+        state.reduce_in_place(MigrationAction::SetHandle("alice.test".to_string()));
+        state.reduce_in_place(MigrationAction::SetPassword("hunter2".to_string()));
+        state.reduce_in_place(MigrationAction::SetLoginResponse(Some(PdsLoginResponse {
+            success: true,
+            message: "ok".to_string(),
+            did: Some("did:plc:alice".to_string()),
+            session: Some(SessionCredentials {
+                did: "did:plc:alice".to_string(),
+                handle: "alice.test".to_string(),
+                pds: "https://old.example".to_string(),
+                access_jwt: "access-token".to_string(),
+                refresh_jwt: "refresh-token".to_string(),
+            }),
+        })));
+        state.reduce_in_place(MigrationAction::SetNewPdsUrl(
+            "https://new.example".to_string(),
+        ));
+        state.reduce_in_place(MigrationAction::SetNewHandle(
+            "alice.new.example".to_string(),
+        ));
+        state.reduce_in_place(MigrationAction::SetEmailAddress(
+            "alice@mail.example".to_string(),
+        ));
+        state.reduce_in_place(MigrationAction::SetInviteCode(
+            "new-example-invite".to_string(),
+        ));
+        state
+    }
+
+    #[test]
+    fn test_round_trip_needs_code_and_expires() {
+        let transfer = SessionTransfer::capture(&state_with_login(), false, 1_000);
+        assert!(transfer.old_session.is_none());
+
+        let encrypted = transfer.encrypt_with_iterations("K7QX2-MP9RT", 10).unwrap();
+        assert!(encrypted.starts_with(SESSION_TRANSFER_PREFIX));
+        assert!(!encrypted.contains("alice"));
+
+        let link = SessionTransfer::link("https://app.example/#help=old", &encrypted);
+        assert!(link.starts_with("https://app.example/#transfer="));
+        // Codes are accepted however they were typed
+        let decrypted = SessionTransfer::decrypt(&link, "k7qx2 mp9rt", 1_100).unwrap();
+        assert_eq!(decrypted, transfer);
+
+        assert!(SessionTransfer::decrypt(&encrypted, "K7QX2-MP9RX", 1_100).is_err());
+        assert!(SessionTransfer::decrypt(&encrypted, "", 1_100).is_err());
+        assert!(SessionTransfer::decrypt(
+            &encrypted,
+            "K7QX2-MP9RT",
+            1_000 + SESSION_TRANSFER_TTL_SECS + 1
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_session_only_with_consent_and_never_passwords() {
+        let state = state_with_login();
+        let transfer = SessionTransfer::capture(&state, true, 1_000);
+        let json = serde_json::to_string(&transfer).unwrap();
+        assert!(json.contains("refresh-token"));
+        assert!(!json.contains("hunter2"));
+        assert!(!json.contains("alice@mail.example"));
+        assert!(!json.contains("new-example-invite"));
+
+        let mut imported = MigrationState::default();
+        for action in transfer.actions() {
+            imported.reduce_in_place(action);
+        }
+        assert!(imported.session_stored());
+        assert_eq!(imported.form1.original_handle, "alice.test");
+        assert_eq!(imported.form2.pds_url, "https://new.example");
+        assert_eq!(imported.form3.handle, "alice.new.example");
+        assert!(imported.form1.password.is_empty());
+        assert!(imported.form3.email.is_empty());
+        assert!(imported.form3.invite_code.is_empty());
+
+        let code = generate_transfer_code();
+        assert_eq!(code.len(), CODE_LEN + 1);
+        assert_eq!(normalize_code(&code).len(), CODE_LEN);
+    }
+
+    #[test]
+    fn test_code_characters_are_uniform() {
+        let mut counts = vec![0; CODE_ALPHABET.len()];
+        for byte in 0..=u8::MAX {
+            if let Some(c) = code_char(byte) {
+                let index = CODE_ALPHABET.iter().position(|a| *a as char == c).unwrap();
+                counts[index] += 1;
+            }
+        }
+        assert!(counts.iter().all(|count| *count == counts[0]));
+        assert_eq!(code_char(u8::MAX), None);
+    }
+}
@@ -0,0 +1,94 @@
+//! Following the old PDS when it moves mid-migration
+//!
+//! The old session records the PDS endpoint resolved at login. If the account's
+//! host moves while a migration is running (a new domain, a DNS change, a
+//! provider consolidating servers), every request to that endpoint fails to
+//! connect and the retry budget is spent on a host that will never answer.
+//! When a step fails that way, the orchestrator asks the DID document where
+//! the account lives now; if the endpoint changed, the old session is pointed
+//! at it, saved, and the step runs once more against the new host.
+
+use serde::{Deserialize, Serialize};
+
+use crate::console_warn;
+use crate::migration::storage::LocalStorageManager;
+use crate::services::client::{OldPds, PdsApi};
+use crate::utils::handle::normalize_pds_url;
+
+/// The old PDS endpoint before and after re-resolving the DID document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SourceEndpointChange {
+    pub from: String,
+    pub to: String,
+}
+
+/// Whether a step error means the old PDS couldn't be reached at all, as
+/// opposed to answering with an error
+pub fn is_connection_failure(error: &str) -> bool {
+    const MARKERS: [&str; 7] = [
+        "Failed to fetch",
+        "error sending request",
+        "NetworkError when attempting to fetch",
+        "dns error",
+        "Connection refused",
+        "connection reset",
+        "Load failed",
+    ];
+    // "Failed to fetch" also prefixes HTTP errors from our own messages
+    !error.contains("HTTP error") && MARKERS.iter().any(|marker| error.contains(marker))
+}
+
+/// The new endpoint if the DID document no longer lists the one `old` uses
+pub async fn relocated_source(old: &OldPds<&dyn PdsApi>) -> Option<SourceEndpointChange> {
+    let resolved = match old.client().resolve_pds_endpoint(old.did()).await {
+        Ok(endpoint) => normalize_pds_url(&endpoint),
+        Err(e) => {
+            console_warn!(
+                "[Migration] Could not re-resolve the old PDS for {}: {}",
+                old.did(),
+                e
+            );
+            return None;
+        }
+    };
+    let current = normalize_pds_url(old.host());
+    (!resolved.is_empty() && resolved != current).then_some(SourceEndpointChange {
+        from: current,
+        to: resolved,
+    })
+}
+
+/// Point the stored old session at the new endpoint so later phases and a
+/// resumed migration use it too
+pub fn save_source_endpoint(change: &SourceEndpointChange) {
+    match LocalStorageManager::get_old_session() {
+        Ok(mut session) => {
+            session.pds = change.to.clone();
+            if let Err(e) = LocalStorageManager::store_old_session(&session) {
+                console_warn!("[Migration] Could not save the moved old PDS: {}", e);
+            }
+        }
+        Err(e) => console_warn!("[Migration] No stored old session to update: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connection_failures() {
+        assert!(is_connection_failure(
+            "Failed to fetch repo stream: error sending request for url (https://old.example/xrpc/com.atproto.sync.getRepo)"
+        ));
+        assert!(is_connection_failure(
+            "Network error: TypeError: NetworkError when attempting to fetch resource."
+        ));
+        assert!(!is_connection_failure(
+            "Failed to fetch blob stream: HTTP error: 404"
+        ));
+        assert!(!is_connection_failure(
+            "Repository import failed: RepoTooLarge"
+        ));
+    }
+}
@@ -1,23 +1,60 @@
 //! Blob migration step using streaming architecture
 
+use async_trait::async_trait;
+
+use crate::migration::blob_manifest::{begin_blob_manifest, last_blob_manifest};
+use crate::migration::plan::PlanStep;
+use crate::migration::progress::{LargeBlobTracker, ThrottledDispatch};
+use crate::migration::steps::blob_enumeration::plan_blob_enumeration;
+use crate::migration::steps::traits::{MigrationStep, StepContext};
+use crate::migration::unrecoverable_blobs::{
+    is_source_not_found, record_unrecoverable_blobs, unrecoverable_blobs,
+};
+use crate::migration::upload_log::{begin_upload_log, flush_upload_log, observe_upload};
+use crate::services::client::bandwidth_probe::estimate_transfer_secs;
 #[cfg(feature = "web")]
-use crate::services::client::{ClientSessionCredentials, PdsClient, RefreshableSessionProvider};
+use crate::services::client::{
+    last_bandwidth_report, last_route_report, NewPds, OldPds, PdsClient, RefreshableSessionProvider,
+};
+use crate::services::config::{get_global_config, BlobNotFoundPolicy, RetryPhase};
 use crate::services::streaming::{
-    BlobSource, BlobTarget, BufferedStorage, DataSource, DataTarget, ProgressEvent, ProgressPhase,
-    ProgressUpdate, SyncOrchestrator,
+    detect_storage_capabilities, sample_storage_usage, BlobCache, BlobSource, BlobTarget,
+    DataSource, DiskStorage, LocalStorageCache, MinimalFlowLimits, ProgressEvent, ProgressPhase,
+    ProgressUpdate, SelectedStorage, StorageUsageLevel, StorageUsageMonitor, SyncOrchestrator,
+    ESTIMATED_BYTES_PER_BLOB,
 };
+use crate::utils::console_macros::ConsoleLevel;
+use crate::utils::platform::sleep_ms;
+use crate::utils::time_format::humanize_duration;
 use crate::{console_error, console_info, console_warn};
 use dioxus::prelude::*;
+use std::cell::Cell;
+use std::rc::Rc;
 use std::sync::Arc;
 
+/// Interval between origin storage usage samples while blobs stream
+const STORAGE_USAGE_POLL_MS: u64 = 2000;
+
+/// Time left for the remaining blobs at the preflight rate, sized by the blobs done so far
+fn blob_eta_secs(completed: u32, total: u32, processed_bytes: u64, rate: f64) -> Option<u64> {
+    let average = if completed > 0 && processed_bytes > 0 {
+        processed_bytes / completed as u64
+    } else {
+        ESTIMATED_BYTES_PER_BLOB
+    };
+    let remaining = total.saturating_sub(completed) as u64;
+    estimate_transfer_secs(remaining.saturating_mul(average), rate)
+}
+
 use crate::migration::types::*;
 
-pub async fn execute_streaming_blob_migration(
-    old_session: &ClientSessionCredentials,
-    new_session: &ClientSessionCredentials,
+pub async fn execute_streaming_blob_migration<C>(
+    old: &OldPds<C>,
+    new: &NewPds<C>,
     dispatch: &EventHandler<MigrationAction>,
     state: &MigrationState,
 ) -> Result<(), String> {
+    let (old_session, new_session) = (old.session(), new.session());
     console_info!("[Migration] Starting blob migration using streaming architecture");
 
     // UPDATE UI IMMEDIATELY before any async operations
@@ -26,7 +63,12 @@ pub async fn execute_streaming_blob_migration(
     ));
 
     // Create WASM streaming orchestrator
-    let orchestrator = SyncOrchestrator::new();
+    let orchestrator = SyncOrchestrator::with_retry(
+        state.retry_config(),
+        RetryPhase::BlobDownload,
+        RetryPhase::BlobUpload,
+    )
+    .with_hosts(&old_session.pds, &new_session.pds);
 
     // Create PdsClient for session refresh
     let pds_client = Arc::new(PdsClient::new());
@@ -36,8 +78,16 @@ pub async fn execute_streaming_blob_migration(
         RefreshableSessionProvider::new(new_session.clone(), Arc::clone(&pds_client));
 
     // Create source and target using WASM clients
-    let source = BlobSource::new(old_session);
-    let target = BlobTarget::new(new_session_provider);
+    // Fastest-first origins from preflight; the source PDS is always a fallback
+    let source = match last_route_report() {
+        Some(report) if !report.blob_source_order.is_empty() => {
+            BlobSource::with_origins(old_session, report.blob_source_order)
+        }
+        _ => BlobSource::new(old_session),
+    };
+    begin_blob_manifest(&new_session.did, &new_session.pds);
+    begin_upload_log(&new_session.did);
+    let target = BlobTarget::new(new_session_provider).with_upload_observer(observe_upload);
 
     // Show progress during source listing
     dispatch.call(MigrationAction::SetMigrationStep(
@@ -66,25 +116,89 @@ pub async fn execute_streaming_blob_migration(
         source_items.len()
     )));
 
-    let missing_items = target
-        .list_missing()
-        .await
-        .map_err(|e| format!("Failed to list missing blobs: {}", e))?;
+    let requested_method = state.blob_enumeration_method();
+    let mut plan = plan_blob_enumeration(
+        &requested_method,
+        old_session,
+        new_session,
+        &source_items,
+        &target,
+    )
+    .await?;
+    dispatch.call(MigrationAction::SetBlobEnumeration(Some(
+        plan.summary(requested_method, source_items.len()),
+    )));
+
+    let not_found_policy = state.blob_not_found_policy();
+    if not_found_policy == BlobNotFoundPolicy::SkipAndRecord {
+        // Blobs an earlier pass found missing from the source would only 404 again
+        let known_lost = unrecoverable_blobs(&old_session.did);
+        let before = plan.cids.len();
+        plan.cids.retain(|cid| !known_lost.contains(cid));
+        if plan.cids.len() < before {
+            console_info!(
+                "[Migration] Skipping {} blobs the source PDS is known not to have",
+                before - plan.cids.len()
+            );
+        }
+    }
+
+    if plan.cids.is_empty() {
+        console_info!("[Migration] Target already holds every source blob, skipping blob phase");
+        dispatch.call(MigrationAction::SetMigrationStep(
+            "All blobs already present on new PDS - skipping blob migration".to_string(),
+        ));
+        return Ok(());
+    }
 
     // Calculate the actual number of blobs that will be processed
-    let initial_total_blobs = if missing_items.is_empty() {
-        source_items.len()
+    let initial_total_blobs = plan.cids.len() as u32;
+
+    console_info!(
+        "[Migration] Pre-fetched blob counts: {} source blobs, {} will be processed",
+        source_items.len(),
+        initial_total_blobs
+    );
+
+    // Blobs are cached by CID so retries and other accounts can reuse them
+    let capabilities = detect_storage_capabilities().await;
+    let storage = if capabilities.requires_minimal_flow() {
+        console_warn!(
+            "[Migration] No OPFS or IndexedDB; using the minimal localStorage blob cache"
+        );
+        SelectedStorage::Minimal(LocalStorageCache::new(MinimalFlowLimits::default()))
+    } else if capabilities.disk {
+        SelectedStorage::Disk(
+            DiskStorage::open("blobs")
+                .map_err(|e| format!("Failed to create blob storage: {}", e))?,
+        )
     } else {
-        missing_items.len() // Use missing items count if available
-    } as u32;
-
-    console_info!("[Migration] Pre-fetched blob counts: {} source blobs, {} missing blobs, {} will be processed", 
-        source_items.len(), missing_items.len(), initial_total_blobs);
+        SelectedStorage::Full(
+            BlobCache::open()
+                .await
+                .map_err(|e| format!("Failed to create blob storage: {}", e))?,
+        )
+    };
 
-    // Initialize WASM storage backend
-    let storage = BufferedStorage::new(format!("blobs/{}", old_session.did))
-        .await
-        .map_err(|e| format!("Failed to create blob storage: {}", e))?;
+    // Download the next blob while the current one uploads, and tune how many
+    // run at once to the target, except in the minimal flow where memory is
+    // the only storage
+    let orchestrator = match storage {
+        SelectedStorage::Full(_) | SelectedStorage::Disk(_) => {
+            let config = get_global_config();
+            let concurrency = config.concurrency;
+            orchestrator
+                .with_prefetch(config.blob.prefetch_max_bytes)
+                .with_upload_tuning(
+                    concurrency.initial_upload_concurrency,
+                    concurrency
+                        .max_concurrent_transfers
+                        .min(concurrency.opfs_concurrency),
+                    concurrency.upload_error_threshold,
+                )
+        }
+        SelectedStorage::Minimal(_) => orchestrator,
+    };
 
     // Update initial progress
     let mut migration_progress = state.migration_progress.clone();
@@ -99,13 +213,22 @@ pub async fn execute_streaming_blob_migration(
         "Streaming blobs with channel-tee pattern...".to_string(),
     ));
 
+    // Per-chunk progress is coalesced so fast transfers don't flood the renderer
+    let handler = *dispatch;
+    let throttled = ThrottledDispatch::new(
+        move |action| handler.call(action),
+        get_global_config().progress.max_updates_per_second,
+    );
+
     // Create simple progress callback like in working commit 065e5938
     let progress_callback = {
-        let dispatch_clone = *dispatch;
+        let throttled = throttled.clone();
         let mut completed_blobs: u32 = 0;
         let mut total_bytes: u64 = 0;
         let mut processed_bytes: u64 = 0;
-        let mut last_ui_update_time: Option<u64> = None;
+        let mut large_blobs =
+            LargeBlobTracker::new(get_global_config().progress.large_blob_threshold_bytes);
+        let preflight_rate = last_bandwidth_report().and_then(|r| r.bottleneck_bytes_per_sec());
 
         console_info!(
             "[DEBUG Dynamic Total] Initial total set from pre-fetch: {}",
@@ -113,8 +236,6 @@ pub async fn execute_streaming_blob_migration(
         );
 
         move |progress_update: ProgressUpdate| {
-            let current_time = js_sys::Date::now() as u64;
-
             // DEBUG: Log all progress events to understand what we're receiving
             crate::console_info!(
                 "[PROGRESS-DEBUG] Received event: phase={:?}, event={:?}, item_id={:?}, bytes={}",
@@ -173,106 +294,92 @@ pub async fn execute_streaming_blob_migration(
                 }
             }
 
-            // Throttle UI updates to prevent overwhelming the render loop (reduced to 50ms for responsiveness)
-            let should_update_ui = match last_ui_update_time {
-                Some(last_time) => current_time - last_time >= 50,
-                None => true,
-            };
-
-            // Force immediate UI update when blobs complete for better responsiveness
-            let force_update_on_completion = matches!(
-                (&progress_update.phase, &progress_update.event),
-                (ProgressPhase::Completing, ProgressEvent::Completed)
-                    | (ProgressPhase::Uploading, ProgressEvent::Completed)
-            );
-
-            if should_update_ui || force_update_on_completion {
-                // Create simple blob progress like commit 065e5938
-                let blob_progress = BlobProgress {
-                    total_blobs: initial_total_blobs,
-                    processed_blobs: completed_blobs,
-                    total_bytes,
-                    processed_bytes,
-                    current_blob_cid: progress_update.item_id.clone(),
-                    current_blob_progress: if progress_update.total_bytes_estimate > 0 {
-                        Some(
-                            (progress_update.bytes_processed as f64
-                                / progress_update.total_bytes_estimate as f64)
-                                * 100.0,
-                        )
-                    } else {
-                        None
-                    },
-                    error: None,
-                };
+            if large_blobs.observe(&progress_update) {
+                throttled.dispatch(MigrationAction::SetLargeBlobTransfers(
+                    large_blobs.transfers().to_vec(),
+                ));
+            }
 
-                // Simple debug logging
-                let progress_percentage = if blob_progress.total_blobs > 0 {
-                    (blob_progress.processed_blobs as f64 / blob_progress.total_blobs as f64)
-                        * 100.0
+            // Create simple blob progress like commit 065e5938
+            let blob_progress = BlobProgress {
+                total_blobs: initial_total_blobs,
+                processed_blobs: completed_blobs,
+                total_bytes,
+                processed_bytes,
+                current_blob_cid: progress_update.item_id.clone(),
+                current_blob_progress: if progress_update.total_bytes_estimate > 0 {
+                    Some(
+                        (progress_update.bytes_processed as f64
+                            / progress_update.total_bytes_estimate as f64)
+                            * 100.0,
+                    )
                 } else {
-                    0.0
-                };
-
-                crate::console_info!(
-                    "[DEBUG SetBlobProgress] Dispatching: {}/{} blobs ({:.1}%)",
-                    blob_progress.processed_blobs,
-                    blob_progress.total_blobs,
-                    progress_percentage
-                );
-
-                // Dispatch simple progress update
-                dispatch_clone.call(MigrationAction::SetBlobProgress(blob_progress));
-
-                // Only update timestamp for regular updates, not forced updates
-                if !force_update_on_completion {
-                    last_ui_update_time = Some(current_time);
-                }
-
-                // Enhanced migration step messages with completion indicators
-                if let Some(ref cid) = progress_update.item_id {
-                    let step_message = match (&progress_update.phase, &progress_update.event) {
-                        (ProgressPhase::Completing, ProgressEvent::Completed)
-                        | (ProgressPhase::Uploading, ProgressEvent::Completed) => {
-                            if completed_blobs >= initial_total_blobs {
-                                "✅ All blobs completed successfully!".to_string()
-                            } else {
-                                format!(
-                                    "✅ Completed blob {} ({}/{} blobs)",
-                                    cid.chars().take(12).collect::<String>() + "...",
-                                    completed_blobs,
-                                    initial_total_blobs
-                                )
-                            }
-                        }
-                        (phase, _) => {
-                            let phase_text = match phase {
-                                ProgressPhase::Starting => "Starting",
-                                ProgressPhase::Downloading => "Downloading",
-                                ProgressPhase::Uploading => "Uploading",
-                                ProgressPhase::Completing => "Completing",
-                            };
+                    None
+                },
+                error: None,
+            };
 
+            // Dispatch simple progress update
+            throttled.dispatch(MigrationAction::SetBlobProgress(blob_progress));
+
+            // Enhanced migration step messages with completion indicators
+            if let Some(ref cid) = progress_update.item_id {
+                let eta = preflight_rate
+                    .and_then(|rate| {
+                        blob_eta_secs(completed_blobs, initial_total_blobs, processed_bytes, rate)
+                    })
+                    .filter(|secs| *secs > 0)
+                    .map(|secs| format!(", {} left", humanize_duration(secs)))
+                    .unwrap_or_default();
+                let step_message = match (&progress_update.phase, &progress_update.event) {
+                    (ProgressPhase::Completing, ProgressEvent::Completed)
+                    | (ProgressPhase::Uploading, ProgressEvent::Completed) => {
+                        if completed_blobs >= initial_total_blobs {
+                            "✅ All blobs completed successfully!".to_string()
+                        } else {
                             format!(
-                                "{} blob {} ({}/{} blobs)",
-                                phase_text,
+                                "✅ Completed blob {} ({}/{} blobs{})",
                                 cid.chars().take(12).collect::<String>() + "...",
                                 completed_blobs,
-                                initial_total_blobs
+                                initial_total_blobs,
+                                eta
                             )
                         }
-                    };
+                    }
+                    (phase, _) => {
+                        let phase_text = match phase {
+                            ProgressPhase::Starting => "Starting",
+                            ProgressPhase::Downloading => "Downloading",
+                            ProgressPhase::Uploading => "Uploading",
+                            ProgressPhase::Completing => "Completing",
+                        };
+
+                        format!(
+                            "{} blob {} ({}/{} blobs{})",
+                            phase_text,
+                            cid.chars().take(12).collect::<String>() + "...",
+                            completed_blobs,
+                            initial_total_blobs,
+                            eta
+                        )
+                    }
+                };
 
-                    dispatch_clone.call(MigrationAction::SetMigrationStep(step_message));
-                }
+                throttled.dispatch(MigrationAction::SetMigrationStep(step_message));
             }
         }
     };
 
-    match orchestrator
-        .sync_with_tee(source, target, storage, Some(progress_callback))
-        .await
-    {
+    let storage_poll_active = spawn_storage_usage_poller(*dispatch);
+
+    let sync_result = orchestrator
+        .sync_items_with_tee(source, target, storage, plan.cids, Some(progress_callback))
+        .await;
+    storage_poll_active.set(false);
+    throttled.dispatch(MigrationAction::SetLargeBlobTransfers(Vec::new()));
+    throttled.flush();
+
+    match sync_result {
         Ok(result) => {
             console_info!(
                 "[Migration] Streaming blob migration completed successfully: {}/{} items, {} bytes processed",
@@ -310,6 +417,10 @@ pub async fn execute_streaming_blob_migration(
             );
 
             dispatch.call(MigrationAction::SetBlobProgress(final_blob_progress));
+            flush_upload_log().await;
+            dispatch.call(MigrationAction::SetBlobManifest(
+                last_blob_manifest().map(|manifest| manifest.info()),
+            ));
 
             dispatch.call(MigrationAction::SetMigrationStep(
                 "Blob streaming migration completed successfully".to_string(),
@@ -329,11 +440,36 @@ pub async fn execute_streaming_blob_migration(
                 }
             }
 
+            let lost: Vec<String> = result
+                .failed_items
+                .iter()
+                .filter(|failure| is_source_not_found(&failure.error))
+                .map(|failure| failure.item_id.clone())
+                .collect();
+            if !lost.is_empty() {
+                let lost_count = lost.len();
+                let first = lost[0].clone();
+                dispatch.call(MigrationAction::SetUnrecoverableBlobs(
+                    record_unrecoverable_blobs(&old_session.did, lost),
+                ));
+                if not_found_policy == BlobNotFoundPolicy::Fail {
+                    return Err(format!(
+                        "The old PDS no longer has {} blobs that your records still reference (first: {}). Choose \"Skip and list them\" under Advanced options to migrate without them.",
+                        lost_count, first
+                    ));
+                }
+                console_warn!(
+                    "[Migration] {} blobs are missing from the source PDS and were skipped",
+                    lost_count
+                );
+            }
+
             Ok(())
         }
         Err(e) => {
             let error_msg = format!("Streaming blob migration failed: {}", e);
             console_error!("[Migration] {}", error_msg);
+            flush_upload_log().await;
 
             // Update progress with error
             let mut migration_progress = state.migration_progress.clone();
@@ -344,3 +480,72 @@ pub async fn execute_streaming_blob_migration(
         }
     }
 }
+
+/// Sample origin storage usage until the returned flag is cleared
+///
+/// Level changes are reported through `StorageUsageMonitor` hooks; this is where strategy
+/// adjustments for storage pressure belong.
+fn spawn_storage_usage_poller(dispatch: EventHandler<MigrationAction>) -> Rc<Cell<bool>> {
+    let active = Rc::new(Cell::new(true));
+    let poll_active = Rc::clone(&active);
+
+    spawn(async move {
+        let mut monitor = StorageUsageMonitor::new().on_level_change(move |usage, level| {
+            if let Some(message) = level.message() {
+                console_warn!(
+                    "[Migration] Storage usage at {:.0}%: {}",
+                    usage.usage_ratio() * 100.0,
+                    message
+                );
+                dispatch.call(MigrationAction::AddConsoleMessage(ConsoleEntry::new(
+                    ConsoleLevel::Warn,
+                    module_path!(),
+                    message,
+                )));
+            }
+            if level == StorageUsageLevel::Critical {
+                console_error!(
+                    "[Migration] Origin storage nearly exhausted ({} of {} bytes)",
+                    usage.origin_usage,
+                    usage.origin_quota
+                );
+            }
+        });
+
+        while poll_active.get() {
+            match sample_storage_usage().await {
+                Some(usage) => {
+                    monitor.observe(&usage);
+                    dispatch.call(MigrationAction::SetStorageUsage(Some(usage)));
+                }
+                None => {
+                    console_info!("[Migration] StorageManager unavailable, storage meter disabled");
+                    break;
+                }
+            }
+            sleep_ms(STORAGE_USAGE_POLL_MS).await;
+        }
+    });
+
+    active
+}
+
+/// Copies blobs from the old PDS to the new one
+pub struct BlobStep;
+
+#[async_trait(?Send)]
+impl MigrationStep for BlobStep {
+    fn plan_step(&self) -> PlanStep {
+        PlanStep::Blobs
+    }
+
+    fn describe(&self) -> &'static str {
+        "Copy blobs to the new PDS"
+    }
+
+    async fn execute(&self, ctx: &mut StepContext<'_>) -> Result<(), String> {
+        let target = ctx.target(self.plan_step())?;
+        let handler = ctx.handler(self.plan_step())?;
+        execute_streaming_blob_migration(&ctx.old, target, &handler, ctx.state).await
+    }
+}
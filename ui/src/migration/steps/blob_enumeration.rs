@@ -0,0 +1,283 @@
+//! Per-migration choice between listMissingBlobs and a full sync.listBlobs diff
+//!
+//! `com.atproto.repo.listMissingBlobs` is the cheap path, but it only reflects
+//! reality when the target created the account with the source DID and has
+//! finished indexing the imported repository. When the target can't answer,
+//! or answers "nothing missing" while holding fewer blobs than the source, the
+//! source and target `sync.listBlobs` inventories are diffed instead. Targets
+//! whose software is known to get listMissingBlobs wrong go straight to the diff.
+//! What each listing returned is kept as a [`BlobEnumerationSummary`], so a
+//! count that disagrees with goat's full enumeration can be traced to its source.
+
+#[cfg(feature = "web")]
+use crate::services::client::{
+    last_target_health, ClientSessionCredentials, PdsClient, Workaround,
+};
+use crate::services::config::BlobEnumerationMethod;
+use crate::services::streaming::{BlobTarget, DataTarget};
+use crate::{console_info, console_warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Blobs to transfer and how they were chosen
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlobEnumerationPlan {
+    /// The method actually used, never [`BlobEnumerationMethod::Auto`]
+    pub method: BlobEnumerationMethod,
+    pub reason: String,
+    pub cids: Vec<String>,
+    /// Size of the target's listMissingBlobs answer, if it was asked
+    pub target_missing_count: Option<usize>,
+    /// Size of the target's sync.listBlobs inventory, if it was listed
+    pub target_inventory_count: Option<usize>,
+}
+
+/// Which method decided the blob transfer and what each listing returned
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlobEnumerationSummary {
+    /// The method chosen by the user or configuration
+    pub requested: BlobEnumerationMethod,
+    /// The method actually used
+    pub method: BlobEnumerationMethod,
+    pub reason: String,
+    /// Blobs listed by the source's sync.listBlobs
+    pub source_count: usize,
+    pub target_missing_count: Option<usize>,
+    pub target_inventory_count: Option<usize>,
+    pub to_transfer: usize,
+}
+
+impl BlobEnumerationPlan {
+    pub fn summary(
+        &self,
+        requested: BlobEnumerationMethod,
+        source_count: usize,
+    ) -> BlobEnumerationSummary {
+        BlobEnumerationSummary {
+            requested,
+            method: self.method,
+            reason: self.reason.clone(),
+            source_count,
+            target_missing_count: self.target_missing_count,
+            target_inventory_count: self.target_inventory_count,
+            to_transfer: self.cids.len(),
+        }
+    }
+}
+
+/// What to do with the target's listMissingBlobs answer
+#[derive(Debug, Clone, PartialEq)]
+pub enum MissingViewVerdict {
+    /// Transfer exactly the listed blobs
+    Use(Vec<String>),
+    /// The answer may be stale; confirm against the target's own inventory
+    Verify,
+    /// Ignore the answer and diff full inventories
+    FullDiff(String),
+}
+
+/// Judge a listMissingBlobs response given the configured method
+pub fn assess_missing_view(
+    configured: &BlobEnumerationMethod,
+    source_cids: &[String],
+    missing: Result<Vec<String>, String>,
+) -> MissingViewVerdict {
+    let missing = match missing {
+        Ok(missing) => missing,
+        Err(e) => {
+            return MissingViewVerdict::FullDiff(format!("target listMissingBlobs failed ({})", e))
+        }
+    };
+
+    if !missing.is_empty() || source_cids.is_empty() {
+        return MissingViewVerdict::Use(intersect_in_source_order(source_cids, &missing));
+    }
+
+    match configured {
+        BlobEnumerationMethod::MissingBlobs => MissingViewVerdict::Use(Vec::new()),
+        _ => MissingViewVerdict::Verify,
+    }
+}
+
+/// Source CIDs the target does not hold, in source order
+pub fn diff_blob_lists(source_cids: &[String], target_cids: &[String]) -> Vec<String> {
+    let present: HashSet<&str> = target_cids.iter().map(String::as_str).collect();
+    source_cids
+        .iter()
+        .filter(|cid| !present.contains(cid.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// Missing CIDs that the source can actually serve, in source order
+pub fn intersect_in_source_order(source_cids: &[String], missing: &[String]) -> Vec<String> {
+    let wanted: HashSet<&str> = missing.iter().map(String::as_str).collect();
+    source_cids
+        .iter()
+        .filter(|cid| wanted.contains(cid.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// Pick the enumeration method for this source/target pairing and list the blobs to move
+#[cfg(feature = "web")]
+pub async fn plan_blob_enumeration(
+    configured: &BlobEnumerationMethod,
+    old_session: &ClientSessionCredentials,
+    new_session: &ClientSessionCredentials,
+    source_cids: &[String],
+    target: &BlobTarget,
+) -> Result<BlobEnumerationPlan, String> {
+    let mut target_missing_count = None;
+    let fallback_reason = if *configured == BlobEnumerationMethod::SyncListBlobs {
+        "configured to always use sync.listBlobs".to_string()
+    } else if let Some(software) =
+        last_target_health()
+            .map(|report| report.software)
+            .filter(|software| {
+                *configured == BlobEnumerationMethod::Auto
+                    && software.needs(Workaround::FullBlobDiff)
+            })
+    {
+        format!("listMissingBlobs is not trusted on {}", software.describe())
+    } else if old_session.did != new_session.did {
+        format!(
+            "target account {} was not created with source DID {}",
+            new_session.did, old_session.did
+        )
+    } else {
+        let missing = target.list_missing().await.map_err(|e| e.to_string());
+        target_missing_count = missing.as_ref().ok().map(Vec::len);
+        match assess_missing_view(configured, source_cids, missing) {
+            MissingViewVerdict::Use(cids) => {
+                let plan = BlobEnumerationPlan {
+                    method: BlobEnumerationMethod::MissingBlobs,
+                    reason: "target reported its missing blobs".to_string(),
+                    cids,
+                    target_missing_count,
+                    target_inventory_count: None,
+                };
+                log_plan(&plan, source_cids.len());
+                return Ok(plan);
+            }
+            MissingViewVerdict::Verify => {
+                "target reported no missing blobs; confirming against its blob inventory"
+                    .to_string()
+            }
+            MissingViewVerdict::FullDiff(reason) => reason,
+        }
+    };
+
+    let target_cids: Vec<String> = PdsClient::new()
+        .list_all_target_blobs(new_session, &new_session.did)
+        .await
+        .map_err(|e| format!("Failed to list target blobs: {}", e))?
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+
+    let plan = BlobEnumerationPlan {
+        method: BlobEnumerationMethod::SyncListBlobs,
+        reason: fallback_reason,
+        cids: diff_blob_lists(source_cids, &target_cids),
+        target_missing_count,
+        target_inventory_count: Some(target_cids.len()),
+    };
+    log_plan(&plan, source_cids.len());
+    Ok(plan)
+}
+
+fn log_plan(plan: &BlobEnumerationPlan, source_count: usize) {
+    match plan.method {
+        BlobEnumerationMethod::SyncListBlobs => console_warn!(
+            "[Migration] Blob enumeration via sync.listBlobs diff: {} of {} blobs to transfer ({})",
+            plan.cids.len(),
+            source_count,
+            plan.reason
+        ),
+        _ => console_info!(
+            "[Migration] Blob enumeration via listMissingBlobs: {} of {} blobs to transfer ({})",
+            plan.cids.len(),
+            source_count,
+            plan.reason
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cids(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_missing_view_used_when_it_lists_blobs() {
+        let source = cids(&["a", "b", "c"]);
+        let verdict = assess_missing_view(
+            &BlobEnumerationMethod::Auto,
+            &source,
+            Ok(cids(&["c", "x", "a"])),
+        );
+        assert_eq!(verdict, MissingViewVerdict::Use(cids(&["a", "c"])));
+    }
+
+    #[test]
+    fn test_empty_missing_view_is_verified_unless_forced() {
+        let source = cids(&["a", "b"]);
+        assert_eq!(
+            assess_missing_view(&BlobEnumerationMethod::Auto, &source, Ok(Vec::new())),
+            MissingViewVerdict::Verify
+        );
+        assert_eq!(
+            assess_missing_view(
+                &BlobEnumerationMethod::MissingBlobs,
+                &source,
+                Ok(Vec::new())
+            ),
+            MissingViewVerdict::Use(Vec::new())
+        );
+        assert_eq!(
+            assess_missing_view(&BlobEnumerationMethod::Auto, &[], Ok(Vec::new())),
+            MissingViewVerdict::Use(Vec::new())
+        );
+    }
+
+    #[test]
+    fn test_unsupported_missing_view_falls_back_to_diff() {
+        let verdict = assess_missing_view(
+            &BlobEnumerationMethod::MissingBlobs,
+            &cids(&["a"]),
+            Err("HTTP 501".to_string()),
+        );
+        assert!(matches!(verdict, MissingViewVerdict::FullDiff(reason) if reason.contains("501")));
+    }
+
+    #[test]
+    fn test_diff_blob_lists_keeps_source_order() {
+        let source = cids(&["a", "b", "c", "d"]);
+        let target = cids(&["d", "b", "z"]);
+        assert_eq!(diff_blob_lists(&source, &target), cids(&["a", "c"]));
+    }
+
+    #[test]
+    fn test_summary_records_requested_and_used_methods() {
+        let plan = BlobEnumerationPlan {
+            method: BlobEnumerationMethod::SyncListBlobs,
+            reason: "target reported no missing blobs".to_string(),
+            cids: cids(&["a"]),
+            target_missing_count: Some(0),
+            target_inventory_count: Some(3),
+        };
+        let summary = plan.summary(BlobEnumerationMethod::MissingBlobs, 4);
+        assert_eq!(summary.requested, BlobEnumerationMethod::MissingBlobs);
+        assert_eq!(summary.method, BlobEnumerationMethod::SyncListBlobs);
+        assert_eq!(summary.source_count, 4);
+        assert_eq!(summary.to_transfer, 1);
+
+        for method in BlobEnumerationMethod::ALL {
+            assert_eq!(BlobEnumerationMethod::parse(method.as_str()), Some(method));
+        }
+    }
+}
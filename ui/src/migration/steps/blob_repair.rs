@@ -0,0 +1,183 @@
+//! Upload-only blob repair fed from the local blob cache
+//!
+//! For when the source PDS has already shut down: the target is asked which
+//! blobs it is missing and every one of those found in the [`BlobCache`] is
+//! uploaded. Nothing is downloaded from the source.
+
+use crate::migration::blob_manifest::{begin_blob_manifest, last_blob_manifest};
+use crate::migration::steps::blob_enumeration::intersect_in_source_order;
+use crate::migration::types::*;
+use crate::migration::upload_log::{begin_upload_log, flush_upload_log, observe_upload};
+use crate::services::client::{ClientSessionCredentials, PdsClient, RefreshableSessionProvider};
+use crate::services::config::{RetryConfig, RetryPhase};
+use crate::services::streaming::{
+    BlobCache, BlobTarget, CacheOnlySource, DataTarget, ProgressEvent, ProgressPhase,
+    ProgressUpdate, SyncOrchestrator,
+};
+use crate::{console_error, console_info, console_warn};
+use dioxus::prelude::*;
+use std::sync::Arc;
+
+/// Outcome of an upload-only repair pass
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheRepairSummary {
+    pub missing_on_target: usize,
+    pub uploaded: u32,
+    pub failed: usize,
+    /// Missing blobs that were never cached locally and can't be repaired this way
+    pub not_cached: usize,
+}
+
+/// Run the repair from the UI, reporting through the usual migration actions
+pub async fn execute_cache_repair_client_side(
+    state: MigrationState,
+    dispatch: EventHandler<MigrationAction>,
+) {
+    let Some(new_session) = state.new_pds_session.as_ref() else {
+        dispatch.call(MigrationAction::SetMigrationError(Some(
+            "Missing new PDS session credentials".to_string(),
+        )));
+        return;
+    };
+    let new_session: ClientSessionCredentials = new_session.into();
+
+    dispatch.call(MigrationAction::SetMigrating(true));
+    dispatch.call(MigrationAction::SetMigrationError(None));
+
+    let result = repair_blobs_from_cache(&new_session, &dispatch, state.retry_config()).await;
+    dispatch.call(MigrationAction::SetMigrating(false));
+
+    match result {
+        Ok(summary) if summary.failed == 0 && summary.not_cached == 0 => {
+            dispatch.call(MigrationAction::SetMigrationStep(format!(
+                "Repair complete: uploaded {} cached blobs",
+                summary.uploaded
+            )));
+        }
+        Ok(summary) => {
+            dispatch.call(MigrationAction::SetMigrationError(Some(format!(
+                "Uploaded {} cached blobs; {} failed to upload and {} are not cached locally",
+                summary.uploaded, summary.failed, summary.not_cached
+            ))));
+        }
+        Err(e) => {
+            console_error!("[Repair] Upload-only repair failed: {}", e);
+            dispatch.call(MigrationAction::SetMigrationError(Some(e)));
+        }
+    }
+}
+
+/// Upload every cached blob the target reports missing
+pub async fn repair_blobs_from_cache(
+    new_session: &ClientSessionCredentials,
+    dispatch: &EventHandler<MigrationAction>,
+    retry: RetryConfig,
+) -> Result<CacheRepairSummary, String> {
+    console_info!("[Repair] Starting upload-only blob repair from local cache");
+    dispatch.call(MigrationAction::SetMigrationStep(
+        "Checking which blobs the new PDS is missing...".to_string(),
+    ));
+
+    let pds_client = Arc::new(PdsClient::new());
+    begin_blob_manifest(&new_session.did, &new_session.pds);
+    begin_upload_log(&new_session.did);
+    let target = BlobTarget::new(RefreshableSessionProvider::new(
+        new_session.clone(),
+        pds_client,
+    ))
+    .with_upload_observer(observe_upload);
+
+    let missing = target
+        .list_missing()
+        .await
+        .map_err(|e| format!("Failed to list missing blobs: {}", e))?;
+
+    let cache = BlobCache::open()
+        .await
+        .map_err(|e| format!("Failed to open blob cache: {}", e))?;
+    let cached = cache.cached_cids().await?;
+
+    let items = intersect_in_source_order(&missing, &cached);
+    let not_cached = missing.len() - items.len();
+    console_info!(
+        "[Repair] Target is missing {} blobs; {} are cached locally, {} are not",
+        missing.len(),
+        items.len(),
+        not_cached
+    );
+    if not_cached > 0 {
+        console_warn!(
+            "[Repair] {} missing blobs were never cached and cannot be repaired without the source PDS",
+            not_cached
+        );
+    }
+
+    if items.is_empty() {
+        return Ok(CacheRepairSummary {
+            missing_on_target: missing.len(),
+            uploaded: 0,
+            failed: 0,
+            not_cached,
+        });
+    }
+
+    dispatch.call(MigrationAction::SetMigrationStep(format!(
+        "Uploading {} cached blobs to new PDS...",
+        items.len()
+    )));
+
+    let total_blobs = items.len() as u32;
+    let progress_callback = {
+        let dispatch = *dispatch;
+        let mut completed_blobs: u32 = 0;
+        let mut processed_bytes: u64 = 0;
+
+        move |update: ProgressUpdate| {
+            if update.phase != ProgressPhase::Completing || update.event != ProgressEvent::Completed
+            {
+                return;
+            }
+            completed_blobs = (completed_blobs + 1).min(total_blobs);
+            processed_bytes += update.bytes_processed;
+            dispatch.call(MigrationAction::SetBlobProgress(BlobProgress {
+                total_blobs,
+                processed_blobs: completed_blobs,
+                total_bytes: processed_bytes,
+                processed_bytes,
+                current_blob_cid: update.item_id,
+                current_blob_progress: None,
+                error: None,
+            }));
+        }
+    };
+
+    let result =
+        SyncOrchestrator::with_retry(retry, RetryPhase::BlobDownload, RetryPhase::BlobUpload)
+            .sync_items_with_tee(
+                CacheOnlySource,
+                target,
+                cache,
+                items,
+                Some(progress_callback),
+            )
+            .await
+            .map_err(|e| format!("Repair upload failed: {}", e))?;
+
+    console_info!(
+        "[Repair] Uploaded {}/{} cached blobs ({} bytes)",
+        result.successful_items,
+        result.total_items,
+        result.total_bytes_processed
+    );
+    flush_upload_log().await;
+    dispatch.call(MigrationAction::SetBlobManifest(
+        last_blob_manifest().map(|manifest| manifest.info()),
+    ));
+
+    Ok(CacheRepairSummary {
+        missing_on_target: missing.len(),
+        uploaded: result.successful_items,
+        failed: result.failed_items.len(),
+        not_cached,
+    })
+}
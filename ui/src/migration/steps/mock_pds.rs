@@ -0,0 +1,297 @@
+//! In-memory [`PdsApi`] for step unit tests
+
+use async_trait::async_trait;
+use cid::Cid;
+use serde_json::Value;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+use crate::services::client::{
+    ClientAppPassword, ClientBlobExportResponse, ClientBlobUploadResponse, ClientEmailStatus,
+    ClientError, ClientLoginResponse, ClientPlcRecommendationResponse, ClientPlcTokenResponse,
+    ClientPreferencesExportResponse, ClientPreferencesImportResponse, ClientRecord,
+    ClientRecordPage, ClientSessionCredentials, PdsApi,
+};
+
+/// Canned responses; unset ones answer with an error
+#[derive(Default)]
+pub struct MockPds {
+    /// Last body passed to `import_preferences`, returned by later exports
+    pub imported_preferences: RefCell<Option<String>>,
+    /// Accept imports without storing them
+    pub discard_imports: bool,
+    pub server_did: Option<String>,
+    /// Endpoint in the DID document; `None` fails resolution
+    pub did_pds_endpoint: Option<String>,
+    /// `None` answers like a PDS without getRecommendedDidCredentials
+    pub plc_recommendation: Option<String>,
+    pub plc_data: Option<Value>,
+    pub collections: Option<Vec<String>>,
+    /// Records by collection, listed one per page
+    pub records: HashMap<String, Vec<ClientRecord>>,
+    /// `(collection, rkey, record)` for every `put_record`
+    pub written_records: RefCell<Vec<(String, String, Value)>>,
+    /// Blob bytes by CID
+    pub blobs: HashMap<String, Vec<u8>>,
+    pub uploaded_blobs: RefCell<Vec<String>>,
+    pub app_passwords: Option<Vec<ClientAppPassword>>,
+    /// Changed by `update_email`, which needs a token once the address is confirmed
+    pub email: RefCell<Option<ClientEmailStatus>>,
+    pub email_confirmation_requests: Cell<u32>,
+    pub plc_token_requests: Cell<u32>,
+    /// Refreshed sessions get this expiry; `None` fails the refresh
+    pub refreshed_expires_at: Option<u64>,
+    pub session_refreshes: Cell<u32>,
+    /// Session handed out by `try_login_before_creation`; `None` refuses the login
+    pub login_session: Option<ClientSessionCredentials>,
+    pub logins: Cell<u32>,
+}
+
+fn unavailable(what: &str) -> ClientError {
+    ClientError::ApiError {
+        message: format!("mock PDS has no {}", what),
+    }
+}
+
+pub fn session(pds: &str) -> ClientSessionCredentials {
+    ClientSessionCredentials {
+        did: "did:plc:alice".to_string(),
+        handle: "alice.new.example".to_string(),
+        pds: pds.to_string(),
+        access_jwt: String::new(),
+        refresh_jwt: String::new(),
+        expires_at: None,
+    }
+}
+
+#[async_trait(?Send)]
+impl PdsApi for MockPds {
+    async fn describe_server(&self, _pds_url: &str) -> Result<Value, ClientError> {
+        let did = self.server_did.clone().ok_or_else(|| unavailable("did"))?;
+        Ok(serde_json::json!({ "did": did, "availableUserDomains": [] }))
+    }
+
+    async fn resolve_pds_endpoint(&self, _did: &str) -> Result<String, ClientError> {
+        self.did_pds_endpoint
+            .clone()
+            .ok_or_else(|| unavailable("DID document"))
+    }
+
+    async fn export_preferences(
+        &self,
+        _session: &ClientSessionCredentials,
+    ) -> Result<ClientPreferencesExportResponse, ClientError> {
+        let stored = if self.discard_imports {
+            None
+        } else {
+            self.imported_preferences.borrow().clone()
+        };
+        Ok(ClientPreferencesExportResponse {
+            success: true,
+            message: String::new(),
+            preferences_json: Some(stored.unwrap_or_else(|| r#"{"preferences":[]}"#.to_string())),
+        })
+    }
+
+    async fn import_preferences(
+        &self,
+        _session: &ClientSessionCredentials,
+        preferences_json: String,
+    ) -> Result<ClientPreferencesImportResponse, ClientError> {
+        *self.imported_preferences.borrow_mut() = Some(preferences_json);
+        Ok(ClientPreferencesImportResponse {
+            success: true,
+            message: String::new(),
+        })
+    }
+
+    async fn get_plc_recommendation(
+        &self,
+        _session: &ClientSessionCredentials,
+    ) -> Result<ClientPlcRecommendationResponse, ClientError> {
+        Ok(match &self.plc_recommendation {
+            Some(operation) => ClientPlcRecommendationResponse {
+                success: true,
+                message: "PLC recommendation retrieved".to_string(),
+                plc_unsigned: Some(operation.clone()),
+                endpoint_missing: false,
+            },
+            None => ClientPlcRecommendationResponse {
+                success: false,
+                message: "MethodNotImplemented".to_string(),
+                plc_unsigned: None,
+                endpoint_missing: true,
+            },
+        })
+    }
+
+    async fn fetch_plc_data(&self, _did: &str) -> Result<Value, ClientError> {
+        self.plc_data.clone().ok_or_else(|| unavailable("PLC data"))
+    }
+
+    async fn describe_repo_collections(
+        &self,
+        _session: &ClientSessionCredentials,
+        _did: &str,
+    ) -> Result<Vec<String>, ClientError> {
+        self.collections
+            .clone()
+            .ok_or_else(|| unavailable("collections"))
+    }
+
+    async fn list_records(
+        &self,
+        _session: &ClientSessionCredentials,
+        _did: &str,
+        collection: &str,
+        cursor: Option<&str>,
+    ) -> Result<ClientRecordPage, ClientError> {
+        let records = self
+            .records
+            .get(collection)
+            .ok_or_else(|| unavailable("collection"))?;
+        let index = cursor.and_then(|c| c.parse::<usize>().ok()).unwrap_or(0);
+        Ok(ClientRecordPage {
+            records: records.get(index).cloned().into_iter().collect(),
+            cursor: (index + 1 < records.len()).then(|| (index + 1).to_string()),
+        })
+    }
+
+    async fn put_record(
+        &self,
+        _session: &ClientSessionCredentials,
+        collection: &str,
+        rkey: &str,
+        record: Value,
+    ) -> Result<(), ClientError> {
+        self.written_records
+            .borrow_mut()
+            .push((collection.to_string(), rkey.to_string(), record));
+        Ok(())
+    }
+
+    async fn export_blob(
+        &self,
+        _session: &ClientSessionCredentials,
+        cid: &Cid,
+    ) -> Result<ClientBlobExportResponse, ClientError> {
+        let data = self.blobs.get(&cid.to_string()).cloned();
+        Ok(ClientBlobExportResponse {
+            success: data.is_some(),
+            message: String::new(),
+            blob_data: data,
+        })
+    }
+
+    async fn upload_blob(
+        &self,
+        _session: &ClientSessionCredentials,
+        cid: &Cid,
+        _blob_data: Vec<u8>,
+    ) -> Result<ClientBlobUploadResponse, ClientError> {
+        self.uploaded_blobs.borrow_mut().push(cid.to_string());
+        Ok(ClientBlobUploadResponse {
+            success: true,
+            message: String::new(),
+        })
+    }
+
+    async fn list_app_passwords(
+        &self,
+        _session: &ClientSessionCredentials,
+    ) -> Result<Vec<ClientAppPassword>, ClientError> {
+        self.app_passwords
+            .clone()
+            .ok_or_else(|| unavailable("app passwords"))
+    }
+
+    async fn get_email_status(
+        &self,
+        _session: &ClientSessionCredentials,
+    ) -> Result<ClientEmailStatus, ClientError> {
+        self.email
+            .borrow()
+            .clone()
+            .ok_or_else(|| unavailable("email"))
+    }
+
+    async fn request_email_confirmation(
+        &self,
+        _session: &ClientSessionCredentials,
+    ) -> Result<(), ClientError> {
+        self.email_confirmation_requests
+            .set(self.email_confirmation_requests.get() + 1);
+        Ok(())
+    }
+
+    async fn update_email(
+        &self,
+        _session: &ClientSessionCredentials,
+        email: &str,
+        token: Option<&str>,
+    ) -> Result<(), ClientError> {
+        let mut current = self.email.borrow_mut();
+        let confirmed = current.as_ref().is_some_and(|status| status.confirmed);
+        if confirmed && token.is_none() {
+            return Err(ClientError::ApiError {
+                message: "TokenRequired".to_string(),
+            });
+        }
+        *current = Some(ClientEmailStatus {
+            email: Some(email.to_string()),
+            confirmed: false,
+        });
+        Ok(())
+    }
+
+    async fn request_plc_token(
+        &self,
+        _session: &ClientSessionCredentials,
+    ) -> Result<ClientPlcTokenResponse, ClientError> {
+        self.plc_token_requests
+            .set(self.plc_token_requests.get() + 1);
+        Ok(ClientPlcTokenResponse {
+            success: true,
+            message: "PLC token sent".to_string(),
+        })
+    }
+
+    async fn refresh_session(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<ClientSessionCredentials, ClientError> {
+        let expires_at = self
+            .refreshed_expires_at
+            .ok_or_else(|| unavailable("refresh"))?;
+        self.session_refreshes.set(self.session_refreshes.get() + 1);
+        Ok(ClientSessionCredentials {
+            access_jwt: "refreshed".to_string(),
+            expires_at: Some(expires_at),
+            ..session.clone()
+        })
+    }
+
+    async fn try_login_before_creation(
+        &self,
+        _handle: &str,
+        _password: &str,
+        _pds_url: &str,
+    ) -> Result<ClientLoginResponse, ClientError> {
+        self.logins.set(self.logins.get() + 1);
+        Ok(ClientLoginResponse {
+            success: self.login_session.is_some(),
+            message: if self.login_session.is_some() {
+                String::new()
+            } else {
+                "Invalid identifier or password".to_string()
+            },
+            did: self
+                .login_session
+                .as_ref()
+                .map(|session| session.did.clone()),
+            session: self.login_session.clone(),
+            active: Some(true),
+            status: None,
+        })
+    }
+}
@@ -1,4 +1,13 @@
 pub mod blob;
+pub mod blob_enumeration;
+pub mod blob_repair;
+#[cfg(test)]
+pub(crate) mod mock_pds;
 pub mod plc;
+pub mod plc_fallback;
 pub mod preferences;
+pub mod repo_import;
 pub mod repository;
+pub mod traits;
+
+pub use traits::{MigrationStep, StepContext};
@@ -1,100 +1,242 @@
 //! PLC (Personal Learning Certificate) transition setup step
 
+use async_trait::async_trait;
+
 use crate::console_info;
-#[cfg(feature = "web")]
-use crate::services::client::{ClientSessionCredentials, PdsClient};
-use dioxus::prelude::*;
+use crate::migration::did_method::{did_method_for, DidMethod};
+use crate::migration::plan::PlanStep;
+use crate::migration::plc_token_delivery::{save_plc_token_delivery, PlcTokenDelivery};
+use crate::migration::service_accounts::detect_for_migration;
+use crate::migration::steps::traits::{MigrationStep, StepContext};
+use crate::services::client::{NewPds, OldPds, PdsApi};
+use crate::services::config::get_global_config;
+use crate::utils::platform::now_ms;
 
 use crate::migration::types::*;
 
 /// Set up PLC transition by getting recommendation and requesting token
 // NEWBOLD.md Steps: goat account plc recommended > plc_recommended.json (line 127) + goat account plc request-token (line 134)
-// Implements: PLC identity transition setup for DID document update
+// Implements: identity transition setup for DID document update, through the DID's method
 pub async fn setup_plc_transition_client_side(
-    old_session: &ClientSessionCredentials,
-    new_session: &ClientSessionCredentials,
-    dispatch: &EventHandler<MigrationAction>,
+    method: &dyn DidMethod,
+    old: &OldPds<&dyn PdsApi>,
+    new: &NewPds<&dyn PdsApi>,
+    dispatch: &dyn Fn(MigrationAction),
     state: &MigrationState,
 ) -> Result<(), String> {
     // Step 16: Get PLC recommendation from new PDS
     // NEWBOLD.md Step: goat account plc recommended > plc_recommended.json (line 127)
     // Implements: Gets recommended DID credentials from new PDS for PLC transition
-    console_info!("[Migration] Step 16: Getting PLC recommendation from new PDS");
-    dispatch.call(MigrationAction::SetMigrationStep(
+    console_info!(
+        "[Migration] Step 16: Building did:{} update from the new PDS",
+        method.method()
+    );
+    dispatch(MigrationAction::SetMigrationStep(
         "Getting PLC recommendation from new PDS...".to_string(),
     ));
 
-    let pds_client = PdsClient::new();
-
-    let plc_unsigned = match pds_client.get_plc_recommendation(new_session).await {
-        Ok(response) => {
-            if response.success {
-                console_info!("[Migration] PLC recommendation retrieved successfully");
+    // Labeler and feed generator services have to survive the update
+    let service_account = detect_for_migration(old, new).await;
+    dispatch(MigrationAction::SetServiceAccount(service_account));
 
-                // Update PLC progress
-                let plc_progress = PlcProgress {
-                    recommendation_complete: true,
-                    ..Default::default()
-                };
-                dispatch.call(MigrationAction::SetPlcProgress(plc_progress));
-
-                // Update migration progress
-                let mut migration_progress = state.migration_progress.clone();
-                migration_progress.plc_recommended = true;
-                dispatch.call(MigrationAction::SetMigrationProgress(migration_progress));
+    let update = method
+        .build_update(new, state.reserved_signing_key.as_deref())
+        .await?;
+    let plc_unsigned = update.operation;
+    dispatch(MigrationAction::SetPlcUpdateConfirmation(
+        update.confirmation,
+    ));
+    dispatch(MigrationAction::SetPlcUpdateConfirmed(false));
 
-                response.plc_unsigned.unwrap_or_default()
-            } else {
-                return Err(response.message);
-            }
-        }
-        Err(e) => return Err(format!("Failed to get PLC recommendation: {}", e)),
+    // Update PLC progress
+    let plc_progress = PlcProgress {
+        recommendation_complete: true,
+        ..Default::default()
     };
+    dispatch(MigrationAction::SetPlcProgress(plc_progress));
+
+    // Update migration progress
+    let mut migration_progress = state.migration_progress.clone();
+    migration_progress.plc_recommended = true;
+    dispatch(MigrationAction::SetMigrationProgress(migration_progress));
 
     // Step 17: Request PLC token from old PDS - this triggers Form 4
     // NEWBOLD.md Step: goat account plc request-token (line 134)
     // Implements: Requests PLC signing token via email for identity transition
-    console_info!("[Migration] Step 17: Requesting PLC token from old PDS");
-    dispatch.call(MigrationAction::SetMigrationStep(
-        "Requesting PLC token from old PDS...".to_string(),
+    if method.requires_token() {
+        // A new request replaces the token already emailed, so a migration
+        // restarted after a refresh waits for that email while it's recent
+        let now = now_ms() as u64;
+        let token_config = get_global_config().plc_token;
+        let earlier = state
+            .plc_token_delivery
+            .clone()
+            .filter(|delivery| delivery.reusable_for(&old.session().did, now, &token_config));
+        let delivery = match earlier {
+            Some(delivery) => {
+                console_info!(
+                    "[Migration] Step 17: PLC token was emailed {}s ago, not requesting another",
+                    delivery.elapsed_ms(now) / 1000
+                );
+                delivery
+            }
+            None => {
+                console_info!("[Migration] Step 17: Requesting PLC token from old PDS");
+                dispatch(MigrationAction::SetMigrationStep(
+                    "Requesting PLC token from old PDS...".to_string(),
+                ));
+                method.request_token(old).await?;
+                console_info!("[Migration] PLC token requested successfully - showing Form 4");
+                PlcTokenDelivery::new(&old.session().did, now)
+            }
+        };
+        save_plc_token_delivery(&delivery);
+        dispatch(MigrationAction::SetPlcTokenDelivery(Some(delivery)));
+
+        // Update PLC progress
+        let mut plc_progress = state.plc_progress.clone();
+        plc_progress.token_requested = true;
+        dispatch(MigrationAction::SetPlcProgress(plc_progress));
+
+        // Update migration progress
+        let mut migration_progress = state.migration_progress.clone();
+        migration_progress.plc_token_requested = true;
+        dispatch(MigrationAction::SetMigrationProgress(migration_progress));
+    }
+
+    // Set up Form 4 data and transition to PLC verification
+    dispatch(MigrationAction::SetPlcUnsigned(plc_unsigned.clone()));
+    dispatch(MigrationAction::SetPlcVerificationCode(String::new()));
+    let handle_context = state.form1.original_handle.clone();
+
+    // Update form4 with context
+    let mut form4 = state.form4.clone();
+    form4.handle_context = handle_context;
+    form4.plc_unsigned = plc_unsigned;
+
+    // Transition to Form 4
+    dispatch(MigrationAction::SetCurrentStep(FormStep::PlcVerification));
+    dispatch(MigrationAction::SetMigrationStep(
+        if method.requires_token() {
+            "PLC token sent to email. Please check your email and enter the verification code in Form 4.".to_string()
+        } else {
+            "Publish the new DID document shown in Form 4, then confirm to continue.".to_string()
+        },
     ));
+    dispatch(MigrationAction::SetMigrating(false)); // End migration here - Form 4 will continue
 
-    match pds_client.request_plc_token(old_session).await {
-        Ok(response) => {
-            if response.success {
-                console_info!("[Migration] PLC token requested successfully - showing Form 4");
+    console_info!("[Migration] Migration paused at Form 4 for identity update");
+    Ok(())
+}
 
-                // Update PLC progress
-                let mut plc_progress = state.plc_progress.clone();
-                plc_progress.token_requested = true;
-                dispatch.call(MigrationAction::SetPlcProgress(plc_progress));
-
-                // Update migration progress
-                let mut migration_progress = state.migration_progress.clone();
-                migration_progress.plc_token_requested = true;
-                dispatch.call(MigrationAction::SetMigrationProgress(migration_progress));
-
-                // Set up Form 4 data and transition to PLC verification
-                dispatch.call(MigrationAction::SetPlcUnsigned(plc_unsigned.clone()));
-                dispatch.call(MigrationAction::SetPlcVerificationCode(String::new()));
-                let handle_context = state.form1.original_handle.clone();
-
-                // Update form4 with context
-                let mut form4 = state.form4.clone();
-                form4.handle_context = handle_context;
-                form4.plc_unsigned = plc_unsigned;
-
-                // Transition to Form 4
-                dispatch.call(MigrationAction::SetCurrentStep(FormStep::PlcVerification));
-                dispatch.call(MigrationAction::SetMigrationStep("PLC token sent to email. Please check your email and enter the verification code in Form 4.".to_string()));
-                dispatch.call(MigrationAction::SetMigrating(false)); // End migration here - Form 4 will continue
-
-                console_info!("[Migration] Migration paused at Form 4 for PLC token verification");
-                Ok(())
-            } else {
-                Err(response.message)
-            }
-        }
-        Err(e) => Err(format!("Failed to request PLC token: {}", e)),
+/// Fetches the PLC recommendation and requests the PLC token, handing over to Form 4
+pub struct PlcSetupStep;
+
+#[async_trait(?Send)]
+impl MigrationStep for PlcSetupStep {
+    fn plan_step(&self) -> PlanStep {
+        PlanStep::PlcSetup
+    }
+
+    fn describe(&self) -> &'static str {
+        "Get the PLC recommendation and request the PLC token"
+    }
+
+    async fn execute(&self, ctx: &mut StepContext<'_>) -> Result<(), String> {
+        let target = ctx.target(self.plan_step())?;
+        let method = did_method_for(target.did())?;
+        setup_plc_transition_client_side(method.as_ref(), &ctx.old, target, ctx.dispatch, ctx.state)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migration::steps::mock_pds::{session, MockPds};
+    use std::cell::RefCell;
+
+    fn run_plc_setup(pds: &MockPds) -> (Result<(), String>, Vec<MigrationAction>) {
+        let old = session("https://old.example");
+        let new = session("https://new.example");
+        let state = MigrationState::default();
+        let actions = RefCell::new(Vec::new());
+        let dispatch = |action: MigrationAction| actions.borrow_mut().push(action);
+
+        let pds: &dyn PdsApi = pds;
+        let mut ctx = StepContext {
+            old: OldPds::new(pds, old),
+            new: Some(NewPds::new(pds, new)),
+            state: &state,
+            dispatch: &dispatch,
+            handler: None,
+            preferences: None,
+        };
+        let result = futures::executor::block_on(PlcSetupStep.execute(&mut ctx));
+        (result, actions.into_inner())
+    }
+
+    #[test]
+    fn test_plc_setup_requests_token_and_hands_over_to_form4() {
+        let pds = MockPds {
+            plc_recommendation: Some(r#"{"rotationKeys":[]}"#.to_string()),
+            ..MockPds::default()
+        };
+        let (result, actions) = run_plc_setup(&pds);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(pds.plc_token_requests.get(), 1);
+        assert!(actions.iter().any(|action| matches!(
+            action,
+            MigrationAction::SetCurrentStep(FormStep::PlcVerification)
+        )));
+        assert!(actions.iter().any(|action| matches!(
+            action,
+            MigrationAction::SetPlcUnsigned(unsigned) if unsigned.contains("rotationKeys")
+        )));
+        // Recommended credentials come from the target; nothing to confirm
+        assert!(actions
+            .iter()
+            .any(|action| matches!(action, MigrationAction::SetPlcUpdateConfirmation(None))));
+    }
+
+    #[test]
+    fn test_plc_setup_keeps_labeler_services_from_current_plc_data() {
+        let pds = MockPds {
+            plc_recommendation: Some(
+                r#"{"rotationKeys":[],"verificationMethods":{"atproto":"did:key:znew"},"services":{"atproto_pds":{"type":"AtprotoPersonalDataServer","endpoint":"https://new.example"}}}"#
+                    .to_string(),
+            ),
+            plc_data: Some(serde_json::json!({
+                "verificationMethods": { "atproto": "did:key:zold", "atproto_label": "did:key:zlabel" },
+                "services": {
+                    "atproto_pds": { "type": "AtprotoPersonalDataServer", "endpoint": "https://old.example" },
+                    "atproto_labeler": { "type": "AtprotoLabeler", "endpoint": "https://labels.example" }
+                }
+            })),
+            collections: Some(vec!["app.bsky.labeler.service".to_string()]),
+            ..MockPds::default()
+        };
+        let (result, actions) = run_plc_setup(&pds);
+
+        assert_eq!(result, Ok(()));
+        assert!(actions.iter().any(|action| matches!(
+            action,
+            MigrationAction::SetServiceAccount(Some(profile)) if profile.services.len() == 1
+        )));
+        assert!(actions.iter().any(|action| matches!(
+            action,
+            MigrationAction::SetPlcUnsigned(unsigned)
+                if unsigned.contains("https://labels.example") && unsigned.contains("did:key:znew")
+        )));
+    }
+
+    #[test]
+    fn test_plc_setup_fails_without_recommendation_or_fallback_data() {
+        let pds = MockPds::default();
+        let (result, _) = run_plc_setup(&pds);
+
+        assert!(result.is_err());
+        assert_eq!(pds.plc_token_requests.get(), 0);
     }
 }
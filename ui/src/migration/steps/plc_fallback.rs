@@ -0,0 +1,310 @@
+//! Client-side PLC operation construction for PDSes without getRecommendedDidCredentials
+//!
+//! The recommendation endpoint is optional, and some PDS implementations don't
+//! ship it. For those the unsigned operation is assembled here instead: the
+//! current PLC data supplies the rotation keys and any extra entries, and the
+//! handle and PDS endpoint come from the new account. Every input is checked
+//! before the operation reaches Form 4, since a bad signing key or endpoint
+//! would leave the identity pointing somewhere the account can't follow.
+//!
+//! The signing key must be the one the target signs the repo with. Once the
+//! account exists, `reserveSigningKey` only hands out a fresh, unused key, so
+//! the key reserved before `createAccount` is the only one trusted here;
+//! without it the operation is refused. The target's own rotation key isn't
+//! published anywhere, so the operation can't add one: Form 4 spells out what
+//! that means and waits for the user to confirm before signing.
+
+use serde_json::{json, Map, Value};
+
+use crate::migration::did_method::UnsignedUpdate;
+use crate::services::client::{NewPds, PdsApi};
+use crate::utils::handle::normalize_handle;
+use crate::{console_info, console_warn};
+
+pub(crate) const ATPROTO_PDS_SERVICE: &str = "atproto_pds";
+pub(crate) const ATPROTO_PDS_TYPE: &str = "AtprotoPersonalDataServer";
+
+/// Host part of an `https://` endpoint, rejecting anything with a path or query
+pub(crate) fn endpoint_host(endpoint: &str) -> Result<&str, String> {
+    let rest = endpoint
+        .strip_prefix("https://")
+        .ok_or_else(|| format!("PDS endpoint must use https: {}", endpoint))?;
+    let authority = rest.strip_suffix('/').unwrap_or(rest);
+    if authority.is_empty() || authority.contains(['/', '?', '#', '@']) {
+        return Err(format!("PDS endpoint is not a bare origin: {}", endpoint));
+    }
+    Ok(authority.split(':').next().unwrap_or(authority))
+}
+
+/// The handle must already be in canonical lowercase punycode form
+pub(crate) fn check_handle(handle: &str) -> Result<(), String> {
+    match normalize_handle(handle) {
+        Ok(canonical) if canonical == handle => Ok(()),
+        _ => Err(format!(
+            "Handle is not valid for a PLC operation: {}",
+            handle
+        )),
+    }
+}
+
+pub(crate) fn check_did_key(key: &str, what: &str) -> Result<(), String> {
+    let multibase = key
+        .strip_prefix("did:key:z")
+        .ok_or_else(|| format!("{} is not a did:key: {}", what, key))?;
+    if multibase.len() < 40 || !multibase.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(format!("{} is malformed: {}", what, key));
+    }
+    Ok(())
+}
+
+/// Assemble the unsigned operation from the current PLC data and the target's values
+pub fn build_fallback_plc_operation(
+    current: &Value,
+    signing_key: &str,
+    handle: &str,
+    pds_endpoint: &str,
+    server_did: Option<&str>,
+) -> Result<Value, String> {
+    check_did_key(signing_key, "Target signing key")?;
+    check_handle(handle)?;
+    let host = endpoint_host(pds_endpoint)?;
+
+    // A did:web service DID names its host; it must agree with where we're pointing
+    if let Some(server_host) = server_did.and_then(|did| did.strip_prefix("did:web:")) {
+        if !server_host.eq_ignore_ascii_case(host) {
+            return Err(format!(
+                "Target PDS identifies as {} but its endpoint is {}",
+                server_host, pds_endpoint
+            ));
+        }
+    }
+
+    // Rotation keys are carried over unchanged so existing recovery keys keep working
+    let rotation_keys = current
+        .get("rotationKeys")
+        .and_then(Value::as_array)
+        .filter(|keys| !keys.is_empty())
+        .ok_or("Current PLC data has no rotation keys")?;
+    for key in rotation_keys {
+        let key = key
+            .as_str()
+            .ok_or("Current PLC data has a non-string rotation key")?;
+        check_did_key(key, "Rotation key")?;
+    }
+
+    // The chosen handle goes first; other non-handle aliases are preserved
+    let mut also_known_as = vec![Value::String(format!("at://{}", handle))];
+    if let Some(existing) = current.get("alsoKnownAs").and_then(Value::as_array) {
+        also_known_as.extend(
+            existing
+                .iter()
+                .filter(|aka| aka.as_str().is_some_and(|aka| !aka.starts_with("at://")))
+                .cloned(),
+        );
+    }
+
+    let mut verification_methods = current
+        .get("verificationMethods")
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_else(Map::new);
+    verification_methods.insert("atproto".to_string(), json!(signing_key));
+
+    let mut services = current
+        .get("services")
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_else(Map::new);
+    services.insert(
+        ATPROTO_PDS_SERVICE.to_string(),
+        json!({
+            "type": ATPROTO_PDS_TYPE,
+            "endpoint": pds_endpoint.trim_end_matches('/'),
+        }),
+    );
+
+    Ok(json!({
+        "rotationKeys": rotation_keys,
+        "alsoKnownAs": also_known_as,
+        "verificationMethods": verification_methods,
+        "services": services,
+    }))
+}
+
+/// What the user agrees to by signing an operation that keeps the current rotation keys
+pub fn rotation_key_notice(rotation_keys: usize, pds_endpoint: &str) -> String {
+    let host = endpoint_host(pds_endpoint).unwrap_or(pds_endpoint);
+    format!(
+        "{} doesn't publish DID credentials, so this operation was built in your browser. \
+         It keeps your current {} rotation key(s) and adds none for {}: your old PDS, and any \
+         recovery key of your own, can still change your identity, while {} can't update it \
+         for you (for example to change your handle or move you again).",
+        host, rotation_keys, host, host
+    )
+}
+
+/// Gather the inputs from the target and the PLC directory and build the unsigned
+/// operation. `reserved_signing_key` is the key reserved before the account was
+/// created; without it the operation is refused.
+pub async fn construct_plc_operation_fallback(
+    new: &NewPds<impl PdsApi>,
+    reserved_signing_key: Option<&str>,
+) -> Result<UnsignedUpdate, String> {
+    let new_session = new.session();
+    if !new_session.did.starts_with("did:plc:") {
+        return Err(format!(
+            "{} is not a did:plc, so there is no PLC operation to construct",
+            new_session.did
+        ));
+    }
+    let signing_key = reserved_signing_key.ok_or(
+        "No signing key was reserved before the account was created, so the key the new PDS \
+         signs your repository with is unknown. Pointing your DID at any other key would make \
+         the repository unverifiable.",
+    )?;
+
+    let server_did = match new.describe_server().await {
+        Ok(describe) => describe
+            .get("did")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        Err(e) => {
+            console_warn!(
+                "[Migration] describeServer failed during PLC fallback: {}",
+                e
+            );
+            None
+        }
+    };
+
+    let current = new
+        .fetch_plc_data()
+        .await
+        .map_err(|e| format!("Could not read the current PLC data: {}", e))?;
+
+    let operation = build_fallback_plc_operation(
+        &current,
+        signing_key,
+        &new_session.handle,
+        &new_session.pds,
+        server_did.as_deref(),
+    )?;
+    let rotation_keys = operation["rotationKeys"].as_array().map_or(0, Vec::len);
+
+    console_info!(
+        "[Migration] Constructed PLC operation client-side (signing key {}, endpoint {})",
+        signing_key,
+        new_session.pds
+    );
+    Ok(UnsignedUpdate {
+        operation: operation.to_string(),
+        confirmation: Some(rotation_key_notice(rotation_keys, &new_session.pds)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migration::steps::mock_pds::{session, MockPds};
+
+    const ROTATION_KEY: &str = "did:key:zQ3shhCGUqDKjStzuDxPkTxN6ujddP4RkEKJJouJGRRkaLGbg";
+    const SIGNING_KEY: &str = "did:key:zQ3shXjHeiBuRCKmM36cuYnm7YEMzhGnCmCyW92sRJ9pribSF";
+
+    fn current() -> Value {
+        json!({
+            "rotationKeys": [ROTATION_KEY],
+            "alsoKnownAs": ["at://alice.old.example", "https://alice.example"],
+            "verificationMethods": { "atproto": ROTATION_KEY },
+            "services": {
+                "atproto_pds": { "type": ATPROTO_PDS_TYPE, "endpoint": "https://old.example" },
+                "atproto_labeler": { "type": "AtprotoLabeler", "endpoint": "https://labels.example" }
+            }
+        })
+    }
+
+    #[test]
+    fn test_builds_operation_for_target() {
+        let operation = build_fallback_plc_operation(
+            &current(),
+            SIGNING_KEY,
+            "alice.new.example",
+            "https://new.example/",
+            Some("did:web:new.example"),
+        )
+        .unwrap();
+
+        assert_eq!(operation["rotationKeys"], json!([ROTATION_KEY]));
+        assert_eq!(
+            operation["alsoKnownAs"],
+            json!(["at://alice.new.example", "https://alice.example"])
+        );
+        assert_eq!(operation["verificationMethods"]["atproto"], SIGNING_KEY);
+        assert_eq!(
+            operation["services"]["atproto_pds"]["endpoint"],
+            "https://new.example"
+        );
+        assert_eq!(
+            operation["services"]["atproto_labeler"]["endpoint"],
+            "https://labels.example"
+        );
+    }
+
+    #[test]
+    fn test_rejects_bad_inputs() {
+        let build = |key: &str, handle: &str, endpoint: &str, server_did: Option<&str>| {
+            build_fallback_plc_operation(&current(), key, handle, endpoint, server_did)
+        };
+
+        assert!(build("zQ3sh", "alice.new.example", "https://new.example", None).is_err());
+        assert!(build(SIGNING_KEY, "alice", "https://new.example", None).is_err());
+        assert!(build(SIGNING_KEY, "bücher.example", "https://new.example", None).is_err());
+        assert!(build(SIGNING_KEY, "alice.new.example", "http://new.example", None).is_err());
+        assert!(build(
+            SIGNING_KEY,
+            "alice.new.example",
+            "https://new.example/xrpc",
+            None
+        )
+        .is_err());
+        assert!(build(
+            SIGNING_KEY,
+            "alice.new.example",
+            "https://new.example",
+            Some("did:web:other.example")
+        )
+        .is_err());
+
+        let no_rotation_keys = json!({ "rotationKeys": [] });
+        assert!(build_fallback_plc_operation(
+            &no_rotation_keys,
+            SIGNING_KEY,
+            "alice.new.example",
+            "https://new.example",
+            None
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_fallback_needs_key_reserved_before_creation() {
+        let pds = MockPds {
+            plc_data: Some(current()),
+            ..MockPds::default()
+        };
+        let api: &dyn PdsApi = &pds;
+        let new = NewPds::new(api, session("https://new.example"));
+        let construct =
+            |key| futures::executor::block_on(construct_plc_operation_fallback(&new, key));
+
+        // A key reserved after creation isn't what the repo is signed with
+        let refused = construct(None).unwrap_err();
+        assert!(refused.contains("No signing key was reserved"));
+
+        let update = construct(Some(SIGNING_KEY)).unwrap();
+        let operation: Value = serde_json::from_str(&update.operation).unwrap();
+        assert_eq!(operation["verificationMethods"]["atproto"], SIGNING_KEY);
+        assert_eq!(operation["rotationKeys"], json!([ROTATION_KEY]));
+        let notice = update.confirmation.unwrap();
+        assert!(notice.contains("1 rotation key(s) and adds none for new.example"));
+    }
+}
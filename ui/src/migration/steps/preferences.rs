@@ -1,32 +1,37 @@
 //! Preferences migration step
 
-use crate::console_info;
-#[cfg(feature = "web")]
-use crate::services::client::{ClientSessionCredentials, PdsClient};
-use dioxus::prelude::*;
+use async_trait::async_trait;
 
+use crate::services::client::{NewPds, OldPds, PdsApi};
+use crate::services::config::{get_global_config, RetryPhase};
+use crate::services::retry::with_retries;
+use crate::{console_info, console_warn};
+
+use crate::migration::plan::PlanStep;
+use crate::migration::preferences_backup::{
+    load_preferences_backup, save_preferences_backup, PreferencesBackup,
+};
+use crate::migration::preferences_roundtrip::{compare_preferences, RoundTripOutcome};
+use crate::migration::steps::traits::{MigrationStep, StepContext};
 use crate::migration::types::*;
 
-/// Migrate preferences from old PDS to new PDS
-// NEWBOLD.md Steps: goat bsky prefs export > prefs.json (line 115) + goat bsky prefs import prefs.json (line 118)
-// Implements: Complete preferences migration for Bluesky app settings
-pub async fn migrate_preferences_client_side(
-    old_session: &ClientSessionCredentials,
-    new_session: &ClientSessionCredentials,
-    dispatch: &EventHandler<MigrationAction>,
-    state: &MigrationState,
-) -> Result<(), String> {
+/// Export preferences from the old PDS and save a versioned backup of them
+// NEWBOLD.md Step: goat bsky prefs export > prefs.json (line 115)
+pub async fn export_preferences_client_side(
+    old: &OldPds<impl PdsApi>,
+    dispatch: &dyn Fn(MigrationAction),
+) -> Result<PreferencesBackup, String> {
     // Step 14: Export preferences from old PDS
     // NEWBOLD.md Step: goat bsky prefs export > prefs.json (line 115)
     // Implements: Exports Bluesky app preferences as JSON
     console_info!("[Migration] Step 14: Exporting preferences from old PDS");
-    dispatch.call(MigrationAction::SetMigrationStep(
+    dispatch(MigrationAction::SetMigrationStep(
         "Exporting preferences from old PDS...".to_string(),
     ));
 
-    let pds_client = PdsClient::new();
-
-    let preferences_json = match pds_client.export_preferences(old_session).await {
+    let retry = get_global_config().retry;
+    let export = with_retries(&retry, RetryPhase::Preferences, || old.export_preferences()).await;
+    let preferences_json = match export {
         Ok(response) => {
             if response.success {
                 console_info!("[Migration] Preferences exported successfully");
@@ -36,7 +41,7 @@ pub async fn migrate_preferences_client_side(
                     export_complete: true,
                     ..Default::default()
                 };
-                dispatch.call(MigrationAction::SetPreferencesProgress(prefs_progress));
+                dispatch(MigrationAction::SetPreferencesProgress(prefs_progress));
 
                 response.preferences_json.unwrap_or_default()
             } else {
@@ -46,18 +51,47 @@ pub async fn migrate_preferences_client_side(
         Err(e) => return Err(format!("Failed to export preferences: {}", e)),
     };
 
+    // Keep a versioned copy before the target gets a chance to drop anything
+    dispatch(MigrationAction::SetMigrationStep(
+        "Saving a backup of your preferences...".to_string(),
+    ));
+    let backup = PreferencesBackup::from_export(old.did(), old.host(), &preferences_json)?;
+    let backup_info = save_preferences_backup(&backup).await;
+    dispatch(MigrationAction::SetPreferencesBackup(Some(backup_info)));
+
+    Ok(backup)
+}
+
+/// Import exported (or restored) preferences to the new PDS
+// NEWBOLD.md Step: goat bsky prefs import prefs.json (line 118)
+pub async fn import_preferences_client_side(
+    new: &NewPds<impl PdsApi>,
+    backup: &PreferencesBackup,
+    dispatch: &dyn Fn(MigrationAction),
+    state: &MigrationState,
+) -> Result<(), String> {
+    if backup.did != new.did() {
+        return Err(format!(
+            "Preferences backup belongs to {}, not {}",
+            backup.did,
+            new.did()
+        ));
+    }
+
     // Step 15: Import preferences to new PDS
     // NEWBOLD.md Step: goat bsky prefs import prefs.json (line 118)
     // Implements: Imports Bluesky app preferences to new PDS
     console_info!("[Migration] Step 15: Importing preferences to new PDS");
-    dispatch.call(MigrationAction::SetMigrationStep(
+    dispatch(MigrationAction::SetMigrationStep(
         "Importing preferences to new PDS...".to_string(),
     ));
 
-    match pds_client
-        .import_preferences(new_session, preferences_json)
-        .await
-    {
+    let retry = get_global_config().retry;
+    let import = with_retries(&retry, RetryPhase::Preferences, || {
+        new.import_preferences(backup.export_json())
+    })
+    .await;
+    match import {
         Ok(response) => {
             if response.success {
                 console_info!("[Migration] Preferences imported successfully");
@@ -65,13 +99,13 @@ pub async fn migrate_preferences_client_side(
                 // Update preferences progress
                 let mut prefs_progress = state.preferences_progress.clone();
                 prefs_progress.import_complete = true;
-                dispatch.call(MigrationAction::SetPreferencesProgress(prefs_progress));
+                dispatch(MigrationAction::SetPreferencesProgress(prefs_progress));
 
                 // Update migration progress
                 let mut migration_progress = state.migration_progress.clone();
                 migration_progress.preferences_exported = true;
                 migration_progress.preferences_imported = true;
-                dispatch.call(MigrationAction::SetMigrationProgress(migration_progress));
+                dispatch(MigrationAction::SetMigrationProgress(migration_progress));
 
                 Ok(())
             } else {
@@ -81,3 +115,190 @@ pub async fn migrate_preferences_client_side(
         Err(e) => Err(format!("Failed to import preferences: {}", e)),
     }
 }
+
+/// Exports preferences from the old PDS into the run's context
+pub struct ExportPreferencesStep;
+
+#[async_trait(?Send)]
+impl MigrationStep for ExportPreferencesStep {
+    fn plan_step(&self) -> PlanStep {
+        PlanStep::ExportPreferences
+    }
+
+    fn describe(&self) -> &'static str {
+        "Export preferences from the old PDS and save a backup"
+    }
+
+    async fn execute(&self, ctx: &mut StepContext<'_>) -> Result<(), String> {
+        let backup = export_preferences_client_side(&ctx.old, ctx.dispatch).await?;
+        ctx.preferences = Some(backup);
+        Ok(())
+    }
+}
+
+/// Loads a previously saved backup into the run's context
+pub struct RestorePreferencesBackupStep;
+
+#[async_trait(?Send)]
+impl MigrationStep for RestorePreferencesBackupStep {
+    fn plan_step(&self) -> PlanStep {
+        PlanStep::RestorePreferencesBackup
+    }
+
+    fn describe(&self) -> &'static str {
+        "Load the most recent saved preferences backup"
+    }
+
+    async fn execute(&self, ctx: &mut StepContext<'_>) -> Result<(), String> {
+        (ctx.dispatch)(MigrationAction::SetMigrationStep(
+            "Loading saved preferences backup...".to_string(),
+        ));
+        let backup = load_preferences_backup(ctx.old.did()).await?;
+        console_info!(
+            "[Migration] Restored preferences backup {} ({} preferences)",
+            backup.file_name(),
+            backup.categories().len()
+        );
+        ctx.preferences = Some(backup);
+        Ok(())
+    }
+}
+
+/// Writes the context's preferences to the new PDS
+pub struct ImportPreferencesStep;
+
+#[async_trait(?Send)]
+impl MigrationStep for ImportPreferencesStep {
+    fn plan_step(&self) -> PlanStep {
+        PlanStep::ImportPreferences
+    }
+
+    fn describe(&self) -> &'static str {
+        "Import preferences to the new PDS"
+    }
+
+    async fn execute(&self, ctx: &mut StepContext<'_>) -> Result<(), String> {
+        let target = ctx.target(self.plan_step())?;
+        let backup = ctx
+            .preferences
+            .as_ref()
+            .ok_or("There are no exported or restored preferences to import")?;
+        import_preferences_client_side(target, backup, ctx.dispatch, ctx.state).await
+    }
+
+    /// Read the preferences back; an empty result after a non-empty import means the
+    /// target accepted and discarded them. Individual dropped or changed categories
+    /// only go into the round-trip report, since the backup covers those.
+    async fn verify(&self, ctx: &StepContext<'_>) -> Result<(), String> {
+        let (Some(backup), Some(target)) = (ctx.preferences.as_ref(), ctx.new.as_ref()) else {
+            return Ok(());
+        };
+        let expected = backup.categories();
+        if expected.is_empty() {
+            return Ok(());
+        }
+
+        let response = target
+            .export_preferences()
+            .await
+            .map_err(|e| format!("Could not read preferences back from the new PDS: {}", e))?;
+        let stored = PreferencesBackup::from_export(
+            target.did(),
+            target.host(),
+            response.preferences_json.as_deref().unwrap_or("{}"),
+        )
+        .map(|readback| readback.export)
+        .unwrap_or_default();
+
+        let report = compare_preferences(&backup.export, &stored);
+        let kept = report.categories.len() - report.with_outcome(RoundTripOutcome::Dropped).count();
+        if kept == 0 {
+            (ctx.dispatch)(MigrationAction::SetPreferencesRoundTrip(Some(report)));
+            return Err(format!(
+                "The new PDS accepted {} preferences but stored none",
+                expected.len()
+            ));
+        }
+        if !report.is_complete() {
+            console_warn!(
+                "[Migration] Preferences that did not round-trip: {:?}",
+                report
+                    .categories
+                    .iter()
+                    .filter(|c| c.outcome != RoundTripOutcome::RoundTripped)
+                    .map(|c| format!("{} ({:?})", c.category, c.outcome))
+                    .collect::<Vec<_>>()
+            );
+        }
+        (ctx.dispatch)(MigrationAction::SetPreferencesRoundTrip(Some(report)));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migration::steps::mock_pds::{session, MockPds};
+    use std::cell::RefCell;
+
+    fn import_and_verify(pds: &MockPds) -> (Result<(), String>, Vec<MigrationAction>) {
+        let old = session("https://old.example");
+        let new = session("https://new.example");
+        let state = MigrationState::default();
+        let actions = RefCell::new(Vec::new());
+        let dispatch = |action: MigrationAction| actions.borrow_mut().push(action);
+        let backup = PreferencesBackup::from_export(
+            &old.did,
+            &old.pds,
+            r#"{"preferences":[{"$type":"app.bsky.actor.defs#adultContentPref","enabled":false}]}"#,
+        )
+        .unwrap();
+
+        let pds: &dyn PdsApi = pds;
+        let mut ctx = StepContext {
+            old: OldPds::new(pds, old.clone()),
+            new: Some(NewPds::new(pds, new)),
+            state: &state,
+            dispatch: &dispatch,
+            handler: None,
+            preferences: Some(backup),
+        };
+        let result = futures::executor::block_on(async {
+            ImportPreferencesStep.execute(&mut ctx).await?;
+            ImportPreferencesStep.verify(&ctx).await
+        });
+        (result, actions.into_inner())
+    }
+
+    #[test]
+    fn test_import_step_writes_and_verifies_preferences() {
+        let pds = MockPds::default();
+        let (result, actions) = import_and_verify(&pds);
+
+        assert_eq!(result, Ok(()));
+        assert!(pds
+            .imported_preferences
+            .borrow()
+            .as_deref()
+            .unwrap()
+            .contains("adultContentPref"));
+        assert!(actions.iter().any(|action| matches!(
+            action,
+            MigrationAction::SetPreferencesProgress(progress) if progress.import_complete
+        )));
+        assert!(actions.iter().any(|action| matches!(
+            action,
+            MigrationAction::SetPreferencesRoundTrip(Some(report)) if report.is_complete()
+        )));
+    }
+
+    #[test]
+    fn test_import_step_verify_catches_discarded_preferences() {
+        let pds = MockPds {
+            discard_imports: true,
+            ..MockPds::default()
+        };
+        let (result, _) = import_and_verify(&pds);
+        assert!(result.unwrap_err().contains("stored none"));
+    }
+}
@@ -0,0 +1,287 @@
+//! Live progress for repository imports the target PDS indexes asynchronously
+//!
+//! Some PDS implementations accept the CAR upload and keep indexing afterwards,
+//! which otherwise looks like a silent multi-minute wait. While the import runs,
+//! and for a bounded time after it returns, the target's `checkAccountStatus` is
+//! polled and compared with the source's counts. `describeRepo` on both sides
+//! gives a per-collection breakdown where the PDS supports it.
+
+use serde::{Deserialize, Serialize};
+
+use crate::migration::types::MigrationAction;
+use crate::services::client::{ClientAccountStatusResponse, ClientSessionCredentials, PdsClient};
+use crate::utils::platform::sleep_ms;
+use crate::{console_debug, console_info, console_warn};
+use dioxus::prelude::*;
+
+/// Delay between status polls
+pub const IMPORT_POLL_INTERVAL_MS: u64 = 2_000;
+
+/// Polls after the import request returns before giving up on indexing catching up
+pub const MAX_POST_IMPORT_POLLS: u32 = 30;
+
+/// Consecutive unchanged polls after which indexing is treated as finished
+const SETTLED_AFTER_STABLE_POLLS: u32 = 3;
+
+/// Whether a collection from the source repository has appeared on the target
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CollectionProgress {
+    pub nsid: String,
+    pub imported: bool,
+}
+
+/// Target-side indexing counters, with the source's counts as the goal
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RepoImportStatus {
+    pub indexed_records: u64,
+    pub repo_blocks: u64,
+    pub expected_records: Option<u64>,
+    pub expected_blocks: Option<u64>,
+    /// Empty when describeRepo isn't available
+    pub collections: Vec<CollectionProgress>,
+    pub polls: u32,
+    #[serde(default)]
+    stable_polls: u32,
+}
+
+impl RepoImportStatus {
+    pub fn new(expected_records: Option<u64>, expected_blocks: Option<u64>) -> Self {
+        Self {
+            expected_records,
+            expected_blocks,
+            ..Self::default()
+        }
+    }
+
+    /// Fold in a target status poll
+    pub fn observe(&mut self, status: &ClientAccountStatusResponse) {
+        let indexed_records = status.indexed_records.unwrap_or_default().max(0) as u64;
+        let repo_blocks = status.repo_blocks.unwrap_or_default().max(0) as u64;
+
+        if self.polls > 0
+            && indexed_records == self.indexed_records
+            && repo_blocks == self.repo_blocks
+        {
+            self.stable_polls += 1;
+        } else {
+            self.stable_polls = 0;
+        }
+        self.indexed_records = indexed_records;
+        self.repo_blocks = repo_blocks;
+        self.polls += 1;
+    }
+
+    /// Fraction of the source's records indexed on the target, if the source count is known
+    pub fn record_ratio(&self) -> Option<f64> {
+        self.expected_records
+            .filter(|expected| *expected > 0)
+            .map(|expected| (self.indexed_records as f64 / expected as f64).min(1.0))
+    }
+
+    /// Indexing has caught up with the source, or stopped moving
+    pub fn is_settled(&self) -> bool {
+        let caught_up = self
+            .expected_records
+            .is_some_and(|expected| self.indexed_records >= expected);
+        caught_up || self.stable_polls >= SETTLED_AFTER_STABLE_POLLS
+    }
+}
+
+/// Mark which of the source's collections the target already lists, in source order
+pub fn collection_breakdown(
+    source_collections: &[String],
+    target_collections: &[String],
+) -> Vec<CollectionProgress> {
+    source_collections
+        .iter()
+        .map(|nsid| CollectionProgress {
+            nsid: nsid.clone(),
+            imported: target_collections.contains(nsid),
+        })
+        .collect()
+}
+
+/// Source-side counts and collections that the import is measured against
+pub async fn source_import_baseline(
+    old_session: &ClientSessionCredentials,
+) -> (RepoImportStatus, Vec<String>) {
+    let pds_client = PdsClient::new();
+
+    let status = match pds_client.check_account_status(old_session).await {
+        Ok(status) if status.success => RepoImportStatus::new(
+            status.indexed_records.map(|n| n.max(0) as u64),
+            status.repo_blocks.map(|n| n.max(0) as u64),
+        ),
+        Ok(status) => {
+            console_warn!(
+                "[Migration] Source account status unavailable: {}",
+                status.message
+            );
+            RepoImportStatus::default()
+        }
+        Err(e) => {
+            console_warn!("[Migration] Source account status unavailable: {}", e);
+            RepoImportStatus::default()
+        }
+    };
+
+    let collections = pds_client
+        .describe_repo_collections(old_session, &old_session.did)
+        .await
+        .unwrap_or_else(|e| {
+            console_debug!("[Migration] Source describeRepo unavailable: {}", e);
+            Vec::new()
+        });
+
+    (status, collections)
+}
+
+/// Poll the target once and dispatch the updated status
+async fn poll_once(
+    pds_client: &PdsClient,
+    new_session: &ClientSessionCredentials,
+    source_collections: &[String],
+    status: &mut RepoImportStatus,
+    dispatch: &EventHandler<MigrationAction>,
+) {
+    match pds_client.check_account_status(new_session).await {
+        Ok(account) if account.success => status.observe(&account),
+        Ok(account) => {
+            console_debug!("[Migration] Import status poll failed: {}", account.message);
+            return;
+        }
+        Err(e) => {
+            console_debug!("[Migration] Import status poll failed: {}", e);
+            return;
+        }
+    }
+
+    if !source_collections.is_empty() {
+        if let Ok(target_collections) = pds_client
+            .describe_repo_collections(new_session, &new_session.did)
+            .await
+        {
+            status.collections = collection_breakdown(source_collections, &target_collections);
+        }
+    }
+
+    dispatch.call(MigrationAction::SetRepoImportStatus(Some(status.clone())));
+}
+
+/// Poll until dropped; meant to run alongside the import request
+pub async fn poll_import_progress(
+    new_session: &ClientSessionCredentials,
+    source_collections: &[String],
+    status: &mut RepoImportStatus,
+    dispatch: &EventHandler<MigrationAction>,
+) {
+    let pds_client = PdsClient::new();
+    loop {
+        sleep_ms(IMPORT_POLL_INTERVAL_MS).await;
+        poll_once(
+            &pds_client,
+            new_session,
+            source_collections,
+            status,
+            dispatch,
+        )
+        .await;
+    }
+}
+
+/// Keep polling after the import returned until the target's indexing settles
+pub async fn wait_for_import_indexing(
+    new_session: &ClientSessionCredentials,
+    source_collections: &[String],
+    status: &mut RepoImportStatus,
+    dispatch: &EventHandler<MigrationAction>,
+) {
+    let pds_client = PdsClient::new();
+
+    for attempt in 0..MAX_POST_IMPORT_POLLS {
+        if attempt > 0 {
+            sleep_ms(IMPORT_POLL_INTERVAL_MS).await;
+        }
+        poll_once(
+            &pds_client,
+            new_session,
+            source_collections,
+            status,
+            dispatch,
+        )
+        .await;
+        if status.is_settled() {
+            console_info!(
+                "[Migration] Target indexed {} records and {} blocks",
+                status.indexed_records,
+                status.repo_blocks
+            );
+            return;
+        }
+        dispatch.call(MigrationAction::SetMigrationStep(format!(
+            "Target PDS is still indexing the repository: {} records so far...",
+            status.indexed_records
+        )));
+    }
+
+    console_warn!(
+        "[Migration] Target was still indexing after {} polls ({} records, {} blocks); continuing",
+        MAX_POST_IMPORT_POLLS,
+        status.indexed_records,
+        status.repo_blocks
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(indexed_records: i64, repo_blocks: i64) -> ClientAccountStatusResponse {
+        ClientAccountStatusResponse {
+            success: true,
+            indexed_records: Some(indexed_records),
+            repo_blocks: Some(repo_blocks),
+            ..ClientAccountStatusResponse::default()
+        }
+    }
+
+    #[test]
+    fn test_settles_when_caught_up_with_source() {
+        let mut status = RepoImportStatus::new(Some(100), Some(250));
+        status.observe(&account(40, 90));
+        assert!(!status.is_settled());
+        assert_eq!(status.record_ratio(), Some(0.4));
+
+        status.observe(&account(100, 250));
+        assert!(status.is_settled());
+    }
+
+    #[test]
+    fn test_settles_when_counts_stop_moving() {
+        // Source counts unknown, so only stability can end the wait
+        let mut status = RepoImportStatus::default();
+        status.observe(&account(10, 20));
+        for _ in 0..SETTLED_AFTER_STABLE_POLLS - 1 {
+            status.observe(&account(10, 20));
+            assert!(!status.is_settled());
+        }
+        status.observe(&account(10, 20));
+        assert!(status.is_settled());
+        assert_eq!(status.record_ratio(), None);
+    }
+
+    #[test]
+    fn test_collection_breakdown_keeps_source_order() {
+        let source = vec![
+            "app.bsky.actor.profile".to_string(),
+            "app.bsky.feed.post".to_string(),
+            "app.bsky.graph.follow".to_string(),
+        ];
+        let target = vec!["app.bsky.feed.post".to_string()];
+
+        let breakdown = collection_breakdown(&source, &target);
+        let imported: Vec<bool> = breakdown.iter().map(|c| c.imported).collect();
+        assert_eq!(imported, vec![false, true, false]);
+        assert_eq!(breakdown[0].nsid, "app.bsky.actor.profile");
+    }
+}
@@ -1,28 +1,47 @@
 //! Repository migration step - WASM-first implementation
 
-use crate::services::client::{ClientSessionCredentials, PdsClient, RefreshableSessionProvider};
-use crate::services::streaming::{BufferedStorage, RepoSource, RepoTarget, SyncOrchestrator};
+use async_trait::async_trait;
+
+use crate::services::client::{
+    clear_import_failure, last_import_failure, NewPds, OldPds, PdsClient,
+    RefreshableSessionProvider,
+};
+use crate::services::config::{get_global_config, RetryConfig, RetryPhase};
+use crate::services::streaming::{
+    detect_storage_capabilities, BufferedStorage, DiskStorage, LocalStorageCache,
+    MinimalFlowLimits, RepoSource, RepoTarget, SelectedStorage, SyncOrchestrator,
+};
 use crate::{console_debug, console_error, console_info, console_warn};
 use dioxus::prelude::*;
 use std::sync::Arc;
 
+use crate::migration::plan::PlanStep;
+use crate::migration::progress::ThrottledDispatch;
+use crate::migration::steps::repo_import::{
+    poll_import_progress, source_import_baseline, wait_for_import_indexing,
+};
+use crate::migration::steps::traits::{MigrationStep, StepContext};
 use crate::migration::types::*;
 
 /// Migrate repository from old PDS to new PDS using new streaming architecture
 // NEWBOLD.md Steps: goat repo export $ACCOUNTDID (line 76) + goat repo import ./did:plc:do2ar6uqzrvyzq3wevji6fbe.20250625142552.car (line 81)
 // Implements: Complete repository migration using streaming with channel-tee pattern
-pub async fn migrate_repository_client_side(
-    old_session: &ClientSessionCredentials,
-    new_session: &ClientSessionCredentials,
+pub async fn migrate_repository_client_side<C>(
+    old: &OldPds<C>,
+    new: &NewPds<C>,
     dispatch: &EventHandler<MigrationAction>,
+    retry: RetryConfig,
 ) -> Result<(), String> {
+    let (old_session, new_session) = (old.session(), new.session());
     console_info!("[Migration] Starting repository migration using streaming architecture");
     dispatch.call(MigrationAction::SetMigrationStep(
         "Starting repository migration with streaming...".to_string(),
     ));
 
     // Create WASM streaming orchestrator
-    let orchestrator = SyncOrchestrator::new();
+    let orchestrator = SyncOrchestrator::with_retry(retry, RetryPhase::Repo, RetryPhase::Repo)
+        .with_hosts(&old_session.pds, &new_session.pds)
+        .with_stream_checksums();
 
     // Create PdsClient for session refresh
     let pds_client = Arc::new(PdsClient::new());
@@ -34,9 +53,21 @@ pub async fn migrate_repository_client_side(
     // Create source, target, and storage using WASM clients
     let source = RepoSource::new(old_session);
     let target = RepoTarget::new(new_session_provider);
-    let storage = BufferedStorage::new(format!("repos/{}", old_session.did))
-        .await
-        .map_err(|e| format!("Failed to create storage: {}", e))?;
+    let capabilities = detect_storage_capabilities().await;
+    let storage = if capabilities.requires_minimal_flow() {
+        console_warn!("[Migration] No OPFS or IndexedDB; buffering the repository in memory");
+        SelectedStorage::Minimal(LocalStorageCache::new(MinimalFlowLimits::default()))
+    } else if capabilities.disk {
+        SelectedStorage::Disk(
+            DiskStorage::open("repos").map_err(|e| format!("Failed to create storage: {}", e))?,
+        )
+    } else {
+        SelectedStorage::Full(
+            BufferedStorage::new(format!("repos/{}", old_session.did))
+                .await
+                .map_err(|e| format!("Failed to create storage: {}", e))?,
+        )
+    };
 
     // Update progress - starting export
     console_info!("[Migration] Step 7: Streaming repository from old PDS");
@@ -51,13 +82,29 @@ pub async fn migrate_repository_client_side(
         error: None,
     };
     dispatch.call(MigrationAction::SetRepoProgress(repo_progress));
+    clear_import_failure();
+    dispatch.call(MigrationAction::SetRepoImportFailure(None));
+    dispatch.call(MigrationAction::SetRepoCarChecksum(None));
+
+    // Source counts are the goal the target's indexing progress is measured against
+    let (mut import_status, source_collections) = source_import_baseline(old_session).await;
+    dispatch.call(MigrationAction::SetRepoImportStatus(Some(
+        import_status.clone(),
+    )));
+
+    // Per-chunk progress is coalesced so fast transfers don't flood the renderer
+    let handler = *dispatch;
+    let throttled = ThrottledDispatch::new(
+        move |action| handler.call(action),
+        get_global_config().progress.max_updates_per_second,
+    );
 
     // Create progress callback to update repo progress in real-time
     // Wrapper to convert old callback signature to new ProgressUpdate format
     let legacy_progress_callback = {
-        let dispatch_clone = *dispatch;
+        let throttled = throttled.clone();
         move |current_item_id: Option<String>, bytes_processed: u64, total_estimate: u64| {
-            console_info!(
+            console_debug!(
                 "[Migration] Progress callback invoked: {} bytes processed, {} estimated total",
                 bytes_processed,
                 total_estimate
@@ -74,7 +121,7 @@ pub async fn migrate_repository_client_side(
                 "[Migration] Dispatching SetRepoProgress with {} bytes",
                 bytes_processed
             );
-            dispatch_clone.call(MigrationAction::SetRepoProgress(repo_progress));
+            throttled.dispatch(MigrationAction::SetRepoProgress(repo_progress));
 
             // Also update BlobProgress during repository streaming since repos contain blobs
             // Estimate blob counts based on data size (rough approximation: ~10KB average blob size)
@@ -84,7 +131,7 @@ pub async fn migrate_repository_client_side(
                 0
             };
 
-            let _current_time = js_sys::Date::now() as u64;
+            let _current_time = crate::utils::platform::now_ms() as u64;
             let blob_progress = BlobProgress {
                 total_blobs: if total_estimate > 0 {
                     std::cmp::max(1, (total_estimate / 10_000) as u32)
@@ -107,7 +154,7 @@ pub async fn migrate_repository_client_side(
                 estimated_blobs,
                 bytes_processed
             );
-            dispatch_clone.call(MigrationAction::SetBlobProgress(blob_progress));
+            throttled.dispatch(MigrationAction::SetBlobProgress(blob_progress));
 
             // Also update migration step with progress
             if bytes_processed > 0 {
@@ -124,7 +171,7 @@ pub async fn migrate_repository_client_side(
                     )
                 };
                 console_debug!("[Migration] Dispatching SetMigrationStep: {}", step_message);
-                dispatch_clone.call(MigrationAction::SetMigrationStep(step_message));
+                throttled.dispatch(MigrationAction::SetMigrationStep(step_message));
             } else {
                 console_warn!("[Migration] Progress callback invoked with 0 bytes processed");
             }
@@ -156,13 +203,32 @@ pub async fn migrate_repository_client_side(
             // Return a future that we can await
             orchestrator.sync_with_tee(source, target, storage, Some(progress_callback))
         })) {
-            Ok(future) => future.await,
+            Ok(future) => {
+                // Poll the target's indexing counters for as long as the import runs
+                let poller = poll_import_progress(
+                    new_session,
+                    &source_collections,
+                    &mut import_status,
+                    dispatch,
+                );
+                match futures::future::select(Box::pin(future), Box::pin(poller)).await {
+                    futures::future::Either::Left((result, _)) => result,
+                    futures::future::Either::Right(((), future)) => future.await,
+                }
+            }
             Err(_) => {
                 console_error!("[Migration] Panic occurred during sync operation setup");
                 Err("Sync operation panicked during setup".into())
             }
         }
     };
+    throttled.flush();
+
+    // The orchestrator reports a refused import as a failed item, not an error
+    let migration_result = migration_result.and_then(|result| match result.failed_items.first() {
+        Some(failure) => Err(failure.error.clone().into()),
+        None => Ok(result),
+    });
 
     match migration_result {
         Ok(result) => {
@@ -171,6 +237,19 @@ pub async fn migrate_repository_client_side(
                 result.total_bytes_processed
             );
 
+            dispatch.call(MigrationAction::SetRepoCarChecksum(
+                result.checksums.values().next().cloned(),
+            ));
+
+            // Some PDSes keep indexing after importRepo returns
+            wait_for_import_indexing(
+                new_session,
+                &source_collections,
+                &mut import_status,
+                dispatch,
+            )
+            .await;
+
             // Update progress - both export and import complete
             let repo_progress = RepoProgress {
                 export_complete: true,
@@ -184,18 +263,17 @@ pub async fn migrate_repository_client_side(
                 "Repository migration completed successfully".to_string(),
             ));
 
-            if !result.failed_items.is_empty() {
-                console_info!(
-                    "[Migration] Warning: {} items failed during migration",
-                    result.failed_items.len()
-                );
-            }
-
             Ok(())
         }
         Err(e) => {
-            let error_msg = format!("Repository streaming migration failed: {}", e);
+            // The server's own explanation beats the transfer error wrapped around it
+            let diagnosis = last_import_failure();
+            let error_msg = match &diagnosis {
+                Some(diagnosis) => diagnosis.summary(),
+                None => format!("Repository streaming migration failed: {}", e),
+            };
             console_info!("[Migration] {}", error_msg);
+            dispatch.call(MigrationAction::SetRepoImportFailure(diagnosis));
 
             // Update progress with error
             let repo_progress = RepoProgress {
@@ -210,3 +288,39 @@ pub async fn migrate_repository_client_side(
         }
     }
 }
+
+/// Streams the repository CAR from the old PDS into the new one
+pub struct RepositoryStep;
+
+#[async_trait(?Send)]
+impl MigrationStep for RepositoryStep {
+    fn plan_step(&self) -> PlanStep {
+        PlanStep::Repository
+    }
+
+    fn describe(&self) -> &'static str {
+        "Stream the repository from the old PDS into the new one"
+    }
+
+    async fn execute(&self, ctx: &mut StepContext<'_>) -> Result<(), String> {
+        let target = ctx.target(self.plan_step())?;
+        let handler = ctx.handler(self.plan_step())?;
+        if ctx
+            .state
+            .resume_reconciliation
+            .as_ref()
+            .is_some_and(|reconciliation| reconciliation.repo_already_imported)
+        {
+            // An interrupted attempt already got the repository across; the
+            // verification step still compares it with the source
+            console_info!("[Migration] New PDS already has the latest commit; skipping the import");
+            (ctx.dispatch)(MigrationAction::SetRepoProgress(RepoProgress {
+                export_complete: true,
+                import_complete: true,
+                ..RepoProgress::default()
+            }));
+            return Ok(());
+        }
+        migrate_repository_client_side(&ctx.old, target, &handler, ctx.state.retry_config()).await
+    }
+}
@@ -0,0 +1,67 @@
+//! Common interface for migration steps
+//!
+//! Each step module implements [`MigrationStep`] for the plan steps it owns.
+//! Everything a step needs arrives through [`StepContext`]: the old and new
+//! PDS as [`OldPds`] / [`NewPds`] over a `&dyn PdsApi`, the state snapshot and
+//! a dispatch function, so a step can be exercised natively against a mock PDS
+//! and can't send a new-PDS write with the old session.
+
+use async_trait::async_trait;
+use dioxus::prelude::EventHandler;
+
+use crate::migration::plan::PlanStep;
+use crate::migration::preferences_backup::PreferencesBackup;
+use crate::migration::types::{MigrationAction, MigrationState};
+use crate::services::client::{NewPds, OldPds, PdsApi};
+
+/// Inputs shared by every step of one plan run
+pub struct StepContext<'a> {
+    pub old: OldPds<&'a dyn PdsApi>,
+    /// Absent for plans that never write to the new PDS
+    pub new: Option<NewPds<&'a dyn PdsApi>>,
+    pub state: &'a MigrationState,
+    pub dispatch: &'a dyn Fn(MigrationAction),
+    /// UI handler for the streaming steps, whose progress callbacks outlive a borrow
+    pub handler: Option<EventHandler<MigrationAction>>,
+    /// Preferences exported or restored earlier in the run
+    pub preferences: Option<PreferencesBackup>,
+}
+
+impl<'a> StepContext<'a> {
+    /// The new PDS, or an error naming the step that needed it
+    pub fn target(&self, step: PlanStep) -> Result<&NewPds<&'a dyn PdsApi>, String> {
+        self.new.as_ref().ok_or_else(|| {
+            format!(
+                "{} needs an account on the new PDS, but there is no session for it",
+                step.label()
+            )
+        })
+    }
+
+    pub fn handler(&self, step: PlanStep) -> Result<EventHandler<MigrationAction>, String> {
+        self.handler
+            .ok_or_else(|| format!("{} can only run from the migration UI", step.label()))
+    }
+}
+
+/// One unit of migration work the orchestrator can run, check and undo
+#[async_trait(?Send)]
+pub trait MigrationStep {
+    /// The plan step this implements
+    fn plan_step(&self) -> PlanStep;
+
+    /// One-line description for logs and diagnostics
+    fn describe(&self) -> &'static str;
+
+    async fn execute(&self, ctx: &mut StepContext<'_>) -> Result<(), String>;
+
+    /// Confirm the step's effect is visible; runs right after `execute`
+    async fn verify(&self, _ctx: &StepContext<'_>) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Undo what can be undone after a later required step fails
+    async fn rollback(&self, _ctx: &StepContext<'_>) -> Result<(), String> {
+        Ok(())
+    }
+}
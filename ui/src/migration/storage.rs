@@ -1,8 +1,15 @@
 use crate::migration::*;
+use crate::utils::byte_format::ByteUnits;
+use crate::utils::platform::{storage_delete, storage_get, storage_set, StorageArea};
 use gloo_storage::errors::StorageError;
-use gloo_storage::{LocalStorage, Storage};
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "web")]
+use crate::migration::old_account_deletion::ScheduledDeletion;
+#[cfg(feature = "web")]
+use crate::migration::old_account_grace::ScheduledDeactivation;
+use crate::migration::plc_token_delivery::PlcTokenDelivery;
+use crate::migration::post_migration_checklist::ChecklistProgress;
 #[cfg(feature = "web")]
 use crate::services::client::ClientSessionCredentials;
 
@@ -44,55 +51,135 @@ pub struct LocalStorageManager;
 impl LocalStorageManager {
     // Session Management
     pub fn store_old_session(session: &SessionCredentials) -> Result<(), StorageError> {
-        LocalStorage::set("old_pds_session", session)
+        storage_set(StorageArea::Local, "old_pds_session", session)
     }
 
     pub fn store_new_session(session: &SessionCredentials) -> Result<(), StorageError> {
-        LocalStorage::set("new_pds_session", session)
+        storage_set(StorageArea::Local, "new_pds_session", session)
     }
 
     pub fn get_old_session() -> Result<SessionCredentials, StorageError> {
-        LocalStorage::get("old_pds_session")
+        storage_get(StorageArea::Local, "old_pds_session")
     }
 
     pub fn get_new_session() -> Result<SessionCredentials, StorageError> {
-        LocalStorage::get("new_pds_session")
+        storage_get(StorageArea::Local, "new_pds_session")
     }
 
     // PLC Operation Management
     pub fn store_plc_operation(data: &PlcOperationData) -> Result<(), StorageError> {
-        LocalStorage::set("plc_operation_data", data)
+        storage_set(StorageArea::Local, "plc_operation_data", data)
     }
 
     pub fn get_plc_operation() -> Result<PlcOperationData, StorageError> {
-        LocalStorage::get("plc_operation_data")
+        storage_get(StorageArea::Local, "plc_operation_data")
     }
 
     // Preferences Backup
     pub fn store_user_preferences(preferences: &serde_json::Value) -> Result<(), StorageError> {
-        LocalStorage::set("user_preferences", preferences)
+        storage_set(StorageArea::Local, "user_preferences", preferences)
     }
 
     pub fn get_user_preferences() -> Result<serde_json::Value, StorageError> {
-        LocalStorage::get("user_preferences")
+        storage_get(StorageArea::Local, "user_preferences")
     }
 
     // Migration Progress Tracking
     pub fn store_migration_progress(progress: &MigrationProgressData) -> Result<(), StorageError> {
-        LocalStorage::set("migration_progress", progress)
+        storage_set(StorageArea::Local, "migration_progress", progress)
     }
 
     pub fn get_migration_progress() -> Result<MigrationProgressData, StorageError> {
-        LocalStorage::get("migration_progress")
+        storage_get(StorageArea::Local, "migration_progress")
+    }
+
+    // PLC token request time, so Form 4 keeps counting after a refresh
+    pub fn store_plc_token_delivery(delivery: &PlcTokenDelivery) -> Result<(), StorageError> {
+        storage_set(StorageArea::Local, "plc_token_delivery", delivery)
+    }
+
+    pub fn get_plc_token_delivery() -> Result<PlcTokenDelivery, StorageError> {
+        storage_get(StorageArea::Local, "plc_token_delivery")
+    }
+
+    // Text-only progress display (kept across clear_migration_data)
+    pub fn store_text_only_progress(enabled: bool) -> Result<(), StorageError> {
+        storage_set(StorageArea::Local, "text_only_progress", enabled)
+    }
+
+    pub fn get_text_only_progress() -> Result<bool, StorageError> {
+        storage_get(StorageArea::Local, "text_only_progress")
+    }
+
+    // Binary or decimal size units (kept across clear_migration_data)
+    pub fn store_byte_units(units: ByteUnits) -> Result<(), StorageError> {
+        storage_set(StorageArea::Local, "byte_units", units)
+    }
+
+    pub fn get_byte_units() -> Result<ByteUnits, StorageError> {
+        storage_get(StorageArea::Local, "byte_units")
+    }
+
+    // Console log filter, e.g. "info,services::streaming=debug" (kept across clear_migration_data)
+    pub fn store_log_filter(spec: &str) -> Result<(), StorageError> {
+        storage_set(StorageArea::Local, "log_filter", spec)
+    }
+
+    pub fn get_log_filter() -> Result<String, StorageError> {
+        storage_get(StorageArea::Local, "log_filter")
+    }
+
+    // Old Account Deletion Reminder (kept across clear_migration_data)
+    #[cfg(feature = "web")]
+    pub fn store_scheduled_deletion(schedule: &ScheduledDeletion) -> Result<(), StorageError> {
+        storage_set(StorageArea::Local, "old_account_deletion", schedule)
+    }
+
+    #[cfg(feature = "web")]
+    pub fn get_scheduled_deletion() -> Result<ScheduledDeletion, StorageError> {
+        storage_get(StorageArea::Local, "old_account_deletion")
+    }
+
+    #[cfg(feature = "web")]
+    pub fn clear_scheduled_deletion() {
+        storage_delete(StorageArea::Local, "old_account_deletion");
+    }
+
+    // Old Account Deactivation Reminder (kept across clear_migration_data)
+    #[cfg(feature = "web")]
+    pub fn store_scheduled_deactivation(
+        schedule: &ScheduledDeactivation,
+    ) -> Result<(), StorageError> {
+        storage_set(StorageArea::Local, "old_account_deactivation", schedule)
+    }
+
+    #[cfg(feature = "web")]
+    pub fn get_scheduled_deactivation() -> Result<ScheduledDeactivation, StorageError> {
+        storage_get(StorageArea::Local, "old_account_deactivation")
+    }
+
+    #[cfg(feature = "web")]
+    pub fn clear_scheduled_deactivation() {
+        storage_delete(StorageArea::Local, "old_account_deactivation");
+    }
+
+    // Post-migration checklist (kept across clear_migration_data)
+    pub fn store_checklist_progress(progress: &ChecklistProgress) -> Result<(), StorageError> {
+        storage_set(StorageArea::Local, "post_migration_checklist", progress)
+    }
+
+    pub fn get_checklist_progress() -> Result<ChecklistProgress, StorageError> {
+        storage_get(StorageArea::Local, "post_migration_checklist")
     }
 
     // Cleanup
     pub fn clear_migration_data() -> Result<(), StorageError> {
-        LocalStorage::delete("old_pds_session");
-        LocalStorage::delete("new_pds_session");
-        LocalStorage::delete("plc_operation_data");
-        LocalStorage::delete("user_preferences");
-        LocalStorage::delete("migration_progress");
+        storage_delete(StorageArea::Local, "old_pds_session");
+        storage_delete(StorageArea::Local, "new_pds_session");
+        storage_delete(StorageArea::Local, "plc_operation_data");
+        storage_delete(StorageArea::Local, "plc_token_delivery");
+        storage_delete(StorageArea::Local, "user_preferences");
+        storage_delete(StorageArea::Local, "migration_progress");
         Ok(())
     }
 
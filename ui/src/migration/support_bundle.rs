@@ -0,0 +1,495 @@
+//! Encrypted support bundles for escalating failed migrations
+//!
+//! A bundle captures a sanitized state snapshot, the redacted action log, the settings
+//! of the active configuration and recent HTTP traces. It is encrypted with AES-256-GCM under a key
+//! derived from a user-chosen passphrase, so the file and the passphrase can be sent to
+//! maintainers over separate channels.
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::VecDeque;
+use thiserror::Error;
+
+use super::car_diff::RepoCarDiff;
+use super::continuous_sync::ContinuousSyncStatus;
+use super::event_log::MigrationEventLog;
+use super::password_manager::CredentialSaveStatus;
+use super::plc_token_delivery::PlcTokenDelivery;
+use super::service_accounts::ServiceAccountProfile;
+use super::source_endpoint::SourceEndpointChange;
+use super::steps::blob_enumeration::BlobEnumerationSummary;
+use super::types::*;
+use crate::services::client::http_trace::{recent_http_traces, HttpTrace};
+use crate::services::client::types::current_time_secs;
+use crate::services::client::{
+    last_bandwidth_report, last_body_limit_report, last_cors_report, last_handle_resolution,
+    last_route_report, last_target_health, BandwidthReport, BodyLimitReport, CorsReport,
+    HandleResolution, RouteReport, TargetHealthReport,
+};
+use crate::services::client::{ImportDiagnosis, PropagationReport};
+use crate::services::config::{
+    get_global_config, BlobEnumerationMethod, BlobNotFoundPolicy, MigrationConfig,
+    VerificationLevel,
+};
+use crate::services::streaming::{pipeline_stats, OperationStats, StorageUsage};
+
+/// Envelope format identifier written into every exported file
+pub const SUPPORT_BUNDLE_FORMAT: &str = "tektite-support-bundle";
+pub const SUPPORT_BUNDLE_VERSION: u32 = 1;
+
+/// PBKDF2-HMAC-SHA256 rounds used for exported bundles; envelopes asking for
+/// more are refused rather than tying up the tab
+pub const SUPPORT_BUNDLE_KDF_ITERATIONS: u32 = 100_000;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+#[derive(Error, Debug)]
+pub enum SupportBundleError {
+    #[error("Passphrase must not be empty")]
+    EmptyPassphrase,
+
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[error("Unsupported support bundle: {0}")]
+    UnsupportedFormat(String),
+
+    #[error("Failed to generate random bytes: {0}")]
+    Random(String),
+
+    #[error("Invalid encoding: {0}")]
+    Encoding(String),
+
+    #[error("Encryption failed")]
+    Encryption,
+
+    #[error("Decryption failed - wrong passphrase or corrupted bundle")]
+    Decryption,
+}
+
+/// Sanitized view of `MigrationState`; passwords, tokens and email are never included
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    pub current_step: FormStep,
+    pub is_migrating: bool,
+    pub migration_completed: bool,
+    pub migration_error: Option<String>,
+    pub migration_step: String,
+    pub handle: String,
+    pub provider: crate::services::client::ClientPdsProvider,
+    pub new_pds_url: String,
+    pub new_handle: String,
+    pub migration_progress: MigrationProgress,
+    pub repo_progress: RepoProgress,
+    pub blob_progress: BlobProgress,
+    pub preferences_progress: PreferencesProgress,
+    pub plc_progress: PlcProgress,
+    #[serde(default)]
+    pub storage_usage: Option<StorageUsage>,
+    /// Compatibility decision taken for a target that auto-activates accounts
+    #[serde(default)]
+    pub target_auto_activation: Option<TargetAutoActivation>,
+    /// Blob enumeration method used and each listing's count
+    #[serde(default)]
+    pub blob_enumeration: Option<BlobEnumerationSummary>,
+    /// Block-level comparison of the repository exports, when full verification ran
+    #[serde(default)]
+    pub repo_car_diff: Option<RepoCarDiff>,
+    /// Why the new PDS refused the repository import, with its full response
+    #[serde(default)]
+    pub repo_import_failure: Option<ImportDiagnosis>,
+    /// sha-256 of the repository CAR as it was exported
+    #[serde(default)]
+    pub repo_car_checksum: Option<String>,
+    /// Old PDS endpoint followed after the original stopped answering
+    #[serde(default)]
+    pub source_endpoint_change: Option<SourceEndpointChange>,
+    /// When the PLC token email was requested and how often it was resent
+    #[serde(default)]
+    pub plc_token_delivery: Option<PlcTokenDelivery>,
+    /// Keep-in-sync loop status, when that plan ran
+    #[serde(default)]
+    pub continuous_sync: Option<ContinuousSyncStatus>,
+    /// Relay crawl request and propagation after activation
+    #[serde(default)]
+    pub propagation: Option<PropagationReport>,
+    /// Labeler or feed generator services preserved through the identity update
+    #[serde(default)]
+    pub service_account: Option<ServiceAccountProfile>,
+    /// Whether the new password was offered to the browser's password manager
+    #[serde(default)]
+    pub credential_save: CredentialSaveStatus,
+    pub console_messages: VecDeque<ConsoleEntry>,
+}
+
+impl StateSnapshot {
+    pub fn capture(state: &MigrationState) -> Self {
+        Self {
+            current_step: state.current_step.clone(),
+            is_migrating: state.is_migrating,
+            migration_completed: state.migration_completed,
+            migration_error: state.migration_error.clone(),
+            migration_step: state.migration_step.clone(),
+            handle: state.form1.handle.clone(),
+            provider: state.form1.provider.clone(),
+            new_pds_url: state.form2.pds_url.clone(),
+            new_handle: state.form3.handle.clone(),
+            migration_progress: state.migration_progress.clone(),
+            repo_progress: state.repo_progress.clone(),
+            blob_progress: state.blob_progress.clone(),
+            preferences_progress: state.preferences_progress.clone(),
+            plc_progress: state.plc_progress.clone(),
+            storage_usage: state.storage_usage.clone(),
+            target_auto_activation: state.target_auto_activation.clone(),
+            blob_enumeration: state.blob_enumeration.clone(),
+            repo_car_diff: state.repo_car_diff.clone(),
+            repo_import_failure: state.repo_import_failure.clone(),
+            repo_car_checksum: state.repo_car_checksum.clone(),
+            source_endpoint_change: state.source_endpoint_change.clone(),
+            plc_token_delivery: state.plc_token_delivery.clone(),
+            continuous_sync: state.continuous_sync.clone(),
+            propagation: state.propagation.clone(),
+            service_account: state.service_account.clone(),
+            credential_save: state.credential_save.clone(),
+            console_messages: state.console_messages.clone(),
+        }
+    }
+}
+
+/// Decrypted contents of a support bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupportBundle {
+    pub created_at_secs: u64,
+    pub app_version: String,
+    pub state: StateSnapshot,
+    /// Redacted actions retained by the event log, oldest first
+    pub actions: Vec<MigrationAction>,
+    /// Actions that were folded out of the log before export and cannot be replayed
+    pub dropped_action_count: u64,
+    #[serde(default, deserialize_with = "deserialize_config_snapshot")]
+    pub config: ConfigSnapshot,
+    pub http_traces: Vec<HttpTrace>,
+    #[serde(default)]
+    pub pipeline_stats: Vec<OperationStats>,
+    /// Which method won the most recent handle resolution race
+    #[serde(default)]
+    pub handle_resolution: Option<HandleResolution>,
+    /// Preflight latency measurements and the route orderings chosen from them
+    #[serde(default)]
+    pub route_report: Option<RouteReport>,
+    /// Readiness checks run against the new PDS before transferring anything
+    #[serde(default)]
+    pub target_health: Option<TargetHealthReport>,
+    /// Throughput measured to both hosts and the estimates derived from it
+    #[serde(default)]
+    pub bandwidth: Option<BandwidthReport>,
+    /// CORS and mixed-content findings for the new PDS
+    #[serde(default)]
+    pub cors: Option<CorsReport>,
+    /// Test uploads that bracket the new PDS's request body size limit
+    #[serde(default)]
+    pub body_limit: Option<BodyLimitReport>,
+}
+
+impl SupportBundle {
+    /// Collect a bundle from the live state, the action log and global diagnostics
+    pub fn capture(state: &MigrationState, event_log: &MigrationEventLog) -> Self {
+        Self {
+            created_at_secs: current_time_secs(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            state: StateSnapshot::capture(state),
+            actions: event_log.actions().map(MigrationAction::redacted).collect(),
+            dropped_action_count: event_log.total_recorded() - event_log.len() as u64,
+            config: ConfigSnapshot::capture(&get_global_config()),
+            http_traces: recent_http_traces(),
+            pipeline_stats: pipeline_stats(),
+            handle_resolution: last_handle_resolution(),
+            route_report: last_route_report(),
+            target_health: last_target_health(),
+            bandwidth: last_bandwidth_report(),
+            cors: last_cors_report(),
+            body_limit: last_body_limit_report(),
+        }
+    }
+
+    /// Encrypt the bundle into the JSON envelope written to disk
+    pub fn encrypt(&self, passphrase: &str) -> Result<String, SupportBundleError> {
+        self.encrypt_with_iterations(passphrase, SUPPORT_BUNDLE_KDF_ITERATIONS)
+    }
+
+    pub(crate) fn encrypt_with_iterations(
+        &self,
+        passphrase: &str,
+        iterations: u32,
+    ) -> Result<String, SupportBundleError> {
+        if passphrase.is_empty() {
+            return Err(SupportBundleError::EmptyPassphrase);
+        }
+
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        getrandom::getrandom(&mut salt).map_err(|e| SupportBundleError::Random(e.to_string()))?;
+        getrandom::getrandom(&mut nonce).map_err(|e| SupportBundleError::Random(e.to_string()))?;
+
+        let plaintext = serde_json::to_vec(self)?;
+        let cipher = cipher_for(passphrase, &salt, iterations)?;
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_ref())
+            .map_err(|_| SupportBundleError::Encryption)?;
+
+        let engine = base64::engine::general_purpose::STANDARD;
+        let envelope = EncryptedEnvelope {
+            format: SUPPORT_BUNDLE_FORMAT.to_string(),
+            version: SUPPORT_BUNDLE_VERSION,
+            kdf_iterations: iterations,
+            salt: engine.encode(salt),
+            nonce: engine.encode(nonce),
+            ciphertext: engine.encode(ciphertext),
+        };
+        Ok(serde_json::to_string_pretty(&envelope)?)
+    }
+
+    /// Decrypt an exported envelope
+    pub fn decrypt(envelope_json: &str, passphrase: &str) -> Result<Self, SupportBundleError> {
+        let envelope: EncryptedEnvelope = serde_json::from_str(envelope_json)?;
+        if envelope.format != SUPPORT_BUNDLE_FORMAT || envelope.version != SUPPORT_BUNDLE_VERSION {
+            return Err(SupportBundleError::UnsupportedFormat(format!(
+                "{} v{}",
+                envelope.format, envelope.version
+            )));
+        }
+
+        let engine = base64::engine::general_purpose::STANDARD;
+        let decode = |value: &str| {
+            engine
+                .decode(value)
+                .map_err(|e| SupportBundleError::Encoding(e.to_string()))
+        };
+        let salt = decode(&envelope.salt)?;
+        let nonce = decode(&envelope.nonce)?;
+        let ciphertext = decode(&envelope.ciphertext)?;
+        if nonce.len() != NONCE_LEN {
+            return Err(SupportBundleError::Encoding(
+                "invalid nonce length".to_string(),
+            ));
+        }
+
+        if envelope.kdf_iterations == 0 || envelope.kdf_iterations > SUPPORT_BUNDLE_KDF_ITERATIONS {
+            return Err(SupportBundleError::UnsupportedFormat(format!(
+                "{} key derivation rounds",
+                envelope.kdf_iterations
+            )));
+        }
+
+        let cipher = cipher_for(passphrase, &salt, envelope.kdf_iterations)?;
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+            .map_err(|_| SupportBundleError::Decryption)?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    /// Suggested file name for the exported envelope
+    pub fn file_name(&self) -> String {
+        format!("tektite-support-{}.json", self.created_at_secs)
+    }
+
+    /// Rebuild a state by replaying the recorded actions from a fresh state
+    ///
+    /// Secrets were redacted on export, so the result is for inspecting a reported
+    /// issue and cannot resume a real migration.
+    pub fn replay(&self) -> MigrationState {
+        self.actions
+            .iter()
+            .cloned()
+            .fold(MigrationState::default(), MigrationState::reduce)
+    }
+}
+
+/// Configuration that shapes how a migration behaves, field by field; host
+/// lists, mirror URLs and email text set by the deployment are left out
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConfigSnapshot {
+    pub architecture: String,
+    pub max_concurrent_transfers: usize,
+    pub initial_upload_concurrency: usize,
+    pub upload_error_threshold: f64,
+    pub blob_download_retries: u32,
+    pub blob_upload_retries: u32,
+    pub retry_total_budget_secs: u64,
+    pub idempotency_header: bool,
+    pub blob_enumeration_method: Option<BlobEnumerationMethod>,
+    pub blob_not_found_policy: Option<BlobNotFoundPolicy>,
+    pub blob_mirror_count: usize,
+    pub prefetch_max_bytes: u64,
+    pub body_limit_probe_max_bytes: u64,
+    pub verification_level: Option<VerificationLevel>,
+    pub clock_skew_secs: u64,
+    pub server_side_validation: bool,
+    pub anonymous_sync_reads: bool,
+    pub max_updates_per_second: u32,
+    pub state_updates_per_second: u32,
+    pub log_filter: String,
+    pub allowed_host_count: usize,
+    pub denied_host_count: usize,
+}
+
+impl ConfigSnapshot {
+    pub fn capture(config: &MigrationConfig) -> Self {
+        Self {
+            architecture: format!("{:?}", config.architecture),
+            max_concurrent_transfers: config.concurrency.max_concurrent_transfers,
+            initial_upload_concurrency: config.concurrency.initial_upload_concurrency,
+            upload_error_threshold: config.concurrency.upload_error_threshold,
+            blob_download_retries: config.retry.blob_download.max_retries,
+            blob_upload_retries: config.retry.blob_upload.max_retries,
+            retry_total_budget_secs: config.retry.total_budget_secs,
+            idempotency_header: config.retry.idempotency_header,
+            blob_enumeration_method: Some(config.blob.enumeration_method),
+            blob_not_found_policy: Some(config.blob.not_found_policy),
+            blob_mirror_count: config.blob.mirror_urls.len(),
+            prefetch_max_bytes: config.blob.prefetch_max_bytes,
+            body_limit_probe_max_bytes: config.blob.body_limit_probe_max_bytes,
+            verification_level: Some(config.verification.level),
+            clock_skew_secs: config.session.clock_skew_secs,
+            server_side_validation: config.session.server_side_validation,
+            anonymous_sync_reads: config.session.anonymous_sync_reads,
+            max_updates_per_second: config.progress.max_updates_per_second,
+            state_updates_per_second: config.progress.state_updates_per_second,
+            log_filter: config.logging.filter.clone(),
+            allowed_host_count: config.target_hosts.allowed_hosts.len(),
+            denied_host_count: config.target_hosts.denied_hosts.len(),
+        }
+    }
+}
+
+/// Bundles from before the snapshot carried the whole configuration as one
+/// debug string; those read as an empty snapshot
+fn deserialize_config_snapshot<'de, D>(deserializer: D) -> Result<ConfigSnapshot, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+    Ok(serde_json::from_value(value).unwrap_or_default())
+}
+
+/// On-disk representation of an encrypted bundle
+#[derive(Debug, Serialize, Deserialize)]
+struct EncryptedEnvelope {
+    format: String,
+    version: u32,
+    kdf_iterations: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+fn cipher_for(
+    passphrase: &str,
+    salt: &[u8],
+    iterations: u32,
+) -> Result<Aes256Gcm, SupportBundleError> {
+    if passphrase.is_empty() {
+        return Err(SupportBundleError::EmptyPassphrase);
+    }
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, iterations, &mut key);
+    Aes256Gcm::new_from_slice(&key).map_err(|_| SupportBundleError::Encryption)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_bundle() -> SupportBundle {
+        let mut log = MigrationEventLog::default();
+        let mut state = MigrationState::default();
+        log.apply(
+            &mut state,
+            MigrationAction::SetHandle("alice.test".to_string()),
+        );
+        log.apply(
+            &mut state,
+            MigrationAction::SetPassword("hunter2".to_string()),
+        );
+
+        SupportBundle {
+            created_at_secs: 0,
+            app_version: "test".to_string(),
+            state: StateSnapshot::capture(&state),
+            actions: log.actions().map(MigrationAction::redacted).collect(),
+            dropped_action_count: 0,
+            config: ConfigSnapshot::default(),
+            http_traces: Vec::new(),
+            pipeline_stats: Vec::new(),
+            handle_resolution: None,
+            route_report: None,
+            target_health: None,
+            bandwidth: None,
+            cors: None,
+            body_limit: None,
+        }
+    }
+
+    #[test]
+    fn test_round_trip_and_redaction() {
+        let bundle = sample_bundle();
+        let encrypted = bundle.encrypt_with_iterations("passphrase", 10).unwrap();
+        assert!(!encrypted.contains("alice.test"));
+
+        let decrypted = SupportBundle::decrypt(&encrypted, "passphrase").unwrap();
+        assert_eq!(decrypted.state.handle, "alice.test");
+        assert!(matches!(
+            &decrypted.actions[1],
+            MigrationAction::SetPassword(p) if p == REDACTED
+        ));
+
+        let replayed = decrypted.replay();
+        assert_eq!(replayed.form1.handle, "alice.test");
+        assert_eq!(replayed.form1.password, REDACTED);
+    }
+
+    #[test]
+    fn test_config_snapshot_and_kdf_bound() {
+        let mut config = MigrationConfig::new();
+        config.blob.mirror_urls = vec!["https://mirror.internal.example".to_string()];
+        config.target_hosts.denied_hosts = vec!["secret-host.example".to_string()];
+        let snapshot = ConfigSnapshot::capture(&config);
+        assert_eq!(snapshot.blob_mirror_count, 1);
+        assert_eq!(snapshot.denied_host_count, 1);
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert!(!json.contains("mirror.internal.example"));
+        assert!(!json.contains("secret-host.example"));
+
+        // A crafted envelope can't make decryption run for minutes
+        let mut envelope: serde_json::Value = serde_json::from_str(
+            &sample_bundle()
+                .encrypt_with_iterations("passphrase", 10)
+                .unwrap(),
+        )
+        .unwrap();
+        envelope["kdf_iterations"] = serde_json::json!(u32::MAX);
+        assert!(matches!(
+            SupportBundle::decrypt(&envelope.to_string(), "passphrase"),
+            Err(SupportBundleError::UnsupportedFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_wrong_passphrase_is_rejected() {
+        let encrypted = sample_bundle()
+            .encrypt_with_iterations("passphrase", 10)
+            .unwrap();
+        assert!(matches!(
+            SupportBundle::decrypt(&encrypted, "nope"),
+            Err(SupportBundleError::Decryption)
+        ));
+        assert!(matches!(
+            sample_bundle().encrypt_with_iterations("", 10),
+            Err(SupportBundleError::EmptyPassphrase)
+        ));
+    }
+}
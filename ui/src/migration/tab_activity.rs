@@ -0,0 +1,176 @@
+//! Keeping the tab awake and noticing when the browser throttles it
+//!
+//! A migration runs entirely in the page, so a sleeping screen or a
+//! backgrounded tab slows it to a crawl: browsers clamp timers in hidden tabs
+//! (Chrome to once a minute after a few minutes, Safari can suspend the page
+//! outright). While a migration runs the page holds a screen wake lock where the
+//! Wake Lock API exists, and a one-second ticker measures how late its timer
+//! fires while the tab is hidden. Lateness beyond [`THROTTLE_DRIFT_MS`] means
+//! the browser is throttling us, and the user is asked to keep the tab in front.
+//!
+//! Going to the background pauses stall detection; coming back re-acquires the
+//! wake lock (browsers drop it on hide) and restarts the idle clock so the time
+//! spent hidden isn't reported as a stall.
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils::platform::has_browser_window;
+
+/// Interval of the drift-measuring ticker
+pub const DRIFT_TICK_MS: u32 = 1_000;
+
+/// Lateness of a tick, beyond its interval, that counts as throttling
+pub const THROTTLE_DRIFT_MS: u64 = 2_000;
+
+/// Whether the screen is being kept awake
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WakeLockStatus {
+    /// Not requested yet in this migration
+    #[default]
+    Inactive,
+    Held,
+    /// The browser has no Wake Lock API or refused the request
+    Unavailable,
+}
+
+/// Visibility and throttling observed during the current migration
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TabActivity {
+    pub hidden: bool,
+    pub hidden_since_ms: Option<u64>,
+    /// Largest timer lateness seen while hidden, once it crossed the threshold
+    pub throttled_drift_ms: Option<u64>,
+    pub wake_lock: WakeLockStatus,
+}
+
+impl TabActivity {
+    pub fn set_hidden(&mut self, hidden: bool, now_ms: u64) {
+        self.hidden = hidden;
+        self.hidden_since_ms = hidden.then_some(now_ms);
+    }
+
+    pub fn record_drift(&mut self, drift_ms: u64) {
+        self.throttled_drift_ms = Some(self.throttled_drift_ms.unwrap_or(0).max(drift_ms));
+    }
+
+    pub fn is_throttled(&self) -> bool {
+        self.throttled_drift_ms.is_some()
+    }
+}
+
+/// Measures how late a periodic timer fires
+#[derive(Debug, Clone)]
+pub struct DriftMeter {
+    interval_ms: u64,
+    last_tick_ms: Option<u64>,
+}
+
+impl DriftMeter {
+    pub fn new(interval_ms: u32) -> Self {
+        Self {
+            interval_ms: u64::from(interval_ms),
+            last_tick_ms: None,
+        }
+    }
+
+    /// Record a tick; returns the lateness if it crossed [`THROTTLE_DRIFT_MS`]
+    pub fn tick(&mut self, now_ms: u64) -> Option<u64> {
+        let previous = self.last_tick_ms.replace(now_ms)?;
+        let drift = now_ms
+            .saturating_sub(previous)
+            .saturating_sub(self.interval_ms);
+        (drift >= THROTTLE_DRIFT_MS).then_some(drift)
+    }
+
+    /// Forget the last tick, e.g. after a visibility change
+    pub fn reset(&mut self) {
+        self.last_tick_ms = None;
+    }
+}
+
+/// Whether the page is currently in a background tab or minimized
+pub fn is_page_hidden() -> bool {
+    if !has_browser_window() {
+        return false;
+    }
+    web_sys::window()
+        .and_then(|w| w.document())
+        .map(|d| d.hidden())
+        .unwrap_or(false)
+}
+
+/// Request a screen wake lock; returns the sentinel to release later
+///
+/// `navigator.wakeLock` isn't in stable `web_sys`, so it's reached via `Reflect`.
+#[cfg(feature = "web")]
+pub async fn request_wake_lock() -> Result<wasm_bindgen::JsValue, String> {
+    use wasm_bindgen::{JsCast, JsValue};
+
+    if !has_browser_window() {
+        return Err("No browser window".to_string());
+    }
+    let navigator = web_sys::window().ok_or("No browser window")?.navigator();
+    let wake_lock = js_sys::Reflect::get(&navigator, &JsValue::from_str("wakeLock"))
+        .ok()
+        .filter(|value| !value.is_undefined() && !value.is_null())
+        .ok_or("This browser can't keep the screen awake")?;
+    let request: js_sys::Function = js_sys::Reflect::get(&wake_lock, &JsValue::from_str("request"))
+        .map_err(|_| "This browser can't keep the screen awake")?
+        .dyn_into()
+        .map_err(|_| "This browser can't keep the screen awake")?;
+    let promise: js_sys::Promise = request
+        .call1(&wake_lock, &JsValue::from_str("screen"))
+        .map_err(|e| format!("Wake lock request failed: {:?}", e))?
+        .dyn_into()
+        .map_err(|_| "Wake lock request did not return a promise")?;
+    wasm_bindgen_futures::JsFuture::from(promise)
+        .await
+        .map_err(|e| format!("Wake lock request was refused: {:?}", e))
+}
+
+/// Release a sentinel returned by [`request_wake_lock`]
+#[cfg(feature = "web")]
+pub fn release_wake_lock(sentinel: &wasm_bindgen::JsValue) {
+    use wasm_bindgen::{JsCast, JsValue};
+
+    let release = js_sys::Reflect::get(sentinel, &JsValue::from_str("release"))
+        .ok()
+        .and_then(|value| value.dyn_into::<js_sys::Function>().ok());
+    if let Some(release) = release {
+        let _ = release.call0(sentinel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drift_meter_flags_late_ticks_only() {
+        let mut meter = DriftMeter::new(DRIFT_TICK_MS);
+        assert_eq!(meter.tick(10_000), None);
+        assert_eq!(meter.tick(11_050), None);
+        assert_eq!(meter.tick(12_900), None);
+        // Chrome's intensive throttling: one tick a minute
+        assert_eq!(meter.tick(72_900), Some(59_000));
+
+        meter.reset();
+        assert_eq!(meter.tick(200_000), None);
+    }
+
+    #[test]
+    fn test_tab_activity_keeps_largest_drift() {
+        let mut activity = TabActivity::default();
+        activity.set_hidden(true, 5_000);
+        assert_eq!(activity.hidden_since_ms, Some(5_000));
+        assert!(!activity.is_throttled());
+
+        activity.record_drift(9_000);
+        activity.record_drift(3_000);
+        assert_eq!(activity.throttled_drift_ms, Some(9_000));
+
+        activity.set_hidden(false, 20_000);
+        assert_eq!(activity.hidden_since_ms, None);
+        assert!(activity.is_throttled());
+    }
+}
@@ -0,0 +1,279 @@
+//! Step-level view of a migration for the timeline display
+//!
+//! Statuses are derived from the progress flags already in [`MigrationState`];
+//! only the start and finish times are tracked separately, updated by the
+//! reducer whenever a step changes status.
+
+use serde::{Deserialize, Serialize};
+
+use super::types::{FormStep, MigrationState};
+
+/// The user-visible phases of a migration, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimelineStep {
+    Login,
+    Account,
+    Repository,
+    Blobs,
+    Preferences,
+    Plc,
+    Activation,
+}
+
+impl TimelineStep {
+    pub const ALL: [TimelineStep; 7] = [
+        TimelineStep::Login,
+        TimelineStep::Account,
+        TimelineStep::Repository,
+        TimelineStep::Blobs,
+        TimelineStep::Preferences,
+        TimelineStep::Plc,
+        TimelineStep::Activation,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TimelineStep::Login => "Login",
+            TimelineStep::Account => "Account",
+            TimelineStep::Repository => "Repo",
+            TimelineStep::Blobs => "Blobs",
+            TimelineStep::Preferences => "Prefs",
+            TimelineStep::Plc => "PLC",
+            TimelineStep::Activation => "Activation",
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StepStatus {
+    Pending,
+    Active,
+    Done,
+    Failed,
+}
+
+impl StepStatus {
+    pub fn icon(&self) -> &'static str {
+        match self {
+            StepStatus::Pending => "○",
+            StepStatus::Active => "⏳",
+            StepStatus::Done => "✅",
+            StepStatus::Failed => "❌",
+        }
+    }
+
+    pub fn css_class(&self) -> &'static str {
+        match self {
+            StepStatus::Pending => "pending",
+            StepStatus::Active => "active",
+            StepStatus::Done => "done",
+            StepStatus::Failed => "failed",
+        }
+    }
+}
+
+/// When each step was first seen running and when it finished
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StepTimings {
+    started_ms: [Option<u64>; 7],
+    finished_ms: [Option<u64>; 7],
+}
+
+impl StepTimings {
+    /// Stamp start/finish times for any step whose status changed
+    pub fn record(&mut self, statuses: &[StepStatus; 7], now_ms: u64) {
+        for (i, status) in statuses.iter().enumerate() {
+            match status {
+                StepStatus::Pending => {}
+                StepStatus::Active => {
+                    self.started_ms[i].get_or_insert(now_ms);
+                    // A retried step is running again
+                    self.finished_ms[i] = None;
+                }
+                StepStatus::Done | StepStatus::Failed => {
+                    // Steps that finished within a single action never showed as active
+                    self.started_ms[i].get_or_insert(now_ms);
+                    self.finished_ms[i].get_or_insert(now_ms);
+                }
+            }
+        }
+    }
+
+    /// Elapsed time for a step; still-running steps are measured up to `now_ms`
+    pub fn duration_ms(&self, step: TimelineStep, now_ms: u64) -> Option<u64> {
+        let started = self.started_ms[step.index()]?;
+        let finished = self.finished_ms[step.index()].unwrap_or(now_ms);
+        Some(finished.saturating_sub(started))
+    }
+}
+
+/// One rendered step of the timeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimelineEntry {
+    pub step: TimelineStep,
+    pub status: StepStatus,
+    pub duration_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl MigrationState {
+    /// Derive each step's status from the progress flags
+    pub fn timeline_statuses(&self) -> [StepStatus; 7] {
+        let done = [
+            self.form1.session_stored,
+            self.new_pds_session.is_some(),
+            self.repo_progress.import_complete || self.migration_progress.repo_imported,
+            self.migration_progress.blobs_imported,
+            self.preferences_progress.import_complete
+                || self.migration_progress.preferences_imported,
+            self.plc_progress.operation_submitted,
+            self.migration_progress.new_account_activated,
+        ];
+
+        // Steps can be skipped (e.g. an account with no blobs), so anything before
+        // the furthest completed step counts as done
+        let completed = done.iter().rposition(|d| *d).map_or(0, |i| i + 1);
+
+        let running = self.is_migrating
+            || self.form1.is_authenticating
+            || self.form4.is_verifying
+            || self.current_step == FormStep::PlcVerification;
+
+        let mut statuses = [StepStatus::Pending; 7];
+        for status in statuses.iter_mut().take(completed) {
+            *status = StepStatus::Done;
+        }
+        if completed < statuses.len() {
+            let failed = !running
+                && completed > 0
+                && (self.migration_error.is_some()
+                    || self.step_error(TimelineStep::ALL[completed]).is_some());
+            if failed {
+                statuses[completed] = StepStatus::Failed;
+            } else if running {
+                statuses[completed] = StepStatus::Active;
+            }
+        }
+        statuses
+    }
+
+    /// Error reported by a step's own progress, if any
+    fn step_error(&self, step: TimelineStep) -> Option<&String> {
+        match step {
+            TimelineStep::Repository => self.repo_progress.error.as_ref(),
+            TimelineStep::Blobs => self.blob_progress.error.as_ref(),
+            TimelineStep::Preferences => self.preferences_progress.error.as_ref(),
+            TimelineStep::Plc => self.plc_progress.error.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Timeline entries for display, with durations measured up to `now_ms`
+    pub fn timeline(&self, now_ms: u64) -> Vec<TimelineEntry> {
+        self.timeline_statuses()
+            .into_iter()
+            .zip(TimelineStep::ALL)
+            .map(|(status, step)| TimelineEntry {
+                step,
+                status,
+                duration_ms: self.step_timings.duration_ms(step, now_ms),
+                error: (status == StepStatus::Failed).then(|| {
+                    self.step_error(step)
+                        .or(self.migration_error.as_ref())
+                        .cloned()
+                        .unwrap_or_default()
+                }),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migration::types::{MigrationAction, RepoProgress, SessionCredentials};
+
+    fn migrating_state() -> MigrationState {
+        let mut state = MigrationState::default();
+        state.form1.session_stored = true;
+        state.new_pds_session = Some(SessionCredentials {
+            did: "did:plc:alice".to_string(),
+            handle: "alice.test".to_string(),
+            pds: "https://pds.example.com".to_string(),
+            access_jwt: String::new(),
+            refresh_jwt: String::new(),
+        });
+        state.is_migrating = true;
+        state
+    }
+
+    #[test]
+    fn test_statuses_follow_progress_flags() {
+        let state = migrating_state();
+        assert_eq!(
+            state.timeline_statuses(),
+            [
+                StepStatus::Done,
+                StepStatus::Done,
+                StepStatus::Active,
+                StepStatus::Pending,
+                StepStatus::Pending,
+                StepStatus::Pending,
+                StepStatus::Pending,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_skipped_steps_count_as_done() {
+        let mut state = migrating_state();
+        state.repo_progress.import_complete = true;
+        // No blobs to migrate, preferences already imported
+        state.preferences_progress.import_complete = true;
+
+        let statuses = state.timeline_statuses();
+        assert_eq!(statuses[TimelineStep::Blobs.index()], StepStatus::Done);
+        assert_eq!(statuses[TimelineStep::Plc.index()], StepStatus::Active);
+    }
+
+    #[test]
+    fn test_error_marks_next_step_failed() {
+        let mut state = migrating_state();
+        state.reduce_in_place(MigrationAction::SetRepoProgress(RepoProgress {
+            error: Some("import rejected".to_string()),
+            ..RepoProgress::default()
+        }));
+        state.reduce_in_place(MigrationAction::SetMigrating(false));
+
+        let repo = &state.timeline(u64::MAX)[TimelineStep::Repository.index()];
+        assert_eq!(repo.status, StepStatus::Failed);
+        assert_eq!(repo.error.as_deref(), Some("import rejected"));
+        assert!(repo.duration_ms.is_some());
+    }
+
+    #[test]
+    fn test_timings_restart_when_a_step_is_retried() {
+        let mut timings = StepTimings::default();
+        let mut statuses = [StepStatus::Pending; 7];
+
+        statuses[2] = StepStatus::Active;
+        timings.record(&statuses, 1_000);
+        statuses[2] = StepStatus::Failed;
+        timings.record(&statuses, 4_000);
+        assert_eq!(
+            timings.duration_ms(TimelineStep::Repository, 9_000),
+            Some(3_000)
+        );
+
+        statuses[2] = StepStatus::Active;
+        timings.record(&statuses, 5_000);
+        assert_eq!(
+            timings.duration_ms(TimelineStep::Repository, 9_000),
+            Some(8_000)
+        );
+    }
+}
@@ -2,7 +2,36 @@
 use serde::{Deserialize, Serialize, Serializer};
 use std::collections::VecDeque;
 
-use crate::services::client::ClientPdsProvider;
+use crate::migration::blob_manifest::BlobManifestInfo;
+use crate::migration::car_diff::RepoCarDiff;
+use crate::migration::content_copy::ContentCopyReport;
+use crate::migration::continuous_sync::ContinuousSyncStatus;
+use crate::migration::email_verification::{EmailVerification, EmailVerificationStatus};
+use crate::migration::password_manager::CredentialSaveStatus;
+use crate::migration::plan::PlanVariant;
+use crate::migration::plc_token_delivery::PlcTokenDelivery;
+use crate::migration::preferences_backup::PreferencesBackupInfo;
+use crate::migration::preferences_roundtrip::PreferencesRoundTrip;
+use crate::migration::progress::LargeBlobTransfer;
+use crate::migration::resume_reconcile::ResumeReconciliation;
+use crate::migration::service_accounts::ServiceAccountProfile;
+use crate::migration::source_endpoint::SourceEndpointChange;
+use crate::migration::steps::blob_enumeration::BlobEnumerationSummary;
+use crate::migration::steps::repo_import::RepoImportStatus;
+use crate::migration::tab_activity::{TabActivity, WakeLockStatus};
+use crate::migration::timeline::StepTimings;
+use crate::migration::watchdog::ProgressWatch;
+use crate::services::client::{
+    BodyLimitReport, ClientAppPassword, ClientPdsProvider, ImportDiagnosis, PropagationReport,
+    SavedFeedsReport,
+};
+use crate::services::config::{
+    get_global_config, BlobEnumerationMethod, BlobNotFoundPolicy, RetryConfig, VerificationLevel,
+};
+use crate::services::streaming::{StorageCapabilities, StorageUsage};
+use crate::utils::byte_format::ByteUnits;
+use crate::utils::console_macros::ConsoleLevel;
+use crate::utils::serialization::deserialize_u64_flexible;
 
 /// PDS server description response structures
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -52,6 +81,109 @@ impl PdsDescribeResponse {
     }
 }
 
+/// How the target PDS gates new accounts, derived from its describeServer flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignupGating {
+    /// The server didn't say; the invite code field is offered as optional
+    Unknown,
+    Open,
+    InviteCode,
+    /// Verified through the PDS signup gate (captcha, email or phone) instead of an invite
+    VerificationGate,
+    InviteCodeAndVerification,
+}
+
+impl SignupGating {
+    pub fn from_describe(response: &PdsDescribeResponse) -> Self {
+        match (
+            response.invite_code_required,
+            response.phone_verification_required,
+        ) {
+            (None, None) => SignupGating::Unknown,
+            (Some(true), Some(true)) => SignupGating::InviteCodeAndVerification,
+            (Some(true), _) => SignupGating::InviteCode,
+            (_, Some(true)) => SignupGating::VerificationGate,
+            _ => SignupGating::Open,
+        }
+    }
+
+    pub fn requires_invite_code(&self) -> bool {
+        matches!(
+            self,
+            SignupGating::InviteCode | SignupGating::InviteCodeAndVerification
+        )
+    }
+
+    /// Whether the invite code field should be shown at all
+    pub fn shows_invite_code(&self) -> bool {
+        self.requires_invite_code() || *self == SignupGating::Unknown
+    }
+
+    pub fn uses_verification_gate(&self) -> bool {
+        matches!(
+            self,
+            SignupGating::VerificationGate | SignupGating::InviteCodeAndVerification
+        )
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            SignupGating::Unknown => "Signup requirements not advertised",
+            SignupGating::Open => "✓ Open signup, no invite code required",
+            SignupGating::InviteCode => "⚠️ Invite code required",
+            SignupGating::VerificationGate => {
+                "✓ No invite code required; the PDS verifies new accounts during signup"
+            }
+            SignupGating::InviteCodeAndVerification => {
+                "⚠️ Invite code and signup verification required"
+            }
+        }
+    }
+}
+
+/// Whether a createAccount failure means the PDS wants signup verification
+/// (a captcha or text message code) that was missing or rejected
+pub fn is_signup_verification_error(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    message.contains("invalidphoneverification")
+        || (message.contains("verification")
+            && (message.contains("phone") || message.contains("code is required")))
+}
+
+/// Phrases PDSes use when they refuse migrated accounts outright
+const EXISTING_DID_REFUSALS: &[&str] = &[
+    "not accepting repo imports",
+    "not accepting imports",
+    "not accepting migrations",
+    "repo imports are disabled",
+    "repository imports are disabled",
+    "repo import is disabled",
+    "imports are not enabled",
+    "account migration is disabled",
+    "account migrations are disabled",
+    "migrations are not accepted",
+];
+
+/// Whether a createAccount or importRepo failure means the PDS refuses to
+/// take in an account that keeps its existing DID
+///
+/// Only the server's own wording counts: import errors all carry the
+/// "Repository import failed" prefix and often the DID, so "import" or "did:"
+/// next to a generic "disabled" would also match taken-down accounts.
+pub fn is_existing_did_rejected(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    if EXISTING_DID_REFUSALS
+        .iter()
+        .any(|phrase| message.contains(phrase))
+    {
+        return true;
+    }
+    message.contains("existing did")
+        && ["not allowed", "not permitted", "not supported", "disabled"]
+            .iter()
+            .any(|phrase| message.contains(phrase))
+}
+
 /// Generic PDS login response
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PdsLoginResponse {
@@ -84,7 +216,7 @@ impl PdsLoginResponse {
 }
 
 /// Session credentials for ATProto PDS authentication
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct SessionCredentials {
     pub did: String,
     pub handle: String,
@@ -103,7 +235,7 @@ pub enum FormStep {
 }
 
 // Validation status enums
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub enum HandleValidation {
     None,
     Checking,
@@ -127,7 +259,7 @@ pub enum EmailValidation {
 }
 
 // Action enum for state mutations
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum MigrationAction {
     // Form 1 actions
     SetHandle(String),
@@ -138,6 +270,10 @@ pub enum MigrationAction {
     SetLoginResponse(Option<PdsLoginResponse>),
     SetSessionStored(bool),
     SetOriginalHandle(String),
+    // PDS endpoint the handle's DID document lists, once resolved
+    SetResolvedPds(Option<String>),
+    // PDS the user typed in place of the resolved one
+    SetPdsOverride(Option<String>),
 
     // Form 2 actions
     SetNewPdsUrl(String),
@@ -153,11 +289,28 @@ pub enum MigrationAction {
     SetInviteCode(String),
     SetSelectedDomain(String),
     SetVerificationCode(Option<String>),
+    SetVerificationPhone(Option<String>),
+    SetVerificationRejected(bool),
+    SetExistingDidRejected(bool),
+    SetVerificationLevel(VerificationLevel),
+    SetBlobEnumerationMethod(BlobEnumerationMethod),
+    SetBlobRetries(u32),
+    SetBlobNotFoundPolicy(BlobNotFoundPolicy),
+    SetPlanVariant(PlanVariant),
 
     // Form 4 - PLC Verification actions
     SetPlcVerificationCode(String),
     SetPlcUnsigned(String),
     SetPlcVerifying(bool),
+    // When the PLC token email was requested, restored after a refresh
+    SetPlcTokenDelivery(Option<PlcTokenDelivery>),
+    // Operation the user signed with a recovery key instead of an emailed token
+    SetPlcSignedOperation(String),
+    // What signing the operation means, when the user must confirm it first
+    SetPlcUpdateConfirmation(Option<String>),
+    SetPlcUpdateConfirmed(bool),
+    // Days to leave the old account active instead of deactivating it, if chosen
+    SetOldAccountGrace(Option<u32>),
 
     // Validation actions (only handle validation is still needed)
     SetHandleValidation(HandleValidation),
@@ -166,24 +319,284 @@ pub enum MigrationAction {
     // Migration process actions
     SetMigrating(bool),
     SetMigrationError(Option<String>),
+    // The user stopped the run; cleared when a run starts
+    SetMigrationAborted(bool),
     SetMigrationStep(String),
     SetNewPdsSession(Option<SessionCredentials>),
+    // Repo signing key reserved on the target before the account was created
+    SetReservedSigningKey(Option<String>),
     SetCurrentStep(FormStep),
 
     // Extended migration progress tracking
     SetMigrationProgress(MigrationProgress),
     SetRepoProgress(RepoProgress),
+    SetRepoImportStatus(Option<RepoImportStatus>),
     SetBlobProgress(BlobProgress),
+    SetLargeBlobTransfers(Vec<LargeBlobTransfer>),
     SetPreferencesProgress(PreferencesProgress),
     SetPlcProgress(PlcProgress),
     SetMigrationCompleted(bool),
+    SetTargetAutoActivation(Option<TargetAutoActivation>),
 
     // PLC recommendation storage
     SetPlcRecommendation(Option<String>),
     // Original PDS describe response cache
     SetOriginalPdsDescribe(Option<PdsDescribeResponse>),
+    // Live origin storage usage sample
+    SetStorageUsage(Option<StorageUsage>),
+    // Persistent storage backends detected at startup
+    SetStorageCapabilities(Option<StorageCapabilities>),
+    // Backup of the exported preferences, saved before importing them
+    SetPreferencesBackup(Option<PreferencesBackupInfo>),
+    // Which preference categories the new PDS kept after the import
+    SetPreferencesRoundTrip(Option<PreferencesRoundTrip>),
+    // Checksum manifest of the blobs uploaded to the new PDS
+    SetBlobManifest(Option<BlobManifestInfo>),
+    // How the blobs to transfer were enumerated, with each listing's count
+    SetBlobEnumeration(Option<BlobEnumerationSummary>),
+    // Blobs the source PDS no longer has, skipped under the skip-and-record policy
+    SetUnrecoverableBlobs(Vec<String>),
+    // Block-level comparison of the source and target repository exports
+    SetRepoCarDiff(Option<RepoCarDiff>),
+    // Why the new PDS refused the repository import, with its full answer
+    SetRepoImportFailure(Option<ImportDiagnosis>),
+    // Test uploads bracketing the new PDS's request body size limit
+    SetBodyLimitReport(Option<BodyLimitReport>),
+    // sha-256 of the repository CAR as exported, re-checked before the import
+    SetRepoCarChecksum(Option<String>),
+    // Old PDS endpoint the DID document moved to during the migration
+    SetSourceEndpointChange(Option<SourceEndpointChange>),
+    // Progress of the keep-in-sync loop before the identity switch
+    SetContinuousSync(Option<ContinuousSyncStatus>),
+    // What the new PDS already held when an interrupted migration was resumed
+    SetResumeReconciliation(Option<ResumeReconciliation>),
+    // Relay crawl request and network propagation after activation
+    SetPropagation(Option<PropagationReport>),
+    // Saved feeds and lists looked up as the new account after activation
+    SetSavedFeeds(Option<SavedFeedsReport>),
+    // Labeler or feed generator services carried through the identity update
+    SetServiceAccount(Option<ServiceAccountProfile>),
+    // Offer to save the new account's password in the browser
+    SetCredentialSave(CredentialSaveStatus),
+    // Names of the old account's app passwords
+    SetAppPasswords(Option<Vec<ClientAppPassword>>),
+    // Email on the new account and its confirmation
+    SetEmailVerification(EmailVerification),
+    // Outcome of copying content into an existing account
+    SetContentCopyReport(Option<ContentCopyReport>),
+    // Show progress as sentences instead of bars and animations
+    SetTextOnlyProgress(bool),
+    // Show sizes in decimal (MB) rather than binary (MiB) units
+    SetByteUnits(ByteUnits),
+    // Console log filter, e.g. "info,services::streaming=debug"
+    SetLogFilter(String),
+    // Page visibility, timer throttling and wake lock during a migration
+    SetTabHidden(bool),
+    RecordTimerDrift(u64),
+    SetWakeLockStatus(WakeLockStatus),
     // Console message logging
-    AddConsoleMessage(String),
+    AddConsoleMessage(ConsoleEntry),
+}
+
+/// Number of console entries kept for the in-app log; older entries are dropped
+pub const CONSOLE_MESSAGE_CAPACITY: usize = 200;
+
+/// Structured console message captured for the in-app log
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(from = "ConsoleEntryRepr")]
+pub struct ConsoleEntry {
+    pub level: ConsoleLevel,
+    /// Module that emitted the message
+    pub source: String,
+    pub timestamp_ms: f64,
+    /// Position in the session's message stream, assigned by the reducer
+    pub seq: u64,
+    pub message: String,
+}
+
+impl ConsoleEntry {
+    pub fn new(level: ConsoleLevel, source: &str, message: impl Into<String>) -> Self {
+        Self {
+            level,
+            source: source.to_string(),
+            timestamp_ms: crate::utils::platform::now_ms(),
+            seq: 0,
+            message: message.into(),
+        }
+    }
+}
+
+/// Accepts both structured entries and the plain strings stored by older support bundles
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ConsoleEntryRepr {
+    Entry {
+        level: ConsoleLevel,
+        source: String,
+        timestamp_ms: f64,
+        #[serde(default)]
+        seq: u64,
+        message: String,
+    },
+    Legacy(String),
+}
+
+impl From<ConsoleEntryRepr> for ConsoleEntry {
+    fn from(repr: ConsoleEntryRepr) -> Self {
+        match repr {
+            ConsoleEntryRepr::Entry {
+                level,
+                source,
+                timestamp_ms,
+                seq,
+                message,
+            } => Self {
+                level,
+                source,
+                timestamp_ms,
+                seq,
+                message,
+            },
+            ConsoleEntryRepr::Legacy(message) => {
+                // Legacy strings carried their level as a "[WARN] " style prefix
+                let level = [
+                    ConsoleLevel::Debug,
+                    ConsoleLevel::Log,
+                    ConsoleLevel::Info,
+                    ConsoleLevel::Warn,
+                    ConsoleLevel::Error,
+                ]
+                .into_iter()
+                .find(|level| message.starts_with(&format!("[{}] ", level.label())))
+                .unwrap_or(ConsoleLevel::Info);
+                Self {
+                    level,
+                    source: String::new(),
+                    timestamp_ms: 0.0,
+                    seq: 0,
+                    message,
+                }
+            }
+        }
+    }
+}
+
+/// Which console entries the in-app log shows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConsoleFilter {
+    ErrorsOnly,
+    /// Info and above
+    #[default]
+    Standard,
+    /// Everything, including debug output
+    Verbose,
+}
+
+impl ConsoleFilter {
+    pub const ALL: [ConsoleFilter; 3] = [
+        ConsoleFilter::ErrorsOnly,
+        ConsoleFilter::Standard,
+        ConsoleFilter::Verbose,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConsoleFilter::ErrorsOnly => "errors",
+            ConsoleFilter::Standard => "standard",
+            ConsoleFilter::Verbose => "verbose",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|filter| filter.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConsoleFilter::ErrorsOnly => "Errors only",
+            ConsoleFilter::Standard => "Standard",
+            ConsoleFilter::Verbose => "Verbose",
+        }
+    }
+
+    pub fn allows(&self, level: ConsoleLevel) -> bool {
+        match self {
+            ConsoleFilter::ErrorsOnly => level >= ConsoleLevel::Error,
+            ConsoleFilter::Standard => level >= ConsoleLevel::Info,
+            ConsoleFilter::Verbose => true,
+        }
+    }
+}
+
+/// Placeholder used wherever a secret is stripped from diagnostics
+pub const REDACTED: &str = "[redacted]";
+
+impl SessionCredentials {
+    /// Copy of the credentials with both JWTs removed
+    pub fn redacted(&self) -> Self {
+        Self {
+            access_jwt: REDACTED.to_string(),
+            refresh_jwt: REDACTED.to_string(),
+            ..self.clone()
+        }
+    }
+}
+
+/// Address with its mailbox hidden; the domain is kept since it often explains
+/// delivery problems
+pub fn mask_email(email: &str) -> String {
+    match email.rsplit_once('@') {
+        Some((_, domain)) => format!("{}@{}", REDACTED, domain),
+        None if email.is_empty() => String::new(),
+        None => REDACTED.to_string(),
+    }
+}
+
+impl MigrationAction {
+    /// Copy of the action with passwords, tokens and verification codes removed
+    /// and email addresses masked,
+    /// suitable for diagnostics that leave the browser
+    pub fn redacted(&self) -> Self {
+        match self {
+            Self::SetPassword(_) => Self::SetPassword(REDACTED.to_string()),
+            Self::SetNewPassword(_) => Self::SetNewPassword(REDACTED.to_string()),
+            Self::SetNewPasswordConfirm(_) => Self::SetNewPasswordConfirm(REDACTED.to_string()),
+            Self::SetEmailAddress(_) => Self::SetEmailAddress(REDACTED.to_string()),
+            Self::SetInviteCode(_) => Self::SetInviteCode(REDACTED.to_string()),
+            Self::SetVerificationCode(code) => {
+                Self::SetVerificationCode(code.as_ref().map(|_| REDACTED.to_string()))
+            }
+            Self::SetVerificationPhone(phone) => {
+                Self::SetVerificationPhone(phone.as_ref().map(|_| REDACTED.to_string()))
+            }
+            Self::SetPlcVerificationCode(_) => Self::SetPlcVerificationCode(REDACTED.to_string()),
+            Self::SetEmailVerification(verification) => {
+                let status = match &verification.status {
+                    EmailVerificationStatus::UpdateTokenSent { new_email } => {
+                        EmailVerificationStatus::UpdateTokenSent {
+                            new_email: mask_email(new_email),
+                        }
+                    }
+                    other => other.clone(),
+                };
+                Self::SetEmailVerification(EmailVerification {
+                    email: mask_email(&verification.email),
+                    status,
+                })
+            }
+            Self::SetLoginResponse(Some(response)) => {
+                let mut response = response.clone();
+                response.session = response.session.as_ref().map(|s| s.redacted());
+                Self::SetLoginResponse(Some(response))
+            }
+            Self::SetNewPdsSession(Some(session)) => {
+                Self::SetNewPdsSession(Some(session.redacted()))
+            }
+            other => other.clone(),
+        }
+    }
 }
 
 // Form state structs
@@ -197,6 +610,8 @@ pub struct LoginForm {
     pub login_response: Option<PdsLoginResponse>,
     pub session_stored: bool,
     pub original_handle: String,
+    pub resolved_pds: Option<String>,
+    pub pds_override: Option<String>,
 }
 
 #[derive(Clone, Default)]
@@ -217,8 +632,24 @@ pub struct MigrationDetailsForm {
     pub suggested_handle: String,
     pub is_checking_handle: bool,
     pub selected_domain: Option<String>,
-    /// Captcha verification code from PDS /gate/signup flow
+    /// Captcha verification code from PDS /gate/signup flow, or a text message code
     pub verification_code: Option<String>,
+    /// Phone number the text message code was sent to
+    pub verification_phone: Option<String>,
+    /// The PDS refused to create the account without (valid) signup verification
+    pub verification_rejected: bool,
+    /// The PDS refused to create or import an account with the existing DID
+    pub existing_did_rejected: bool,
+    /// User override of the configured verification level
+    pub verification_level: Option<VerificationLevel>,
+    /// User override of the configured blob enumeration method
+    pub blob_enumeration_method: Option<BlobEnumerationMethod>,
+    /// User override of the configured retries per blob download and upload
+    pub blob_retries: Option<u32>,
+    /// User override of the configured policy for blobs the source PDS has lost
+    pub blob_not_found_policy: Option<BlobNotFoundPolicy>,
+    /// Which migration plan to run
+    pub plan_variant: PlanVariant,
 }
 
 #[derive(Clone, Default)]
@@ -227,6 +658,19 @@ pub struct PlcVerificationForm {
     pub plc_unsigned: String,
     pub handle_context: String,
     pub is_verifying: bool,
+    pub signed_operation: String,
+    // Leave the old account active this many days instead of deactivating it
+    pub old_account_grace_days: Option<u32>,
+    // What signing the operation means, when the user must confirm it first
+    pub update_confirmation: Option<String>,
+    pub update_confirmed: bool,
+}
+
+impl PlcVerificationForm {
+    /// Signing waits until the user confirms what the operation changes
+    pub fn awaiting_confirmation(&self) -> bool {
+        self.update_confirmation.is_some() && !self.update_confirmed
+    }
 }
 
 #[derive(Clone)]
@@ -238,12 +682,41 @@ pub struct ValidationStates {
 
 // Migration progress tracking structures
 
+/// How the flow adapted to a target PDS that activates accounts as soon as they are created
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TargetAutoActivation {
+    /// Deactivated before importing; the final activation step turns it back on
+    Deactivated,
+    /// The target refused deactivation, so the migration continued with the account active
+    LeftActive { reason: String },
+}
+
+impl TargetAutoActivation {
+    /// Compatibility decision recorded for the migration report
+    pub fn compatibility_note(&self) -> String {
+        match self {
+            TargetAutoActivation::Deactivated => "Target PDS activated the account on creation; \
+                it was deactivated before importing data and reactivated after the PLC update"
+                .to_string(),
+            TargetAutoActivation::LeftActive { reason } => format!(
+                "Target PDS activated the account on creation and refused deactivation ({}); \
+                the migration continued with the account active, so it may have been visible \
+                before the PLC update",
+                reason
+            ),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct MigrationProgress {
     // Repository migration
     pub repo_exported: bool,
     pub repo_imported: bool,
-    #[serde(serialize_with = "serialize_u64_as_string")]
+    #[serde(
+        serialize_with = "serialize_u64_as_string",
+        deserialize_with = "deserialize_u64_flexible"
+    )]
     pub repo_car_size: u64,
 
     // OPFS Blob migration
@@ -252,11 +725,20 @@ pub struct MigrationProgress {
     pub blobs_imported: bool,
     pub total_blob_count: u32,
     pub imported_blob_count: u32,
-    #[serde(serialize_with = "serialize_u64_as_string")]
+    #[serde(
+        serialize_with = "serialize_u64_as_string",
+        deserialize_with = "deserialize_u64_flexible"
+    )]
     pub total_blob_bytes: u64,
-    #[serde(serialize_with = "serialize_u64_as_string")]
+    #[serde(
+        serialize_with = "serialize_u64_as_string",
+        deserialize_with = "deserialize_u64_flexible"
+    )]
     pub downloaded_blob_bytes: u64,
-    #[serde(serialize_with = "serialize_u64_as_string")]
+    #[serde(
+        serialize_with = "serialize_u64_as_string",
+        deserialize_with = "deserialize_u64_flexible"
+    )]
     pub opfs_storage_used: u64,
 
     // Preferences migration
@@ -278,11 +760,14 @@ pub struct MigrationProgress {
     pub last_checkpoint: Option<String>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Default)]
 pub struct RepoProgress {
     pub export_complete: bool,
     pub import_complete: bool,
-    #[serde(serialize_with = "serialize_u64_as_string")]
+    #[serde(
+        serialize_with = "serialize_u64_as_string",
+        deserialize_with = "deserialize_u64_flexible"
+    )]
     pub car_size: u64,
     pub error: Option<String>,
 }
@@ -291,23 +776,29 @@ pub struct RepoProgress {
 pub struct BlobProgress {
     pub total_blobs: u32,
     pub processed_blobs: u32,
-    #[serde(serialize_with = "serialize_u64_as_string")]
+    #[serde(
+        serialize_with = "serialize_u64_as_string",
+        deserialize_with = "deserialize_u64_flexible"
+    )]
     pub total_bytes: u64,
-    #[serde(serialize_with = "serialize_u64_as_string")]
+    #[serde(
+        serialize_with = "serialize_u64_as_string",
+        deserialize_with = "deserialize_u64_flexible"
+    )]
     pub processed_bytes: u64,
     pub current_blob_cid: Option<String>,
     pub current_blob_progress: Option<f64>,
     pub error: Option<String>,
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct PreferencesProgress {
     pub export_complete: bool,
     pub import_complete: bool,
     pub error: Option<String>,
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct PlcProgress {
     pub recommendation_complete: bool,
     pub token_requested: bool,
@@ -327,21 +818,89 @@ pub struct MigrationState {
     // Migration process state
     pub is_migrating: bool,
     pub migration_error: Option<String>,
+    // The user stopped the run, as opposed to a step failing
+    pub migration_aborted: bool,
     pub migration_step: String,
     pub new_pds_session: Option<SessionCredentials>,
     // Extended progress tracking
     pub migration_progress: MigrationProgress,
     pub repo_progress: RepoProgress,
+    // Target-side indexing progress while the repository imports
+    pub repo_import_status: Option<RepoImportStatus>,
     pub blob_progress: BlobProgress,
+    // Blobs above the large-blob threshold currently in flight
+    pub large_blob_transfers: Vec<LargeBlobTransfer>,
     pub preferences_progress: PreferencesProgress,
     pub plc_progress: PlcProgress,
     pub migration_completed: bool,
+    // Set when the target PDS activated the account at creation
+    pub target_auto_activation: Option<TargetAutoActivation>,
     // PLC recommendation storage
     pub plc_recommendation: Option<String>,
     // Original PDS describe response cache
     pub original_pds_describe: Option<PdsDescribeResponse>,
-    // Console messages for blob progress display (max 10 recent messages)
-    pub console_messages: VecDeque<String>,
+    // Latest origin storage usage sample while blobs stream
+    pub storage_usage: Option<StorageUsage>,
+    // Persistent storage backends this browser can open
+    pub storage_capabilities: Option<StorageCapabilities>,
+    // Saved copy of the exported preferences
+    pub preferences_backup: Option<PreferencesBackupInfo>,
+    // Preferences read back from the new PDS, compared category by category
+    pub preferences_round_trip: Option<PreferencesRoundTrip>,
+    // Summary of the blob checksum manifest recorded during upload
+    pub blob_manifest: Option<BlobManifestInfo>,
+    // Enumeration method used for the blob phase and what each listing returned
+    pub blob_enumeration: Option<BlobEnumerationSummary>,
+    // CIDs records reference but the source PDS answers 404 for
+    pub unrecoverable_blobs: Vec<String>,
+    // Result of diffing the repository exports at full verification
+    pub repo_car_diff: Option<RepoCarDiff>,
+    // Diagnosis of a refused repository import
+    pub repo_import_failure: Option<ImportDiagnosis>,
+    // Request body size check against the new PDS and its proxy
+    pub body_limit_report: Option<BodyLimitReport>,
+    // Hex sha-256 of the exported repository CAR
+    pub repo_car_checksum: Option<String>,
+    // Old PDS endpoint followed after the original stopped answering
+    pub source_endpoint_change: Option<SourceEndpointChange>,
+    // When the PLC token email was last requested
+    pub plc_token_delivery: Option<PlcTokenDelivery>,
+    // Repo signing key reserved on the target before the account was created
+    pub reserved_signing_key: Option<String>,
+    // Keep-in-sync loop status, while that plan runs
+    pub continuous_sync: Option<ContinuousSyncStatus>,
+    // Earlier attempt checked against the new PDS, when this run resumed one
+    pub resume_reconciliation: Option<ResumeReconciliation>,
+    // Whether the relay and AppView have picked up the activated account
+    pub propagation: Option<PropagationReport>,
+    // Which saved feeds and lists still resolve from the new account
+    pub saved_feeds: Option<SavedFeedsReport>,
+    // Labeler or feed generator services, when the account has them
+    pub service_account: Option<ServiceAccountProfile>,
+    // Whether the new password was handed to the browser's password manager
+    pub credential_save: CredentialSaveStatus,
+    // App passwords found on the old account, to re-create on the new one
+    pub app_passwords: Option<Vec<ClientAppPassword>>,
+    // Whether the new account's email is confirmed or a code is on its way
+    pub email_verification: EmailVerification,
+    // Records and blobs written by the content-copy plan
+    pub content_copy_report: Option<ContentCopyReport>,
+    // Console messages for blob progress display (ring buffer of CONSOLE_MESSAGE_CAPACITY)
+    pub console_messages: VecDeque<ConsoleEntry>,
+    // Console messages received this session, including ones dropped from the buffer
+    pub console_messages_total: u64,
+    // Start/finish times for the migration timeline
+    pub step_timings: StepTimings,
+    // Last time anything counted as forward progress, for stall detection
+    pub progress_watch: ProgressWatch,
+    // Progress shown as sentences, with no bars or animations
+    pub text_only_progress: bool,
+    // Unit system for sizes and transfer rates
+    pub byte_units: ByteUnits,
+    // Console log filter applied by the console macros
+    pub log_filter: String,
+    // Background-tab throttling and wake lock state for the running migration
+    pub tab_activity: TabActivity,
     // Performance optimization: cache for unified_blob_progress
     pub cached_unified_blob_progress: Option<BlobProgress>,
     pub blob_progress_cache_key: u64,
@@ -349,7 +908,15 @@ pub struct MigrationState {
 
 impl MigrationState {
     /// Reduces the state based on an action
+    ///
+    /// Thin wrapper over `reduce_in_place` so both entry points share one reducer.
     pub fn reduce(mut self, action: MigrationAction) -> Self {
+        self.reduce_in_place(action);
+        self
+    }
+
+    /// Reduces the state based on an action in-place (preserves Dioxus Signal reactivity)
+    pub fn reduce_in_place(&mut self, action: MigrationAction) {
         match action {
             // Form 1 actions
             MigrationAction::SetHandle(handle) => {
@@ -376,6 +943,12 @@ impl MigrationState {
             MigrationAction::SetOriginalHandle(handle) => {
                 self.form1.original_handle = handle;
             }
+            MigrationAction::SetResolvedPds(pds) => {
+                self.form1.resolved_pds = pds;
+            }
+            MigrationAction::SetPdsOverride(pds) => {
+                self.form1.pds_override = pds;
+            }
 
             // Form 2 actions
             MigrationAction::SetNewPdsUrl(url) => {
@@ -407,48 +980,89 @@ impl MigrationState {
             MigrationAction::SetInviteCode(code) => {
                 self.form3.invite_code = code;
             }
-            MigrationAction::SetCheckingHandle(checking) => {
-                self.form3.is_checking_handle = checking;
-            }
             MigrationAction::SetSelectedDomain(domain) => {
                 self.form3.selected_domain = Some(domain);
             }
             MigrationAction::SetVerificationCode(code) => {
                 self.form3.verification_code = code;
             }
+            MigrationAction::SetVerificationPhone(phone) => {
+                self.form3.verification_phone = phone;
+            }
+            MigrationAction::SetVerificationRejected(rejected) => {
+                self.form3.verification_rejected = rejected;
+            }
+            MigrationAction::SetExistingDidRejected(rejected) => {
+                self.form3.existing_did_rejected = rejected;
+            }
+            MigrationAction::SetVerificationLevel(level) => {
+                self.form3.verification_level = Some(level);
+            }
+            MigrationAction::SetBlobEnumerationMethod(method) => {
+                self.form3.blob_enumeration_method = Some(method);
+            }
+            MigrationAction::SetBlobRetries(retries) => {
+                self.form3.blob_retries = Some(retries);
+            }
+            MigrationAction::SetBlobNotFoundPolicy(policy) => {
+                self.form3.blob_not_found_policy = Some(policy);
+            }
+            MigrationAction::SetPlanVariant(variant) => {
+                self.form3.plan_variant = variant;
+            }
 
             // Form 4 - PLC Verification actions
             MigrationAction::SetPlcVerificationCode(code) => {
                 self.form4.verification_code = code;
             }
-            MigrationAction::SetPlcUnsigned(plc_unsigned) => {
-                self.form4.plc_unsigned = plc_unsigned;
+            MigrationAction::SetPlcUnsigned(unsigned) => {
+                self.form4.plc_unsigned = unsigned;
             }
             MigrationAction::SetPlcVerifying(verifying) => {
                 self.form4.is_verifying = verifying;
             }
+            MigrationAction::SetPlcTokenDelivery(delivery) => {
+                self.plc_token_delivery = delivery;
+            }
+            MigrationAction::SetPlcSignedOperation(operation) => {
+                self.form4.signed_operation = operation;
+            }
+            MigrationAction::SetPlcUpdateConfirmation(confirmation) => {
+                self.form4.update_confirmation = confirmation;
+            }
+            MigrationAction::SetPlcUpdateConfirmed(confirmed) => {
+                self.form4.update_confirmed = confirmed;
+            }
+            MigrationAction::SetOldAccountGrace(days) => {
+                self.form4.old_account_grace_days = days;
+            }
 
             // Validation actions
             MigrationAction::SetHandleValidation(validation) => {
                 self.validations.handle = validation;
             }
+            MigrationAction::SetCheckingHandle(checking) => {
+                self.form3.is_checking_handle = checking;
+            }
 
             // Migration process actions
             MigrationAction::SetMigrating(migrating) => {
                 crate::console_info!(
-                    "[REDUCER] SetMigrating reducer entered with value: {} - timestamp: {}",
-                    migrating,
-                    js_sys::Date::now()
+                    "[REDUCER] SetMigrating reducer entered with value: {}",
+                    migrating
                 );
 
                 let old_value = self.is_migrating;
                 self.is_migrating = migrating;
+                if migrating && !old_value {
+                    self.tab_activity = TabActivity::default();
+                    self.migration_aborted = false;
+                }
 
                 crate::console_info!(
-                    "[STATE] Migration state changing: is_migrating={} -> {} - timestamp: {}",
+                    "[STATE] Migration state changing: is_migrating={} -> {}",
                     old_value,
-                    migrating,
-                    js_sys::Date::now()
+                    migrating
                 );
 
                 crate::console_info!("[REDUCER] SetMigrating reducer completed successfully - final is_migrating: {}", 
@@ -457,15 +1071,19 @@ impl MigrationState {
             MigrationAction::SetMigrationError(error) => {
                 self.migration_error = error;
             }
+            MigrationAction::SetMigrationAborted(aborted) => {
+                self.migration_aborted = aborted;
+            }
             MigrationAction::SetMigrationStep(step) => {
                 self.migration_step = step;
             }
             MigrationAction::SetNewPdsSession(session) => {
                 self.new_pds_session = session;
             }
+            MigrationAction::SetReservedSigningKey(key) => {
+                self.reserved_signing_key = key;
+            }
             MigrationAction::SetCurrentStep(step) => {
-                let old_step = &self.current_step;
-
                 // Initialize domain selection when entering MigrationDetails form
                 if step == FormStep::MigrationDetails && self.form3.selected_domain.is_none() {
                     let domains = self.get_available_domains();
@@ -474,8 +1092,8 @@ impl MigrationState {
                     }
                 }
 
-                crate::console_info!("[FORM] Transitioning from {:?} to {:?} - migration_status: is_migrating={}, completed={} - timestamp: {}", 
-                    old_step, step, self.is_migrating, self.migration_completed, js_sys::Date::now());
+                crate::console_info!("[FORM] Transitioning from {:?} to {:?} - migration_status: is_migrating={}, completed={}", 
+                    self.current_step, step, self.is_migrating, self.migration_completed);
 
                 self.current_step = step;
             }
@@ -488,12 +1106,18 @@ impl MigrationState {
                 self.repo_progress = progress;
                 self.update_unified_blob_progress_cache();
             }
+            MigrationAction::SetRepoImportStatus(status) => {
+                self.repo_import_status = status;
+            }
             MigrationAction::SetBlobProgress(progress) => {
                 crate::console_debug!("[BLOB] Progress state updated: total={}, processed={}, total_bytes={}, processed_bytes={}", 
                     progress.total_blobs, progress.processed_blobs, progress.total_bytes, progress.processed_bytes);
                 self.blob_progress = progress;
                 self.update_unified_blob_progress_cache();
             }
+            MigrationAction::SetLargeBlobTransfers(transfers) => {
+                self.large_blob_transfers = transfers;
+            }
             MigrationAction::SetPreferencesProgress(progress) => {
                 self.preferences_progress = progress;
             }
@@ -503,198 +1127,178 @@ impl MigrationState {
             MigrationAction::SetMigrationCompleted(completed) => {
                 let old_value = self.migration_completed;
                 self.migration_completed = completed;
-                crate::console_info!("[STATE] Migration completion changing: migration_completed={} -> {} - timestamp: {}", 
-                    old_value, completed, js_sys::Date::now());
+                crate::console_info!(
+                    "[STATE] Migration completion changing: migration_completed={} -> {}",
+                    old_value,
+                    completed
+                );
+            }
+            MigrationAction::SetTargetAutoActivation(handling) => {
+                self.target_auto_activation = handling;
             }
+
+            // PLC recommendation storage
             MigrationAction::SetPlcRecommendation(recommendation) => {
                 self.plc_recommendation = recommendation;
             }
+            // Original PDS describe response cache
             MigrationAction::SetOriginalPdsDescribe(describe) => {
                 self.original_pds_describe = describe;
             }
-            MigrationAction::AddConsoleMessage(message) => {
-                self.console_messages.push_back(message);
-                // Keep only the most recent 10 messages
-                while self.console_messages.len() > 10 {
-                    self.console_messages.pop_front();
-                }
+            // Live storage usage sample
+            MigrationAction::SetStorageUsage(usage) => {
+                self.storage_usage = usage;
             }
-        }
-        self
-    }
-
-    /// Reduces the state based on an action in-place (preserves Dioxus Signal reactivity)
-    pub fn reduce_in_place(&mut self, action: MigrationAction) {
-        match action {
-            // Form 1 actions
-            MigrationAction::SetHandle(handle) => {
-                self.form1.handle = handle;
+            MigrationAction::SetStorageCapabilities(capabilities) => {
+                self.storage_capabilities = capabilities;
             }
-            MigrationAction::SetPassword(password) => {
-                self.form1.password = password;
+            MigrationAction::SetBlobManifest(manifest) => {
+                self.blob_manifest = manifest;
             }
-            MigrationAction::SetProvider(provider) => {
-                self.form1.provider = provider;
+            MigrationAction::SetBlobEnumeration(summary) => {
+                self.blob_enumeration = summary;
             }
-            MigrationAction::SetLoading(loading) => {
-                self.form1.is_loading = loading;
+            MigrationAction::SetUnrecoverableBlobs(cids) => {
+                self.unrecoverable_blobs = cids;
             }
-            MigrationAction::SetAuthenticating(auth) => {
-                self.form1.is_authenticating = auth;
+            MigrationAction::SetRepoCarDiff(diff) => {
+                self.repo_car_diff = diff;
             }
-            MigrationAction::SetLoginResponse(response) => {
-                self.form1.login_response = response;
+            MigrationAction::SetRepoImportFailure(diagnosis) => {
+                self.repo_import_failure = diagnosis;
             }
-            MigrationAction::SetSessionStored(stored) => {
-                self.form1.session_stored = stored;
+            MigrationAction::SetBodyLimitReport(report) => {
+                self.body_limit_report = report;
             }
-            MigrationAction::SetOriginalHandle(handle) => {
-                self.form1.original_handle = handle;
+            MigrationAction::SetRepoCarChecksum(checksum) => {
+                self.repo_car_checksum = checksum;
             }
-
-            // Form 2 actions
-            MigrationAction::SetNewPdsUrl(url) => {
-                self.form2.pds_url = url;
+            MigrationAction::SetSourceEndpointChange(change) => {
+                self.source_endpoint_change = change;
             }
-            MigrationAction::SetForm2Submitted(submitted) => {
-                self.form2.submitted = submitted;
+            MigrationAction::SetContinuousSync(status) => {
+                self.continuous_sync = status;
             }
-            MigrationAction::SetPdsDescribeResponse(response) => {
-                self.form2.describe_response = response;
+            MigrationAction::SetResumeReconciliation(reconciliation) => {
+                self.resume_reconciliation = reconciliation;
             }
-            MigrationAction::SetDescribingPds(describing) => {
-                self.form2.is_describing = describing;
+            MigrationAction::SetPropagation(report) => {
+                self.propagation = report;
             }
-
-            // Form 3 actions
-            MigrationAction::SetNewHandle(handle) => {
-                self.form3.handle = handle;
-            }
-            MigrationAction::SetNewPassword(password) => {
-                self.form3.password = password;
+            MigrationAction::SetSavedFeeds(report) => {
+                self.saved_feeds = report;
             }
-            MigrationAction::SetNewPasswordConfirm(password) => {
-                self.form3.password_confirm = password;
-            }
-            MigrationAction::SetEmailAddress(email) => {
-                self.form3.email = email;
-            }
-            MigrationAction::SetInviteCode(code) => {
-                self.form3.invite_code = code;
-            }
-            MigrationAction::SetSelectedDomain(domain) => {
-                self.form3.selected_domain = Some(domain);
-            }
-            MigrationAction::SetVerificationCode(code) => {
-                self.form3.verification_code = code;
-            }
-
-            // Form 4 - PLC Verification actions
-            MigrationAction::SetPlcVerificationCode(code) => {
-                self.form4.verification_code = code;
+            MigrationAction::SetServiceAccount(profile) => {
+                self.service_account = profile;
             }
-            MigrationAction::SetPlcUnsigned(unsigned) => {
-                self.form4.plc_unsigned = unsigned;
+            MigrationAction::SetCredentialSave(status) => {
+                self.credential_save = status;
             }
-            MigrationAction::SetPlcVerifying(verifying) => {
-                self.form4.is_verifying = verifying;
+            MigrationAction::SetAppPasswords(passwords) => {
+                self.app_passwords = passwords;
             }
-
-            // Validation actions
-            MigrationAction::SetHandleValidation(validation) => {
-                self.validations.handle = validation;
+            MigrationAction::SetEmailVerification(verification) => {
+                self.email_verification = verification;
             }
-            MigrationAction::SetCheckingHandle(checking) => {
-                // This should likely update the form3.is_checking_handle field instead
-                self.form3.is_checking_handle = checking;
+            MigrationAction::SetContentCopyReport(report) => {
+                self.content_copy_report = report;
             }
-
-            // Migration process actions
-            MigrationAction::SetMigrating(migrating) => {
-                crate::console_info!(
-                    "[REDUCER] SetMigrating reducer entered with value: {} - timestamp: {}",
-                    migrating,
-                    js_sys::Date::now()
-                );
-
-                let old_value = self.is_migrating;
-                self.is_migrating = migrating;
-
-                crate::console_info!(
-                    "[STATE] Migration state changing: is_migrating={} -> {} - timestamp: {}",
-                    old_value,
-                    migrating,
-                    js_sys::Date::now()
-                );
-
-                crate::console_info!("[REDUCER] SetMigrating reducer completed successfully - final is_migrating: {}", 
-                    self.is_migrating);
+            MigrationAction::SetPreferencesBackup(backup) => {
+                self.preferences_backup = backup;
             }
-            MigrationAction::SetMigrationError(error) => {
-                self.migration_error = error;
+            MigrationAction::SetPreferencesRoundTrip(report) => {
+                self.preferences_round_trip = report;
             }
-            MigrationAction::SetMigrationStep(step) => {
-                self.migration_step = step;
+            MigrationAction::SetTextOnlyProgress(enabled) => {
+                self.text_only_progress = enabled;
             }
-            MigrationAction::SetNewPdsSession(session) => {
-                self.new_pds_session = session;
+            MigrationAction::SetByteUnits(units) => {
+                self.byte_units = units;
             }
-            MigrationAction::SetCurrentStep(step) => {
-                self.current_step = step;
+            MigrationAction::SetLogFilter(spec) => {
+                self.log_filter = spec;
             }
-
-            // Extended migration progress tracking
-            MigrationAction::SetMigrationProgress(progress) => {
-                self.migration_progress = progress;
-            }
-            MigrationAction::SetRepoProgress(progress) => {
-                self.repo_progress = progress;
-                self.update_unified_blob_progress_cache();
-            }
-            MigrationAction::SetBlobProgress(progress) => {
-                crate::console_debug!("[BLOB] Progress state updated: total={}, processed={}, total_bytes={}, processed_bytes={}", 
-                    progress.total_blobs, progress.processed_blobs, progress.total_bytes, progress.processed_bytes);
-                self.blob_progress = progress;
-                self.update_unified_blob_progress_cache();
-            }
-            MigrationAction::SetPreferencesProgress(progress) => {
-                self.preferences_progress = progress;
-            }
-            MigrationAction::SetPlcProgress(progress) => {
-                self.plc_progress = progress;
-            }
-            MigrationAction::SetMigrationCompleted(completed) => {
-                let old_value = self.migration_completed;
-                self.migration_completed = completed;
-                crate::console_info!("[STATE] Migration completion changing: migration_completed={} -> {} - timestamp: {}", 
-                    old_value, completed, js_sys::Date::now());
+            MigrationAction::SetTabHidden(hidden) => {
+                let now_ms = crate::utils::platform::now_ms() as u64;
+                self.tab_activity.set_hidden(hidden, now_ms);
+                if !hidden {
+                    // Time spent in the background doesn't count towards a stall
+                    self.progress_watch.resume(now_ms);
+                }
             }
-
-            // PLC recommendation storage
-            MigrationAction::SetPlcRecommendation(recommendation) => {
-                self.plc_recommendation = recommendation;
+            MigrationAction::RecordTimerDrift(drift_ms) => {
+                self.tab_activity.record_drift(drift_ms);
             }
-            // Original PDS describe response cache
-            MigrationAction::SetOriginalPdsDescribe(describe) => {
-                self.original_pds_describe = describe;
+            MigrationAction::SetWakeLockStatus(status) => {
+                self.tab_activity.wake_lock = status;
             }
-            MigrationAction::AddConsoleMessage(message) => {
-                self.console_messages.push_back(message);
-                // Keep only the most recent 10 messages
-                while self.console_messages.len() > 10 {
+            MigrationAction::AddConsoleMessage(mut entry) => {
+                self.console_messages_total += 1;
+                entry.seq = self.console_messages_total;
+                self.console_messages.push_back(entry);
+                while self.console_messages.len() > CONSOLE_MESSAGE_CAPACITY {
                     self.console_messages.pop_front();
                 }
             }
         }
+
+        let now_ms = crate::utils::platform::now_ms() as u64;
+        let statuses = self.timeline_statuses();
+        self.step_timings.record(&statuses, now_ms);
+        let fingerprint = self.progress_fingerprint();
+        self.progress_watch.observe(fingerprint, now_ms);
     }
 
-    /// Whether the target PDS requires captcha verification for account creation
-    pub fn captcha_required(&self) -> bool {
+    /// Verification level for this migration: the user's choice, else the configured default
+    pub fn verification_level(&self) -> VerificationLevel {
+        self.form3
+            .verification_level
+            .unwrap_or_else(|| get_global_config().verification.level)
+            .effective()
+    }
+
+    /// Blob enumeration method for this migration: the user's choice, else the configured default
+    pub fn blob_enumeration_method(&self) -> BlobEnumerationMethod {
+        self.form3
+            .blob_enumeration_method
+            .unwrap_or(get_global_config().blob.enumeration_method)
+    }
+
+    /// Policy for blobs the source PDS has lost: the user's choice, else the configured default
+    pub fn blob_not_found_policy(&self) -> BlobNotFoundPolicy {
+        self.form3
+            .blob_not_found_policy
+            .unwrap_or(get_global_config().blob.not_found_policy)
+    }
+
+    /// Retry budgets for this migration: the configured ones, with the user's blob retries
+    pub fn retry_config(&self) -> RetryConfig {
+        let config = get_global_config().retry;
+        match self.form3.blob_retries {
+            Some(retries) => config.with_blob_retries(retries),
+            None => config,
+        }
+    }
+
+    /// Only the small-account flow can run in this browser
+    pub fn minimal_flow_required(&self) -> bool {
+        self.storage_capabilities
+            .is_some_and(|capabilities| capabilities.requires_minimal_flow())
+    }
+
+    /// Signup gating advertised by the target PDS
+    pub fn signup_gating(&self) -> SignupGating {
         self.form2
             .describe_response
             .as_ref()
-            .and_then(|r| r.phone_verification_required)
-            .unwrap_or(false)
+            .map(SignupGating::from_describe)
+            .unwrap_or(SignupGating::Unknown)
+    }
+
+    /// Whether the target PDS requires captcha verification for account creation,
+    /// either advertised up front or learned from a rejected createAccount
+    pub fn captcha_required(&self) -> bool {
+        self.signup_gating().uses_verification_gate() || self.form3.verification_rejected
     }
 
     /// Helper methods for common state queries
@@ -795,24 +1399,9 @@ impl MigrationState {
         // Use the existing blob_progress which gets updated during both phases
         // Repository phase: estimates blobs from streamed data
         // Blob phase: uses actual blob counts and continues from where repository left off
-        let mut unified = self.blob_progress.clone();
-
-        // If we're in repository migration phase and have repo progress, ensure we show something
-        if unified.total_blobs == 0
-            && unified.processed_blobs == 0
-            && self.repo_progress.car_size > 0
-        {
-            // Estimate blobs from repository size as fallback
-            let estimated_blobs = std::cmp::max(1, (self.repo_progress.car_size / 10_000) as u32);
-            unified.total_blobs = estimated_blobs;
-            unified.processed_blobs = estimated_blobs;
-            unified.total_bytes = self.repo_progress.car_size;
-            unified.processed_bytes = self.repo_progress.car_size;
-        }
-
         // Note: We can't update the cache here because self is immutable
         // The cache will be updated in the reducer when state changes
-        unified
+        crate::migration::progress::unify_blob_progress(&self.blob_progress, &self.repo_progress)
     }
 
     /// Update the unified blob progress cache - called from the reducer when relevant state changes
@@ -820,19 +1409,10 @@ impl MigrationState {
         let current_cache_key = self.calculate_blob_progress_cache_key();
         if self.blob_progress_cache_key != current_cache_key {
             // Recalculate and cache the result
-            let mut unified = self.blob_progress.clone();
-
-            if unified.total_blobs == 0
-                && unified.processed_blobs == 0
-                && self.repo_progress.car_size > 0
-            {
-                let estimated_blobs =
-                    std::cmp::max(1, (self.repo_progress.car_size / 10_000) as u32);
-                unified.total_blobs = estimated_blobs;
-                unified.processed_blobs = estimated_blobs;
-                unified.total_bytes = self.repo_progress.car_size;
-                unified.processed_bytes = self.repo_progress.car_size;
-            }
+            let unified = crate::migration::progress::unify_blob_progress(
+                &self.blob_progress,
+                &self.repo_progress,
+            );
 
             self.cached_unified_blob_progress = Some(unified);
             self.blob_progress_cache_key = current_cache_key;
@@ -873,6 +1453,8 @@ impl Default for LoginForm {
             login_response: None,
             session_stored: false,
             original_handle: String::new(),
+            resolved_pds: None,
+            pds_override: None,
         }
     }
 }
@@ -898,17 +1480,51 @@ impl Default for MigrationState {
             validations: ValidationStates::default(),
             is_migrating: false,
             migration_error: None,
+            migration_aborted: false,
             migration_step: String::new(),
             new_pds_session: None,
             migration_progress: MigrationProgress::default(),
             repo_progress: RepoProgress::default(),
+            repo_import_status: None,
             blob_progress: BlobProgress::default(),
+            large_blob_transfers: Vec::new(),
             preferences_progress: PreferencesProgress::default(),
             plc_progress: PlcProgress::default(),
             migration_completed: false,
+            target_auto_activation: None,
             plc_recommendation: None,
             original_pds_describe: None,
+            storage_usage: None,
+            storage_capabilities: None,
+            preferences_backup: None,
+            preferences_round_trip: None,
+            blob_manifest: None,
+            blob_enumeration: None,
+            unrecoverable_blobs: Vec::new(),
+            repo_car_diff: None,
+            repo_import_failure: None,
+            body_limit_report: None,
+            repo_car_checksum: None,
+            source_endpoint_change: None,
+            plc_token_delivery: None,
+            reserved_signing_key: None,
+            continuous_sync: None,
+            resume_reconciliation: None,
+            propagation: None,
+            saved_feeds: None,
+            service_account: None,
+            credential_save: CredentialSaveStatus::default(),
+            app_passwords: None,
+            email_verification: EmailVerification::default(),
+            content_copy_report: None,
             console_messages: VecDeque::new(),
+            console_messages_total: 0,
+            step_timings: StepTimings::default(),
+            progress_watch: ProgressWatch::default(),
+            text_only_progress: false,
+            byte_units: ByteUnits::default(),
+            log_filter: crate::utils::log_filter::LogFilter::default().to_string(),
+            tab_activity: TabActivity::default(),
             cached_unified_blob_progress: None,
             blob_progress_cache_key: 0,
         }
@@ -925,3 +1541,166 @@ where
 {
     serializer.serialize_str(&value.to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redacted_masks_email_verification() {
+        let action = MigrationAction::SetEmailVerification(EmailVerification::new(
+            "alice@example.com",
+            EmailVerificationStatus::UpdateTokenSent {
+                new_email: "alice@new.example".to_string(),
+            },
+        ));
+        let MigrationAction::SetEmailVerification(redacted) = action.redacted() else {
+            panic!("redaction changed the action");
+        };
+        assert_eq!(redacted.email, "[redacted]@example.com");
+        assert_eq!(
+            redacted.status,
+            EmailVerificationStatus::UpdateTokenSent {
+                new_email: "[redacted]@new.example".to_string()
+            }
+        );
+        assert!(!format!("{:?}", action.redacted()).contains("alice"));
+        assert_eq!(mask_email(""), "");
+        assert_eq!(mask_email("not-an-address"), REDACTED);
+    }
+
+    #[test]
+    fn test_console_messages_ring_buffer() {
+        let mut state = MigrationState::default();
+        for i in 0..CONSOLE_MESSAGE_CAPACITY + 5 {
+            state.reduce_in_place(MigrationAction::AddConsoleMessage(ConsoleEntry::new(
+                ConsoleLevel::Info,
+                "test",
+                format!("message {}", i),
+            )));
+        }
+
+        assert_eq!(state.console_messages.len(), CONSOLE_MESSAGE_CAPACITY);
+        assert_eq!(
+            state.console_messages_total,
+            CONSOLE_MESSAGE_CAPACITY as u64 + 5
+        );
+        assert_eq!(state.console_messages.front().unwrap().seq, 6);
+        assert_eq!(state.console_messages.front().unwrap().message, "message 5");
+    }
+
+    #[test]
+    fn test_console_entry_levels_and_legacy_format() {
+        assert!(ConsoleFilter::ErrorsOnly.allows(ConsoleLevel::Error));
+        assert!(!ConsoleFilter::ErrorsOnly.allows(ConsoleLevel::Warn));
+        assert!(ConsoleFilter::Standard.allows(ConsoleLevel::Warn));
+        assert!(!ConsoleFilter::Standard.allows(ConsoleLevel::Debug));
+        assert!(ConsoleFilter::Verbose.allows(ConsoleLevel::Debug));
+
+        let entry = ConsoleEntry::new(ConsoleLevel::Warn, "migration::steps::blob", "low space");
+        let json = serde_json::to_string(&entry).unwrap();
+        assert_eq!(serde_json::from_str::<ConsoleEntry>(&json).unwrap(), entry);
+
+        // Support bundles written before entries were structured hold plain strings
+        let legacy: ConsoleEntry = serde_json::from_str(r#""[ERROR] upload failed""#).unwrap();
+        assert_eq!(legacy.level, ConsoleLevel::Error);
+        assert_eq!(legacy.message, "[ERROR] upload failed");
+    }
+
+    #[test]
+    fn test_target_auto_activation_is_recorded() {
+        let mut state = MigrationState::default();
+        let handling = TargetAutoActivation::LeftActive {
+            reason: "InvalidRequest".to_string(),
+        };
+        state.reduce_in_place(MigrationAction::SetTargetAutoActivation(Some(
+            handling.clone(),
+        )));
+
+        assert_eq!(state.target_auto_activation, Some(handling.clone()));
+        assert!(handling.compatibility_note().contains("InvalidRequest"));
+    }
+
+    #[test]
+    fn test_blob_retries_override_leaves_plc_budget_alone() {
+        let mut state = MigrationState::default();
+        let configured = state.retry_config();
+        state.reduce_in_place(MigrationAction::SetBlobRetries(20));
+
+        let retry = state.retry_config();
+        assert_eq!(retry.blob_download.max_retries, 20);
+        assert_eq!(retry.blob_upload.max_retries, 20);
+        assert_eq!(retry.plc, configured.plc);
+        assert_eq!(retry.repo, configured.repo);
+    }
+
+    #[test]
+    fn test_signup_gating_from_describe_flags() {
+        let describe = |invite, verification| {
+            PdsDescribeResponse::success(
+                vec![".example.com".to_string()],
+                None,
+                "did:web:pds.example.com".to_string(),
+                invite,
+                None,
+                verification,
+            )
+        };
+
+        let gate = SignupGating::from_describe(&describe(Some(false), Some(true)));
+        assert_eq!(gate, SignupGating::VerificationGate);
+        assert!(!gate.shows_invite_code());
+        assert!(gate.uses_verification_gate());
+
+        let unknown = SignupGating::from_describe(&describe(None, None));
+        assert!(unknown.shows_invite_code() && !unknown.requires_invite_code());
+
+        assert_eq!(
+            SignupGating::from_describe(&describe(Some(true), None)),
+            SignupGating::InviteCode
+        );
+        assert_eq!(
+            SignupGating::from_describe(&describe(Some(false), Some(false))),
+            SignupGating::Open
+        );
+    }
+
+    #[test]
+    fn test_rejected_verification_requires_the_gate() {
+        assert!(is_signup_verification_error(
+            r#"Account creation failed: {"error":"InvalidPhoneVerification","message":"Text verification is now required on this server."}"#
+        ));
+        assert!(is_signup_verification_error(
+            "Account creation failed: Phone number verification is required on this server and none was provided."
+        ));
+        assert!(!is_signup_verification_error(
+            r#"Account creation failed: {"error":"InvalidInviteCode"}"#
+        ));
+        assert!(is_existing_did_rejected(
+            r#"Repository import failed: {"error":"InvalidRequest","message":"Service is not accepting repo imports"}"#
+        ));
+        assert!(is_existing_did_rejected(
+            "Account creation failed: Account migration is disabled on this server"
+        ));
+        assert!(!is_existing_did_rejected(
+            r#"Account creation failed: {"error":"HandleNotAvailable","message":"Handle already taken"}"#
+        ));
+        assert!(is_existing_did_rejected(
+            "Account creation failed: Creating accounts with an existing DID is not supported"
+        ));
+        // Generic import failures only share the prefix and the DID
+        for generic in [
+            r#"Repository import failed: {"error":"AccountTakedown","message":"Account has been disabled"}"#,
+            "Repository import failed: did:plc:abc123 is deactivated; writes not allowed",
+            "Repository import failed: CAR version 2 is not supported",
+            "Repository import failed: migration of did:plc:abc123 not permitted while takendown",
+        ] {
+            assert!(!is_existing_did_rejected(generic), "{}", generic);
+        }
+
+        let mut state = MigrationState::default();
+        assert!(!state.captcha_required());
+        state.reduce_in_place(MigrationAction::SetVerificationRejected(true));
+        assert!(state.captcha_required());
+    }
+}
@@ -0,0 +1,88 @@
+//! Blobs the source PDS no longer has
+//!
+//! A record can keep pointing at an image or video its PDS has already lost;
+//! `getBlob` answers 404 for it no matter how often it is asked. Under the
+//! skip-and-record policy those CIDs are collected here, per account, so a
+//! later pass — the re-upload during verification, or a retry — doesn't try
+//! them again and the final report can list them.
+
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+static REGISTER: Mutex<Option<UnrecoverableRegister>> = Mutex::new(None);
+
+struct UnrecoverableRegister {
+    did: String,
+    cids: BTreeSet<String>,
+}
+
+/// Whether a failed blob transfer means the source PDS doesn't have the blob
+pub fn is_source_not_found(error: &str) -> bool {
+    error.contains("Failed to fetch blob stream") && error.contains("HTTP error: 404")
+}
+
+/// Add `cids` to the account's unrecoverable blobs and return all of them
+pub fn record_unrecoverable_blobs(
+    did: &str,
+    cids: impl IntoIterator<Item = String>,
+) -> Vec<String> {
+    let Ok(mut register) = REGISTER.lock() else {
+        return Vec::new();
+    };
+    if register.as_ref().is_none_or(|current| current.did != did) {
+        *register = Some(UnrecoverableRegister {
+            did: did.to_string(),
+            cids: BTreeSet::new(),
+        });
+    }
+    let Some(current) = register.as_mut() else {
+        return Vec::new();
+    };
+    current.cids.extend(cids);
+    current.cids.iter().cloned().collect()
+}
+
+/// Blobs already found missing from the account's source PDS
+pub fn unrecoverable_blobs(did: &str) -> BTreeSet<String> {
+    REGISTER
+        .lock()
+        .ok()
+        .and_then(|register| {
+            register
+                .as_ref()
+                .filter(|current| current.did == did)
+                .map(|current| current.cids.clone())
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unrecoverable_blobs_per_account() {
+        assert!(is_source_not_found(
+            "Failed after 3 retries: Failed to fetch blob stream: HTTP error: 404 Not Found"
+        ));
+        assert!(!is_source_not_found(
+            "Failed after 3 retries: Failed to fetch blob stream: HTTP error: 502 Bad Gateway"
+        ));
+        assert!(!is_source_not_found(
+            "Upload error: HTTP error: 404 Not Found"
+        ));
+
+        let did = "did:plc:unrecoverable";
+        record_unrecoverable_blobs(did, ["bafkb".to_string()]);
+        let all = record_unrecoverable_blobs(did, ["bafka".to_string(), "bafkb".to_string()]);
+        assert_eq!(all, ["bafka", "bafkb"]);
+        assert!(unrecoverable_blobs(did).contains("bafka"));
+        assert!(unrecoverable_blobs("did:plc:other").is_empty());
+
+        record_unrecoverable_blobs("did:plc:other", ["bafkc".to_string()]);
+        assert!(
+            unrecoverable_blobs(did).is_empty(),
+            "a new account starts over"
+        );
+    }
+}
@@ -0,0 +1,278 @@
+//! Append-only log of every blob uploaded to the new PDS
+//!
+//! The checksum manifest in [`blob_manifest`](super::blob_manifest) is only
+//! assembled once the blob step returns, so a tab that crashes or is closed
+//! mid-migration leaves nothing behind. This log is written while blobs
+//! stream: each accepted upload becomes one JSON line (CID, bytes, duration,
+//! attempts) and every [`UPLOAD_LOG_BATCH`] lines are written to OPFS as a new
+//! chunk file. Chunks are never rewritten; they are named after the DID, the
+//! time the run started and their first line, so reading them back in name
+//! order yields the log of every run for that account, crashed ones included.
+//! At most one partial batch is lost when the page dies.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+use opfs::persistent::app_specific_dir;
+use opfs::{CreateWritableOptions, GetDirectoryHandleOptions, GetFileHandleOptions};
+use opfs::{DirectoryHandle as _, FileHandle as _, WritableFileStream as _};
+
+use crate::migration::blob_manifest::record_uploaded_blob;
+use crate::services::client::types::current_time_secs;
+use crate::services::streaming::UploadedBlob;
+use crate::utils::platform::now_ms;
+
+/// Lines buffered before a chunk is written
+pub const UPLOAD_LOG_BATCH: usize = 25;
+
+/// OPFS directory the chunks are written to
+const UPLOAD_LOG_DIR: &str = "upload_log";
+
+/// Uploads recorded in this page for the account being migrated
+static LOG: Mutex<Option<UploadLog>> = Mutex::new(None);
+
+struct UploadLog {
+    did: String,
+    run_started_secs: u64,
+    next_line: u64,
+    pending: Vec<String>,
+}
+
+impl UploadLog {
+    /// Take the buffered lines as a chunk named after its first line
+    fn take_chunk(&mut self) -> Option<UploadLogChunk> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        let lines = std::mem::take(&mut self.pending);
+        let first_line = self.next_line - lines.len() as u64;
+        Some(UploadLogChunk {
+            file_name: format!(
+                "{}-{}-{:08}.ndjson",
+                file_prefix(&self.did),
+                self.run_started_secs,
+                first_line
+            ),
+            lines,
+        })
+    }
+}
+
+/// One accepted upload
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadLogEntry {
+    pub cid: String,
+    pub bytes: u64,
+    pub duration_ms: u64,
+    pub attempts: u32,
+    pub uploaded_at_ms: u64,
+}
+
+impl From<&UploadedBlob<'_>> for UploadLogEntry {
+    fn from(upload: &UploadedBlob<'_>) -> Self {
+        Self {
+            cid: upload.cid.to_string(),
+            bytes: upload.data.len() as u64,
+            duration_ms: upload.duration_ms,
+            attempts: upload.attempts,
+            uploaded_at_ms: now_ms() as u64,
+        }
+    }
+}
+
+/// Lines ready to be written as one chunk file
+#[derive(Debug, Clone, PartialEq)]
+pub struct UploadLogChunk {
+    pub file_name: String,
+    pub lines: Vec<String>,
+}
+
+fn file_prefix(did: &str) -> String {
+    did.replace(':', "_")
+}
+
+/// Start logging for an account; a log already running for it carries on
+pub fn begin_upload_log(did: &str) {
+    if let Ok(mut log) = LOG.lock() {
+        if log.as_ref().is_some_and(|current| current.did == did) {
+            return;
+        }
+        *log = Some(UploadLog {
+            did: did.to_string(),
+            run_started_secs: current_time_secs(),
+            next_line: 0,
+            pending: Vec::new(),
+        });
+    }
+}
+
+/// Buffer an upload; returns a full batch once one is ready to write
+pub fn record_upload(entry: &UploadLogEntry) -> Option<UploadLogChunk> {
+    let mut log = LOG.lock().ok()?;
+    let log = log.as_mut()?;
+    log.pending.push(serde_json::to_string(entry).ok()?);
+    log.next_line += 1;
+    if log.pending.len() >= UPLOAD_LOG_BATCH {
+        log.take_chunk()
+    } else {
+        None
+    }
+}
+
+/// Whatever is buffered, as a chunk
+pub fn take_pending_upload_log() -> Option<UploadLogChunk> {
+    LOG.lock().ok()?.as_mut()?.take_chunk()
+}
+
+/// Upload observer for the blob steps: checksum manifest and upload log
+pub fn observe_upload(upload: &UploadedBlob) {
+    record_uploaded_blob(upload.cid, upload.data);
+    if let Some(chunk) = record_upload(&UploadLogEntry::from(upload)) {
+        spawn_chunk_write(chunk);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn spawn_chunk_write(chunk: UploadLogChunk) {
+    wasm_bindgen_futures::spawn_local(async move {
+        if let Err(e) = write_upload_log_chunk(&chunk).await {
+            crate::console_warn!("[UploadLog] {}", e);
+        }
+    });
+}
+
+/// No OPFS natively; the chunk is dropped
+#[cfg(not(target_arch = "wasm32"))]
+fn spawn_chunk_write(_chunk: UploadLogChunk) {}
+
+/// Write out the buffered lines, e.g. when the blob step returns
+pub async fn flush_upload_log() {
+    if let Some(chunk) = take_pending_upload_log() {
+        if let Err(e) = write_upload_log_chunk(&chunk).await {
+            crate::console_warn!("[UploadLog] {}", e);
+        }
+    }
+}
+
+/// Persist a chunk to OPFS
+pub async fn write_upload_log_chunk(chunk: &UploadLogChunk) -> Result<(), String> {
+    let app_dir = app_specific_dir()
+        .await
+        .map_err(|e| format!("OPFS unavailable: {:?}", e))?;
+    let dir = app_dir
+        .get_directory_handle_with_options(
+            UPLOAD_LOG_DIR,
+            &GetDirectoryHandleOptions { create: true },
+        )
+        .await
+        .map_err(|e| format!("Failed to open {}: {:?}", UPLOAD_LOG_DIR, e))?;
+    let mut file = dir
+        .get_file_handle_with_options(&chunk.file_name, &GetFileHandleOptions { create: true })
+        .await
+        .map_err(|e| format!("Failed to create {}: {:?}", chunk.file_name, e))?;
+    let mut writer = file
+        .create_writable_with_options(&CreateWritableOptions {
+            keep_existing_data: false,
+        })
+        .await
+        .map_err(|e| format!("Failed to open {} for writing: {:?}", chunk.file_name, e))?;
+    let mut contents = chunk.lines.join("\n");
+    contents.push('\n');
+    writer
+        .write_at_cursor_pos(contents.into_bytes())
+        .await
+        .map_err(|e| format!("Failed to write {}: {:?}", chunk.file_name, e))?;
+    writer
+        .close()
+        .await
+        .map_err(|e| format!("Failed to finish {}: {:?}", chunk.file_name, e))
+}
+
+/// Every chunk written for an account, oldest run first, as NDJSON
+pub async fn read_upload_log(did: &str) -> Result<String, String> {
+    use futures_util::StreamExt;
+
+    let app_dir = app_specific_dir()
+        .await
+        .map_err(|e| format!("OPFS unavailable: {:?}", e))?;
+    let dir = app_dir
+        .get_directory_handle_with_options(
+            UPLOAD_LOG_DIR,
+            &GetDirectoryHandleOptions { create: true },
+        )
+        .await
+        .map_err(|e| format!("Failed to open {}: {:?}", UPLOAD_LOG_DIR, e))?;
+    let prefix = format!("{}-", file_prefix(did));
+    let mut files: Vec<_> = dir
+        .entries()
+        .await
+        .map_err(|e| format!("Failed to list {}: {:?}", UPLOAD_LOG_DIR, e))?
+        .filter_map(|entry| async move {
+            match entry {
+                Ok((name, opfs::DirectoryEntry::File(file))) => Some((name, file)),
+                _ => None,
+            }
+        })
+        .filter(|(name, _)| std::future::ready(name.starts_with(&prefix)))
+        .collect()
+        .await;
+    files.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut log = String::new();
+    for (name, file) in files {
+        let bytes = file
+            .read()
+            .await
+            .map_err(|e| format!("Failed to read {}: {:?}", name, e))?;
+        log.push_str(&String::from_utf8_lossy(&bytes));
+    }
+    Ok(log)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(cid: &str) -> UploadLogEntry {
+        UploadLogEntry {
+            cid: cid.to_string(),
+            bytes: 1024,
+            duration_ms: 250,
+            attempts: 2,
+            uploaded_at_ms: 1_700_000_000_000,
+        }
+    }
+
+    #[test]
+    fn test_upload_log_batches_lines_into_ordered_chunks() {
+        begin_upload_log("did:plc:uploadlog");
+        let _ = take_pending_upload_log();
+
+        let mut chunks = Vec::new();
+        for i in 0..UPLOAD_LOG_BATCH + 3 {
+            chunks.extend(record_upload(&entry(&format!("bafkrei{}", i))));
+        }
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].lines.len(), UPLOAD_LOG_BATCH);
+        assert!(chunks[0].file_name.starts_with("did_plc_uploadlog-"));
+        assert!(chunks[0].file_name.ends_with("-00000000.ndjson"));
+        let first: UploadLogEntry = serde_json::from_str(&chunks[0].lines[0]).unwrap();
+        assert_eq!(first, entry("bafkrei0"));
+
+        let rest = take_pending_upload_log().unwrap();
+        assert_eq!(rest.lines.len(), 3);
+        assert!(rest
+            .file_name
+            .ends_with(&format!("-{:08}.ndjson", UPLOAD_LOG_BATCH)));
+        assert!(take_pending_upload_log().is_none());
+
+        // Restarting for the same account keeps counting lines
+        begin_upload_log("did:plc:uploadlog");
+        record_upload(&entry("bafkreilater"));
+        let later = take_pending_upload_log().unwrap();
+        assert!(later
+            .file_name
+            .ends_with(&format!("-{:08}.ndjson", UPLOAD_LOG_BATCH + 3)));
+    }
+}
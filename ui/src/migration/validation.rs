@@ -3,27 +3,57 @@
 //! This module handles validation and verification of migration steps,
 //! including blob migration verification and data integrity checking.
 
+#[cfg(feature = "web")]
+use async_trait::async_trait;
 use dioxus::prelude::*;
 
 #[cfg(feature = "web")]
-use crate::services::client::{ClientSessionCredentials, PdsClient};
+use crate::migration::car_diff::{CarDiffer, CarStreamReader};
+#[cfg(feature = "web")]
+use crate::migration::{
+    plan::PlanStep,
+    steps::{MigrationStep, StepContext},
+};
+#[cfg(feature = "web")]
+use crate::services::client::{ClientSessionCredentials, NewPds, OldPds, PdsClient};
+use crate::services::config::VerificationLevel;
+#[cfg(feature = "web")]
+use futures_util::StreamExt;
 
+#[cfg(feature = "hash-verification")]
+use crate::migration::integrity::verify_blob_bytes;
+#[cfg(feature = "hash-verification")]
+use crate::services::config::get_global_config;
+
+#[cfg(feature = "web")]
+use crate::migration::unrecoverable_blobs::unrecoverable_blobs;
 use crate::migration::{
     steps::blob::execute_streaming_blob_migration,
     types::{MigrationAction, MigrationState},
 };
+#[cfg(feature = "web")]
+use crate::services::config::BlobNotFoundPolicy;
 
+#[cfg(feature = "hash-verification")]
+use crate::console_error;
 use crate::{console_info, console_warn};
 
 /// Verify blob migration completion using CID-level comparison for data integrity
 /// This implements the CLAUDE.md requirement for account status verification before PLC token step
 #[cfg(feature = "web")]
-pub async fn verify_and_complete_blob_migration(
-    old_session: &ClientSessionCredentials,
-    new_session: &ClientSessionCredentials,
+pub async fn verify_and_complete_blob_migration<C>(
+    old: &OldPds<C>,
+    new: &NewPds<C>,
     dispatch: &EventHandler<MigrationAction>,
     state: &MigrationState,
 ) -> Result<(), String> {
+    let (old_session, new_session) = (old.session(), new.session());
+    let level = state.verification_level();
+    if level == VerificationLevel::None {
+        console_warn!("[Migration] Blob verification skipped (verification level: none)");
+        return Ok(());
+    }
+
     console_info!("[Migration] Starting comprehensive blob migration verification with account status comparison...");
     dispatch.call(MigrationAction::SetMigrationStep(
         "Verifying blob migration with account status comparison before PLC token step..."
@@ -129,6 +159,19 @@ pub async fn verify_and_complete_blob_migration(
         }
     };
 
+    // Blobs the source PDS has already lost can't be re-uploaded; they are in the report
+    let mut missing_blobs = missing_blobs;
+    if state.blob_not_found_policy() == BlobNotFoundPolicy::SkipAndRecord {
+        let known_lost = unrecoverable_blobs(&old_session.did);
+        missing_blobs.retain(|blob| !known_lost.contains(&blob.cid.to_string()));
+        if !known_lost.is_empty() {
+            console_warn!(
+                "[Migration] {} blobs are missing from the source PDS and won't be re-uploaded",
+                known_lost.len()
+            );
+        }
+    }
+
     // If we found missing blobs, attempt to migrate them
     if !missing_blobs.is_empty() {
         console_info!(
@@ -140,14 +183,277 @@ pub async fn verify_and_complete_blob_migration(
         );
 
         console_info!("[Migration] Starting blob reconciliation using streaming architecture...");
-        execute_streaming_blob_migration(old_session, new_session, dispatch, state).await?;
+        execute_streaming_blob_migration(old, new, dispatch, state).await?;
         console_info!("[Migration] ✅ Streaming blob migration completed successfully");
     } else {
         console_info!("[Migration] No missing blobs found via API, proceeding to PLC operations");
     }
 
+    if level.rehashes_blobs() {
+        verify_blob_hashes(&pds_client, new_session, dispatch, level).await?;
+    }
+
     console_info!(
         "[Migration] ✅ Blob migration verification completed, proceeding to PLC operations"
     );
     Ok(())
 }
+
+/// Download blobs back from the new PDS and check their contents against their CIDs
+#[cfg(all(feature = "web", feature = "hash-verification"))]
+async fn verify_blob_hashes(
+    pds_client: &PdsClient,
+    new_session: &ClientSessionCredentials,
+    dispatch: &EventHandler<MigrationAction>,
+    level: VerificationLevel,
+) -> Result<(), String> {
+    let target_blobs = pds_client
+        .list_all_target_blobs(new_session, &new_session.did)
+        .await
+        .map_err(|e| format!("Failed to list blobs on new PDS for verification: {}", e))?;
+
+    let to_check = if level == VerificationLevel::FullRehash {
+        target_blobs
+    } else {
+        sample_evenly(
+            &target_blobs,
+            get_global_config().verification.blob_sample_size,
+        )
+    };
+
+    let mut failures = Vec::new();
+    for (i, cid) in to_check.iter().enumerate() {
+        dispatch.call(MigrationAction::SetMigrationStep(format!(
+            "Verifying blob hashes on new PDS ({}/{})...",
+            i + 1,
+            to_check.len()
+        )));
+
+        let result = match pds_client.export_blob(new_session, cid).await {
+            Ok(response) => match response.blob_data {
+                Some(data) => verify_blob_bytes(cid, &data),
+                None => Err(response.message),
+            },
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = result {
+            console_error!("[Migration] Blob {} failed hash verification: {}", cid, e);
+            failures.push(cid.to_string());
+        }
+    }
+
+    if !failures.is_empty() {
+        return Err(format!(
+            "{} of {} blobs failed hash verification on the new PDS (first: {})",
+            failures.len(),
+            to_check.len(),
+            failures[0]
+        ));
+    }
+
+    console_info!(
+        "[Migration] ✅ {} blobs re-hashed and verified on new PDS",
+        to_check.len()
+    );
+    Ok(())
+}
+
+#[cfg(all(feature = "web", not(feature = "hash-verification")))]
+async fn verify_blob_hashes(
+    _pds_client: &PdsClient,
+    _new_session: &ClientSessionCredentials,
+    _dispatch: &EventHandler<MigrationAction>,
+    _level: VerificationLevel,
+) -> Result<(), String> {
+    console_warn!("[Migration] Blob re-hashing skipped: built without hash-verification");
+    Ok(())
+}
+
+/// Verify the imported repository on the new PDS at the requested level
+#[cfg(feature = "web")]
+pub async fn verify_repository_migration<C>(
+    old: &OldPds<C>,
+    new: &NewPds<C>,
+    dispatch: &EventHandler<MigrationAction>,
+    level: VerificationLevel,
+) -> Result<(), String> {
+    let (old_session, new_session) = (old.session(), new.session());
+    if level == VerificationLevel::None {
+        console_warn!("[Migration] Repository verification skipped (verification level: none)");
+        return Ok(());
+    }
+
+    dispatch.call(MigrationAction::SetMigrationStep(
+        "Verifying repository on new PDS...".to_string(),
+    ));
+    let pds_client = PdsClient::new();
+
+    let old_status = pds_client
+        .check_account_status(old_session)
+        .await
+        .map_err(|e| format!("Failed to check old account status: {}", e))?;
+    let new_status = pds_client
+        .check_account_status(new_session)
+        .await
+        .map_err(|e| format!("Failed to check new account status: {}", e))?;
+
+    if old_status.indexed_records != new_status.indexed_records
+        || old_status.repo_blocks != new_status.repo_blocks
+    {
+        console_warn!(
+            "[Migration] Repository count mismatch: records {:?} -> {:?}, blocks {:?} -> {:?}",
+            old_status.indexed_records,
+            new_status.indexed_records,
+            old_status.repo_blocks,
+            new_status.repo_blocks
+        );
+    }
+
+    if level.rehashes_blobs() && old_status.repo_commit != new_status.repo_commit {
+        return Err(format!(
+            "Repository commit mismatch: old PDS {:?}, new PDS {:?}",
+            old_status.repo_commit, new_status.repo_commit
+        ));
+    }
+
+    if level == VerificationLevel::FullRehash {
+        diff_repository_exports(&pds_client, old_session, new_session, dispatch).await?;
+    }
+
+    console_info!("[Migration] ✅ Repository verification passed");
+    Ok(())
+}
+
+/// Stream one repository export through the CAR reader, handing each block to `on_block`
+#[cfg(feature = "web")]
+async fn stream_repository_blocks(
+    pds_client: &PdsClient,
+    session: &ClientSessionCredentials,
+    reader: &mut CarStreamReader,
+    mut on_block: impl FnMut(&cid::Cid, &[u8]),
+) -> Result<(), String> {
+    let response = pds_client
+        .export_repository_stream(session)
+        .await
+        .map_err(|e| format!("Failed to export repository from {}: {}", session.pds, e))?;
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk =
+            chunk.map_err(|e| format!("Repository export from {} failed: {}", session.pds, e))?;
+        reader.push(&chunk, |cid, data| {
+            on_block(cid, data);
+            Ok(())
+        })?;
+    }
+    reader.finish()
+}
+
+/// Export the repository from both PDSes and compare the CARs block by block
+#[cfg(feature = "web")]
+async fn diff_repository_exports(
+    pds_client: &PdsClient,
+    old_session: &ClientSessionCredentials,
+    new_session: &ClientSessionCredentials,
+    dispatch: &EventHandler<MigrationAction>,
+) -> Result<(), String> {
+    dispatch.call(MigrationAction::SetMigrationStep(
+        "Comparing repository exports from both PDSes...".to_string(),
+    ));
+    let mut differ = CarDiffer::new();
+
+    let mut source = CarStreamReader::new();
+    stream_repository_blocks(pds_client, old_session, &mut source, |cid, _| {
+        differ.add_source_block(cid)
+    })
+    .await?;
+    differ.finish_source(&source);
+
+    let mut target = CarStreamReader::new();
+    stream_repository_blocks(pds_client, new_session, &mut target, |cid, data| {
+        differ.add_target_block(cid, data)
+    })
+    .await?;
+    let diff = differ.finish(&target);
+
+    let summary = diff.summary();
+    let identical = diff.is_identical();
+    dispatch.call(MigrationAction::SetRepoCarDiff(Some(diff)));
+    if !identical {
+        return Err(summary);
+    }
+    console_info!("[Migration] ✅ {}", summary);
+    #[cfg(not(feature = "hash-verification"))]
+    console_warn!("[Migration] Repository blocks not re-hashed: built without hash-verification");
+    Ok(())
+}
+
+/// Checks the imported repository at the selected verification level
+#[cfg(feature = "web")]
+pub struct VerifyRepositoryStep;
+
+#[cfg(feature = "web")]
+#[async_trait(?Send)]
+impl MigrationStep for VerifyRepositoryStep {
+    fn plan_step(&self) -> PlanStep {
+        PlanStep::VerifyRepository
+    }
+
+    fn describe(&self) -> &'static str {
+        "Verify the imported repository on the new PDS"
+    }
+
+    async fn execute(&self, ctx: &mut StepContext<'_>) -> Result<(), String> {
+        let target = ctx.target(self.plan_step())?;
+        let handler = ctx.handler(self.plan_step())?;
+        verify_repository_migration(&ctx.old, target, &handler, ctx.state.verification_level())
+            .await
+    }
+}
+
+/// Compares blob counts and re-uploads anything missing
+#[cfg(feature = "web")]
+pub struct VerifyBlobsStep;
+
+#[cfg(feature = "web")]
+#[async_trait(?Send)]
+impl MigrationStep for VerifyBlobsStep {
+    fn plan_step(&self) -> PlanStep {
+        PlanStep::VerifyBlobs
+    }
+
+    fn describe(&self) -> &'static str {
+        "Compare blob counts and re-upload missing blobs"
+    }
+
+    async fn execute(&self, ctx: &mut StepContext<'_>) -> Result<(), String> {
+        let target = ctx.target(self.plan_step())?;
+        let handler = ctx.handler(self.plan_step())?;
+        verify_and_complete_blob_migration(&ctx.old, target, &handler, ctx.state).await
+    }
+}
+
+/// Pick up to `count` items spread evenly across the slice
+pub fn sample_evenly<T: Clone>(items: &[T], count: usize) -> Vec<T> {
+    if count == 0 || items.is_empty() {
+        return Vec::new();
+    }
+    if items.len() <= count {
+        return items.to_vec();
+    }
+    (0..count)
+        .map(|i| items[i * items.len() / count].clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_evenly() {
+        let items: Vec<u32> = (0..10).collect();
+        assert_eq!(sample_evenly(&items, 3), vec![0, 3, 6]);
+        assert_eq!(sample_evenly(&items, 20), items);
+        assert!(sample_evenly(&items, 0).is_empty());
+    }
+}
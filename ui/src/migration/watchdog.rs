@@ -0,0 +1,237 @@
+//! Stall detection for long-running migration steps
+//!
+//! The reducer fingerprints everything that counts as forward progress (the
+//! step message, byte and record counters, step statuses) after every action
+//! and stamps when that fingerprint last changed. Console messages don't count:
+//! retry loops log plenty while moving nothing. A step is stalled once the
+//! fingerprint has been unchanged for longer than that step's threshold.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use super::continuous_sync::ContinuousSyncStatus;
+use super::timeline::{StepStatus, TimelineStep};
+use super::types::MigrationState;
+use crate::services::client::PdsClient;
+use crate::utils::platform::now_ms;
+
+/// When the progress fingerprint last changed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProgressWatch {
+    fingerprint: u64,
+    last_change_ms: u64,
+}
+
+impl ProgressWatch {
+    /// Record the latest fingerprint, restarting the clock if it moved
+    pub fn observe(&mut self, fingerprint: u64, now_ms: u64) {
+        if fingerprint != self.fingerprint || self.last_change_ms == 0 {
+            self.fingerprint = fingerprint;
+            self.last_change_ms = now_ms;
+        }
+    }
+
+    /// Restart the clock without a new fingerprint, e.g. when the tab comes back
+    pub fn resume(&mut self, now_ms: u64) {
+        self.last_change_ms = now_ms;
+    }
+
+    pub fn idle_ms(&self, now_ms: u64) -> u64 {
+        now_ms.saturating_sub(self.last_change_ms)
+    }
+}
+
+/// How long a step may go without progress before it's reported as stalled
+pub fn stall_threshold_ms(step: TimelineStep) -> u64 {
+    const MINUTE: u64 = 60_000;
+    match step {
+        // Large CAR uploads and target-side indexing legitimately sit quiet
+        TimelineStep::Repository => 5 * MINUTE,
+        TimelineStep::Blobs => 3 * MINUTE,
+        _ => 2 * MINUTE,
+    }
+}
+
+/// Suggested ways out of a stall
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StallAction {
+    RetryStep,
+    CheckNetwork,
+    ViewTraces,
+}
+
+impl StallAction {
+    pub fn label(&self) -> &'static str {
+        match self {
+            StallAction::RetryStep => "Stop and retry",
+            StallAction::CheckNetwork => "Check network",
+            StallAction::ViewTraces => "View recent requests",
+        }
+    }
+}
+
+/// A step that has stopped making progress
+#[derive(Debug, Clone, PartialEq)]
+pub struct StallInfo {
+    pub step: TimelineStep,
+    pub idle_ms: u64,
+    pub actions: Vec<StallAction>,
+}
+
+/// Outcome of reaching one PDS during a network check
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectivityCheck {
+    pub label: &'static str,
+    pub url: String,
+    pub result: Result<u64, String>,
+}
+
+/// Time a describeServer round trip to the source and target PDS
+pub async fn probe_connectivity(state: &MigrationState) -> Vec<ConnectivityCheck> {
+    let source = state
+        .form1
+        .login_response
+        .as_ref()
+        .and_then(|login| login.session.as_ref())
+        .map(|session| session.pds.clone());
+    let target = state
+        .new_pds_session
+        .as_ref()
+        .map(|session| session.pds.clone())
+        .or_else(|| (!state.form2.pds_url.is_empty()).then(|| state.form2.pds_url.clone()));
+
+    let pds_client = PdsClient::new();
+    let mut checks = Vec::new();
+    for (label, url) in [("Current PDS", source), ("New PDS", target)] {
+        let Some(url) = url else { continue };
+        let started = now_ms();
+        let result = pds_client
+            .describe_server(&url)
+            .await
+            .map(|_| (now_ms() - started).max(0.0) as u64)
+            .map_err(|e| e.to_string());
+        checks.push(ConnectivityCheck { label, url, result });
+    }
+    checks
+}
+
+impl MigrationState {
+    /// Hash of everything that counts as forward progress
+    pub fn progress_fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.migration_step.hash(&mut hasher);
+        self.repo_progress.export_complete.hash(&mut hasher);
+        self.repo_progress.import_complete.hash(&mut hasher);
+        self.repo_progress.car_size.hash(&mut hasher);
+        self.blob_progress.processed_blobs.hash(&mut hasher);
+        self.blob_progress.processed_bytes.hash(&mut hasher);
+        self.blob_progress.current_blob_cid.hash(&mut hasher);
+        self.blob_progress
+            .current_blob_progress
+            .map(f64::to_bits)
+            .hash(&mut hasher);
+        if let Some(import) = &self.repo_import_status {
+            import.indexed_records.hash(&mut hasher);
+            import.repo_blocks.hash(&mut hasher);
+        }
+        for status in self.timeline_statuses() {
+            status.css_class().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// The active step, if it has gone quiet for longer than its threshold
+    pub fn stall(&self, now_ms: u64) -> Option<StallInfo> {
+        // Hidden tabs are throttled, so quiet there says nothing about the PDS
+        if !self.is_migrating || self.tab_activity.hidden {
+            return None;
+        }
+        // The keep-in-sync loop is meant to sit quiet between rounds
+        if self
+            .continuous_sync
+            .as_ref()
+            .is_some_and(ContinuousSyncStatus::is_waiting)
+        {
+            return None;
+        }
+        let (_, step) = self
+            .timeline_statuses()
+            .into_iter()
+            .zip(TimelineStep::ALL)
+            .find(|(status, _)| *status == StepStatus::Active)?;
+
+        let idle_ms = self.progress_watch.idle_ms(now_ms);
+        if idle_ms < stall_threshold_ms(step) {
+            return None;
+        }
+
+        Some(StallInfo {
+            step,
+            idle_ms,
+            actions: vec![
+                StallAction::RetryStep,
+                StallAction::CheckNetwork,
+                StallAction::ViewTraces,
+            ],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migration::types::{BlobProgress, MigrationAction};
+
+    #[test]
+    fn test_stall_reported_after_threshold_without_progress() {
+        let mut state = MigrationState::default();
+        state.form1.session_stored = true;
+        state.reduce_in_place(MigrationAction::SetMigrating(true));
+        let started = state.progress_watch.last_change_ms;
+        let threshold = stall_threshold_ms(TimelineStep::Account);
+
+        assert!(state.stall(started + threshold - 1).is_none());
+        let stall = state.stall(started + threshold).unwrap();
+        assert_eq!(stall.step, TimelineStep::Account);
+        assert_eq!(stall.actions.len(), 3);
+
+        state.reduce_in_place(MigrationAction::SetMigrating(false));
+        assert!(state.stall(started + threshold).is_none());
+    }
+
+    #[test]
+    fn test_only_real_progress_resets_the_clock() {
+        let mut watch = ProgressWatch::default();
+        let mut state = MigrationState::default();
+        watch.observe(state.progress_fingerprint(), 1_000);
+
+        // Same fingerprint later on: still idle since the first observation
+        watch.observe(state.progress_fingerprint(), 50_000);
+        assert_eq!(watch.idle_ms(60_000), 59_000);
+
+        state.blob_progress = BlobProgress {
+            processed_bytes: 4096,
+            ..BlobProgress::default()
+        };
+        watch.observe(state.progress_fingerprint(), 61_000);
+        assert_eq!(watch.idle_ms(62_000), 1_000);
+    }
+
+    #[test]
+    fn test_hidden_tab_pauses_stall_detection_and_resets_clock() {
+        let mut state = MigrationState::default();
+        state.form1.session_stored = true;
+        state.reduce_in_place(MigrationAction::SetMigrating(true));
+        let started = state.progress_watch.last_change_ms;
+        let threshold = stall_threshold_ms(TimelineStep::Account);
+
+        state.reduce_in_place(MigrationAction::SetTabHidden(true));
+        assert!(state.stall(started + threshold).is_none());
+
+        state.reduce_in_place(MigrationAction::SetTabHidden(false));
+        let resumed = state.progress_watch.last_change_ms;
+        assert!(resumed >= started);
+        assert!(state.stall(resumed + threshold - 1).is_none());
+        assert!(state.stall(resumed + threshold).is_some());
+    }
+}
@@ -4,6 +4,7 @@
 //! across different storage backends. It intelligently divides large blobs into
 //! optimally-sized chunks based on backend capabilities and WASM memory constraints.
 
+use crate::utils::byte_format::format_size;
 use crate::{console_debug, console_error, console_info};
 use serde::{Deserialize, Serialize};
 
@@ -188,10 +189,10 @@ impl BlobChunker {
         console_debug!(
             "{}",
             format!(
-                "📊 [BlobChunker] Config: max={:.1}MB, optimal={:.1}MB, min={:.1}MB",
-                config.max_chunk_size() as f64 / 1_048_576.0,
-                config.optimal_chunk_size() as f64 / 1_048_576.0,
-                config.min_chunk_size() as f64 / 1_048_576.0
+                "📊 [BlobChunker] Config: max={}, optimal={}, min={}",
+                format_size(config.max_chunk_size()),
+                format_size(config.optimal_chunk_size()),
+                format_size(config.min_chunk_size())
             )
         );
 
@@ -203,8 +204,8 @@ impl BlobChunker {
         console_debug!(
             "{}",
             format!(
-                "🔍 [BlobChunker] Analyzing blob of {:.2} MB",
-                blob_size as f64 / 1_048_576.0
+                "🔍 [BlobChunker] Analyzing blob of {}",
+                format_size(blob_size)
             )
         );
 
@@ -258,8 +259,8 @@ impl BlobChunker {
             .to_string(),
         };
 
-        console_info!("{}", format!("📋 [BlobChunker] Analysis: {} chunks recommended, {:.2} MB per chunk, {}% memory efficiency gain",
-                       recommended_chunks, chunk_size as f64 / 1_048_576.0, analysis.memory_efficiency_gain));
+        console_info!("{}", format!("📋 [BlobChunker] Analysis: {} chunks recommended, {} per chunk, {}% memory efficiency gain",
+                       recommended_chunks, format_size(chunk_size), analysis.memory_efficiency_gain));
 
         analysis
     }
@@ -270,9 +271,9 @@ impl BlobChunker {
         console_info!(
             "{}",
             format!(
-                "✂️ [BlobChunker] Chunking blob {} ({:.2} MB)",
+                "✂️ [BlobChunker] Chunking blob {} ({})",
                 cid,
-                blob_size as f64 / 1_048_576.0
+                format_size(blob_size)
             )
         );
 
@@ -303,9 +304,9 @@ impl BlobChunker {
         console_info!(
             "{}",
             format!(
-                "🔧 [BlobChunker] Creating {} chunks of ~{:.2} MB each",
+                "🔧 [BlobChunker] Creating {} chunks of ~{} each",
                 total_chunks,
-                chunk_size as f64 / 1_048_576.0
+                format_size(chunk_size as u64)
             )
         );
 
@@ -323,9 +324,9 @@ impl BlobChunker {
             console_debug!(
                 "{}",
                 format!(
-                    "📦 [BlobChunker] Created chunk {} ({:.2} MB)",
+                    "📦 [BlobChunker] Created chunk {} ({})",
                     chunk_id,
-                    chunk.chunk_size as f64 / 1_048_576.0
+                    format_size(chunk.chunk_size)
                 )
             );
             chunks.push(chunk);
@@ -419,9 +420,9 @@ impl BlobChunker {
             console_debug!(
                 "{}",
                 format!(
-                    "🔧 [BlobChunker] Adding chunk {} ({:.2} MB)",
+                    "🔧 [BlobChunker] Adding chunk {} ({})",
                     chunk.chunk_index,
-                    chunk.chunk_size as f64 / 1_048_576.0
+                    format_size(chunk.chunk_size)
                 )
             );
             reassembled_data.extend(chunk.data);
@@ -431,9 +432,9 @@ impl BlobChunker {
         console_info!(
             "{}",
             format!(
-                "✅ [BlobChunker] Successfully reassembled blob {} ({:.2} MB total)",
+                "✅ [BlobChunker] Successfully reassembled blob {} ({} total)",
                 &parent_cid,
-                total_size as f64 / 1_048_576.0
+                format_size(total_size)
             )
         );
         Ok(reassembled_data)
@@ -461,17 +462,17 @@ impl BlobAnalysis {
     pub fn summary(&self) -> String {
         if self.should_chunk {
             format!(
-                "{:.2} MB blob → {} chunks of {:.2} MB each ({}% memory efficiency gain, {})",
-                self.blob_size as f64 / 1_048_576.0,
+                "{} blob → {} chunks of {} each ({}% memory efficiency gain, {})",
+                format_size(self.blob_size),
                 self.recommended_chunks,
-                self.estimated_chunk_size as f64 / 1_048_576.0,
+                format_size(self.estimated_chunk_size),
                 self.memory_efficiency_gain,
                 self.backend_compatibility
             )
         } else {
             format!(
-                "{:.2} MB blob → no chunking needed ({})",
-                self.blob_size as f64 / 1_048_576.0,
+                "{} blob → no chunking needed ({})",
+                format_size(self.blob_size),
                 self.backend_compatibility
             )
         }
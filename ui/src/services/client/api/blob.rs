@@ -12,11 +12,14 @@ use tracing::{error, info, instrument};
 // Import console macros from our crate
 use crate::console_debug;
 
+use crate::services::client::body_limit::body_limit_hint;
 use crate::services::client::errors::ClientError;
+use crate::services::client::read_scope::send_sync_read;
 use crate::services::client::types::{
     ClientBlobExportResponse, ClientBlobUploadResponse, ClientSessionCredentials,
 };
 use crate::services::client::PdsClient;
+use crate::services::transport::HttpRequest;
 
 /// Export/download a blob from PDS
 // NEWBOLD.md Step: goat blob export $ACCOUNTDID (line 98) - individual blob download
@@ -35,11 +38,7 @@ pub async fn export_blob_impl(
         session.pds, session.did, cid
     );
 
-    let response = client
-        .http_client
-        .get(&export_url)
-        .header("Authorization", format!("Bearer {}", session.access_jwt))
-        .send()
+    let response = send_sync_read(client.http_client.get(&export_url), &session.access_jwt)
         .await
         .map_err(|e| ClientError::NetworkError {
             message: format!("Failed to export blob: {}", e),
@@ -103,11 +102,7 @@ pub async fn export_blob_stream_impl(
         session.pds, session.did, cid
     );
 
-    let response = client
-        .http_client
-        .get(&export_url)
-        .header("Authorization", format!("Bearer {}", session.access_jwt))
-        .send()
+    let response = send_sync_read(client.http_client.get(&export_url), &session.access_jwt)
         .await
         .map_err(|e| ClientError::NetworkError {
             message: format!("Failed to start blob stream export: {}", e),
@@ -237,22 +232,18 @@ pub async fn upload_blob_impl(
 
     // NEWBOLD.md: com.atproto.repo.uploadBlob for individual blob upload
     let upload_url = format!("{}/xrpc/com.atproto.repo.uploadBlob", session.pds);
+    let blob_size = blob_data.len() as u64;
 
     // Don't compress - not part of the protocol
+    let request = HttpRequest::post(&upload_url, blob_data, "application/octet-stream");
     let response = client
-        .http_client
-        .post(&upload_url)
-        .header("Authorization", format!("Bearer {}", session.access_jwt))
-        .header("Content-Type", "application/octet-stream")
-        .header("Content-Length", blob_data.len().to_string()) // Required!
-        .body(blob_data) // Send raw
-        .send()
+        .send_xrpc(request, Some(&session.access_jwt))
         .await
         .map_err(|e| ClientError::NetworkError {
             message: format!("Failed to upload blob: {}", e),
         })?;
 
-    if response.status().is_success() {
+    if response.is_success() {
         info!("Blob {} uploaded successfully", cid);
 
         Ok(ClientBlobUploadResponse {
@@ -260,12 +251,19 @@ pub async fn upload_blob_impl(
             message: "Blob uploaded successfully".to_string(),
         })
     } else {
-        let error_text = response.text().await.unwrap_or_default();
+        let status = response.status;
+        let server = response.header("server").map(str::to_string);
+        let error_text = response.text();
         error!("Blob upload failed: {}", error_text);
 
+        // A proxy's size limit gets the configuration change that lifts it
+        let message = match body_limit_hint(status, server.as_deref(), &error_text, blob_size) {
+            Some(hint) => format!("Blob upload failed: {}", hint),
+            None => format!("Blob upload failed: {}", error_text),
+        };
         Ok(ClientBlobUploadResponse {
             success: false,
-            message: format!("Blob upload failed: {}", error_text),
+            message,
         })
     }
 }
@@ -4,14 +4,21 @@
 //! - PLC recommendations and token management
 //! - PLC operation signing and submission
 //! - Account activation and deactivation
+//! - Account deletion
 
 use anyhow::Result;
 use serde_json::json;
 use tracing::{error, info, instrument};
 
 use crate::services::client::errors::ClientError;
+use crate::services::client::idempotency::{add_idempotency_header, idempotency_key};
+use crate::services::client::schema::{
+    check_response, parse_response, RecommendedDidCredentialsOutput, ReserveSigningKeyOutput,
+    SignPlcOperationOutput,
+};
 use crate::services::client::types::*;
 use crate::services::client::PdsClient;
+use crate::services::transport::HttpRequest;
 
 /// Get PLC recommendation from PDS
 #[instrument(skip(client), err)]
@@ -27,23 +34,18 @@ pub async fn get_plc_recommendation_impl(
     );
 
     let response = client
-        .http_client
-        .get(&plc_url)
-        .header("Authorization", format!("Bearer {}", session.access_jwt))
-        .send()
+        .send_xrpc(HttpRequest::get(&plc_url), Some(&session.access_jwt))
         .await
         .map_err(|e| ClientError::NetworkError {
             message: format!("Failed to get PLC recommendation: {}", e),
         })?;
 
-    if response.status().is_success() {
+    if response.is_success() {
         let plc_data: serde_json::Value =
-            response
-                .json()
-                .await
-                .map_err(|e| ClientError::NetworkError {
-                    message: format!("Failed to parse PLC recommendation response: {}", e),
-                })?;
+            response.json().map_err(|e| ClientError::NetworkError {
+                message: format!("Failed to parse PLC recommendation response: {}", e),
+            })?;
+        check_response::<RecommendedDidCredentialsOutput>(&plc_data)?;
 
         info!("PLC recommendation retrieved successfully");
 
@@ -51,19 +53,130 @@ pub async fn get_plc_recommendation_impl(
             success: true,
             message: "PLC recommendation retrieved successfully".to_string(),
             plc_unsigned: Some(plc_data.to_string()),
+            endpoint_missing: false,
         })
     } else {
-        let error_text = response.text().await.unwrap_or_default();
+        let status = response.status;
+        let error_text = response.text();
         error!("PLC recommendation failed: {}", error_text);
 
         Ok(ClientPlcRecommendationResponse {
             success: false,
             message: format!("PLC recommendation failed: {}", error_text),
             plc_unsigned: None,
+            endpoint_missing: is_missing_method(status, &error_text),
         })
     }
 }
 
+/// Whether an XRPC failure means the server doesn't implement the method
+pub fn is_missing_method(status: u16, error_text: &str) -> bool {
+    matches!(status, 404 | 501)
+        || error_text.contains("MethodNotImplemented")
+        || error_text.contains("XRPCNotSupported")
+}
+
+/// Reserve (or fetch the already reserved) repo signing key for a DID on the PDS,
+/// before the account exists. Unauthenticated, like the endpoint itself.
+#[instrument(skip(client), err)]
+pub async fn reserve_signing_key_impl(
+    client: &PdsClient,
+    pds_url: &str,
+    did: &str,
+) -> Result<String, ClientError> {
+    info!("Reserving signing key for DID: {}", did);
+
+    let reserve_url = format!("{}/xrpc/com.atproto.server.reserveSigningKey", pds_url);
+
+    let response = client
+        .send_xrpc(
+            HttpRequest::post_json(&reserve_url, &json!({ "did": did })),
+            None,
+        )
+        .await
+        .map_err(|e| ClientError::NetworkError {
+            message: format!("Failed to reserve signing key: {}", e),
+        })?;
+
+    if !response.is_success() {
+        let status_code = response.status;
+        let error_text = response.text();
+        error!("Signing key reservation failed: {}", error_text);
+        return Err(ClientError::PdsOperationFailed {
+            operation: format!("reserveSigningKey (HTTP {})", status_code),
+            message: error_text,
+        });
+    }
+
+    let json_response: serde_json::Value =
+        response.json().map_err(|e| ClientError::NetworkError {
+            message: format!("Failed to parse signing key response: {}", e),
+        })?;
+    Ok(parse_response::<ReserveSigningKeyOutput>(json_response)?.signing_key)
+}
+
+/// Current PLC data (rotation keys, handles, keys, services) for a did:plc
+#[instrument(skip(client), err)]
+pub async fn fetch_plc_data_impl(
+    client: &PdsClient,
+    did: &str,
+) -> Result<serde_json::Value, ClientError> {
+    let data_url = format!("https://plc.directory/{}/data", did);
+    info!("Fetching current PLC data from: {}", data_url);
+
+    let request = HttpRequest::get(&data_url).header("Accept", "application/json");
+    let response =
+        client
+            .send_xrpc(request, None)
+            .await
+            .map_err(|e| ClientError::NetworkError {
+                message: format!("Failed to fetch PLC data: {}", e),
+            })?;
+
+    if !response.is_success() {
+        return Err(ClientError::ResourceNotFound {
+            resource: format!("PLC data for {} (HTTP {})", did, response.status),
+        });
+    }
+
+    response
+        .json()
+        .map_err(|e| ClientError::SerializationError {
+            message: format!("Failed to parse PLC data: {}", e),
+        })
+}
+
+/// Every operation ever applied to a did:plc, oldest first
+#[instrument(skip(client), err)]
+pub async fn fetch_plc_audit_log_impl(
+    client: &PdsClient,
+    did: &str,
+) -> Result<Vec<serde_json::Value>, ClientError> {
+    let log_url = format!("https://plc.directory/{}/log/audit", did);
+    info!("Fetching PLC audit log from: {}", log_url);
+
+    let request = HttpRequest::get(&log_url).header("Accept", "application/json");
+    let response =
+        client
+            .send_xrpc(request, None)
+            .await
+            .map_err(|e| ClientError::NetworkError {
+                message: format!("Failed to fetch PLC audit log: {}", e),
+            })?;
+
+    if !response.is_success() {
+        return Err(ClientError::ResourceNotFound {
+            resource: format!("PLC audit log for {} (HTTP {})", did, response.status),
+        });
+    }
+
+    response
+        .json()
+        .map_err(|e| ClientError::SerializationError {
+            message: format!("Failed to parse PLC audit log: {}", e),
+        })
+}
+
 /// Request PLC token from PDS
 #[instrument(skip(client), err)]
 pub async fn request_plc_token_impl(
@@ -78,16 +191,16 @@ pub async fn request_plc_token_impl(
     );
 
     let response = client
-        .http_client
-        .post(&token_url)
-        .header("Authorization", format!("Bearer {}", session.access_jwt))
-        .send()
+        .send_xrpc(
+            HttpRequest::new("POST", &token_url),
+            Some(&session.access_jwt),
+        )
         .await
         .map_err(|e| ClientError::NetworkError {
             message: format!("Failed to request PLC token: {}", e),
         })?;
 
-    if response.status().is_success() {
+    if response.is_success() {
         info!("PLC token requested successfully - check email for token");
 
         Ok(ClientPlcTokenResponse {
@@ -95,7 +208,7 @@ pub async fn request_plc_token_impl(
             message: "PLC token sent to email. Check your email for verification code.".to_string(),
         })
     } else {
-        let error_text = response.text().await.unwrap_or_default();
+        let error_text = response.text();
         error!("PLC token request failed: {}", error_text);
 
         Ok(ClientPlcTokenResponse {
@@ -139,35 +252,25 @@ pub async fn sign_plc_operation_impl(
     info!("Making PLC signing request to: {}", sign_url);
 
     let response = client
-        .http_client
-        .post(&sign_url)
-        .header("Authorization", format!("Bearer {}", session.access_jwt))
-        .header("Content-Type", "application/json")
-        .json(&payload)
-        .send()
+        .send_xrpc(
+            HttpRequest::post_json(&sign_url, &payload),
+            Some(&session.access_jwt),
+        )
         .await
         .map_err(|e| ClientError::NetworkError {
             message: format!("Failed to sign PLC operation: {}", e),
         })?;
 
-    if response.status().is_success() {
+    if response.is_success() {
         let json_response: serde_json::Value =
-            response
-                .json()
-                .await
-                .map_err(|e| ClientError::NetworkError {
-                    message: format!("Failed to parse sign response: {}", e),
-                })?;
+            response.json().map_err(|e| ClientError::NetworkError {
+                message: format!("Failed to parse sign response: {}", e),
+            })?;
 
         info!("PLC operation signing response received");
 
         // Extract the 'operation' field from the response (matches Go implementation)
-        let operation =
-            json_response
-                .get("operation")
-                .ok_or_else(|| ClientError::NetworkError {
-                    message: "No 'operation' field in response".to_string(),
-                })?;
+        let operation = &parse_response::<SignPlcOperationOutput>(json_response)?.operation;
 
         // Convert signed operation to pretty JSON string
         let plc_signed =
@@ -183,7 +286,7 @@ pub async fn sign_plc_operation_impl(
             plc_signed: Some(plc_signed),
         })
     } else {
-        let error_text = response.text().await.unwrap_or_default();
+        let error_text = response.text();
         error!("PLC signing failed: {}", error_text);
 
         Ok(ClientPlcSignResponse {
@@ -223,21 +326,21 @@ pub async fn submit_plc_operation_impl(
         "operation": plc_signed_value
     });
 
+    // A signed operation is one change; resubmitting it must not count twice
+    let key = idempotency_key("submitPlcOperation", &[&session.did, &plc_signed]);
+
     info!("Making PLC submission request to: {}", submit_url);
 
+    let mut request = HttpRequest::post_json(&submit_url, &submission_payload);
+    add_idempotency_header(&mut request, &key);
     let response = client
-        .http_client
-        .post(&submit_url)
-        .header("Authorization", format!("Bearer {}", session.access_jwt))
-        .header("Content-Type", "application/json")
-        .json(&submission_payload)
-        .send()
+        .send_xrpc(request, Some(&session.access_jwt))
         .await
         .map_err(|e| ClientError::NetworkError {
             message: format!("Failed to submit PLC operation: {}", e),
         })?;
 
-    if response.status().is_success() {
+    if response.is_success() {
         info!("PLC operation submitted successfully");
 
         Ok(ClientPlcSubmitResponse {
@@ -245,7 +348,7 @@ pub async fn submit_plc_operation_impl(
             message: "PLC operation submitted successfully".to_string(),
         })
     } else {
-        let error_text = response.text().await.unwrap_or_default();
+        let error_text = response.text();
         error!("PLC submission failed: {}", error_text);
 
         Ok(ClientPlcSubmitResponse {
@@ -273,16 +376,16 @@ pub async fn activate_account_impl(
 
     // Make the request - this is a POST with no body (AT Protocol requirement)
     let response = client
-        .http_client
-        .post(&activate_url)
-        .header("Authorization", format!("Bearer {}", session.access_jwt))
-        .send()
+        .send_xrpc(
+            HttpRequest::new("POST", &activate_url),
+            Some(&session.access_jwt),
+        )
         .await
         .map_err(|e| ClientError::NetworkError {
             message: format!("Failed to activate account: {}", e),
         })?;
 
-    if response.status().is_success() {
+    if response.is_success() {
         info!("Account activated successfully");
 
         Ok(ClientActivationResponse {
@@ -290,7 +393,7 @@ pub async fn activate_account_impl(
             message: "Account activated successfully".to_string(),
         })
     } else {
-        let error_text = response.text().await.unwrap_or_default();
+        let error_text = response.text();
         error!("Account activation failed: {}", error_text);
 
         Ok(ClientActivationResponse {
@@ -318,18 +421,16 @@ pub async fn deactivate_account_impl(
 
     // Make the request - this is a POST with empty body
     let response = client
-        .http_client
-        .post(&deactivate_url)
-        .header("Authorization", format!("Bearer {}", session.access_jwt))
-        .header("Content-Type", "application/json")
-        .json(&json!({}))
-        .send()
+        .send_xrpc(
+            HttpRequest::post_json(&deactivate_url, &json!({})),
+            Some(&session.access_jwt),
+        )
         .await
         .map_err(|e| ClientError::NetworkError {
             message: format!("Failed to deactivate account: {}", e),
         })?;
 
-    if response.status().is_success() {
+    if response.is_success() {
         info!("Account deactivated successfully");
 
         Ok(ClientDeactivationResponse {
@@ -337,7 +438,7 @@ pub async fn deactivate_account_impl(
             message: "Account deactivated successfully".to_string(),
         })
     } else {
-        let error_text = response.text().await.unwrap_or_default();
+        let error_text = response.text();
         error!("Account deactivation failed: {}", error_text);
 
         Ok(ClientDeactivationResponse {
@@ -346,3 +447,89 @@ pub async fn deactivate_account_impl(
         })
     }
 }
+
+/// Ask the PDS to email a token that authorizes deleting the account
+#[instrument(skip(client, session), err)]
+pub async fn request_account_delete_impl(
+    client: &PdsClient,
+    session: &ClientSessionCredentials,
+) -> Result<ClientAccountDeleteResponse, ClientError> {
+    info!("Requesting account deletion token for DID: {}", session.did);
+
+    let request_url = format!(
+        "{}/xrpc/com.atproto.server.requestAccountDelete",
+        session.pds
+    );
+
+    let response = client
+        .send_xrpc(
+            HttpRequest::new("POST", &request_url),
+            Some(&session.access_jwt),
+        )
+        .await
+        .map_err(|e| ClientError::NetworkError {
+            message: format!("Failed to request account deletion: {}", e),
+        })?;
+
+    if response.is_success() {
+        Ok(ClientAccountDeleteResponse {
+            success: true,
+            message: "Account deletion token sent by email".to_string(),
+        })
+    } else {
+        let error_text = response.text();
+        error!("Account deletion request failed: {}", error_text);
+
+        Ok(ClientAccountDeleteResponse {
+            success: false,
+            message: format!("Account deletion request failed: {}", error_text),
+        })
+    }
+}
+
+/// Permanently delete the account using the emailed token
+#[instrument(skip(client, session, password, token), err)]
+pub async fn delete_account_impl(
+    client: &PdsClient,
+    session: &ClientSessionCredentials,
+    password: &str,
+    token: &str,
+) -> Result<ClientAccountDeleteResponse, ClientError> {
+    info!("Deleting account for DID: {}", session.did);
+
+    let delete_url = format!("{}/xrpc/com.atproto.server.deleteAccount", session.pds);
+
+    let response = client
+        .send_xrpc(
+            HttpRequest::post_json(
+                &delete_url,
+                &json!({
+                    "did": session.did,
+                    "password": password,
+                    "token": token,
+                }),
+            ),
+            None,
+        )
+        .await
+        .map_err(|e| ClientError::NetworkError {
+            message: format!("Failed to delete account: {}", e),
+        })?;
+
+    if response.is_success() {
+        info!("Account deleted successfully");
+
+        Ok(ClientAccountDeleteResponse {
+            success: true,
+            message: "Account deleted successfully".to_string(),
+        })
+    } else {
+        let error_text = response.text();
+        error!("Account deletion failed: {}", error_text);
+
+        Ok(ClientAccountDeleteResponse {
+            success: false,
+            message: format!("Account deletion failed: {}", error_text),
+        })
+    }
+}
@@ -14,11 +14,19 @@ use tracing::{error, info, instrument};
 use crate::console_debug;
 
 use crate::services::client::errors::ClientError;
+use crate::services::client::http_trace::TracedSend;
+use crate::services::client::import_diagnosis::{diagnose_import_failure, record_import_failure};
+use crate::services::client::read_scope::send_sync_read;
+use crate::services::client::schema::{
+    parse_response, DescribeRepoOutput, ListBlobsOutput, ListMissingBlobsOutput, ListRecordsOutput,
+    PutRecordOutput,
+};
 use crate::services::client::types::{
-    ClientMissingBlob, ClientMissingBlobsResponse, ClientRepoExportResponse,
+    ClientMissingBlobsResponse, ClientRecord, ClientRecordPage, ClientRepoExportResponse,
     ClientRepoImportResponse, ClientSessionCredentials, ClientSyncListBlobsResponse,
 };
 use crate::services::client::PdsClient;
+use crate::services::transport::HttpRequest;
 
 /// Export repository from PDS as CAR file
 // NEWBOLD.md Step: goat repo export $ACCOUNTDID (line 76)
@@ -27,22 +35,34 @@ use crate::services::client::PdsClient;
 pub async fn export_repository_impl(
     client: &PdsClient,
     session: &ClientSessionCredentials,
+) -> Result<ClientRepoExportResponse, ClientError> {
+    export_repository_since_impl(client, session, None).await
+}
+
+/// Export the repository, or with `since` only the blocks written after that revision
+#[instrument(skip(client), err)]
+pub async fn export_repository_since_impl(
+    client: &PdsClient,
+    session: &ClientSessionCredentials,
+    since: Option<&str>,
 ) -> Result<ClientRepoExportResponse, ClientError> {
     info!("Exporting repository for DID: {}", session.did);
 
     // NEWBOLD.md: com.atproto.sync.getRepo for repository export
-    let export_url = format!(
+    let mut export_url = format!(
         "{}/xrpc/com.atproto.sync.getRepo?did={}",
         session.pds, session.did
     );
+    if let Some(since) = since {
+        export_url.push_str(&format!("&since={}", since));
+    }
 
-    let response = client
+    let request = client
         .http_client
         .get(&export_url)
-        .header("Authorization", format!("Bearer {}", session.access_jwt))
         // Tell server we accept gzip compression
-        .header(header::ACCEPT_ENCODING, "gzip, deflate")
-        .send()
+        .header(header::ACCEPT_ENCODING, "gzip, deflate");
+    let response = send_sync_read(request, &session.access_jwt)
         .await
         .map_err(|e| ClientError::NetworkError {
             message: format!("Failed to export repository: {}", e),
@@ -90,6 +110,46 @@ pub async fn export_repository_impl(
     }
 }
 
+/// Start a repository export and hand back the response for streaming
+///
+/// The access token is only sent if the PDS refuses an anonymous read, so a
+/// deactivated account can still export its own repository.
+#[instrument(skip(client), err)]
+pub async fn export_repository_stream_impl(
+    client: &PdsClient,
+    session: &ClientSessionCredentials,
+) -> Result<reqwest::Response, ClientError> {
+    let export_url = format!(
+        "{}/xrpc/com.atproto.sync.getRepo?did={}",
+        session.pds, session.did
+    );
+
+    let request = client
+        .http_client
+        .get(&export_url)
+        .header(header::ACCEPT_ENCODING, "gzip, deflate");
+    let response = send_sync_read(request, &session.access_jwt)
+        .await
+        .map_err(|e| ClientError::NetworkError {
+            message: format!("Failed to start repository stream export: {}", e),
+        })?;
+
+    if response.status().is_success() {
+        Ok(response)
+    } else {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        error!("Repository stream export failed: {}", error_text);
+
+        Err(ClientError::NetworkError {
+            message: format!(
+                "Repository stream export failed ({}): {}",
+                status, error_text
+            ),
+        })
+    }
+}
+
 /// Import repository to PDS from CAR file
 // NEWBOLD.md Step: goat repo import ./did:plc:do2ar6uqzrvyzq3wevji6fbe.20250625142552.car (line 81)
 // Implements: Imports repository CAR file to new PDS
@@ -110,20 +170,15 @@ pub async fn import_repository_impl(
 
     // Don't compress - server expects raw CAR data
     // Server will compress the response if needed
+    let request = HttpRequest::post(&import_url, car_data, "application/vnd.ipld.car");
     let response = client
-        .http_client
-        .post(&import_url)
-        .header("Authorization", format!("Bearer {}", session.access_jwt))
-        .header("Content-Type", "application/vnd.ipld.car")
-        .header("Content-Length", car_data.len().to_string()) // Required!
-        .body(car_data)
-        .send()
+        .send_xrpc(request, Some(&session.access_jwt))
         .await
         .map_err(|e| ClientError::NetworkError {
             message: format!("Failed to import repository: {}", e),
         })?;
 
-    if response.status().is_success() {
+    if response.is_success() {
         info!("Repository imported successfully");
 
         Ok(ClientRepoImportResponse {
@@ -131,16 +186,146 @@ pub async fn import_repository_impl(
             message: "Repository imported successfully".to_string(),
         })
     } else {
-        let error_text = response.text().await.unwrap_or_default();
+        let status = response.status;
+        let error_text = response.text();
         error!("Repository import failed: {}", error_text);
 
+        let diagnosis = diagnose_import_failure(status, &error_text);
+        let message = diagnosis.summary();
+        record_import_failure(diagnosis);
         Ok(ClientRepoImportResponse {
             success: false,
-            message: format!("Repository import failed: {}", error_text),
+            message,
         })
     }
 }
 
+/// List the record collections present in a repository
+#[instrument(skip(client), err)]
+pub async fn describe_repo_collections_impl(
+    client: &PdsClient,
+    session: &ClientSessionCredentials,
+    did: &str,
+) -> Result<Vec<String>, ClientError> {
+    let describe_url = format!(
+        "{}/xrpc/com.atproto.repo.describeRepo?repo={}",
+        session.pds, did
+    );
+
+    let response = client
+        .send_xrpc(HttpRequest::get(&describe_url), Some(&session.access_jwt))
+        .await
+        .map_err(|e| ClientError::NetworkError {
+            message: format!("Failed to describe repository: {}", e),
+        })?;
+
+    if !response.is_success() {
+        let error_text = response.text();
+        return Err(ClientError::NetworkError {
+            message: format!("Repository describe failed: {}", error_text),
+        });
+    }
+
+    let describe_data: serde_json::Value =
+        response.json().map_err(|e| ClientError::NetworkError {
+            message: format!("Failed to parse describeRepo response: {}", e),
+        })?;
+    let described: DescribeRepoOutput = parse_response(describe_data)?;
+    Ok(described.collections)
+}
+
+/// List one page of a collection's records
+#[instrument(skip(client), err)]
+pub async fn list_records_impl(
+    client: &PdsClient,
+    session: &ClientSessionCredentials,
+    did: &str,
+    collection: &str,
+    cursor: Option<&str>,
+) -> Result<ClientRecordPage, ClientError> {
+    let mut list_url = format!(
+        "{}/xrpc/com.atproto.repo.listRecords?repo={}&collection={}&limit=100",
+        session.pds, did, collection
+    );
+    if let Some(cursor) = cursor {
+        list_url.push_str(&format!("&cursor={}", cursor));
+    }
+
+    let response = client
+        .send_xrpc(HttpRequest::get(&list_url), Some(&session.access_jwt))
+        .await
+        .map_err(|e| ClientError::NetworkError {
+            message: format!("Failed to list records: {}", e),
+        })?;
+
+    if !response.is_success() {
+        let error_text = response.text();
+        return Err(ClientError::PdsOperationFailed {
+            operation: "list_records".to_string(),
+            message: format!("Listing {} failed: {}", collection, error_text),
+        });
+    }
+
+    let list_data: serde_json::Value = response.json().map_err(|e| ClientError::NetworkError {
+        message: format!("Failed to parse listRecords response: {}", e),
+    })?;
+    let listed: ListRecordsOutput = parse_response(list_data)?;
+    Ok(ClientRecordPage {
+        records: listed
+            .records
+            .into_iter()
+            .map(|record| ClientRecord {
+                uri: record.uri,
+                cid: record.cid,
+                value: record.value,
+            })
+            .collect(),
+        cursor: listed.cursor,
+    })
+}
+
+/// Write a record under a fixed record key, replacing any record already there
+#[instrument(skip(client, record), err)]
+pub async fn put_record_impl(
+    client: &PdsClient,
+    session: &ClientSessionCredentials,
+    collection: &str,
+    rkey: &str,
+    record: serde_json::Value,
+) -> Result<(), ClientError> {
+    let put_url = format!("{}/xrpc/com.atproto.repo.putRecord", session.pds);
+    let body = serde_json::json!({
+        "repo": session.did,
+        "collection": collection,
+        "rkey": rkey,
+        "record": record,
+    });
+
+    let response = client
+        .send_xrpc(
+            HttpRequest::post_json(&put_url, &body),
+            Some(&session.access_jwt),
+        )
+        .await
+        .map_err(|e| ClientError::NetworkError {
+            message: format!("Failed to write record: {}", e),
+        })?;
+
+    if !response.is_success() {
+        let error_text = response.text();
+        return Err(ClientError::PdsOperationFailed {
+            operation: "put_record".to_string(),
+            message: format!("Writing {}/{} failed: {}", collection, rkey, error_text),
+        });
+    }
+
+    let put_data: serde_json::Value = response.json().map_err(|e| ClientError::NetworkError {
+        message: format!("Failed to parse putRecord response: {}", e),
+    })?;
+    parse_response::<PutRecordOutput>(put_data)?;
+    Ok(())
+}
+
 /// Get list of missing blobs for account
 // NEWBOLD.md Step: goat account missing-blobs (line 86)
 // Implements: Lists missing blobs that need migration to new PDS
@@ -170,40 +355,23 @@ pub async fn get_missing_blobs_impl(
     }
 
     let response = client
-        .http_client
-        .get(&missing_blobs_url)
-        .header("Authorization", format!("Bearer {}", session.access_jwt))
-        .send()
+        .send_xrpc(
+            HttpRequest::get(&missing_blobs_url),
+            Some(&session.access_jwt),
+        )
         .await
         .map_err(|e| ClientError::NetworkError {
             message: format!("Failed to get missing blobs: {}", e),
         })?;
 
-    if response.status().is_success() {
+    if response.is_success() {
         let blobs_data: serde_json::Value =
-            response
-                .json()
-                .await
-                .map_err(|e| ClientError::NetworkError {
-                    message: format!("Failed to parse missing blobs response: {}", e),
-                })?;
-
-        // Parse the blobs from the response using proper deserialization
-        let missing_blobs = if let Some(blobs_array) =
-            blobs_data.get("blobs").and_then(|b| b.as_array())
-        {
-            blobs_array
-                .iter()
-                .filter_map(|blob| serde_json::from_value::<ClientMissingBlob>(blob.clone()).ok())
-                .collect()
-        } else {
-            Vec::new()
-        };
-
-        let cursor = blobs_data
-            .get("cursor")
-            .and_then(|c| c.as_str())
-            .map(|s| s.to_string());
+            response.json().map_err(|e| ClientError::NetworkError {
+                message: format!("Failed to parse missing blobs response: {}", e),
+            })?;
+        let listed: ListMissingBlobsOutput = parse_response(blobs_data)?;
+        let missing_blobs = listed.missing_blobs();
+        let cursor = listed.cursor;
 
         info!("Found {} missing blobs", missing_blobs.len().to_string());
 
@@ -214,7 +382,7 @@ pub async fn get_missing_blobs_impl(
             cursor,
         })
     } else {
-        let error_text = response.text().await.unwrap_or_default();
+        let error_text = response.text();
         error!("Failed to get missing blobs: {}", error_text);
 
         Ok(ClientMissingBlobsResponse {
@@ -262,11 +430,7 @@ pub async fn sync_list_blobs_impl(
     list_blobs_url.push('?');
     list_blobs_url.push_str(&query_params.join("&"));
 
-    let response = client
-        .http_client
-        .get(&list_blobs_url)
-        .header("Authorization", format!("Bearer {}", session.access_jwt))
-        .send()
+    let response = send_sync_read(client.http_client.get(&list_blobs_url), &session.access_jwt)
         .await
         .map_err(|e| ClientError::NetworkError {
             message: format!("Failed to list blobs: {}", e),
@@ -280,21 +444,15 @@ pub async fn sync_list_blobs_impl(
                 .map_err(|e| ClientError::NetworkError {
                     message: format!("Failed to parse list blobs response: {}", e),
                 })?;
+        let listed: ListBlobsOutput = parse_response(blobs_data)?;
 
-        // Parse the CIDs array from the response and validate each CID
-        let cids = if let Some(cids_array) = blobs_data.get("cids").and_then(|c| c.as_array()) {
-            cids_array
-                .iter()
-                .filter_map(|cid| cid.as_str().and_then(|s| Cid::try_from(s).ok()))
-                .collect()
-        } else {
-            Vec::new()
-        };
-
-        let cursor = blobs_data
-            .get("cursor")
-            .and_then(|c| c.as_str())
-            .map(|s| s.to_string());
+        // Validate each CID, skipping any the PDS returned in a form we can't parse
+        let cids: Vec<Cid> = listed
+            .cids
+            .iter()
+            .filter_map(|cid| Cid::try_from(cid.as_str()).ok())
+            .collect();
+        let cursor = listed.cursor;
 
         info!("Found {} blobs in repository", cids.len());
 
@@ -476,7 +634,7 @@ pub async fn verify_blobs_exist_impl(
             .get(&url)
             .bearer_auth(&session.access_jwt)
             .query(&[("did", &session.did), ("cid", &cid.to_string())])
-            .send()
+            .send_traced()
             .await
             .map_err(|e| ClientError::NetworkError {
                 message: format!("Failed to verify blob {}: {}", cid, e),
@@ -2,9 +2,17 @@ use anyhow::Result;
 use serde_json::json;
 use tracing::{error, info, instrument};
 
+use crate::services::client::clock_skew::observe_issued_token;
+use crate::services::client::idempotency::add_idempotency_header;
+use crate::services::client::schema::{
+    parse_response, CheckAccountStatusOutput, CreateAccountOutput, CreateAppPasswordOutput,
+    GetServiceAuthOutput, GetSessionEmailOutput, ListAppPasswordsOutput, RefreshSessionOutput,
+    RequestEmailUpdateOutput,
+};
 use crate::services::client::session::JwtUtils;
 use crate::services::client::types::*;
 use crate::services::client::{ClientError, PdsClient};
+use crate::services::transport::{HttpRequest, HttpResponse};
 
 /// Implementation of create_account functionality
 /// Create account on a PDS
@@ -13,22 +21,28 @@ use crate::services::client::{ClientError, PdsClient};
 #[instrument(skip(client), err)]
 pub async fn create_account_impl(
     client: &PdsClient,
+    pds_url: &str,
     request: ClientCreateAccountRequest,
 ) -> Result<ClientCreateAccountResponse, ClientError> {
     info!("Creating account for handle: {}", request.handle);
 
-    // Derive PDS URL from handle domain (simplified approach)
-    let pds_url = client.derive_pds_url_from_handle(&request.handle);
+    // The account goes to the PDS the user selected; a handle on a sub-domain
+    // of that PDS would otherwise point at the parent domain
+    let pds_url = pds_url.trim_end_matches('/').to_string();
 
     // NEWBOLD.md: com.atproto.server.createAccount for account creation with existing DID
     let create_url = format!("{}/xrpc/com.atproto.server.createAccount", pds_url);
     let mut request_body = json!({
-        "did": request.did,
         "handle": request.handle,
         "password": request.password,
         "email": request.email
     });
 
+    // Left out for a brand-new account, which gets its DID from the PDS
+    if !request.did.is_empty() {
+        request_body["did"] = json!(request.did);
+    }
+
     if let Some(invite_code) = &request.invite_code {
         request_body["inviteCode"] = json!(invite_code);
     }
@@ -37,60 +51,43 @@ pub async fn create_account_impl(
         request_body["verificationCode"] = json!(verification_code);
     }
 
-    let mut request_builder = client
-        .http_client
-        .post(&create_url)
-        .header("Content-Type", "application/json")
-        .json(&request_body);
+    if let Some(verification_phone) = &request.verification_phone {
+        request_body["verificationPhone"] = json!(verification_phone);
+    }
 
-    // Add authorization header if service auth token is provided (for existing DID accounts)
-    if let Some(service_auth_token) = &request.service_auth_token {
-        request_builder =
-            request_builder.header("Authorization", format!("Bearer {}", service_auth_token));
+    let mut http_request = HttpRequest::post_json(&create_url, &request_body);
+
+    if let Some(key) = &request.idempotency_key {
+        add_idempotency_header(&mut http_request, key);
     }
 
-    let response = request_builder
-        .send()
+    // Service auth token as the bearer token (for existing DID accounts)
+    let response = client
+        .send_xrpc(http_request, request.service_auth_token.as_deref())
         .await
         .map_err(|e| ClientError::NetworkError {
             message: format!("Failed to call createAccount: {}", e),
         })?;
 
-    if response.status().is_success() {
+    if response.is_success() {
         let account_data: serde_json::Value =
             response
                 .json()
-                .await
-                .map_err(|e| ClientError::NetworkError {
+                .map_err(|e| ClientError::SerializationError {
                     message: format!("Failed to parse response: {}", e),
                 })?;
+        let account: CreateAccountOutput = parse_response(account_data)?;
 
-        // Parse JWT to get expiration
-        let access_jwt = account_data["accessJwt"]
-            .as_str()
-            .unwrap_or_default()
-            .to_string();
-        let expires_at = if !access_jwt.is_empty() {
-            JwtUtils::get_expiration(&access_jwt)
-        } else {
-            None
-        };
+        // Parse JWT to get expiration; its issue time tells us the PDS's clock
+        observe_issued_token(&account.access_jwt, &pds_url);
+        let expires_at = JwtUtils::get_expiration(&account.access_jwt);
 
         let session = ClientSessionCredentials {
-            did: account_data["did"]
-                .as_str()
-                .unwrap_or(&request.did)
-                .to_string(),
-            handle: account_data["handle"]
-                .as_str()
-                .unwrap_or(&request.handle)
-                .to_string(),
+            did: account.did,
+            handle: account.handle,
             pds: pds_url,
-            access_jwt,
-            refresh_jwt: account_data["refreshJwt"]
-                .as_str()
-                .unwrap_or_default()
-                .to_string(),
+            access_jwt: account.access_jwt,
+            refresh_jwt: account.refresh_jwt,
             expires_at,
         };
 
@@ -103,13 +100,8 @@ pub async fn create_account_impl(
             resumable: false,
         })
     } else {
-        let status = response.status();
-        let error_text = response
-            .text()
-            .await
-            .map_err(|e| ClientError::NetworkError {
-                message: format!("Failed to read error response: {}", e),
-            })?;
+        let status = response.status;
+        let error_text = response.text();
 
         // Try to parse structured JSON error response
         let (error_code, resumable, session) =
@@ -176,6 +168,16 @@ pub async fn create_account_impl(
                 error_code,
                 resumable,
             })
+        } else if status >= 500 {
+            // Worth retrying, unlike the PDS refusing the request
+            error!(
+                "Account creation failed with status {}: {}",
+                status, error_text
+            );
+            Err(ClientError::ServerError {
+                status_code: status,
+                message: error_text,
+            })
         } else {
             error!(
                 "Account creation failed with status {}: {}",
@@ -192,6 +194,46 @@ pub async fn create_account_impl(
     }
 }
 
+/// Ask a PDS that requires phone verification to text a signup code
+#[instrument(skip(client, phone_number), err)]
+pub async fn request_phone_verification_impl(
+    client: &PdsClient,
+    pds_url: &str,
+    phone_number: &str,
+) -> Result<ClientPhoneVerificationResponse, ClientError> {
+    info!("Requesting phone verification code from {}", pds_url);
+
+    let request_url = format!(
+        "{}/xrpc/com.atproto.temp.requestPhoneVerification",
+        pds_url.trim_end_matches('/')
+    );
+
+    let response = client
+        .send_xrpc(
+            HttpRequest::post_json(&request_url, &json!({ "phoneNumber": phone_number })),
+            None,
+        )
+        .await
+        .map_err(|e| ClientError::NetworkError {
+            message: format!("Failed to request phone verification: {}", e),
+        })?;
+
+    if response.is_success() {
+        Ok(ClientPhoneVerificationResponse {
+            success: true,
+            message: "Verification code sent by text message".to_string(),
+        })
+    } else {
+        let error_text = response.text();
+        error!("Phone verification request failed: {}", error_text);
+
+        Ok(ClientPhoneVerificationResponse {
+            success: false,
+            message: format!("Phone verification request failed: {}", error_text),
+        })
+    }
+}
+
 /// Implementation of check_account_status functionality
 /// Check account status
 // NEWBOLD.md Step: goat account status (line 58)
@@ -205,54 +247,34 @@ pub async fn check_account_status_impl(
     let status_url = format!("{}/xrpc/com.atproto.server.checkAccountStatus", session.pds);
 
     let response = client
-        .http_client
-        .get(&status_url)
-        .header("Authorization", format!("Bearer {}", session.access_jwt))
-        .send()
+        .send_xrpc(HttpRequest::get(&status_url), Some(&session.access_jwt))
         .await
         .map_err(|e| ClientError::NetworkError {
             message: format!("Failed to check account status: {}", e),
         })?;
 
-    if response.status().is_success() {
+    if response.is_success() {
         let status_data: serde_json::Value =
-            response
-                .json()
-                .await
-                .map_err(|e| ClientError::NetworkError {
-                    message: format!("Failed to parse status response: {}", e),
-                })?;
-
-        let activated = status_data["activated"].as_bool();
-        let expected_blobs = status_data["expectedBlobs"].as_i64();
-        let imported_blobs = status_data["importedBlobs"].as_i64();
-        let indexed_records = status_data["indexedRecords"].as_i64();
-        let private_state_values = status_data["privateStateValues"].as_i64();
-        let repo_blocks = status_data["repoBlocks"].as_i64();
-        let repo_commit = status_data["repoCommit"].as_str().map(|s| s.to_string());
-        let repo_rev = status_data["repoRev"].as_str().map(|s| s.to_string());
-        let valid_did = status_data["validDid"].as_bool();
+            response.json().map_err(|e| ClientError::NetworkError {
+                message: format!("Failed to parse status response: {}", e),
+            })?;
+        let status: CheckAccountStatusOutput = parse_response(status_data)?;
 
         Ok(ClientAccountStatusResponse {
             success: true,
             message: "Account status retrieved".to_string(),
-            activated,
-            expected_blobs,
-            imported_blobs,
-            indexed_records,
-            private_state_values,
-            repo_blocks,
-            repo_commit,
-            repo_rev,
-            valid_did,
+            activated: Some(status.activated),
+            expected_blobs: Some(status.expected_blobs),
+            imported_blobs: Some(status.imported_blobs),
+            indexed_records: Some(status.indexed_records),
+            private_state_values: Some(status.private_state_values),
+            repo_blocks: Some(status.repo_blocks),
+            repo_commit: Some(status.repo_commit),
+            repo_rev: Some(status.repo_rev),
+            valid_did: Some(status.valid_did),
         })
     } else {
-        let error_text = response
-            .text()
-            .await
-            .map_err(|e| ClientError::NetworkError {
-                message: format!("Failed to read error response: {}", e),
-            })?;
+        let error_text = response.text();
 
         Ok(ClientAccountStatusResponse {
             success: false,
@@ -270,6 +292,222 @@ pub async fn check_account_status_impl(
     }
 }
 
+/// List the account's app passwords (com.atproto.server.listAppPasswords); names only
+#[instrument(skip(client, session), err)]
+pub async fn list_app_passwords_impl(
+    client: &PdsClient,
+    session: &ClientSessionCredentials,
+) -> Result<Vec<ClientAppPassword>, ClientError> {
+    let list_url = format!("{}/xrpc/com.atproto.server.listAppPasswords", session.pds);
+
+    let response = client
+        .send_xrpc(HttpRequest::get(&list_url), Some(&session.access_jwt))
+        .await
+        .map_err(|e| ClientError::NetworkError {
+            message: format!("Failed to list app passwords: {}", e),
+        })?;
+
+    let status = response.status;
+    if !response.is_success() {
+        let error_text = response.text();
+        return Err(ClientError::PdsOperationFailed {
+            operation: "listAppPasswords".to_string(),
+            message: format!("HTTP {}: {}", status, error_text),
+        });
+    }
+
+    let list_data: serde_json::Value = response.json().map_err(|e| ClientError::NetworkError {
+        message: format!("Failed to parse app password list: {}", e),
+    })?;
+    let list: ListAppPasswordsOutput = parse_response(list_data)?;
+    Ok(list
+        .passwords
+        .into_iter()
+        .map(|password| ClientAppPassword {
+            name: password.name,
+            privileged: password.privileged.unwrap_or(false),
+        })
+        .collect())
+}
+
+/// Create an app password (com.atproto.server.createAppPassword)
+#[instrument(skip(client, session), err)]
+pub async fn create_app_password_impl(
+    client: &PdsClient,
+    session: &ClientSessionCredentials,
+    name: &str,
+    privileged: bool,
+) -> Result<ClientCreatedAppPassword, ClientError> {
+    info!("Creating app password {}", name);
+    let create_url = format!("{}/xrpc/com.atproto.server.createAppPassword", session.pds);
+
+    let response = client
+        .send_xrpc(
+            HttpRequest::post_json(
+                &create_url,
+                &json!({ "name": name, "privileged": privileged }),
+            ),
+            Some(&session.access_jwt),
+        )
+        .await
+        .map_err(|e| ClientError::NetworkError {
+            message: format!("Failed to create app password: {}", e),
+        })?;
+
+    let status = response.status;
+    if !response.is_success() {
+        let error_text = response.text();
+        error!("App password creation failed: {}", error_text);
+        return Err(ClientError::PdsOperationFailed {
+            operation: "createAppPassword".to_string(),
+            message: format!("HTTP {}: {}", status, error_text),
+        });
+    }
+
+    let created_data: serde_json::Value =
+        response.json().map_err(|e| ClientError::NetworkError {
+            message: format!("Failed to parse created app password: {}", e),
+        })?;
+    let created: CreateAppPasswordOutput = parse_response(created_data)?;
+    Ok(ClientCreatedAppPassword {
+        name: created.name,
+        password: created.password,
+        privileged: created.privileged.unwrap_or(privileged),
+    })
+}
+
+/// Read the account's email and whether it is confirmed (com.atproto.server.getSession)
+#[instrument(skip(client, session), err)]
+pub async fn get_email_status_impl(
+    client: &PdsClient,
+    session: &ClientSessionCredentials,
+) -> Result<ClientEmailStatus, ClientError> {
+    let session_url = format!("{}/xrpc/com.atproto.server.getSession", session.pds);
+
+    let response = client
+        .send_xrpc(HttpRequest::get(&session_url), Some(&session.access_jwt))
+        .await
+        .map_err(|e| ClientError::NetworkError {
+            message: format!("Failed to read account email: {}", e),
+        })?;
+
+    let status = response.status;
+    if !response.is_success() {
+        let error_text = response.text();
+        return Err(ClientError::PdsOperationFailed {
+            operation: "getSession".to_string(),
+            message: format!("HTTP {}: {}", status, error_text),
+        });
+    }
+
+    let session_data: serde_json::Value =
+        response.json().map_err(|e| ClientError::NetworkError {
+            message: format!("Failed to parse session: {}", e),
+        })?;
+    let output: GetSessionEmailOutput = parse_response(session_data)?;
+    Ok(ClientEmailStatus {
+        email: output.email,
+        confirmed: output.email_confirmed.unwrap_or(false),
+    })
+}
+
+/// POST an email-related XRPC call with the account's session
+async fn post_email_call(
+    client: &PdsClient,
+    session: &ClientSessionCredentials,
+    method: &str,
+    body: Option<serde_json::Value>,
+) -> Result<HttpResponse, ClientError> {
+    let url = format!("{}/xrpc/com.atproto.server.{}", session.pds, method);
+
+    let request = match body {
+        Some(body) => HttpRequest::post_json(&url, &body),
+        None => HttpRequest::new("POST", &url),
+    };
+    let response = client
+        .send_xrpc(request, Some(&session.access_jwt))
+        .await
+        .map_err(|e| ClientError::NetworkError {
+            message: format!("Failed to call {}: {}", method, e),
+        })?;
+
+    let status = response.status;
+    if response.is_success() {
+        Ok(response)
+    } else {
+        let error_text = response.text();
+        error!("{} failed: {}", method, error_text);
+        Err(ClientError::PdsOperationFailed {
+            operation: method.to_string(),
+            message: format!("HTTP {}: {}", status, error_text),
+        })
+    }
+}
+
+/// Have the PDS email a confirmation code (com.atproto.server.requestEmailConfirmation)
+#[instrument(skip(client, session), err)]
+pub async fn request_email_confirmation_impl(
+    client: &PdsClient,
+    session: &ClientSessionCredentials,
+) -> Result<(), ClientError> {
+    post_email_call(client, session, "requestEmailConfirmation", None).await?;
+    info!("Email confirmation requested for DID: {}", session.did);
+    Ok(())
+}
+
+/// Confirm the email with the emailed code (com.atproto.server.confirmEmail)
+#[instrument(skip(client, session, token), err)]
+pub async fn confirm_email_impl(
+    client: &PdsClient,
+    session: &ClientSessionCredentials,
+    email: &str,
+    token: &str,
+) -> Result<(), ClientError> {
+    post_email_call(
+        client,
+        session,
+        "confirmEmail",
+        Some(json!({ "email": email, "token": token })),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Start changing the email (com.atproto.server.requestEmailUpdate)
+///
+/// Returns whether the change needs a token, which the PDS then sends to the
+/// current address; unconfirmed addresses can be changed without one.
+#[instrument(skip(client, session), err)]
+pub async fn request_email_update_impl(
+    client: &PdsClient,
+    session: &ClientSessionCredentials,
+) -> Result<bool, ClientError> {
+    let response = post_email_call(client, session, "requestEmailUpdate", None).await?;
+    let update_data: serde_json::Value =
+        response.json().map_err(|e| ClientError::NetworkError {
+            message: format!("Failed to parse email update response: {}", e),
+        })?;
+    let output: RequestEmailUpdateOutput = parse_response(update_data)?;
+    Ok(output.token_required)
+}
+
+/// Change the email (com.atproto.server.updateEmail)
+#[instrument(skip(client, session, token), err)]
+pub async fn update_email_impl(
+    client: &PdsClient,
+    session: &ClientSessionCredentials,
+    email: &str,
+    token: Option<&str>,
+) -> Result<(), ClientError> {
+    let mut body = json!({ "email": email });
+    if let Some(token) = token {
+        body["token"] = json!(token);
+    }
+    post_email_call(client, session, "updateEmail", Some(body)).await?;
+    info!("Email updated for DID: {}", session.did);
+    Ok(())
+}
+
 /// Implementation of refresh_session functionality
 /// Refresh session tokens
 #[instrument(skip(client), err)]
@@ -280,41 +518,27 @@ pub async fn refresh_session_impl(
     let refresh_url = format!("{}/xrpc/com.atproto.server.refreshSession", session.pds);
 
     let response = client
-        .http_client
-        .post(&refresh_url)
-        .header("Authorization", format!("Bearer {}", session.refresh_jwt))
-        .send()
+        .send_xrpc(
+            HttpRequest::new("POST", &refresh_url),
+            Some(&session.refresh_jwt),
+        )
         .await
         .map_err(|e| ClientError::NetworkError {
             message: format!("Failed to refresh session: {}", e),
         })?;
 
-    if response.status().is_success() {
+    if response.is_success() {
         let refresh_data: serde_json::Value =
-            response
-                .json()
-                .await
-                .map_err(|e| ClientError::NetworkError {
-                    message: format!("Failed to parse refresh response: {}", e),
-                })?;
-
-        let new_access_jwt = refresh_data["accessJwt"]
-            .as_str()
-            .unwrap_or_default()
-            .to_string();
-        let expires_at = if !new_access_jwt.is_empty() {
-            JwtUtils::get_expiration(&new_access_jwt)
-        } else {
-            None
-        };
+            response.json().map_err(|e| ClientError::NetworkError {
+                message: format!("Failed to parse refresh response: {}", e),
+            })?;
+        let refreshed: RefreshSessionOutput = parse_response(refresh_data)?;
 
         let mut updated_session = session.clone();
-        updated_session.access_jwt = new_access_jwt;
-        updated_session.refresh_jwt = refresh_data["refreshJwt"]
-            .as_str()
-            .unwrap_or(&session.refresh_jwt)
-            .to_string();
-        updated_session.expires_at = expires_at;
+        observe_issued_token(&refreshed.access_jwt, &session.pds);
+        updated_session.expires_at = JwtUtils::get_expiration(&refreshed.access_jwt);
+        updated_session.access_jwt = refreshed.access_jwt;
+        updated_session.refresh_jwt = refreshed.refresh_jwt;
 
         info!(
             "Session refreshed successfully for DID: {}",
@@ -322,12 +546,47 @@ pub async fn refresh_session_impl(
         );
         Ok(updated_session)
     } else {
-        let error_text = response.text().await.unwrap_or_default();
+        let error_text = response.text();
         error!("Session refresh failed: {}", error_text);
         Err(ClientError::SessionExpired)
     }
 }
 
+/// Implementation of validate_session functionality
+/// Ask the PDS whether the access token is still accepted (com.atproto.server.getSession)
+/// Used when a token cannot be judged locally, e.g. opaque tokens or non-standard claims
+#[instrument(skip(client), err)]
+pub async fn validate_session_impl(
+    client: &PdsClient,
+    session: &ClientSessionCredentials,
+) -> Result<bool, ClientError> {
+    let session_url = format!("{}/xrpc/com.atproto.server.getSession", session.pds);
+
+    let response = client
+        .send_xrpc(HttpRequest::get(&session_url), Some(&session.access_jwt))
+        .await
+        .map_err(|e| ClientError::NetworkError {
+            message: format!("Failed to validate session: {}", e),
+        })?;
+
+    let status = response.status;
+    if response.is_success() {
+        info!("Session accepted by PDS for DID: {}", session.did);
+        Ok(true)
+    } else if status == 400 || status == 401 {
+        // ExpiredToken / InvalidToken
+        let error_text = response.text();
+        error!("Session rejected by PDS: {}", error_text);
+        Ok(false)
+    } else {
+        let error_text = response.text();
+        Err(ClientError::PdsOperationFailed {
+            operation: "getSession".to_string(),
+            message: format!("HTTP {}: {}", status, error_text),
+        })
+    }
+}
+
 /// Implementation of get_service_auth functionality
 /// Generate service auth token for secure account creation on new PDS
 /// This implements com.atproto.server.getServiceAuth
@@ -370,25 +629,22 @@ pub async fn get_service_auth_impl(
     }
 
     let response = client
-        .http_client
-        .get(&service_auth_url)
-        .header("Authorization", format!("Bearer {}", session.access_jwt))
-        .send()
+        .send_xrpc(
+            HttpRequest::get(&service_auth_url),
+            Some(&session.access_jwt),
+        )
         .await
         .map_err(|e| ClientError::NetworkError {
             message: format!("Failed to call getServiceAuth: {}", e),
         })?;
 
-    if response.status().is_success() {
+    if response.is_success() {
         let auth_data: serde_json::Value =
-            response
-                .json()
-                .await
-                .map_err(|e| ClientError::NetworkError {
-                    message: format!("Failed to parse service auth response: {}", e),
-                })?;
+            response.json().map_err(|e| ClientError::NetworkError {
+                message: format!("Failed to parse service auth response: {}", e),
+            })?;
 
-        let token = auth_data["token"].as_str().unwrap_or_default().to_string();
+        let token = parse_response::<GetServiceAuthOutput>(auth_data)?.token;
 
         if token.is_empty() {
             error!("Service auth token generation returned empty token");
@@ -406,7 +662,7 @@ pub async fn get_service_auth_impl(
             token: Some(token),
         })
     } else {
-        let error_text = response.text().await.unwrap_or_default();
+        let error_text = response.text();
         error!("Service auth token generation failed: {}", error_text);
 
         Ok(ClientServiceAuthResponse {
@@ -1,10 +1,14 @@
 use anyhow::Result;
 use serde_json::json;
-use tracing::{error, info, instrument};
+use tracing::{error, info, instrument, warn};
 
+use crate::services::client::clock_skew::observe_issued_token;
+use crate::services::client::schema::{parse_response, CreateSessionOutput};
 use crate::services::client::session::JwtUtils;
 use crate::services::client::types::*;
 use crate::services::client::{ClientError, PdsClient};
+use crate::services::transport::HttpRequest;
+use crate::utils::handle::normalize_pds_url;
 
 /// Core createSession implementation that all login functions use
 #[instrument(skip(client, password, auth_factor_token), err)]
@@ -38,28 +42,23 @@ pub async fn create_session_core(
     }
 
     let response = client
-        .http_client
-        .post(&session_url)
-        .header("Content-Type", "application/json")
-        .json(&request_body)
-        .send()
+        .send_xrpc(HttpRequest::post_json(&session_url, &request_body), None)
         .await
         .map_err(|e| ClientError::NetworkError {
             message: format!("Failed to call createSession: {}", e),
         })?;
 
-    if response.status().is_success() {
+    if response.is_success() {
         let session_data: serde_json::Value =
-            response
-                .json()
-                .await
-                .map_err(|e| ClientError::NetworkError {
-                    message: format!("Failed to parse response: {}", e),
-                })?;
+            response.json().map_err(|e| ClientError::NetworkError {
+                message: format!("Failed to parse response: {}", e),
+            })?;
+
+        let session_data: CreateSessionOutput = parse_response(session_data)?;
 
         // Check for active status and handle takendown accounts
-        let is_active = session_data["active"].as_bool().unwrap_or(true);
-        let status = session_data["status"].as_str();
+        let is_active = session_data.active.unwrap_or(true);
+        let status = session_data.status.as_deref();
 
         // If account is not active and we're not allowing takendown, fail
         if !is_active && allow_takendown != Some(true) {
@@ -67,47 +66,35 @@ pub async fn create_session_core(
             return Ok(ClientLoginResponse {
                 success: false,
                 message: format!("Account is not active (status: {})", status_msg),
-                did: Some(session_data["did"].as_str().unwrap_or_default().to_string()),
+                did: Some(session_data.did.clone()),
                 session: None,
                 active: Some(is_active),
                 status: status.map(|s| s.to_string()),
             });
         }
 
-        // Extract tokens
-        let access_jwt = session_data["accessJwt"]
-            .as_str()
-            .unwrap_or_default()
-            .to_string();
-        let refresh_jwt = session_data["refreshJwt"]
-            .as_str()
-            .unwrap_or_default()
-            .to_string();
-
         // Check if we got valid tokens
-        if access_jwt.is_empty() || refresh_jwt.is_empty() {
+        if session_data.access_jwt.is_empty() || session_data.refresh_jwt.is_empty() {
             return Ok(ClientLoginResponse {
                 success: false,
                 message: "Login succeeded but no session tokens provided".to_string(),
-                did: Some(session_data["did"].as_str().unwrap_or_default().to_string()),
+                did: Some(session_data.did.clone()),
                 session: None,
                 active: Some(is_active),
                 status: status.map(|s| s.to_string()),
             });
         }
 
-        // Parse JWT for expiration
-        let expires_at = JwtUtils::get_expiration(&access_jwt);
+        // Parse JWT for expiration; its issue time tells us the PDS's clock
+        observe_issued_token(&session_data.access_jwt, pds_url);
+        let expires_at = JwtUtils::get_expiration(&session_data.access_jwt);
 
         let session = ClientSessionCredentials {
-            did: session_data["did"].as_str().unwrap_or_default().to_string(),
-            handle: session_data["handle"]
-                .as_str()
-                .unwrap_or(identifier)
-                .to_string(),
+            did: session_data.did.clone(),
+            handle: session_data.handle.clone(),
             pds: pds_url.to_string(),
-            access_jwt,
-            refresh_jwt,
+            access_jwt: session_data.access_jwt.clone(),
+            refresh_jwt: session_data.refresh_jwt.clone(),
             expires_at,
         };
 
@@ -133,11 +120,8 @@ pub async fn create_session_core(
         })
     } else {
         // Handle error responses
-        let status = response.status();
-        let error_text = response
-            .text()
-            .await
-            .unwrap_or_else(|e| format!("Failed to read error response: {}", e));
+        let status = response.status;
+        let error_text = response.text();
 
         error!("Login failed with status {}: {}", status, error_text);
 
@@ -181,37 +165,105 @@ pub async fn create_session_core(
 }
 
 /// Main login implementation with handle/DID resolution
+///
+/// `pds_override` is a PDS the user typed in place of the resolved one; it
+/// is still checked against the DID document once the session says which
+/// account logged in.
 #[instrument(skip(client, password), err)]
 pub async fn login_impl(
     client: &PdsClient,
     identifier: &str,
     password: &str,
+    pds_override: Option<&str>,
 ) -> Result<ClientLoginResponse, ClientError> {
     info!("Starting login for identifier: {}", identifier);
 
     // First resolve identifier to DID and PDS URL if needed
-    let (_did, pds_url) = if identifier.starts_with("did:") {
-        // If it's already a DID, resolve to PDS
-        let did = identifier.to_string();
-        let pds_url = client.resolve_pds_from_did(&did).await?;
-        (did, pds_url)
-    } else {
-        // If it's a handle, resolve to DID and PDS
-        let resolved_did = client
-            .identity_resolver
-            .resolve_handle(identifier)
-            .await
-            .map_err(ClientError::ResolutionFailed)?;
-        let pds_url = client.resolve_pds_from_did(&resolved_did).await?;
-        (resolved_did, pds_url)
+    let (did, pds_url) = match pds_override {
+        None => {
+            let (did, pds_url) = client.resolve_identity(identifier).await?;
+            (Some(did), pds_url)
+        }
+        Some(pds) => {
+            // The override usually stands in for a resolution that failed,
+            // so only the DID is wanted and only if it can be found
+            let did = match client.resolve_identity(identifier).await {
+                Ok((did, _)) => Some(did),
+                Err(e) => {
+                    warn!("Logging in at {} without a resolved DID: {}", pds, e);
+                    identifier
+                        .starts_with("did:")
+                        .then(|| identifier.to_string())
+                }
+            };
+            (did, normalize_pds_url(pds))
+        }
     };
 
     // Use the core implementation
-    create_session_core(
+    let response = create_session_core(
         client, identifier, password, &pds_url, None, // No auth factor token
         None, // Default takendown behavior
     )
-    .await
+    .await?;
+
+    let Some(session) = response.session.as_ref().filter(|_| response.success) else {
+        return Ok(response);
+    };
+    // The PDS may log the identifier into another account than the one it
+    // resolved to; ask the session DID's own document where it lives
+    let did = did.unwrap_or_else(|| session.did.clone());
+    let session_pds = if session.did == did && pds_override.is_none() {
+        Some(pds_url.clone())
+    } else {
+        client.resolve_pds_from_did(&session.did).await.ok()
+    };
+    if let Err(message) = check_session_identity(identifier, &did, session, session_pds.as_deref())
+    {
+        error!("Refusing login: {}", message);
+        return Ok(ClientLoginResponse {
+            success: false,
+            message,
+            did: Some(session.did.clone()),
+            session: None,
+            active: response.active,
+            status: response.status.clone(),
+        });
+    }
+    Ok(response)
+}
+
+/// Check that a login landed on the account the identifier resolves to, and
+/// that the session's PDS is the one the account's DID document lists
+///
+/// A handle on a sub-domain of a PDS, or a stale `.well-known` record, can
+/// resolve to one account while the PDS logs the same identifier into
+/// another; migrating that session would move the wrong account.
+pub fn check_session_identity(
+    identifier: &str,
+    resolved_did: &str,
+    session: &ClientSessionCredentials,
+    session_did_pds: Option<&str>,
+) -> Result<(), String> {
+    if session.did != resolved_did {
+        return Err(format!(
+            "{} resolves to {}, but {} logged you in as {} (@{}). Migrating this session would move a different account than the one you entered. \
+             This happens when the handle points somewhere other than the account's PDS, for example a handle on a sub-domain of a PDS. \
+             Log in with your DID instead, or fix the handle's DNS or .well-known record first.",
+            identifier, resolved_did, session.pds, session.did, session.handle
+        ));
+    }
+    if let Some(document_pds) = session_did_pds {
+        if normalize_pds_url(document_pds) != normalize_pds_url(&session.pds) {
+            return Err(format!(
+                "You are logged in at {}, but the DID document of {} lists {} as its PDS. \
+                 The migration reads from the PDS in the DID document, so it would copy a different repository than the one you logged into. \
+                 Log in at {} instead.",
+                session.pds, session.did, document_pds, document_pds
+            ));
+        }
+    }
+    Ok(())
 }
 
 /// Full implementation with all createSession parameters
@@ -255,3 +307,33 @@ pub async fn try_login_before_creation_impl(
     )
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_identity_must_match_resolution() {
+        let session = ClientSessionCredentials {
+            did: "did:plc:alice".to_string(),
+            handle: "alice.pds.example".to_string(),
+            pds: "https://pds.example".to_string(),
+            access_jwt: String::new(),
+            refresh_jwt: String::new(),
+            expires_at: None,
+        };
+        let check = |resolved: &str, document_pds: Option<&str>| {
+            check_session_identity("alice.pds.example", resolved, &session, document_pds)
+        };
+
+        assert!(check("did:plc:alice", Some("https://PDS.example/")).is_ok());
+        assert!(check("did:plc:alice", None).is_ok());
+
+        let wrong_account = check("did:plc:bob", Some("https://pds.example")).unwrap_err();
+        assert!(wrong_account.contains("did:plc:bob"));
+        assert!(wrong_account.contains("logged you in as did:plc:alice"));
+
+        let wrong_pds = check("did:plc:alice", Some("https://other.example")).unwrap_err();
+        assert!(wrong_pds.contains("lists https://other.example"));
+    }
+}
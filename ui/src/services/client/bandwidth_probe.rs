@@ -0,0 +1,358 @@
+//! Preflight throughput measurement to the source and target PDS
+//!
+//! Latency alone doesn't say how long a migration will take. Once the target
+//! account exists, a slice of the source repository is downloaded and a
+//! random test blob is uploaded to the target, both timed. The slower of the
+//! two bounds the whole transfer, since every byte passes through the browser.
+//! That rate drives the ETA shown while blobs stream, the concurrency suggested
+//! for the link, and a warning when the migration would outlast many access
+//! tokens. The report is kept for the support bundle.
+//!
+//! Like the readiness check's test blob, the upload is never referenced by a
+//! record and is discarded by the PDS.
+
+use futures_util::StreamExt;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::info;
+
+use super::host_stats::{host_concurrency, host_of};
+use super::http_trace::TracedSend;
+use super::session::JwtUtils;
+use super::types::ClientSessionCredentials;
+use crate::utils::byte_format::format_throughput;
+use crate::utils::platform::{fill_random, now_ms};
+use crate::utils::time_format::humanize_duration;
+
+/// Bytes of the source repository read before the download is timed out
+const DOWNLOAD_PROBE_BYTES: u64 = 512 * 1024;
+
+/// Size of the random blob uploaded to the target
+const UPLOAD_PROBE_BYTES: usize = 256 * 1024;
+
+const BANDWIDTH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Access token lifetime assumed when the token carries no `iat`/`exp` claims;
+/// the reference PDS issues two-hour access tokens
+pub const DEFAULT_ACCESS_TOKEN_LIFETIME_SECS: u64 = 2 * 60 * 60;
+
+/// Token refreshes a migration may need before the user is warned about the link
+pub const TOKEN_REFRESH_WARNING_THRESHOLD: u32 = 6;
+
+/// Below this rate a link is treated as slow and transfers run one at a time
+const SLOW_LINK_BYTES_PER_SEC: f64 = 256.0 * 1024.0;
+
+/// Above this rate a link can keep the configured maximum of transfers busy
+const FAST_LINK_BYTES_PER_SEC: f64 = 4.0 * 1024.0 * 1024.0;
+
+/// Which way a sample moved data, seen from the browser
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransferDirection {
+    /// From the source PDS
+    Download,
+    /// To the target PDS
+    Upload,
+}
+
+/// One timed transfer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThroughputSample {
+    pub direction: TransferDirection,
+    pub url: String,
+    pub bytes: u64,
+    pub elapsed_ms: u64,
+    pub error: Option<String>,
+}
+
+impl ThroughputSample {
+    /// Measured rate, or `None` if the transfer failed or moved nothing
+    pub fn bytes_per_sec(&self) -> Option<f64> {
+        if self.error.is_some() || self.bytes == 0 {
+            return None;
+        }
+        Some(self.bytes as f64 * 1000.0 / self.elapsed_ms.max(1) as f64)
+    }
+}
+
+/// Preflight throughput and what was derived from it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BandwidthReport {
+    pub samples: Vec<ThroughputSample>,
+    /// Approximate size of everything the migration will move
+    pub estimated_bytes: u64,
+    pub estimated_secs: Option<u64>,
+    pub token_lifetime_secs: u64,
+    pub token_refreshes: Option<u32>,
+    pub suggested_concurrency: usize,
+}
+
+impl BandwidthReport {
+    /// The slower direction, which bounds the transfer
+    pub fn bottleneck_bytes_per_sec(&self) -> Option<f64> {
+        self.samples
+            .iter()
+            .filter_map(ThroughputSample::bytes_per_sec)
+            .min_by(f64::total_cmp)
+    }
+
+    /// Warning for the user when the link would outlast many access tokens
+    pub fn slow_link_warning(&self) -> Option<String> {
+        let refreshes = self.token_refreshes?;
+        if refreshes < TOKEN_REFRESH_WARNING_THRESHOLD {
+            return None;
+        }
+        let rate = self.bottleneck_bytes_per_sec()?;
+        Some(format!(
+            "This connection moves about {}, so the migration may take {} and its login will be refreshed about {} times. Keep this tab open, or use a faster connection if you can.",
+            format_throughput(rate),
+            humanize_duration(self.estimated_secs.unwrap_or_default()),
+            refreshes
+        ))
+    }
+}
+
+static LAST_BANDWIDTH_REPORT: Mutex<Option<BandwidthReport>> = Mutex::new(None);
+
+/// Most recent preflight bandwidth report, if one has run
+pub fn last_bandwidth_report() -> Option<BandwidthReport> {
+    LAST_BANDWIDTH_REPORT
+        .lock()
+        .ok()
+        .and_then(|last| last.clone())
+}
+
+/// Seconds to move `bytes` at `bytes_per_sec`
+pub fn estimate_transfer_secs(bytes: u64, bytes_per_sec: f64) -> Option<u64> {
+    (bytes_per_sec > 0.0).then(|| (bytes as f64 / bytes_per_sec).ceil() as u64)
+}
+
+/// Refreshes needed to keep a session alive for `secs`
+pub fn token_refreshes_needed(secs: u64, token_lifetime_secs: u64) -> u32 {
+    (secs / token_lifetime_secs.max(1)) as u32
+}
+
+/// Parallel transfers the link can keep busy, never above `configured_max`
+pub fn suggested_concurrency(bytes_per_sec: Option<f64>, configured_max: usize) -> usize {
+    let configured_max = configured_max.max(1);
+    let suggested = match bytes_per_sec {
+        None => 2,
+        Some(rate) if rate < SLOW_LINK_BYTES_PER_SEC => 1,
+        Some(rate) if rate < FAST_LINK_BYTES_PER_SEC => 4,
+        Some(_) => configured_max,
+    };
+    suggested.min(configured_max)
+}
+
+/// Lifetime of the access token, from its claims when it has them
+pub fn token_lifetime_secs(access_jwt: &str) -> u64 {
+    let claims = JwtUtils::decode_claims(access_jwt);
+    let issued = claims
+        .as_ref()
+        .and_then(|claims| claims.get("iat")?.as_u64());
+    match (issued, JwtUtils::get_expiration(access_jwt)) {
+        (Some(iat), Some(exp)) if exp > iat => exp - iat,
+        _ => DEFAULT_ACCESS_TOKEN_LIFETIME_SECS,
+    }
+}
+
+/// Assemble the report from the samples and the account size
+pub fn build_report(
+    samples: Vec<ThroughputSample>,
+    estimated_bytes: u64,
+    token_lifetime_secs: u64,
+    configured_concurrency: usize,
+) -> BandwidthReport {
+    let mut report = BandwidthReport {
+        samples,
+        estimated_bytes,
+        token_lifetime_secs,
+        ..BandwidthReport::default()
+    };
+    let rate = report.bottleneck_bytes_per_sec();
+    report.estimated_secs = rate.and_then(|rate| estimate_transfer_secs(estimated_bytes, rate));
+    report.token_refreshes = report
+        .estimated_secs
+        .map(|secs| token_refreshes_needed(secs, token_lifetime_secs));
+    report.suggested_concurrency = suggested_concurrency(rate, configured_concurrency);
+    report
+}
+
+async fn measure_download(client: &Client, session: &ClientSessionCredentials) -> ThroughputSample {
+    let url = format!(
+        "{}/xrpc/com.atproto.sync.getRepo?did={}",
+        session.pds.trim_end_matches('/'),
+        session.did
+    );
+    let mut sample = ThroughputSample {
+        direction: TransferDirection::Download,
+        url: url.clone(),
+        bytes: 0,
+        elapsed_ms: 0,
+        error: None,
+    };
+    let response = match client
+        .get(&url)
+        .timeout(BANDWIDTH_TIMEOUT)
+        .send_traced()
+        .await
+    {
+        Ok(response) if response.status().is_success() => response,
+        Ok(response) => {
+            sample.error = Some(format!("HTTP {}", response.status().as_u16()));
+            return sample;
+        }
+        Err(e) => {
+            sample.error = Some(e.to_string());
+            return sample;
+        }
+    };
+
+    // Timed from the first byte so the round trip isn't counted as throughput
+    let started = now_ms();
+    let mut stream = response.bytes_stream();
+    while sample.bytes < DOWNLOAD_PROBE_BYTES {
+        match stream.next().await {
+            Some(Ok(chunk)) => sample.bytes += chunk.len() as u64,
+            Some(Err(e)) => {
+                sample.error = Some(e.to_string());
+                break;
+            }
+            None => break,
+        }
+    }
+    sample.elapsed_ms = (now_ms() - started).max(0.0) as u64;
+    sample
+}
+
+async fn measure_upload(client: &Client, session: &ClientSessionCredentials) -> ThroughputSample {
+    let url = format!(
+        "{}/xrpc/com.atproto.repo.uploadBlob",
+        session.pds.trim_end_matches('/')
+    );
+    // Random bytes, so compression on the way can't flatter the result
+    let mut body = vec![0u8; UPLOAD_PROBE_BYTES];
+    fill_random(&mut body);
+
+    let started = now_ms();
+    let result = client
+        .post(&url)
+        .header("Authorization", format!("Bearer {}", session.access_jwt))
+        .header("Content-Type", "application/octet-stream")
+        .body(body)
+        .timeout(BANDWIDTH_TIMEOUT)
+        .send_traced()
+        .await;
+    let elapsed_ms = (now_ms() - started).max(0.0) as u64;
+    let error = match result {
+        Ok(response) if response.status().is_success() => None,
+        Ok(response) => Some(format!("HTTP {}", response.status().as_u16())),
+        Err(e) => Some(e.to_string()),
+    };
+    ThroughputSample {
+        direction: TransferDirection::Upload,
+        url,
+        bytes: UPLOAD_PROBE_BYTES as u64,
+        elapsed_ms,
+        error,
+    }
+}
+
+/// Time a download from the source and an upload to the target, and record the report
+pub async fn run_bandwidth_preflight(
+    old_session: &ClientSessionCredentials,
+    new_session: &ClientSessionCredentials,
+    estimated_bytes: u64,
+    configured_concurrency: usize,
+) -> BandwidthReport {
+    let client = Client::new();
+    // One after the other, so the two don't compete for the same link
+    let download = measure_download(&client, old_session).await;
+    let upload = measure_upload(&client, new_session).await;
+
+    for sample in [&download, &upload] {
+        match sample.bytes_per_sec() {
+            Some(rate) => info!(
+                "Bandwidth {:?} {}: {}",
+                sample.direction,
+                sample.url,
+                format_throughput(rate)
+            ),
+            None => info!(
+                "Bandwidth {:?} {}: not measured ({})",
+                sample.direction,
+                sample.url,
+                sample.error.as_deref().unwrap_or("no data")
+            ),
+        }
+    }
+
+    // Hosts that have been rate-limiting or failing get fewer parallel transfers
+    let configured_concurrency = [old_session, new_session]
+        .into_iter()
+        .map(|session| host_concurrency(&host_of(&session.pds), configured_concurrency))
+        .min()
+        .unwrap_or(configured_concurrency);
+    let report = build_report(
+        vec![download, upload],
+        estimated_bytes,
+        token_lifetime_secs(&new_session.access_jwt),
+        configured_concurrency,
+    );
+    if let Ok(mut last) = LAST_BANDWIDTH_REPORT.lock() {
+        *last = Some(report.clone());
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(direction: TransferDirection, bytes: u64, elapsed_ms: u64) -> ThroughputSample {
+        ThroughputSample {
+            direction,
+            url: "https://pds.example".to_string(),
+            bytes,
+            elapsed_ms,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_slowest_direction_drives_estimates() {
+        let samples = vec![
+            sample(TransferDirection::Download, 1_048_576, 1000),
+            // 64 KB/s upload
+            sample(TransferDirection::Upload, 262_144, 4000),
+        ];
+        let report = build_report(samples, 10 * 1024 * 1024 * 1024, 7200, 10);
+
+        assert_eq!(report.bottleneck_bytes_per_sec(), Some(65_536.0));
+        assert_eq!(report.estimated_secs, Some(163_840));
+        assert_eq!(report.token_refreshes, Some(22));
+        assert_eq!(report.suggested_concurrency, 1);
+        assert!(report.slow_link_warning().is_some());
+    }
+
+    #[test]
+    fn test_fast_or_unmeasured_links_stay_quiet() {
+        let fast = build_report(
+            vec![sample(TransferDirection::Upload, 8 * 1024 * 1024, 1000)],
+            500 * 1024 * 1024,
+            7200,
+            6,
+        );
+        assert_eq!(fast.suggested_concurrency, 6);
+        assert_eq!(fast.token_refreshes, Some(0));
+        assert!(fast.slow_link_warning().is_none());
+
+        let mut failed = sample(TransferDirection::Download, 0, 30_000);
+        failed.error = Some("timeout".to_string());
+        let unmeasured = build_report(vec![failed], 500 * 1024 * 1024, 7200, 10);
+        assert_eq!(unmeasured.estimated_secs, None);
+        assert_eq!(unmeasured.suggested_concurrency, 2);
+        assert!(unmeasured.slow_link_warning().is_none());
+    }
+}
@@ -0,0 +1,521 @@
+//! Finding a request body size limit on the new PDS before it bites
+//!
+//! Reverse proxies cap request bodies, and the caps are small by default:
+//! nginx refuses anything over 1 MiB unless `client_max_body_size` is raised.
+//! The readiness check's tiny blob gets through, so the limit only shows when
+//! the first large video or the repository import arrives, often hours in.
+//! After the bandwidth probe, random test blobs are uploaded just above the
+//! limits proxies commonly have (1, 10, 50 and 100 MiB), up to the larger of
+//! the estimated repository and the largest blob the target should accept.
+//! The ladder is capped by what the measured upload rate sends within
+//! `BlobConfig::body_limit_probe_max_secs`, and stops at the first refusal, so
+//! the limit is bracketed between
+//! the last accepted and the first refused size.
+//!
+//! A refusal is traced to whoever sent it (nginx, Caddy, Traefik, Cloudflare
+//! or the PDS itself) from its body and `Server` header, and paired with the
+//! exact configuration line that lifts it. A proxy's 413 page rarely carries
+//! CORS headers, so in the browser it often surfaces as a dropped connection
+//! instead; one right after a smaller upload succeeded is reported as a likely
+//! limit. Like the other test blobs, these are never referenced by a record and
+//! are discarded by the PDS.
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::info;
+
+use super::http_trace::TracedSend;
+use super::types::ClientSessionCredentials;
+use crate::services::config::get_global_config;
+use crate::utils::byte_format::format_size;
+use crate::utils::platform::fill_random;
+
+const MIB: u64 = 1024 * 1024;
+
+/// Body limits proxies ship with or are commonly set to
+const COMMON_LIMITS: [u64; 4] = [MIB, 10 * MIB, 50 * MIB, 100 * MIB];
+
+/// How far above a common limit each test upload goes
+const OVERSHOOT_BYTES: u64 = 64 * 1024;
+
+/// Fixed per-request time on top of the transfer itself
+const PROBE_TIMEOUT_BASE: Duration = Duration::from_secs(30);
+
+/// Whatever answered the oversized request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BodyLimitSource {
+    Nginx,
+    Caddy,
+    Traefik,
+    Cloudflare,
+    /// The PDS's own upload limit
+    Pds,
+    Unknown,
+}
+
+impl BodyLimitSource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            BodyLimitSource::Nginx => "nginx",
+            BodyLimitSource::Caddy => "Caddy",
+            BodyLimitSource::Traefik => "Traefik",
+            BodyLimitSource::Cloudflare => "Cloudflare",
+            BodyLimitSource::Pds => "the PDS itself",
+            BodyLimitSource::Unknown => "a reverse proxy",
+        }
+    }
+}
+
+/// Tell who refused a body from the response's `Server` header and body
+pub fn identify_limit_source(server: Option<&str>, body: &str) -> BodyLimitSource {
+    let server = server.unwrap_or_default().to_ascii_lowercase();
+    let body_lower = body.to_ascii_lowercase();
+    let from = |name: &str| server.contains(name) || body_lower.contains(name);
+
+    if from("cloudflare") {
+        BodyLimitSource::Cloudflare
+    } else if serde_json::from_str::<Value>(body)
+        .ok()
+        .is_some_and(|value| value.get("error").is_some())
+    {
+        // Only XRPC handlers answer with an error object
+        BodyLimitSource::Pds
+    } else if from("nginx") || from("openresty") {
+        BodyLimitSource::Nginx
+    } else if from("caddy") {
+        BodyLimitSource::Caddy
+    } else if from("traefik") {
+        BodyLimitSource::Traefik
+    } else {
+        BodyLimitSource::Unknown
+    }
+}
+
+/// Limit to configure for `needed_bytes`: a quarter of headroom, in whole MiB
+pub fn suggested_limit_mib(needed_bytes: u64) -> u64 {
+    (needed_bytes.saturating_add(needed_bytes / 4))
+        .div_ceil(MIB)
+        .max(1)
+}
+
+/// The configuration change that lets `needed_bytes` through `source`
+pub fn body_limit_fix(source: BodyLimitSource, needed_bytes: u64) -> String {
+    let mib = suggested_limit_mib(needed_bytes);
+    let bytes = mib * MIB;
+    let nginx = format!(
+        "nginx: add `client_max_body_size {}M;` to the server block that proxies to the PDS, then run `nginx -s reload`",
+        mib
+    );
+    let caddy = format!(
+        "Caddy: add `request_body {{ max_size {}MiB }}` to the PDS's site block, then run `caddy reload`",
+        mib
+    );
+    match source {
+        BodyLimitSource::Nginx => nginx,
+        BodyLimitSource::Caddy => caddy,
+        BodyLimitSource::Traefik => format!(
+            "Traefik: attach a buffering middleware with `maxRequestBodyBytes: {}` to the PDS's router, or remove the one limiting it",
+            bytes
+        ),
+        BodyLimitSource::Cloudflare => format!(
+            "Cloudflare: proxied uploads are capped by plan (100 MB on Free and Pro). Set the PDS's DNS record to \"DNS only\" or use a plan allowing {} MiB",
+            mib
+        ),
+        BodyLimitSource::Pds => format!(
+            "PDS: set `PDS_BLOB_UPLOAD_LIMIT={}` in pds.env and restart the PDS",
+            bytes
+        ),
+        BodyLimitSource::Unknown => format!(
+            "Raise the request body limit of the proxy in front of the PDS to at least {} MiB. {}. {}",
+            mib, nginx, caddy
+        ),
+    }
+}
+
+/// Sizes to upload, smallest first: just above each common limit below the
+/// target, then the target itself
+pub fn calibration_sizes(needed_bytes: u64, cap_bytes: u64) -> Vec<u64> {
+    let target = needed_bytes.min(cap_bytes);
+    let mut sizes: Vec<u64> = COMMON_LIMITS
+        .iter()
+        .map(|limit| limit + OVERSHOOT_BYTES)
+        .filter(|size| *size < target)
+        .collect();
+    if target > 0 {
+        sizes.push(target);
+    }
+    sizes
+}
+
+/// Largest test upload worth its transfer time on this link; never below the
+/// first rung, so the nginx default is always checked
+pub fn probe_cap_bytes(max_bytes: u64, max_secs: u64, upload_bytes_per_sec: Option<f64>) -> u64 {
+    let floor = (COMMON_LIMITS[0] + OVERSHOOT_BYTES).min(max_bytes);
+    upload_bytes_per_sec
+        .map_or(max_bytes, |rate| {
+            ((rate * max_secs as f64) as u64).min(max_bytes)
+        })
+        .max(floor)
+}
+
+/// What happened to one test upload
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BodyProbeOutcome {
+    Accepted,
+    /// Refused as too large
+    TooLarge,
+    /// Any other failure, including a dropped connection
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BodyLimitProbe {
+    pub bytes: u64,
+    pub outcome: BodyProbeOutcome,
+    pub status: Option<u16>,
+    pub source: Option<BodyLimitSource>,
+    pub detail: Option<String>,
+}
+
+/// Test uploads to the new PDS and the limit they bracket
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BodyLimitReport {
+    /// Largest request body the migration is expected to send
+    pub needed_bytes: u64,
+    pub probes: Vec<BodyLimitProbe>,
+}
+
+impl BodyLimitReport {
+    pub fn largest_accepted(&self) -> Option<u64> {
+        self.probes
+            .iter()
+            .filter(|probe| probe.outcome == BodyProbeOutcome::Accepted)
+            .map(|probe| probe.bytes)
+            .max()
+    }
+
+    /// First upload refused as too large
+    pub fn refusal(&self) -> Option<&BodyLimitProbe> {
+        self.probes
+            .iter()
+            .find(|probe| probe.outcome == BodyProbeOutcome::TooLarge)
+    }
+
+    /// A dropped upload right after a smaller one got through
+    pub fn likely_refusal(&self) -> Option<&BodyLimitProbe> {
+        let last = self.probes.last()?;
+        (last.outcome == BodyProbeOutcome::Failed && self.largest_accepted().is_some())
+            .then_some(last)
+    }
+
+    fn bracket(&self, refused: u64) -> String {
+        match self.largest_accepted() {
+            Some(accepted) => format!(
+                "between {} and {}",
+                format_size(accepted),
+                format_size(refused)
+            ),
+            None => format!("below {}", format_size(refused)),
+        }
+    }
+
+    /// A confirmed limit below what the migration needs; stops the migration
+    pub fn problem(&self) -> Option<String> {
+        let refused = self.refusal()?;
+        Some(format!(
+            "{} refuses request bodies larger than a limit {}, but this migration needs to send up to {}",
+            refused.source.unwrap_or(BodyLimitSource::Unknown).label(),
+            self.bracket(refused.bytes),
+            format_size(self.needed_bytes)
+        ))
+    }
+
+    /// Signs of a limit that couldn't be confirmed, or a check that stopped short
+    pub fn warning(&self) -> Option<String> {
+        if self.refusal().is_some() {
+            return None;
+        }
+        if let Some(dropped) = self.likely_refusal() {
+            return Some(format!(
+                "The new PDS dropped a {} test upload after accepting {}; a proxy in front of it probably limits request bodies",
+                format_size(dropped.bytes),
+                format_size(self.largest_accepted().unwrap_or_default())
+            ));
+        }
+        if let Some(failed) = self
+            .probes
+            .last()
+            .filter(|probe| probe.outcome == BodyProbeOutcome::Failed)
+        {
+            return Some(format!(
+                "A {} test upload to the new PDS failed ({}); its request body limit is unknown",
+                format_size(failed.bytes),
+                failed.detail.as_deref().unwrap_or("no details")
+            ));
+        }
+        let tested = self.largest_accepted()?;
+        (tested < self.needed_bytes).then(|| {
+            format!(
+                "Uploads up to {} reached the new PDS; larger ones, up to the {} this migration may send, were not tested on this connection",
+                format_size(tested),
+                format_size(self.needed_bytes)
+            )
+        })
+    }
+
+    /// Configuration change for a confirmed or likely limit
+    pub fn fix(&self) -> Option<String> {
+        let probe = self.refusal().or_else(|| self.likely_refusal())?;
+        Some(body_limit_fix(
+            probe.source.unwrap_or(BodyLimitSource::Unknown),
+            self.needed_bytes,
+        ))
+    }
+}
+
+static LAST_BODY_LIMIT: Mutex<Option<BodyLimitReport>> = Mutex::new(None);
+
+/// Most recent body size check, if one has run
+pub fn last_body_limit_report() -> Option<BodyLimitReport> {
+    LAST_BODY_LIMIT.lock().ok().and_then(|last| last.clone())
+}
+
+/// Whether an upload's answer means its body was too large
+pub fn is_body_too_large(status: u16, body: &str) -> bool {
+    let body = body.to_ascii_lowercase();
+    status == 413 || body.contains("payloadtoolarge") || body.contains("blobtoolarge")
+}
+
+/// Guidance for a failed upload of `bytes`, when it was refused as too large
+pub fn body_limit_hint(
+    status: u16,
+    server: Option<&str>,
+    body: &str,
+    bytes: u64,
+) -> Option<String> {
+    is_body_too_large(status, body).then(|| {
+        format!(
+            "The new PDS refused a {} upload as too large. {}",
+            format_size(bytes),
+            body_limit_fix(identify_limit_source(server, body), bytes)
+        )
+    })
+}
+
+async fn probe_upload(
+    client: &Client,
+    session: &ClientSessionCredentials,
+    bytes: u64,
+    upload_bytes_per_sec: Option<f64>,
+) -> BodyLimitProbe {
+    let url = format!(
+        "{}/xrpc/com.atproto.repo.uploadBlob",
+        session.pds.trim_end_matches('/')
+    );
+    // Random bytes, so nothing on the way can compress them under the limit
+    let mut body = vec![0u8; bytes as usize];
+    fill_random(&mut body);
+    let transfer_secs = upload_bytes_per_sec
+        .filter(|rate| *rate > 0.0)
+        .map_or(0.0, |rate| bytes as f64 / rate);
+
+    let result = client
+        .post(&url)
+        .header("Authorization", format!("Bearer {}", session.access_jwt))
+        .header("Content-Type", "application/octet-stream")
+        .header("Content-Length", bytes.to_string())
+        .body(body)
+        .timeout(PROBE_TIMEOUT_BASE + Duration::from_secs_f64(transfer_secs * 2.0))
+        .send_traced()
+        .await;
+    let response = match result {
+        Ok(response) => response,
+        Err(e) => {
+            return BodyLimitProbe {
+                bytes,
+                outcome: BodyProbeOutcome::Failed,
+                status: None,
+                source: None,
+                detail: Some(e.to_string()),
+            }
+        }
+    };
+
+    let status = response.status().as_u16();
+    let server = response
+        .headers()
+        .get("server")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let text = response.text().await.unwrap_or_default();
+    let outcome = if (200..300).contains(&status) {
+        BodyProbeOutcome::Accepted
+    } else if is_body_too_large(status, &text) {
+        BodyProbeOutcome::TooLarge
+    } else {
+        BodyProbeOutcome::Failed
+    };
+    BodyLimitProbe {
+        bytes,
+        outcome,
+        status: Some(status),
+        source: (outcome == BodyProbeOutcome::TooLarge)
+            .then(|| identify_limit_source(server.as_deref(), &text)),
+        detail: (outcome != BodyProbeOutcome::Accepted)
+            .then(|| text.trim().chars().take(200).collect()),
+    }
+}
+
+/// Upload the calibration ladder to the new PDS and record the report
+pub async fn run_body_limit_preflight(
+    session: &ClientSessionCredentials,
+    needed_bytes: u64,
+    upload_bytes_per_sec: Option<f64>,
+) -> BodyLimitReport {
+    let config = get_global_config().blob;
+    let mut report = BodyLimitReport {
+        needed_bytes,
+        probes: Vec::new(),
+    };
+
+    if config.body_limit_probe_max_bytes > 0 {
+        let cap = probe_cap_bytes(
+            config.body_limit_probe_max_bytes,
+            config.body_limit_probe_max_secs,
+            upload_bytes_per_sec,
+        );
+        let client = Client::new();
+        for bytes in calibration_sizes(needed_bytes, cap) {
+            let probe = probe_upload(&client, session, bytes, upload_bytes_per_sec).await;
+            info!(
+                "Body limit probe {}: {:?} (HTTP {:?})",
+                format_size(bytes),
+                probe.outcome,
+                probe.status
+            );
+            let accepted = probe.outcome == BodyProbeOutcome::Accepted;
+            report.probes.push(probe);
+            if !accepted {
+                break;
+            }
+        }
+    }
+
+    if let Ok(mut last) = LAST_BODY_LIMIT.lock() {
+        *last = Some(report.clone());
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probe(
+        bytes: u64,
+        outcome: BodyProbeOutcome,
+        source: Option<BodyLimitSource>,
+    ) -> BodyLimitProbe {
+        BodyLimitProbe {
+            bytes,
+            outcome,
+            status: None,
+            source,
+            detail: None,
+        }
+    }
+
+    #[test]
+    fn test_calibration_and_guided_fix() {
+        let nginx_page = "<html><head><title>413 Request Entity Too Large</title></head><body><center><h1>413 Request Entity Too Large</h1></center><hr><center>nginx/1.24.0</center></body></html>";
+        assert_eq!(
+            identify_limit_source(None, nginx_page),
+            BodyLimitSource::Nginx
+        );
+        assert_eq!(
+            identify_limit_source(Some("Caddy"), ""),
+            BodyLimitSource::Caddy
+        );
+        assert_eq!(
+            identify_limit_source(
+                None,
+                r#"{"error":"PayloadTooLarge","message":"request entity too large"}"#
+            ),
+            BodyLimitSource::Pds
+        );
+        assert_eq!(
+            identify_limit_source(Some("cloudflare"), "<html>413</html>"),
+            BodyLimitSource::Cloudflare
+        );
+        assert_eq!(identify_limit_source(None, ""), BodyLimitSource::Unknown);
+
+        // 80 MiB plus a quarter rounds up to 100 MiB
+        assert_eq!(suggested_limit_mib(80 * MIB), 100);
+        assert!(
+            body_limit_fix(BodyLimitSource::Nginx, 80 * MIB).contains("client_max_body_size 100M;")
+        );
+        assert!(body_limit_fix(BodyLimitSource::Pds, 80 * MIB)
+            .contains("PDS_BLOB_UPLOAD_LIMIT=104857600"));
+
+        assert_eq!(
+            calibration_sizes(30 * MIB, 64 * MIB),
+            vec![MIB + OVERSHOOT_BYTES, 10 * MIB + OVERSHOOT_BYTES, 30 * MIB]
+        );
+        assert_eq!(calibration_sizes(200 * 1024, 64 * MIB), vec![200 * 1024]);
+        // A slow link tests less, but always past the nginx default
+        assert_eq!(
+            probe_cap_bytes(32 * MIB, 10, Some(1024.0 * 1024.0)),
+            10 * MIB
+        );
+        assert_eq!(
+            probe_cap_bytes(32 * MIB, 10, Some(1024.0)),
+            MIB + OVERSHOOT_BYTES
+        );
+        assert_eq!(probe_cap_bytes(32 * MIB, 10, None), 32 * MIB);
+
+        let refused = BodyLimitReport {
+            needed_bytes: 80 * MIB,
+            probes: vec![
+                probe(MIB + OVERSHOOT_BYTES, BodyProbeOutcome::Accepted, None),
+                probe(
+                    10 * MIB + OVERSHOOT_BYTES,
+                    BodyProbeOutcome::TooLarge,
+                    Some(BodyLimitSource::Nginx),
+                ),
+            ],
+        };
+        let problem = refused.problem().unwrap();
+        assert!(problem.starts_with("nginx refuses"), "{}", problem);
+        assert!(refused
+            .fix()
+            .unwrap()
+            .contains("client_max_body_size 100M;"));
+        assert_eq!(refused.warning(), None);
+
+        let dropped = BodyLimitReport {
+            needed_bytes: 80 * MIB,
+            probes: vec![
+                probe(MIB + OVERSHOOT_BYTES, BodyProbeOutcome::Accepted, None),
+                probe(10 * MIB + OVERSHOOT_BYTES, BodyProbeOutcome::Failed, None),
+            ],
+        };
+        assert_eq!(dropped.problem(), None);
+        assert!(dropped.warning().unwrap().contains("probably limits"));
+        assert!(dropped.fix().is_some());
+
+        let short = BodyLimitReport {
+            needed_bytes: 80 * MIB,
+            probes: vec![probe(10 * MIB, BodyProbeOutcome::Accepted, None)],
+        };
+        assert!(short.warning().unwrap().contains("not tested"));
+        assert_eq!(short.fix(), None);
+
+        assert!(body_limit_hint(413, Some("nginx"), "", 5 * MIB)
+            .unwrap()
+            .contains("client_max_body_size"));
+        assert_eq!(body_limit_hint(500, None, "disk full", 5 * MIB), None);
+    }
+}
@@ -0,0 +1,188 @@
+//! Offset between the browser's clock and the PDS servers' clocks
+//!
+//! Token expiry, rate-limit resets and the service-auth `exp` are all in the
+//! server's time, but were compared against the local clock; a laptop whose
+//! clock is an hour off then sees fresh tokens as expired, or asks for a
+//! service-auth token that the old PDS refuses as too far in the future.
+//! Every response's `Date` header (when CORS exposes it) and the `iat` claim
+//! of every freshly issued access token tell us the server's time, so the
+//! latest observation becomes the offset that [`server_now_secs`] applies.
+
+use std::sync::Mutex;
+
+use super::session::JwtUtils;
+use super::types::current_time_secs;
+
+/// Offsets up to this many seconds are network latency and `Date` rounding
+pub const SKEW_TOLERANCE_SECS: i64 = 5;
+
+/// Offsets beyond this are worth telling the user about at preflight
+pub const SKEW_WARNING_SECS: i64 = 120;
+
+static SKEW: Mutex<Option<ClockSkew>> = Mutex::new(None);
+
+/// Last observed server time relative to the local clock
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClockSkew {
+    /// Server time minus local time, in seconds
+    pub offset_secs: i64,
+    /// Where the server time came from, e.g. `Date header from pds.example`
+    pub source: String,
+}
+
+impl ClockSkew {
+    pub fn is_significant(&self) -> bool {
+        self.offset_secs.abs() >= SKEW_WARNING_SECS
+    }
+
+    /// Preflight warning for a clock that is far enough off to confuse the user
+    pub fn warning(&self) -> Option<String> {
+        if !self.is_significant() {
+            return None;
+        }
+        let minutes = (self.offset_secs.abs() + 30) / 60;
+        let direction = if self.offset_secs > 0 {
+            "behind"
+        } else {
+            "ahead of"
+        };
+        Some(format!(
+            "This device's clock is about {} minutes {} the server ({}). Token expiry is adjusted for it, but setting the clock automatically in your system settings avoids surprises.",
+            minutes, direction, self.source
+        ))
+    }
+}
+
+/// Seconds since the epoch of an HTTP `Date` header (`Sun, 06 Nov 1994 08:49:37 GMT`)
+pub fn parse_http_date(value: &str) -> Option<u64> {
+    let mut parts = value.split_whitespace();
+    let _weekday = parts.next()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+    let month = match parts.next()? {
+        "Jan" => 1,
+        "Feb" => 2,
+        "Mar" => 3,
+        "Apr" => 4,
+        "May" => 5,
+        "Jun" => 6,
+        "Jul" => 7,
+        "Aug" => 8,
+        "Sep" => 9,
+        "Oct" => 10,
+        "Nov" => 11,
+        "Dec" => 12,
+        _ => return None,
+    };
+    let year: i64 = parts.next()?.parse().ok()?;
+    let mut time = parts
+        .next()?
+        .split(':')
+        .map(|part| part.parse::<u64>().ok());
+    let (hours, minutes, seconds) = (time.next()??, time.next()??, time.next()??);
+    if parts.next()? != "GMT" || !(1..=31).contains(&day) || hours > 23 || minutes > 59 {
+        return None;
+    }
+    let days = days_from_civil(year, month, day);
+    u64::try_from(days * 86_400)
+        .ok()
+        .map(|secs| secs + hours * 3600 + minutes * 60 + seconds)
+}
+
+/// Days since 1970-01-01 of a civil date
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Server time minus local time, with latency-sized differences treated as none
+pub fn skew_between(server_secs: u64, local_secs: u64) -> i64 {
+    let offset = server_secs as i64 - local_secs as i64;
+    if offset.abs() <= SKEW_TOLERANCE_SECS {
+        0
+    } else {
+        offset
+    }
+}
+
+/// Record the server's current time as seen in a response
+pub fn observe_server_time(server_secs: u64, source: &str) {
+    let skew = ClockSkew {
+        offset_secs: skew_between(server_secs, current_time_secs()),
+        source: source.to_string(),
+    };
+    if let Ok(mut current) = SKEW.lock() {
+        if skew.offset_secs != current.as_ref().map_or(0, |c| c.offset_secs) {
+            tracing::info!(
+                "Clock offset to servers is now {}s ({})",
+                skew.offset_secs,
+                skew.source
+            );
+        }
+        *current = Some(skew);
+    }
+}
+
+/// Record a response's `Date` header
+pub fn observe_date_header(value: &str, host: &str) {
+    if let Some(server_secs) = parse_http_date(value) {
+        observe_server_time(server_secs, &format!("Date header from {}", host));
+    }
+}
+
+/// Record the issue time of an access token the PDS just returned
+pub fn observe_issued_token(access_jwt: &str, pds: &str) {
+    let issued = JwtUtils::decode_claims(access_jwt).and_then(|claims| claims.get("iat")?.as_u64());
+    if let Some(iat) = issued {
+        observe_server_time(iat, &format!("token issued by {}", pds));
+    }
+}
+
+/// Last observed offset, if any server has been heard from
+pub fn clock_skew() -> Option<ClockSkew> {
+    SKEW.lock().ok()?.clone()
+}
+
+/// The current time on the servers' clock, in seconds since the epoch
+pub fn server_now_secs() -> u64 {
+    let offset = clock_skew().map_or(0, |skew| skew.offset_secs);
+    current_time_secs().saturating_add_signed(offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_http_date_and_skew() {
+        assert_eq!(
+            parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"),
+            Some(784_111_777)
+        );
+        assert_eq!(
+            parse_http_date("Sat, 15 Jun 2024 14:30:45 GMT"),
+            Some(1_718_461_845)
+        );
+        assert_eq!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"), None);
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 PST"), None);
+
+        assert_eq!(skew_between(1_000_003, 1_000_000), 0);
+        assert_eq!(skew_between(1_003_600, 1_000_000), 3600);
+        assert_eq!(skew_between(1_000_000, 1_000_600), -600);
+
+        let behind = ClockSkew {
+            offset_secs: 3600,
+            source: "Date header from pds.test".to_string(),
+        };
+        assert!(behind.warning().unwrap().contains("60 minutes behind"));
+        let close = ClockSkew {
+            offset_secs: 30,
+            ..behind
+        };
+        assert_eq!(close.warning(), None);
+    }
+}
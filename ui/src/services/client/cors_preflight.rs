@@ -0,0 +1,447 @@
+//! Preflight check of the new PDS for CORS and mixed-content problems
+//!
+//! Every request of a migration leaves the browser cross-origin, so a PDS
+//! behind a reverse proxy that strips or never adds the CORS headers fails
+//! with nothing more than "Failed to fetch", often halfway through when the
+//! first upload or import is attempted. Before the migration starts, the
+//! target is asked for each kind of request the browser will send, one
+//! non-safelisted header at a time: a plain `describeServer`, then requests
+//! carrying `Authorization`, a JSON body, a blob body, a CAR body and the
+//! `atproto-proxy` header. The first one refused names exactly which header
+//! the proxy has to allow. Authentication is a dummy token, so nothing is
+//! created or changed on the server.
+//!
+//! A browser never shows a refused response to the page, so refusals are
+//! inferred: a request that fails while a `no-cors` request to the same URL
+//! gets through was blocked by CORS. Outside the browser the preflight
+//! headers are read and checked directly. An `http://` PDS behind an
+//! `https://` page is blocked as mixed content before any of this, so that is
+//! reported without probing.
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::info;
+
+use super::http_trace::TracedSend;
+use crate::utils::lookalike::url_host;
+
+const CORS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Token sent where one is required; the PDS rejects it, readably if CORS allows
+const DUMMY_TOKEN: &str = "Bearer cors-preflight-check";
+
+/// One shape of cross-origin request the migration sends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CorsRequirement {
+    pub endpoint: &'static str,
+    pub method: &'static str,
+    /// Request headers beyond the CORS-safelisted ones
+    pub headers: &'static [(&'static str, &'static str)],
+    /// What the PDS or its proxy has to send for this request to work
+    pub needs: &'static str,
+}
+
+/// Requests to probe, each adding one thing the browser has to be allowed
+pub const CORS_REQUIREMENTS: &[CorsRequirement] = &[
+    CorsRequirement {
+        endpoint: "com.atproto.server.describeServer",
+        method: "GET",
+        headers: &[],
+        needs: "Access-Control-Allow-Origin on /xrpc responses",
+    },
+    CorsRequirement {
+        endpoint: "com.atproto.server.getSession",
+        method: "GET",
+        headers: &[("Authorization", DUMMY_TOKEN)],
+        needs: "Access-Control-Allow-Headers including authorization",
+    },
+    CorsRequirement {
+        endpoint: "com.atproto.server.createSession",
+        method: "POST",
+        headers: &[("Content-Type", "application/json")],
+        needs: "Access-Control-Allow-Headers including content-type, and OPTIONS requests answered",
+    },
+    CorsRequirement {
+        endpoint: "com.atproto.repo.uploadBlob",
+        method: "POST",
+        headers: &[
+            ("Authorization", DUMMY_TOKEN),
+            ("Content-Type", "application/octet-stream"),
+        ],
+        needs: "CORS headers on uploadBlob, including its OPTIONS preflight",
+    },
+    CorsRequirement {
+        endpoint: "com.atproto.repo.importRepo",
+        method: "POST",
+        headers: &[
+            ("Authorization", DUMMY_TOKEN),
+            ("Content-Type", "application/vnd.ipld.car"),
+        ],
+        needs: "CORS headers on importRepo, including its OPTIONS preflight",
+    },
+    CorsRequirement {
+        endpoint: "app.bsky.actor.getPreferences",
+        method: "GET",
+        headers: &[
+            ("Authorization", DUMMY_TOKEN),
+            ("atproto-proxy", "did:web:api.bsky.app#bsky_appview"),
+        ],
+        needs: "Access-Control-Allow-Headers including atproto-proxy",
+    },
+];
+
+/// What happened to one probe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CorsOutcome {
+    Allowed,
+    /// The PDS answered, but the browser may not read the answer
+    Blocked,
+    /// No answer at all; CORS can't be judged
+    Unreachable,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CorsProbe {
+    pub endpoint: String,
+    pub method: String,
+    pub needs: String,
+    pub outcome: CorsOutcome,
+    pub detail: Option<String>,
+}
+
+/// Everything found about the target before the migration starts
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CorsReport {
+    pub target: String,
+    /// Why the browser refuses to contact the target at all
+    pub mixed_content: Option<String>,
+    pub probes: Vec<CorsProbe>,
+}
+
+impl CorsReport {
+    /// Problems to fix, most fundamental first
+    pub fn problems(&self) -> Vec<String> {
+        if let Some(problem) = &self.mixed_content {
+            return vec![problem.clone()];
+        }
+        if !self.probes.is_empty()
+            && self
+                .probes
+                .iter()
+                .all(|probe| probe.outcome == CorsOutcome::Unreachable)
+        {
+            return vec![format!("{} did not answer", self.target)];
+        }
+        self.probes
+            .iter()
+            .filter(|probe| probe.outcome == CorsOutcome::Blocked)
+            .map(|probe| format!("{} {}: needs {}", probe.method, probe.endpoint, probe.needs))
+            .collect()
+    }
+
+    pub fn is_clear(&self) -> bool {
+        self.problems().is_empty()
+    }
+}
+
+static LAST_CORS_REPORT: Mutex<Option<CorsReport>> = Mutex::new(None);
+
+/// Most recent CORS preflight report, if one has run
+pub fn last_cors_report() -> Option<CorsReport> {
+    LAST_CORS_REPORT.lock().ok().and_then(|last| last.clone())
+}
+
+fn is_loopback(host: &str) -> bool {
+    matches!(host, "localhost" | "127.0.0.1" | "[::1]" | "::1") || host.ends_with(".localhost")
+}
+
+/// Why an `https://` page can't reach `target`, if it can't
+pub fn mixed_content_problem(page_url: &str, target: &str) -> Option<String> {
+    let page_secure = page_url.trim().to_ascii_lowercase().starts_with("https://");
+    let target_plain = target.trim().to_ascii_lowercase().starts_with("http://");
+    // Browsers treat loopback addresses as secure, which is how local test PDSes work
+    let loopback = url_host(target).is_some_and(|host| is_loopback(&host));
+    (page_secure && target_plain && !loopback).then(|| {
+        format!(
+            "{} uses plain http, and browsers block http requests from this https page (mixed content). Serve the PDS over https, e.g. with a TLS-terminating reverse proxy",
+            target.trim()
+        )
+    })
+}
+
+/// Check the headers a server returned for a preflight (or, for simple
+/// requests, the response itself) against what the browser would require
+pub fn evaluate_cors_headers(
+    requirement: &CorsRequirement,
+    origin: &str,
+    headers: &[(String, String)],
+) -> Result<(), String> {
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.trim().to_string())
+    };
+    let listed = |value: &str, wanted: &str| {
+        value
+            .split(',')
+            .any(|item| item.trim().eq_ignore_ascii_case(wanted))
+    };
+
+    match header("access-control-allow-origin") {
+        Some(allowed) if allowed == "*" || allowed == origin => {}
+        Some(allowed) => {
+            return Err(format!(
+                "Access-Control-Allow-Origin is {}, not {} or *",
+                allowed, origin
+            ))
+        }
+        None => return Err("no Access-Control-Allow-Origin header".to_string()),
+    }
+    if requirement.headers.is_empty() {
+        return Ok(());
+    }
+
+    let allowed_headers = header("access-control-allow-headers").unwrap_or_default();
+    for (name, _) in requirement.headers {
+        // A wildcard never covers Authorization
+        let wildcard = allowed_headers.trim() == "*" && !name.eq_ignore_ascii_case("authorization");
+        if !wildcard && !listed(&allowed_headers, name) {
+            return Err(format!(
+                "Access-Control-Allow-Headers does not include {}",
+                name.to_ascii_lowercase()
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn requirement_url(target: &str, requirement: &CorsRequirement) -> String {
+    format!(
+        "{}/xrpc/{}",
+        target.trim_end_matches('/'),
+        requirement.endpoint
+    )
+}
+
+fn probe(requirement: &CorsRequirement, outcome: CorsOutcome, detail: Option<String>) -> CorsProbe {
+    CorsProbe {
+        endpoint: requirement.endpoint.to_string(),
+        method: requirement.method.to_string(),
+        needs: requirement.needs.to_string(),
+        outcome,
+        detail,
+    }
+}
+
+/// In the browser: send the request itself and let the browser enforce CORS
+#[cfg(target_arch = "wasm32")]
+async fn probe_requirement(
+    client: &Client,
+    target: &str,
+    requirement: &CorsRequirement,
+    _origin: &str,
+) -> CorsProbe {
+    let url = requirement_url(target, requirement);
+    let method =
+        reqwest::Method::from_bytes(requirement.method.as_bytes()).unwrap_or(reqwest::Method::GET);
+    let mut request = client.request(method.clone(), &url).timeout(CORS_TIMEOUT);
+    for (name, value) in requirement.headers {
+        request = request.header(*name, *value);
+    }
+    if requirement.method == "POST" {
+        request = request.body(Vec::new());
+    }
+    let error = match request.send_traced().await {
+        Ok(_) => return probe(requirement, CorsOutcome::Allowed, None),
+        Err(e) => e.to_string(),
+    };
+
+    // An opaque no-cors answer proves the server is there and only CORS refused
+    let reachable = client
+        .request(method, &url)
+        .fetch_mode_no_cors()
+        .timeout(CORS_TIMEOUT)
+        .send()
+        .await
+        .is_ok();
+    let outcome = if reachable {
+        CorsOutcome::Blocked
+    } else {
+        CorsOutcome::Unreachable
+    };
+    probe(requirement, outcome, Some(error))
+}
+
+/// Outside the browser: send the preflight the browser would and read its headers
+#[cfg(not(target_arch = "wasm32"))]
+async fn probe_requirement(
+    client: &Client,
+    target: &str,
+    requirement: &CorsRequirement,
+    origin: &str,
+) -> CorsProbe {
+    let url = requirement_url(target, requirement);
+    let request = if requirement.headers.is_empty() {
+        client.get(&url)
+    } else {
+        let names: Vec<String> = requirement
+            .headers
+            .iter()
+            .map(|(name, _)| name.to_ascii_lowercase())
+            .collect();
+        client
+            .request(reqwest::Method::OPTIONS, &url)
+            .header("Access-Control-Request-Method", requirement.method)
+            .header("Access-Control-Request-Headers", names.join(","))
+    };
+    let response = match request
+        .header("Origin", origin)
+        .timeout(CORS_TIMEOUT)
+        .send_traced()
+        .await
+    {
+        Ok(response) => response,
+        Err(e) => return probe(requirement, CorsOutcome::Unreachable, Some(e.to_string())),
+    };
+    let headers: Vec<(String, String)> = response
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect();
+    match evaluate_cors_headers(requirement, origin, &headers) {
+        Ok(()) => probe(requirement, CorsOutcome::Allowed, None),
+        Err(problem) => probe(requirement, CorsOutcome::Blocked, Some(problem)),
+    }
+}
+
+/// Check the target for mixed content and every request shape in turn, and
+/// record the report
+pub async fn run_cors_preflight(target: &str, page_url: Option<&str>) -> CorsReport {
+    let target = target.trim().trim_end_matches('/').to_string();
+    let page_url = page_url.unwrap_or_default();
+    let mut report = CorsReport {
+        target: target.clone(),
+        mixed_content: mixed_content_problem(page_url, &target),
+        probes: Vec::new(),
+    };
+
+    if report.mixed_content.is_none() {
+        let origin = reqwest::Url::parse(page_url)
+            .map(|url| url.origin().ascii_serialization())
+            .unwrap_or_else(|_| "null".to_string());
+        let client = Client::new();
+        for requirement in CORS_REQUIREMENTS {
+            let probe = probe_requirement(&client, &target, requirement, &origin).await;
+            let unreachable = probe.outcome == CorsOutcome::Unreachable;
+            report.probes.push(probe);
+            // Nothing further to learn from a host that doesn't answer
+            if unreachable && report.probes.len() == 1 {
+                break;
+            }
+        }
+    }
+
+    for problem in report.problems() {
+        info!("[CORS] {}", problem);
+    }
+    if let Ok(mut last) = LAST_CORS_REPORT.lock() {
+        *last = Some(report.clone());
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_mixed_content_and_cors_header_evaluation() {
+        assert!(
+            mixed_content_problem("https://migrate.example/", "http://pds.example")
+                .unwrap()
+                .contains("mixed content")
+        );
+        assert_eq!(
+            mixed_content_problem("https://migrate.example/", "http://localhost:2583"),
+            None
+        );
+        assert_eq!(
+            mixed_content_problem("http://localhost:8080/", "http://pds.example"),
+            None
+        );
+        assert_eq!(
+            mixed_content_problem("https://migrate.example/", "https://pds.example"),
+            None
+        );
+
+        let origin = "https://migrate.example";
+        let [simple, authorized, json, ..] = CORS_REQUIREMENTS else {
+            unreachable!()
+        };
+        assert_eq!(
+            evaluate_cors_headers(simple, origin, &headers(&[])),
+            Err("no Access-Control-Allow-Origin header".to_string())
+        );
+        assert!(evaluate_cors_headers(
+            simple,
+            origin,
+            &headers(&[("Access-Control-Allow-Origin", "https://other.example")])
+        )
+        .unwrap_err()
+        .contains("not https://migrate.example"));
+        assert_eq!(
+            evaluate_cors_headers(
+                simple,
+                origin,
+                &headers(&[("access-control-allow-origin", "*")])
+            ),
+            Ok(())
+        );
+
+        // A wildcard covers content-type but never authorization
+        let wildcard = headers(&[
+            ("Access-Control-Allow-Origin", "*"),
+            ("Access-Control-Allow-Headers", "*"),
+        ]);
+        assert_eq!(evaluate_cors_headers(json, origin, &wildcard), Ok(()));
+        assert_eq!(
+            evaluate_cors_headers(authorized, origin, &wildcard),
+            Err("Access-Control-Allow-Headers does not include authorization".to_string())
+        );
+        let listed = headers(&[
+            ("Access-Control-Allow-Origin", origin),
+            (
+                "Access-Control-Allow-Headers",
+                "Content-Type, Authorization",
+            ),
+        ]);
+        assert_eq!(evaluate_cors_headers(authorized, origin, &listed), Ok(()));
+
+        let report = CorsReport {
+            target: "https://pds.example".to_string(),
+            mixed_content: None,
+            probes: vec![
+                probe(simple, CorsOutcome::Allowed, None),
+                probe(authorized, CorsOutcome::Blocked, None),
+            ],
+        };
+        assert_eq!(
+            report.problems(),
+            vec![
+                "GET com.atproto.server.getSession: needs Access-Control-Allow-Headers including authorization"
+                    .to_string()
+            ]
+        );
+        assert!(!report.is_clear());
+    }
+}
@@ -1,6 +1,5 @@
 use anyhow::Result;
 use async_trait::async_trait;
-use js_sys;
 use lru::LruCache;
 use reqwest::Client;
 use std::num::NonZeroUsize;
@@ -9,11 +8,40 @@ use std::time::Duration;
 use tracing::{error, info, warn};
 
 use super::errors::ResolveError;
+use super::http_trace::TracedSend;
 use super::types::{CachedDnsResponse, CloudflareDoHResponse};
 
 /// Get current time in milliseconds since UNIX epoch (WASM compatible)
 fn current_time_millis() -> u64 {
-    js_sys::Date::now() as u64
+    crate::utils::platform::now_ms() as u64
+}
+
+/// DoH providers in their default order; the first is the primary endpoint
+pub const DOH_PROVIDERS: &[&str] = &[
+    "https://mozilla.cloudflare-dns.com/dns-query",
+    "https://cloudflare-dns.com/dns-query",
+    // "https://dns.google/resolve",
+    "https://dns.quad9.net:5053/dns-query",
+];
+
+/// Provider order chosen by the latest preflight latency probe
+static PREFERRED_DOH_ORDER: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Make resolvers created from now on try providers in this order
+pub fn set_preferred_doh_order(order: Vec<String>) {
+    if let Ok(mut preferred) = PREFERRED_DOH_ORDER.lock() {
+        *preferred = order;
+    }
+}
+
+/// Providers in the preferred order if one was measured, otherwise the default
+fn doh_endpoints() -> Vec<String> {
+    PREFERRED_DOH_ORDER
+        .lock()
+        .ok()
+        .filter(|preferred| !preferred.is_empty())
+        .map(|preferred| preferred.clone())
+        .unwrap_or_else(|| DOH_PROVIDERS.iter().map(|url| url.to_string()).collect())
 }
 
 /// DNS resolver trait for handle resolution
@@ -35,6 +63,7 @@ pub struct DnsOverHttpsResolver {
 impl DnsOverHttpsResolver {
     /// Create a new DNS-over-HTTPS resolver
     pub fn new() -> Self {
+        let mut endpoints = doh_endpoints();
         Self {
             http_client: {
                 Client::builder()
@@ -42,12 +71,8 @@ impl DnsOverHttpsResolver {
                     .build()
                     .expect("Failed to create HTTP client")
             },
-            primary_endpoint: "https://mozilla.cloudflare-dns.com/dns-query".to_string(),
-            fallback_endpoints: vec![
-                "https://cloudflare-dns.com/dns-query".to_string(),
-                // "https://dns.google/resolve".to_string(),
-                "https://dns.quad9.net:5053/dns-query".to_string(),
-            ],
+            primary_endpoint: endpoints.remove(0),
+            fallback_endpoints: endpoints,
             cache: Arc::new(Mutex::new(LruCache::new(NonZeroUsize::new(1000).unwrap()))),
             timeout: Duration::from_secs(5),
         }
@@ -84,7 +109,7 @@ impl DnsOverHttpsResolver {
             .get(&url)
             .header("accept", "application/dns-json")
             .timeout(self.timeout)
-            .send()
+            .send_traced()
             .await
             .map_err(|e| ResolveError::HttpRequestFailed {
                 error: format!("HTTP request failed: {}", e),
@@ -139,7 +139,8 @@ impl RateLimitInfo {
     /// Calculate seconds until rate limit resets
     pub fn retry_after_seconds(&self) -> Option<u64> {
         if let Some(reset) = self.reset {
-            let now = super::types::current_time_secs();
+            // Reset is in the server's clock
+            let now = super::clock_skew::server_now_secs();
             if reset > now {
                 Some(reset - now)
             } else {
@@ -187,6 +188,17 @@ pub enum ClientError {
     InvalidResponse { expected: String, got: String },
 }
 
+impl ClientError {
+    /// Whether the request may succeed if sent again: the connection failed
+    /// or the server had a 5xx error. Anything else is the server's answer.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            ClientError::NetworkError { .. } | ClientError::ServerError { .. }
+        )
+    }
+}
+
 impl fmt::Display for ClientError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -0,0 +1,340 @@
+//! Per-host request statistics for the current migration
+//!
+//! Every request the transport or the traced reqwest client makes is counted
+//! against its host: successes, failures, 429s and recent latencies. The
+//! numbers are mirrored to `sessionStorage` so a reload mid-migration keeps
+//! them, and they are cleared with the migration sessions.
+//!
+//! A host that keeps rate-limiting or failing is treated more gently: the
+//! suggested concurrency drops and its retry budget waits longer between
+//! attempts. Hosts with fewer than [`MIN_SAMPLES`] requests are left alone.
+//!
+//! Blob uploads go further and tune their parallelism per host with
+//! [`AimdConcurrency`]: starting low, one more upload in flight after every
+//! window's worth of uploads with few 429s and 5xx answers, half as many after
+//! one with more. The width it settles on is kept with the host's statistics,
+//! so later steps of the same migration start from it.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+use tracing::debug;
+
+use crate::services::config::{RetryBudget, RetryConfig, RetryPhase};
+use crate::utils::platform::{storage_delete, storage_get, storage_set, StorageArea};
+
+/// Session storage key for the mirrored statistics
+const HOST_STATS_STORAGE_KEY: &str = "host_stats";
+
+/// Requests seen before a host's statistics change anything
+pub const MIN_SAMPLES: u32 = 10;
+
+/// Latencies kept per host for the median
+const LATENCY_WINDOW: usize = 64;
+
+/// Successful requests between writes to session storage
+const PERSIST_EVERY: u32 = 10;
+
+static HOST_STATS: Mutex<Option<BTreeMap<String, HostStats>>> = Mutex::new(None);
+
+/// What one host has answered so far
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HostStats {
+    pub requests: u32,
+    /// Network errors and 5xx responses
+    pub failures: u32,
+    pub rate_limited: u32,
+    /// Most recent latencies, oldest first
+    latencies_ms: VecDeque<u64>,
+    /// Parallel uploads upload tuning last settled on for this host
+    #[serde(default)]
+    pub tuned_concurrency: Option<usize>,
+}
+
+impl HostStats {
+    /// Count one request; `status` is `None` when no response arrived
+    pub fn record(&mut self, status: Option<u16>, latency_ms: u64) {
+        self.requests += 1;
+        match status {
+            Some(429) => self.rate_limited += 1,
+            Some(status) if status < 500 => {}
+            _ => self.failures += 1,
+        }
+        self.latencies_ms.push_back(latency_ms);
+        while self.latencies_ms.len() > LATENCY_WINDOW {
+            self.latencies_ms.pop_front();
+        }
+    }
+
+    /// Share of requests that neither failed nor were rate-limited
+    pub fn success_rate(&self) -> f64 {
+        if self.requests == 0 {
+            return 1.0;
+        }
+        1.0 - (self.failures + self.rate_limited) as f64 / self.requests as f64
+    }
+
+    /// Requests that were rate-limited, failed or got a 5xx
+    pub fn errors(&self) -> u32 {
+        self.failures + self.rate_limited
+    }
+
+    pub fn rate_limited_share(&self) -> f64 {
+        if self.requests == 0 {
+            return 0.0;
+        }
+        self.rate_limited as f64 / self.requests as f64
+    }
+
+    pub fn median_latency_ms(&self) -> Option<u64> {
+        let mut sorted: Vec<u64> = self.latencies_ms.iter().copied().collect();
+        sorted.sort_unstable();
+        sorted.get(sorted.len() / 2).copied()
+    }
+
+    fn has_enough_samples(&self) -> bool {
+        self.requests >= MIN_SAMPLES
+    }
+
+    /// Parallel transfers to keep in flight against this host
+    pub fn adapted_concurrency(&self, configured: usize) -> usize {
+        let configured = configured.max(1);
+        if !self.has_enough_samples() {
+            return configured;
+        }
+        if self.rate_limited_share() >= 0.05 || self.success_rate() < 0.8 {
+            (configured / 2).max(1)
+        } else if self.median_latency_ms().is_some_and(|ms| ms > 5_000) {
+            (configured - 1).max(1)
+        } else {
+            configured
+        }
+    }
+
+    /// The retry budget for this host: longer waits after 429s and slow
+    /// answers, one more attempt when it fails often
+    pub fn adapted_retry_budget(&self, budget: RetryBudget) -> RetryBudget {
+        if !self.has_enough_samples() {
+            return budget;
+        }
+        let mut adapted = budget;
+        if self.rate_limited_share() >= 0.05 {
+            adapted.base_delay_ms = budget.base_delay_ms.saturating_mul(4);
+            adapted.max_delay_ms = budget.max_delay_ms.saturating_mul(2);
+        } else if self.median_latency_ms().is_some_and(|ms| ms > 5_000) {
+            adapted.base_delay_ms = budget.base_delay_ms.saturating_mul(2);
+        }
+        if self.success_rate() < 0.8 && budget.max_retries > 0 {
+            adapted.max_retries = budget.max_retries + 1;
+        }
+        adapted
+    }
+}
+
+/// Host part of a URL, or the input when it has none
+pub fn host_of(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| url.to_string())
+}
+
+/// Run `f` against the statistics, loading them from session storage on first use
+fn with_stats<R>(f: impl FnOnce(&mut BTreeMap<String, HostStats>) -> R) -> Option<R> {
+    let mut guard = HOST_STATS.lock().ok()?;
+    let stats = guard.get_or_insert_with(|| {
+        storage_get(StorageArea::Session, HOST_STATS_STORAGE_KEY).unwrap_or_default()
+    });
+    Some(f(stats))
+}
+
+/// Count a request to `host`
+pub fn record_host_request(host: &str, status: Option<u16>, latency_ms: u64) {
+    if host.is_empty() {
+        return;
+    }
+    with_stats(|stats| {
+        let entry = stats.entry(host.to_string()).or_default();
+        entry.record(status, latency_ms);
+        // Trouble is worth keeping across a reload right away, successes in batches
+        let notable = status.is_none_or(|status| status == 429 || status >= 500);
+        if notable || entry.requests % PERSIST_EVERY == 0 {
+            if let Err(e) = storage_set(StorageArea::Session, HOST_STATS_STORAGE_KEY, &*stats) {
+                debug!("Failed to persist host statistics: {:?}", e);
+            }
+        }
+    });
+}
+
+/// Statistics of one host, if it has been contacted
+pub fn stats_for_host(host: &str) -> Option<HostStats> {
+    with_stats(|stats| stats.get(host).cloned()).flatten()
+}
+
+/// Every contacted host with its statistics, sorted by host
+pub fn host_stats() -> Vec<(String, HostStats)> {
+    with_stats(|stats| {
+        stats
+            .iter()
+            .map(|(host, stats)| (host.clone(), stats.clone()))
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// Forget all statistics, in memory and in session storage
+pub fn clear_host_stats() {
+    if let Ok(mut guard) = HOST_STATS.lock() {
+        *guard = Some(BTreeMap::new());
+    }
+    storage_delete(StorageArea::Session, HOST_STATS_STORAGE_KEY);
+}
+
+/// Concurrency for transfers against `host`, preferring what upload tuning
+/// learned about it
+pub fn host_concurrency(host: &str, configured: usize) -> usize {
+    stats_for_host(host).map_or(configured.max(1), |stats| match stats.tuned_concurrency {
+        Some(tuned) => tuned.clamp(1, configured.max(1)),
+        None => stats.adapted_concurrency(configured),
+    })
+}
+
+/// Additive-increase, multiplicative-decrease tuning of parallel uploads
+#[derive(Debug, Clone, PartialEq)]
+pub struct AimdConcurrency {
+    current: usize,
+    max: usize,
+    /// Share of errors in a round above which the width is halved
+    error_threshold: f64,
+}
+
+impl AimdConcurrency {
+    pub fn new(start: usize, max: usize, error_threshold: f64) -> Self {
+        let max = max.max(1);
+        Self {
+            current: start.clamp(1, max),
+            max,
+            error_threshold,
+        }
+    }
+
+    /// Uploads to keep in flight at once
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Adjust to a round of `requests` of which `errors` were 429s, 5xx or
+    /// network failures; rounds without requests change nothing
+    pub fn observe(&mut self, requests: u32, errors: u32) -> usize {
+        if requests == 0 {
+            return self.current;
+        }
+        if errors as f64 / requests as f64 > self.error_threshold {
+            self.current = (self.current / 2).max(1);
+        } else if self.current < self.max {
+            self.current += 1;
+        }
+        self.current
+    }
+}
+
+/// Upload tuning for `host`, starting where an earlier step of this migration
+/// left it, or at `initial`
+pub fn upload_tuning(
+    host: &str,
+    initial: usize,
+    max: usize,
+    error_threshold: f64,
+) -> AimdConcurrency {
+    let start = stats_for_host(host)
+        .and_then(|stats| stats.tuned_concurrency)
+        .unwrap_or(initial);
+    AimdConcurrency::new(start, max, error_threshold)
+}
+
+/// Keep the upload width tuning settled on for `host`
+pub fn remember_tuned_concurrency(host: &str, concurrency: usize) {
+    if host.is_empty() {
+        return;
+    }
+    with_stats(|stats| {
+        stats.entry(host.to_string()).or_default().tuned_concurrency = Some(concurrency);
+        if let Err(e) = storage_set(StorageArea::Session, HOST_STATS_STORAGE_KEY, &*stats) {
+            debug!("Failed to persist host statistics: {:?}", e);
+        }
+    });
+}
+
+/// `config` with the phase's budget adapted to what `host` has answered so far
+pub fn host_retry_config(config: &RetryConfig, phase: RetryPhase, host: &str) -> RetryConfig {
+    match stats_for_host(host) {
+        Some(stats) => config
+            .clone()
+            .with_budget(phase, stats.adapted_retry_budget(config.budget(phase))),
+        None => config.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_stats_adapt_to_rate_limits() {
+        let budget = RetryBudget::new(3, 1_000, 30_000);
+        let mut healthy = HostStats::default();
+        for latency in 1..=20 {
+            healthy.record(Some(200), latency * 10);
+        }
+        assert_eq!(healthy.success_rate(), 1.0);
+        assert_eq!(healthy.median_latency_ms(), Some(110));
+        assert_eq!(healthy.adapted_concurrency(6), 6);
+        assert_eq!(healthy.adapted_retry_budget(budget), budget);
+
+        let mut limited = healthy.clone();
+        for _ in 0..3 {
+            limited.record(Some(429), 50);
+        }
+        limited.record(None, 50);
+        assert_eq!(limited.requests, 24);
+        assert_eq!(limited.adapted_concurrency(6), 3);
+        assert_eq!(
+            limited.adapted_retry_budget(budget),
+            RetryBudget::new(3, 4_000, 60_000)
+        );
+
+        let mut new_host = HostStats::default();
+        new_host.record(Some(503), 50);
+        assert_eq!(new_host.adapted_concurrency(6), 6);
+
+        record_host_request("pds.stats.test", Some(200), 40);
+        record_host_request("pds.stats.test", Some(429), 60);
+        let recorded = stats_for_host("pds.stats.test").unwrap();
+        assert_eq!((recorded.requests, recorded.rate_limited), (2, 1));
+        assert_eq!(
+            host_of("https://pds.stats.test/xrpc/x?y=1"),
+            "pds.stats.test"
+        );
+    }
+
+    #[test]
+    fn test_upload_concurrency_ramps_up_and_backs_off() {
+        let mut tuning = AimdConcurrency::new(2, 6, 0.05);
+        assert_eq!(tuning.observe(20, 0), 3);
+        assert_eq!(tuning.observe(20, 1), 4);
+        assert_eq!(tuning.observe(0, 0), 4);
+        assert_eq!(tuning.observe(20, 4), 2);
+        assert_eq!(tuning.observe(20, 20), 1);
+        assert_eq!(tuning.observe(20, 20), 1);
+        for _ in 0..10 {
+            tuning.observe(20, 0);
+        }
+        assert_eq!(tuning.current(), 6);
+        assert_eq!(AimdConcurrency::new(0, 0, 0.05).current(), 1);
+
+        remember_tuned_concurrency("pds.tuning.test", 4);
+        assert_eq!(upload_tuning("pds.tuning.test", 2, 8, 0.05).current(), 4);
+        assert_eq!(upload_tuning("pds.untuned.test", 2, 8, 0.05).current(), 2);
+        assert_eq!(host_concurrency("pds.tuning.test", 3), 3);
+    }
+}
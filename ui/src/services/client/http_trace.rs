@@ -0,0 +1,150 @@
+//! Ring buffer of recent HTTP requests for support diagnostics
+//!
+//! Only the method, a redacted URL, the status and any transport error are kept;
+//! headers and bodies are never recorded.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::Mutex;
+
+use super::clock_skew::observe_date_header;
+use super::host_stats::record_host_request;
+use super::types::current_time_secs;
+use crate::utils::platform::now_ms;
+
+/// Maximum number of traces retained
+pub const MAX_HTTP_TRACES: usize = 200;
+
+/// Query parameters whose values are replaced before a URL is recorded
+const REDACTED_QUERY_KEYS: &[&str] = &["token", "code", "password", "jwt", "secret"];
+
+static HTTP_TRACES: Mutex<VecDeque<HttpTrace>> = Mutex::new(VecDeque::new());
+
+/// A single recorded HTTP request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HttpTrace {
+    pub timestamp_secs: u64,
+    pub method: String,
+    pub url: String,
+    pub status: Option<u16>,
+    pub error: Option<String>,
+}
+
+/// Record a request outcome in the global trace buffer
+pub fn record_http_trace(method: &str, url: &str, status: Option<u16>, error: Option<String>) {
+    let trace = HttpTrace {
+        timestamp_secs: current_time_secs(),
+        method: method.to_string(),
+        url: redact_url(url),
+        status,
+        error,
+    };
+
+    if let Ok(mut traces) = HTTP_TRACES.lock() {
+        traces.push_back(trace);
+        while traces.len() > MAX_HTTP_TRACES {
+            traces.pop_front();
+        }
+    }
+}
+
+/// Snapshot of the recorded traces, oldest first
+pub fn recent_http_traces() -> Vec<HttpTrace> {
+    HTTP_TRACES
+        .lock()
+        .map(|traces| traces.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Replace the values of sensitive query parameters with `[redacted]`
+pub fn redact_url(url: &str) -> String {
+    let Some((base, query)) = url.split_once('?') else {
+        return url.to_string();
+    };
+
+    let query = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((key, _))
+                if REDACTED_QUERY_KEYS
+                    .iter()
+                    .any(|sensitive| key.to_ascii_lowercase().contains(sensitive)) =>
+            {
+                format!("{}=[redacted]", key)
+            }
+            _ => pair.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+
+    format!("{}?{}", base, query)
+}
+
+/// `send()` variant that records the request in the trace buffer
+pub trait TracedSend {
+    fn send_traced(self) -> impl Future<Output = reqwest::Result<reqwest::Response>>;
+}
+
+impl TracedSend for reqwest::RequestBuilder {
+    async fn send_traced(self) -> reqwest::Result<reqwest::Response> {
+        let (client, request) = self.build_split();
+        let request = request?;
+        let method = request.method().to_string();
+        let url = request.url().to_string();
+        let host = request.url().host_str().unwrap_or_default().to_string();
+
+        let started_ms = now_ms();
+        let result = client.execute(request).await;
+        let latency_ms = (now_ms() - started_ms).max(0.0) as u64;
+        record_host_request(
+            &host,
+            result.as_ref().ok().map(|r| r.status().as_u16()),
+            latency_ms,
+        );
+        match &result {
+            Ok(response) => {
+                record_http_trace(&method, &url, Some(response.status().as_u16()), None);
+                if let Some(date) = response
+                    .headers()
+                    .get(reqwest::header::DATE)
+                    .and_then(|date| date.to_str().ok())
+                {
+                    observe_date_header(date, response.url().host_str().unwrap_or_default());
+                }
+            }
+            Err(e) => record_http_trace(&method, &url, None, Some(e.to_string())),
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_url() {
+        assert_eq!(
+            redact_url("https://pds.test/xrpc/com.atproto.sync.getBlob?did=did:plc:abc&cid=bafy"),
+            "https://pds.test/xrpc/com.atproto.sync.getBlob?did=did:plc:abc&cid=bafy"
+        );
+        assert_eq!(
+            redact_url("https://pds.test/callback?state=1&code=secret&access_token=abc"),
+            "https://pds.test/callback?state=1&code=[redacted]&access_token=[redacted]"
+        );
+        assert_eq!(redact_url("https://pds.test/"), "https://pds.test/");
+    }
+
+    #[test]
+    fn test_record_http_trace_is_bounded() {
+        for i in 0..(MAX_HTTP_TRACES + 5) {
+            record_http_trace("GET", &format!("https://pds.test/{}", i), Some(200), None);
+        }
+
+        let traces = recent_http_traces();
+        assert_eq!(traces.len(), MAX_HTTP_TRACES);
+        let last_url = format!("https://pds.test/{}", MAX_HTTP_TRACES + 4);
+        assert!(traces.iter().any(|t| t.url == last_url));
+    }
+}
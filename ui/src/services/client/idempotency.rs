@@ -0,0 +1,80 @@
+//! Idempotency keys for requests that must not take effect twice
+//!
+//! Creating the account and submitting the PLC operation are retried when the
+//! network fails, but a lost response doesn't mean the server didn't act.
+//! Each request gets a key derived from what it changes, so every retry of the
+//! same change (including one after a page reload) carries the same key.
+//!
+//! Nothing in atproto deduplicates on such a key, and the lexicons have no
+//! field for one, so request bodies are left as the lexicons define them. A
+//! deployment whose target (or a proxy in front of it) honors an
+//! `Idempotency-Key` header can turn it on with
+//! [`RetryConfig::idempotency_header`]; otherwise retries rely on the server's
+//! own answers, such as `AlreadyExists` for an account created by a request
+//! whose response was lost.
+//!
+//! [`RetryConfig::idempotency_header`]: crate::services::config::RetryConfig
+
+use crate::services::config::get_global_config;
+use crate::services::transport::HttpRequest;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+fn fnv1a(seed: u64, parts: &[&str]) -> u64 {
+    parts.iter().fold(seed, |hash, part| {
+        // A separator keeps ["ab", "c"] and ["a", "bc"] apart
+        part.bytes()
+            .chain(std::iter::once(0))
+            .fold(hash, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+            })
+    })
+}
+
+/// Stable key for one logical change, e.g. `createAccount` of a DID on a PDS
+pub fn idempotency_key(operation: &str, parts: &[&str]) -> String {
+    let mut all = Vec::with_capacity(parts.len() + 1);
+    all.push(operation);
+    all.extend_from_slice(parts);
+    format!(
+        "{}-{:016x}{:016x}",
+        operation,
+        fnv1a(FNV_OFFSET, &all),
+        fnv1a(!FNV_OFFSET, &all)
+    )
+}
+
+/// Add the key as a header if the host enabled it
+pub fn add_idempotency_header(request: &mut HttpRequest, key: &str) {
+    if get_global_config().retry.idempotency_header {
+        request.set_header(IDEMPOTENCY_KEY_HEADER, key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idempotency_keys_are_stable_per_change() {
+        let key = idempotency_key("createAccount", &["did:plc:abc", "https://new.example"]);
+        assert_eq!(
+            key,
+            idempotency_key("createAccount", &["did:plc:abc", "https://new.example"])
+        );
+        assert!(key.starts_with("createAccount-"));
+        assert_eq!(key.len(), "createAccount-".len() + 32);
+
+        assert_ne!(
+            key,
+            idempotency_key("createAccount", &["did:plc:abc", "https://other.example"])
+        );
+        assert_ne!(
+            idempotency_key("submitPlcOperation", &["ab", "c"]),
+            idempotency_key("submitPlcOperation", &["a", "bc"])
+        );
+    }
+}
@@ -0,0 +1,212 @@
+//! Session-scoped cache of handle → DID and DID → document resolutions
+//!
+//! Form validation, provider detection, login and the migration steps all
+//! re-resolve the same identity. Successful lookups are kept in memory and
+//! mirrored to `sessionStorage` so a page reload in the same tab reuses them.
+//! Entries expire after [`IDENTITY_CACHE_TTL_SECS`] and are dropped explicitly
+//! once the PLC operation moves the DID to its new PDS.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tracing::debug;
+
+use super::types::{current_time_secs, DidDocument};
+use crate::utils::platform::{storage_delete, storage_get, storage_set, StorageArea};
+
+/// Session storage key for the mirrored cache
+const IDENTITY_CACHE_STORAGE_KEY: &str = "identity_cache";
+
+/// How long a resolved identity is trusted before it is resolved again
+pub const IDENTITY_CACHE_TTL_SECS: u64 = 10 * 60;
+
+static IDENTITY_CACHE: Mutex<Option<IdentityCache>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedEntry<T> {
+    value: T,
+    cached_at_secs: u64,
+}
+
+impl<T> CachedEntry<T> {
+    fn is_fresh(&self, now_secs: u64) -> bool {
+        now_secs.saturating_sub(self.cached_at_secs) < IDENTITY_CACHE_TTL_SECS
+    }
+}
+
+/// Resolved identities keyed by lowercase handle and by DID
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IdentityCache {
+    handles: HashMap<String, CachedEntry<String>>,
+    documents: HashMap<String, CachedEntry<DidDocument>>,
+}
+
+impl IdentityCache {
+    fn handle_key(handle: &str) -> String {
+        handle.trim().trim_start_matches('@').to_ascii_lowercase()
+    }
+
+    pub fn did_for_handle(&self, handle: &str, now_secs: u64) -> Option<String> {
+        self.handles
+            .get(&Self::handle_key(handle))
+            .filter(|entry| entry.is_fresh(now_secs))
+            .map(|entry| entry.value.clone())
+    }
+
+    pub fn document_for_did(&self, did: &str, now_secs: u64) -> Option<DidDocument> {
+        self.documents
+            .get(did)
+            .filter(|entry| entry.is_fresh(now_secs))
+            .map(|entry| entry.value.clone())
+    }
+
+    pub fn insert_handle(&mut self, handle: &str, did: &str, now_secs: u64) {
+        self.handles.insert(
+            Self::handle_key(handle),
+            CachedEntry {
+                value: did.to_string(),
+                cached_at_secs: now_secs,
+            },
+        );
+    }
+
+    pub fn insert_document(&mut self, did: &str, document: DidDocument, now_secs: u64) {
+        self.documents.insert(
+            did.to_string(),
+            CachedEntry {
+                value: document,
+                cached_at_secs: now_secs,
+            },
+        );
+    }
+
+    /// Drop a DID's document and every handle that resolved to it
+    pub fn invalidate_did(&mut self, did: &str) {
+        self.documents.remove(did);
+        self.handles.retain(|_, entry| entry.value != did);
+    }
+}
+
+/// Run `f` against the process-wide cache, loading it from session storage on first use
+fn with_cache<R>(f: impl FnOnce(&mut IdentityCache) -> R) -> Option<R> {
+    let mut guard = IDENTITY_CACHE.lock().ok()?;
+    let cache = guard.get_or_insert_with(|| {
+        storage_get(StorageArea::Session, IDENTITY_CACHE_STORAGE_KEY).unwrap_or_default()
+    });
+    Some(f(cache))
+}
+
+/// Run `f` against the cache and mirror the result to session storage
+fn update_cache(f: impl FnOnce(&mut IdentityCache)) {
+    with_cache(|cache| {
+        f(cache);
+        if let Err(e) = storage_set(StorageArea::Session, IDENTITY_CACHE_STORAGE_KEY, &*cache) {
+            debug!("Failed to persist identity cache: {:?}", e);
+        }
+    });
+}
+
+/// Cached DID for a handle, if it was resolved recently
+pub fn cached_did_for_handle(handle: &str) -> Option<String> {
+    with_cache(|cache| cache.did_for_handle(handle, current_time_secs())).flatten()
+}
+
+/// Cached DID document, if it was resolved recently
+pub fn cached_did_document(did: &str) -> Option<DidDocument> {
+    with_cache(|cache| cache.document_for_did(did, current_time_secs())).flatten()
+}
+
+pub fn cache_handle_resolution(handle: &str, did: &str) {
+    update_cache(|cache| cache.insert_handle(handle, did, current_time_secs()));
+}
+
+pub fn cache_did_document(did: &str, document: &DidDocument) {
+    update_cache(|cache| cache.insert_document(did, document.clone(), current_time_secs()));
+}
+
+/// Forget everything cached about a DID, e.g. after its PLC operation is submitted
+pub fn invalidate_identity(did: &str) {
+    debug!("Invalidating cached identity for {}", did);
+    update_cache(|cache| cache.invalidate_did(did));
+}
+
+/// Drop the whole cache, in memory and in session storage
+pub fn clear_identity_cache() {
+    if let Ok(mut guard) = IDENTITY_CACHE.lock() {
+        *guard = Some(IdentityCache::default());
+    }
+    storage_delete(StorageArea::Session, IDENTITY_CACHE_STORAGE_KEY);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::client::types::DidService;
+
+    fn document(did: &str, endpoint: &str) -> DidDocument {
+        DidDocument {
+            id: did.to_string(),
+            service: vec![DidService {
+                id: "#atproto_pds".to_string(),
+                service_type: "AtprotoPersonalDataServer".to_string(),
+                service_endpoint: endpoint.to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_lookup_normalizes_handles_and_expires() {
+        let mut cache = IdentityCache::default();
+        cache.insert_handle("@Alice.Example.com", "did:plc:alice", 1_000);
+
+        assert_eq!(
+            cache.did_for_handle("alice.example.com", 1_000),
+            Some("did:plc:alice".to_string())
+        );
+        assert_eq!(
+            cache.did_for_handle("alice.example.com", 1_000 + IDENTITY_CACHE_TTL_SECS),
+            None
+        );
+    }
+
+    #[test]
+    fn test_invalidate_did_drops_document_and_handles() {
+        let mut cache = IdentityCache::default();
+        cache.insert_handle("alice.example.com", "did:plc:alice", 0);
+        cache.insert_handle("bob.example.com", "did:plc:bob", 0);
+        cache.insert_document(
+            "did:plc:alice",
+            document("did:plc:alice", "https://old.pds"),
+            0,
+        );
+
+        cache.invalidate_did("did:plc:alice");
+
+        assert_eq!(cache.did_for_handle("alice.example.com", 0), None);
+        assert_eq!(
+            cache
+                .document_for_did("did:plc:alice", 0)
+                .map(|doc| doc.pds_endpoints()),
+            None
+        );
+        assert_eq!(
+            cache.did_for_handle("bob.example.com", 0),
+            Some("did:plc:bob".to_string())
+        );
+    }
+
+    #[test]
+    fn test_cache_round_trips_through_session_storage() {
+        let did = "did:plc:roundtrip";
+        cache_did_document(did, &document(did, "https://pds.example.com"));
+
+        // Simulate a reload: the in-memory copy is gone but session storage remains
+        *IDENTITY_CACHE.lock().unwrap() = None;
+
+        let restored = cached_did_document(did).expect("document restored from storage");
+        assert_eq!(restored.pds_endpoints(), vec!["https://pds.example.com"]);
+
+        invalidate_identity(did);
+        assert!(cached_did_document(did).is_none());
+    }
+}
@@ -1,21 +1,26 @@
 use anyhow::Result;
+use futures::future::{FutureExt, LocalBoxFuture};
+use futures::stream::{FuturesUnordered, StreamExt};
 use reqwest::Client;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
+use std::sync::Mutex;
 use tracing::{debug, info, instrument, warn};
 
 use super::dns_over_https::{DnsOverHttpsResolver, DnsResolver};
 use super::errors::ResolveError;
+use super::http_trace::TracedSend;
+use super::identity_cache::{
+    cache_did_document, cache_handle_resolution, cached_did_document, cached_did_for_handle,
+};
 use super::types::{ClientPdsProvider, DidDocument};
+use crate::utils::handle::{is_valid_handle_syntax, normalize_handle, normalize_identifier};
+use crate::utils::platform::{has_browser_window, now_ms};
 
 /// Check if a handle is potentially valid and worth resolving
 fn should_resolve_handle(handle: &str) -> bool {
-    // Basic validation to prevent unnecessary network calls
-    handle.len() > 6 &&  // Minimum viable handle length (e.g., "a.b.co")
-    handle.contains('.') &&
-    handle.chars().last().is_some_and(|c| c.is_alphabetic()) &&
-    !handle.ends_with('.') &&  // Don't resolve incomplete handles like "torrho."
-    handle.split('.').count() >= 2 &&  // Must have at least domain.tld
-    !handle.contains(' ') // No spaces allowed
+    // Prevents network calls for incomplete input like "torrho." or "alice"
+    is_valid_handle_syntax(handle)
 }
 
 /// Resolve handle to DID using DNS-over-HTTPS
@@ -73,7 +78,7 @@ pub async fn resolve_handle_slingshot(
         .get(&slingshot_url)
         .header("Accept", "application/json")
         .timeout(std::time::Duration::from_secs(10))
-        .send()
+        .send_traced()
         .await
         .map_err(|e| ResolveError::HttpRequestFailed {
             error: format!("Failed to fetch from Slingshot: {}", e),
@@ -127,7 +132,7 @@ pub async fn resolve_handle_http(
         .timeout(std::time::Duration::from_secs(10));
 
     // Add Origin header if in browser context
-    let request = if let Some(window) = web_sys::window() {
+    let request = if let Some(window) = has_browser_window().then(web_sys::window).flatten() {
         match window.location().origin() {
             Ok(origin) => request.header("Origin", &origin),
             Err(e) => {
@@ -141,7 +146,7 @@ pub async fn resolve_handle_http(
         request
     };
 
-    let response = request.send().await.map_err(|e| {
+    let response = request.send_traced().await.map_err(|e| {
         // Try to detect SSL-related errors in the error message
         if e.to_string().contains("SSL") || e.to_string().contains("TLS") {
             ResolveError::SslProtocolError {
@@ -179,107 +184,178 @@ pub async fn resolve_handle_http(
     }
 }
 
-/// Parallel DNS + Slingshot resolution with validation
+/// Handle resolution method that produced a DID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HandleResolutionMethod {
+    DnsTxt,
+    WellKnown,
+    Slingshot,
+}
+
+impl HandleResolutionMethod {
+    pub fn label(&self) -> &'static str {
+        match self {
+            HandleResolutionMethod::DnsTxt => "DNS TXT",
+            HandleResolutionMethod::WellKnown => "HTTP .well-known",
+            HandleResolutionMethod::Slingshot => "Slingshot",
+        }
+    }
+}
+
+/// Outcome of a raced handle resolution, kept for diagnostics
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HandleResolution {
+    pub handle: String,
+    pub did: String,
+    pub method: HandleResolutionMethod,
+    pub elapsed_ms: u64,
+}
+
+static LAST_HANDLE_RESOLUTION: Mutex<Option<HandleResolution>> = Mutex::new(None);
+
+/// Most recent successful handle resolution, if any
+pub fn last_handle_resolution() -> Option<HandleResolution> {
+    LAST_HANDLE_RESOLUTION
+        .lock()
+        .ok()
+        .and_then(|last| last.clone())
+}
+
+fn record_handle_resolution(resolution: &HandleResolution) {
+    if let Ok(mut last) = LAST_HANDLE_RESOLUTION.lock() {
+        *last = Some(resolution.clone());
+    }
+}
+
+/// CORS failures are expected for domains that don't serve .well-known cross-origin
+fn is_cors_failure(error: &ResolveError) -> bool {
+    let error_msg = error.to_string();
+    error_msg.contains("CORS")
+        || error_msg.contains("Cross-Origin")
+        || error_msg.contains("error sending request")
+        || error_msg.contains("Failed to fetch")
+}
+
+/// Take the first successful result from a set of racing resolution futures
+async fn first_successful_resolution(
+    attempts: Vec<LocalBoxFuture<'_, (HandleResolutionMethod, Result<String, ResolveError>)>>,
+) -> Result<(HandleResolutionMethod, String), Vec<(HandleResolutionMethod, ResolveError)>> {
+    let mut pending: FuturesUnordered<_> = attempts.into_iter().collect();
+    let mut failures = Vec::new();
+
+    while let Some((method, result)) = pending.next().await {
+        match result {
+            Ok(did) => return Ok((method, did)),
+            Err(e) => failures.push((method, e)),
+        }
+    }
+
+    Err(failures)
+}
+
+/// Race DNS, .well-known and Slingshot resolution and return the first valid DID
 #[instrument(skip(doh_resolver, http_client))]
-pub async fn resolve_handle_client_side(
+pub async fn resolve_handle_racing(
     handle: &str,
     doh_resolver: &dyn DnsResolver,
     http_client: &Client,
-) -> Result<String, ResolveError> {
-    // Validate handle before making network calls
-    if !should_resolve_handle(handle) {
-        return Err(ResolveError::InvalidHandle {
-            handle: handle.to_string(),
-        });
-    }
-
-    info!("Starting parallel handle resolution for {}", handle);
-
-    let (dns_result, slingshot_result) = futures::join!(
-        resolve_handle_dns_doh(doh_resolver, handle),
-        // resolve_handle_http(http_client, handle),
-        resolve_handle_slingshot(http_client, handle)
-    );
+) -> Result<HandleResolution, ResolveError> {
+    // Validate handle before making network calls; IDN input is looked up in punycode
+    let handle = match normalize_handle(handle) {
+        Ok(normalized) => normalized,
+        Err(_) => {
+            return Err(ResolveError::InvalidHandle {
+                handle: handle.to_string(),
+            })
+        }
+    };
+    let handle = handle.as_str();
 
-    // Collect successful results
-    let mut results: Vec<String> = Vec::new();
+    info!("Racing handle resolution methods for {}", handle);
+    let started_ms = now_ms();
 
-    match &dns_result {
-        Ok(did) => {
-            info!("DNS resolution succeeded for {}: {}", handle, did);
-            results.push(did.clone());
+    let attempts = vec![
+        async {
+            (
+                HandleResolutionMethod::DnsTxt,
+                resolve_handle_dns_doh(doh_resolver, handle).await,
+            )
+        }
+        .boxed_local(),
+        async {
+            (
+                HandleResolutionMethod::WellKnown,
+                resolve_handle_http(http_client, handle).await,
+            )
+        }
+        .boxed_local(),
+        async {
+            (
+                HandleResolutionMethod::Slingshot,
+                resolve_handle_slingshot(http_client, handle).await,
+            )
         }
-        Err(e) => warn!("DNS resolution failed for {}: {}", handle, e),
-    }
-
-    // match &http_result {
-    //     Ok(did) => {
-    //         info!("HTTP resolution succeeded for {}: {}", handle, did);
-    //         results.push(did.clone());
-    //     }
-    //     Err(e) => {
-    //         // Check if this is a CORS error (common and expected for many domains)
-    //         let error_msg = format!("{}", e);
-    //         if error_msg.contains("CORS")
-    //             || error_msg.contains("Cross-Origin")
-    //             || error_msg.contains("error sending request")
-    //             || error_msg.contains("Failed to fetch")
-    //         {
-    //             // CORS errors are expected for domains that don't configure CORS for .well-known
-    //             debug!(
-    //                 "HTTP resolution failed for {} due to CORS/network restriction (expected): {}",
-    //                 handle, e
-    //             );
-    //         } else {
-    //             warn!("HTTP resolution failed for {}: {}", handle, e);
-    //         }
-    //     }
-    // }
-
-    match &slingshot_result {
-        Ok(did) => {
-            info!("Slingshot resolution succeeded for {}: {}", handle, did);
-            results.push(did.clone());
+        .boxed_local(),
+    ];
+
+    match first_successful_resolution(attempts).await {
+        Ok((method, did)) => {
+            let resolution = HandleResolution {
+                handle: handle.to_string(),
+                did,
+                method,
+                elapsed_ms: (now_ms() - started_ms).max(0.0) as u64,
+            };
+            info!(
+                "{} resolution won for {}: {} in {}ms",
+                method.label(),
+                handle,
+                resolution.did,
+                resolution.elapsed_ms
+            );
+            record_handle_resolution(&resolution);
+            Ok(resolution)
+        }
+        Err(failures) => {
+            for (method, e) in &failures {
+                if *method == HandleResolutionMethod::WellKnown && is_cors_failure(e) {
+                    debug!(
+                        "HTTP resolution failed for {} due to CORS/network restriction (expected): {}",
+                        handle, e
+                    );
+                } else {
+                    warn!("{} resolution failed for {}: {}", method.label(), handle, e);
+                }
+            }
+            Err(ResolveError::NoDIDsFound {
+                domain: format!("all resolution methods failed for {}", handle),
+            })
         }
-        Err(e) => debug!("Slingshot resolution failed for {}: {}", handle, e),
     }
+}
 
-    if results.is_empty() {
-        return Err(ResolveError::NoDIDsFound {
-            domain: format!("both DNS and Slingshot resolution failed for {}", handle),
-        });
-    }
+/// Resolve a handle to a DID, taking whichever resolution method answers first
+///
+/// Results are cached for the session; see [`super::identity_cache`].
+pub async fn resolve_handle_client_side(
+    handle: &str,
+    doh_resolver: &dyn DnsResolver,
+    http_client: &Client,
+) -> Result<String, ResolveError> {
+    // Cache under the canonical form so "Alice.Example." and "alice.example" share an entry
+    let normalized = normalize_handle(handle).unwrap_or_else(|_| handle.to_string());
+    let handle = normalized.as_str();
 
-    // Success if we have at least one result
-    if results.len() == 1 {
-        info!(
-            "Single resolution method succeeded for {}: {}",
-            handle, results[0]
-        );
-        return Ok(results[0].clone());
+    if let Some(did) = cached_did_for_handle(handle) {
+        debug!("Using cached DID for {}: {}", handle, did);
+        return Ok(did);
     }
 
-    // If we have multiple results, validate they agree
-    let first_did: &String = &results[0];
-    if results.iter().all(|did| did == first_did) {
-        info!(
-            "Multiple resolution methods agree for {}: {}",
-            handle, first_did
-        );
-        Ok(first_did.clone())
-    } else {
-        // Log conflict but still return the first successful result
-        // This is more user-friendly than failing completely
-        warn!(
-            "Resolution methods disagree for {} - DNS: {:?}, Slingshot: {:?}",
-            handle, dns_result, slingshot_result
-        );
-        info!(
-            "Using first successful result for {}: {}",
-            handle, results[0]
-        );
-        Ok(results[0].clone())
-    }
+    let did = resolve_handle_racing(handle, doh_resolver, http_client)
+        .await?
+        .did;
+    cache_handle_resolution(handle, &did);
+    Ok(did)
 }
 
 /// Determine PDS provider from handle or DID (mirrors API logic)
@@ -288,6 +364,9 @@ pub async fn determine_pds_provider_client_side(
     doh_resolver: &dyn DnsResolver,
     http_client: &Client,
 ) -> ClientPdsProvider {
+    let normalized = normalize_identifier(handle_or_did);
+    let handle_or_did = normalized.as_str();
+
     // If it's already a DID, try to resolve the DID document
     if handle_or_did.starts_with("did:") {
         return determine_provider_from_did(handle_or_did, http_client).await;
@@ -348,21 +427,29 @@ async fn determine_provider_from_did(did: &str, http_client: &Client) -> ClientP
     determine_provider_from_pds_endpoint(pds_endpoint)
 }
 
-/// Resolve DID document from various DID methods
+/// Resolve DID document from various DID methods, reusing the session cache
 #[instrument(skip(http_client))]
 async fn resolve_did_document(
     did: &str,
     http_client: &Client,
 ) -> Result<DidDocument, ResolveError> {
-    if let Some(plc_id) = did.strip_prefix("did:plc:") {
-        resolve_did_plc(plc_id, http_client).await
+    if let Some(document) = cached_did_document(did) {
+        debug!("Using cached DID document for {}", did);
+        return Ok(document);
+    }
+
+    let document = if let Some(plc_id) = did.strip_prefix("did:plc:") {
+        resolve_did_plc(plc_id, http_client).await?
     } else if let Some(web_domain) = did.strip_prefix("did:web:") {
-        resolve_did_web(web_domain, http_client).await
+        resolve_did_web(web_domain, http_client).await?
     } else {
-        Err(ResolveError::UnsupportedDidMethod {
+        return Err(ResolveError::UnsupportedDidMethod {
             did: did.to_string(),
-        })
-    }
+        });
+    };
+
+    cache_did_document(did, &document);
+    Ok(document)
 }
 
 /// Resolve DID:PLC document from plc.directory
@@ -375,7 +462,7 @@ async fn resolve_did_plc(plc_id: &str, http_client: &Client) -> Result<DidDocume
         .get(&plc_url)
         .header("Accept", "application/json")
         .timeout(std::time::Duration::from_secs(10))
-        .send()
+        .send_traced()
         .await
         .map_err(|e| ResolveError::HttpRequestFailed {
             error: format!("Failed to fetch DID:PLC document: {}", e),
@@ -413,7 +500,7 @@ async fn resolve_did_web(
         .get(&web_url)
         .header("Accept", "application/json")
         .timeout(std::time::Duration::from_secs(10))
-        .send()
+        .send_traced()
         .await
         .map_err(|e| ResolveError::HttpRequestFailed {
             error: format!("Failed to fetch DID:WEB document: {}", e),
@@ -547,7 +634,7 @@ impl WebIdentityResolver {
         }
     }
 
-    /// Resolve handle to DID using both DNS and HTTP methods
+    /// Resolve handle to DID, racing DNS, .well-known and Slingshot
     pub async fn resolve_handle(&self, handle: &str) -> Result<String, ResolveError> {
         resolve_handle_client_side(handle, &self.dns_resolver, &self.http_client).await
     }
@@ -558,17 +645,9 @@ impl WebIdentityResolver {
             .await
     }
 
-    /// Validate handle format
+    /// Validate handle format, accepting IDN and punycode domains
     pub fn is_valid_handle(&self, handle: &str) -> bool {
-        // Basic handle validation - should contain at least one dot and valid characters
-        if handle.is_empty() || !handle.contains('.') {
-            return false;
-        }
-
-        // Check for valid characters (alphanumeric, dots, hyphens)
-        handle
-            .chars()
-            .all(|c| c.is_alphanumeric() || c == '.' || c == '-')
+        is_valid_handle_syntax(handle)
     }
 
     /// Validate DID format
@@ -621,6 +700,52 @@ impl Default for WebIdentityResolver {
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn test_first_successful_resolution_skips_failures() {
+        let attempts = vec![
+            futures::future::ready((
+                HandleResolutionMethod::DnsTxt,
+                Err(ResolveError::NoDIDsFound {
+                    domain: "_atproto.alice.test".to_string(),
+                }),
+            ))
+            .boxed_local(),
+            futures::future::pending().boxed_local(),
+            futures::future::ready((
+                HandleResolutionMethod::WellKnown,
+                Ok("did:plc:alice".to_string()),
+            ))
+            .boxed_local(),
+        ];
+
+        let (method, did) = first_successful_resolution(attempts).await.unwrap();
+        assert_eq!(method, HandleResolutionMethod::WellKnown);
+        assert_eq!(did, "did:plc:alice");
+    }
+
+    #[tokio::test]
+    async fn test_first_successful_resolution_collects_all_failures() {
+        let attempts = vec![
+            futures::future::ready((
+                HandleResolutionMethod::DnsTxt,
+                Err(ResolveError::NoDIDsFound {
+                    domain: "_atproto.alice.test".to_string(),
+                }),
+            ))
+            .boxed_local(),
+            futures::future::ready((
+                HandleResolutionMethod::Slingshot,
+                Err(ResolveError::HttpRequestFailed {
+                    error: "HTTP 404".to_string(),
+                }),
+            ))
+            .boxed_local(),
+        ];
+
+        let failures = first_successful_resolution(attempts).await.unwrap_err();
+        assert_eq!(failures.len(), 2);
+    }
+
     #[tokio::test]
     async fn test_handle_resolution_end_to_end() {
         let identity_resolver = WebIdentityResolver::new();
@@ -671,6 +796,8 @@ mod tests {
         assert!(!resolver.is_valid_handle(""));
         assert!(!resolver.is_valid_handle("nodomainpart"));
         assert!(!resolver.is_valid_handle("invalid@handle.com"));
+        assert!(resolver.is_valid_handle("alice.xn--p1ai"));
+        assert!(resolver.is_valid_handle("bücher.example"));
     }
 
     #[test]
@@ -0,0 +1,227 @@
+//! Explaining why the new PDS refused a repository import
+//!
+//! `com.atproto.repo.importRepo` answers a rejected CAR with a status and an
+//! XRPC error body that usually names the offending block or record. The body
+//! is kept whole, matched against the failures seen in practice — an upload
+//! over the server's size limit, damaged blocks, records the new PDS's
+//! lexicons reject — and paired with what to try next.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Mutex;
+
+/// Likely reason behind a rejected import
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ImportFailureCause {
+    /// The CAR is bigger than the server or its proxy accepts
+    CarTooLarge,
+    /// Blocks that don't decode, don't match their CID, or are missing
+    InvalidBlocks,
+    /// A record the new PDS's lexicons reject
+    LexiconValidation,
+    Unknown,
+}
+
+impl ImportFailureCause {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ImportFailureCause::CarTooLarge => "Repository too large for the new PDS",
+            ImportFailureCause::InvalidBlocks => "Damaged repository data",
+            ImportFailureCause::LexiconValidation => "A record failed schema validation",
+            ImportFailureCause::Unknown => "Unrecognised import error",
+        }
+    }
+
+    pub fn next_action(&self) -> &'static str {
+        match self {
+            ImportFailureCause::CarTooLarge => "The new PDS limits request size below the size of your repository. Ask its operator to raise the upload limit (the PDS's own, or the reverse proxy's such as nginx client_max_body_size), or choose a PDS that accepts larger imports.",
+            ImportFailureCause::InvalidBlocks => "The exported repository arrived damaged or incomplete. Retry to export it again; if the same block fails every time, the old PDS is serving a broken repository and its operator needs the CID below.",
+            ImportFailureCause::LexiconValidation => "A record doesn't pass the new PDS's schema checks. If the new PDS is running an old version, ask its operator to update it; otherwise edit or delete the record named below on your old account and retry.",
+            ImportFailureCause::Unknown => "Retry the migration. If it fails the same way, send the server response below, or a support bundle, to the new PDS's operator.",
+        }
+    }
+}
+
+/// A rejected import with the server's full answer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportDiagnosis {
+    pub status: u16,
+    /// XRPC error name, e.g. `InvalidRequest`
+    pub error: Option<String>,
+    pub message: Option<String>,
+    /// Response body exactly as received
+    pub body: String,
+    pub cause: ImportFailureCause,
+    /// Record URI or block CID the server complained about
+    pub offending: Option<String>,
+}
+
+impl ImportDiagnosis {
+    /// One-line explanation for the migration error
+    pub fn summary(&self) -> String {
+        let detail = match (&self.error, &self.message) {
+            (Some(error), Some(message)) => format!("{}: {}", error, message),
+            (None, Some(message)) => message.clone(),
+            (Some(error), None) => error.clone(),
+            (None, None) if self.body.trim().is_empty() => "no response body".to_string(),
+            (None, None) => self.body.trim().chars().take(200).collect(),
+        };
+        format!(
+            "The new PDS rejected the repository ({}), HTTP {}: {}",
+            self.cause.label(),
+            self.status,
+            detail
+        )
+    }
+}
+
+const TOO_LARGE: &[&str] = &[
+    "payloadtoolarge",
+    "too large",
+    "request entity",
+    "body limit",
+    "exceeds the maximum",
+];
+
+const LEXICON: &[&str] = &[
+    "lexicon",
+    "invalidrecord",
+    "invalid record",
+    "record/",
+    "validation",
+    "schema",
+    "invalid $type",
+];
+
+const BLOCKS: &[&str] = &[
+    "invalid car",
+    "bad car",
+    "car file",
+    "block not found",
+    "missing block",
+    "could not find block",
+    "invalid block",
+    "cid mismatch",
+    "mismatched cid",
+    "invalid cid",
+    "missing root",
+    "invalid commit",
+];
+
+/// Work out why `importRepo` answered `status` with `body`
+pub fn diagnose_import_failure(status: u16, body: &str) -> ImportDiagnosis {
+    let parsed: Option<Value> = serde_json::from_str(body).ok();
+    let field = |name: &str| {
+        parsed
+            .as_ref()
+            .and_then(|value| value.get(name))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+    let error = field("error");
+    let message = field("message");
+
+    let haystack = body.to_ascii_lowercase();
+    let mentions = |phrases: &[&str]| phrases.iter().any(|phrase| haystack.contains(phrase));
+    let cause = if status == 413 || mentions(TOO_LARGE) {
+        ImportFailureCause::CarTooLarge
+    } else if mentions(LEXICON) {
+        ImportFailureCause::LexiconValidation
+    } else if mentions(BLOCKS) {
+        ImportFailureCause::InvalidBlocks
+    } else {
+        ImportFailureCause::Unknown
+    };
+
+    ImportDiagnosis {
+        status,
+        error,
+        message,
+        body: body.to_string(),
+        cause,
+        offending: offending_reference(body),
+    }
+}
+
+/// First AT-URI in the text, else the first CID-looking token
+fn offending_reference(text: &str) -> Option<String> {
+    let tokens = || {
+        text.split(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | ',' | '(' | ')'))
+            .map(|token| token.trim_end_matches(['.', ':', ';']))
+    };
+    tokens()
+        .find(|token| token.starts_with("at://") && token.len() > "at://".len())
+        .or_else(|| {
+            tokens().find(|token| {
+                (token.starts_with("bafy") || token.starts_with("bafk"))
+                    && token.len() > 40
+                    && token.chars().all(|c| c.is_ascii_alphanumeric())
+            })
+        })
+        .map(str::to_string)
+}
+
+static LAST_IMPORT_FAILURE: Mutex<Option<ImportDiagnosis>> = Mutex::new(None);
+
+/// Keep the latest rejected import for the migration error and support bundle
+pub fn record_import_failure(diagnosis: ImportDiagnosis) {
+    if let Ok(mut last) = LAST_IMPORT_FAILURE.lock() {
+        *last = Some(diagnosis);
+    }
+}
+
+/// Forget an earlier rejected import before trying again
+pub fn clear_import_failure() {
+    if let Ok(mut last) = LAST_IMPORT_FAILURE.lock() {
+        *last = None;
+    }
+}
+
+/// Most recent rejected import, if any
+pub fn last_import_failure() -> Option<ImportDiagnosis> {
+    LAST_IMPORT_FAILURE
+        .lock()
+        .ok()
+        .and_then(|last| last.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnose_import_failure_causes() {
+        let too_large = diagnose_import_failure(413, "<html>413 Request Entity Too Large</html>");
+        assert_eq!(too_large.cause, ImportFailureCause::CarTooLarge);
+        assert!(too_large.summary().contains("HTTP 413"));
+
+        let lexicon = diagnose_import_failure(
+            400,
+            r#"{"error":"InvalidRequest","message":"Invalid app.bsky.feed.post record at://did:plc:abc/app.bsky.feed.post/3kxyz: Record/text must not be longer than 3000 graphemes"}"#,
+        );
+        assert_eq!(lexicon.cause, ImportFailureCause::LexiconValidation);
+        assert_eq!(lexicon.error.as_deref(), Some("InvalidRequest"));
+        assert_eq!(
+            lexicon.offending.as_deref(),
+            Some("at://did:plc:abc/app.bsky.feed.post/3kxyz")
+        );
+        assert!(lexicon.summary().starts_with(
+            "The new PDS rejected the repository (A record failed schema validation), HTTP 400: InvalidRequest: Invalid"
+        ));
+
+        let cid = "bafyreib2rxk3rybk3aobmv5cjuql3bm2twh4jo5uxgf5sbqgr5g4xdbwfu";
+        let blocks = diagnose_import_failure(
+            400,
+            &format!(
+                r#"{{"error":"InvalidRequest","message":"Could not find block: {}"}}"#,
+                cid
+            ),
+        );
+        assert_eq!(blocks.cause, ImportFailureCause::InvalidBlocks);
+        assert_eq!(blocks.offending.as_deref(), Some(cid));
+
+        let unknown = diagnose_import_failure(500, "");
+        assert_eq!(unknown.cause, ImportFailureCause::Unknown);
+        assert!(unknown.summary().ends_with("no response body"));
+    }
+}
@@ -10,12 +10,31 @@
 
 pub mod api;
 pub mod auth;
+pub mod bandwidth_probe;
+pub mod body_limit;
+pub mod clock_skew;
+pub mod cors_preflight;
 pub mod dns_over_https;
 pub mod errors;
+pub mod host_stats;
+pub mod http_trace;
+pub mod idempotency;
+pub mod identity_cache;
 pub mod identity_resolver;
+pub mod import_diagnosis;
+pub mod pds_api;
 pub mod pds_client;
+pub mod pds_fingerprint;
+pub mod propagation;
+pub mod read_scope;
+pub mod roles;
+pub mod route_probe;
+pub mod saved_feeds;
+pub mod schema;
+pub mod server_info_cache;
 pub mod session;
 pub mod session_refresh;
+pub mod target_health;
 pub mod types;
 
 #[cfg(test)]
@@ -24,17 +43,22 @@ pub mod cursor_test;
 // Re-export core types for easy access
 pub use types::{
     CachedDnsResponse,
+    ClientAccountDeleteResponse,
     ClientAccountStatusResponse,
+    ClientAppPassword,
     ClientBlobExportResponse,
     ClientBlobUploadResponse,
     ClientCreateAccountRequest,
     ClientCreateAccountResponse,
+    ClientCreatedAppPassword,
+    ClientEmailStatus,
     ClientLoginRequest,
     ClientLoginResponse,
     // Blob types
     ClientMissingBlob,
     ClientMissingBlobsResponse,
     ClientPdsProvider,
+    ClientPhoneVerificationResponse,
     // PLC types
     ClientPlcRecommendationResponse,
     ClientPlcSignResponse,
@@ -43,6 +67,8 @@ pub use types::{
     // Preferences types
     ClientPreferencesExportResponse,
     ClientPreferencesImportResponse,
+    ClientRecord,
+    ClientRecordPage,
     // Repository types
     ClientRepoExportResponse,
     ClientRepoImportResponse,
@@ -60,17 +86,61 @@ pub use types::{
 };
 
 // Re-export error types
+pub use clock_skew::{clock_skew, server_now_secs, ClockSkew};
+pub use cors_preflight::{
+    last_cors_report, mixed_content_problem, run_cors_preflight, CorsOutcome, CorsProbe, CorsReport,
+};
 pub use errors::{ClientError, ClientResult, ResolveError};
+pub use host_stats::{clear_host_stats, host_stats, AimdConcurrency, HostStats};
+pub use http_trace::{recent_http_traces, HttpTrace};
+pub use idempotency::idempotency_key;
+pub use identity_cache::{clear_identity_cache, invalidate_identity};
+pub use schema::{ResponseSchema, SchemaError};
+pub use server_info_cache::{
+    cached_server_info, clear_server_info_cache, invalidate_server_info, server_info_age_secs,
+    ServerInfo,
+};
 
 // Re-export main client classes
+pub use bandwidth_probe::{
+    last_bandwidth_report, run_bandwidth_preflight, BandwidthReport, ThroughputSample,
+    TransferDirection,
+};
+pub use body_limit::{
+    body_limit_fix, body_limit_hint, identify_limit_source, last_body_limit_report,
+    run_body_limit_preflight, BodyLimitProbe, BodyLimitReport, BodyLimitSource, BodyProbeOutcome,
+};
 pub use dns_over_https::{DnsOverHttpsResolver, DnsResolver};
 pub use identity_resolver::{
-    determine_pds_provider_client_side, resolve_handle_client_side, resolve_handle_dns_doh,
-    resolve_handle_http, WebIdentityResolver,
+    determine_pds_provider_client_side, last_handle_resolution, resolve_handle_client_side,
+    resolve_handle_dns_doh, resolve_handle_http, resolve_handle_racing, HandleResolution,
+    HandleResolutionMethod, WebIdentityResolver,
+};
+pub use import_diagnosis::{
+    clear_import_failure, diagnose_import_failure, last_import_failure, record_import_failure,
+    ImportDiagnosis, ImportFailureCause,
 };
+pub use pds_api::PdsApi;
 pub use pds_client::PdsClient;
-pub use session::{JwtUtils, MigrationSessionManager, SessionManager};
+pub use pds_fingerprint::{PdsFingerprint, PdsImplementation, Workaround};
+pub use propagation::{
+    run_propagation_monitor, PropagationCheck, PropagationCheckKind, PropagationReport,
+    PropagationStatus,
+};
+pub use roles::{NewPds, OldPds};
+pub use route_probe::{
+    last_route_report, rank_by_latency, run_route_preflight, RouteKind, RouteProbe, RouteReport,
+};
+pub use saved_feeds::{
+    check_saved_feeds, saved_references, ReferenceStatus, SavedFeedsReport, SavedReference,
+    SavedReferenceCheck, SavedReferenceKind,
+};
+pub use session::{JwtUtils, MigrationSessionManager, SessionManager, TokenStatus};
 pub use session_refresh::RefreshableSessionProvider;
+pub use target_health::{
+    last_target_health, run_target_health_check, HealthCheck, HealthCheckKind, HealthStatus,
+    ServerLimits, TargetHealthReport,
+};
 
 /// Convenience factory for creating a complete client setup
 pub struct MigrationClient {
@@ -107,11 +177,18 @@ impl MigrationClient {
     }
 
     /// Create account on new PDS and store session
+    ///
+    /// Refused for hosts the deployment's target host policy rules out.
     pub async fn create_account_new_pds(
         &self,
+        pds_url: &str,
         request: ClientCreateAccountRequest,
     ) -> ClientResult<ClientCreateAccountResponse> {
-        let response = self.pds_client.create_account(request).await?;
+        crate::services::config::get_global_config()
+            .target_hosts
+            .check_url(pds_url)
+            .map_err(|message| ClientError::ApiError { message })?;
+        let response = self.pds_client.create_account(pds_url, request).await?;
 
         if response.success {
             if let Some(ref session) = response.session {
@@ -201,10 +278,11 @@ pub mod compat {
 
     /// Create account using client-side operations (replaces api::create_account)
     pub async fn create_account(
+        pds_url: &str,
         form: ClientCreateAccountRequest,
     ) -> ClientResult<ClientCreateAccountResponse> {
         let client = get_pds_client();
-        client.create_account(form).await
+        client.create_account(pds_url, form).await
     }
 
     /// Check account status using client-side operations (replaces api::check_account_status)
@@ -218,7 +296,7 @@ pub mod compat {
     /// Describe server using client-side operations (replaces api::describe_server)
     pub async fn describe_server(pds_url: String) -> ClientResult<serde_json::Value> {
         let client = get_pds_client();
-        client.describe_server(&pds_url).await
+        client.server_info(&pds_url).await.map(|info| info.response)
     }
 }
 
@@ -0,0 +1,391 @@
+//! The PDS calls migration steps make, as a trait so tests can substitute a mock
+//!
+//! [`PdsClient`] is the only production implementation. Steps take a
+//! `&dyn PdsApi` instead of constructing a client themselves, which keeps them
+//! runnable natively against canned responses.
+
+use async_trait::async_trait;
+use cid::Cid;
+
+use super::errors::ClientError;
+use super::identity_cache::invalidate_identity;
+use super::pds_client::PdsClient;
+use super::types::*;
+
+#[async_trait(?Send)]
+pub trait PdsApi {
+    async fn describe_server(&self, pds_url: &str) -> Result<serde_json::Value, ClientError>;
+
+    /// PDS endpoint in the DID's current document, bypassing the identity cache
+    async fn resolve_pds_endpoint(&self, did: &str) -> Result<String, ClientError>;
+
+    async fn export_preferences(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<ClientPreferencesExportResponse, ClientError>;
+
+    async fn import_preferences(
+        &self,
+        session: &ClientSessionCredentials,
+        preferences_json: String,
+    ) -> Result<ClientPreferencesImportResponse, ClientError>;
+
+    async fn get_plc_recommendation(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<ClientPlcRecommendationResponse, ClientError>;
+
+    async fn fetch_plc_data(&self, did: &str) -> Result<serde_json::Value, ClientError>;
+
+    async fn describe_repo_collections(
+        &self,
+        session: &ClientSessionCredentials,
+        did: &str,
+    ) -> Result<Vec<String>, ClientError>;
+
+    async fn list_records(
+        &self,
+        session: &ClientSessionCredentials,
+        did: &str,
+        collection: &str,
+        cursor: Option<&str>,
+    ) -> Result<ClientRecordPage, ClientError>;
+
+    async fn put_record(
+        &self,
+        session: &ClientSessionCredentials,
+        collection: &str,
+        rkey: &str,
+        record: serde_json::Value,
+    ) -> Result<(), ClientError>;
+
+    async fn export_blob(
+        &self,
+        session: &ClientSessionCredentials,
+        cid: &Cid,
+    ) -> Result<ClientBlobExportResponse, ClientError>;
+
+    async fn upload_blob(
+        &self,
+        session: &ClientSessionCredentials,
+        cid: &Cid,
+        blob_data: Vec<u8>,
+    ) -> Result<ClientBlobUploadResponse, ClientError>;
+
+    async fn list_app_passwords(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<Vec<ClientAppPassword>, ClientError>;
+
+    async fn get_email_status(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<ClientEmailStatus, ClientError>;
+
+    async fn request_email_confirmation(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<(), ClientError>;
+
+    async fn update_email(
+        &self,
+        session: &ClientSessionCredentials,
+        email: &str,
+        token: Option<&str>,
+    ) -> Result<(), ClientError>;
+
+    async fn request_plc_token(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<ClientPlcTokenResponse, ClientError>;
+
+    /// New tokens for the session, using its refresh token
+    async fn refresh_session(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<ClientSessionCredentials, ClientError>;
+
+    /// Log in to an account on `pds_url`, which may not exist yet
+    async fn try_login_before_creation(
+        &self,
+        handle: &str,
+        password: &str,
+        pds_url: &str,
+    ) -> Result<ClientLoginResponse, ClientError>;
+}
+
+#[async_trait(?Send)]
+impl PdsApi for PdsClient {
+    async fn describe_server(&self, pds_url: &str) -> Result<serde_json::Value, ClientError> {
+        self.server_info(pds_url).await.map(|info| info.response)
+    }
+
+    async fn resolve_pds_endpoint(&self, did: &str) -> Result<String, ClientError> {
+        invalidate_identity(did);
+        self.resolve_pds_from_did(did).await
+    }
+
+    async fn export_preferences(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<ClientPreferencesExportResponse, ClientError> {
+        PdsClient::export_preferences(self, session).await
+    }
+
+    async fn import_preferences(
+        &self,
+        session: &ClientSessionCredentials,
+        preferences_json: String,
+    ) -> Result<ClientPreferencesImportResponse, ClientError> {
+        PdsClient::import_preferences(self, session, preferences_json).await
+    }
+
+    async fn get_plc_recommendation(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<ClientPlcRecommendationResponse, ClientError> {
+        PdsClient::get_plc_recommendation(self, session).await
+    }
+
+    async fn fetch_plc_data(&self, did: &str) -> Result<serde_json::Value, ClientError> {
+        PdsClient::fetch_plc_data(self, did).await
+    }
+
+    async fn describe_repo_collections(
+        &self,
+        session: &ClientSessionCredentials,
+        did: &str,
+    ) -> Result<Vec<String>, ClientError> {
+        PdsClient::describe_repo_collections(self, session, did).await
+    }
+
+    async fn list_records(
+        &self,
+        session: &ClientSessionCredentials,
+        did: &str,
+        collection: &str,
+        cursor: Option<&str>,
+    ) -> Result<ClientRecordPage, ClientError> {
+        PdsClient::list_records(self, session, did, collection, cursor).await
+    }
+
+    async fn put_record(
+        &self,
+        session: &ClientSessionCredentials,
+        collection: &str,
+        rkey: &str,
+        record: serde_json::Value,
+    ) -> Result<(), ClientError> {
+        PdsClient::put_record(self, session, collection, rkey, record).await
+    }
+
+    async fn export_blob(
+        &self,
+        session: &ClientSessionCredentials,
+        cid: &Cid,
+    ) -> Result<ClientBlobExportResponse, ClientError> {
+        PdsClient::export_blob(self, session, cid).await
+    }
+
+    async fn upload_blob(
+        &self,
+        session: &ClientSessionCredentials,
+        cid: &Cid,
+        blob_data: Vec<u8>,
+    ) -> Result<ClientBlobUploadResponse, ClientError> {
+        PdsClient::upload_blob(self, session, cid, blob_data).await
+    }
+
+    async fn list_app_passwords(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<Vec<ClientAppPassword>, ClientError> {
+        PdsClient::list_app_passwords(self, session).await
+    }
+
+    async fn get_email_status(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<ClientEmailStatus, ClientError> {
+        PdsClient::get_email_status(self, session).await
+    }
+
+    async fn request_email_confirmation(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<(), ClientError> {
+        PdsClient::request_email_confirmation(self, session).await
+    }
+
+    async fn update_email(
+        &self,
+        session: &ClientSessionCredentials,
+        email: &str,
+        token: Option<&str>,
+    ) -> Result<(), ClientError> {
+        PdsClient::update_email(self, session, email, token).await
+    }
+
+    async fn request_plc_token(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<ClientPlcTokenResponse, ClientError> {
+        PdsClient::request_plc_token(self, session).await
+    }
+
+    async fn refresh_session(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<ClientSessionCredentials, ClientError> {
+        PdsClient::refresh_session(self, session).await
+    }
+
+    async fn try_login_before_creation(
+        &self,
+        handle: &str,
+        password: &str,
+        pds_url: &str,
+    ) -> Result<ClientLoginResponse, ClientError> {
+        PdsClient::try_login_before_creation(self, handle, password, pds_url).await
+    }
+}
+
+/// Lets role wrappers hold a borrowed `&dyn PdsApi`
+#[async_trait(?Send)]
+impl<T: PdsApi + ?Sized> PdsApi for &T {
+    async fn describe_server(&self, pds_url: &str) -> Result<serde_json::Value, ClientError> {
+        (**self).describe_server(pds_url).await
+    }
+
+    async fn resolve_pds_endpoint(&self, did: &str) -> Result<String, ClientError> {
+        (**self).resolve_pds_endpoint(did).await
+    }
+
+    async fn export_preferences(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<ClientPreferencesExportResponse, ClientError> {
+        (**self).export_preferences(session).await
+    }
+
+    async fn import_preferences(
+        &self,
+        session: &ClientSessionCredentials,
+        preferences_json: String,
+    ) -> Result<ClientPreferencesImportResponse, ClientError> {
+        (**self).import_preferences(session, preferences_json).await
+    }
+
+    async fn get_plc_recommendation(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<ClientPlcRecommendationResponse, ClientError> {
+        (**self).get_plc_recommendation(session).await
+    }
+
+    async fn fetch_plc_data(&self, did: &str) -> Result<serde_json::Value, ClientError> {
+        (**self).fetch_plc_data(did).await
+    }
+
+    async fn describe_repo_collections(
+        &self,
+        session: &ClientSessionCredentials,
+        did: &str,
+    ) -> Result<Vec<String>, ClientError> {
+        (**self).describe_repo_collections(session, did).await
+    }
+
+    async fn list_records(
+        &self,
+        session: &ClientSessionCredentials,
+        did: &str,
+        collection: &str,
+        cursor: Option<&str>,
+    ) -> Result<ClientRecordPage, ClientError> {
+        (**self)
+            .list_records(session, did, collection, cursor)
+            .await
+    }
+
+    async fn put_record(
+        &self,
+        session: &ClientSessionCredentials,
+        collection: &str,
+        rkey: &str,
+        record: serde_json::Value,
+    ) -> Result<(), ClientError> {
+        (**self).put_record(session, collection, rkey, record).await
+    }
+
+    async fn export_blob(
+        &self,
+        session: &ClientSessionCredentials,
+        cid: &Cid,
+    ) -> Result<ClientBlobExportResponse, ClientError> {
+        (**self).export_blob(session, cid).await
+    }
+
+    async fn upload_blob(
+        &self,
+        session: &ClientSessionCredentials,
+        cid: &Cid,
+        blob_data: Vec<u8>,
+    ) -> Result<ClientBlobUploadResponse, ClientError> {
+        (**self).upload_blob(session, cid, blob_data).await
+    }
+
+    async fn list_app_passwords(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<Vec<ClientAppPassword>, ClientError> {
+        (**self).list_app_passwords(session).await
+    }
+
+    async fn get_email_status(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<ClientEmailStatus, ClientError> {
+        (**self).get_email_status(session).await
+    }
+
+    async fn request_email_confirmation(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<(), ClientError> {
+        (**self).request_email_confirmation(session).await
+    }
+
+    async fn update_email(
+        &self,
+        session: &ClientSessionCredentials,
+        email: &str,
+        token: Option<&str>,
+    ) -> Result<(), ClientError> {
+        (**self).update_email(session, email, token).await
+    }
+
+    async fn request_plc_token(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<ClientPlcTokenResponse, ClientError> {
+        (**self).request_plc_token(session).await
+    }
+
+    async fn refresh_session(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<ClientSessionCredentials, ClientError> {
+        (**self).refresh_session(session).await
+    }
+
+    async fn try_login_before_creation(
+        &self,
+        handle: &str,
+        password: &str,
+        pds_url: &str,
+    ) -> Result<ClientLoginResponse, ClientError> {
+        (**self)
+            .try_login_before_creation(handle, password, pds_url)
+            .await
+    }
+}
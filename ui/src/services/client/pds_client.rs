@@ -4,8 +4,17 @@ use reqwest::Client;
 use tracing::{error, info, instrument};
 
 use super::errors::ClientError;
+use super::http_trace::TracedSend;
 use super::identity_resolver::WebIdentityResolver;
+use super::schema::{check_response, DescribeServerOutput, GetPreferencesOutput};
+use super::server_info_cache::{
+    cache_server_info, cached_server_info, invalidate_server_info, ServerInfo,
+};
 use super::types::*;
+use crate::services::transport::{
+    shared_transport, AuthMiddleware, HttpRequest, HttpResponse, HttpTransport, MiddlewareStack,
+    StaticToken, USER_AGENT,
+};
 
 /// Client for ATProto PDS operations
 #[derive(Clone)]
@@ -18,16 +27,34 @@ impl PdsClient {
     /// Create a new PDS client
     pub fn new() -> Self {
         Self {
-            http_client: {
-                Client::builder()
-                    .user_agent("tektite-cc-atproto-migration-service/1.0")
-                    .build()
-                    .expect("Failed to create HTTP client")
-            },
+            http_client: Client::builder()
+                .user_agent(USER_AGENT)
+                .build()
+                .expect("Failed to create HTTP client"),
             identity_resolver: WebIdentityResolver::new(),
         }
     }
 
+    /// Send an XRPC call through the transport middleware, with `token` as the
+    /// bearer token when given. A 401 comes back as a response: the session's
+    /// owner decides whether to refresh it.
+    pub(crate) async fn send_xrpc(
+        &self,
+        request: HttpRequest,
+        token: Option<&str>,
+    ) -> Result<HttpResponse, String> {
+        let transport = shared_transport();
+        match token {
+            Some(token) => {
+                MiddlewareStack::new(Box::new(transport))
+                    .with(AuthMiddleware::new(StaticToken(token.to_string())))
+                    .send(request)
+                    .await
+            }
+            None => transport.send(request).await,
+        }
+    }
+
     /// Login to a PDS using identifier and password
     // NEWBOLD.md Step: goat account login --pds-host $NEWPDSHOST -u $ACCOUNTDID -p $NEWPASSWORD (line 52)
     // Implements: Creates session on PDS for specified account identifier
@@ -37,7 +64,19 @@ impl PdsClient {
         identifier: &str,
         password: &str,
     ) -> Result<ClientLoginResponse, ClientError> {
-        crate::services::client::auth::login_impl(self, identifier, password).await
+        crate::services::client::auth::login_impl(self, identifier, password, None).await
+    }
+
+    /// Login like [`PdsClient::login`], but at `pds_url` when given instead of
+    /// the PDS the identifier's DID document lists
+    #[instrument(skip(self, password), err)]
+    pub async fn login_at(
+        &self,
+        identifier: &str,
+        password: &str,
+        pds_url: Option<&str>,
+    ) -> Result<ClientLoginResponse, ClientError> {
+        crate::services::client::auth::login_impl(self, identifier, password, pds_url).await
     }
 
     /// Try to login with full options including auth factor and takendown support
@@ -115,9 +154,21 @@ impl PdsClient {
     #[instrument(skip(self), err)]
     pub async fn create_account(
         &self,
+        pds_url: &str,
         request: ClientCreateAccountRequest,
     ) -> Result<ClientCreateAccountResponse, ClientError> {
-        crate::services::client::auth::create_account_impl(self, request).await
+        crate::services::client::auth::create_account_impl(self, pds_url, request).await
+    }
+
+    /// Have the PDS text a signup verification code to a phone number
+    #[instrument(skip(self, phone_number), err)]
+    pub async fn request_phone_verification(
+        &self,
+        pds_url: &str,
+        phone_number: &str,
+    ) -> Result<ClientPhoneVerificationResponse, ClientError> {
+        crate::services::client::auth::request_phone_verification_impl(self, pds_url, phone_number)
+            .await
     }
 
     /// Check account status
@@ -131,6 +182,76 @@ impl PdsClient {
         crate::services::client::auth::check_account_status_impl(self, session).await
     }
 
+    /// Names of the account's app passwords
+    #[instrument(skip(self, session), err)]
+    pub async fn list_app_passwords(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<Vec<ClientAppPassword>, ClientError> {
+        crate::services::client::auth::list_app_passwords_impl(self, session).await
+    }
+
+    /// Create an app password; the secret is only in this response
+    #[instrument(skip(self, session), err)]
+    pub async fn create_app_password(
+        &self,
+        session: &ClientSessionCredentials,
+        name: &str,
+        privileged: bool,
+    ) -> Result<ClientCreatedAppPassword, ClientError> {
+        crate::services::client::auth::create_app_password_impl(self, session, name, privileged)
+            .await
+    }
+
+    /// The account's email and whether it is confirmed
+    #[instrument(skip(self, session), err)]
+    pub async fn get_email_status(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<ClientEmailStatus, ClientError> {
+        crate::services::client::auth::get_email_status_impl(self, session).await
+    }
+
+    /// Email a confirmation code to the account's address
+    #[instrument(skip(self, session), err)]
+    pub async fn request_email_confirmation(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<(), ClientError> {
+        crate::services::client::auth::request_email_confirmation_impl(self, session).await
+    }
+
+    /// Confirm the address with the emailed code
+    #[instrument(skip(self, session, token), err)]
+    pub async fn confirm_email(
+        &self,
+        session: &ClientSessionCredentials,
+        email: &str,
+        token: &str,
+    ) -> Result<(), ClientError> {
+        crate::services::client::auth::confirm_email_impl(self, session, email, token).await
+    }
+
+    /// Start an email change; true if it needs a token sent to the current address
+    #[instrument(skip(self, session), err)]
+    pub async fn request_email_update(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<bool, ClientError> {
+        crate::services::client::auth::request_email_update_impl(self, session).await
+    }
+
+    /// Change the account's email
+    #[instrument(skip(self, session, token), err)]
+    pub async fn update_email(
+        &self,
+        session: &ClientSessionCredentials,
+        email: &str,
+        token: Option<&str>,
+    ) -> Result<(), ClientError> {
+        crate::services::client::auth::update_email_impl(self, session, email, token).await
+    }
+
     /// Refresh session tokens
     #[instrument(skip(self), err)]
     pub async fn refresh_session(
@@ -140,6 +261,33 @@ impl PdsClient {
         crate::services::client::auth::refresh_session_impl(self, session).await
     }
 
+    /// Check with the PDS whether the session's access token is still accepted
+    #[instrument(skip(self), err)]
+    pub async fn validate_session(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<bool, ClientError> {
+        crate::services::client::auth::validate_session_impl(self, session).await
+    }
+
+    /// Resolve a handle or DID to the account's DID and the PDS its DID
+    /// document lists
+    pub async fn resolve_identity(
+        &self,
+        identifier: &str,
+    ) -> Result<(String, String), ClientError> {
+        let did = if identifier.starts_with("did:") {
+            identifier.to_string()
+        } else {
+            self.identity_resolver
+                .resolve_handle(identifier)
+                .await
+                .map_err(ClientError::ResolutionFailed)?
+        };
+        let pds_url = self.resolve_pds_from_did(&did).await?;
+        Ok((did, pds_url))
+    }
+
     /// Resolve PDS URL from DID by resolving the DID document
     pub(crate) async fn resolve_pds_from_did(&self, did: &str) -> Result<String, ClientError> {
         info!("Resolving PDS URL from DID: {}", did);
@@ -196,7 +344,7 @@ impl PdsClient {
             .http_client
             .get(&describe_url)
             .timeout(std::time::Duration::from_secs(15)) // Specific timeout for describe_server
-            .send()
+            .send_traced()
             .await
             .map_err(|e| {
                 if e.is_timeout() {
@@ -217,6 +365,7 @@ impl PdsClient {
                 .map_err(|e| ClientError::NetworkError {
                     message: format!("Failed to parse server description: {}", e),
                 })?;
+            check_response::<DescribeServerOutput>(&server_info)?;
 
             Ok(server_info)
         } else {
@@ -228,6 +377,22 @@ impl PdsClient {
         }
     }
 
+    /// describeServer for `pds_url`, from the session cache when it is fresh
+    pub async fn server_info(&self, pds_url: &str) -> Result<ServerInfo, ClientError> {
+        if let Some(info) = cached_server_info(pds_url) {
+            return Ok(info);
+        }
+        let info = ServerInfo::from_response(self.describe_server(pds_url).await?)?;
+        cache_server_info(pds_url, &info);
+        Ok(info)
+    }
+
+    /// Ask the server again, replacing whatever was cached for it
+    pub async fn refresh_server_info(&self, pds_url: &str) -> Result<ServerInfo, ClientError> {
+        invalidate_server_info(pds_url);
+        self.server_info(pds_url).await
+    }
+
     /// Export repository from PDS as CAR file
     // NEWBOLD.md Step: goat repo export $ACCOUNTDID (line 76)
     // Implements: Exports repository as CAR file for migration
@@ -239,6 +404,26 @@ impl PdsClient {
         crate::services::client::api::export_repository_impl(self, session).await
     }
 
+    /// Export only the repository blocks written after revision `since`
+    #[instrument(skip(self), err)]
+    pub async fn export_repository_since(
+        &self,
+        session: &ClientSessionCredentials,
+        since: &str,
+    ) -> Result<ClientRepoExportResponse, ClientError> {
+        crate::services::client::api::export_repository_since_impl(self, session, Some(since)).await
+    }
+
+    /// Stream a repository export; the caller reads `bytes_stream()` so the CAR
+    /// never has to fit in memory
+    #[instrument(skip(self), err)]
+    pub async fn export_repository_stream(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<reqwest::Response, ClientError> {
+        crate::services::client::api::export_repository_stream_impl(self, session).await
+    }
+
     /// Import repository to PDS from CAR file
     // NEWBOLD.md Step: goat repo import ./did:plc:do2ar6uqzrvyzq3wevji6fbe.20250625142552.car (line 81)
     // Implements: Imports repository CAR file to new PDS
@@ -251,6 +436,41 @@ impl PdsClient {
         crate::services::client::api::import_repository_impl(self, session, car_data).await
     }
 
+    /// List the record collections present in a repository
+    #[instrument(skip(self), err)]
+    pub async fn describe_repo_collections(
+        &self,
+        session: &ClientSessionCredentials,
+        did: &str,
+    ) -> Result<Vec<String>, ClientError> {
+        crate::services::client::api::describe_repo_collections_impl(self, session, did).await
+    }
+
+    /// List one page of a collection's records
+    #[instrument(skip(self), err)]
+    pub async fn list_records(
+        &self,
+        session: &ClientSessionCredentials,
+        did: &str,
+        collection: &str,
+        cursor: Option<&str>,
+    ) -> Result<ClientRecordPage, ClientError> {
+        crate::services::client::api::list_records_impl(self, session, did, collection, cursor)
+            .await
+    }
+
+    /// Write a record under a fixed record key in the session's repository
+    #[instrument(skip(self, record), err)]
+    pub async fn put_record(
+        &self,
+        session: &ClientSessionCredentials,
+        collection: &str,
+        rkey: &str,
+        record: serde_json::Value,
+    ) -> Result<(), ClientError> {
+        crate::services::client::api::put_record_impl(self, session, collection, rkey, record).await
+    }
+
     /// Get list of missing blobs for account
     // NEWBOLD.md Step: goat account missing-blobs (line 86)
     // Implements: Lists missing blobs that need migration to new PDS
@@ -451,23 +671,21 @@ impl PdsClient {
         let preferences_url = format!("{}/xrpc/app.bsky.actor.getPreferences", session.pds);
 
         let response = self
-            .http_client
-            .get(&preferences_url)
-            .header("Authorization", format!("Bearer {}", session.access_jwt))
-            .send()
+            .send_xrpc(
+                HttpRequest::get(&preferences_url),
+                Some(&session.access_jwt),
+            )
             .await
             .map_err(|e| ClientError::NetworkError {
                 message: format!("Failed to export preferences: {}", e),
             })?;
 
-        if response.status().is_success() {
+        if response.is_success() {
             let preferences_data: serde_json::Value =
-                response
-                    .json()
-                    .await
-                    .map_err(|e| ClientError::NetworkError {
-                        message: format!("Failed to parse preferences response: {}", e),
-                    })?;
+                response.json().map_err(|e| ClientError::NetworkError {
+                    message: format!("Failed to parse preferences response: {}", e),
+                })?;
+            check_response::<GetPreferencesOutput>(&preferences_data)?;
 
             info!("Preferences exported successfully");
 
@@ -477,7 +695,7 @@ impl PdsClient {
                 preferences_json: Some(preferences_data.to_string()),
             })
         } else {
-            let error_text = response.text().await.unwrap_or_default();
+            let error_text = response.text();
             error!("Preferences export failed: {}", error_text);
 
             Ok(ClientPreferencesExportResponse {
@@ -515,18 +733,16 @@ impl PdsClient {
         });
 
         let response = self
-            .http_client
-            .post(&preferences_url)
-            .header("Authorization", format!("Bearer {}", session.access_jwt))
-            .header("Content-Type", "application/json")
-            .json(&request_body)
-            .send()
+            .send_xrpc(
+                HttpRequest::post_json(&preferences_url, &request_body),
+                Some(&session.access_jwt),
+            )
             .await
             .map_err(|e| ClientError::NetworkError {
                 message: format!("Failed to import preferences: {}", e),
             })?;
 
-        if response.status().is_success() {
+        if response.is_success() {
             info!("Preferences imported successfully");
 
             Ok(ClientPreferencesImportResponse {
@@ -534,7 +750,7 @@ impl PdsClient {
                 message: "Preferences imported successfully".to_string(),
             })
         } else {
-            let error_text = response.text().await.unwrap_or_default();
+            let error_text = response.text();
             error!("Preferences import failed: {}", error_text);
 
             Ok(ClientPreferencesImportResponse {
@@ -553,6 +769,33 @@ impl PdsClient {
         crate::services::client::api::get_plc_recommendation_impl(self, session).await
     }
 
+    /// Reserve the repo signing key the target will use when it creates the account
+    /// for `did`. Only meaningful before `createAccount`: afterwards the PDS hands
+    /// out a fresh key the repo isn't signed with.
+    #[instrument(skip(self), err)]
+    pub async fn reserve_signing_key(
+        &self,
+        pds_url: &str,
+        did: &str,
+    ) -> Result<String, ClientError> {
+        crate::services::client::api::reserve_signing_key_impl(self, pds_url, did).await
+    }
+
+    /// Fetch the current PLC data for a did:plc from the directory
+    #[instrument(skip(self), err)]
+    pub async fn fetch_plc_data(&self, did: &str) -> Result<serde_json::Value, ClientError> {
+        crate::services::client::api::fetch_plc_data_impl(self, did).await
+    }
+
+    /// Fetch the full operation history of a did:plc from the directory
+    #[instrument(skip(self), err)]
+    pub async fn fetch_plc_audit_log(
+        &self,
+        did: &str,
+    ) -> Result<Vec<serde_json::Value>, ClientError> {
+        crate::services::client::api::fetch_plc_audit_log_impl(self, did).await
+    }
+
     /// Request PLC token from PDS
     #[instrument(skip(self), err)]
     pub async fn request_plc_token(
@@ -599,6 +842,26 @@ impl PdsClient {
         crate::services::client::api::activate_account_impl(self, session).await
     }
 
+    /// Request an emailed token authorizing account deletion
+    #[instrument(skip(self, session), err)]
+    pub async fn request_account_delete(
+        &self,
+        session: &ClientSessionCredentials,
+    ) -> Result<ClientAccountDeleteResponse, ClientError> {
+        crate::services::client::api::request_account_delete_impl(self, session).await
+    }
+
+    /// Permanently delete an account on its PDS
+    #[instrument(skip(self, session, password, token), err)]
+    pub async fn delete_account(
+        &self,
+        session: &ClientSessionCredentials,
+        password: &str,
+        token: &str,
+    ) -> Result<ClientAccountDeleteResponse, ClientError> {
+        crate::services::client::api::delete_account_impl(self, session, password, token).await
+    }
+
     /// Deactivate account on PDS
     // NEWBOLD.md Step: goat account deactivate (line 163)
     // Implements: Deactivates old account after successful migration
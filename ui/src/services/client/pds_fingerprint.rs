@@ -0,0 +1,332 @@
+//! Identifying the PDS software a migration targets
+//!
+//! Implementations differ in ways that matter for a migration: which endpoints
+//! exist, how far their listMissingBlobs can be trusted, how accounts get
+//! created. The target is fingerprinted from its `/xrpc/_health` version
+//! string, its describeServer response and, where the browser exposes them,
+//! the `Server` and `X-Powered-By` response headers. The result goes into the
+//! readiness report and the support bundle, and selects entries from
+//! [`KNOWN_ISSUES`]: guidance shown to the user and workarounds the migration
+//! applies for that implementation or version range.
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+
+use super::http_trace::TracedSend;
+use super::target_health::HealthStatus;
+
+const FINGERPRINT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Known PDS implementations
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PdsImplementation {
+    /// bluesky-social/pds, the reference implementation
+    Reference,
+    /// DavidBuchanan314/millipds
+    Millipds,
+    /// blacksky-algorithms/rsky-pds
+    Rsky,
+    /// haileyok/cocoon
+    Cocoon,
+    #[default]
+    Unknown,
+}
+
+impl PdsImplementation {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PdsImplementation::Reference => "bluesky-social/pds",
+            PdsImplementation::Millipds => "millipds",
+            PdsImplementation::Rsky => "rsky-pds",
+            PdsImplementation::Cocoon => "cocoon",
+            PdsImplementation::Unknown => "unrecognized PDS software",
+        }
+    }
+}
+
+/// What the target appears to run, and what gave it away
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PdsFingerprint {
+    pub implementation: PdsImplementation,
+    /// Version as reported, e.g. `0.4.107`
+    pub version: Option<String>,
+    pub evidence: Vec<String>,
+}
+
+impl PdsFingerprint {
+    pub fn describe(&self) -> String {
+        match &self.version {
+            Some(version) => format!("{} {}", self.implementation.label(), version),
+            None => self.implementation.label().to_string(),
+        }
+    }
+
+    /// Numeric version, ignoring a leading `v` and any suffix after the patch number
+    pub fn version_triple(&self) -> Option<(u32, u32, u32)> {
+        parse_version(self.version.as_deref()?)
+    }
+
+    /// Whether a workaround applies to this target
+    pub fn needs(&self, workaround: Workaround) -> bool {
+        applicable_issues(self)
+            .iter()
+            .any(|issue| issue.workaround == Some(workaround))
+    }
+}
+
+/// Behavior the migration changes for particular targets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Workaround {
+    /// Ignore listMissingBlobs and diff full sync.listBlobs inventories
+    FullBlobDiff,
+}
+
+/// Guidance and an optional workaround for an implementation or version range
+#[derive(Debug, Clone, PartialEq)]
+pub struct KnownIssue {
+    pub implementation: PdsImplementation,
+    /// Applies only below this version; `None` means every version
+    pub fixed_in: Option<(u32, u32, u32)>,
+    pub severity: HealthStatus,
+    pub guidance: &'static str,
+    pub workaround: Option<Workaround>,
+}
+
+/// Oldest reference PDS release this tool's flow has been used against
+pub const OLDEST_TESTED_REFERENCE_VERSION: (u32, u32, u32) = (0, 4, 0);
+
+pub const KNOWN_ISSUES: &[KnownIssue] = &[
+    KnownIssue {
+        implementation: PdsImplementation::Reference,
+        fixed_in: Some(OLDEST_TESTED_REFERENCE_VERSION),
+        severity: HealthStatus::Warning,
+        guidance: "This PDS runs a release older than any this tool has been used with; ask its operator to update before migrating",
+        workaround: Some(Workaround::FullBlobDiff),
+    },
+    KnownIssue {
+        implementation: PdsImplementation::Millipds,
+        fixed_in: None,
+        severity: HealthStatus::Warning,
+        guidance: "millipds is built for a handful of accounts set up by its operator; if account creation fails, ask them to create the account for your DID",
+        workaround: Some(Workaround::FullBlobDiff),
+    },
+    KnownIssue {
+        implementation: PdsImplementation::Rsky,
+        fixed_in: None,
+        severity: HealthStatus::Ok,
+        guidance: "Blob inventories are compared in full instead of trusting listMissingBlobs",
+        workaround: Some(Workaround::FullBlobDiff),
+    },
+    KnownIssue {
+        implementation: PdsImplementation::Cocoon,
+        fixed_in: None,
+        severity: HealthStatus::Ok,
+        guidance: "Blob inventories are compared in full instead of trusting listMissingBlobs",
+        workaround: Some(Workaround::FullBlobDiff),
+    },
+    KnownIssue {
+        implementation: PdsImplementation::Unknown,
+        fixed_in: None,
+        severity: HealthStatus::Warning,
+        guidance: "The PDS software could not be identified, so the migration takes the slower, conservative paths",
+        workaround: Some(Workaround::FullBlobDiff),
+    },
+];
+
+/// Known issues that apply to a fingerprint
+pub fn applicable_issues(fingerprint: &PdsFingerprint) -> Vec<&'static KnownIssue> {
+    let version = fingerprint.version_triple();
+    KNOWN_ISSUES
+        .iter()
+        .filter(|issue| issue.implementation == fingerprint.implementation)
+        .filter(|issue| match (issue.fixed_in, version) {
+            (None, _) => true,
+            (Some(fixed), Some(version)) => version < fixed,
+            // Can't tell the version; don't warn about an old release on a guess
+            (Some(_), None) => false,
+        })
+        .collect()
+}
+
+fn parse_version(version: &str) -> Option<(u32, u32, u32)> {
+    let version = version.trim().trim_start_matches('v');
+    let mut parts = version.split(|c: char| !c.is_ascii_digit()).take(3);
+    let mut next = || parts.next().and_then(|part| part.parse::<u32>().ok());
+    Some((next()?, next()?, next().unwrap_or(0)))
+}
+
+/// Identify the implementation from whatever the target revealed
+///
+/// `headers` are lowercase names with their values.
+pub fn fingerprint_pds(
+    health: Option<&Value>,
+    describe: &Value,
+    headers: &[(String, String)],
+) -> PdsFingerprint {
+    let mut fingerprint = PdsFingerprint::default();
+    let version = health
+        .and_then(|health| health.get("version"))
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|version| !version.is_empty());
+
+    let named = [
+        ("millipds", PdsImplementation::Millipds),
+        ("rsky", PdsImplementation::Rsky),
+        ("cocoon", PdsImplementation::Cocoon),
+    ];
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.to_lowercase())
+    };
+
+    if let Some(version) = version {
+        let lower = version.to_lowercase();
+        if let Some((_, implementation)) = named.iter().find(|(name, _)| lower.contains(name)) {
+            fingerprint.implementation = *implementation;
+            fingerprint
+                .evidence
+                .push(format!("_health version \"{}\"", version));
+            // Keep just the number, e.g. "millipds v0.0.5" -> "0.0.5"
+            fingerprint.version = version
+                .split_whitespace()
+                .find(|word| parse_version(word).is_some())
+                .map(|word| word.trim_start_matches('v').to_string());
+        } else if parse_version(version).is_some() {
+            // The reference PDS reports a bare semver
+            fingerprint.implementation = PdsImplementation::Reference;
+            fingerprint
+                .evidence
+                .push(format!("_health version \"{}\"", version));
+            fingerprint.version = Some(version.trim_start_matches('v').to_string());
+        }
+    }
+
+    if fingerprint.implementation == PdsImplementation::Unknown {
+        for (name, value) in [
+            ("server", header("server")),
+            ("x-powered-by", header("x-powered-by")),
+        ] {
+            let Some(value) = value else { continue };
+            let implementation = if let Some((_, implementation)) =
+                named.iter().find(|(name, _)| value.contains(name))
+            {
+                Some(*implementation)
+            } else if value.contains("aiohttp") {
+                Some(PdsImplementation::Millipds)
+            } else if value.contains("rocket") {
+                Some(PdsImplementation::Rsky)
+            } else if value.contains("express") {
+                Some(PdsImplementation::Reference)
+            } else {
+                None
+            };
+            if let Some(implementation) = implementation {
+                fingerprint.implementation = implementation;
+                fingerprint
+                    .evidence
+                    .push(format!("{} header \"{}\"", name, value));
+                break;
+            }
+        }
+    }
+
+    // The reference PDS always advertises its links and contact objects
+    if fingerprint.implementation == PdsImplementation::Unknown
+        && describe.get("links").is_some()
+        && describe.get("contact").is_some()
+        && version.is_none()
+    {
+        fingerprint
+            .evidence
+            .push("describeServer shaped like the reference PDS, but no version".to_string());
+    }
+    fingerprint
+}
+
+/// Fetch `/xrpc/_health` and fingerprint the target
+pub async fn detect_pds_software(pds_url: &str, describe: &Value) -> PdsFingerprint {
+    let url = format!("{}/xrpc/_health", pds_url.trim_end_matches('/'));
+    let response = Client::new()
+        .get(&url)
+        .timeout(FINGERPRINT_TIMEOUT)
+        .send_traced()
+        .await;
+    let (health, headers) = match response {
+        Ok(response) => {
+            let headers: Vec<(String, String)> = response
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((
+                        name.as_str().to_lowercase(),
+                        value.to_str().ok()?.to_string(),
+                    ))
+                })
+                .collect();
+            (response.json::<Value>().await.ok(), headers)
+        }
+        Err(_) => (None, Vec::new()),
+    };
+    fingerprint_pds(health.as_ref(), describe, &headers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_fingerprints_from_health_and_headers() {
+        let describe = json!({"did": "did:web:pds.example"});
+
+        let reference = fingerprint_pds(Some(&json!({"version": "0.4.107"})), &describe, &[]);
+        assert_eq!(reference.implementation, PdsImplementation::Reference);
+        assert_eq!(reference.version_triple(), Some((0, 4, 107)));
+
+        let millipds =
+            fingerprint_pds(Some(&json!({"version": "millipds v0.0.5"})), &describe, &[]);
+        assert_eq!(millipds.implementation, PdsImplementation::Millipds);
+        assert_eq!(millipds.version.as_deref(), Some("0.0.5"));
+
+        let by_header = fingerprint_pds(
+            None,
+            &describe,
+            &[(
+                "server".to_string(),
+                "Python/3.12 aiohttp/3.9.5".to_string(),
+            )],
+        );
+        assert_eq!(by_header.implementation, PdsImplementation::Millipds);
+        assert_eq!(by_header.version, None);
+
+        let unknown = fingerprint_pds(Some(&json!({"status": "ok"})), &describe, &[]);
+        assert_eq!(unknown.implementation, PdsImplementation::Unknown);
+    }
+
+    #[test]
+    fn test_known_issues_follow_implementation_and_version() {
+        let at = |version: &str| PdsFingerprint {
+            implementation: PdsImplementation::Reference,
+            version: Some(version.to_string()),
+            evidence: Vec::new(),
+        };
+        assert!(applicable_issues(&at("0.4.107")).is_empty());
+        assert!(!at("0.4.107").needs(Workaround::FullBlobDiff));
+        assert_eq!(applicable_issues(&at("0.3.9")).len(), 1);
+        assert!(at("0.3.9").needs(Workaround::FullBlobDiff));
+
+        // An unknown reference version isn't assumed to be old
+        let no_version = PdsFingerprint {
+            version: None,
+            ..at("")
+        };
+        assert!(applicable_issues(&no_version).is_empty());
+
+        assert!(PdsFingerprint::default().needs(Workaround::FullBlobDiff));
+    }
+}
@@ -0,0 +1,354 @@
+//! Watching the migrated account reach the rest of the network
+//!
+//! Activation makes the new PDS emit the account's events, but the network
+//! only hears them once the public relay is crawling that host, and the
+//! AppView only updates from the relay. Right after activation the relay is
+//! asked to crawl the new PDS, then the relay and the AppView are polled until
+//! both see the account as active. There is no public endpoint for asking the
+//! AppView to refresh an account; the crawl request is what reaches it.
+//!
+//! Nothing here can fail the migration, which is already complete by then.
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tracing::info;
+
+use super::http_trace::TracedSend;
+use super::target_health::{relay_hostname, PUBLIC_RELAY_URL};
+use super::types::ClientSessionCredentials;
+use crate::utils::platform::sleep_ms;
+
+/// AppView asked whether it shows the account
+pub const PUBLIC_APPVIEW_URL: &str = "https://public.api.bsky.app";
+
+const PROPAGATION_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Time between polls of the relay and the AppView
+pub const PROPAGATION_POLL_MS: u64 = 15_000;
+
+/// Polls before the monitor gives up and leaves the last answers on screen
+pub const MAX_PROPAGATION_POLLS: u32 = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PropagationStatus {
+    /// Not there yet; the monitor keeps asking
+    Pending,
+    Done,
+    /// Asking didn't work; the network will usually still catch up on its own
+    Failed,
+}
+
+impl PropagationStatus {
+    pub fn icon(&self) -> &'static str {
+        match self {
+            PropagationStatus::Pending => "⏳",
+            PropagationStatus::Done => "✅",
+            PropagationStatus::Failed => "⚠️",
+        }
+    }
+}
+
+/// What a propagation check looked at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PropagationCheckKind {
+    CrawlRequest,
+    RelayAccount,
+    AppView,
+}
+
+impl PropagationCheckKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PropagationCheckKind::CrawlRequest => "Relay crawl request",
+            PropagationCheckKind::RelayAccount => "Relay",
+            PropagationCheckKind::AppView => "AppView",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PropagationCheck {
+    pub kind: PropagationCheckKind,
+    pub status: PropagationStatus,
+    pub detail: String,
+}
+
+impl PropagationCheck {
+    fn new(
+        kind: PropagationCheckKind,
+        status: PropagationStatus,
+        detail: impl Into<String>,
+    ) -> Self {
+        Self {
+            kind,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PropagationReport {
+    pub checks: Vec<PropagationCheck>,
+    pub polls: u32,
+    /// The monitor stopped polling, whether or not everything arrived
+    pub finished: bool,
+}
+
+impl PropagationReport {
+    /// Whether the relay and the AppView both see the active account
+    pub fn is_complete(&self) -> bool {
+        [
+            PropagationCheckKind::RelayAccount,
+            PropagationCheckKind::AppView,
+        ]
+        .iter()
+        .all(|kind| {
+            self.checks
+                .iter()
+                .any(|check| check.kind == *kind && check.status == PropagationStatus::Done)
+        })
+    }
+
+    fn set(&mut self, check: PropagationCheck) {
+        match self.checks.iter_mut().find(|c| c.kind == check.kind) {
+            Some(existing) => *existing = check,
+            None => self.checks.push(check),
+        }
+    }
+}
+
+/// Judge the relay's answer to `com.atproto.sync.requestCrawl`
+pub fn crawl_request_check(status: u16, body: &str, hostname: &str) -> PropagationCheck {
+    let kind = PropagationCheckKind::CrawlRequest;
+    if (200..300).contains(&status) {
+        PropagationCheck::new(
+            kind,
+            PropagationStatus::Done,
+            format!("The public relay was asked to crawl {}", hostname),
+        )
+    } else {
+        PropagationCheck::new(
+            kind,
+            PropagationStatus::Failed,
+            format!(
+                "The public relay refused a crawl request for {} (HTTP {}: {})",
+                hostname,
+                status,
+                body.trim()
+            ),
+        )
+    }
+}
+
+/// Judge the relay's `com.atproto.sync.getRepoStatus` answer for the account
+pub fn relay_account_check(status: u16, body: &Value) -> PropagationCheck {
+    let kind = PropagationCheckKind::RelayAccount;
+    if !(200..300).contains(&status) {
+        return PropagationCheck::new(
+            kind,
+            PropagationStatus::Pending,
+            match body.get("error").and_then(Value::as_str) {
+                Some("RepoNotFound") => "The relay hasn't seen the account yet".to_string(),
+                _ => format!("The relay answered HTTP {}", status),
+            },
+        );
+    }
+    if body.get("active").and_then(Value::as_bool) == Some(true) {
+        let rev = body.get("rev").and_then(Value::as_str).unwrap_or("unknown");
+        return PropagationCheck::new(
+            kind,
+            PropagationStatus::Done,
+            format!("The relay sees the account as active (revision {})", rev),
+        );
+    }
+    PropagationCheck::new(
+        kind,
+        PropagationStatus::Pending,
+        format!(
+            "The relay still sees the account as {}",
+            body.get("status")
+                .and_then(Value::as_str)
+                .unwrap_or("inactive")
+        ),
+    )
+}
+
+/// Judge the AppView's `app.bsky.actor.getProfile` answer for the account
+pub fn appview_check(status: u16, body: &Value) -> PropagationCheck {
+    let kind = PropagationCheckKind::AppView;
+    if (200..300).contains(&status) {
+        let handle = body.get("handle").and_then(Value::as_str).unwrap_or("");
+        return PropagationCheck::new(
+            kind,
+            PropagationStatus::Done,
+            format!("The AppView shows @{}", handle),
+        );
+    }
+    PropagationCheck::new(
+        kind,
+        PropagationStatus::Pending,
+        match body.get("error").and_then(Value::as_str) {
+            Some("AccountDeactivated") => {
+                "The AppView still shows the account as deactivated".to_string()
+            }
+            Some(error) => format!("The AppView answered {}", error),
+            None => format!("The AppView answered HTTP {}", status),
+        },
+    )
+}
+
+async fn request_crawl(client: &Client, pds_url: &str) -> PropagationCheck {
+    let kind = PropagationCheckKind::CrawlRequest;
+    let Some(hostname) = relay_hostname(pds_url) else {
+        return PropagationCheck::new(
+            kind,
+            PropagationStatus::Failed,
+            format!("{} has no host name to crawl", pds_url),
+        );
+    };
+    let url = format!("{}/xrpc/com.atproto.sync.requestCrawl", PUBLIC_RELAY_URL);
+    match client
+        .post(&url)
+        .json(&json!({ "hostname": hostname }))
+        .timeout(PROPAGATION_TIMEOUT)
+        .send_traced()
+        .await
+    {
+        Ok(response) => {
+            let status = response.status().as_u16();
+            let body = response.text().await.unwrap_or_default();
+            crawl_request_check(status, &body, &hostname)
+        }
+        Err(e) => PropagationCheck::new(
+            kind,
+            PropagationStatus::Failed,
+            format!("Could not reach the public relay: {}", e),
+        ),
+    }
+}
+
+/// GET a JSON endpoint, mapping a transport failure to a pending check
+async fn poll_json(
+    client: &Client,
+    url: &str,
+    kind: PropagationCheckKind,
+    judge: fn(u16, &Value) -> PropagationCheck,
+) -> PropagationCheck {
+    match client
+        .get(url)
+        .timeout(PROPAGATION_TIMEOUT)
+        .send_traced()
+        .await
+    {
+        Ok(response) => {
+            let status = response.status().as_u16();
+            let body = response.json::<Value>().await.unwrap_or(Value::Null);
+            judge(status, &body)
+        }
+        Err(e) => PropagationCheck::new(
+            kind,
+            PropagationStatus::Pending,
+            format!("Could not reach {}: {}", kind.label(), e),
+        ),
+    }
+}
+
+/// Ask the relay to crawl the new PDS, then poll until the network shows the
+/// account, passing every intermediate report to `on_update`
+pub async fn run_propagation_monitor(
+    session: &ClientSessionCredentials,
+    on_update: impl Fn(PropagationReport),
+) -> PropagationReport {
+    let client = Client::new();
+    let mut report = PropagationReport::default();
+
+    report.set(request_crawl(&client, &session.pds).await);
+    on_update(report.clone());
+
+    let relay_url = format!(
+        "{}/xrpc/com.atproto.sync.getRepoStatus?did={}",
+        PUBLIC_RELAY_URL, session.did
+    );
+    let appview_url = format!(
+        "{}/xrpc/app.bsky.actor.getProfile?actor={}",
+        PUBLIC_APPVIEW_URL, session.did
+    );
+    while report.polls < MAX_PROPAGATION_POLLS {
+        if report.polls > 0 {
+            sleep_ms(PROPAGATION_POLL_MS).await;
+        }
+        let (relay, appview) = futures_util::join!(
+            poll_json(
+                &client,
+                &relay_url,
+                PropagationCheckKind::RelayAccount,
+                relay_account_check
+            ),
+            poll_json(
+                &client,
+                &appview_url,
+                PropagationCheckKind::AppView,
+                appview_check
+            )
+        );
+        report.set(relay);
+        report.set(appview);
+        report.polls += 1;
+        if report.is_complete() {
+            break;
+        }
+        on_update(report.clone());
+    }
+
+    report.finished = true;
+    for check in &report.checks {
+        info!(
+            "Propagation {:?}: {:?} ({})",
+            check.kind, check.status, check.detail
+        );
+    }
+    on_update(report.clone());
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relay_and_appview_interpretation() {
+        assert_eq!(
+            crawl_request_check(200, "", "pds.example").status,
+            PropagationStatus::Done
+        );
+        assert_eq!(
+            crawl_request_check(400, "rate limited", "pds.example").status,
+            PropagationStatus::Failed
+        );
+
+        let relay = relay_account_check(200, &json!({"active": true, "rev": "3k2a"}));
+        assert_eq!(relay.status, PropagationStatus::Done);
+        assert_eq!(
+            relay_account_check(200, &json!({"active": false, "status": "deactivated"})).status,
+            PropagationStatus::Pending
+        );
+        assert_eq!(
+            relay_account_check(404, &json!({"error": "RepoNotFound"})).status,
+            PropagationStatus::Pending
+        );
+
+        let appview = appview_check(400, &json!({"error": "AccountDeactivated"}));
+        assert_eq!(appview.status, PropagationStatus::Pending);
+
+        let mut report = PropagationReport::default();
+        report.set(relay);
+        report.set(appview);
+        assert!(!report.is_complete());
+        report.set(appview_check(200, &json!({"handle": "alice.example"})));
+        assert_eq!(report.checks.len(), 2);
+        assert!(report.is_complete());
+    }
+}
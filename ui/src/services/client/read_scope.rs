@@ -0,0 +1,67 @@
+//! Reading the old repository without sending the access token
+//!
+//! `com.atproto.sync.getRepo`, `getBlob` and `listBlobs` are public for an
+//! active account, so the export phases don't need the access JWT at all. A
+//! request that carries it anyway hands a token able to post, delete and
+//! deactivate to every hop and log along the way. Sync reads therefore go out
+//! anonymously first, and the token is only attached when the PDS refuses
+//! them, which happens for deactivated or taken-down accounts whose owner is
+//! still allowed to export. `SessionConfig::anonymous_sync_reads` turns this
+//! off for a PDS that rate-limits anonymous reads more tightly.
+//!
+//! This only keeps the token off the wire for the export requests. The old
+//! session, access JWT included, is still stored in browser storage as
+//! before, since the PLC, preferences and deactivation steps need it and a
+//! reload has to be able to resume; it does not limit what a compromise of
+//! that storage exposes.
+
+use reqwest::{RequestBuilder, Response, StatusCode};
+use tracing::info;
+
+use crate::services::client::http_trace::TracedSend;
+use crate::services::config::get_global_config;
+
+/// Whether a refused anonymous read may succeed for the account's owner
+pub fn needs_owner_token(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::BAD_REQUEST | StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN
+    )
+}
+
+/// Send a public sync read without credentials, repeating it with
+/// `access_jwt` only if the PDS requires the owner's token
+pub async fn send_sync_read(
+    request: RequestBuilder,
+    access_jwt: &str,
+) -> reqwest::Result<Response> {
+    if get_global_config().session.anonymous_sync_reads {
+        if let Some(anonymous) = request.try_clone() {
+            let response = anonymous.send_traced().await?;
+            if !needs_owner_token(response.status()) {
+                return Ok(response);
+            }
+            info!(
+                "Anonymous sync read refused with {}, retrying with the access token",
+                response.status()
+            );
+        }
+    }
+    request.bearer_auth(access_jwt).send_traced().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_owner_token_only_for_refusals() {
+        assert!(needs_owner_token(StatusCode::BAD_REQUEST));
+        assert!(needs_owner_token(StatusCode::UNAUTHORIZED));
+        assert!(needs_owner_token(StatusCode::FORBIDDEN));
+        assert!(!needs_owner_token(StatusCode::OK));
+        assert!(!needs_owner_token(StatusCode::NOT_FOUND));
+        assert!(!needs_owner_token(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!needs_owner_token(StatusCode::BAD_GATEWAY));
+    }
+}
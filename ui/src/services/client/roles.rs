@@ -0,0 +1,255 @@
+//! Old and new PDS handles that can't be mixed up
+//!
+//! Both sides of a migration use the same [`ClientSessionCredentials`] type, so
+//! nothing stopped a write meant for the new PDS from going out with the old
+//! session, or a request from going to a host guessed from a handle instead of
+//! the one the account lives on. [`OldPds`] and [`NewPds`] pair a client with
+//! the session for one side and only offer what that side is used for: reading
+//! and retiring the old account, writing to and activating the new one. Every
+//! call goes to the host recorded in the wrapped session.
+
+use cid::Cid;
+
+use super::errors::ClientError;
+use super::pds_api::PdsApi;
+use super::pds_client::PdsClient;
+use super::types::*;
+
+/// The PDS the account is moving away from
+#[derive(Debug, Clone)]
+pub struct OldPds<C = PdsClient> {
+    client: C,
+    session: ClientSessionCredentials,
+}
+
+/// The PDS the account is moving to
+#[derive(Debug, Clone)]
+pub struct NewPds<C = PdsClient> {
+    client: C,
+    session: ClientSessionCredentials,
+}
+
+impl<C> OldPds<C> {
+    pub fn new(client: C, session: ClientSessionCredentials) -> Self {
+        Self { client, session }
+    }
+
+    pub fn session(&self) -> &ClientSessionCredentials {
+        &self.session
+    }
+
+    pub fn did(&self) -> &str {
+        &self.session.did
+    }
+
+    pub fn host(&self) -> &str {
+        &self.session.pds
+    }
+
+    pub fn client(&self) -> &C {
+        &self.client
+    }
+}
+
+impl<C: Clone> OldPds<C> {
+    /// The same account and session at the host its DID document now lists
+    pub fn with_host(&self, pds: &str) -> Self {
+        let mut session = self.session.clone();
+        session.pds = pds.to_string();
+        Self::new(self.client.clone(), session)
+    }
+}
+
+impl<C: PdsApi + Clone> OldPds<C> {
+    /// The same account with freshly issued tokens
+    pub async fn refreshed(&self) -> Result<Self, ClientError> {
+        let session = self.client.refresh_session(&self.session).await?;
+        Ok(Self::new(self.client.clone(), session))
+    }
+}
+
+impl<C> NewPds<C> {
+    pub fn new(client: C, session: ClientSessionCredentials) -> Self {
+        Self { client, session }
+    }
+
+    pub fn session(&self) -> &ClientSessionCredentials {
+        &self.session
+    }
+
+    pub fn did(&self) -> &str {
+        &self.session.did
+    }
+
+    pub fn host(&self) -> &str {
+        &self.session.pds
+    }
+}
+
+impl<C: PdsApi> OldPds<C> {
+    pub async fn export_preferences(&self) -> Result<ClientPreferencesExportResponse, ClientError> {
+        self.client.export_preferences(&self.session).await
+    }
+
+    /// Record collections in the account's repository
+    pub async fn describe_repo_collections(&self) -> Result<Vec<String>, ClientError> {
+        self.client
+            .describe_repo_collections(&self.session, &self.session.did)
+            .await
+    }
+
+    /// One page of a collection in the account's repository
+    pub async fn list_records(
+        &self,
+        collection: &str,
+        cursor: Option<&str>,
+    ) -> Result<ClientRecordPage, ClientError> {
+        self.client
+            .list_records(&self.session, &self.session.did, collection, cursor)
+            .await
+    }
+
+    pub async fn export_blob(&self, cid: &Cid) -> Result<ClientBlobExportResponse, ClientError> {
+        self.client.export_blob(&self.session, cid).await
+    }
+
+    /// App passwords on the old account, which don't move with it
+    pub async fn list_app_passwords(&self) -> Result<Vec<ClientAppPassword>, ClientError> {
+        self.client.list_app_passwords(&self.session).await
+    }
+
+    /// Email the PLC signing token; only the current identity host can send it
+    pub async fn request_plc_token(&self) -> Result<ClientPlcTokenResponse, ClientError> {
+        self.client.request_plc_token(&self.session).await
+    }
+}
+
+impl<C: PdsApi + Clone> NewPds<C> {
+    /// The same account with freshly issued tokens
+    pub async fn refreshed(&self) -> Result<Self, ClientError> {
+        let session = self.client.refresh_session(&self.session).await?;
+        Ok(Self::new(self.client.clone(), session))
+    }
+}
+
+impl<C: PdsApi> NewPds<C> {
+    pub async fn describe_server(&self) -> Result<serde_json::Value, ClientError> {
+        self.client.describe_server(&self.session.pds).await
+    }
+
+    pub async fn import_preferences(
+        &self,
+        preferences_json: String,
+    ) -> Result<ClientPreferencesImportResponse, ClientError> {
+        self.client
+            .import_preferences(&self.session, preferences_json)
+            .await
+    }
+
+    /// Read preferences back to confirm an import
+    pub async fn export_preferences(&self) -> Result<ClientPreferencesExportResponse, ClientError> {
+        self.client.export_preferences(&self.session).await
+    }
+
+    pub async fn get_plc_recommendation(
+        &self,
+    ) -> Result<ClientPlcRecommendationResponse, ClientError> {
+        self.client.get_plc_recommendation(&self.session).await
+    }
+
+    /// Current PLC data for the account's DID
+    pub async fn fetch_plc_data(&self) -> Result<serde_json::Value, ClientError> {
+        self.client.fetch_plc_data(&self.session.did).await
+    }
+
+    /// Write a record into the new account's repository
+    pub async fn put_record(
+        &self,
+        collection: &str,
+        rkey: &str,
+        record: serde_json::Value,
+    ) -> Result<(), ClientError> {
+        self.client
+            .put_record(&self.session, collection, rkey, record)
+            .await
+    }
+
+    pub async fn upload_blob(
+        &self,
+        cid: &Cid,
+        blob_data: Vec<u8>,
+    ) -> Result<ClientBlobUploadResponse, ClientError> {
+        self.client.upload_blob(&self.session, cid, blob_data).await
+    }
+
+    pub async fn email_status(&self) -> Result<ClientEmailStatus, ClientError> {
+        self.client.get_email_status(&self.session).await
+    }
+
+    pub async fn request_email_confirmation(&self) -> Result<(), ClientError> {
+        self.client.request_email_confirmation(&self.session).await
+    }
+
+    /// Change the email; `token` is only needed once the current address is confirmed
+    pub async fn update_email(&self, email: &str, token: Option<&str>) -> Result<(), ClientError> {
+        self.client.update_email(&self.session, email, token).await
+    }
+}
+
+impl OldPds<PdsClient> {
+    pub async fn sign_plc_operation(
+        &self,
+        plc_unsigned: String,
+        token: String,
+    ) -> Result<ClientPlcSignResponse, ClientError> {
+        self.client
+            .sign_plc_operation(&self.session, plc_unsigned, token)
+            .await
+    }
+
+    pub async fn deactivate_account(&self) -> Result<ClientDeactivationResponse, ClientError> {
+        self.client.deactivate_account(&self.session).await
+    }
+
+    pub async fn check_account_status(&self) -> Result<ClientAccountStatusResponse, ClientError> {
+        self.client.check_account_status(&self.session).await
+    }
+}
+
+impl NewPds<PdsClient> {
+    pub async fn submit_plc_operation(
+        &self,
+        plc_signed: String,
+    ) -> Result<ClientPlcSubmitResponse, ClientError> {
+        self.client
+            .submit_plc_operation(&self.session, plc_signed)
+            .await
+    }
+
+    pub async fn activate_account(&self) -> Result<ClientActivationResponse, ClientError> {
+        self.client.activate_account(&self.session).await
+    }
+
+    pub async fn create_app_password(
+        &self,
+        name: &str,
+        privileged: bool,
+    ) -> Result<ClientCreatedAppPassword, ClientError> {
+        self.client
+            .create_app_password(&self.session, name, privileged)
+            .await
+    }
+
+    pub async fn check_account_status(&self) -> Result<ClientAccountStatusResponse, ClientError> {
+        self.client.check_account_status(&self.session).await
+    }
+
+    /// Start an email change; true if a token was sent to the current address
+    pub async fn request_email_update(&self) -> Result<bool, ClientError> {
+        self.client.request_email_update(&self.session).await
+    }
+
+    pub async fn confirm_email(&self, email: &str, token: &str) -> Result<(), ClientError> {
+        self.client.confirm_email(&self.session, email, token).await
+    }
+}
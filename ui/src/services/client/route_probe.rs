@@ -0,0 +1,223 @@
+//! Preflight latency probing for the endpoints a migration talks to
+//!
+//! Before anything is transferred, the source and target PDS, each DoH
+//! provider and any configured blob mirrors are timed with a couple of small
+//! requests. The fastest DoH provider becomes the resolver's primary endpoint
+//! and blob origins are tried fastest-first. The report is kept for the support
+//! bundle so slow migrations can be explained afterwards.
+
+use futures_util::future::join_all;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::info;
+
+use super::dns_over_https::{set_preferred_doh_order, DOH_PROVIDERS};
+use super::http_trace::TracedSend;
+use crate::utils::platform::now_ms;
+
+/// Requests per endpoint; the best one is kept to discount connection setup
+const PROBE_SAMPLES: usize = 2;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What a probed endpoint is used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RouteKind {
+    SourcePds,
+    TargetPds,
+    DohProvider,
+    BlobMirror,
+}
+
+/// Latency measured for one endpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteProbe {
+    pub kind: RouteKind,
+    pub url: String,
+    /// Best round trip, or `None` if every sample failed
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// All preflight measurements and the orderings chosen from them
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RouteReport {
+    pub probes: Vec<RouteProbe>,
+    pub doh_order: Vec<String>,
+    pub blob_source_order: Vec<String>,
+}
+
+static LAST_ROUTE_REPORT: Mutex<Option<RouteReport>> = Mutex::new(None);
+
+/// Most recent preflight report, if one has run
+pub fn last_route_report() -> Option<RouteReport> {
+    LAST_ROUTE_REPORT.lock().ok().and_then(|last| last.clone())
+}
+
+/// Endpoints of one kind, fastest first; unreachable ones keep their original order at the end
+pub fn rank_by_latency(probes: &[RouteProbe], kinds: &[RouteKind]) -> Vec<String> {
+    let mut candidates: Vec<&RouteProbe> = probes
+        .iter()
+        .filter(|probe| kinds.contains(&probe.kind))
+        .collect();
+    candidates.sort_by_key(|probe| probe.latency_ms.unwrap_or(u64::MAX));
+    let mut ranked: Vec<String> = Vec::new();
+    for probe in candidates {
+        if !ranked.contains(&probe.url) {
+            ranked.push(probe.url.clone());
+        }
+    }
+    ranked
+}
+
+/// URL that exercises an endpoint cheaply without authentication
+fn probe_url(kind: RouteKind, url: &str) -> String {
+    match kind {
+        RouteKind::DohProvider => format!("{}?name=atproto.com&type=16", url),
+        _ => format!("{}/xrpc/_health", url.trim_end_matches('/')),
+    }
+}
+
+async fn probe_endpoint(client: &Client, kind: RouteKind, url: String) -> RouteProbe {
+    let target = probe_url(kind, &url);
+    let mut best: Option<u64> = None;
+    let mut error = None;
+
+    for _ in 0..PROBE_SAMPLES {
+        let started = now_ms();
+        let result = client
+            .get(&target)
+            .header("accept", "application/dns-json, application/json")
+            .timeout(PROBE_TIMEOUT)
+            .send_traced()
+            .await;
+        match result {
+            // Any HTTP answer proves the route works; only the round trip matters
+            Ok(_) => {
+                let elapsed = (now_ms() - started).max(0.0) as u64;
+                best = Some(best.map_or(elapsed, |b| b.min(elapsed)));
+            }
+            Err(e) => error = Some(e.to_string()),
+        }
+    }
+
+    RouteProbe {
+        kind,
+        url,
+        latency_ms: best,
+        error: best.is_none().then_some(error).flatten(),
+    }
+}
+
+/// Probe every route concurrently, apply the DoH ordering and record the report
+pub async fn run_route_preflight(
+    source_pds: &str,
+    target_pds: &str,
+    blob_mirrors: &[String],
+) -> RouteReport {
+    let client = Client::new();
+
+    let mut endpoints = vec![
+        (RouteKind::SourcePds, source_pds.to_string()),
+        (RouteKind::TargetPds, target_pds.to_string()),
+    ];
+    endpoints.extend(
+        DOH_PROVIDERS
+            .iter()
+            .map(|url| (RouteKind::DohProvider, url.to_string())),
+    );
+    endpoints.extend(
+        blob_mirrors
+            .iter()
+            .map(|url| (RouteKind::BlobMirror, url.trim_end_matches('/').to_string())),
+    );
+
+    let probes = join_all(
+        endpoints
+            .into_iter()
+            .map(|(kind, url)| probe_endpoint(&client, kind, url)),
+    )
+    .await;
+
+    let doh_order = rank_by_latency(&probes, &[RouteKind::DohProvider]);
+    // Mirrors only go ahead of the source PDS if they actually answered faster
+    let blob_source_order =
+        rank_by_latency(&probes, &[RouteKind::SourcePds, RouteKind::BlobMirror]);
+
+    for probe in &probes {
+        match probe.latency_ms {
+            Some(ms) => info!("Route probe {:?} {}: {} ms", probe.kind, probe.url, ms),
+            None => info!(
+                "Route probe {:?} {}: unreachable ({})",
+                probe.kind,
+                probe.url,
+                probe.error.as_deref().unwrap_or("unknown error")
+            ),
+        }
+    }
+
+    set_preferred_doh_order(doh_order.clone());
+
+    let report = RouteReport {
+        probes,
+        doh_order,
+        blob_source_order,
+    };
+    if let Ok(mut last) = LAST_ROUTE_REPORT.lock() {
+        *last = Some(report.clone());
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probe(kind: RouteKind, url: &str, latency_ms: Option<u64>) -> RouteProbe {
+        RouteProbe {
+            kind,
+            url: url.to_string(),
+            latency_ms,
+            error: latency_ms.is_none().then(|| "timeout".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_rank_puts_fastest_first_and_failures_last() {
+        let probes = vec![
+            probe(RouteKind::DohProvider, "https://a.example", Some(120)),
+            probe(RouteKind::DohProvider, "https://b.example", None),
+            probe(RouteKind::DohProvider, "https://c.example", Some(30)),
+            probe(RouteKind::SourcePds, "https://pds.example", Some(10)),
+        ];
+
+        assert_eq!(
+            rank_by_latency(&probes, &[RouteKind::DohProvider]),
+            vec![
+                "https://c.example",
+                "https://a.example",
+                "https://b.example"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_blob_order_keeps_source_ahead_of_slower_mirrors() {
+        let probes = vec![
+            probe(RouteKind::SourcePds, "https://pds.example", Some(80)),
+            probe(RouteKind::BlobMirror, "https://fast.mirror", Some(20)),
+            probe(RouteKind::BlobMirror, "https://slow.mirror", Some(300)),
+        ];
+
+        assert_eq!(
+            rank_by_latency(&probes, &[RouteKind::SourcePds, RouteKind::BlobMirror]),
+            vec![
+                "https://fast.mirror",
+                "https://pds.example",
+                "https://slow.mirror"
+            ]
+        );
+    }
+}
@@ -0,0 +1,330 @@
+//! Checking that saved feeds and lists still resolve after the migration
+//!
+//! Saved and pinned feeds live in the preferences as AT-URIs of feed
+//! generator and list records, most of them in other people's repos. The
+//! preferences copy over verbatim, but a generator that went offline, a list
+//! that was deleted, or a creator who blocks the account leaves a dead entry
+//! the Bluesky app silently skips. Once the new account is active every
+//! referenced generator and list is looked up through the new PDS, which
+//! proxies to the AppView as the new account, and the dead ones are listed so
+//! they can be replaced.
+//!
+//! Like the propagation monitor, nothing here can fail the migration.
+
+use futures_util::{stream, StreamExt};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+use tracing::info;
+
+use super::http_trace::TracedSend;
+use super::types::ClientSessionCredentials;
+
+/// Service the new PDS proxies `app.bsky.*` reads to
+const APPVIEW_PROXY: &str = "did:web:api.bsky.app#bsky_appview";
+
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Lookups in flight at once
+const LOOKUP_CONCURRENCY: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SavedReferenceKind {
+    FeedGenerator,
+    List,
+}
+
+impl SavedReferenceKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SavedReferenceKind::FeedGenerator => "Feed",
+            SavedReferenceKind::List => "List",
+        }
+    }
+
+    /// Kind of record an AT-URI points at, if it is a generator or a list
+    fn of_uri(uri: &str) -> Option<Self> {
+        let collection = uri.strip_prefix("at://")?.split('/').nth(1)?;
+        match collection {
+            "app.bsky.feed.generator" => Some(SavedReferenceKind::FeedGenerator),
+            "app.bsky.graph.list" => Some(SavedReferenceKind::List),
+            _ => None,
+        }
+    }
+}
+
+/// A generator or list the preferences point at
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedReference {
+    pub uri: String,
+    pub kind: SavedReferenceKind,
+    pub pinned: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReferenceStatus {
+    Resolves,
+    /// Gone, offline or hidden from the new account
+    Dead,
+    /// The lookup itself didn't work; says nothing about the reference
+    Unchecked,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedReferenceCheck {
+    pub reference: SavedReference,
+    pub status: ReferenceStatus,
+    /// Display name when it resolved, the reason otherwise
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SavedFeedsReport {
+    pub checks: Vec<SavedReferenceCheck>,
+}
+
+impl SavedFeedsReport {
+    pub fn with_status(
+        &self,
+        status: ReferenceStatus,
+    ) -> impl Iterator<Item = &SavedReferenceCheck> {
+        self.checks
+            .iter()
+            .filter(move |check| check.status == status)
+    }
+
+    pub fn dead(&self) -> impl Iterator<Item = &SavedReferenceCheck> {
+        self.with_status(ReferenceStatus::Dead)
+    }
+}
+
+/// Every feed generator and list saved in a `getPreferences` export, from
+/// both the current `savedFeedsPrefV2` and the legacy `savedFeedsPref`
+pub fn saved_references(export: &Value) -> Vec<SavedReference> {
+    let mut references: Vec<SavedReference> = Vec::new();
+    let mut add = |uri: &str, pinned: bool| {
+        let Some(kind) = SavedReferenceKind::of_uri(uri) else {
+            return;
+        };
+        match references.iter_mut().find(|r| r.uri == uri) {
+            Some(existing) => existing.pinned |= pinned,
+            None => references.push(SavedReference {
+                uri: uri.to_string(),
+                kind,
+                pinned,
+            }),
+        }
+    };
+
+    let prefs = export
+        .get("preferences")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    for pref in prefs {
+        match pref.get("$type").and_then(Value::as_str) {
+            Some("app.bsky.actor.defs#savedFeedsPrefV2") => {
+                let items = pref.get("items").and_then(Value::as_array);
+                for item in items.into_iter().flatten() {
+                    if let Some(uri) = item.get("value").and_then(Value::as_str) {
+                        let pinned = item.get("pinned").and_then(Value::as_bool) == Some(true);
+                        add(uri, pinned);
+                    }
+                }
+            }
+            Some("app.bsky.actor.defs#savedFeedsPref") => {
+                for (field, pinned) in [("pinned", true), ("saved", false)] {
+                    let uris = pref.get(field).and_then(Value::as_array);
+                    for uri in uris.into_iter().flatten().filter_map(Value::as_str) {
+                        add(uri, pinned);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    references
+}
+
+/// Judge the AppView's `getFeedGenerator` or `getList` answer for a reference
+pub fn judge_reference(
+    reference: &SavedReference,
+    status: u16,
+    body: &Value,
+) -> SavedReferenceCheck {
+    let check = |status: ReferenceStatus, detail: String| SavedReferenceCheck {
+        reference: reference.clone(),
+        status,
+        detail,
+    };
+
+    if (200..300).contains(&status) {
+        let (view, name_field) = match reference.kind {
+            SavedReferenceKind::FeedGenerator => (body.get("view"), "displayName"),
+            SavedReferenceKind::List => (body.get("list"), "name"),
+        };
+        let name = view
+            .and_then(|view| view.get(name_field))
+            .and_then(Value::as_str)
+            .unwrap_or("Untitled")
+            .to_string();
+        // The AppView still knows generators whose service stopped answering
+        let flag = |field: &str| body.get(field).and_then(Value::as_bool) != Some(false);
+        if reference.kind == SavedReferenceKind::FeedGenerator && !flag("isOnline") {
+            return check(
+                ReferenceStatus::Dead,
+                format!("\"{}\" exists but its feed service is offline", name),
+            );
+        }
+        if reference.kind == SavedReferenceKind::FeedGenerator && !flag("isValid") {
+            return check(
+                ReferenceStatus::Dead,
+                format!("\"{}\" exists but its feed service is misconfigured", name),
+            );
+        }
+        return check(ReferenceStatus::Resolves, name);
+    }
+
+    let error = body.get("error").and_then(Value::as_str);
+    let message = body.get("message").and_then(Value::as_str).unwrap_or("");
+    match (status, error) {
+        (400, Some("BlockedActor" | "BlockedByActor")) => check(
+            ReferenceStatus::Dead,
+            "Its creator and this account block each other".to_string(),
+        ),
+        (400 | 404, Some("NotFound" | "UnknownFeed" | "InvalidRequest")) => check(
+            ReferenceStatus::Dead,
+            if message.is_empty() {
+                "No longer exists".to_string()
+            } else {
+                format!("No longer exists ({})", message)
+            },
+        ),
+        (_, Some(error)) => check(
+            ReferenceStatus::Unchecked,
+            format!("The AppView answered {}", error),
+        ),
+        (_, None) => check(
+            ReferenceStatus::Unchecked,
+            format!("The AppView answered HTTP {}", status),
+        ),
+    }
+}
+
+/// Look one reference up through the new PDS's AppView proxy
+async fn check_reference(
+    client: &Client,
+    session: &ClientSessionCredentials,
+    reference: SavedReference,
+) -> SavedReferenceCheck {
+    let (method, param) = match reference.kind {
+        SavedReferenceKind::FeedGenerator => ("app.bsky.feed.getFeedGenerator", "feed"),
+        SavedReferenceKind::List => ("app.bsky.graph.getList", "list"),
+    };
+    let url = format!("{}/xrpc/{}", session.pds, method);
+    let mut query = vec![(param, reference.uri.as_str())];
+    if reference.kind == SavedReferenceKind::List {
+        query.push(("limit", "1"));
+    }
+
+    match client
+        .get(&url)
+        .query(&query)
+        .bearer_auth(&session.access_jwt)
+        .header("atproto-proxy", APPVIEW_PROXY)
+        .timeout(LOOKUP_TIMEOUT)
+        .send_traced()
+        .await
+    {
+        Ok(response) => {
+            let status = response.status().as_u16();
+            let body = response.json::<Value>().await.unwrap_or(Value::Null);
+            judge_reference(&reference, status, &body)
+        }
+        Err(e) => SavedReferenceCheck {
+            reference,
+            status: ReferenceStatus::Unchecked,
+            detail: format!("Could not reach the new PDS: {}", e),
+        },
+    }
+}
+
+/// Look up every feed and list saved in `export` as the new account
+pub async fn check_saved_feeds(
+    session: &ClientSessionCredentials,
+    export: &Value,
+) -> SavedFeedsReport {
+    let client = Client::new();
+    let checks: Vec<SavedReferenceCheck> = stream::iter(saved_references(export))
+        .map(|reference| check_reference(&client, session, reference))
+        .buffered(LOOKUP_CONCURRENCY)
+        .collect()
+        .await;
+
+    let report = SavedFeedsReport { checks };
+    info!(
+        "Saved feeds check: {} resolve, {} dead, {} unchecked",
+        report.with_status(ReferenceStatus::Resolves).count(),
+        report.dead().count(),
+        report.with_status(ReferenceStatus::Unchecked).count()
+    );
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_saved_references_and_judgement() {
+        let feed = "at://did:plc:creator/app.bsky.feed.generator/whats-hot";
+        let list = "at://did:plc:creator/app.bsky.graph.list/3kabc";
+        let export = json!({"preferences": [
+            {"$type": "app.bsky.actor.defs#savedFeedsPrefV2", "items": [
+                {"id": "1", "type": "timeline", "value": "following", "pinned": true},
+                {"id": "2", "type": "feed", "value": feed, "pinned": false},
+                {"id": "3", "type": "list", "value": list, "pinned": true},
+            ]},
+            {"$type": "app.bsky.actor.defs#savedFeedsPref", "pinned": [feed], "saved": [feed]},
+            {"$type": "app.bsky.actor.defs#adultContentPref", "enabled": false},
+        ]});
+
+        let references = saved_references(&export);
+        assert_eq!(references.len(), 2);
+        assert_eq!(references[0].kind, SavedReferenceKind::FeedGenerator);
+        assert!(references[0].pinned, "legacy pin carries over");
+        assert_eq!(references[1].kind, SavedReferenceKind::List);
+
+        let online = judge_reference(
+            &references[0],
+            200,
+            &json!({"view": {"displayName": "What's Hot"}, "isOnline": true, "isValid": true}),
+        );
+        assert_eq!(online.status, ReferenceStatus::Resolves);
+        assert_eq!(online.detail, "What's Hot");
+
+        let offline = judge_reference(
+            &references[0],
+            200,
+            &json!({"view": {"displayName": "What's Hot"}, "isOnline": false, "isValid": true}),
+        );
+        assert_eq!(offline.status, ReferenceStatus::Dead);
+
+        let deleted = judge_reference(
+            &references[1],
+            400,
+            &json!({"error": "InvalidRequest", "message": "List not found"}),
+        );
+        assert_eq!(deleted.status, ReferenceStatus::Dead);
+
+        let flaky = judge_reference(&references[1], 502, &Value::Null);
+        assert_eq!(flaky.status, ReferenceStatus::Unchecked);
+
+        let report = SavedFeedsReport {
+            checks: vec![online, offline, deleted, flaky],
+        };
+        assert_eq!(report.dead().count(), 2);
+    }
+}
@@ -0,0 +1,814 @@
+//! Strict response schemas for the PDS endpoints used during migration
+//!
+//! Every endpoint output is checked against a field list before it is deserialized, so a
+//! misbehaving PDS produces "unexpected response shape at field X" instead of a generic
+//! deserialize error. Fields the schema doesn't know about are logged and otherwise
+//! ignored, since PDS implementations are free to return extra data.
+
+use cid::Cid;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+use thiserror::Error;
+use tracing::warn;
+
+use super::errors::ClientError;
+use super::types::ClientMissingBlob;
+
+/// Expected JSON type of a response field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    String,
+    Bool,
+    Integer,
+    /// String that parses as a CID
+    Cid,
+    Object,
+    /// Array with arbitrary elements (e.g. open unions)
+    Array,
+    /// Array of strings
+    Strings,
+    /// Array of objects with the given shape
+    Objects(&'static [FieldSpec]),
+    /// Any JSON value, including null
+    Any,
+}
+
+impl FieldKind {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            FieldKind::String => "string",
+            FieldKind::Bool => "boolean",
+            FieldKind::Integer => "integer",
+            FieldKind::Cid => "CID string",
+            FieldKind::Object => "object",
+            FieldKind::Array => "array",
+            FieldKind::Strings => "array of strings",
+            FieldKind::Objects(_) => "array of objects",
+            FieldKind::Any => "any value",
+        }
+    }
+}
+
+/// A single field in an endpoint's output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldSpec {
+    pub name: &'static str,
+    pub kind: FieldKind,
+    pub required: bool,
+}
+
+impl FieldSpec {
+    pub const fn required(name: &'static str, kind: FieldKind) -> Self {
+        Self {
+            name,
+            kind,
+            required: true,
+        }
+    }
+
+    /// Optional fields may be absent or null
+    pub const fn optional(name: &'static str, kind: FieldKind) -> Self {
+        Self {
+            name,
+            kind,
+            required: false,
+        }
+    }
+}
+
+/// A response that doesn't match its endpoint schema
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error(
+    "unexpected response shape from {endpoint} at field {path}: expected {expected}, got {found}"
+)]
+pub struct SchemaError {
+    pub endpoint: &'static str,
+    /// Path of the offending field, e.g. `blobs[2].cid` (`(root)` for the body itself)
+    pub path: String,
+    pub expected: String,
+    pub found: String,
+}
+
+impl From<SchemaError> for ClientError {
+    fn from(err: SchemaError) -> Self {
+        ClientError::PdsOperationFailed {
+            operation: err.endpoint.to_string(),
+            message: err.to_string(),
+        }
+    }
+}
+
+/// An endpoint output with a strict field list
+pub trait ResponseSchema: DeserializeOwned {
+    /// XRPC method name, used in error messages and logs
+    const ENDPOINT: &'static str;
+    const FIELDS: &'static [FieldSpec];
+}
+
+const ROOT_PATH: &str = "(root)";
+
+/// Check a response body against a field list
+/// Returns the paths of fields not covered by the schema
+pub fn validate_shape(
+    endpoint: &'static str,
+    fields: &'static [FieldSpec],
+    value: &Value,
+) -> Result<Vec<String>, SchemaError> {
+    let mut unknown = Vec::new();
+    check_object(endpoint, fields, value, "", &mut unknown)?;
+    Ok(unknown)
+}
+
+/// Validate a response against `T`'s schema without deserializing it
+///
+/// For endpoints whose body is passed on verbatim (PLC operations, preferences).
+pub fn check_response<T: ResponseSchema>(value: &Value) -> Result<(), SchemaError> {
+    let unknown = validate_shape(T::ENDPOINT, T::FIELDS, value)?;
+    log_unknown_fields(T::ENDPOINT, &unknown);
+    Ok(())
+}
+
+/// Validate a response against `T`'s schema and deserialize it
+pub fn parse_response<T: ResponseSchema>(value: Value) -> Result<T, SchemaError> {
+    check_response::<T>(&value)?;
+    serde_json::from_value(value).map_err(|e| SchemaError {
+        endpoint: T::ENDPOINT,
+        path: ROOT_PATH.to_string(),
+        expected: format!("{} output", T::ENDPOINT),
+        found: e.to_string(),
+    })
+}
+
+fn log_unknown_fields(endpoint: &str, unknown: &[String]) {
+    for path in unknown {
+        warn!("{}: ignoring unknown response field '{}'", endpoint, path);
+    }
+}
+
+fn join_path(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", prefix, name)
+    }
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "float",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn mismatch(endpoint: &'static str, path: &str, expected: &str, found: &Value) -> SchemaError {
+    SchemaError {
+        endpoint,
+        path: if path.is_empty() {
+            ROOT_PATH.to_string()
+        } else {
+            path.to_string()
+        },
+        expected: expected.to_string(),
+        found: json_type(found).to_string(),
+    }
+}
+
+fn check_object(
+    endpoint: &'static str,
+    fields: &'static [FieldSpec],
+    value: &Value,
+    prefix: &str,
+    unknown: &mut Vec<String>,
+) -> Result<(), SchemaError> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| mismatch(endpoint, prefix, "object", value))?;
+
+    for spec in fields {
+        let path = join_path(prefix, spec.name);
+        match object.get(spec.name) {
+            None | Some(Value::Null) if !spec.required => {}
+            None => {
+                return Err(SchemaError {
+                    endpoint,
+                    path,
+                    expected: format!("required {}", spec.kind.describe()),
+                    found: "missing field".to_string(),
+                })
+            }
+            Some(field) => check_value(endpoint, spec.kind, field, &path, unknown)?,
+        }
+    }
+
+    unknown.extend(
+        object
+            .keys()
+            .filter(|key| !fields.iter().any(|spec| spec.name == key.as_str()))
+            .map(|key| join_path(prefix, key)),
+    );
+    Ok(())
+}
+
+fn check_value(
+    endpoint: &'static str,
+    kind: FieldKind,
+    value: &Value,
+    path: &str,
+    unknown: &mut Vec<String>,
+) -> Result<(), SchemaError> {
+    let matches = match kind {
+        FieldKind::String => value.is_string(),
+        FieldKind::Bool => value.is_boolean(),
+        FieldKind::Integer => value.is_i64() || value.is_u64(),
+        FieldKind::Cid => value.as_str().is_some_and(|s| Cid::try_from(s).is_ok()),
+        FieldKind::Object => value.is_object(),
+        FieldKind::Array => value.is_array(),
+        FieldKind::Any => true,
+        FieldKind::Strings | FieldKind::Objects(_) => {
+            let items = value
+                .as_array()
+                .ok_or_else(|| mismatch(endpoint, path, kind.describe(), value))?;
+            for (index, item) in items.iter().enumerate() {
+                let item_path = format!("{}[{}]", path, index);
+                match kind {
+                    FieldKind::Objects(fields) => {
+                        check_object(endpoint, fields, item, &item_path, unknown)?
+                    }
+                    _ if !item.is_string() => {
+                        return Err(mismatch(endpoint, &item_path, "string", item))
+                    }
+                    _ => {}
+                }
+            }
+            true
+        }
+    };
+
+    if matches {
+        Ok(())
+    } else {
+        Err(mismatch(endpoint, path, kind.describe(), value))
+    }
+}
+
+/// com.atproto.server.createSession output
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateSessionOutput {
+    pub access_jwt: String,
+    pub refresh_jwt: String,
+    pub handle: String,
+    pub did: String,
+    pub active: Option<bool>,
+    pub status: Option<String>,
+}
+
+impl ResponseSchema for CreateSessionOutput {
+    const ENDPOINT: &'static str = "com.atproto.server.createSession";
+    const FIELDS: &'static [FieldSpec] = &[
+        FieldSpec::required("accessJwt", FieldKind::String),
+        FieldSpec::required("refreshJwt", FieldKind::String),
+        FieldSpec::required("handle", FieldKind::String),
+        FieldSpec::required("did", FieldKind::String),
+        FieldSpec::optional("didDoc", FieldKind::Object),
+        FieldSpec::optional("email", FieldKind::String),
+        FieldSpec::optional("emailConfirmed", FieldKind::Bool),
+        FieldSpec::optional("emailAuthFactor", FieldKind::Bool),
+        FieldSpec::optional("active", FieldKind::Bool),
+        FieldSpec::optional("status", FieldKind::String),
+    ];
+}
+
+/// com.atproto.server.createAccount output
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAccountOutput {
+    pub access_jwt: String,
+    pub refresh_jwt: String,
+    pub handle: String,
+    pub did: String,
+}
+
+impl ResponseSchema for CreateAccountOutput {
+    const ENDPOINT: &'static str = "com.atproto.server.createAccount";
+    const FIELDS: &'static [FieldSpec] = &[
+        FieldSpec::required("accessJwt", FieldKind::String),
+        FieldSpec::required("refreshJwt", FieldKind::String),
+        FieldSpec::required("handle", FieldKind::String),
+        FieldSpec::required("did", FieldKind::String),
+        FieldSpec::optional("didDoc", FieldKind::Object),
+    ];
+}
+
+/// com.atproto.server.refreshSession output
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshSessionOutput {
+    pub access_jwt: String,
+    pub refresh_jwt: String,
+    pub handle: String,
+    pub did: String,
+}
+
+impl ResponseSchema for RefreshSessionOutput {
+    const ENDPOINT: &'static str = "com.atproto.server.refreshSession";
+    const FIELDS: &'static [FieldSpec] = &[
+        FieldSpec::required("accessJwt", FieldKind::String),
+        FieldSpec::required("refreshJwt", FieldKind::String),
+        FieldSpec::required("handle", FieldKind::String),
+        FieldSpec::required("did", FieldKind::String),
+        FieldSpec::optional("didDoc", FieldKind::Object),
+        FieldSpec::optional("active", FieldKind::Bool),
+        FieldSpec::optional("status", FieldKind::String),
+    ];
+}
+
+/// Entry in a listAppPasswords response
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AppPasswordRef {
+    pub name: String,
+    pub privileged: Option<bool>,
+}
+
+/// com.atproto.server.listAppPasswords output
+#[derive(Deserialize, Debug, Clone)]
+pub struct ListAppPasswordsOutput {
+    pub passwords: Vec<AppPasswordRef>,
+}
+
+impl ResponseSchema for ListAppPasswordsOutput {
+    const ENDPOINT: &'static str = "com.atproto.server.listAppPasswords";
+    const FIELDS: &'static [FieldSpec] = &[FieldSpec::required(
+        "passwords",
+        FieldKind::Objects(&[
+            FieldSpec::required("name", FieldKind::String),
+            FieldSpec::required("createdAt", FieldKind::String),
+            FieldSpec::optional("privileged", FieldKind::Bool),
+        ]),
+    )];
+}
+
+/// com.atproto.server.createAppPassword output
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAppPasswordOutput {
+    pub name: String,
+    pub password: String,
+    pub privileged: Option<bool>,
+}
+
+impl ResponseSchema for CreateAppPasswordOutput {
+    const ENDPOINT: &'static str = "com.atproto.server.createAppPassword";
+    const FIELDS: &'static [FieldSpec] = &[
+        FieldSpec::required("name", FieldKind::String),
+        FieldSpec::required("password", FieldKind::String),
+        FieldSpec::required("createdAt", FieldKind::String),
+        FieldSpec::optional("privileged", FieldKind::Bool),
+    ];
+}
+
+/// com.atproto.server.getSession output, the email fields only
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GetSessionEmailOutput {
+    pub email: Option<String>,
+    pub email_confirmed: Option<bool>,
+}
+
+impl ResponseSchema for GetSessionEmailOutput {
+    const ENDPOINT: &'static str = "com.atproto.server.getSession";
+    const FIELDS: &'static [FieldSpec] = &[
+        FieldSpec::required("handle", FieldKind::String),
+        FieldSpec::required("did", FieldKind::String),
+        FieldSpec::optional("email", FieldKind::String),
+        FieldSpec::optional("emailConfirmed", FieldKind::Bool),
+    ];
+}
+
+/// com.atproto.server.requestEmailUpdate output
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestEmailUpdateOutput {
+    pub token_required: bool,
+}
+
+impl ResponseSchema for RequestEmailUpdateOutput {
+    const ENDPOINT: &'static str = "com.atproto.server.requestEmailUpdate";
+    const FIELDS: &'static [FieldSpec] = &[FieldSpec::required("tokenRequired", FieldKind::Bool)];
+}
+
+/// com.atproto.server.checkAccountStatus output
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckAccountStatusOutput {
+    pub activated: bool,
+    pub valid_did: bool,
+    pub repo_commit: String,
+    pub repo_rev: String,
+    pub repo_blocks: i64,
+    pub indexed_records: i64,
+    pub private_state_values: i64,
+    pub expected_blobs: i64,
+    pub imported_blobs: i64,
+}
+
+impl ResponseSchema for CheckAccountStatusOutput {
+    const ENDPOINT: &'static str = "com.atproto.server.checkAccountStatus";
+    const FIELDS: &'static [FieldSpec] = &[
+        FieldSpec::required("activated", FieldKind::Bool),
+        FieldSpec::required("validDid", FieldKind::Bool),
+        FieldSpec::required("repoCommit", FieldKind::Cid),
+        FieldSpec::required("repoRev", FieldKind::String),
+        FieldSpec::required("repoBlocks", FieldKind::Integer),
+        FieldSpec::required("indexedRecords", FieldKind::Integer),
+        FieldSpec::required("privateStateValues", FieldKind::Integer),
+        FieldSpec::required("expectedBlobs", FieldKind::Integer),
+        FieldSpec::required("importedBlobs", FieldKind::Integer),
+    ];
+}
+
+/// com.atproto.server.getServiceAuth output
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetServiceAuthOutput {
+    pub token: String,
+}
+
+impl ResponseSchema for GetServiceAuthOutput {
+    const ENDPOINT: &'static str = "com.atproto.server.getServiceAuth";
+    const FIELDS: &'static [FieldSpec] = &[FieldSpec::required("token", FieldKind::String)];
+}
+
+/// com.atproto.server.describeServer output
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DescribeServerOutput {
+    pub did: String,
+    pub available_user_domains: Vec<String>,
+    pub invite_code_required: Option<bool>,
+}
+
+impl ResponseSchema for DescribeServerOutput {
+    const ENDPOINT: &'static str = "com.atproto.server.describeServer";
+    const FIELDS: &'static [FieldSpec] = &[
+        FieldSpec::required("did", FieldKind::String),
+        FieldSpec::required("availableUserDomains", FieldKind::Strings),
+        FieldSpec::optional("inviteCodeRequired", FieldKind::Bool),
+        FieldSpec::optional("phoneVerificationRequired", FieldKind::Bool),
+        FieldSpec::optional("links", FieldKind::Object),
+        FieldSpec::optional("contact", FieldKind::Object),
+    ];
+}
+
+/// com.atproto.repo.describeRepo output
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DescribeRepoOutput {
+    pub handle: String,
+    pub did: String,
+    pub collections: Vec<String>,
+    pub handle_is_correct: bool,
+}
+
+impl ResponseSchema for DescribeRepoOutput {
+    const ENDPOINT: &'static str = "com.atproto.repo.describeRepo";
+    const FIELDS: &'static [FieldSpec] = &[
+        FieldSpec::required("handle", FieldKind::String),
+        FieldSpec::required("did", FieldKind::String),
+        FieldSpec::required("didDoc", FieldKind::Any),
+        FieldSpec::required("collections", FieldKind::Strings),
+        FieldSpec::required("handleIsCorrect", FieldKind::Bool),
+    ];
+}
+
+/// Entry in a listMissingBlobs page
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingBlobRef {
+    /// String form of the CID (`Cid`'s own serde impl expects bytes)
+    pub cid: String,
+    pub record_uri: String,
+}
+
+/// com.atproto.repo.listMissingBlobs output
+#[derive(Deserialize, Debug, Clone)]
+pub struct ListMissingBlobsOutput {
+    pub blobs: Vec<MissingBlobRef>,
+    pub cursor: Option<String>,
+}
+
+impl ListMissingBlobsOutput {
+    /// Convert to client blob entries; CIDs were already checked by the schema
+    pub fn missing_blobs(&self) -> Vec<ClientMissingBlob> {
+        self.blobs
+            .iter()
+            .filter_map(|blob| {
+                Cid::try_from(blob.cid.as_str())
+                    .ok()
+                    .map(|cid| ClientMissingBlob {
+                        cid,
+                        record_uri: blob.record_uri.clone(),
+                    })
+            })
+            .collect()
+    }
+}
+
+impl ResponseSchema for ListMissingBlobsOutput {
+    const ENDPOINT: &'static str = "com.atproto.repo.listMissingBlobs";
+    const FIELDS: &'static [FieldSpec] = &[
+        FieldSpec::required(
+            "blobs",
+            FieldKind::Objects(&[
+                FieldSpec::required("cid", FieldKind::Cid),
+                FieldSpec::required("recordUri", FieldKind::String),
+            ]),
+        ),
+        FieldSpec::optional("cursor", FieldKind::String),
+    ];
+}
+
+/// com.atproto.repo.listRecords output
+#[derive(Deserialize, Debug, Clone)]
+pub struct ListRecordsOutput {
+    pub records: Vec<ListedRecord>,
+    pub cursor: Option<String>,
+}
+
+/// Entry in a listRecords page
+#[derive(Deserialize, Debug, Clone)]
+pub struct ListedRecord {
+    pub uri: String,
+    pub cid: String,
+    pub value: Value,
+}
+
+impl ResponseSchema for ListRecordsOutput {
+    const ENDPOINT: &'static str = "com.atproto.repo.listRecords";
+    const FIELDS: &'static [FieldSpec] = &[
+        FieldSpec::required(
+            "records",
+            FieldKind::Objects(&[
+                FieldSpec::required("uri", FieldKind::String),
+                FieldSpec::required("cid", FieldKind::Cid),
+                FieldSpec::required("value", FieldKind::Object),
+            ]),
+        ),
+        FieldSpec::optional("cursor", FieldKind::String),
+    ];
+}
+
+/// com.atproto.repo.putRecord output
+#[derive(Deserialize, Debug, Clone)]
+pub struct PutRecordOutput {
+    pub uri: String,
+    pub cid: String,
+}
+
+impl ResponseSchema for PutRecordOutput {
+    const ENDPOINT: &'static str = "com.atproto.repo.putRecord";
+    const FIELDS: &'static [FieldSpec] = &[
+        FieldSpec::required("uri", FieldKind::String),
+        FieldSpec::required("cid", FieldKind::Cid),
+    ];
+}
+
+/// com.atproto.sync.listBlobs output
+#[derive(Deserialize, Debug, Clone)]
+pub struct ListBlobsOutput {
+    pub cids: Vec<String>,
+    pub cursor: Option<String>,
+}
+
+impl ResponseSchema for ListBlobsOutput {
+    const ENDPOINT: &'static str = "com.atproto.sync.listBlobs";
+    const FIELDS: &'static [FieldSpec] = &[
+        FieldSpec::required("cids", FieldKind::Strings),
+        FieldSpec::optional("cursor", FieldKind::String),
+    ];
+}
+
+/// com.atproto.identity.getRecommendedDidCredentials output
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RecommendedDidCredentialsOutput {
+    pub rotation_keys: Option<Vec<String>>,
+    pub also_known_as: Option<Vec<String>>,
+    pub verification_methods: Option<Value>,
+    pub services: Option<Value>,
+}
+
+impl ResponseSchema for RecommendedDidCredentialsOutput {
+    const ENDPOINT: &'static str = "com.atproto.identity.getRecommendedDidCredentials";
+    const FIELDS: &'static [FieldSpec] = &[
+        FieldSpec::optional("rotationKeys", FieldKind::Strings),
+        FieldSpec::optional("alsoKnownAs", FieldKind::Strings),
+        FieldSpec::optional("verificationMethods", FieldKind::Object),
+        FieldSpec::optional("services", FieldKind::Object),
+    ];
+}
+
+/// com.atproto.server.reserveSigningKey output
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReserveSigningKeyOutput {
+    pub signing_key: String,
+}
+
+impl ResponseSchema for ReserveSigningKeyOutput {
+    const ENDPOINT: &'static str = "com.atproto.server.reserveSigningKey";
+    const FIELDS: &'static [FieldSpec] = &[FieldSpec::required("signingKey", FieldKind::String)];
+}
+
+/// com.atproto.identity.signPlcOperation output
+#[derive(Deserialize, Debug, Clone)]
+pub struct SignPlcOperationOutput {
+    pub operation: Value,
+}
+
+impl ResponseSchema for SignPlcOperationOutput {
+    const ENDPOINT: &'static str = "com.atproto.identity.signPlcOperation";
+    const FIELDS: &'static [FieldSpec] = &[FieldSpec::required("operation", FieldKind::Object)];
+}
+
+/// app.bsky.actor.getPreferences output
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetPreferencesOutput {
+    pub preferences: Vec<Value>,
+}
+
+impl ResponseSchema for GetPreferencesOutput {
+    const ENDPOINT: &'static str = "app.bsky.actor.getPreferences";
+    const FIELDS: &'static [FieldSpec] = &[FieldSpec::required("preferences", FieldKind::Array)];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const BLOB_CID: &str = "bafkreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm";
+
+    fn session_fixture() -> Value {
+        json!({
+            "accessJwt": "access",
+            "refreshJwt": "refresh",
+            "handle": "alice.test",
+            "did": "did:plc:alice",
+            "active": true
+        })
+    }
+
+    fn missing_blobs_fixture() -> Value {
+        json!({
+            "blobs": [
+                { "cid": BLOB_CID, "recordUri": "at://did:plc:alice/app.bsky.feed.post/1" },
+                { "cid": BLOB_CID, "recordUri": "at://did:plc:alice/app.bsky.feed.post/2" }
+            ],
+            "cursor": "next"
+        })
+    }
+
+    fn error_path<T: ResponseSchema + std::fmt::Debug>(value: Value) -> String {
+        parse_response::<T>(value).unwrap_err().path
+    }
+
+    #[test]
+    fn test_valid_responses_parse() {
+        let session: CreateSessionOutput = parse_response(session_fixture()).unwrap();
+        assert_eq!(session.did, "did:plc:alice");
+        assert_eq!(session.active, Some(true));
+
+        let blobs: ListMissingBlobsOutput = parse_response(missing_blobs_fixture()).unwrap();
+        assert_eq!(blobs.missing_blobs().len(), 2);
+        assert_eq!(blobs.cursor.as_deref(), Some("next"));
+
+        // Optional fields may be null
+        let mut value = missing_blobs_fixture();
+        value["cursor"] = Value::Null;
+        let blobs: ListMissingBlobsOutput = parse_response(value).unwrap();
+        assert_eq!(blobs.cursor, None);
+    }
+
+    #[test]
+    fn test_malformed_fixtures_report_field_path() {
+        let mut value = session_fixture();
+        value.as_object_mut().unwrap().remove("accessJwt");
+        let err = parse_response::<CreateSessionOutput>(value).unwrap_err();
+        assert_eq!(err.path, "accessJwt");
+        assert_eq!(err.found, "missing field");
+        assert!(err.to_string().starts_with(
+            "unexpected response shape from com.atproto.server.createSession at field accessJwt"
+        ));
+
+        let mut value = missing_blobs_fixture();
+        value["blobs"][1]["cid"] = json!(42);
+        let err = parse_response::<ListMissingBlobsOutput>(value).unwrap_err();
+        assert_eq!(err.path, "blobs[1].cid");
+        assert_eq!(err.expected, "CID string");
+        assert_eq!(err.found, "integer");
+
+        let mut value = missing_blobs_fixture();
+        value["blobs"][0]["cid"] = json!("not-a-cid");
+        assert_eq!(error_path::<ListMissingBlobsOutput>(value), "blobs[0].cid");
+
+        assert_eq!(
+            error_path::<ListBlobsOutput>(json!({ "cids": ["a", null] })),
+            "cids[1]"
+        );
+        assert_eq!(
+            error_path::<CreateSessionOutput>(json!([session_fixture()])),
+            "(root)"
+        );
+        assert_eq!(
+            error_path::<GetServiceAuthOutput>(json!({ "token": null })),
+            "token"
+        );
+    }
+
+    #[test]
+    fn test_unknown_fields_are_collected() {
+        let mut value = missing_blobs_fixture();
+        value["extra"] = json!(1);
+        value["blobs"][1]["size"] = json!(100);
+
+        let unknown = validate_shape(
+            ListMissingBlobsOutput::ENDPOINT,
+            ListMissingBlobsOutput::FIELDS,
+            &value,
+        )
+        .unwrap();
+        assert_eq!(
+            unknown,
+            vec!["blobs[1].size".to_string(), "extra".to_string()]
+        );
+        assert!(parse_response::<ListMissingBlobsOutput>(value).is_ok());
+    }
+
+    /// Drop or corrupt each required field of a valid fixture in turn
+    fn fuzz_required_fields<T: ResponseSchema + std::fmt::Debug>(valid: Value) {
+        assert!(
+            parse_response::<T>(valid.clone()).is_ok(),
+            "fixture for {}",
+            T::ENDPOINT
+        );
+
+        for spec in T::FIELDS.iter().filter(|spec| spec.required) {
+            let mut missing = valid.clone();
+            missing.as_object_mut().unwrap().remove(spec.name);
+            assert_eq!(error_path::<T>(missing), spec.name);
+
+            let wrong = match spec.kind {
+                FieldKind::String | FieldKind::Cid => json!(7),
+                _ => json!("wrong"),
+            };
+            let mut corrupted = valid.clone();
+            corrupted[spec.name] = wrong;
+            assert_eq!(error_path::<T>(corrupted), spec.name);
+        }
+    }
+
+    #[test]
+    fn test_fuzz_required_fields() {
+        fuzz_required_fields::<CreateSessionOutput>(session_fixture());
+        fuzz_required_fields::<CreateAccountOutput>(session_fixture());
+        fuzz_required_fields::<RefreshSessionOutput>(session_fixture());
+        fuzz_required_fields::<ListMissingBlobsOutput>(missing_blobs_fixture());
+        fuzz_required_fields::<ListBlobsOutput>(json!({ "cids": [BLOB_CID] }));
+        fuzz_required_fields::<ListRecordsOutput>(json!({
+            "records": [{
+                "uri": "at://did:plc:alice/app.bsky.feed.post/3k",
+                "cid": BLOB_CID,
+                "value": { "text": "hi" }
+            }]
+        }));
+        fuzz_required_fields::<PutRecordOutput>(json!({
+            "uri": "at://did:plc:alice/app.bsky.feed.post/3k",
+            "cid": BLOB_CID
+        }));
+        fuzz_required_fields::<GetServiceAuthOutput>(json!({ "token": "jwt" }));
+        fuzz_required_fields::<SignPlcOperationOutput>(json!({ "operation": {} }));
+        fuzz_required_fields::<GetPreferencesOutput>(json!({ "preferences": [] }));
+        fuzz_required_fields::<DescribeServerOutput>(json!({
+            "did": "did:web:pds.test",
+            "availableUserDomains": [".pds.test"]
+        }));
+        fuzz_required_fields::<CheckAccountStatusOutput>(json!({
+            "activated": false,
+            "validDid": true,
+            "repoCommit": BLOB_CID,
+            "repoRev": "3l",
+            "repoBlocks": 10,
+            "indexedRecords": 5,
+            "privateStateValues": 0,
+            "expectedBlobs": 2,
+            "importedBlobs": 1
+        }));
+    }
+}
@@ -0,0 +1,173 @@
+//! Session-scoped cache of `com.atproto.server.describeServer` answers
+//!
+//! The PDS form, handle suggestions, account creation and the PLC fallback
+//! each used to describe the same two servers on their own, so a step could
+//! act on a different server DID or domain list than the one the user saw.
+//! The first answer for a host is kept here, keyed by its normalized URL and
+//! mirrored to `sessionStorage` like the identity cache, and every later
+//! caller reads that copy until it is older than [`SERVER_INFO_TTL_SECS`] or
+//! the user refreshes it. Reachability probes keep calling the server directly.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tracing::debug;
+
+use super::errors::ClientError;
+use super::schema::{parse_response, DescribeServerOutput};
+use super::types::current_time_secs;
+use crate::utils::handle::normalize_pds_url;
+use crate::utils::platform::{storage_delete, storage_get, storage_set, StorageArea};
+
+/// Session storage key for the mirrored cache
+const SERVER_INFO_CACHE_STORAGE_KEY: &str = "server_info_cache";
+
+/// How long a describeServer answer is trusted before the server is asked again
+pub const SERVER_INFO_TTL_SECS: u64 = 10 * 60;
+
+static SERVER_INFO_CACHE: Mutex<Option<ServerInfoCache>> = Mutex::new(None);
+
+/// A describeServer answer with the fields the migration relies on pulled out
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerInfo {
+    /// The server's service DID, the audience for service auth and the PLC operation
+    pub did: String,
+    pub available_user_domains: Vec<String>,
+    /// The answer as received, for callers that read other fields
+    pub response: Value,
+}
+
+impl ServerInfo {
+    pub fn from_response(response: Value) -> Result<Self, ClientError> {
+        let output = parse_response::<DescribeServerOutput>(response.clone())?;
+        Ok(Self {
+            did: output.did,
+            available_user_domains: output.available_user_domains,
+            response,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedServerInfo {
+    info: ServerInfo,
+    cached_at_secs: u64,
+}
+
+/// describeServer answers keyed by normalized PDS URL
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServerInfoCache {
+    servers: HashMap<String, CachedServerInfo>,
+}
+
+impl ServerInfoCache {
+    fn fresh(&self, pds_url: &str, now_secs: u64) -> Option<&CachedServerInfo> {
+        self.servers
+            .get(&normalize_pds_url(pds_url))
+            .filter(|entry| now_secs.saturating_sub(entry.cached_at_secs) < SERVER_INFO_TTL_SECS)
+    }
+
+    pub fn get(&self, pds_url: &str, now_secs: u64) -> Option<ServerInfo> {
+        self.fresh(pds_url, now_secs)
+            .map(|entry| entry.info.clone())
+    }
+
+    /// Seconds since the cached answer was fetched, while it is still fresh
+    pub fn age_secs(&self, pds_url: &str, now_secs: u64) -> Option<u64> {
+        self.fresh(pds_url, now_secs)
+            .map(|entry| now_secs.saturating_sub(entry.cached_at_secs))
+    }
+
+    pub fn insert(&mut self, pds_url: &str, info: ServerInfo, now_secs: u64) {
+        self.servers.insert(
+            normalize_pds_url(pds_url),
+            CachedServerInfo {
+                info,
+                cached_at_secs: now_secs,
+            },
+        );
+    }
+
+    pub fn invalidate(&mut self, pds_url: &str) {
+        self.servers.remove(&normalize_pds_url(pds_url));
+    }
+}
+
+/// Run `f` against the process-wide cache, loading it from session storage on first use
+fn with_cache<R>(f: impl FnOnce(&mut ServerInfoCache) -> R) -> Option<R> {
+    let mut guard = SERVER_INFO_CACHE.lock().ok()?;
+    let cache = guard.get_or_insert_with(|| {
+        storage_get(StorageArea::Session, SERVER_INFO_CACHE_STORAGE_KEY).unwrap_or_default()
+    });
+    Some(f(cache))
+}
+
+/// Run `f` against the cache and mirror the result to session storage
+fn update_cache(f: impl FnOnce(&mut ServerInfoCache)) {
+    with_cache(|cache| {
+        f(cache);
+        if let Err(e) = storage_set(StorageArea::Session, SERVER_INFO_CACHE_STORAGE_KEY, &*cache) {
+            debug!("Failed to persist server info cache: {:?}", e);
+        }
+    });
+}
+
+/// Cached describeServer answer for a PDS, if it was fetched recently
+pub fn cached_server_info(pds_url: &str) -> Option<ServerInfo> {
+    with_cache(|cache| cache.get(pds_url, current_time_secs())).flatten()
+}
+
+/// How long ago the cached answer for a PDS was fetched
+pub fn server_info_age_secs(pds_url: &str) -> Option<u64> {
+    with_cache(|cache| cache.age_secs(pds_url, current_time_secs())).flatten()
+}
+
+pub fn cache_server_info(pds_url: &str, info: &ServerInfo) {
+    update_cache(|cache| cache.insert(pds_url, info.clone(), current_time_secs()));
+}
+
+/// Forget a PDS's answer so the next caller asks the server again
+pub fn invalidate_server_info(pds_url: &str) {
+    debug!("Invalidating cached server info for {}", pds_url);
+    update_cache(|cache| cache.invalidate(pds_url));
+}
+
+/// Drop the whole cache, in memory and in session storage
+pub fn clear_server_info_cache() {
+    if let Ok(mut guard) = SERVER_INFO_CACHE.lock() {
+        *guard = Some(ServerInfoCache::default());
+    }
+    storage_delete(StorageArea::Session, SERVER_INFO_CACHE_STORAGE_KEY);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_lookup_normalizes_urls_expires_and_invalidates() {
+        let info = ServerInfo::from_response(json!({
+            "did": "did:web:pds.example.com",
+            "availableUserDomains": [".pds.example.com"],
+            "inviteCodeRequired": false
+        }))
+        .unwrap();
+        assert_eq!(info.did, "did:web:pds.example.com");
+        assert!(ServerInfo::from_response(json!({ "availableUserDomains": [] })).is_err());
+
+        let mut cache = ServerInfoCache::default();
+        cache.insert("pds.example.com/", info.clone(), 1_000);
+
+        assert_eq!(cache.get("https://pds.example.com", 1_060), Some(info));
+        assert_eq!(cache.age_secs("https://pds.example.com", 1_060), Some(60));
+        assert_eq!(
+            cache.get("https://pds.example.com", 1_000 + SERVER_INFO_TTL_SECS),
+            None
+        );
+
+        cache.invalidate("https://pds.example.com");
+        assert_eq!(cache.get("https://pds.example.com", 1_000), None);
+    }
+}
@@ -1,10 +1,12 @@
 use anyhow::Result;
-use gloo_storage::{LocalStorage, SessionStorage, Storage};
 use tracing::{info, warn};
 
+use super::clock_skew::server_now_secs;
 use super::errors::ClientError;
-use super::types::{current_time_secs, ClientSessionCredentials};
+use super::types::ClientSessionCredentials;
 use crate::migration::types::MigrationProgress;
+use crate::services::config::{get_global_config, SessionConfig};
+use crate::utils::platform::{storage_delete, storage_get, storage_set, StorageArea};
 
 /// Session manager for secure credential storage and management
 pub struct SessionManager {
@@ -37,13 +39,13 @@ impl SessionManager {
             })?;
 
         if self.use_session_storage {
-            SessionStorage::set(&self.storage_key, session_json).map_err(|e| {
+            storage_set(StorageArea::Session, &self.storage_key, session_json).map_err(|e| {
                 ClientError::StorageError {
                     message: format!("Failed to store session in sessionStorage: {:?}", e),
                 }
             })?;
         } else {
-            LocalStorage::set(&self.storage_key, session_json).map_err(|e| {
+            storage_set(StorageArea::Local, &self.storage_key, session_json).map_err(|e| {
                 ClientError::StorageError {
                     message: format!("Failed to store session in localStorage: {:?}", e),
                 }
@@ -57,12 +59,12 @@ impl SessionManager {
     /// Get stored session credentials with validation
     pub fn get_session(&self) -> Result<Option<ClientSessionCredentials>, ClientError> {
         let session_json = if self.use_session_storage {
-            match SessionStorage::get::<String>(&self.storage_key) {
+            match storage_get::<String>(StorageArea::Session, &self.storage_key) {
                 Ok(json) => json,
                 Err(_) => return Ok(None),
             }
         } else {
-            match LocalStorage::get::<String>(&self.storage_key) {
+            match storage_get::<String>(StorageArea::Local, &self.storage_key) {
                 Ok(json) => json,
                 Err(_) => return Ok(None),
             }
@@ -86,9 +88,9 @@ impl SessionManager {
     /// Clear stored session
     pub fn clear_session(&self) -> Result<(), ClientError> {
         if self.use_session_storage {
-            SessionStorage::delete(&self.storage_key);
+            storage_delete(StorageArea::Session, &self.storage_key);
         } else {
-            LocalStorage::delete(&self.storage_key);
+            storage_delete(StorageArea::Local, &self.storage_key);
         }
         info!("Session cleared");
         Ok(())
@@ -133,8 +135,10 @@ impl SessionManager {
             })?;
 
         // Always use localStorage for migration progress (needs persistence across sessions)
-        LocalStorage::set(&storage_key, progress_json).map_err(|e| ClientError::StorageError {
-            message: format!("Failed to store migration progress: {:?}", e),
+        storage_set(StorageArea::Local, &storage_key, progress_json).map_err(|e| {
+            ClientError::StorageError {
+                message: format!("Failed to store migration progress: {:?}", e),
+            }
         })?;
 
         info!("Migration progress stored for DID: {}", did);
@@ -148,7 +152,7 @@ impl SessionManager {
     ) -> Result<Option<MigrationProgress>, ClientError> {
         let storage_key = format!("migration_progress_{}", did);
 
-        let progress_json = match LocalStorage::get::<String>(&storage_key) {
+        let progress_json = match storage_get::<String>(StorageArea::Local, &storage_key) {
             Ok(json) => json,
             Err(_) => return Ok(None),
         };
@@ -164,64 +168,96 @@ impl SessionManager {
     /// Clear migration progress for a DID
     pub fn clear_migration_progress(&self, did: &str) -> Result<(), ClientError> {
         let storage_key = format!("migration_progress_{}", did);
-        LocalStorage::delete(&storage_key);
+        storage_delete(StorageArea::Local, &storage_key);
         info!("Migration progress cleared for DID: {}", did);
         Ok(())
     }
 }
 
+/// Local assessment of an access token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenStatus {
+    /// Token is valid and outside the refresh window
+    Valid,
+    /// Token is valid but expires within the refresh window
+    NeedsRefresh,
+    /// Token expired, even allowing for clock skew
+    Expired,
+    /// Token is opaque or has no usable `exp` claim; only the PDS can tell
+    Unknown,
+}
+
 /// JWT token utilities
+///
+/// Parsing is deliberately lenient: some PDS forks issue tokens with base64url padding,
+/// fractional or string `exp` claims, or opaque non-JWT tokens. Anything that cannot be
+/// interpreted locally is reported as `TokenStatus::Unknown` rather than expired.
 pub struct JwtUtils;
 
 impl JwtUtils {
-    /// Parse JWT expiration time (basic implementation without verification)
-    pub fn get_expiration(jwt: &str) -> Option<u64> {
+    /// Decode the JWT payload claims (basic implementation without verification)
+    pub fn decode_claims(jwt: &str) -> Option<serde_json::Value> {
         let parts: Vec<&str> = jwt.split('.').collect();
         if parts.len() != 3 {
             return None;
         }
 
-        // Decode the payload (second part)
-        let payload_b64 = parts[1];
-
-        // Add padding if needed
-        let padded = match payload_b64.len() % 4 {
-            2 => format!("{}==", payload_b64),
-            3 => format!("{}=", payload_b64),
-            _ => payload_b64.to_string(),
-        };
-
-        // Decode base64
+        // Accept both base64url (per spec) and standard alphabets, with or without padding
         use base64::Engine;
-        let decoded = base64::engine::general_purpose::STANDARD
-            .decode(&padded)
+        let payload_b64 = parts[1].trim_end_matches('=');
+        let decoded = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(payload_b64)
+            .or_else(|_| base64::engine::general_purpose::STANDARD_NO_PAD.decode(payload_b64))
             .ok()?;
-        let payload_str = String::from_utf8(decoded).ok()?;
 
-        // Parse JSON to get exp claim
-        let payload: serde_json::Value = serde_json::from_str(&payload_str).ok()?;
-        payload.get("exp")?.as_u64()
+        serde_json::from_slice(&decoded).ok()
     }
 
-    /// Check if JWT is expired
-    pub fn is_expired(jwt: &str) -> bool {
-        if let Some(exp) = Self::get_expiration(jwt) {
-            let now = current_time_secs();
-            now >= exp
-        } else {
-            true // Assume expired if we can't parse
+    /// Parse JWT expiration time, accepting integer, fractional and string `exp` claims
+    pub fn get_expiration(jwt: &str) -> Option<u64> {
+        let exp = Self::decode_claims(jwt)?.get("exp")?.clone();
+        match exp {
+            serde_json::Value::Number(n) => n
+                .as_u64()
+                .or_else(|| n.as_f64().filter(|f| *f >= 0.0).map(|f| f as u64)),
+            serde_json::Value::String(s) => s.trim().parse::<f64>().ok().map(|f| f as u64),
+            _ => None,
         }
     }
 
-    /// Check if JWT needs refresh (within 5 minutes of expiry)
-    pub fn needs_refresh(jwt: &str) -> bool {
-        if let Some(exp) = Self::get_expiration(jwt) {
-            let now = current_time_secs();
-            now >= (exp - 300) // 5 minutes before expiry
+    /// Classify a token against the given tolerances at time `now`
+    pub fn status_at(jwt: &str, now: u64, config: &SessionConfig) -> TokenStatus {
+        let Some(exp) = Self::get_expiration(jwt) else {
+            return TokenStatus::Unknown;
+        };
+
+        let expires_with_skew = exp.saturating_add(config.clock_skew_secs);
+        if now >= expires_with_skew {
+            TokenStatus::Expired
+        } else if now >= exp.saturating_sub(config.refresh_window_secs) {
+            TokenStatus::NeedsRefresh
         } else {
-            true
+            TokenStatus::Valid
         }
     }
+
+    /// Classify a token using the global session configuration
+    pub fn status(jwt: &str) -> TokenStatus {
+        Self::status_at(jwt, server_now_secs(), &get_global_config().session)
+    }
+
+    /// Check if JWT is definitely expired; opaque tokens are passed through
+    pub fn is_expired(jwt: &str) -> bool {
+        Self::status(jwt) == TokenStatus::Expired
+    }
+
+    /// Check if JWT needs refresh (within the configured refresh window of expiry)
+    pub fn needs_refresh(jwt: &str) -> bool {
+        matches!(
+            Self::status(jwt),
+            TokenStatus::NeedsRefresh | TokenStatus::Expired
+        )
+    }
 }
 
 /// Migration-specific session manager
@@ -263,6 +299,9 @@ impl MigrationSessionManager {
     pub fn clear_all_sessions(&self) -> Result<(), ClientError> {
         self.old_session_manager.clear_session()?;
         self.new_session_manager.clear_session()?;
+        super::identity_cache::clear_identity_cache();
+        super::server_info_cache::clear_server_info_cache();
+        super::host_stats::clear_host_stats();
         info!("All migration sessions cleared");
         Ok(())
     }
@@ -330,6 +369,47 @@ mod tests {
         assert!(JwtUtils::needs_refresh(expired_jwt));
     }
 
+    #[test]
+    fn test_jwt_tolerant_parsing() {
+        use base64::Engine;
+        let engine = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        let token = |claims: &str| format!("e30.{}.sig", engine.encode(claims));
+        let config = SessionConfig::default();
+
+        assert_eq!(
+            JwtUtils::get_expiration(&token(r#"{"exp":1000.5}"#)),
+            Some(1000)
+        );
+        assert_eq!(
+            JwtUtils::get_expiration(&token(r#"{"exp":"1000"}"#)),
+            Some(1000)
+        );
+        assert_eq!(JwtUtils::get_expiration(&token(r#"{"sub":"x"}"#)), None);
+
+        // Expired by less than the allowed clock skew is still accepted
+        let jwt = token(r#"{"exp":1000}"#);
+        assert_eq!(
+            JwtUtils::status_at(&jwt, 1030, &config),
+            TokenStatus::NeedsRefresh
+        );
+        assert_eq!(
+            JwtUtils::status_at(&jwt, 1060, &config),
+            TokenStatus::Expired
+        );
+        assert_eq!(JwtUtils::status_at(&jwt, 0, &config), TokenStatus::Valid);
+
+        // Missing exp and opaque tokens are left for the PDS to judge
+        assert_eq!(
+            JwtUtils::status_at(&token(r#"{"sub":"x"}"#), 0, &config),
+            TokenStatus::Unknown
+        );
+        assert_eq!(
+            JwtUtils::status_at("opaque-token", 0, &config),
+            TokenStatus::Unknown
+        );
+        assert!(!JwtUtils::is_expired("opaque-token"));
+    }
+
     #[test]
     fn test_migration_session_manager() {
         let migration_manager = MigrationSessionManager::new();
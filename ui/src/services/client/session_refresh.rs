@@ -8,7 +8,9 @@ use super::auth::account::refresh_session_impl;
 use super::pds_client::PdsClient;
 use super::types::ClientSessionCredentials;
 use super::ClientError;
-use crate::{console_info, console_warn};
+use crate::console_info;
+use crate::services::config::{get_global_config, RetryPhase};
+use crate::services::retry::with_retries;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use tracing::{error, instrument};
@@ -39,7 +41,7 @@ impl RefreshableSessionProvider {
 
     /// Get current time in seconds
     fn current_time_secs() -> u64 {
-        (js_sys::Date::now() / 1000.0) as u64
+        crate::utils::platform::now_secs()
     }
 
     /// Get a fresh access token, refreshing if necessary
@@ -107,38 +109,12 @@ impl RefreshableSessionProvider {
 
     /// Get a fresh access token with retry on failure
     ///
-    /// This is a more robust version that retries token refresh if the first attempt fails.
+    /// This is a more robust version that retries token refresh if the first attempt fails,
+    /// within the configured authentication retry budget.
     /// Useful for handling transient network issues during long-running migrations.
-    pub async fn get_fresh_token_with_retry(
-        &self,
-        max_retries: u32,
-    ) -> Result<String, ClientError> {
-        let mut attempt = 0;
-        let mut last_error = None;
-
-        while attempt < max_retries {
-            match self.get_fresh_token().await {
-                Ok(token) => return Ok(token),
-                Err(e) => {
-                    attempt += 1;
-                    last_error = Some(e);
-
-                    if attempt < max_retries {
-                        console_warn!(
-                            "[RefreshableSessionProvider] Token refresh failed (attempt {}/{}), retrying...",
-                            attempt,
-                            max_retries
-                        );
-
-                        // Exponential backoff: 1s, 2s, 4s, etc.
-                        let delay_ms = (1000 * (2_u64.pow(attempt - 1))).min(10000);
-                        gloo_timers::future::TimeoutFuture::new(delay_ms as u32).await;
-                    }
-                }
-            }
-        }
-
-        Err(last_error.unwrap_or(ClientError::SessionExpired))
+    pub async fn get_fresh_token_with_retry(&self) -> Result<String, ClientError> {
+        let retry = get_global_config().retry;
+        with_retries(&retry, RetryPhase::Auth, || self.get_fresh_token()).await
     }
 
     /// Force an immediate refresh of the session token
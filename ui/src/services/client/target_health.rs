@@ -0,0 +1,472 @@
+//! Operational readiness checks for the new PDS
+//!
+//! A self-hosted PDS can answer describeServer and create accounts while still
+//! being unable to finish a migration: a blobstore without write access or disk
+//! space, an upload limit set by a reverse proxy, or a firehose the public relay
+//! can't reach. Once the account exists, and before any repository or blob is
+//! transferred, the target is asked to store a tiny test blob, its advertised
+//! limits are read from describeServer, and the public relay is asked whether it
+//! is crawling the host. The PDS software is fingerprinted as well, adding any
+//! guidance known for that implementation. A failed upload stops the migration;
+//! the other findings are warnings. The report is kept for the support bundle.
+//!
+//! atproto has no endpoint for deleting a blob. The test blob is never
+//! referenced by a record, so the PDS discards it with its other temporary
+//! uploads.
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::info;
+
+use super::http_trace::TracedSend;
+use super::pds_fingerprint::{applicable_issues, detect_pds_software, PdsFingerprint};
+use super::types::ClientSessionCredentials;
+use crate::utils::byte_format::format_size;
+
+/// Relay asked whether it is crawling the new PDS
+pub const PUBLIC_RELAY_URL: &str = "https://bsky.network";
+
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Contents of the test blob; small enough for any upload limit
+const TEST_BLOB: &[u8] = b"tektite migration readiness check\n";
+
+/// describeServer fields some implementations use to advertise upload limits
+const BLOB_LIMIT_FIELDS: &[&str] = &["maxBlobSize", "blobUploadLimit", "maxBlobBytes"];
+const IMPORT_LIMIT_FIELDS: &[&str] = &["maxImportSize", "maxRepoImportSize", "maxImportBytes"];
+
+/// Outcome of one readiness check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HealthStatus {
+    Ok,
+    /// The migration can go ahead, but something will probably need fixing
+    Warning,
+    /// The migration would fail partway through
+    Failed,
+}
+
+/// What a readiness check looked at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HealthCheckKind {
+    BlobUpload,
+    ServerLimits,
+    RelayReachability,
+    Software,
+}
+
+impl HealthCheckKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            HealthCheckKind::BlobUpload => "Test blob upload",
+            HealthCheckKind::ServerLimits => "Upload limits",
+            HealthCheckKind::RelayReachability => "Relay reachability",
+            HealthCheckKind::Software => "PDS software",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthCheck {
+    pub kind: HealthCheckKind,
+    pub status: HealthStatus,
+    pub detail: String,
+}
+
+impl HealthCheck {
+    fn new(kind: HealthCheckKind, status: HealthStatus, detail: impl Into<String>) -> Self {
+        Self {
+            kind,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Size limits the target advertises, when it advertises any
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServerLimits {
+    pub max_blob_bytes: Option<u64>,
+    pub max_import_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TargetHealthReport {
+    pub checks: Vec<HealthCheck>,
+    pub limits: ServerLimits,
+    /// What the target runs; selects implementation-specific workarounds
+    #[serde(default)]
+    pub software: PdsFingerprint,
+}
+
+impl TargetHealthReport {
+    /// First check that should stop the migration
+    pub fn failure(&self) -> Option<&HealthCheck> {
+        self.checks
+            .iter()
+            .find(|check| check.status == HealthStatus::Failed)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &HealthCheck> {
+        self.checks
+            .iter()
+            .filter(|check| check.status == HealthStatus::Warning)
+    }
+}
+
+static LAST_TARGET_HEALTH: Mutex<Option<TargetHealthReport>> = Mutex::new(None);
+
+/// Most recent readiness report, if one has run
+pub fn last_target_health() -> Option<TargetHealthReport> {
+    LAST_TARGET_HEALTH.lock().ok().and_then(|last| last.clone())
+}
+
+/// Read advertised limits from a describeServer response, top level or under `limits`
+pub fn limits_from_describe(describe: &Value) -> ServerLimits {
+    let find = |fields: &[&str]| {
+        fields.iter().find_map(|field| {
+            describe
+                .get(field)
+                .or_else(|| describe.pointer(&format!("/limits/{}", field)))
+                .and_then(Value::as_u64)
+        })
+    };
+    ServerLimits {
+        max_blob_bytes: find(BLOB_LIMIT_FIELDS),
+        max_import_bytes: find(IMPORT_LIMIT_FIELDS),
+    }
+}
+
+fn limits_check(limits: &ServerLimits) -> HealthCheck {
+    let describe =
+        |limit: Option<u64>| limit.map_or_else(|| "not advertised".to_string(), format_size);
+    HealthCheck::new(
+        HealthCheckKind::ServerLimits,
+        HealthStatus::Ok,
+        format!(
+            "Largest blob: {}; largest repository import: {}",
+            describe(limits.max_blob_bytes),
+            describe(limits.max_import_bytes)
+        ),
+    )
+}
+
+/// Report the detected software with any guidance known for it
+pub fn software_check(fingerprint: &PdsFingerprint) -> HealthCheck {
+    let issues = applicable_issues(fingerprint);
+    let status = if issues
+        .iter()
+        .any(|issue| issue.severity == HealthStatus::Warning)
+    {
+        HealthStatus::Warning
+    } else {
+        HealthStatus::Ok
+    };
+    let mut detail = format!("The new PDS runs {}", fingerprint.describe());
+    for issue in issues {
+        detail.push_str(". ");
+        detail.push_str(issue.guidance);
+    }
+    HealthCheck::new(HealthCheckKind::Software, status, detail)
+}
+
+/// Judge the uploadBlob response for the test blob
+pub fn blob_upload_check(status: u16, body: &str, sent_bytes: usize) -> HealthCheck {
+    let kind = HealthCheckKind::BlobUpload;
+    match status {
+        200..=299 => {
+            let stored = serde_json::from_str::<Value>(body)
+                .ok()
+                .and_then(|response| response.pointer("/blob/size").and_then(Value::as_u64));
+            match stored {
+                Some(size) if size == sent_bytes as u64 => HealthCheck::new(
+                    kind,
+                    HealthStatus::Ok,
+                    "The new PDS stored a test blob",
+                ),
+                Some(size) => HealthCheck::new(
+                    kind,
+                    HealthStatus::Failed,
+                    format!(
+                        "The new PDS stored {} of {} test bytes; its blobstore is truncating uploads",
+                        size, sent_bytes
+                    ),
+                ),
+                None => HealthCheck::new(
+                    kind,
+                    HealthStatus::Warning,
+                    "The new PDS accepted a test blob but did not describe it",
+                ),
+            }
+        }
+        413 => HealthCheck::new(
+            kind,
+            HealthStatus::Failed,
+            "The new PDS (or a proxy in front of it) rejected a tiny test blob as too large; check its request body size limit",
+        ),
+        401 | 403 => HealthCheck::new(
+            kind,
+            HealthStatus::Failed,
+            format!(
+                "The new PDS refused a blob upload for the new account (HTTP {})",
+                status
+            ),
+        ),
+        _ => HealthCheck::new(
+            kind,
+            HealthStatus::Failed,
+            format!(
+                "The new PDS could not store a test blob (HTTP {}: {}); its blobstore may be full or not writable",
+                status,
+                body.trim()
+            ),
+        ),
+    }
+}
+
+/// Host name the relay knows a PDS by
+pub fn relay_hostname(pds_url: &str) -> Option<String> {
+    let host = reqwest::Url::parse(pds_url)
+        .ok()?
+        .host_str()?
+        .to_lowercase();
+    Some(host)
+}
+
+fn is_private_host(host: &str) -> bool {
+    host == "localhost"
+        || host.ends_with(".local")
+        || host.ends_with(".internal")
+        || host
+            .parse::<std::net::IpAddr>()
+            .map(|ip| match ip {
+                std::net::IpAddr::V4(v4) => v4.is_private() || v4.is_loopback(),
+                std::net::IpAddr::V6(v6) => v6.is_loopback(),
+            })
+            .unwrap_or(false)
+}
+
+/// Judge the relay's `com.atproto.sync.getHostStatus` answer for the new PDS
+pub fn relay_check(status: u16, body: &Value, hostname: &str) -> HealthCheck {
+    let kind = HealthCheckKind::RelayReachability;
+    if (200..300).contains(&status) {
+        return match body.get("status").and_then(Value::as_str) {
+            Some("active") | Some("idle") => HealthCheck::new(
+                kind,
+                HealthStatus::Ok,
+                format!("The public relay is crawling {}", hostname),
+            ),
+            Some("offline") => HealthCheck::new(
+                kind,
+                HealthStatus::Warning,
+                format!(
+                    "The public relay can't connect to {}; make sure com.atproto.sync.subscribeRepos is reachable over a websocket, or your posts won't reach the network",
+                    hostname
+                ),
+            ),
+            Some(other) => HealthCheck::new(
+                kind,
+                HealthStatus::Warning,
+                format!(
+                    "The public relay reports {} as {}; your posts may not reach the network",
+                    hostname, other
+                ),
+            ),
+            None => HealthCheck::new(
+                kind,
+                HealthStatus::Warning,
+                "The public relay answered without a host status",
+            ),
+        };
+    }
+
+    if body.get("error").and_then(Value::as_str) == Some("HostNotFound") {
+        return HealthCheck::new(
+            kind,
+            HealthStatus::Warning,
+            format!(
+                "The public relay doesn't know {} yet; the PDS has to request a crawl (PDS_CRAWLERS) before your posts reach the network",
+                hostname
+            ),
+        );
+    }
+    HealthCheck::new(
+        kind,
+        HealthStatus::Warning,
+        format!("Could not check the relay (HTTP {})", status),
+    )
+}
+
+async fn check_blob_upload(client: &Client, session: &ClientSessionCredentials) -> HealthCheck {
+    let url = format!(
+        "{}/xrpc/com.atproto.repo.uploadBlob",
+        session.pds.trim_end_matches('/')
+    );
+    let result = client
+        .post(&url)
+        .header("Authorization", format!("Bearer {}", session.access_jwt))
+        .header("Content-Type", "application/octet-stream")
+        .header("Content-Length", TEST_BLOB.len().to_string())
+        .body(TEST_BLOB.to_vec())
+        .timeout(HEALTH_TIMEOUT)
+        .send_traced()
+        .await;
+    match result {
+        Ok(response) => {
+            let status = response.status().as_u16();
+            let body = response.text().await.unwrap_or_default();
+            blob_upload_check(status, &body, TEST_BLOB.len())
+        }
+        Err(e) => HealthCheck::new(
+            HealthCheckKind::BlobUpload,
+            HealthStatus::Failed,
+            format!("Could not upload a test blob to the new PDS: {}", e),
+        ),
+    }
+}
+
+async fn check_relay(client: &Client, pds_url: &str) -> HealthCheck {
+    let kind = HealthCheckKind::RelayReachability;
+    let Some(hostname) = relay_hostname(pds_url) else {
+        return HealthCheck::new(
+            kind,
+            HealthStatus::Warning,
+            format!("{} has no host name to look up", pds_url),
+        );
+    };
+    if is_private_host(&hostname) {
+        return HealthCheck::new(
+            kind,
+            HealthStatus::Warning,
+            format!(
+                "{} is not a public host name; the public relay can't crawl it",
+                hostname
+            ),
+        );
+    }
+
+    let url = format!(
+        "{}/xrpc/com.atproto.sync.getHostStatus?hostname={}",
+        PUBLIC_RELAY_URL, hostname
+    );
+    match client.get(&url).timeout(HEALTH_TIMEOUT).send_traced().await {
+        Ok(response) => {
+            let status = response.status().as_u16();
+            let body = response.json::<Value>().await.unwrap_or(Value::Null);
+            relay_check(status, &body, &hostname)
+        }
+        Err(e) => HealthCheck::new(
+            kind,
+            HealthStatus::Warning,
+            format!("Could not reach the public relay: {}", e),
+        ),
+    }
+}
+
+/// Run every readiness check against the new account and record the report
+pub async fn run_target_health_check(
+    session: &ClientSessionCredentials,
+    describe: &Value,
+) -> TargetHealthReport {
+    let client = Client::new();
+    let limits = limits_from_describe(describe);
+
+    let (upload, relay, software) = futures_util::join!(
+        check_blob_upload(&client, session),
+        check_relay(&client, &session.pds),
+        detect_pds_software(&session.pds, describe)
+    );
+    let report = TargetHealthReport {
+        checks: vec![
+            upload,
+            limits_check(&limits),
+            relay,
+            software_check(&software),
+        ],
+        limits,
+        software,
+    };
+
+    for check in &report.checks {
+        info!(
+            "Target health {:?}: {:?} ({})",
+            check.kind, check.status, check.detail
+        );
+    }
+    if let Ok(mut last) = LAST_TARGET_HEALTH.lock() {
+        *last = Some(report.clone());
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_blob_upload_and_limits_checks() {
+        let stored = json!({"blob": {"$type": "blob", "ref": {"$link": "bafk"}, "size": 34}});
+        assert_eq!(
+            blob_upload_check(200, &stored.to_string(), 34).status,
+            HealthStatus::Ok
+        );
+        assert_eq!(
+            blob_upload_check(200, &stored.to_string(), 40).status,
+            HealthStatus::Failed
+        );
+        assert_eq!(
+            blob_upload_check(500, "ENOSPC", 34).status,
+            HealthStatus::Failed
+        );
+        assert_eq!(blob_upload_check(413, "", 34).status, HealthStatus::Failed);
+
+        let limits = limits_from_describe(&json!({
+            "did": "did:web:pds.example",
+            "limits": {"maxBlobSize": 104857600u64},
+            "maxImportSize": 524288000u64,
+        }));
+        assert_eq!(limits.max_blob_bytes, Some(104_857_600));
+        assert_eq!(limits.max_import_bytes, Some(524_288_000));
+        assert_eq!(
+            limits_from_describe(&json!({"did": "did:web:pds.example"})),
+            ServerLimits::default()
+        );
+    }
+
+    #[test]
+    fn test_relay_status_interpretation() {
+        let check = |status, body: Value| relay_check(status, &body, "pds.example").status;
+        assert_eq!(check(200, json!({"status": "active"})), HealthStatus::Ok);
+        assert_eq!(check(200, json!({"status": "idle"})), HealthStatus::Ok);
+        assert_eq!(
+            check(200, json!({"status": "offline"})),
+            HealthStatus::Warning
+        );
+        assert_eq!(
+            check(400, json!({"error": "HostNotFound"})),
+            HealthStatus::Warning
+        );
+
+        assert_eq!(
+            relay_hostname("https://PDS.example:443/").as_deref(),
+            Some("pds.example")
+        );
+        assert!(is_private_host("localhost"));
+        assert!(is_private_host("192.168.1.20"));
+        assert!(!is_private_host("pds.example"));
+
+        let report = TargetHealthReport {
+            checks: vec![
+                relay_check(400, &json!({"error": "HostNotFound"}), "pds.example"),
+                blob_upload_check(500, "", 34),
+            ],
+            limits: ServerLimits::default(),
+            software: PdsFingerprint::default(),
+        };
+        assert_eq!(report.warnings().count(), 1);
+        assert_eq!(report.failure().unwrap().kind, HealthCheckKind::BlobUpload);
+    }
+}
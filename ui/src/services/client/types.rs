@@ -1,22 +1,17 @@
 use cid::Cid;
 use serde::{Deserialize, Serialize};
 
-#[cfg(target_arch = "wasm32")]
-use js_sys;
+use super::clock_skew::server_now_secs;
+use crate::services::config::get_global_config;
 
 /// Get current time in seconds since UNIX epoch (WASM compatible)
 pub fn current_time_secs() -> u64 {
-    #[cfg(target_arch = "wasm32")]
-    {
-        (js_sys::Date::now() / 1000.0) as u64
-    }
-    #[cfg(not(target_arch = "wasm32"))]
-    {
-        std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs()
-    }
+    crate::utils::platform::now_secs()
+}
+
+/// Get current time in milliseconds since UNIX epoch (WASM compatible)
+pub fn current_time_ms() -> f64 {
+    crate::utils::platform::now_ms()
 }
 
 /// DNS-over-HTTPS response structure matching Cloudflare's API
@@ -82,18 +77,26 @@ pub struct ClientSessionCredentials {
 impl ClientSessionCredentials {
     pub fn is_expired(&self) -> bool {
         if let Some(expires_at) = self.expires_at {
-            let now = current_time_secs();
-            now >= expires_at
+            let now = server_now_secs();
+            let skew = get_global_config().session.clock_skew_secs;
+            now >= expires_at.saturating_add(skew)
         } else {
             false
         }
     }
 
+    /// Seconds until the access token expires, if its expiry is known
+    pub fn remaining_lifetime_secs(&self) -> Option<u64> {
+        self.expires_at
+            .map(|expires_at| expires_at.saturating_sub(server_now_secs()))
+    }
+
     pub fn needs_refresh(&self) -> bool {
         if let Some(expires_at) = self.expires_at {
-            let now = current_time_secs();
-            // Refresh if within 5 minutes of expiry
-            now >= (expires_at - 300)
+            let now = server_now_secs();
+            // Refresh if within the configured window of expiry
+            let window = get_global_config().session.refresh_window_secs;
+            now >= expires_at.saturating_sub(window)
         } else {
             false
         }
@@ -133,6 +136,13 @@ pub struct ClientCreateAccountRequest {
     /// Required when PDS describeServer returns phoneVerificationRequired: true
     #[serde(rename = "verificationCode", skip_serializing_if = "Option::is_none")]
     pub verification_code: Option<String>,
+    /// Phone number the verification code was texted to, when verified by SMS
+    #[serde(rename = "verificationPhone", skip_serializing_if = "Option::is_none")]
+    pub verification_phone: Option<String>,
+    /// Same for every attempt at creating this account, so a retry after a lost
+    /// response isn't taken as a second signup
+    #[serde(skip)]
+    pub idempotency_key: Option<String>,
 }
 
 /// Account creation response
@@ -259,6 +269,9 @@ pub struct ClientPlcRecommendationResponse {
     pub success: bool,
     pub message: String,
     pub plc_unsigned: Option<String>,
+    /// The PDS doesn't implement getRecommendedDidCredentials at all
+    #[serde(default)]
+    pub endpoint_missing: bool,
 }
 
 /// PLC token response
@@ -297,6 +310,58 @@ pub struct ClientDeactivationResponse {
     pub message: String,
 }
 
+/// Account deletion (or deletion request) response
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ClientAccountDeleteResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+/// Phone verification request response
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ClientPhoneVerificationResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+/// An app password on an account; the secret itself is only returned at creation
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ClientAppPassword {
+    pub name: String,
+    /// Allowed to read and send direct messages
+    pub privileged: bool,
+}
+
+/// A newly created app password, shown to the user once and never stored
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientCreatedAppPassword {
+    pub name: String,
+    pub password: String,
+    pub privileged: bool,
+}
+
+/// The account's contact email as the PDS has it
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ClientEmailStatus {
+    pub email: Option<String>,
+    pub confirmed: bool,
+}
+
+/// A record as listed from a repository
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ClientRecord {
+    pub uri: String,
+    pub cid: String,
+    pub value: serde_json::Value,
+}
+
+/// One page of `com.atproto.repo.listRecords`
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ClientRecordPage {
+    pub records: Vec<ClientRecord>,
+    pub cursor: Option<String>,
+}
+
 /// Account status response
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct ClientAccountStatusResponse {
@@ -334,9 +399,9 @@ impl ClientServiceAuthRequest {
     /// Create a new service auth request for account creation
     pub fn for_account_creation(target_pds_did: &str, expires_in_seconds: Option<u64>) -> Self {
         let expires_at = if let Some(duration) = expires_in_seconds {
-            Some(current_time_secs() + duration)
+            Some(server_now_secs() + duration)
         } else {
-            Some(current_time_secs() + 3600) // Default 1 hour
+            Some(server_now_secs() + 3600) // Default 1 hour
         };
 
         Self {
@@ -352,7 +417,7 @@ impl ClientServiceAuthRequest {
         method: Option<&str>,
         expires_in_seconds: Option<u64>,
     ) -> Self {
-        let expires_at = expires_in_seconds.map(|duration| current_time_secs() + duration);
+        let expires_at = expires_in_seconds.map(|duration| server_now_secs() + duration);
 
         Self {
             aud: target_pds_did.to_string(),
@@ -1,19 +1,29 @@
 mod storage_estimator;
 mod unified_config;
 
+use serde::{Deserialize, Serialize};
+
 use crate::console_warn;
+use crate::utils::log_filter::LogFilter;
+use crate::utils::lookalike::url_host;
 
 pub use storage_estimator::{
     get_storage_estimate, try_get_storage_estimate, StorageEstimate, StorageEstimatorError,
 };
 pub use unified_config::*;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct MigrationConfig {
     pub storage: StorageConfig,
     pub concurrency: ConcurrencyConfig,
     pub retry: RetryConfig,
     pub blob: BlobConfig,
+    pub session: SessionConfig,
+    pub verification: VerificationConfig,
+    pub progress: ProgressConfig,
+    pub logging: LoggingConfig,
+    pub plc_token: PlcTokenConfig,
+    pub target_hosts: TargetHostPolicy,
     pub architecture: MigrationArchitecture,
 }
 
@@ -26,52 +36,472 @@ pub enum MigrationArchitecture {
     Streaming,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct BlobConfig {
     pub enumeration_method: BlobEnumerationMethod,
     pub verification_delay_ms: u64,
     pub max_verification_attempts: u32,
     pub verification_backoff_ms: u64,
+    /// Extra hosts serving `com.atproto.sync.getBlob` for the account (mirrors or
+    /// CDNs); used ahead of the source PDS only when preflight finds them faster
+    pub mirror_urls: Vec<String>,
+    /// What to do when the source PDS no longer has a blob its records reference
+    pub not_found_policy: BlobNotFoundPolicy,
+    /// Largest blob downloaded ahead while the previous one uploads; 0 turns
+    /// the two-stage pipeline off
+    pub prefetch_max_bytes: u64,
+    /// Largest blob a proxy in front of the target must let through when the
+    /// target advertises no limit of its own
+    pub assumed_largest_blob_bytes: u64,
+    /// Largest test upload sent to find a proxy's body size limit; 0 skips the check
+    pub body_limit_probe_max_bytes: u64,
+    /// Test uploads are kept to what the measured upload rate sends in this long
+    pub body_limit_probe_max_secs: u64,
 }
 
 /// Method for enumerating blobs during migration
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BlobEnumerationMethod {
-    /// Use com.atproto.repo.listMissingBlobs (migration-optimized, default)
+    /// Use listMissingBlobs when the target's view can be trusted, otherwise diff
+    /// full sync.listBlobs inventories (default)
+    Auto,
+    /// Use com.atproto.repo.listMissingBlobs (migration-optimized)
     MissingBlobs,
     /// Use com.atproto.sync.listBlobs (full enumeration, matches Go goat)
     SyncListBlobs,
 }
 
-#[derive(Debug, Clone)]
+impl BlobEnumerationMethod {
+    pub const ALL: [BlobEnumerationMethod; 3] = [
+        BlobEnumerationMethod::Auto,
+        BlobEnumerationMethod::MissingBlobs,
+        BlobEnumerationMethod::SyncListBlobs,
+    ];
+
+    /// Stable identifier used in form values
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BlobEnumerationMethod::Auto => "auto",
+            BlobEnumerationMethod::MissingBlobs => "missing",
+            BlobEnumerationMethod::SyncListBlobs => "full",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|method| method.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BlobEnumerationMethod::Auto => "Automatic (recommended)",
+            BlobEnumerationMethod::MissingBlobs => "Missing blobs only (fastest)",
+            BlobEnumerationMethod::SyncListBlobs => "Full comparison (like goat)",
+        }
+    }
+
+    /// Plain-language trade-off shown under the selector
+    pub fn explanation(&self) -> &'static str {
+        match self {
+            BlobEnumerationMethod::Auto => "Asks the new PDS which images and videos it is still missing, and double-checks by comparing both full lists whenever that answer looks incomplete or the server is known to get it wrong.",
+            BlobEnumerationMethod::MissingBlobs => "Trusts the new PDS's own list of missing images and videos. Quickest, but if that server hasn't finished reading your repository it can report nothing missing and some media is skipped.",
+            BlobEnumerationMethod::SyncListBlobs => "Lists every image and video on both servers and transfers the difference, the way goat does. Always complete, but slower for large accounts.",
+        }
+    }
+}
+
+/// What to do with blobs the source PDS answers 404 for
+///
+/// Records can keep referencing media the old PDS already lost; no retry will
+/// bring it back, so the migration either stops or carries on and lists it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BlobNotFoundPolicy {
+    /// Stop the migration on the first blob the source can't find
+    Fail,
+    /// Skip those blobs and list their CIDs in the final report (default)
+    SkipAndRecord,
+}
+
+impl BlobNotFoundPolicy {
+    pub const ALL: [BlobNotFoundPolicy; 2] =
+        [BlobNotFoundPolicy::SkipAndRecord, BlobNotFoundPolicy::Fail];
+
+    /// Stable identifier used in form values
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BlobNotFoundPolicy::Fail => "fail",
+            BlobNotFoundPolicy::SkipAndRecord => "skip",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|policy| policy.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BlobNotFoundPolicy::Fail => "Stop the migration",
+            BlobNotFoundPolicy::SkipAndRecord => "Skip and list them (recommended)",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct StorageConfig {
     pub local_storage_limit: u64,
     pub indexeddb_limit: u64,
     pub opfs_limit: u64,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ConcurrencyConfig {
     pub max_concurrent_transfers: usize,
     pub opfs_concurrency: usize,
     pub indexeddb_concurrency: usize,
     pub localstorage_concurrency: usize,
+    /// Parallel blob uploads a migration starts with before tuning to the target
+    pub initial_upload_concurrency: usize,
+    /// Share of 429 and 5xx answers in a round of uploads above which fewer
+    /// uploads run at once
+    pub upload_error_threshold: f64,
 }
 
-#[derive(Debug, Clone)]
+/// Part of a migration with its own retry budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RetryPhase {
+    Auth,
+    Repo,
+    BlobDownload,
+    BlobUpload,
+    Preferences,
+    Plc,
+}
+
+impl RetryPhase {
+    pub fn label(&self) -> &'static str {
+        match self {
+            RetryPhase::Auth => "authentication",
+            RetryPhase::Repo => "repository transfer",
+            RetryPhase::BlobDownload => "blob download",
+            RetryPhase::BlobUpload => "blob upload",
+            RetryPhase::Preferences => "preferences",
+            RetryPhase::Plc => "PLC operation",
+        }
+    }
+}
+
+/// How often and how patiently one phase retries a failed request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryBudget {
+    /// Retries after the first attempt; 0 means the call is made once
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each later one
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl RetryBudget {
+    pub const fn new(max_retries: u32, base_delay_ms: u64, max_delay_ms: u64) -> Self {
+        Self {
+            max_retries,
+            base_delay_ms,
+            max_delay_ms,
+        }
+    }
+
+    /// Backoff before retry number `retry` (1-based)
+    pub fn delay_ms(&self, retry: u32) -> u64 {
+        let factor = 1u64 << retry.saturating_sub(1).min(16);
+        self.base_delay_ms
+            .saturating_mul(factor)
+            .min(self.max_delay_ms)
+    }
+}
+
+/// Blob retry counts offered on the migration form
+pub const BLOB_RETRY_CHOICES: [u32; 5] = [1, 3, 5, 10, 20];
+
+/// Retry budgets per phase, plus a cap on the time one migration spends waiting to retry
+///
+/// Phases are separate so blob transfers on a flaky network can retry
+/// generously while identity changes, which can't be taken back, stay at
+/// one attempt unless the host raises them.
+#[derive(Debug, Clone, PartialEq)]
 pub struct RetryConfig {
-    pub max_attempts: u32,
+    pub auth: RetryBudget,
+    pub repo: RetryBudget,
+    pub blob_download: RetryBudget,
+    pub blob_upload: RetryBudget,
+    pub preferences: RetryBudget,
+    pub plc: RetryBudget,
+    /// Local storage writes, which don't touch the network
     pub storage_retries: u32,
-    pub migration_retries: u32,
+    /// Total backoff across all phases before retrying stops
+    pub total_budget_secs: u64,
+    /// Send idempotency keys as an `Idempotency-Key` header on account creation
+    /// and PLC submission. Only useful for targets that deduplicate on it; the
+    /// header also triggers a CORS preflight, so only enable it for targets
+    /// that allow it.
+    pub idempotency_header: bool,
+}
+
+/// Tolerances for access tokens issued by PDS implementations with non-standard JWTs
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionConfig {
+    /// Allowed difference between the local clock and the PDS clock
+    pub clock_skew_secs: u64,
+    /// Treat a token as needing refresh this long before it expires
+    pub refresh_window_secs: u64,
+    /// Ask the PDS (`com.atproto.server.getSession`) whether a token is still accepted
+    /// when it cannot be parsed locally or looks expired
+    pub server_side_validation: bool,
+    /// Read the old PDS's public sync endpoints without the access token,
+    /// attaching it only when the PDS refuses the anonymous read
+    pub anonymous_sync_reads: bool,
+    /// Refresh a session before the repository export or blob upload when it
+    /// has less than this left; 0 turns the pre-warm off
+    pub prewarm_min_lifetime_secs: u64,
+    /// How long the pre-warm refresh may take before the step starts anyway
+    pub prewarm_timeout_secs: u64,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            clock_skew_secs: 60,      // 1 minute
+            refresh_window_secs: 300, // 5 minutes
+            server_side_validation: true,
+            anonymous_sync_reads: true,
+            prewarm_min_lifetime_secs: 20 * 60, // 20 minutes
+            prewarm_timeout_secs: 15,
+        }
+    }
+}
+
+/// How thoroughly migrated data is checked before the PLC switch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VerificationLevel {
+    /// Skip verification entirely
+    None,
+    /// Compare record and blob counts, re-uploading any missing blobs
+    #[default]
+    Counts,
+    /// Counts, plus repo commit comparison and re-hashing a sample of blobs
+    SampledHashes,
+    /// Counts, plus diffing both repository exports block by block, re-hashing
+    /// every repository block and every blob on the new PDS
+    FullRehash,
+}
+
+impl VerificationLevel {
+    pub const ALL: [VerificationLevel; 4] = [
+        VerificationLevel::None,
+        VerificationLevel::Counts,
+        VerificationLevel::SampledHashes,
+        VerificationLevel::FullRehash,
+    ];
+
+    /// Stable identifier used in form values
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VerificationLevel::None => "none",
+            VerificationLevel::Counts => "counts",
+            VerificationLevel::SampledHashes => "sampled",
+            VerificationLevel::FullRehash => "full",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|level| level.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            VerificationLevel::None => "None (fastest, no checks)",
+            VerificationLevel::Counts => "Counts (recommended)",
+            VerificationLevel::SampledHashes => "Sampled hashes",
+            VerificationLevel::FullRehash => "Full re-hash (slowest, most thorough)",
+        }
+    }
+
+    /// Whether blob contents are downloaded again and checked against their CIDs
+    pub fn rehashes_blobs(&self) -> bool {
+        matches!(
+            self,
+            VerificationLevel::SampledHashes | VerificationLevel::FullRehash
+        )
+    }
+
+    /// Hash-based levels need the `hash-verification` feature
+    pub fn is_available(&self) -> bool {
+        !self.rehashes_blobs() || cfg!(feature = "hash-verification")
+    }
+
+    /// This level if it is compiled in, otherwise `Counts`
+    pub fn effective(self) -> Self {
+        if self.is_available() {
+            self
+        } else {
+            VerificationLevel::Counts
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerificationConfig {
+    /// Default level; users can override it on the migration form
+    pub level: VerificationLevel,
+    /// Number of blobs re-hashed at `VerificationLevel::SampledHashes`
+    pub blob_sample_size: usize,
+}
+
+impl Default for VerificationConfig {
+    fn default() -> Self {
+        Self {
+            level: VerificationLevel::Counts,
+            blob_sample_size: 20,
+        }
+    }
+}
+
+/// How often streaming steps may push progress into the UI
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressConfig {
+    /// Upper bound on progress dispatches per second; updates in between are
+    /// coalesced so only the latest value of each kind is rendered
+    pub max_updates_per_second: u32,
+    /// Blobs at least this big get their own progress bar
+    pub large_blob_threshold_bytes: u64,
+    /// How often progress reaches `MigrationState` and re-renders the forms;
+    /// the progress displays themselves follow `max_updates_per_second`
+    pub state_updates_per_second: u32,
+}
+
+impl Default for ProgressConfig {
+    fn default() -> Self {
+        Self {
+            max_updates_per_second: 10,
+            large_blob_threshold_bytes: 20 * 1024 * 1024, // 20MB
+            state_updates_per_second: 1,
+        }
+    }
+}
+
+/// How much the console macros print; see `utils::log_filter`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoggingConfig {
+    /// Default level and per-module overrides, e.g. `info,services::streaming=debug`;
+    /// a filter the user saved in this browser takes precedence
+    pub filter: String,
+    /// Debug messages each call site may print per second
+    pub debug_logs_per_second: u32,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            filter: "info".to_string(),
+            debug_logs_per_second: 20,
+        }
+    }
+}
+
+/// Which hosts accounts may be migrated to, set by the deployment
+///
+/// A host matches an entry when it equals it or is a subdomain of it. With a
+/// non-empty allow-list only matching hosts are accepted; the deny-list is
+/// checked either way.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TargetHostPolicy {
+    /// Only these hosts may be chosen; empty allows every host
+    pub allowed_hosts: Vec<String>,
+    /// These hosts are never accepted
+    pub denied_hosts: Vec<String>,
+}
+
+impl TargetHostPolicy {
+    fn matches(host: &str, entry: &str) -> bool {
+        let entry = entry.trim().trim_end_matches('.').to_ascii_lowercase();
+        !entry.is_empty()
+            && (host == entry
+                || host
+                    .strip_suffix(entry.as_str())
+                    .is_some_and(|prefix| prefix.ends_with('.')))
+    }
+
+    /// Whether `host` may be used as the migration target
+    pub fn check_host(&self, host: &str) -> Result<(), String> {
+        let host = host.trim().trim_end_matches('.').to_ascii_lowercase();
+        if self
+            .denied_hosts
+            .iter()
+            .any(|entry| Self::matches(&host, entry))
+        {
+            return Err(format!("Migrating to {} is blocked by this service", host));
+        }
+        if !self.allowed_hosts.is_empty()
+            && !self
+                .allowed_hosts
+                .iter()
+                .any(|entry| Self::matches(&host, entry))
+        {
+            return Err(format!(
+                "This service only migrates accounts to {}",
+                self.allowed_hosts.join(", ")
+            ));
+        }
+        Ok(())
+    }
+
+    /// [`Self::check_host`] for the host of a PDS URL
+    pub fn check_url(&self, url: &str) -> Result<(), String> {
+        match url_host(url) {
+            Some(host) => self.check_host(&host),
+            None => Err(format!("{} is not a valid PDS URL", url)),
+        }
+    }
+}
+
+/// What Form 4 tells the user while the PLC token email is on its way
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlcTokenConfig {
+    /// Subject line of the PDS's PLC token email, for PDSes with their own template
+    pub email_subject: String,
+    /// Point at the spam folder and email aliases once this long has passed
+    pub spam_hint_after_secs: u64,
+    /// Offer a resend and signing with a recovery key once this long has passed
+    pub resend_after_secs: u64,
+    /// How long the PDS accepts an emailed token before it expires
+    pub token_validity_secs: u64,
+}
+
+impl Default for PlcTokenConfig {
+    fn default() -> Self {
+        Self {
+            email_subject: "PLC Update Operation Requested".to_string(),
+            spam_hint_after_secs: 60,     // 1 minute
+            resend_after_secs: 5 * 60,    // 5 minutes
+            token_validity_secs: 15 * 60, // The reference PDS's token lifetime
+        }
+    }
 }
 
 impl Default for BlobConfig {
     fn default() -> Self {
         Self {
-            enumeration_method: BlobEnumerationMethod::MissingBlobs, // Default to migration-optimized
+            enumeration_method: BlobEnumerationMethod::Auto, // Decide per source/target pairing
             verification_delay_ms: 3000, // 3 seconds initial delay after uploads
             max_verification_attempts: 5, // Try up to 5 times to verify uploads
             verification_backoff_ms: 2000, // 2 seconds linear backoff between attempts
+            mirror_urls: Vec::new(),
+            not_found_policy: BlobNotFoundPolicy::SkipAndRecord, // Already-lost media shouldn't block
+            prefetch_max_bytes: 16 * 1024 * 1024,                // 16MB held in memory at most
+            assumed_largest_blob_bytes: 100 * 1024 * 1024,       // Bluesky's video upload limit
+            body_limit_probe_max_bytes: 32 * 1024 * 1024,        // 32MB test upload at most
+            body_limit_probe_max_secs: 20,
         }
     }
 }
@@ -104,6 +534,8 @@ impl Default for ConcurrencyConfig {
             opfs_concurrency: 10,
             indexeddb_concurrency: 5,
             localstorage_concurrency: 1,
+            initial_upload_concurrency: 2,
+            upload_error_threshold: 0.05,
         }
     }
 }
@@ -116,6 +548,8 @@ impl ConcurrencyConfig {
             opfs_concurrency: 5,         // Reduced from 10
             indexeddb_concurrency: 3,    // Reduced from 5
             localstorage_concurrency: 1, // Keep at 1 (unchanged)
+            initial_upload_concurrency: 1,
+            upload_error_threshold: 0.05,
         }
     }
 }
@@ -123,9 +557,15 @@ impl ConcurrencyConfig {
 impl Default for RetryConfig {
     fn default() -> Self {
         Self {
-            max_attempts: 5,
+            auth: RetryBudget::new(3, 1_000, 10_000),
+            repo: RetryBudget::new(3, 2_000, 30_000),
+            blob_download: RetryBudget::new(5, 1_000, 30_000),
+            blob_upload: RetryBudget::new(5, 1_000, 30_000),
+            preferences: RetryBudget::new(3, 1_000, 10_000),
+            plc: RetryBudget::new(0, 2_000, 10_000),
             storage_retries: 3,
-            migration_retries: 3,
+            total_budget_secs: 3_600,
+            idempotency_header: false,
         }
     }
 }
@@ -134,11 +574,44 @@ impl RetryConfig {
     /// Conservative defaults for wasm32-unknown-unknown target
     pub fn conservative_defaults() -> Self {
         Self {
-            max_attempts: 3,      // Reduced from 5
-            storage_retries: 2,   // Reduced from 3
-            migration_retries: 2, // Reduced from 3
+            blob_download: RetryBudget::new(3, 1_000, 30_000),
+            blob_upload: RetryBudget::new(3, 1_000, 30_000),
+            storage_retries: 2,
+            total_budget_secs: 1_800,
+            ..Self::default()
+        }
+    }
+
+    pub fn budget(&self, phase: RetryPhase) -> RetryBudget {
+        match phase {
+            RetryPhase::Auth => self.auth,
+            RetryPhase::Repo => self.repo,
+            RetryPhase::BlobDownload => self.blob_download,
+            RetryPhase::BlobUpload => self.blob_upload,
+            RetryPhase::Preferences => self.preferences,
+            RetryPhase::Plc => self.plc,
         }
     }
+
+    /// Same budgets with both blob phases allowed `retries` retries
+    pub fn with_blob_retries(mut self, retries: u32) -> Self {
+        self.blob_download.max_retries = retries;
+        self.blob_upload.max_retries = retries;
+        self
+    }
+
+    /// Same budgets with `phase` replaced
+    pub fn with_budget(mut self, phase: RetryPhase, budget: RetryBudget) -> Self {
+        match phase {
+            RetryPhase::Auth => self.auth = budget,
+            RetryPhase::Repo => self.repo = budget,
+            RetryPhase::BlobDownload => self.blob_download = budget,
+            RetryPhase::BlobUpload => self.blob_upload = budget,
+            RetryPhase::Preferences => self.preferences = budget,
+            RetryPhase::Plc => self.plc = budget,
+        }
+        self
+    }
 }
 
 impl Default for MigrationConfig {
@@ -161,6 +634,12 @@ impl MigrationConfig {
             concurrency: ConcurrencyConfig::conservative_defaults(),
             retry: RetryConfig::conservative_defaults(),
             blob: BlobConfig::default(),
+            session: SessionConfig::default(),
+            verification: VerificationConfig::default(),
+            progress: ProgressConfig::default(),
+            logging: LoggingConfig::default(),
+            plc_token: PlcTokenConfig::default(),
+            target_hosts: TargetHostPolicy::default(),
             architecture: MigrationArchitecture::Streaming, // Default to streaming for WASM
         }
     }
@@ -197,6 +676,12 @@ impl MigrationConfig {
             },
             retry: RetryConfig::conservative_defaults(),
             blob: BlobConfig::default(),
+            session: SessionConfig::default(),
+            verification: VerificationConfig::default(),
+            progress: ProgressConfig::default(),
+            logging: LoggingConfig::default(),
+            plc_token: PlcTokenConfig::default(),
+            target_hosts: TargetHostPolicy::default(),
             architecture: MigrationArchitecture::Streaming, // Always use streaming for WASM
         }
     }
@@ -206,8 +691,42 @@ impl MigrationConfig {
             return Err("max_concurrent_transfers must be greater than 0".to_string());
         }
 
-        if self.retry.max_attempts == 0 {
-            return Err("max_attempts must be greater than 0".to_string());
+        if !(0.0..1.0).contains(&self.concurrency.upload_error_threshold) {
+            return Err("upload_error_threshold must be at least 0 and below 1".to_string());
+        }
+
+        if self.retry.total_budget_secs == 0 {
+            return Err("total_budget_secs must be greater than 0".to_string());
+        }
+
+        if self.progress.max_updates_per_second == 0 {
+            return Err("max_updates_per_second must be greater than 0".to_string());
+        }
+
+        if self.progress.state_updates_per_second == 0 {
+            return Err("state_updates_per_second must be greater than 0".to_string());
+        }
+
+        if self.progress.large_blob_threshold_bytes == 0 {
+            return Err("large_blob_threshold_bytes must be greater than 0".to_string());
+        }
+
+        LogFilter::parse(&self.logging.filter)?;
+
+        if self.logging.debug_logs_per_second == 0 {
+            return Err("debug_logs_per_second must be greater than 0".to_string());
+        }
+
+        if self.plc_token.resend_after_secs == 0 {
+            return Err("resend_after_secs must be greater than 0".to_string());
+        }
+
+        if self.plc_token.token_validity_secs <= self.plc_token.resend_after_secs {
+            return Err("token_validity_secs must be greater than resend_after_secs".to_string());
+        }
+
+        if self.blob.body_limit_probe_max_bytes > 0 && self.blob.body_limit_probe_max_secs == 0 {
+            return Err("body_limit_probe_max_secs must be greater than 0".to_string());
         }
 
         if self.storage.local_storage_limit == 0 {
@@ -237,6 +756,16 @@ pub fn get_global_config() -> MigrationConfig {
         .clone()
 }
 
+/// Install a host-provided configuration as the global configuration
+/// Must be called before the first `get_global_config()`; returns an error if the
+/// configuration is invalid or the global configuration was already initialized
+pub fn set_global_config(config: MigrationConfig) -> Result<(), String> {
+    config.validate()?;
+    GLOBAL_CONFIG
+        .set(config)
+        .map_err(|_| "Global configuration already initialized".to_string())
+}
+
 /// Initialize global configuration with browser storage integration (async version)
 /// Call this early in your application startup for best results
 pub async fn init_global_config_with_browser_storage() {
@@ -256,3 +785,25 @@ pub async fn get_config_with_browser_storage() -> MigrationConfig {
         config
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_host_policy() {
+        let open = TargetHostPolicy::default();
+        assert!(open.check_url("https://anything.example").is_ok());
+
+        let policy = TargetHostPolicy {
+            allowed_hosts: vec!["blacksky.app".to_string()],
+            denied_hosts: vec!["evil.blacksky.app".to_string()],
+        };
+        assert!(policy.check_url("https://blacksky.app").is_ok());
+        assert!(policy.check_url("pds.Blacksky.app/").is_ok());
+        assert!(policy.check_url("https://notblacksky.app").is_err());
+        assert!(policy.check_url("https://blacksky.app.evil.io").is_err());
+        assert!(policy.check_url("https://evil.blacksky.app").is_err());
+        assert!(policy.check_url("").is_err());
+    }
+}
@@ -68,6 +68,9 @@ impl std::error::Error for StorageEstimatorError {}
 /// Get storage estimate from browser's StorageManager API
 pub async fn get_storage_estimate() -> Result<StorageEstimate, StorageEstimatorError> {
     // Check if we have access to the window object
+    if !crate::utils::platform::has_browser_window() {
+        return Err(StorageEstimatorError::NotSupported);
+    }
     let window = window().ok_or(StorageEstimatorError::NotSupported)?;
 
     // Get navigator
@@ -7,6 +7,8 @@
 //! - **blob**: Legacy blob management (being migrated to streaming architecture)
 //! - **config**: Configuration management and global settings
 //! - **errors**: Common error types and handling utilities
+//! - **retry**: Per-phase retry budgets and the shared retry time budget
+//! - **transport**: HTTP transport trait with auth, retry, rate-limit, tracing and circuit-breaker middleware
 //!
 //! The services are designed to be WASM-first, using browser APIs and async traits
 //! without Send/Sync bounds for compatibility.
@@ -15,4 +17,6 @@ pub mod blob;
 pub mod client;
 pub mod config;
 pub mod errors;
+pub mod retry;
 pub mod streaming;
+pub mod transport;
@@ -0,0 +1,164 @@
+//! Retrying failed requests within the configured budgets
+//!
+//! Each phase of a migration has its own [`RetryBudget`] in [`RetryConfig`],
+//! and all phases share one clock of time spent waiting between retries. Once
+//! that clock passes `total_budget_secs` nothing retries any more, so a link
+//! that keeps failing ends the migration with an error instead of backing off
+//! for hours.
+
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::console_warn;
+use crate::services::config::{RetryBudget, RetryConfig, RetryPhase};
+
+static RETRY_WAIT_MS: AtomicU64 = AtomicU64::new(0);
+
+/// Start a new migration with an empty retry clock
+pub fn reset_retry_clock() {
+    RETRY_WAIT_MS.store(0, Ordering::Relaxed);
+}
+
+/// Time spent waiting to retry since the clock was last reset
+pub fn retry_wait_spent_ms() -> u64 {
+    RETRY_WAIT_MS.load(Ordering::Relaxed)
+}
+
+/// Delay before retry number `retry` (1-based), or `None` once the phase's
+/// retries or the total budget are used up. A server-suggested delay, e.g.
+/// from a 429, replaces the phase's backoff.
+pub fn next_retry_delay(
+    budget: RetryBudget,
+    total_budget_secs: u64,
+    retry: u32,
+    suggested_ms: Option<u64>,
+    spent_ms: u64,
+) -> Option<u64> {
+    if retry == 0 || retry > budget.max_retries {
+        return None;
+    }
+    let delay = suggested_ms.unwrap_or_else(|| budget.delay_ms(retry));
+    let total_ms = total_budget_secs.saturating_mul(1000);
+    (spent_ms.saturating_add(delay) <= total_ms).then_some(delay)
+}
+
+/// Reserve the delay for the next retry on the shared clock
+pub fn claim_retry_delay(
+    config: &RetryConfig,
+    phase: RetryPhase,
+    retry: u32,
+    suggested_ms: Option<u64>,
+) -> Option<u64> {
+    let delay = next_retry_delay(
+        config.budget(phase),
+        config.total_budget_secs,
+        retry,
+        suggested_ms,
+        retry_wait_spent_ms(),
+    )?;
+    RETRY_WAIT_MS.fetch_add(delay, Ordering::Relaxed);
+    Some(delay)
+}
+
+pub use crate::utils::platform::sleep_ms;
+
+/// Run `op` until it succeeds or the phase's budget is spent, returning the last error
+pub async fn with_retries<T, E, Fut>(
+    config: &RetryConfig,
+    phase: RetryPhase,
+    op: impl FnMut() -> Fut,
+) -> Result<T, E>
+where
+    E: Display,
+    Fut: Future<Output = Result<T, E>>,
+{
+    with_retries_when(config, phase, |_| true, op).await
+}
+
+/// Like [`with_retries`], but only errors `should_retry` accepts are retried;
+/// any other error is returned straight away
+pub async fn with_retries_when<T, E, Fut>(
+    config: &RetryConfig,
+    phase: RetryPhase,
+    should_retry: impl Fn(&E) -> bool,
+    mut op: impl FnMut() -> Fut,
+) -> Result<T, E>
+where
+    E: Display,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut retry = 0;
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(e) if !should_retry(&e) => return Err(e),
+            Err(e) => {
+                retry += 1;
+                let Some(delay) = claim_retry_delay(config, phase, retry, None) else {
+                    return Err(e);
+                };
+                console_warn!(
+                    "[Retry] {} failed (retry {}/{} in {}ms): {}",
+                    phase.label(),
+                    retry,
+                    config.budget(phase).max_retries,
+                    delay,
+                    e
+                );
+                sleep_ms(delay).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delays_respect_phase_and_total_budgets() {
+        let budget = RetryBudget::new(3, 1_000, 3_000);
+
+        assert_eq!(next_retry_delay(budget, 60, 1, None, 0), Some(1_000));
+        assert_eq!(next_retry_delay(budget, 60, 2, None, 0), Some(2_000));
+        assert_eq!(next_retry_delay(budget, 60, 3, None, 0), Some(3_000));
+        assert_eq!(next_retry_delay(budget, 60, 4, None, 0), None);
+        assert_eq!(
+            next_retry_delay(budget, 60, 1, Some(30_000), 0),
+            Some(30_000)
+        );
+        assert_eq!(next_retry_delay(budget, 60, 1, None, 59_500), None);
+
+        let once = RetryConfig::default().plc;
+        assert_eq!(next_retry_delay(once, 60, 1, None, 0), None);
+    }
+
+    #[tokio::test]
+    async fn test_with_retries_when_stops_on_rejected_errors() {
+        let config = RetryConfig {
+            auth: RetryBudget::new(3, 1, 1),
+            ..RetryConfig::default()
+        };
+        let attempts = std::cell::Cell::new(0);
+
+        let result: Result<(), String> = with_retries_when(
+            &config,
+            RetryPhase::Auth,
+            |e: &String| e.as_str() == "transient",
+            || {
+                attempts.set(attempts.get() + 1);
+                let error = if attempts.get() == 1 {
+                    "transient"
+                } else {
+                    "rejected"
+                };
+                async move { Err(error.to_string()) }
+            },
+        )
+        .await;
+
+        assert_eq!(result, Err("rejected".to_string()));
+        assert_eq!(attempts.get(), 2);
+    }
+}
@@ -0,0 +1,156 @@
+//! Content-addressed blob cache shared across migration attempts
+//!
+//! Blobs are stored under their CID rather than under a per-migration path, and
+//! a blob is only indexed once its stored bytes have been checked against that
+//! CID. A retried migration, or a second account that shares media, then
+//! uploads straight from the cache instead of downloading again.
+
+use super::browser_storage::{BrowserStorage, CachedBlobRecord};
+use super::traits::{DataChunk, StorageBackend};
+use crate::services::client::types::current_time_ms;
+use crate::{console_debug, console_info, console_warn};
+use async_trait::async_trait;
+use std::error::Error;
+
+/// Blob storage keyed globally by CID with a verified-content index
+pub struct BlobCache {
+    storage: BrowserStorage,
+}
+
+impl BlobCache {
+    pub async fn open() -> Result<Self, Box<dyn Error>> {
+        let storage = BrowserStorage::new()
+            .await
+            .map_err(|e| format!("Failed to open blob cache: {}", e))?;
+        Ok(Self { storage })
+    }
+
+    /// Verified bytes for a CID, or `None` if it isn't cached or no longer matches its index entry
+    pub async fn lookup(&self, cid: &str) -> Result<Option<Vec<u8>>, String> {
+        let Some(record) = self.storage.cached_blob_record(cid).await? else {
+            return Ok(None);
+        };
+
+        let data = match self.storage.read_data(cid).await {
+            Ok(data) => data,
+            Err(e) => {
+                console_warn!("[BlobCache] Indexed blob {} is unreadable: {}", cid, e);
+                self.storage.forget_cached_blob(cid).await?;
+                return Ok(None);
+            }
+        };
+
+        if data.len() as u64 != record.size {
+            console_warn!(
+                "[BlobCache] Cached blob {} is {} bytes, index says {}; discarding",
+                cid,
+                data.len(),
+                record.size
+            );
+            self.storage.forget_cached_blob(cid).await?;
+            return Ok(None);
+        }
+
+        Ok(Some(data))
+    }
+
+    /// CIDs of every verified blob currently cached
+    pub async fn cached_cids(&self) -> Result<Vec<String>, String> {
+        Ok(self
+            .storage
+            .cached_blob_records()
+            .await?
+            .into_iter()
+            .map(|record| record.cid)
+            .collect())
+    }
+}
+
+/// Check stored bytes against the CID they were fetched under
+pub fn verify_cached_blob(cid: &str, data: &[u8]) -> Result<(), String> {
+    let parsed: cid::Cid = cid
+        .parse()
+        .map_err(|e| format!("invalid CID {}: {}", cid, e))?;
+
+    #[cfg(feature = "hash-verification")]
+    {
+        crate::migration::integrity::verify_blob_bytes(&parsed, data)
+    }
+
+    #[cfg(not(feature = "hash-verification"))]
+    {
+        let _ = parsed;
+        if data.is_empty() {
+            return Err("blob is empty".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl StorageBackend for BlobCache {
+    async fn write_chunk(&mut self, chunk: &DataChunk) -> Result<(), Box<dyn Error>> {
+        StorageBackend::write_chunk(&mut self.storage, chunk).await
+    }
+
+    async fn finalize(&mut self, id: &str) -> Result<(), Box<dyn Error>> {
+        StorageBackend::finalize(&mut self.storage, id).await?;
+
+        let data = StorageBackend::read_data(&self.storage, id).await?;
+        match verify_cached_blob(id, &data) {
+            Ok(()) => {
+                let record = CachedBlobRecord {
+                    cid: id.to_string(),
+                    size: data.len() as u64,
+                    verified_at_ms: current_time_ms() as u64,
+                };
+                if let Err(e) = self.storage.record_cached_blob(&record).await {
+                    console_warn!("[BlobCache] Failed to index blob {}: {}", id, e);
+                } else {
+                    console_debug!("[BlobCache] Indexed verified blob {}", id);
+                }
+            }
+            Err(e) => {
+                // Still uploaded as before, but never served from the cache
+                console_warn!("[BlobCache] Blob {} failed verification: {}", id, e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn read_data(&self, id: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        StorageBackend::read_data(&self.storage, id).await
+    }
+
+    async fn cached_data(&self, id: &str) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        let data = self.lookup(id).await?;
+        if data.is_some() {
+            console_info!("[BlobCache] Cache hit for {}", id);
+        }
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_cached_blob_rejects_invalid_cid() {
+        assert!(verify_cached_blob("not-a-cid", b"data").is_err());
+    }
+
+    #[cfg(feature = "hash-verification")]
+    #[test]
+    fn test_verify_cached_blob_checks_content_hash() {
+        use sha2::{Digest, Sha256};
+
+        let data = b"cached blob bytes";
+        let digest = Sha256::digest(data);
+        let hash = cid::multihash::Multihash::<64>::wrap(0x12, &digest).unwrap();
+        let cid = cid::Cid::new_v1(0x55, hash).to_string();
+
+        assert!(verify_cached_blob(&cid, data).is_ok());
+        assert!(verify_cached_blob(&cid, b"tampered").is_err());
+    }
+}
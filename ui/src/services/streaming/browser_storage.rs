@@ -1,5 +1,10 @@
 //! Browser storage implementation using OPFS + IndexedDB with opfs crate
 
+use crate::services::client::types::current_time_ms;
+use crate::services::streaming::metrics::{record_pipeline_operation, PipelineOperation};
+use crate::services::streaming::storage_usage::{
+    record_backend_release, record_backend_write, StorageBackendKind,
+};
 use crate::services::streaming::traits::{DataChunk, StorageBackend};
 use crate::{console_debug, console_error, console_info, console_warn};
 use async_trait::async_trait;
@@ -22,6 +27,14 @@ struct StoredChunk {
     data: Vec<u8>,
 }
 
+/// Index entry for a blob whose stored bytes were verified against its CID
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedBlobRecord {
+    pub cid: String,
+    pub size: u64,
+    pub verified_at_ms: u64,
+}
+
 /// Browser storage backend supporting both OPFS and IndexedDB using the opfs crate
 pub struct BrowserStorage {
     db: Rexie,
@@ -74,11 +87,20 @@ impl BrowserStorage {
 
     /// Write a chunk of data to storage
     pub async fn write_chunk(&self, id: &str, offset: usize, data: &[u8]) -> Result<(), String> {
+        let started = current_time_ms();
         if let Some(ref root) = self.opfs_root {
-            self.write_to_opfs_with_crate(root, id, offset, data).await
+            self.write_to_opfs_with_crate(root, id, offset, data)
+                .await?;
+            record_pipeline_operation(PipelineOperation::OpfsWrite, started, data.len() as u64);
         } else {
-            self.write_to_indexeddb(id, offset, data).await
+            self.write_to_indexeddb(id, offset, data).await?;
+            record_pipeline_operation(
+                PipelineOperation::IndexedDbWrite,
+                started,
+                data.len() as u64,
+            );
         }
+        Ok(())
     }
 
     async fn write_to_opfs_with_crate(
@@ -132,6 +154,7 @@ impl BrowserStorage {
             .await
             .map_err(|e| format!("Failed to close writable: {:?}", e))?;
 
+        record_backend_write(StorageBackendKind::Opfs, data.len() as u64);
         Ok(())
     }
 
@@ -163,16 +186,26 @@ impl BrowserStorage {
             .await
             .map_err(|e| format!("Transaction failed: {:?}", e))?;
 
+        record_backend_write(StorageBackendKind::IndexedDb, data.len() as u64);
         Ok(())
     }
 
     /// Read all data for an ID
     pub async fn read_data(&self, id: &str) -> Result<Vec<u8>, String> {
-        if let Some(ref root) = self.opfs_root {
-            self.read_from_opfs(root, id).await
+        let started = current_time_ms();
+        let (operation, data) = if let Some(ref root) = self.opfs_root {
+            (
+                PipelineOperation::OpfsRead,
+                self.read_from_opfs(root, id).await?,
+            )
         } else {
-            self.read_from_indexeddb(id).await
-        }
+            (
+                PipelineOperation::IndexedDbRead,
+                self.read_from_indexeddb(id).await?,
+            )
+        };
+        record_pipeline_operation(operation, started, data.len() as u64);
+        Ok(data)
     }
 
     /// Read back from OPFS using the opfs crate
@@ -269,6 +302,7 @@ impl BrowserStorage {
                 .write_at_cursor_pos(chunk.to_vec())
                 .await
                 .map_err(|e| format!("Failed to write chunk: {:?}", e))?;
+            record_backend_write(StorageBackendKind::Opfs, chunk.len() as u64);
         }
 
         writable
@@ -341,6 +375,101 @@ impl BrowserStorage {
     }
 }
 
+impl BrowserStorage {
+    /// Record a verified blob in the content-addressed index (`blobs` store)
+    pub async fn record_cached_blob(&self, record: &CachedBlobRecord) -> Result<(), String> {
+        let tx = self
+            .db
+            .transaction(&["blobs"], TransactionMode::ReadWrite)
+            .map_err(|e| format!("Failed to create transaction: {:?}", e))?;
+
+        let store = tx
+            .store("blobs")
+            .map_err(|e| format!("Failed to get store: {:?}", e))?;
+
+        let value = serde_wasm_bindgen::to_value(record)
+            .map_err(|e| format!("Failed to serialize: {:?}", e))?;
+
+        store
+            .put(&value, None)
+            .await
+            .map_err(|e| format!("Failed to put: {:?}", e))?;
+
+        tx.done()
+            .await
+            .map_err(|e| format!("Transaction failed: {:?}", e))?;
+
+        Ok(())
+    }
+
+    /// Look up a blob in the content-addressed index
+    pub async fn cached_blob_record(&self, cid: &str) -> Result<Option<CachedBlobRecord>, String> {
+        let tx = self
+            .db
+            .transaction(&["blobs"], TransactionMode::ReadOnly)
+            .map_err(|e| format!("Failed to create transaction: {:?}", e))?;
+
+        let store = tx
+            .store("blobs")
+            .map_err(|e| format!("Failed to get store: {:?}", e))?;
+
+        let value = store
+            .get(&wasm_bindgen::JsValue::from_str(cid))
+            .await
+            .map_err(|e| format!("Failed to get blob record: {:?}", e))?;
+
+        if value.is_undefined() || value.is_null() {
+            return Ok(None);
+        }
+        Ok(serde_wasm_bindgen::from_value(value).ok())
+    }
+
+    /// Every blob in the content-addressed index
+    pub async fn cached_blob_records(&self) -> Result<Vec<CachedBlobRecord>, String> {
+        let tx = self
+            .db
+            .transaction(&["blobs"], TransactionMode::ReadOnly)
+            .map_err(|e| format!("Failed to create transaction: {:?}", e))?;
+
+        let store = tx
+            .store("blobs")
+            .map_err(|e| format!("Failed to get store: {:?}", e))?;
+
+        let all_values = store
+            .get_all(None, None, None, None)
+            .await
+            .map_err(|e| format!("Failed to list blob records: {:?}", e))?;
+
+        Ok(all_values
+            .into_iter()
+            .filter_map(|(_, value)| serde_wasm_bindgen::from_value(value).ok())
+            .collect())
+    }
+
+    /// Remove a blob from the content-addressed index
+    pub async fn forget_cached_blob(&self, cid: &str) -> Result<(), String> {
+        let tx = self
+            .db
+            .transaction(&["blobs"], TransactionMode::ReadWrite)
+            .map_err(|e| format!("Failed to create transaction: {:?}", e))?;
+
+        let store = tx
+            .store("blobs")
+            .map_err(|e| format!("Failed to get store: {:?}", e))?;
+
+        store
+            .delete(&wasm_bindgen::JsValue::from_str(cid))
+            .await
+            .map_err(|e| format!("Failed to delete blob record: {:?}", e))?;
+
+        tx.done()
+            .await
+            .map_err(|e| format!("Transaction failed: {:?}", e))?;
+
+        Ok(())
+    }
+}
+
 #[async_trait(?Send)]
 impl StorageBackend for BrowserStorage {
     async fn write_chunk(&mut self, chunk: &DataChunk) -> Result<(), Box<dyn Error>> {
@@ -380,6 +509,10 @@ impl StorageBackend for BrowserStorage {
             let required_size = chunk.offset + chunk.data.len();
             if buffer.len() < required_size {
                 buffer.resize(required_size, 0);
+                record_backend_write(
+                    StorageBackendKind::Memory,
+                    (required_size - old_buffer_size) as u64,
+                );
                 console_debug!(
                     "[BrowserStorage] Expanded buffer for {} from {} to {} bytes",
                     chunk.id,
@@ -422,6 +555,7 @@ impl StorageBackend for BrowserStorage {
             // Write buffered data to IndexedDB
             if let Some(buffer) = self.buffers.remove(id) {
                 let buffer_size = buffer.len();
+                record_backend_release(StorageBackendKind::Memory, buffer_size as u64);
                 console_info!(
                     "[BrowserStorage] Writing {} bytes from buffer to IndexedDB for {}",
                     buffer_size,
@@ -0,0 +1,72 @@
+//! Whole-item sha-256 computed while an item streams into storage
+//!
+//! The digest of what arrived over the network is compared with what is read
+//! back from browser storage just before the upload, so bytes corrupted or
+//! truncated by flaky storage in between are caught before they reach the
+//! target. Hashing needs the `hash-verification` feature; without it every
+//! checksum is `None` and nothing is compared.
+
+#[cfg(feature = "hash-verification")]
+use sha2::{Digest, Sha256};
+
+/// Running sha-256 of a byte stream
+#[derive(Default)]
+pub struct StreamChecksum {
+    #[cfg(feature = "hash-verification")]
+    hasher: Sha256,
+}
+
+impl StreamChecksum {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, _data: &[u8]) {
+        #[cfg(feature = "hash-verification")]
+        self.hasher.update(_data);
+    }
+
+    /// Lowercase hex digest, or `None` when built without hashing
+    pub fn finish(self) -> Option<String> {
+        #[cfg(feature = "hash-verification")]
+        {
+            Some(to_hex(&self.hasher.finalize()))
+        }
+        #[cfg(not(feature = "hash-verification"))]
+        {
+            None
+        }
+    }
+}
+
+/// Lowercase hex sha-256 of `data`, or `None` when built without hashing
+pub fn sha256_hex(data: &[u8]) -> Option<String> {
+    let mut checksum = StreamChecksum::new();
+    checksum.update(data);
+    checksum.finish()
+}
+
+#[cfg(feature = "hash-verification")]
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(all(test, feature = "hash-verification"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_streamed_checksum_matches_whole_digest() {
+        let mut checksum = StreamChecksum::new();
+        checksum.update(b"hello ");
+        checksum.update(b"world");
+        let streamed = checksum.finish();
+
+        assert_eq!(streamed, sha256_hex(b"hello world"));
+        assert_eq!(
+            streamed.as_deref(),
+            Some("b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9")
+        );
+        assert_ne!(streamed, sha256_hex(b"hello worle"));
+    }
+}
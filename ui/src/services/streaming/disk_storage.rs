@@ -0,0 +1,137 @@
+//! Staging on the local disk for the desktop app
+//!
+//! A native build has neither OPFS nor IndexedDB, and the minimal flow would
+//! hold every transfer in memory and refuse large accounts. The desktop app
+//! stages items as files under `staging/` in the app's data directory instead,
+//! so account size is bounded by free disk space. Blobs are staged under their
+//! CID and checked against it before reuse, which lets a retried migration
+//! upload straight from disk.
+
+use super::traits::{DataChunk, StorageBackend};
+use crate::console_debug;
+use async_trait::async_trait;
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+/// Directory under the app's data directory that holds staged items
+#[cfg(all(not(target_arch = "wasm32"), feature = "desktop"))]
+const STAGING_DIR: &str = "staging";
+
+/// Where staged items go, when this build stages on disk at all
+pub fn disk_staging_dir() -> Option<PathBuf> {
+    #[cfg(all(not(target_arch = "wasm32"), feature = "desktop"))]
+    {
+        crate::utils::platform::desktop_data_dir().map(|dir| dir.join(STAGING_DIR))
+    }
+
+    #[cfg(not(all(not(target_arch = "wasm32"), feature = "desktop")))]
+    {
+        None
+    }
+}
+
+/// Items staged as one file each
+pub struct DiskStorage {
+    dir: PathBuf,
+}
+
+impl DiskStorage {
+    /// Stage under `subdir` of the staging directory
+    pub fn open(subdir: &str) -> Result<Self, Box<dyn Error>> {
+        let root = disk_staging_dir().ok_or("This build has no disk staging directory")?;
+        Self::open_in(root.join(subdir))
+    }
+
+    pub fn open_in(dir: PathBuf) -> Result<Self, Box<dyn Error>> {
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        let name: String = id
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{}.data", name))
+    }
+}
+
+#[async_trait(?Send)]
+impl StorageBackend for DiskStorage {
+    async fn write_chunk(&mut self, chunk: &DataChunk) -> Result<(), Box<dyn Error>> {
+        let path = self.path(&chunk.id);
+        // A new download replaces whatever an earlier attempt left behind
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(chunk.offset == 0)
+            .open(&path)?;
+        file.seek(SeekFrom::Start(chunk.offset as u64))?;
+        file.write_all(&chunk.data)?;
+        Ok(())
+    }
+
+    async fn finalize(&mut self, id: &str) -> Result<(), Box<dyn Error>> {
+        let file = OpenOptions::new().write(true).open(self.path(id))?;
+        file.sync_all()?;
+        console_debug!("[DiskStorage] Staged {} in {}", id, self.dir.display());
+        Ok(())
+    }
+
+    async fn read_data(&self, id: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        std::fs::read(self.path(id)).map_err(|e| format!("{} is not staged: {}", id, e).into())
+    }
+
+    async fn cached_data(&self, id: &str) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        // Only blobs can be checked against their key; anything else is re-fetched
+        Ok(std::fs::read(self.path(id))
+            .ok()
+            .filter(|data| super::verify_cached_blob(id, data).is_ok()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_items_are_staged_in_files() {
+        let dir = std::env::temp_dir().join(format!("tektite-staging-{}", std::process::id()));
+        let mut storage = DiskStorage::open_in(dir.clone()).unwrap();
+        let chunk = |offset: usize, data: &[u8]| DataChunk {
+            id: "repos/did:plc:abc".to_string(),
+            offset,
+            data: bytes::Bytes::copy_from_slice(data),
+            total_size: None,
+        };
+
+        storage.write_chunk(&chunk(0, b"hello ")).await.unwrap();
+        storage.write_chunk(&chunk(6, b"world")).await.unwrap();
+        storage.finalize("repos/did:plc:abc").await.unwrap();
+        assert_eq!(
+            storage.read_data("repos/did:plc:abc").await.unwrap(),
+            b"hello world"
+        );
+        assert!(dir.join("repos_did_plc_abc.data").exists());
+
+        // Restarting the item drops the longer earlier attempt
+        storage.write_chunk(&chunk(0, b"hi")).await.unwrap();
+        assert_eq!(storage.read_data("repos/did:plc:abc").await.unwrap(), b"hi");
+
+        // Not a CID, so never reused without downloading again
+        assert_eq!(
+            storage.cached_data("repos/did:plc:abc").await.unwrap(),
+            None
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
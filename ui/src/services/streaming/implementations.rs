@@ -3,10 +3,17 @@
 use super::browser_storage::BrowserStorage;
 use super::traits::*;
 use super::wasm_http_client::WasmHttpClient;
-use crate::services::client::{ClientSessionCredentials, RefreshableSessionProvider};
+use crate::services::client::{
+    diagnose_import_failure, record_import_failure, ClientSessionCredentials,
+    RefreshableSessionProvider,
+};
+use crate::utils::platform::{now_ms, sleep_ms};
 use crate::{console_debug, console_error, console_info, console_warn};
 use async_trait::async_trait;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::error::Error;
+use std::rc::Rc;
 
 // ============================================================================
 // Repository Implementations
@@ -80,7 +87,7 @@ impl RepoTarget {
     pub fn new(session_provider: RefreshableSessionProvider) -> Self {
         Self {
             pds_url: String::new(), // Will be populated from session
-            client: WasmHttpClient::new(),
+            client: WasmHttpClient::authenticated(session_provider.clone()),
             session_provider,
         }
     }
@@ -106,65 +113,34 @@ impl DataTarget for RepoTarget {
         data: Vec<u8>,
         _content_type: &str,
     ) -> Result<(), Box<dyn Error>> {
-        // Get fresh token with automatic refresh
-        let access_token = self
-            .session_provider
-            .get_fresh_token()
-            .await
-            .map_err(|e| format!("Failed to get fresh token: {}", e))?;
-
         // Get PDS URL from session
         let session = self.session_provider.get_session().await;
         let url = format!("{}/xrpc/com.atproto.repo.importRepo", session.pds);
 
-        console_info!(
-            "[RepoTarget] Uploading repository to: {} with fresh authentication",
-            url
-        );
+        console_info!("[RepoTarget] Uploading repository to: {}", url);
 
-        // Try upload with fresh token
-        let result = self
+        // The client adds a fresh token and refreshes it once on a 401
+        let response = self
             .client
-            .post_data_with_auth(
-                &url,
-                data.clone(),
-                "application/vnd.ipld.car",
-                Some(&access_token),
-            )
-            .await;
-
-        // If we get a 401, force refresh and retry once
-        if let Err(ref e) = result {
-            if e.contains("401") || e.contains("Unauthorized") {
-                console_warn!("[RepoTarget] Got 401 error, forcing token refresh and retrying...");
-
-                // Force refresh the token
-                let refreshed_token = self
-                    .session_provider
-                    .force_refresh()
-                    .await
-                    .map_err(|e| format!("Failed to refresh token after 401: {}", e))?;
-
-                // Retry the upload with refreshed token
-                self.client
-                    .post_data_with_auth(
-                        &url,
-                        data,
-                        "application/vnd.ipld.car",
-                        Some(&refreshed_token),
-                    )
-                    .await
-                    .map_err(|e| format!("Failed to upload repo after token refresh: {}", e))?;
-
-                console_info!(
-                    "[RepoTarget] Repository upload completed successfully after token refresh"
-                );
-                return Ok(());
+            .post_data_unchecked(&url, data, "application/vnd.ipld.car")
+            .await
+            .map_err(|e| format!("Failed to upload repo: {}", e))?;
+        if let Err(e) = WasmHttpClient::handle_error_response(&response) {
+            // Rate limits keep their usual string so they are retried as such
+            if response.status == 429 {
+                return Err(format!("Failed to upload repo: {}", e).into());
             }
+            let diagnosis =
+                diagnose_import_failure(response.status, &String::from_utf8_lossy(&response.body));
+            console_error!(
+                "[RepoTarget] importRepo rejected the repository: {}",
+                diagnosis.body
+            );
+            let summary = diagnosis.summary();
+            record_import_failure(diagnosis);
+            return Err(format!("Failed to upload repo: {}: {}", e, summary).into());
         }
 
-        result.map_err(|e| format!("Failed to upload repo: {}", e))?;
-
         console_info!("[RepoTarget] Repository upload completed successfully");
         Ok(())
     }
@@ -217,6 +193,8 @@ pub struct BlobSource {
     pub pds_url: String,
     pub did: String,
     pub client: WasmHttpClient,
+    /// Hosts tried in order when fetching a blob; listing always uses `pds_url`
+    pub origins: Vec<String>,
 }
 
 impl BlobSource {
@@ -225,8 +203,20 @@ impl BlobSource {
             pds_url: session.pds.clone(),
             did: session.did.clone(),
             client: WasmHttpClient::new(),
+            origins: vec![session.pds.clone()],
         }
     }
+
+    /// Fetch blobs from `origins` in order, keeping the source PDS as a last resort
+    pub fn with_origins(session: &ClientSessionCredentials, origins: Vec<String>) -> Self {
+        let mut source = Self::new(session);
+        let mut ordered: Vec<String> = origins.into_iter().filter(|o| !o.is_empty()).collect();
+        if !ordered.contains(&session.pds) {
+            ordered.push(session.pds.clone());
+        }
+        source.origins = ordered;
+        source
+    }
 }
 
 #[async_trait(?Send)]
@@ -302,10 +292,7 @@ impl DataSource for BlobSource {
                     }
 
                     // Small delay between requests to avoid overwhelming the server
-                    #[cfg(target_arch = "wasm32")]
-                    gloo_timers::future::TimeoutFuture::new(100).await;
-                    #[cfg(not(target_arch = "wasm32"))]
-                    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                    sleep_ms(100).await;
                 }
                 Err(e) => {
                     // If we have some results, return them; otherwise propagate error
@@ -330,38 +317,99 @@ impl DataSource for BlobSource {
     }
 
     async fn fetch_stream(&self, cid: &Self::Item) -> Result<BrowserStream, Box<dyn Error>> {
-        let url = format!(
-            "{}/xrpc/com.atproto.sync.getBlob?did={}&cid={}",
-            self.pds_url, self.did, cid
-        );
+        let mut last_error = String::from("no blob origins configured");
 
-        console_debug!("[BlobSource] Fetching blob {} from: {}", cid, url);
+        for origin in &self.origins {
+            let url = format!(
+                "{}/xrpc/com.atproto.sync.getBlob?did={}&cid={}",
+                origin, self.did, cid
+            );
 
-        // Blobs (images, videos) - compression headers removed to fix ReadableStream hanging in WASM
-        // The WasmHttpClient uses direct fetch without Accept-Encoding headers
-        let stream = self
-            .client
-            .get_stream(&url)
-            .await
-            .map_err(|e| format!("Failed to fetch blob stream: {}", e))?;
+            console_debug!("[BlobSource] Fetching blob {} from: {}", cid, url);
 
-        Ok(stream)
+            // Blobs (images, videos) - compression headers removed to fix ReadableStream hanging in WASM
+            // The WasmHttpClient uses direct fetch without Accept-Encoding headers
+            match self.client.get_stream(&url).await {
+                Ok(stream) => return Ok(stream),
+                Err(e) => {
+                    console_warn!(
+                        "[BlobSource] Blob {} unavailable from {}: {}",
+                        cid,
+                        origin,
+                        e
+                    );
+                    last_error = e.to_string();
+                }
+            }
+        }
+
+        Err(format!("Failed to fetch blob stream: {}", last_error).into())
     }
 }
 
+/// A blob the target accepted
+pub struct UploadedBlob<'a> {
+    pub cid: &'a str,
+    pub data: &'a [u8],
+    /// From the start of the first attempt to the accepted one
+    pub duration_ms: u64,
+    /// Upload requests sent for this CID, including retries
+    pub attempts: u32,
+}
+
+/// Callback receiving every accepted upload
+pub type UploadObserver = Rc<dyn Fn(&UploadedBlob)>;
+
 /// Blob data target - uploads blob data to target PDS using WASM
 pub struct BlobTarget {
     pub pds_url: String,
     pub client: WasmHttpClient,
     pub session_provider: RefreshableSessionProvider,
+    /// Called for every blob the target accepted
+    pub upload_observer: Option<UploadObserver>,
+    /// First attempt time and attempt count of uploads not yet accepted
+    pending_attempts: RefCell<HashMap<String, (f64, u32)>>,
 }
 
 impl BlobTarget {
     pub fn new(session_provider: RefreshableSessionProvider) -> Self {
         Self {
             pds_url: String::new(), // Will be populated from session
-            client: WasmHttpClient::new(),
+            client: WasmHttpClient::authenticated(session_provider.clone()),
             session_provider,
+            upload_observer: None,
+            pending_attempts: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Observe successful uploads, e.g. to build a checksum manifest
+    pub fn with_upload_observer(mut self, observer: impl Fn(&UploadedBlob) + 'static) -> Self {
+        self.upload_observer = Some(Rc::new(observer));
+        self
+    }
+
+    /// Count an upload request; retries by the caller land on the same entry
+    fn begin_attempt(&self, cid: &str) {
+        self.pending_attempts
+            .borrow_mut()
+            .entry(cid.to_string())
+            .or_insert((now_ms(), 0))
+            .1 += 1;
+    }
+
+    fn notify_uploaded(&self, cid: &str, data: &[u8]) {
+        let (started_ms, attempts) = self
+            .pending_attempts
+            .borrow_mut()
+            .remove(cid)
+            .unwrap_or((now_ms(), 1));
+        if let Some(observer) = &self.upload_observer {
+            observer(&UploadedBlob {
+                cid,
+                data,
+                duration_ms: (now_ms() - started_ms).max(0.0) as u64,
+                attempts,
+            });
         }
     }
 
@@ -386,102 +434,31 @@ impl DataTarget for BlobTarget {
         data: Vec<u8>,
         _content_type: &str,
     ) -> Result<(), Box<dyn Error>> {
-        // Get fresh token with automatic refresh
-        let access_token = self
-            .session_provider
-            .get_fresh_token()
-            .await
-            .map_err(|e| format!("Failed to get fresh token for blob {}: {}", cid, e))?;
-
-        // Basic JWT format validation (should have 3 parts separated by dots)
-        let token_parts: Vec<&str> = access_token.split('.').collect();
-        if token_parts.len() != 3 {
-            return Err("Invalid JWT token format - expected 3 parts separated by dots".into());
-        }
-
         // Get PDS URL from session
         let session = self.session_provider.get_session().await;
         let url = format!("{}/xrpc/com.atproto.repo.uploadBlob", session.pds);
 
-        console_debug!(
-            "[BlobTarget] Uploading blob {} to: {} with fresh authentication (token length: {})",
-            cid,
-            url,
-            access_token.len()
-        );
+        console_debug!("[BlobTarget] Uploading blob {} to: {}", cid, url);
 
-        // Try upload with fresh token
-        let result = self
+        // The client adds a fresh token and refreshes it once on a 401
+        self.begin_attempt(&cid);
+        match self
             .client
-            .post_data_with_auth(
-                &url,
-                data.clone(),
-                "application/octet-stream",
-                Some(&access_token),
-            )
-            .await;
-
-        // Handle errors with potential token refresh retry
-        match result {
+            .post_data(&url, data.clone(), "application/octet-stream")
+            .await
+        {
             Ok(_) => {
                 console_debug!("[BlobTarget] Blob {} upload completed", cid);
+                self.notify_uploaded(&cid, &data);
                 Ok(())
             }
             Err(e) => {
-                // Check if this is an auth error (401)
-                if e.contains("401") || e.contains("Unauthorized") {
-                    console_warn!(
-                        "[BlobTarget] Got 401 error for blob {}, forcing token refresh and retrying...",
-                        cid
-                    );
+                console_error!("[BlobTarget] Upload failed for blob {}: {}", cid, e);
 
-                    // Force refresh the token
-                    let refreshed_token =
-                        self.session_provider
-                            .force_refresh()
-                            .await
-                            .map_err(|refresh_err| {
-                                format!(
-                                    "Failed to refresh token after 401 for blob {}: {}",
-                                    cid, refresh_err
-                                )
-                            })?;
-
-                    // Retry the upload with refreshed token
-                    self.client
-                        .post_data_with_auth(
-                            &url,
-                            data,
-                            "application/octet-stream",
-                            Some(&refreshed_token),
-                        )
-                        .await
-                        .map_err(|retry_err| {
-                            console_error!(
-                                "[BlobTarget] Upload failed for blob {} after token refresh: {}",
-                                cid,
-                                retry_err
-                            );
-                            format!(
-                                "Failed to upload blob {} after token refresh: {}",
-                                cid, retry_err
-                            )
-                        })?;
-
-                    console_debug!(
-                        "[BlobTarget] Blob {} upload completed after token refresh",
-                        cid
-                    );
-                    Ok(())
+                if e.contains("Gateway timeout (504)") {
+                    Err(format!("RATE_LIMIT:Failed to upload blob {}: {}", cid, e).into())
                 } else {
-                    // Not an auth error - check for rate limiting
-                    console_error!("[BlobTarget] Upload failed for blob {}: {}", cid, e);
-
-                    if e.contains("Gateway timeout (504)") {
-                        Err(format!("RATE_LIMIT:Failed to upload blob {}: {}", cid, e).into())
-                    } else {
-                        Err(format!("Failed to upload blob {}: {}", cid, e).into())
-                    }
+                    Err(format!("Failed to upload blob {}: {}", cid, e).into())
                 }
             }
         }
@@ -496,9 +473,7 @@ impl DataTarget for BlobTarget {
         let session = self.session_provider.get_session().await;
         let base_url = format!("{}/xrpc/com.atproto.repo.listMissingBlobs", session.pds);
 
-        console_info!(
-            "[BlobTarget] Starting paginated missing blob listing with fresh authentication"
-        );
+        console_info!("[BlobTarget] Starting paginated missing blob listing");
 
         #[derive(serde::Deserialize)]
         struct RecordBlob {
@@ -515,13 +490,6 @@ impl DataTarget for BlobTarget {
         }
 
         loop {
-            // Get fresh token for each request to handle long-running pagination
-            let access_token = self
-                .session_provider
-                .get_fresh_token()
-                .await
-                .map_err(|e| format!("Failed to get fresh token: {}", e))?;
-
             // Build URL with pagination parameters
             let mut url = format!("{}?limit={}", base_url, BATCH_SIZE);
             if let Some(ref c) = cursor {
@@ -532,7 +500,7 @@ impl DataTarget for BlobTarget {
 
             let response: ListMissingBlobsOutput = self
                 .client
-                .get_json_with_auth(&url, Some(&access_token))
+                .get_json(&url)
                 .await
                 .map_err(|e| format!("Failed to list missing blobs: {}", e))?;
 
@@ -554,7 +522,7 @@ impl DataTarget for BlobTarget {
             }
 
             // Small delay between requests to avoid overwhelming the server
-            gloo_timers::future::TimeoutFuture::new(100).await;
+            sleep_ms(100).await;
         }
 
         console_info!(
@@ -566,6 +534,30 @@ impl DataTarget for BlobTarget {
     }
 }
 
+/// Blob source for upload-only repair: every item must already be in the local cache
+pub struct CacheOnlySource;
+
+#[async_trait(?Send)]
+impl DataSource for CacheOnlySource {
+    type Item = String; // CID
+
+    async fn list_items(&self) -> Result<Vec<Self::Item>, Box<dyn Error>> {
+        Ok(vec![])
+    }
+
+    async fn fetch_stream(&self, item: &Self::Item) -> Result<BrowserStream, Box<dyn Error>> {
+        Err(format!(
+            "Blob {} is not cached locally and the source PDS is not contacted in repair mode",
+            item
+        )
+        .into())
+    }
+
+    fn prefetches(&self) -> bool {
+        false
+    }
+}
+
 // ============================================================================
 // Storage Backend Implementations
 // ============================================================================
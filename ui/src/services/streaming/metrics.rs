@@ -5,8 +5,11 @@
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Mutex;
 use std::time::{Duration, Instant};
 
+use crate::services::client::types::current_time_ms;
+
 /// Comprehensive streaming performance metrics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StreamingMetrics {
@@ -349,6 +352,193 @@ fn generate_warnings(metrics: &StreamingMetrics) -> Vec<StreamingWarning> {
     warnings
 }
 
+// ============================================================================
+// Per-operation latency statistics
+// ============================================================================
+
+/// Upper bounds (ms) of the latency histogram buckets; the last bucket is unbounded
+const LATENCY_BUCKETS_MS: [f64; 14] = [
+    1.0, 2.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0, 30000.0,
+];
+
+static PIPELINE_HISTOGRAMS: Mutex<Vec<(PipelineOperation, LatencyHistogram)>> =
+    Mutex::new(Vec::new());
+
+/// Network direction or storage backend operation timed by the blob pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PipelineOperation {
+    /// Full download of one item from the source PDS
+    NetworkDownload,
+    /// Full upload of one item to the target PDS
+    NetworkUpload,
+    OpfsWrite,
+    OpfsRead,
+    IndexedDbWrite,
+    IndexedDbRead,
+}
+
+impl PipelineOperation {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PipelineOperation::NetworkDownload => "Network download",
+            PipelineOperation::NetworkUpload => "Network upload",
+            PipelineOperation::OpfsWrite => "OPFS write",
+            PipelineOperation::OpfsRead => "OPFS read",
+            PipelineOperation::IndexedDbWrite => "IndexedDB write",
+            PipelineOperation::IndexedDbRead => "IndexedDB read",
+        }
+    }
+}
+
+/// Fixed-bucket latency histogram with byte totals for throughput
+#[derive(Debug, Clone, Default)]
+pub struct LatencyHistogram {
+    buckets: [u64; LATENCY_BUCKETS_MS.len() + 1],
+    count: u64,
+    total_ms: f64,
+    max_ms: f64,
+    total_bytes: u64,
+}
+
+impl LatencyHistogram {
+    pub fn record(&mut self, latency_ms: f64, bytes: u64) {
+        let latency_ms = latency_ms.max(0.0);
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|bound| latency_ms <= *bound)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.buckets[bucket] += 1;
+        self.count += 1;
+        self.total_ms += latency_ms;
+        self.max_ms = self.max_ms.max(latency_ms);
+        self.total_bytes += bytes;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn total_ms(&self) -> f64 {
+        self.total_ms
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.total_bytes
+    }
+
+    /// Running totals per bucket as `(upper bound ms, count)`; `None` is the unbounded bucket
+    pub fn cumulative_buckets(&self) -> Vec<(Option<f64>, u64)> {
+        let mut seen = 0;
+        self.buckets
+            .iter()
+            .enumerate()
+            .map(|(i, count)| {
+                seen += count;
+                (LATENCY_BUCKETS_MS.get(i).copied(), seen)
+            })
+            .collect()
+    }
+
+    /// Estimate a percentile (0.0..=1.0) as the upper bound of the bucket containing it
+    pub fn percentile(&self, p: f64) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+
+        let rank = ((self.count as f64) * p.clamp(0.0, 1.0)).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (i, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return LATENCY_BUCKETS_MS
+                    .get(i)
+                    .copied()
+                    .unwrap_or(self.max_ms)
+                    .min(self.max_ms);
+            }
+        }
+        self.max_ms
+    }
+
+    pub fn stats(&self, operation: PipelineOperation) -> OperationStats {
+        OperationStats {
+            operation,
+            count: self.count,
+            p50_ms: self.percentile(0.50),
+            p95_ms: self.percentile(0.95),
+            max_ms: self.max_ms,
+            avg_ms: if self.count > 0 {
+                self.total_ms / self.count as f64
+            } else {
+                0.0
+            },
+            total_bytes: self.total_bytes,
+            throughput_bytes_per_sec: if self.total_ms > 0.0 {
+                self.total_bytes as f64 / (self.total_ms / 1000.0)
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+/// Aggregated latency and throughput for one pipeline operation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OperationStats {
+    pub operation: PipelineOperation,
+    pub count: u64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+    pub avg_ms: f64,
+    pub total_bytes: u64,
+    /// Bytes per second of time spent inside the operation
+    pub throughput_bytes_per_sec: f64,
+}
+
+/// Record a completed operation that started at `started_ms` (from `current_time_ms`)
+pub fn record_pipeline_operation(operation: PipelineOperation, started_ms: f64, bytes: u64) {
+    let latency_ms = current_time_ms() - started_ms;
+    if let Ok(mut histograms) = PIPELINE_HISTOGRAMS.lock() {
+        match histograms.iter_mut().find(|(op, _)| *op == operation) {
+            Some((_, histogram)) => histogram.record(latency_ms, bytes),
+            None => {
+                let mut histogram = LatencyHistogram::default();
+                histogram.record(latency_ms, bytes);
+                histograms.push((operation, histogram));
+            }
+        }
+    }
+}
+
+/// Statistics for every operation recorded so far, in first-recorded order
+pub fn pipeline_stats() -> Vec<OperationStats> {
+    PIPELINE_HISTOGRAMS
+        .lock()
+        .map(|histograms| {
+            histograms
+                .iter()
+                .map(|(op, histogram)| histogram.stats(*op))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Raw histograms for every operation recorded so far, in first-recorded order
+pub fn pipeline_histograms() -> Vec<(PipelineOperation, LatencyHistogram)> {
+    PIPELINE_HISTOGRAMS
+        .lock()
+        .map(|histograms| histograms.clone())
+        .unwrap_or_default()
+}
+
+/// Clear all recorded operation statistics
+pub fn reset_pipeline_stats() {
+    if let Ok(mut histograms) = PIPELINE_HISTOGRAMS.lock() {
+        histograms.clear();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -378,4 +568,26 @@ mod tests {
         let efficiency = collector.chunk_efficiency();
         assert!((efficiency - 0.6666666666666666).abs() < 0.0001);
     }
+
+    #[test]
+    fn test_latency_histogram_percentiles() {
+        let mut histogram = LatencyHistogram::default();
+        for _ in 0..90 {
+            histogram.record(8.0, 1000);
+        }
+        for _ in 0..10 {
+            histogram.record(400.0, 1000);
+        }
+
+        let stats = histogram.stats(PipelineOperation::OpfsWrite);
+        assert_eq!(stats.count, 100);
+        assert_eq!(stats.p50_ms, 10.0);
+        assert_eq!(stats.p95_ms, 400.0);
+        assert_eq!(stats.max_ms, 400.0);
+        assert_eq!(stats.total_bytes, 100_000);
+        assert!((stats.avg_ms - 47.2).abs() < 0.001);
+        assert!(stats.throughput_bytes_per_sec > 0.0);
+
+        assert_eq!(LatencyHistogram::default().percentile(0.5), 0.0);
+    }
 }
@@ -0,0 +1,380 @@
+//! Reduced "small account only" mode for browsers without OPFS or IndexedDB
+//!
+//! Old browsers and strict privacy modes can leave both persistent backends
+//! unavailable, and [`BrowserStorage`](super::BrowserStorage) can't even be
+//! opened. Those environments still get a migration path: transfers are
+//! buffered in memory, small items are mirrored to `localStorage`, and an
+//! up-front estimate refuses accounts larger than [`MinimalFlowLimits`] before
+//! anything is created on the target.
+
+use super::traits::{DataChunk, StorageBackend};
+use crate::utils::byte_format::format_size;
+use crate::utils::platform::{
+    has_browser_window, storage_delete, storage_get, storage_set, StorageArea,
+};
+use crate::{console_debug, console_info, console_warn};
+use async_trait::async_trait;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Mutex;
+
+/// Average encoded size of a repository block, used for the up-front estimate
+const ESTIMATED_BYTES_PER_BLOCK: u64 = 400;
+
+/// Average blob size, used for the up-front estimate
+pub const ESTIMATED_BYTES_PER_BLOB: u64 = 250 * 1024;
+
+/// Prefix of the `localStorage` keys holding mirrored items
+const LOCAL_CACHE_KEY_PREFIX: &str = "minimal_cache/";
+
+static STORAGE_CAPABILITIES: Mutex<Option<StorageCapabilities>> = Mutex::new(None);
+
+/// Persistent storage backends this browser can actually open
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageCapabilities {
+    pub opfs: bool,
+    pub indexeddb: bool,
+    /// Files on the local disk, in the desktop app
+    #[serde(default)]
+    pub disk: bool,
+}
+
+impl StorageCapabilities {
+    /// Every persistent backend is missing, so only the minimal flow can run
+    pub fn requires_minimal_flow(&self) -> bool {
+        !self.opfs && !self.indexeddb && !self.disk
+    }
+}
+
+/// Probe OPFS and IndexedDB once per page load
+pub async fn detect_storage_capabilities() -> StorageCapabilities {
+    if let Some(known) = STORAGE_CAPABILITIES.lock().ok().and_then(|guard| *guard) {
+        return known;
+    }
+
+    let capabilities = if has_browser_window() {
+        StorageCapabilities {
+            opfs: opfs::persistent::app_specific_dir().await.is_ok(),
+            indexeddb: rexie::Rexie::builder("tektite-capability-probe")
+                .build()
+                .await
+                .is_ok(),
+            disk: false,
+        }
+    } else {
+        StorageCapabilities {
+            opfs: false,
+            indexeddb: false,
+            disk: super::disk_staging_dir().is_some(),
+        }
+    };
+
+    console_info!(
+        "[Storage] Capabilities: OPFS={}, IndexedDB={}, disk={}",
+        capabilities.opfs,
+        capabilities.indexeddb,
+        capabilities.disk
+    );
+    if let Ok(mut guard) = STORAGE_CAPABILITIES.lock() {
+        *guard = Some(capabilities);
+    }
+    capabilities
+}
+
+/// Size caps for the minimal flow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MinimalFlowLimits {
+    /// Largest single item (repository CAR or blob) held in memory
+    pub max_item_bytes: u64,
+    pub max_blobs: u64,
+    /// Estimated repository plus blob bytes
+    pub max_total_bytes: u64,
+    /// Items up to this size are mirrored to `localStorage`
+    pub max_local_cache_item_bytes: u64,
+    /// Total `localStorage` budget for mirrored items
+    pub max_local_cache_bytes: u64,
+}
+
+impl Default for MinimalFlowLimits {
+    fn default() -> Self {
+        Self {
+            max_item_bytes: 25 * 1024 * 1024,
+            max_blobs: 250,
+            max_total_bytes: 100 * 1024 * 1024,
+            max_local_cache_item_bytes: 256 * 1024,
+            max_local_cache_bytes: 3 * 1024 * 1024,
+        }
+    }
+}
+
+impl MinimalFlowLimits {
+    /// Short human-readable form for notices
+    pub fn summary(&self) -> String {
+        format!(
+            "up to {} blobs and about {} in total",
+            self.max_blobs,
+            format_size(self.max_total_bytes)
+        )
+    }
+}
+
+/// Rough account size from the source's checkAccountStatus counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AccountSizeEstimate {
+    pub repo_blocks: u64,
+    pub blobs: u64,
+}
+
+impl AccountSizeEstimate {
+    pub fn repo_bytes(&self) -> u64 {
+        self.repo_blocks.saturating_mul(ESTIMATED_BYTES_PER_BLOCK)
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.repo_bytes()
+            .saturating_add(self.blobs.saturating_mul(ESTIMATED_BYTES_PER_BLOB))
+    }
+}
+
+/// Refuse accounts the minimal flow can't hold, with a message for the user
+pub fn check_minimal_flow_fits(
+    estimate: &AccountSizeEstimate,
+    limits: &MinimalFlowLimits,
+) -> Result<(), String> {
+    const ADVICE: &str = "Please use a current version of Chrome, Firefox or Safari outside private browsing to migrate this account.";
+
+    if estimate.repo_bytes() > limits.max_item_bytes {
+        return Err(format!(
+            "This browser has no persistent storage, so only small accounts can be migrated. Your repository is about {}; the limit here is {}. {}",
+            format_size(estimate.repo_bytes()),
+            format_size(limits.max_item_bytes),
+            ADVICE
+        ));
+    }
+    if estimate.blobs > limits.max_blobs {
+        return Err(format!(
+            "This browser has no persistent storage, so only small accounts can be migrated. Your account has {} images and videos; the limit here is {}. {}",
+            estimate.blobs, limits.max_blobs, ADVICE
+        ));
+    }
+    if estimate.total_bytes() > limits.max_total_bytes {
+        return Err(format!(
+            "This browser has no persistent storage, so only small accounts can be migrated. Your account is about {}; the limit here is {}. {}",
+            format_size(estimate.total_bytes()),
+            format_size(limits.max_total_bytes),
+            ADVICE
+        ));
+    }
+    Ok(())
+}
+
+/// In-memory transfer buffer that mirrors small items to `localStorage`
+pub struct LocalStorageCache {
+    limits: MinimalFlowLimits,
+    buffers: HashMap<String, Vec<u8>>,
+    local_cache_bytes: u64,
+}
+
+impl LocalStorageCache {
+    pub fn new(limits: MinimalFlowLimits) -> Self {
+        Self {
+            limits,
+            buffers: HashMap::new(),
+            local_cache_bytes: 0,
+        }
+    }
+
+    fn cache_key(id: &str) -> String {
+        format!("{}{}", LOCAL_CACHE_KEY_PREFIX, id)
+    }
+
+    fn read_local(id: &str) -> Option<Vec<u8>> {
+        let encoded: String = storage_get(StorageArea::Local, &Self::cache_key(id)).ok()?;
+        base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .ok()
+    }
+
+    /// Drop a mirrored item from `localStorage`
+    pub fn forget(id: &str) {
+        storage_delete(StorageArea::Local, &Self::cache_key(id));
+    }
+}
+
+#[async_trait(?Send)]
+impl StorageBackend for LocalStorageCache {
+    async fn write_chunk(&mut self, chunk: &DataChunk) -> Result<(), Box<dyn Error>> {
+        let buffer = self.buffers.entry(chunk.id.clone()).or_default();
+        let end = chunk.offset + chunk.data.len();
+        if end as u64 > self.limits.max_item_bytes {
+            self.buffers.remove(&chunk.id);
+            return Err(format!(
+                "{} exceeds the {} per-item limit of the minimal storage mode",
+                chunk.id,
+                format_size(self.limits.max_item_bytes)
+            )
+            .into());
+        }
+        if buffer.len() < end {
+            buffer.resize(end, 0);
+        }
+        buffer[chunk.offset..end].copy_from_slice(&chunk.data);
+        Ok(())
+    }
+
+    async fn finalize(&mut self, id: &str) -> Result<(), Box<dyn Error>> {
+        let Some(data) = self.buffers.get(id) else {
+            return Ok(());
+        };
+        let size = data.len() as u64;
+        if size > self.limits.max_local_cache_item_bytes
+            || self.local_cache_bytes + size > self.limits.max_local_cache_bytes
+        {
+            console_debug!(
+                "[MinimalStorage] Keeping {} ({} bytes) in memory only",
+                id,
+                size
+            );
+            return Ok(());
+        }
+
+        let encoded = base64::engine::general_purpose::STANDARD.encode(data);
+        match storage_set(StorageArea::Local, &Self::cache_key(id), encoded) {
+            Ok(()) => {
+                self.local_cache_bytes += size;
+                // The mirrored copy is enough; release the memory
+                self.buffers.remove(id);
+            }
+            Err(e) => console_warn!("[MinimalStorage] localStorage rejected {}: {:?}", id, e),
+        }
+        Ok(())
+    }
+
+    async fn read_data(&self, id: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        if let Some(data) = self.buffers.get(id) {
+            return Ok(data.clone());
+        }
+        Self::read_local(id).ok_or_else(|| format!("{} is not buffered", id).into())
+    }
+
+    async fn cached_data(&self, id: &str) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        // Only blobs can be checked against their key; anything else is re-fetched
+        Ok(Self::read_local(id).filter(|data| super::verify_cached_blob(id, data).is_ok()))
+    }
+}
+
+/// The full persistent backend, files on disk in the desktop app, or the
+/// minimal one when there is neither
+pub enum SelectedStorage<B> {
+    Full(B),
+    Disk(super::DiskStorage),
+    Minimal(LocalStorageCache),
+}
+
+#[async_trait(?Send)]
+impl<B: StorageBackend> StorageBackend for SelectedStorage<B> {
+    async fn write_chunk(&mut self, chunk: &DataChunk) -> Result<(), Box<dyn Error>> {
+        match self {
+            SelectedStorage::Full(storage) => storage.write_chunk(chunk).await,
+            SelectedStorage::Disk(storage) => storage.write_chunk(chunk).await,
+            SelectedStorage::Minimal(storage) => storage.write_chunk(chunk).await,
+        }
+    }
+
+    async fn finalize(&mut self, id: &str) -> Result<(), Box<dyn Error>> {
+        match self {
+            SelectedStorage::Full(storage) => storage.finalize(id).await,
+            SelectedStorage::Disk(storage) => storage.finalize(id).await,
+            SelectedStorage::Minimal(storage) => storage.finalize(id).await,
+        }
+    }
+
+    async fn read_data(&self, id: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        match self {
+            SelectedStorage::Full(storage) => storage.read_data(id).await,
+            SelectedStorage::Disk(storage) => storage.read_data(id).await,
+            SelectedStorage::Minimal(storage) => storage.read_data(id).await,
+        }
+    }
+
+    async fn cached_data(&self, id: &str) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        match self {
+            SelectedStorage::Full(storage) => storage.cached_data(id).await,
+            SelectedStorage::Disk(storage) => storage.cached_data(id).await,
+            SelectedStorage::Minimal(storage) => storage.cached_data(id).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+
+    fn chunk(id: &str, offset: usize, data: &'static [u8]) -> DataChunk {
+        DataChunk {
+            id: id.to_string(),
+            data: Bytes::from_static(data),
+            offset,
+            total_size: None,
+        }
+    }
+
+    #[test]
+    fn test_estimate_check_refuses_large_accounts() {
+        let limits = MinimalFlowLimits::default();
+        let small = AccountSizeEstimate {
+            repo_blocks: 5_000,
+            blobs: 40,
+        };
+        assert!(check_minimal_flow_fits(&small, &limits).is_ok());
+
+        let many_blobs = AccountSizeEstimate {
+            repo_blocks: 5_000,
+            blobs: limits.max_blobs + 1,
+        };
+        assert!(check_minimal_flow_fits(&many_blobs, &limits)
+            .unwrap_err()
+            .contains("images and videos"));
+
+        let huge_repo = AccountSizeEstimate {
+            repo_blocks: 1_000_000,
+            blobs: 0,
+        };
+        assert!(check_minimal_flow_fits(&huge_repo, &limits).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_local_storage_cache_mirrors_small_items() {
+        let mut cache = LocalStorageCache::new(MinimalFlowLimits::default());
+        cache
+            .write_chunk(&chunk("item-a", 0, b"hello "))
+            .await
+            .unwrap();
+        cache
+            .write_chunk(&chunk("item-a", 6, b"world"))
+            .await
+            .unwrap();
+        cache.finalize("item-a").await.unwrap();
+
+        // Released from memory once mirrored, but still readable
+        assert!(cache.buffers.is_empty());
+        assert_eq!(cache.read_data("item-a").await.unwrap(), b"hello world");
+        LocalStorageCache::forget("item-a");
+        assert!(cache.read_data("item-a").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_local_storage_cache_enforces_item_limit() {
+        let mut cache = LocalStorageCache::new(MinimalFlowLimits {
+            max_item_bytes: 4,
+            ..MinimalFlowLimits::default()
+        });
+        assert!(cache
+            .write_chunk(&chunk("big", 0, b"too large"))
+            .await
+            .is_err());
+    }
+}
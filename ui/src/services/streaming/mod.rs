@@ -3,18 +3,28 @@
 //! This module provides reusable streaming patterns for both repository and blob migration,
 //! implementing the channel-tee pattern described in CLAUDE.md
 
+pub mod blob_cache;
 pub mod browser_storage;
+pub mod checksum;
+pub mod disk_storage;
 pub mod errors;
 pub mod implementations;
 pub mod metrics;
+pub mod minimal_storage;
 pub mod orchestrator;
+pub mod storage_usage;
 pub mod traits;
 pub mod wasm_http_client;
 
+pub use blob_cache::*;
 pub use browser_storage::*;
+pub use checksum::*;
+pub use disk_storage::*;
 pub use errors::*;
 pub use implementations::*;
 pub use metrics::*;
+pub use minimal_storage::*;
 pub use orchestrator::*;
+pub use storage_usage::*;
 pub use traits::*;
 pub use wasm_http_client::*;
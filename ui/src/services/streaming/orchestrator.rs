@@ -1,9 +1,24 @@
 //! WASM-first sync orchestrator implementing the channel-tee pattern
 
+use super::checksum::{sha256_hex, StreamChecksum};
+use super::metrics::{record_pipeline_operation, PipelineOperation};
+use super::storage_usage::backend_usage;
 use super::traits::*;
+use crate::services::client::host_stats::{
+    host_of, host_retry_config, remember_tuned_concurrency, stats_for_host, upload_tuning,
+    AimdConcurrency,
+};
+use crate::services::client::types::current_time_ms;
+use crate::services::config::{get_global_config, RetryConfig, RetryPhase};
+use crate::services::retry::{claim_retry_delay, sleep_ms};
 use crate::{console_debug, console_error, console_info, console_warn};
-use futures_util::StreamExt;
+use futures_util::future::{select, Either, LocalBoxFuture};
+use futures_util::stream::FuturesUnordered;
+use futures_util::{FutureExt, StreamExt};
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::error::Error;
+use std::rc::Rc;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -14,6 +29,8 @@ pub struct ProgressUpdate {
     pub phase: ProgressPhase,
     pub bytes_processed: u64,
     pub total_bytes_estimate: u64,
+    /// Whether `total_bytes_estimate` is the real size rather than a guess
+    pub total_known: bool,
     pub event: ProgressEvent,
 }
 
@@ -42,16 +59,208 @@ const CHANNEL_CAPACITY: usize = 16;
 #[allow(dead_code)]
 const STREAM_TIMEOUT_SECS: u64 = 30;
 
-/// Maximum retry attempts for failed operations
-const MAX_RETRY_ATTEMPTS: u32 = 3;
+/// Uploads fail with "Upload ..." errors, everything else is the download
+fn is_upload_error(error: &str) -> bool {
+    error.starts_with("Upload task failed") || error.starts_with("Upload error")
+}
+
+/// Whether an item may be prefetched into memory: the pipeline is on, memory
+/// buffers still awaiting a flush are under the limit, and the item's size, if
+/// the source announced it, fits within the limit too
+fn prefetch_fits(content_length: Option<u64>, buffered_bytes: u64, limit: u64) -> bool {
+    limit > 0 && buffered_bytes < limit && content_length.is_none_or(|length| length <= limit)
+}
+
+/// Next item's bytes, downloaded while the current item uploads
+struct PrefetchedItem {
+    id: String,
+    data: Vec<u8>,
+}
+
+/// Hand `update` to the caller's progress callback, if there is one
+fn report_progress<P: FnMut(ProgressUpdate)>(
+    progress: &Rc<RefCell<Option<P>>>,
+    update: ProgressUpdate,
+) {
+    if let Some(callback) = progress.borrow_mut().as_mut() {
+        callback(update);
+    }
+}
+
+/// A callback of its own for one item in flight, forwarding to the caller's
+fn forward_progress<P: FnMut(ProgressUpdate) + 'static>(
+    progress: &Rc<RefCell<Option<P>>>,
+) -> impl FnMut(ProgressUpdate) + 'static {
+    let progress = Rc::clone(progress);
+    move |update| report_progress(&progress, update)
+}
+
+/// How processing one item ended
+struct ItemAttempts {
+    result: Result<u64, String>,
+    /// Failed attempts, including the last one when the item failed
+    retry_count: u32,
+}
+
+/// Which budget a failed item is retried under
+fn failed_phase(error: &str, download: RetryPhase, upload: RetryPhase) -> RetryPhase {
+    if is_upload_error(error) {
+        upload
+    } else {
+        download
+    }
+}
 
 /// WASM-first sync orchestrator for repository and blob migration
-pub struct SyncOrchestrator;
+pub struct SyncOrchestrator {
+    retry: RetryConfig,
+    download_phase: RetryPhase,
+    upload_phase: RetryPhase,
+    /// Source and target hosts, whose statistics adapt the retry budgets
+    hosts: Option<(String, String)>,
+    /// Whether downloads are hashed and re-checked when read back from storage
+    stream_checksums: bool,
+    /// sha-256 of each item as downloaded, kept across retries of the item
+    checksums: RefCell<HashMap<String, String>>,
+    /// Largest item downloaded ahead while the previous one uploads; 0 disables
+    /// the pipeline
+    prefetch_max_bytes: u64,
+    /// Items in flight at once, tuned to the target's answers; one item
+    /// at a time without it
+    upload_tuning: RefCell<Option<AimdConcurrency>>,
+}
 
 impl SyncOrchestrator {
-    /// Create a new sync orchestrator
+    /// Create a new sync orchestrator with the configured blob retry budgets
     pub fn new() -> Self {
-        Self
+        Self::with_retry(
+            get_global_config().retry,
+            RetryPhase::BlobDownload,
+            RetryPhase::BlobUpload,
+        )
+    }
+
+    /// Retry failed downloads and uploads under the given phases' budgets
+    pub fn with_retry(
+        retry: RetryConfig,
+        download_phase: RetryPhase,
+        upload_phase: RetryPhase,
+    ) -> Self {
+        Self {
+            retry,
+            download_phase,
+            upload_phase,
+            hosts: None,
+            stream_checksums: false,
+            checksums: RefCell::new(HashMap::new()),
+            prefetch_max_bytes: 0,
+            upload_tuning: RefCell::new(None),
+        }
+    }
+
+    /// Download the next item while the current one uploads, for items up to
+    /// `max_bytes`; larger items and items arriving under memory pressure are
+    /// left to the regular download
+    pub fn with_prefetch(mut self, max_bytes: u64) -> Self {
+        self.prefetch_max_bytes = max_bytes;
+        self
+    }
+
+    /// Process items side by side, starting `initial` at a time and tuning the
+    /// width to the target host's 429 and 5xx answers, up to `max`. Needs
+    /// [`Self::with_hosts`] first; a width learned earlier for the target is
+    /// where tuning starts.
+    pub fn with_upload_tuning(self, initial: usize, max: usize, error_threshold: f64) -> Self {
+        let tuning = self
+            .hosts
+            .as_ref()
+            .map(|(_, target)| upload_tuning(target, initial, max, error_threshold));
+        *self.upload_tuning.borrow_mut() = tuning;
+        self
+    }
+
+    /// Items kept in flight at once
+    fn window_width(&self) -> usize {
+        self.upload_tuning
+            .borrow()
+            .as_ref()
+            .map_or(1, AimdConcurrency::current)
+    }
+
+    /// Requests made to the target host so far and how many of them failed
+    fn target_error_counts(&self) -> Option<(u32, u32)> {
+        let (_, target) = self.hosts.as_ref()?;
+        let stats = stats_for_host(target).unwrap_or_default();
+        Some((stats.requests, stats.errors()))
+    }
+
+    /// Widen or narrow the window after how the target answered since
+    /// `counts_before` was taken
+    fn tune_after_window(&self, counts_before: Option<(u32, u32)>) {
+        let mut tuning = self.upload_tuning.borrow_mut();
+        let (Some(tuning), Some((requests_before, errors_before)), Some((requests, errors))) =
+            (tuning.as_mut(), counts_before, self.target_error_counts())
+        else {
+            return;
+        };
+        let previous = tuning.current();
+        let current = tuning.observe(
+            requests.saturating_sub(requests_before),
+            errors.saturating_sub(errors_before),
+        );
+        if current != previous {
+            if let Some((_, target)) = &self.hosts {
+                console_info!(
+                    "[SyncOrchestrator] Upload concurrency for {}: {} -> {}",
+                    target,
+                    previous,
+                    current
+                );
+                remember_tuned_concurrency(target, current);
+            }
+        }
+    }
+
+    /// Hash every item while it downloads and refuse to upload a stored copy
+    /// that no longer matches
+    pub fn with_stream_checksums(mut self) -> Self {
+        self.stream_checksums = true;
+        self
+    }
+
+    /// Check bytes read back from storage against the digest taken while
+    /// downloading `id`
+    fn verify_stored(&self, id: &str, data: &[u8]) -> Result<(), String> {
+        let Some(expected) = self.checksums.borrow().get(id).cloned() else {
+            return Ok(());
+        };
+        match sha256_hex(data) {
+            Some(actual) if actual != expected => Err(format!(
+                "Stored copy of {} no longer matches the download (sha-256 {} became {}, {} bytes); browser storage corrupted it before the upload",
+                id,
+                expected,
+                actual,
+                data.len()
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Adapt each phase's retries to how the source or target host has behaved
+    pub fn with_hosts(mut self, source_url: &str, target_url: &str) -> Self {
+        self.hosts = Some((host_of(source_url), host_of(target_url)));
+        self
+    }
+
+    /// Retry budgets for a failure in `phase`, adapted to the host that failed
+    fn retry_config(&self, phase: RetryPhase, error: &str) -> RetryConfig {
+        match &self.hosts {
+            Some((_, target)) if is_upload_error(error) => {
+                host_retry_config(&self.retry, phase, target)
+            }
+            Some((source, _)) => host_retry_config(&self.retry, phase, source),
+            None => self.retry.clone(),
+        }
     }
 
     /// Generic sync method using channel-tee pattern for WASM
@@ -65,7 +274,7 @@ impl SyncOrchestrator {
         source: S,
         target: T,
         storage: B,
-        mut progress_callback: Option<P>,
+        progress_callback: Option<P>,
     ) -> Result<SyncResult, Box<dyn Error>>
     where
         S: DataSource + 'static,
@@ -74,8 +283,6 @@ impl SyncOrchestrator {
         S::Item: Clone + ToString,
         P: FnMut(ProgressUpdate) + 'static, // Enhanced progress callback with detailed phase information
     {
-        console_info!("[SyncOrchestrator] Starting WASM sync with channel-tee pattern");
-
         // Get items to sync
         let items = source.list_items().await?;
         let missing = target.list_missing().await?;
@@ -90,6 +297,31 @@ impl SyncOrchestrator {
                 .collect()
         };
 
+        self.sync_items_with_tee(source, target, storage, items_to_sync, progress_callback)
+            .await
+    }
+
+    /// Sync a caller-chosen set of items using the channel-tee pattern
+    ///
+    /// Unlike [`Self::sync_with_tee`], no listing is done here; callers that
+    /// already decided what to transfer pass the items directly.
+    pub async fn sync_items_with_tee<S, T, B, P>(
+        &self,
+        source: S,
+        target: T,
+        storage: B,
+        items_to_sync: Vec<S::Item>,
+        progress_callback: Option<P>,
+    ) -> Result<SyncResult, Box<dyn Error>>
+    where
+        S: DataSource + 'static,
+        T: DataTarget + 'static,
+        B: StorageBackend + 'static,
+        S::Item: Clone + ToString,
+        P: FnMut(ProgressUpdate) + 'static,
+    {
+        console_info!("[SyncOrchestrator] Starting WASM sync with channel-tee pattern");
+
         console_info!(
             "[SyncOrchestrator] Processing {} items for sync",
             items_to_sync.len()
@@ -102,138 +334,157 @@ impl SyncOrchestrator {
         // Create shared storage reference
         let storage = Arc::new(Mutex::new(storage));
 
-        // Process each item with retry logic
-        for item in items_to_sync {
-            let id = item.to_string();
-            console_info!("[SyncOrchestrator] Processing item: {}", id);
+        // Keep as many items in flight as upload tuning allows, starting the
+        // next one as soon as any finishes, and download the next queued item
+        // while the window is full. Every item reports its own progress.
+        let progress = Rc::new(RefCell::new(progress_callback));
+        let source = &source;
+        let target = &target;
+        let mut queue = items_to_sync.into_iter().peekable();
+        let mut in_flight = FuturesUnordered::new();
+        let mut prefetch: Option<LocalBoxFuture<'_, Option<PrefetchedItem>>> = None;
+        let mut prefetched: Option<PrefetchedItem> = None;
+        let mut counts_before = self.target_error_counts();
+        let mut finished_since_tuning = 0;
+        loop {
+            // The queue's head waits for its prefetch rather than downloading twice
+            while in_flight.len() < self.window_width() && prefetch.is_none() {
+                let Some(item) = queue.next() else {
+                    break;
+                };
+                let id = item.to_string();
+                console_info!("[SyncOrchestrator] Processing item: {}", id);
+
+                // Invoke progress callback at the START of processing each new item
+                report_progress(
+                    &progress,
+                    ProgressUpdate {
+                        item_id: Some(id.clone()),
+                        phase: ProgressPhase::Starting,
+                        bytes_processed: 0,
+                        total_bytes_estimate: 1000000, // rough estimate
+                        total_known: false,
+                        event: ProgressEvent::Started,
+                    },
+                );
 
-            // Invoke progress callback at the START of processing each new item
-            if let Some(ref mut callback) = progress_callback {
-                console_debug!(
-                    "[SyncOrchestrator] Invoking progress callback for starting item: {}",
-                    id
+                let data = prefetched
+                    .take()
+                    .filter(|prefetch| prefetch.id == id)
+                    .map(|prefetch| prefetch.data);
+                let mut callback = progress
+                    .borrow()
+                    .is_some()
+                    .then(|| forward_progress(&progress));
+                let storage = Arc::clone(&storage);
+                in_flight.push(
+                    async move {
+                        let outcome = self
+                            .process_with_retries(
+                                source,
+                                target,
+                                storage,
+                                &item,
+                                data,
+                                &mut callback,
+                            )
+                            .await;
+                        (id, outcome)
+                    }
+                    .boxed_local(),
                 );
-                callback(ProgressUpdate {
-                    item_id: Some(id.clone()),
-                    phase: ProgressPhase::Starting,
-                    bytes_processed: 0,
-                    total_bytes_estimate: 1000000, // rough estimate
-                    event: ProgressEvent::Started,
-                });
             }
 
-            let mut retry_count = 0;
-            let mut last_error = String::new();
-            let mut success = false;
-
-            while retry_count <= MAX_RETRY_ATTEMPTS && !success {
-                match self
-                    .process_single_item(
-                        &source,
-                        &target,
-                        Arc::clone(&storage),
-                        &item,
-                        &mut progress_callback,
-                    )
-                    .await
-                {
-                    Ok(bytes_processed) => {
-                        total_bytes_processed += bytes_processed;
-                        successful_items += 1;
-                        success = true;
-
-                        // Invoke progress callback for successful item completion
-                        if let Some(ref mut callback) = progress_callback {
-                            console_debug!("[SyncOrchestrator] Invoking progress callback for completed item: {} ({} bytes)", id, bytes_processed);
-                            callback(ProgressUpdate {
-                                item_id: Some(id.clone()),
-                                phase: ProgressPhase::Completing,
-                                bytes_processed,
-                                total_bytes_estimate: bytes_processed,
-                                event: ProgressEvent::Completed,
-                            });
-                        }
+            if prefetch.is_none()
+                && prefetched.is_none()
+                && in_flight.len() >= self.window_width()
+                && self.prefetch_max_bytes > 0
+                && source.prefetches()
+            {
+                if let Some(next) = queue.peek().cloned() {
+                    let storage = Arc::clone(&storage);
+                    prefetch = Some(
+                        async move { self.prefetch_item(source, &storage, &next).await }
+                            .boxed_local(),
+                    );
+                }
+            }
 
-                        if retry_count > 0 {
-                            console_info!(
-                                "[SyncOrchestrator] Successfully processed item: {} ({} bytes) after {} retries",
-                                id, bytes_processed, retry_count
-                            );
-                        } else {
-                            console_info!(
-                                "[SyncOrchestrator] Successfully processed item: {} ({} bytes)",
-                                id,
-                                bytes_processed
-                            );
-                        }
-                    }
-                    Err(e) => {
-                        last_error = e.to_string();
-                        retry_count += 1;
-
-                        if retry_count <= MAX_RETRY_ATTEMPTS {
-                            console_debug!(
-                                "[SyncOrchestrator] Failed to process item {} (attempt {}): {}. Analyzing error...",
-                                id, retry_count, last_error
-                            );
-
-                            // Parse rate limit error for intelligent retry
-                            let delay_ms = if last_error.starts_with("RATE_LIMIT:429:") {
-                                // Extract retry-after from error message
-                                // Format: "RATE_LIMIT:429:{retry_after}:..."
-                                let parts: Vec<&str> = last_error.split(':').collect();
-                                let retry_after_secs = parts
-                                    .get(2)
-                                    .and_then(|s| s.parse::<u64>().ok())
-                                    .unwrap_or(60);
-
-                                // Add jitter to prevent thundering herd
-                                let jitter = (retry_count as u64) * 1000; // 1-3 seconds jitter
-                                let delay = (retry_after_secs * 1000) + jitter;
-
-                                console_info!(
-                                    "[SyncOrchestrator] Rate limit detected for {}, waiting {}s as instructed by server (plus {}ms jitter)",
-                                    id, retry_after_secs, jitter
-                                );
-                                delay
-                            } else if last_error.contains("Gateway timeout (504)") {
-                                // Actual gateway timeout - use exponential backoff
-                                let base_delay = 2000; // 2 seconds base
-                                let exponential_delay = base_delay * (2_u64.pow(retry_count - 1));
-                                console_info!(
-                                    "[SyncOrchestrator] Gateway timeout for {}, using exponential backoff: {}ms",
-                                    id, exponential_delay
-                                );
-                                exponential_delay
-                            } else {
-                                // Other errors - progressive delay
-                                1000 * retry_count as u64
-                            };
-
-                            #[cfg(target_arch = "wasm32")]
-                            gloo_timers::future::TimeoutFuture::new(delay_ms as u32).await;
-                            #[cfg(not(target_arch = "wasm32"))]
-                            tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
-                        } else {
-                            console_error!(
-                                "[SyncOrchestrator] Failed to process item {} after {} attempts: {}",
-                                id, retry_count, last_error
-                            );
+            let finished = match prefetch.as_mut() {
+                Some(pending) if !in_flight.is_empty() => {
+                    match select(in_flight.next(), pending).await {
+                        Either::Left((finished, _)) => finished,
+                        Either::Right((data, _)) => {
+                            prefetched = data;
+                            prefetch = None;
+                            continue;
                         }
                     }
                 }
+                Some(pending) => {
+                    prefetched = pending.await;
+                    prefetch = None;
+                    continue;
+                }
+                None => in_flight.next().await,
+            };
+            let Some((id, outcome)) = finished else {
+                break;
+            };
+
+            // Tune once a window's worth of items has finished
+            finished_since_tuning += 1;
+            if finished_since_tuning >= self.window_width() {
+                self.tune_after_window(counts_before);
+                counts_before = self.target_error_counts();
+                finished_since_tuning = 0;
             }
 
-            if !success {
-                failed_items.push(SyncFailure {
+            match outcome.result {
+                Ok(bytes_processed) => {
+                    total_bytes_processed += bytes_processed;
+                    successful_items += 1;
+
+                    // Invoke progress callback for successful item completion
+                    console_debug!("[SyncOrchestrator] Invoking progress callback for completed item: {} ({} bytes)", id, bytes_processed);
+                    report_progress(
+                        &progress,
+                        ProgressUpdate {
+                            item_id: Some(id.clone()),
+                            phase: ProgressPhase::Completing,
+                            bytes_processed,
+                            total_bytes_estimate: bytes_processed,
+                            total_known: true,
+                            event: ProgressEvent::Completed,
+                        },
+                    );
+
+                    if outcome.retry_count > 0 {
+                        console_info!(
+                            "[SyncOrchestrator] Successfully processed item: {} ({} bytes) after {} retries",
+                            id, bytes_processed, outcome.retry_count
+                        );
+                    } else {
+                        console_info!(
+                            "[SyncOrchestrator] Successfully processed item: {} ({} bytes)",
+                            id,
+                            bytes_processed
+                        );
+                    }
+                }
+                Err(last_error) => failed_items.push(SyncFailure {
                     item_id: id,
                     error: format!(
                         "Failed after {} retries: {}",
-                        MAX_RETRY_ATTEMPTS, last_error
+                        outcome.retry_count.saturating_sub(1),
+                        last_error
                     ),
-                });
+                }),
             }
         }
+        if finished_since_tuning > 0 {
+            self.tune_after_window(counts_before);
+        }
 
         console_info!(
             "[SyncOrchestrator] Sync completed: {}/{} successful, {} failed, {} bytes total",
@@ -248,6 +499,7 @@ impl SyncOrchestrator {
             successful_items,
             failed_items,
             total_bytes_processed,
+            checksums: self.checksums.borrow().clone(),
         })
     }
 
@@ -268,6 +520,101 @@ impl SyncOrchestrator {
             .await
     }
 
+    /// Process one item, retrying failures under the phase budgets until it
+    /// succeeds or the budget for the failing phase is spent
+    async fn process_with_retries<S, T, B, P>(
+        &self,
+        source: &S,
+        target: &T,
+        storage: Arc<Mutex<B>>,
+        item: &S::Item,
+        mut prefetched: Option<Vec<u8>>,
+        progress_callback: &mut Option<P>,
+    ) -> ItemAttempts
+    where
+        S: DataSource,
+        T: DataTarget,
+        B: StorageBackend,
+        S::Item: Clone + ToString,
+        P: FnMut(ProgressUpdate) + 'static,
+    {
+        let id = item.to_string();
+        let mut retry_count: u32 = 0;
+        let mut download_retries = 0;
+        let mut upload_retries = 0;
+
+        loop {
+            let error = match self
+                .process_single_item(
+                    source,
+                    target,
+                    Arc::clone(&storage),
+                    item,
+                    prefetched.take(),
+                    progress_callback,
+                )
+                .await
+            {
+                Ok(bytes_processed) => {
+                    return ItemAttempts {
+                        result: Ok(bytes_processed),
+                        retry_count,
+                    }
+                }
+                Err(e) => e.to_string(),
+            };
+            retry_count += 1;
+
+            let phase = failed_phase(&error, self.download_phase, self.upload_phase);
+            let phase_retries = if phase == self.download_phase {
+                &mut download_retries
+            } else {
+                &mut upload_retries
+            };
+            *phase_retries += 1;
+            // A rate limit says how long to wait; that replaces the phase's backoff
+            let suggested_ms = if error.starts_with("RATE_LIMIT:429:") {
+                // Format: "RATE_LIMIT:429:{retry_after}:..."
+                let retry_after_secs = error
+                    .split(':')
+                    .nth(2)
+                    .and_then(|s| s.parse::<u64>().ok())
+                    .unwrap_or(60);
+                // Add jitter to prevent thundering herd
+                let jitter = (retry_count as u64) * 1000;
+                console_info!(
+                    "[SyncOrchestrator] Rate limit detected for {}, waiting {}s as instructed by server (plus {}ms jitter)",
+                    id, retry_after_secs, jitter
+                );
+                Some(retry_after_secs * 1000 + jitter)
+            } else {
+                None
+            };
+
+            if let Some(delay_ms) = claim_retry_delay(
+                &self.retry_config(phase, &error),
+                phase,
+                *phase_retries,
+                suggested_ms,
+            ) {
+                console_debug!(
+                    "[SyncOrchestrator] Failed to process item {} (attempt {}): {}. Retrying under the {} budget in {}ms",
+                    id, retry_count, error, phase.label(), delay_ms
+                );
+                sleep_ms(delay_ms).await;
+            } else {
+                console_error!(
+                    "[SyncOrchestrator] Failed to process item {} after {} attempts ({} retry budget spent): {}",
+                    id, retry_count, phase.label(), error
+                );
+                return ItemAttempts {
+                    result: Err(error),
+                    retry_count,
+                };
+            }
+        }
+    }
+
     /// Process a single item using the WASM channel-tee pattern
     async fn process_single_item<S, T, B, P>(
         &self,
@@ -275,6 +622,7 @@ impl SyncOrchestrator {
         target: &T,
         storage: Arc<Mutex<B>>,
         item: &S::Item,
+        prefetched: Option<Vec<u8>>,
         progress_callback: &mut Option<P>,
     ) -> Result<u64, Box<dyn Error>>
     where
@@ -285,7 +633,36 @@ impl SyncOrchestrator {
         P: FnMut(ProgressUpdate) + 'static,
     {
         let id = item.to_string();
+
+        let cached = match storage.lock().await.cached_data(&id).await {
+            Ok(cached) => cached,
+            Err(e) => {
+                console_warn!("[SyncOrchestrator] Cache lookup failed for {}: {}", id, e);
+                None
+            }
+        };
+        if let Some(data) = cached {
+            match self.verify_stored(&id, &data) {
+                Ok(()) => {
+                    return self
+                        .upload_cached_item(target, &id, data, progress_callback)
+                        .await;
+                }
+                Err(e) => {
+                    console_warn!("[SyncOrchestrator] Ignoring cached copy: {}", e);
+                }
+            }
+        }
+
+        if let Some(data) = prefetched {
+            return self
+                .upload_prefetched_item(target, storage, &id, data, progress_callback)
+                .await;
+        }
+
+        let download_started = current_time_ms();
         let stream = source.fetch_stream(item).await?;
+        let content_length = stream.content_length();
 
         // Create the tee for storage and upload (2 outputs)
         let (tee, mut receivers) = ChannelTee::<{ CHANNEL_CAPACITY }>::new(2);
@@ -308,6 +685,7 @@ impl SyncOrchestrator {
 
         // Task 1: Read stream and tee to channels with progress reporting
         let tee_task = async move {
+            let mut checksum = self.stream_checksums.then(StreamChecksum::new);
             let mut offset = 0;
             let mut total_bytes = 0u64;
             let mut last_progress_report = 0u64;
@@ -360,6 +738,9 @@ impl SyncOrchestrator {
 
                 chunk_count += 1;
                 let chunk_size = chunk.len();
+                if let Some(checksum) = checksum.as_mut() {
+                    checksum.update(&chunk);
+                }
                 console_debug!(
                     "[SyncOrchestrator] Processing chunk {} for {} ({} bytes)",
                     chunk_count,
@@ -398,7 +779,9 @@ impl SyncOrchestrator {
                                 item_id: Some(tee_id.clone()),
                                 phase: ProgressPhase::Downloading,
                                 bytes_processed: total_bytes,
-                                total_bytes_estimate: total_bytes + 1000000, // rough estimate
+                                total_bytes_estimate: content_length
+                                    .unwrap_or(total_bytes + 1000000), // rough estimate without Content-Length
+                                total_known: content_length.is_some(),
                                 event: ProgressEvent::Progress,
                             });
                         }
@@ -429,6 +812,15 @@ impl SyncOrchestrator {
                 total_bytes,
                 chunk_count
             );
+            record_pipeline_operation(
+                PipelineOperation::NetworkDownload,
+                download_started,
+                total_bytes,
+            );
+            if let Some(digest) = checksum.and_then(StreamChecksum::finish) {
+                console_info!("[SyncOrchestrator] sha-256 of {}: {}", tee_id, digest);
+                self.checksums.borrow_mut().insert(tee_id.clone(), digest);
+            }
 
             // Final progress callback to ensure download phase completion is reported
             let mut cb_guard = progress_cb_tee.lock().await;
@@ -439,6 +831,7 @@ impl SyncOrchestrator {
                     phase: ProgressPhase::Downloading,
                     bytes_processed: total_bytes,
                     total_bytes_estimate: total_bytes,
+                    total_known: true,
                     event: ProgressEvent::Completed,
                 });
             }
@@ -539,6 +932,10 @@ impl SyncOrchestrator {
 
             match storage_guard.read_data(&upload_id).await {
                 Ok(data) => {
+                    self.verify_stored(&upload_id, &data).map_err(|e| {
+                        console_error!("[SyncOrchestrator] {}", e);
+                        e
+                    })?;
                     let data_size = data.len();
                     console_info!(
                         "[SyncOrchestrator] Read {} bytes from storage for {}",
@@ -555,6 +952,7 @@ impl SyncOrchestrator {
                                     phase: ProgressPhase::Uploading,
                                     bytes_processed: 0,
                                     total_bytes_estimate: data_size as u64,
+                                    total_known: true,
                                     event: ProgressEvent::Started,
                                 });
                             }
@@ -565,6 +963,7 @@ impl SyncOrchestrator {
                             data_size,
                             upload_id
                         );
+                        let upload_started = current_time_ms();
                         target
                             .upload_data(upload_id.clone(), data, "application/octet-stream")
                             .await
@@ -573,6 +972,11 @@ impl SyncOrchestrator {
                                 console_error!("[SyncOrchestrator] {}", error_msg);
                                 error_msg
                             })?;
+                        record_pipeline_operation(
+                            PipelineOperation::NetworkUpload,
+                            upload_started,
+                            data_size as u64,
+                        );
                         console_info!(
                             "[SyncOrchestrator] Successfully uploaded {} bytes for {}",
                             data_size,
@@ -587,6 +991,7 @@ impl SyncOrchestrator {
                                     phase: ProgressPhase::Uploading,
                                     bytes_processed: data_size as u64,
                                     total_bytes_estimate: data_size as u64,
+                                    total_known: true,
                                     event: ProgressEvent::Completed,
                                 });
                             }
@@ -657,6 +1062,182 @@ impl SyncOrchestrator {
     }
 }
 
+impl SyncOrchestrator {
+    /// Download `item` into memory ahead of its turn
+    ///
+    /// Skips items the storage backend already holds, which are uploaded from
+    /// there. Gives up once the item outgrows the prefetch limit or fails to
+    /// download; the item is then fetched the regular way when its turn comes.
+    async fn prefetch_item<S, B>(
+        &self,
+        source: &S,
+        storage: &Arc<Mutex<B>>,
+        item: &S::Item,
+    ) -> Option<PrefetchedItem>
+    where
+        S: DataSource,
+        B: StorageBackend,
+        S::Item: ToString,
+    {
+        let id = item.to_string();
+        if let Ok(Some(_)) = storage.lock().await.cached_data(&id).await {
+            console_debug!("[SyncOrchestrator] Not prefetching {}: already cached", id);
+            return None;
+        }
+        let limit = self.prefetch_max_bytes;
+        if !prefetch_fits(None, backend_usage().memory_bytes, limit) {
+            console_debug!(
+                "[SyncOrchestrator] Not prefetching {}: memory buffers are over the limit",
+                id
+            );
+            return None;
+        }
+
+        let download_started = current_time_ms();
+        let mut stream = match source.fetch_stream(item).await {
+            Ok(stream) => stream,
+            Err(e) => {
+                console_debug!("[SyncOrchestrator] Prefetch of {} failed: {}", id, e);
+                return None;
+            }
+        };
+        if !prefetch_fits(stream.content_length(), 0, limit) {
+            console_debug!(
+                "[SyncOrchestrator] Not prefetching {}: larger than {} bytes",
+                id,
+                limit
+            );
+            return None;
+        }
+
+        let mut checksum = self.stream_checksums.then(StreamChecksum::new);
+        let mut data = Vec::with_capacity(stream.content_length().unwrap_or(0) as usize);
+        while let Some(chunk) = stream.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    console_debug!("[SyncOrchestrator] Prefetch of {} failed: {}", id, e);
+                    return None;
+                }
+            };
+            if !prefetch_fits(Some((data.len() + chunk.len()) as u64), 0, limit) {
+                console_debug!(
+                    "[SyncOrchestrator] Abandoning prefetch of {}: larger than {} bytes",
+                    id,
+                    limit
+                );
+                return None;
+            }
+            if let Some(checksum) = checksum.as_mut() {
+                checksum.update(&chunk);
+            }
+            data.extend_from_slice(&chunk);
+        }
+
+        record_pipeline_operation(
+            PipelineOperation::NetworkDownload,
+            download_started,
+            data.len() as u64,
+        );
+        if let Some(digest) = checksum.and_then(StreamChecksum::finish) {
+            self.checksums.borrow_mut().insert(id.clone(), digest);
+        }
+        console_info!(
+            "[SyncOrchestrator] Prefetched {} ({} bytes) while the previous item uploaded",
+            id,
+            data.len()
+        );
+        Some(PrefetchedItem { id, data })
+    }
+
+    /// Store a prefetched item like a streamed one, then upload it from memory
+    async fn upload_prefetched_item<T, B, P>(
+        &self,
+        target: &T,
+        storage: Arc<Mutex<B>>,
+        id: &str,
+        data: Vec<u8>,
+        progress_callback: &mut Option<P>,
+    ) -> Result<u64, Box<dyn Error>>
+    where
+        T: DataTarget,
+        B: StorageBackend,
+        P: FnMut(ProgressUpdate) + 'static,
+    {
+        let chunk = DataChunk {
+            id: id.to_string(),
+            data: data.into(),
+            offset: 0,
+            total_size: None,
+        };
+        {
+            let mut storage_guard = storage.lock().await;
+            storage_guard
+                .write_chunk(&chunk)
+                .await
+                .map_err(|e| format!("Storage write error for {} chunk 1: {}", id, e))?;
+            storage_guard
+                .finalize(id)
+                .await
+                .map_err(|e| format!("Storage finalize error for {}: {}", id, e))?;
+        }
+
+        self.upload_cached_item(target, id, chunk.data.into(), progress_callback)
+            .await
+    }
+
+    /// Upload bytes already held by the storage backend without touching the source
+    async fn upload_cached_item<T, P>(
+        &self,
+        target: &T,
+        id: &str,
+        data: Vec<u8>,
+        progress_callback: &mut Option<P>,
+    ) -> Result<u64, Box<dyn Error>>
+    where
+        T: DataTarget,
+        P: FnMut(ProgressUpdate) + 'static,
+    {
+        let data_size = data.len() as u64;
+        console_info!(
+            "[SyncOrchestrator] Uploading {} bytes for {} from local cache",
+            data_size,
+            id
+        );
+
+        if let Some(ref mut callback) = progress_callback {
+            callback(ProgressUpdate {
+                item_id: Some(id.to_string()),
+                phase: ProgressPhase::Uploading,
+                bytes_processed: 0,
+                total_bytes_estimate: data_size,
+                total_known: true,
+                event: ProgressEvent::Started,
+            });
+        }
+
+        let upload_started = current_time_ms();
+        target
+            .upload_data(id.to_string(), data, "application/octet-stream")
+            .await
+            .map_err(|e| format!("Upload error for {}: {}", id, e))?;
+        record_pipeline_operation(PipelineOperation::NetworkUpload, upload_started, data_size);
+
+        if let Some(ref mut callback) = progress_callback {
+            callback(ProgressUpdate {
+                item_id: Some(id.to_string()),
+                phase: ProgressPhase::Uploading,
+                bytes_processed: data_size,
+                total_bytes_estimate: data_size,
+                total_known: true,
+                event: ProgressEvent::Completed,
+            });
+        }
+
+        Ok(data_size)
+    }
+}
+
 impl Default for SyncOrchestrator {
     fn default() -> Self {
         Self::new()
@@ -670,6 +1251,8 @@ pub struct SyncResult {
     pub successful_items: u32,
     pub failed_items: Vec<SyncFailure>,
     pub total_bytes_processed: u64,
+    /// sha-256 of each downloaded item, when stream checksums are on
+    pub checksums: HashMap<String, String>,
 }
 
 /// Information about a failed sync item
@@ -678,3 +1261,244 @@ pub struct SyncFailure {
     pub item_id: String,
     pub error: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::cell::Cell;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    /// Source that counts requests and never answers them
+    struct CountingSource {
+        fetches: Rc<Cell<u32>>,
+        prefetches: bool,
+    }
+
+    #[async_trait(?Send)]
+    impl DataSource for CountingSource {
+        type Item = String;
+
+        async fn list_items(&self) -> Result<Vec<String>, Box<dyn Error>> {
+            Ok(vec![])
+        }
+
+        async fn fetch_stream(&self, item: &String) -> Result<BrowserStream, Box<dyn Error>> {
+            self.fetches.set(self.fetches.get() + 1);
+            Err(format!("source is offline, cannot fetch {}", item).into())
+        }
+
+        fn prefetches(&self) -> bool {
+            self.prefetches
+        }
+    }
+
+    /// Storage holding a fixed set of cached items
+    struct MemoryCache {
+        cached: HashMap<String, Vec<u8>>,
+    }
+
+    impl MemoryCache {
+        fn holding(ids: &[&str]) -> Self {
+            Self {
+                cached: ids
+                    .iter()
+                    .map(|id| (id.to_string(), id.as_bytes().to_vec()))
+                    .collect(),
+            }
+        }
+    }
+
+    #[async_trait(?Send)]
+    impl StorageBackend for MemoryCache {
+        async fn write_chunk(&mut self, _chunk: &DataChunk) -> Result<(), Box<dyn Error>> {
+            Ok(())
+        }
+
+        async fn finalize(&mut self, _id: &str) -> Result<(), Box<dyn Error>> {
+            Ok(())
+        }
+
+        async fn read_data(&self, id: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+            self.cached
+                .get(id)
+                .cloned()
+                .ok_or_else(|| format!("{} is not stored", id).into())
+        }
+
+        async fn cached_data(&self, id: &str) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+            Ok(self.cached.get(id).cloned())
+        }
+    }
+
+    /// Returns to the executor once before finishing
+    struct YieldOnce(bool);
+
+    impl Future for YieldOnce {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                return Poll::Ready(());
+            }
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    /// Records uploads in the order they finish
+    #[derive(Default)]
+    struct RecordingTarget {
+        uploads: Rc<RefCell<Vec<String>>>,
+        /// Item whose upload takes many times longer than the others
+        slow: Option<String>,
+    }
+
+    #[async_trait(?Send)]
+    impl DataTarget for RecordingTarget {
+        async fn upload_data(
+            &self,
+            id: String,
+            _data: Vec<u8>,
+            _content_type: &str,
+        ) -> Result<(), Box<dyn Error>> {
+            let polls = if self.slow.as_ref() == Some(&id) {
+                50
+            } else {
+                1
+            };
+            for _ in 0..polls {
+                YieldOnce(false).await;
+            }
+            self.uploads.borrow_mut().push(id);
+            Ok(())
+        }
+
+        async fn list_missing(&self) -> Result<Vec<String>, Box<dyn Error>> {
+            Ok(vec![])
+        }
+    }
+
+    fn sync_without_retries(
+        source: CountingSource,
+        target: RecordingTarget,
+        storage: MemoryCache,
+        items: &[&str],
+    ) -> SyncResult {
+        let orchestrator = SyncOrchestrator::with_retry(
+            RetryConfig::conservative_defaults().with_blob_retries(0),
+            RetryPhase::BlobDownload,
+            RetryPhase::BlobUpload,
+        )
+        .with_prefetch(1 << 20);
+        futures::executor::block_on(
+            orchestrator.sync_items_with_tee::<_, _, _, fn(ProgressUpdate)>(
+                source,
+                target,
+                storage,
+                items.iter().map(|id| id.to_string()).collect(),
+                None,
+            ),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_cached_items_are_not_prefetched() {
+        let fetches = Rc::new(Cell::new(0));
+        let target = RecordingTarget::default();
+        let uploads = Rc::clone(&target.uploads);
+        let result = sync_without_retries(
+            CountingSource {
+                fetches: Rc::clone(&fetches),
+                prefetches: true,
+            },
+            target,
+            MemoryCache::holding(&["a", "b", "c"]),
+            &["a", "b", "c"],
+        );
+
+        assert_eq!(result.successful_items, 3);
+        assert_eq!(fetches.get(), 0);
+        assert_eq!(*uploads.borrow(), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_sources_without_prefetch_are_only_asked_in_turn() {
+        let fetches = Rc::new(Cell::new(0));
+        let result = sync_without_retries(
+            CountingSource {
+                fetches: Rc::clone(&fetches),
+                prefetches: false,
+            },
+            RecordingTarget::default(),
+            MemoryCache::holding(&["a"]),
+            &["a", "b", "c"],
+        );
+
+        assert_eq!(result.successful_items, 1);
+        assert_eq!(result.failed_items.len(), 2);
+        // One regular attempt each for b and c, none ahead of time
+        assert_eq!(fetches.get(), 2);
+    }
+
+    #[test]
+    fn test_window_slides_past_a_slow_item_and_every_item_reports() {
+        let target = RecordingTarget {
+            slow: Some("big".to_string()),
+            ..Default::default()
+        };
+        let uploads = Rc::clone(&target.uploads);
+        let upload_starts = Rc::new(RefCell::new(Vec::new()));
+        let progress = {
+            let upload_starts = Rc::clone(&upload_starts);
+            move |update: ProgressUpdate| {
+                if update.phase == ProgressPhase::Uploading
+                    && update.event == ProgressEvent::Started
+                {
+                    upload_starts.borrow_mut().push(update.item_id.unwrap());
+                }
+            }
+        };
+        let orchestrator = SyncOrchestrator::with_retry(
+            RetryConfig::conservative_defaults(),
+            RetryPhase::BlobDownload,
+            RetryPhase::BlobUpload,
+        )
+        .with_hosts("https://source.window.test", "https://target.window.test")
+        .with_upload_tuning(2, 2, 0.5);
+
+        let items = ["big", "a", "b", "c"];
+        let result = futures::executor::block_on(orchestrator.sync_items_with_tee(
+            CountingSource {
+                fetches: Rc::new(Cell::new(0)),
+                prefetches: true,
+            },
+            target,
+            MemoryCache::holding(&items),
+            items.iter().map(|id| id.to_string()).collect(),
+            Some(progress),
+        ))
+        .unwrap();
+
+        assert_eq!(result.successful_items, 4);
+        // The other slot kept moving while the slow upload ran
+        assert_eq!(*uploads.borrow(), ["a", "b", "c", "big"]);
+        let mut started = upload_starts.borrow().clone();
+        started.sort();
+        assert_eq!(started, ["a", "b", "big", "c"]);
+    }
+
+    #[test]
+    fn test_prefetch_is_bounded_by_memory() {
+        const LIMIT: u64 = 1024;
+        assert!(prefetch_fits(None, 0, LIMIT));
+        assert!(prefetch_fits(Some(LIMIT), 0, LIMIT));
+        assert!(!prefetch_fits(Some(LIMIT + 1), 0, LIMIT));
+        assert!(!prefetch_fits(Some(10), LIMIT, LIMIT));
+        assert!(!prefetch_fits(Some(10), 0, 0));
+    }
+}
@@ -0,0 +1,258 @@
+//! Live storage usage tracking for the blob pipeline
+//!
+//! Combines the browser's origin quota estimate with per-backend byte counters so the UI
+//! can warn before the quota is exhausted, and so strategies can react to storage pressure.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::services::config::{try_get_storage_estimate, StorageEstimate};
+
+/// Usage ratio at which the meter starts warning
+pub const STORAGE_WARNING_THRESHOLD: f64 = 0.70;
+/// Usage ratio at which storage pressure is considered high
+pub const STORAGE_HIGH_THRESHOLD: f64 = 0.85;
+/// Usage ratio at which writes are likely to fail soon
+pub const STORAGE_CRITICAL_THRESHOLD: f64 = 0.95;
+
+static OPFS_BYTES: AtomicU64 = AtomicU64::new(0);
+static INDEXEDDB_BYTES: AtomicU64 = AtomicU64::new(0);
+static MEMORY_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Storage backends tracked by the usage counters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageBackendKind {
+    Opfs,
+    IndexedDb,
+    /// In-memory buffers awaiting an IndexedDB flush
+    Memory,
+}
+
+impl StorageBackendKind {
+    fn counter(self) -> &'static AtomicU64 {
+        match self {
+            StorageBackendKind::Opfs => &OPFS_BYTES,
+            StorageBackendKind::IndexedDb => &INDEXEDDB_BYTES,
+            StorageBackendKind::Memory => &MEMORY_BYTES,
+        }
+    }
+}
+
+/// Record bytes written to a backend during this session
+pub fn record_backend_write(kind: StorageBackendKind, bytes: u64) {
+    kind.counter().fetch_add(bytes, Ordering::Relaxed);
+}
+
+/// Record bytes released from a backend (e.g. a memory buffer flushed to IndexedDB)
+pub fn record_backend_release(kind: StorageBackendKind, bytes: u64) {
+    let _ = kind
+        .counter()
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current| {
+            Some(current.saturating_sub(bytes))
+        });
+}
+
+/// Snapshot of the per-backend counters
+pub fn backend_usage() -> BackendUsage {
+    BackendUsage {
+        opfs_bytes: OPFS_BYTES.load(Ordering::Relaxed),
+        indexeddb_bytes: INDEXEDDB_BYTES.load(Ordering::Relaxed),
+        memory_bytes: MEMORY_BYTES.load(Ordering::Relaxed),
+    }
+}
+
+/// Bytes written by this session to each storage backend
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct BackendUsage {
+    pub opfs_bytes: u64,
+    pub indexeddb_bytes: u64,
+    pub memory_bytes: u64,
+}
+
+/// Severity of origin storage usage
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum StorageUsageLevel {
+    #[default]
+    Normal,
+    /// At least 70% of the quota is used
+    Warning,
+    /// At least 85% of the quota is used
+    High,
+    /// At least 95% of the quota is used
+    Critical,
+}
+
+impl StorageUsageLevel {
+    pub fn from_ratio(ratio: f64) -> Self {
+        if ratio >= STORAGE_CRITICAL_THRESHOLD {
+            StorageUsageLevel::Critical
+        } else if ratio >= STORAGE_HIGH_THRESHOLD {
+            StorageUsageLevel::High
+        } else if ratio >= STORAGE_WARNING_THRESHOLD {
+            StorageUsageLevel::Warning
+        } else {
+            StorageUsageLevel::Normal
+        }
+    }
+
+    /// CSS modifier class for the usage gauge
+    pub fn css_class(&self) -> &'static str {
+        match self {
+            StorageUsageLevel::Normal => "normal",
+            StorageUsageLevel::Warning => "warning",
+            StorageUsageLevel::High => "high",
+            StorageUsageLevel::Critical => "critical",
+        }
+    }
+
+    /// User-facing warning, if any
+    pub fn message(&self) -> Option<&'static str> {
+        match self {
+            StorageUsageLevel::Normal => None,
+            StorageUsageLevel::Warning => Some("Browser storage is over 70% full"),
+            StorageUsageLevel::High => {
+                Some("Browser storage is over 85% full - consider freeing space in this browser")
+            }
+            StorageUsageLevel::Critical => {
+                Some("Browser storage is almost full - blob transfers may fail")
+            }
+        }
+    }
+}
+
+/// Origin quota usage together with the per-backend breakdown
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StorageUsage {
+    pub origin_usage: u64,
+    pub origin_quota: u64,
+    pub backends: BackendUsage,
+}
+
+impl StorageUsage {
+    pub fn from_estimate(estimate: &StorageEstimate, backends: BackendUsage) -> Self {
+        Self {
+            origin_usage: estimate.usage,
+            origin_quota: estimate.quota,
+            backends,
+        }
+    }
+
+    /// Fraction of the origin quota in use (0.0 when the quota is unknown)
+    pub fn usage_ratio(&self) -> f64 {
+        if self.origin_quota > 0 {
+            self.origin_usage as f64 / self.origin_quota as f64
+        } else {
+            0.0
+        }
+    }
+
+    pub fn level(&self) -> StorageUsageLevel {
+        StorageUsageLevel::from_ratio(self.usage_ratio())
+    }
+}
+
+/// Read the current origin estimate and backend counters
+/// Returns None if the StorageManager API is unavailable
+pub async fn sample_storage_usage() -> Option<StorageUsage> {
+    let estimate = try_get_storage_estimate().await?;
+    Some(StorageUsage::from_estimate(&estimate, backend_usage()))
+}
+
+/// Callback invoked when the usage level changes
+pub type StorageUsageHook = Box<dyn FnMut(&StorageUsage, StorageUsageLevel)>;
+
+/// Tracks usage samples and notifies hooks when the usage level changes
+///
+/// Hooks are the extension point for strategy adjustments such as switching backends
+/// or pausing transfers under storage pressure.
+#[derive(Default)]
+pub struct StorageUsageMonitor {
+    level: StorageUsageLevel,
+    hooks: Vec<StorageUsageHook>,
+}
+
+impl StorageUsageMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a hook called with the sample and the new level on every level change
+    pub fn on_level_change(
+        mut self,
+        hook: impl FnMut(&StorageUsage, StorageUsageLevel) + 'static,
+    ) -> Self {
+        self.hooks.push(Box::new(hook));
+        self
+    }
+
+    pub fn level(&self) -> StorageUsageLevel {
+        self.level
+    }
+
+    /// Feed a new sample; returns the new level if it changed
+    pub fn observe(&mut self, usage: &StorageUsage) -> Option<StorageUsageLevel> {
+        let level = usage.level();
+        if level == self.level {
+            return None;
+        }
+
+        self.level = level;
+        for hook in &mut self.hooks {
+            hook(usage, level);
+        }
+        Some(level)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn usage(used: u64) -> StorageUsage {
+        StorageUsage {
+            origin_usage: used,
+            origin_quota: 100,
+            backends: BackendUsage::default(),
+        }
+    }
+
+    #[test]
+    fn test_usage_levels() {
+        assert_eq!(usage(69).level(), StorageUsageLevel::Normal);
+        assert_eq!(usage(70).level(), StorageUsageLevel::Warning);
+        assert_eq!(usage(85).level(), StorageUsageLevel::High);
+        assert_eq!(usage(95).level(), StorageUsageLevel::Critical);
+        assert_eq!(StorageUsage::default().level(), StorageUsageLevel::Normal);
+    }
+
+    #[test]
+    fn test_monitor_fires_hooks_on_level_change() {
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let seen_hook = Rc::clone(&seen);
+        let mut monitor = StorageUsageMonitor::new()
+            .on_level_change(move |_, level| seen_hook.borrow_mut().push(level));
+
+        assert_eq!(monitor.observe(&usage(10)), None);
+        assert_eq!(
+            monitor.observe(&usage(72)),
+            Some(StorageUsageLevel::Warning)
+        );
+        assert_eq!(monitor.observe(&usage(75)), None);
+        assert_eq!(
+            monitor.observe(&usage(96)),
+            Some(StorageUsageLevel::Critical)
+        );
+        assert_eq!(monitor.observe(&usage(50)), Some(StorageUsageLevel::Normal));
+
+        assert_eq!(
+            *seen.borrow(),
+            vec![
+                StorageUsageLevel::Warning,
+                StorageUsageLevel::Critical,
+                StorageUsageLevel::Normal
+            ]
+        );
+    }
+}
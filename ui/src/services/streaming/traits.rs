@@ -8,8 +8,6 @@ use std::error::Error;
 use std::pin::Pin;
 use tokio::sync::mpsc;
 
-#[cfg(target_arch = "wasm32")]
-use gloo_timers::future::TimeoutFuture;
 use js_sys::{Reflect, Uint8Array};
 use std::task::{Context, Poll};
 use wasm_bindgen::prelude::*;
@@ -34,6 +32,8 @@ pub struct BrowserStream {
     reader: web_sys::ReadableStreamDefaultReader,
     /// Persistent future for the current read operation - reused across poll calls
     current_read: Option<Pin<Box<JsFuture>>>,
+    /// Body size from the `Content-Length` header, when the server sent one
+    content_length: Option<u64>,
 }
 
 impl BrowserStream {
@@ -48,15 +48,27 @@ impl BrowserStream {
         console_debug!("[BrowserStream] Body extracted successfully, creating reader");
 
         let reader = body.get_reader().unchecked_into();
+        let content_length = response
+            .headers()
+            .get("content-length")
+            .ok()
+            .flatten()
+            .and_then(|value| value.trim().parse().ok());
 
         console_info!("[BrowserStream] ReadableStreamDefaultReader initialized successfully");
 
         Ok(Self {
             reader,
             current_read: None,
+            content_length,
         })
     }
 
+    /// Expected body size in bytes, if known
+    pub fn content_length(&self) -> Option<u64> {
+        self.content_length
+    }
+
     /// Fallback method using arrayBuffer() instead of ReadableStream
     /// Use this if ReadableStream continues to hang
     pub async fn from_response_array_buffer(response: Response) -> Result<Vec<u8>, JsValue> {
@@ -166,6 +178,12 @@ pub trait DataSource {
 
     /// Fetch a stream of bytes for a specific item
     async fn fetch_stream(&self, item: &Self::Item) -> Result<BrowserStream, Box<dyn Error>>;
+
+    /// Whether items may be downloaded ahead of their turn; a source that
+    /// never reaches a server has nothing worth prefetching
+    fn prefetches(&self) -> bool {
+        true
+    }
 }
 
 /// Trait for target operations (uploading data) - WASM-only  
@@ -213,6 +231,11 @@ pub trait StorageBackend {
 
     /// Read back a stored item as bytes (for uploads)
     async fn read_data(&self, id: &str) -> Result<Vec<u8>, Box<dyn Error>>;
+
+    /// Verified bytes kept from an earlier sync, letting the item skip its download
+    async fn cached_data(&self, _id: &str) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        Ok(None)
+    }
 }
 
 /// Channel tee pattern - duplicates stream data to multiple channels (WASM-compatible)
@@ -262,13 +285,13 @@ impl<const CAPACITY: usize> ChannelTee<CAPACITY> {
                     // Wait briefly and try blocking send
                     #[cfg(target_arch = "wasm32")]
                     {
-                        TimeoutFuture::new(100).await;
+                        crate::utils::platform::sleep_ms(100).await;
 
                         // Add timeout detection for blocking send
-                        let send_start = js_sys::Date::now();
+                        let send_start = crate::utils::platform::now_ms();
                         match tx.send(chunk.clone()).await {
                             Ok(()) => {
-                                let send_duration = js_sys::Date::now() - send_start;
+                                let send_duration = crate::utils::platform::now_ms() - send_start;
                                 console_info!(
                                     "[ChannelTee] Recovered from backpressure on channel {} in {:.1}ms",
                                     i,
@@ -289,7 +312,7 @@ impl<const CAPACITY: usize> ChannelTee<CAPACITY> {
                         }
 
                         // Check if send took too long (possible stall)
-                        let total_duration = js_sys::Date::now() - send_start;
+                        let total_duration = crate::utils::platform::now_ms() - send_start;
                         if total_duration > 5000.0 {
                             // 5 seconds
                             console_warn!(
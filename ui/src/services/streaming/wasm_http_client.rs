@@ -1,36 +1,49 @@
-//! WASM HTTP client using browser fetch API
+//! HTTP client for streaming transfers: `fetch` streams plus the middleware transport
 
+use crate::services::client::http_trace::record_http_trace;
 use crate::services::streaming::traits::BrowserStream;
+use crate::services::transport::{
+    shared_transport, AuthMiddleware, HttpRequest, HttpResponse, HttpTransport, MiddlewareStack,
+    TokenSource,
+};
 use crate::{console_debug, console_error, console_info};
-use js_sys::Uint8Array;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::JsFuture;
-use web_sys::{window, Headers, Request, RequestInit, Response};
-
-/// WASM HTTP client for browser-based requests
-pub struct WasmHttpClient;
+use web_sys::{window, Request, RequestInit, Response};
+
+/// HTTP client for the streaming sources and targets
+///
+/// Streaming GETs use `fetch` directly so the body can be read as it arrives;
+/// everything else goes through the [`shared_transport`], whose per-host rate
+/// limit and circuit breaker the XRPC endpoints use too.
+pub struct WasmHttpClient {
+    transport: MiddlewareStack,
+}
 
 impl WasmHttpClient {
-    /// Create a new WASM HTTP client
+    /// Create a client for unauthenticated requests
     pub fn new() -> Self {
-        Self
+        Self::with_layers(None)
+    }
+
+    /// Create a client that sends a bearer token from `tokens` with every
+    /// request and refreshes it once on a 401
+    pub fn authenticated(tokens: impl TokenSource + 'static) -> Self {
+        Self::with_layers(Some(AuthMiddleware::new(tokens)))
     }
 
-    /// Helper method to add authorization header if token is provided
-    fn add_auth_header(headers: &Headers, auth_token: Option<&str>) -> Result<(), String> {
-        if let Some(token) = auth_token {
-            headers
-                .set("Authorization", &format!("Bearer {}", token))
-                .map_err(|e| format!("Failed to set Authorization header: {:?}", e))?;
-            console_debug!("[WasmHttpClient] Authorization header added");
+    fn with_layers(auth: Option<AuthMiddleware>) -> Self {
+        let mut transport = MiddlewareStack::new(Box::new(shared_transport()));
+        if let Some(auth) = auth {
+            transport = transport.with(auth);
         }
-        Ok(())
+        Self { transport }
     }
 
-    /// Handle HTTP error responses with unified error handling
-    fn handle_error_response(response: &Response) -> Result<(), String> {
-        let status = response.status();
-        let status_text = response.status_text();
+    /// Turn HTTP error statuses into the error strings callers match on
+    pub(crate) fn handle_error_response(response: &HttpResponse) -> Result<(), String> {
+        let status = response.status;
+        let status_text = &response.status_text;
 
         match status {
             200..=299 => Ok(()),
@@ -42,38 +55,13 @@ impl WasmHttpClient {
                 ))
             }
             429 => {
-                // Parse rate limit headers
-                let response_headers = response.headers();
-
-                let limit = response_headers
-                    .get("RateLimit-Limit")
-                    .ok()
-                    .flatten()
-                    .and_then(|v| v.parse::<i32>().ok());
-
-                let reset = response_headers
-                    .get("RateLimit-Reset")
-                    .ok()
-                    .flatten()
-                    .and_then(|v| v.parse::<u64>().ok());
-
-                let remaining = response_headers
-                    .get("RateLimit-Remaining")
-                    .ok()
-                    .flatten()
-                    .and_then(|v| v.parse::<i32>().ok());
-
-                // Calculate retry delay based on reset time
-                let retry_after = if let Some(reset_time) = reset {
-                    let now = (js_sys::Date::now() / 1000.0) as u64;
-                    if reset_time > now {
-                        reset_time - now
-                    } else {
-                        60 // Default to 1 minute if reset is in past
-                    }
-                } else {
-                    60 // Default fallback
-                };
+                let limit = response.rate_limit_limit();
+                let remaining = response.rate_limit_remaining();
+                // Default to 1 minute without a usable reset time
+                let retry_after = response
+                    .retry_after_secs()
+                    .filter(|secs| *secs > 0)
+                    .unwrap_or(60);
 
                 console_error!(
                     "[WasmHttpClient] Rate limited (429): limit={:?}, remaining={:?}, retry_after={}s",
@@ -123,13 +111,16 @@ impl WasmHttpClient {
         let promise = window.fetch_with_request(&request);
         let response = JsFuture::from(promise).await.map_err(|e| {
             console_error!("[WasmHttpClient] Fetch failed: {:?}", e);
-            format!("Fetch failed: {:?}", e)
+            let error_msg = format!("Fetch failed: {:?}", e);
+            record_http_trace("GET", url, None, Some(error_msg.clone()));
+            error_msg
         })?;
 
         let response: Response = response.dyn_into().map_err(|_| {
             console_error!("[WasmHttpClient] Failed to cast to Response");
             "Failed to cast to Response"
         })?;
+        record_http_trace("GET", url, Some(response.status()), None);
 
         console_debug!(
             "[WasmHttpClient] Response received: {} {}",
@@ -169,67 +160,23 @@ impl WasmHttpClient {
         url: &str,
         data: Vec<u8>,
         content_type: &str,
-    ) -> Result<Response, String> {
-        self.post_data_with_auth(url, data, content_type, None)
-            .await
-    }
-
-    /// Post data to a URL with optional authorization header
-    pub async fn post_data_with_auth(
-        &self,
-        url: &str,
-        data: Vec<u8>,
-        content_type: &str,
-        auth_token: Option<&str>,
-    ) -> Result<Response, String> {
+    ) -> Result<HttpResponse, String> {
         console_debug!(
             "[WasmHttpClient] POST request to: {} ({} bytes)",
             url,
             data.len()
         );
 
-        let window = window().ok_or("No window object")?;
-
-        let opts = RequestInit::new();
-        opts.set_method("POST");
-
-        // Convert data to Uint8Array
-        let uint8_array = Uint8Array::from(&data[..]);
-        let js_value: JsValue = uint8_array.into();
-        opts.set_body(&js_value);
-
-        // Set headers
-        let headers = Headers::new().map_err(|e| format!("Failed to create headers: {:?}", e))?;
-        headers
-            .set("Content-Type", content_type)
-            .map_err(|e| format!("Failed to set header: {:?}", e))?;
-
-        // For streaming uploads, let browser handle chunked encoding automatically
-        // Don't set Content-Length as we want chunked transfer for streaming
-        console_debug!("[WasmHttpClient] Using browser's automatic chunked encoding for upload");
-
-        // Add authorization header if provided
-        Self::add_auth_header(&headers, auth_token)?;
-        opts.set_headers(&headers);
-
-        let request = Request::new_with_str_and_init(url, &opts)
-            .map_err(|e| format!("Failed to create request: {:?}", e))?;
-
-        console_debug!("[WasmHttpClient] Sending POST request");
-        let promise = window.fetch_with_request(&request);
-        let response = JsFuture::from(promise).await.map_err(|e| {
-            console_error!("[WasmHttpClient] POST request failed: {:?}", e);
-            format!("Fetch failed: {:?}", e)
-        })?;
-
-        let response: Response = response
-            .dyn_into()
-            .map_err(|_| "Failed to cast to Response")?;
+        let response = self
+            .transport
+            .send(HttpRequest::post(url, data, content_type))
+            .await
+            .inspect_err(|e| console_error!("[WasmHttpClient] POST request failed: {}", e))?;
 
         console_debug!(
             "[WasmHttpClient] Response: {} {}",
-            response.status(),
-            response.status_text()
+            response.status,
+            response.status_text
         );
 
         Self::handle_error_response(&response)?;
@@ -238,57 +185,29 @@ impl WasmHttpClient {
         Ok(response)
     }
 
-    /// Get JSON data from a URL
-    pub async fn get_json<T: for<'de> serde::Deserialize<'de>>(
+    /// POST data and return the response whatever its status, for callers
+    /// that need the body of a rejected request
+    pub async fn post_data_unchecked(
         &self,
         url: &str,
-    ) -> Result<T, String> {
-        self.get_json_with_auth(url, None).await
+        data: Vec<u8>,
+        content_type: &str,
+    ) -> Result<HttpResponse, String> {
+        self.transport
+            .send(HttpRequest::post(url, data, content_type))
+            .await
+            .inspect_err(|e| console_error!("[WasmHttpClient] POST request failed: {}", e))
     }
 
-    /// Get JSON data from a URL with optional authorization header
-    pub async fn get_json_with_auth<T: for<'de> serde::Deserialize<'de>>(
+    /// Get JSON data from a URL
+    pub async fn get_json<T: for<'de> serde::Deserialize<'de>>(
         &self,
         url: &str,
-        auth_token: Option<&str>,
     ) -> Result<T, String> {
-        let window = window().ok_or("No window object")?;
-
-        let opts = RequestInit::new();
-        opts.set_method("GET");
-
-        let headers = Headers::new().map_err(|e| format!("Failed to create headers: {:?}", e))?;
-        headers
-            .set("Accept", "application/json")
-            .map_err(|e| format!("Failed to set Accept header: {:?}", e))?;
-
-        // Add authorization header if provided
-        Self::add_auth_header(&headers, auth_token)?;
-        opts.set_headers(&headers);
-
-        let request = Request::new_with_str_and_init(url, &opts)
-            .map_err(|e| format!("Failed to create request: {:?}", e))?;
-
-        let promise = window.fetch_with_request(&request);
-        let response = JsFuture::from(promise)
-            .await
-            .map_err(|e| format!("Fetch failed: {:?}", e))?;
-
-        let response: Response = response
-            .dyn_into()
-            .map_err(|_| "Failed to cast to Response")?;
-
+        let request = HttpRequest::get(url).header("Accept", "application/json");
+        let response = self.transport.send(request).await?;
         Self::handle_error_response(&response)?;
-
-        let json_promise = response
-            .json()
-            .map_err(|e| format!("Failed to get JSON: {:?}", e))?;
-        let json_value = JsFuture::from(json_promise)
-            .await
-            .map_err(|e| format!("Failed to parse JSON: {:?}", e))?;
-
-        serde_wasm_bindgen::from_value(json_value)
-            .map_err(|e| format!("Failed to deserialize JSON: {:?}", e))
+        response.json()
     }
 }
 
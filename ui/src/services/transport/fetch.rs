@@ -0,0 +1,78 @@
+//! Transport over the browser's `fetch`
+
+use async_trait::async_trait;
+use js_sys::{Array, Uint8Array};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{window, Headers, Request, RequestInit, Response};
+
+use super::{HttpRequest, HttpResponse, HttpTransport};
+
+/// Sends requests with `window.fetch` and reads the whole body
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FetchTransport;
+
+impl FetchTransport {
+    fn build_request(request: &HttpRequest) -> Result<Request, String> {
+        let opts = RequestInit::new();
+        opts.set_method(&request.method);
+
+        let headers = Headers::new().map_err(|e| format!("Failed to create headers: {:?}", e))?;
+        for (name, value) in &request.headers {
+            headers
+                .set(name, value)
+                .map_err(|e| format!("Failed to set {} header: {:?}", name, e))?;
+        }
+        opts.set_headers(&headers);
+
+        if let Some(body) = &request.body {
+            let js_value: JsValue = Uint8Array::from(&body[..]).into();
+            opts.set_body(&js_value);
+        }
+
+        Request::new_with_str_and_init(&request.url, &opts)
+            .map_err(|e| format!("Failed to create request: {:?}", e))
+    }
+
+    fn response_headers(response: &Response) -> Vec<(String, String)> {
+        let Ok(Some(entries)) = js_sys::try_iter(&response.headers()) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter_map(|entry| {
+                let pair: Array = entry.dyn_into().ok()?;
+                Some((pair.get(0).as_string()?, pair.get(1).as_string()?))
+            })
+            .collect()
+    }
+}
+
+#[async_trait(?Send)]
+impl HttpTransport for FetchTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, String> {
+        let window = window().ok_or("No window object")?;
+        let fetch_request = Self::build_request(&request)?;
+
+        let response = JsFuture::from(window.fetch_with_request(&fetch_request))
+            .await
+            .map_err(|e| format!("Fetch failed: {:?}", e))?;
+        let response: Response = response
+            .dyn_into()
+            .map_err(|_| "Failed to cast to Response")?;
+
+        let buffer = response
+            .array_buffer()
+            .map_err(|e| format!("Failed to read response body: {:?}", e))?;
+        let buffer = JsFuture::from(buffer)
+            .await
+            .map_err(|e| format!("Failed to read response body: {:?}", e))?;
+
+        Ok(HttpResponse {
+            status: response.status(),
+            status_text: response.status_text(),
+            headers: Self::response_headers(&response),
+            body: Uint8Array::new(&buffer).to_vec(),
+        })
+    }
+}
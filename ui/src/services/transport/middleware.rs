@@ -0,0 +1,508 @@
+//! Middleware layers for [`HttpTransport`]
+//!
+//! A [`MiddlewareStack`] runs its layers outermost first; each layer gets the
+//! request and a [`Next`] that sends it through the remaining layers to the
+//! transport. A layer may change the request, call `next` again (auth refresh)
+//! or not at all (open circuit). Layers that keep state (rate limit, circuit
+//! breaker) keep it per host, so one misbehaving host doesn't hold up others.
+
+use async_trait::async_trait;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::{HttpRequest, HttpResponse, HttpTransport};
+use crate::console_warn;
+use crate::services::client::host_stats::record_host_request;
+use crate::services::client::http_trace::record_http_trace;
+use crate::services::client::{clock_skew, server_now_secs, RefreshableSessionProvider};
+use crate::utils::platform::{now_ms, sleep_ms};
+
+/// One layer around the transport
+#[async_trait(?Send)]
+pub trait Middleware {
+    async fn handle(&self, request: HttpRequest, next: Next<'_>) -> Result<HttpResponse, String>;
+}
+
+/// The rest of the stack below a middleware
+#[derive(Clone, Copy)]
+pub struct Next<'a> {
+    transport: &'a dyn HttpTransport,
+    layers: &'a [Box<dyn Middleware>],
+}
+
+impl Next<'_> {
+    pub async fn run(self, request: HttpRequest) -> Result<HttpResponse, String> {
+        match self.layers.split_first() {
+            Some((layer, rest)) => {
+                layer
+                    .handle(
+                        request,
+                        Next {
+                            transport: self.transport,
+                            layers: rest,
+                        },
+                    )
+                    .await
+            }
+            None => self.transport.send(request).await,
+        }
+    }
+}
+
+/// A transport wrapped in middleware, itself usable as a transport
+pub struct MiddlewareStack {
+    transport: Box<dyn HttpTransport>,
+    layers: Vec<Box<dyn Middleware>>,
+}
+
+impl MiddlewareStack {
+    pub fn new(transport: Box<dyn HttpTransport>) -> Self {
+        Self {
+            transport,
+            layers: Vec::new(),
+        }
+    }
+
+    /// Add a layer inside the ones already added
+    pub fn with(mut self, layer: impl Middleware + 'static) -> Self {
+        self.layers.push(Box::new(layer));
+        self
+    }
+}
+
+#[async_trait(?Send)]
+impl HttpTransport for MiddlewareStack {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, String> {
+        Next {
+            transport: self.transport.as_ref(),
+            layers: &self.layers,
+        }
+        .run(request)
+        .await
+    }
+}
+
+/// Where the auth layer gets bearer tokens
+#[async_trait(?Send)]
+pub trait TokenSource {
+    /// A token that is valid now, refreshed ahead of expiry if needed
+    async fn token(&self) -> Result<String, String>;
+    /// A new token after the server rejected the current one
+    async fn refreshed_token(&self) -> Result<String, String>;
+
+    /// Whether a rejected token can be replaced; when it can't, the 401 is
+    /// handed back to the caller
+    fn refreshes(&self) -> bool {
+        true
+    }
+}
+
+#[async_trait(?Send)]
+impl TokenSource for RefreshableSessionProvider {
+    async fn token(&self) -> Result<String, String> {
+        self.get_fresh_token().await.map_err(|e| e.to_string())
+    }
+
+    async fn refreshed_token(&self) -> Result<String, String> {
+        self.force_refresh().await.map_err(|e| e.to_string())
+    }
+}
+
+/// A token the caller manages, such as a session's access JWT; refreshing it
+/// would rotate the refresh token behind the stored session's back
+pub struct StaticToken(pub String);
+
+#[async_trait(?Send)]
+impl TokenSource for StaticToken {
+    async fn token(&self) -> Result<String, String> {
+        Ok(self.0.clone())
+    }
+
+    async fn refreshed_token(&self) -> Result<String, String> {
+        Err("a static token cannot be refreshed".to_string())
+    }
+
+    fn refreshes(&self) -> bool {
+        false
+    }
+}
+
+/// Adds `Authorization: Bearer` and, on a 401, refreshes the token and tries once more
+pub struct AuthMiddleware {
+    source: Rc<dyn TokenSource>,
+}
+
+impl AuthMiddleware {
+    pub fn new(source: impl TokenSource + 'static) -> Self {
+        Self {
+            source: Rc::new(source),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl Middleware for AuthMiddleware {
+    async fn handle(
+        &self,
+        mut request: HttpRequest,
+        next: Next<'_>,
+    ) -> Result<HttpResponse, String> {
+        let token = self
+            .source
+            .token()
+            .await
+            .map_err(|e| format!("Failed to get fresh token: {}", e))?;
+        request.set_header("Authorization", format!("Bearer {}", token));
+        let response = next.run(request.clone()).await?;
+        if response.status != 401 || !self.source.refreshes() {
+            return Ok(response);
+        }
+
+        console_warn!(
+            "[Transport] 401 from {}, refreshing the token and retrying",
+            request.host()
+        );
+        let token = self
+            .source
+            .refreshed_token()
+            .await
+            .map_err(|e| format!("Failed to refresh token after 401: {}", e))?;
+        request.set_header("Authorization", format!("Bearer {}", token));
+        next.run(request).await
+    }
+}
+
+/// Longest pause the rate-limit layer takes before sending; longer resets are
+/// left to the caller's retry budget
+pub const MAX_RATE_LIMIT_WAIT_SECS: u64 = 120;
+
+/// Holds requests to a host back while its rate-limit window is used up
+#[derive(Default)]
+pub struct RateLimitMiddleware {
+    /// Server time when sending to each host may resume
+    blocked_until: RefCell<HashMap<String, u64>>,
+}
+
+impl RateLimitMiddleware {
+    /// Remember when the host's window resets if this response used it up
+    pub fn observe(&self, host: &str, response: &HttpResponse) {
+        let exhausted = response.status == 429 || response.rate_limit_remaining() == Some(0);
+        if exhausted {
+            let wait = response.retry_after_secs().unwrap_or(60);
+            self.blocked_until
+                .borrow_mut()
+                .insert(host.to_string(), server_now_secs() + wait);
+        }
+    }
+
+    /// Seconds to wait before sending to `host` at server time `now`
+    pub fn wait_secs(&self, host: &str, now: u64) -> u64 {
+        self.blocked_until
+            .borrow()
+            .get(host)
+            .map_or(0, |until| until.saturating_sub(now))
+    }
+}
+
+#[async_trait(?Send)]
+impl Middleware for RateLimitMiddleware {
+    async fn handle(&self, request: HttpRequest, next: Next<'_>) -> Result<HttpResponse, String> {
+        let host = request.host();
+        let wait = self.wait_secs(&host, server_now_secs());
+        if wait > 0 {
+            console_warn!(
+                "[Transport] Rate limit on {} used up, waiting {}s",
+                host,
+                wait.min(MAX_RATE_LIMIT_WAIT_SECS)
+            );
+            sleep_ms(wait.min(MAX_RATE_LIMIT_WAIT_SECS) * 1000).await;
+            self.blocked_until.borrow_mut().remove(&host);
+        }
+        let response = next.run(request).await?;
+        self.observe(&host, &response);
+        Ok(response)
+    }
+}
+
+/// Records every attempt in the support trace and the host statistics, and
+/// watches the `Date` header
+pub struct TracingMiddleware;
+
+#[async_trait(?Send)]
+impl Middleware for TracingMiddleware {
+    async fn handle(&self, request: HttpRequest, next: Next<'_>) -> Result<HttpResponse, String> {
+        let method = request.method.clone();
+        let url = request.url.clone();
+        let host = request.host();
+        let started_ms = now_ms();
+        let result = next.run(request).await;
+        let latency_ms = (now_ms() - started_ms).max(0.0) as u64;
+        record_host_request(
+            &host,
+            result.as_ref().ok().map(|response| response.status),
+            latency_ms,
+        );
+        match &result {
+            Ok(response) => {
+                record_http_trace(&method, &url, Some(response.status), None);
+                if let Some(date) = response.header("Date") {
+                    clock_skew::observe_date_header(date, &host);
+                }
+            }
+            Err(e) => record_http_trace(&method, &url, None, Some(e.clone())),
+        }
+        result
+    }
+}
+
+/// Consecutive failures before the circuit opens
+pub const CIRCUIT_FAILURE_THRESHOLD: u32 = 5;
+
+/// How long an open circuit fails requests without sending them
+pub const CIRCUIT_COOLDOWN_MS: u64 = 30_000;
+
+/// One host's circuit
+#[derive(Debug, Clone, Copy, Default)]
+struct Circuit {
+    failures: u32,
+    open_until_ms: f64,
+}
+
+/// Stops hammering a host that keeps failing; after the cooldown one request
+/// is let through, and its outcome closes or reopens that host's circuit
+pub struct CircuitBreakerMiddleware {
+    threshold: u32,
+    cooldown_ms: u64,
+    circuits: RefCell<HashMap<String, Circuit>>,
+}
+
+impl CircuitBreakerMiddleware {
+    pub fn new(threshold: u32, cooldown_ms: u64) -> Self {
+        Self {
+            threshold,
+            cooldown_ms,
+            circuits: RefCell::new(HashMap::new()),
+        }
+    }
+
+    fn record(&self, host: &str, failed: bool, now: f64) {
+        let mut circuits = self.circuits.borrow_mut();
+        if !failed {
+            circuits.remove(host);
+            return;
+        }
+        let circuit = circuits.entry(host.to_string()).or_default();
+        circuit.failures += 1;
+        if circuit.failures >= self.threshold {
+            circuit.open_until_ms = now + self.cooldown_ms as f64;
+        }
+    }
+
+    /// Milliseconds until requests to `host` are let through again at `now`
+    pub fn open_for_ms(&self, host: &str, now: f64) -> u64 {
+        self.circuits
+            .borrow()
+            .get(host)
+            .map_or(0, |circuit| (circuit.open_until_ms - now).max(0.0) as u64)
+    }
+
+    fn failures(&self, host: &str) -> u32 {
+        self.circuits
+            .borrow()
+            .get(host)
+            .map_or(0, |circuit| circuit.failures)
+    }
+}
+
+impl Default for CircuitBreakerMiddleware {
+    fn default() -> Self {
+        Self::new(CIRCUIT_FAILURE_THRESHOLD, CIRCUIT_COOLDOWN_MS)
+    }
+}
+
+#[async_trait(?Send)]
+impl Middleware for CircuitBreakerMiddleware {
+    async fn handle(&self, request: HttpRequest, next: Next<'_>) -> Result<HttpResponse, String> {
+        let host = request.host();
+        let open_for = self.open_for_ms(&host, now_ms());
+        if open_for > 0 {
+            return Err(format!(
+                "Circuit breaker open: {} failed {} times in a row - retry after {}ms",
+                host,
+                self.failures(&host),
+                open_for
+            ));
+        }
+        let result = next.run(request).await;
+        let failed = match &result {
+            Ok(response) => response.status >= 500,
+            Err(_) => true,
+        };
+        self.record(&host, failed, now_ms());
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::collections::VecDeque;
+
+    /// Answers from a script and remembers what it was sent
+    #[derive(Default)]
+    struct ScriptedTransport {
+        responses: RefCell<VecDeque<Result<HttpResponse, String>>>,
+        sent: Rc<RefCell<Vec<HttpRequest>>>,
+    }
+
+    #[async_trait(?Send)]
+    impl HttpTransport for ScriptedTransport {
+        async fn send(&self, request: HttpRequest) -> Result<HttpResponse, String> {
+            self.sent.borrow_mut().push(request);
+            self.responses
+                .borrow_mut()
+                .pop_front()
+                .unwrap_or_else(|| Ok(HttpResponse::new(200)))
+        }
+    }
+
+    struct Tokens(Cell<u32>);
+
+    #[async_trait(?Send)]
+    impl TokenSource for Tokens {
+        async fn token(&self) -> Result<String, String> {
+            Ok(format!("token-{}", self.0.get()))
+        }
+
+        async fn refreshed_token(&self) -> Result<String, String> {
+            self.0.set(self.0.get() + 1);
+            self.token().await
+        }
+    }
+
+    #[test]
+    fn test_middleware_auth_refresh_rate_limit_and_circuit() {
+        let transport = ScriptedTransport::default();
+        let sent = transport.sent.clone();
+        transport.responses.borrow_mut().extend([
+            Ok(HttpResponse::new(401)),
+            Ok(HttpResponse::new(200)),
+            Err("offline".to_string()),
+        ]);
+        let stack = MiddlewareStack::new(Box::new(transport))
+            .with(CircuitBreakerMiddleware::new(1, 60_000))
+            .with(AuthMiddleware::new(Tokens(Cell::new(0))));
+        let request = HttpRequest::get("https://pds.test/xrpc/com.atproto.repo.listMissingBlobs");
+
+        let response = futures::executor::block_on(stack.send(request.clone())).unwrap();
+        assert_eq!(response.status, 200);
+        let tokens: Vec<_> = sent
+            .borrow()
+            .iter()
+            .map(|r| r.header_value("authorization").unwrap().to_string())
+            .collect();
+        assert_eq!(tokens, ["Bearer token-0", "Bearer token-1"]);
+
+        // One failure opens the circuit; the next request is not sent
+        assert!(futures::executor::block_on(stack.send(request.clone())).is_err());
+        let refused = futures::executor::block_on(stack.send(request)).unwrap_err();
+        assert!(refused.starts_with("Circuit breaker open"), "{}", refused);
+        assert_eq!(sent.borrow().len(), 3);
+
+        let limiter = RateLimitMiddleware::default();
+        let mut ok = HttpResponse::new(200);
+        ok.headers
+            .push(("RateLimit-Remaining".to_string(), "4".to_string()));
+        limiter.observe("pds.test", &ok);
+        assert_eq!(limiter.wait_secs("pds.test", server_now_secs()), 0);
+        let mut limited = HttpResponse::new(429);
+        limited
+            .headers
+            .push(("Retry-After".to_string(), "30".to_string()));
+        limiter.observe("pds.test", &limited);
+        assert!((29..=30).contains(&limiter.wait_secs("pds.test", server_now_secs())));
+    }
+
+    #[test]
+    fn test_static_token_hands_back_401_and_shared_circuit() {
+        let transport = ScriptedTransport::default();
+        let sent = transport.sent.clone();
+        transport.responses.borrow_mut().extend([
+            Ok(HttpResponse::new(401)),
+            Ok(HttpResponse::new(503)),
+            Ok(HttpResponse::new(503)),
+        ]);
+        let shared = Rc::new(
+            MiddlewareStack::new(Box::new(transport))
+                .with(CircuitBreakerMiddleware::new(2, 60_000)),
+        );
+        let authed = |token: &str| {
+            MiddlewareStack::new(Box::new(shared.clone()))
+                .with(AuthMiddleware::new(StaticToken(token.to_string())))
+        };
+        let request = HttpRequest::get("https://pds.test/xrpc/com.atproto.server.getSession");
+
+        // No refresh: the caller decides what a rejected session means
+        let response = futures::executor::block_on(authed("old").send(request.clone())).unwrap();
+        assert_eq!(response.status, 401);
+        assert_eq!(sent.borrow().len(), 1);
+        assert_eq!(
+            sent.borrow()[0].header_value("Authorization"),
+            Some("Bearer old")
+        );
+
+        // Failures seen through different sessions count toward one circuit
+        futures::executor::block_on(authed("new").send(request.clone())).unwrap();
+        futures::executor::block_on(shared.send(request.clone())).unwrap();
+        let refused = futures::executor::block_on(authed("new").send(request)).unwrap_err();
+        assert!(refused.starts_with("Circuit breaker open"), "{}", refused);
+        assert_eq!(sent.borrow().len(), 3);
+    }
+
+    #[test]
+    fn test_rate_limit_and_circuit_are_per_host() {
+        let transport = ScriptedTransport::default();
+        let sent = transport.sent.clone();
+        let mut limited = HttpResponse::new(429);
+        limited
+            .headers
+            .push(("Retry-After".to_string(), "30".to_string()));
+        transport.responses.borrow_mut().extend([
+            Ok(limited),
+            Err("offline".to_string()),
+            Ok(HttpResponse::new(200)),
+        ]);
+        let stack = MiddlewareStack::new(Box::new(transport))
+            .with(CircuitBreakerMiddleware::new(1, 60_000))
+            .with(RateLimitMiddleware::default());
+        let old = HttpRequest::get("https://old.test/xrpc/com.atproto.sync.getRepo");
+        let new = HttpRequest::get("https://new.test/xrpc/com.atproto.server.createSession");
+
+        // A 429 from the old PDS leaves the new one alone; a wait here would
+        // need a timer the executor doesn't have
+        let response = futures::executor::block_on(stack.send(old.clone())).unwrap();
+        assert_eq!(response.status, 429);
+        futures::executor::block_on(stack.send(new.clone())).unwrap_err();
+
+        // The new PDS's failure opened only its own circuit
+        let refused = futures::executor::block_on(stack.send(new)).unwrap_err();
+        assert!(
+            refused.starts_with("Circuit breaker open: new.test"),
+            "{}",
+            refused
+        );
+        assert_eq!(sent.borrow().len(), 2);
+
+        // Neither layer's state for one host shows up under another
+        let limiter = RateLimitMiddleware::default();
+        limiter.observe("old.test", &HttpResponse::new(429));
+        assert!(limiter.wait_secs("old.test", server_now_secs()) > 0);
+        assert_eq!(limiter.wait_secs("new.test", server_now_secs()), 0);
+        let breaker = CircuitBreakerMiddleware::new(1, 60_000);
+        breaker.record("new.test", true, 0.0);
+        assert_eq!(breaker.open_for_ms("old.test", 0.0), 0);
+        assert_eq!(breaker.open_for_ms("new.test", 0.0), 60_000);
+    }
+}
@@ -0,0 +1,204 @@
+//! HTTP transport with composable middleware
+//!
+//! The `PdsClient` XRPC endpoints and the streaming repo and blob transfers
+//! send their buffered requests through [`shared_transport`]. Underneath, the
+//! browser build uses `fetch` ([`FetchTransport`]); native builds use reqwest
+//! ([`ReqwestTransport`]). Cross-cutting behaviour — bearer tokens and
+//! refresh on 401, waiting out rate limits, the support trace and a circuit
+//! breaker — lives in [`Middleware`] layers stacked with [`MiddlewareStack`],
+//! instead of being repeated at every endpoint. The shared stack's rate limit
+//! and circuit breaker are keyed by host, so a 429 from the old PDS slows
+//! every client talking to it but none talking to the new one. Requests that
+//! stream their response body, or need their own timeout (probes,
+//! describeServer), still use reqwest directly.
+
+pub mod fetch;
+pub mod middleware;
+pub mod native;
+
+pub use fetch::*;
+pub use middleware::*;
+pub use native::*;
+
+use async_trait::async_trait;
+use std::rc::Rc;
+
+use crate::services::client::server_now_secs;
+
+/// An HTTP request as seen by transports and middleware
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+}
+
+impl HttpRequest {
+    pub fn new(method: &str, url: impl Into<String>) -> Self {
+        Self {
+            method: method.to_string(),
+            url: url.into(),
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+
+    pub fn get(url: impl Into<String>) -> Self {
+        Self::new("GET", url)
+    }
+
+    pub fn post(url: impl Into<String>, body: Vec<u8>, content_type: &str) -> Self {
+        let mut request = Self::new("POST", url).header("Content-Type", content_type);
+        request.body = Some(body);
+        request
+    }
+
+    pub fn post_json(url: impl Into<String>, body: &serde_json::Value) -> Self {
+        Self::post(url, body.to_string().into_bytes(), "application/json")
+    }
+
+    /// Set a header, replacing any earlier value of the same name
+    pub fn header(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.set_header(name, value);
+        self
+    }
+
+    pub fn set_header(&mut self, name: &str, value: impl Into<String>) {
+        self.headers
+            .retain(|(existing, _)| !existing.eq_ignore_ascii_case(name));
+        self.headers.push((name.to_string(), value.into()));
+    }
+
+    pub fn header_value(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    /// Host of the URL, for logs and the clock-skew source
+    pub fn host(&self) -> String {
+        reqwest::Url::parse(&self.url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_default()
+    }
+}
+
+/// A complete HTTP response; error statuses are responses, not `Err`
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub status_text: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    pub fn new(status: u16) -> Self {
+        Self {
+            status,
+            status_text: String::new(),
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    fn header_number<T: std::str::FromStr>(&self, name: &str) -> Option<T> {
+        self.header(name)?.trim().parse().ok()
+    }
+
+    pub fn rate_limit_limit(&self) -> Option<i32> {
+        self.header_number("RateLimit-Limit")
+    }
+
+    pub fn rate_limit_remaining(&self) -> Option<i32> {
+        self.header_number("RateLimit-Remaining")
+    }
+
+    /// Server time (seconds since the epoch) when the rate-limit window resets
+    pub fn rate_limit_reset(&self) -> Option<u64> {
+        self.header_number("RateLimit-Reset")
+    }
+
+    /// Seconds the server asks us to wait, from `Retry-After` or `RateLimit-Reset`
+    pub fn retry_after_secs(&self) -> Option<u64> {
+        self.header_number("Retry-After").or_else(|| {
+            self.rate_limit_reset()
+                .map(|reset| reset.saturating_sub(server_now_secs()))
+        })
+    }
+
+    pub fn json<T: for<'de> serde::Deserialize<'de>>(&self) -> Result<T, String> {
+        serde_json::from_slice(&self.body).map_err(|e| format!("Failed to deserialize JSON: {}", e))
+    }
+
+    /// The body as text, for error messages
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(existing, _)| existing.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Sends a request and returns the full response; `Err` only when no response
+/// arrived (network failure, CORS, an open circuit)
+#[async_trait(?Send)]
+pub trait HttpTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, String>;
+}
+
+/// A shared transport, so layers that keep state (rate limit, circuit
+/// breaker) see every request sent through any of its handles
+#[async_trait(?Send)]
+impl<T: HttpTransport + ?Sized> HttpTransport for Rc<T> {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, String> {
+        (**self).send(request).await
+    }
+}
+
+/// Sent with native requests; browsers don't let pages set it
+pub const USER_AGENT: &str = "tektite-cc-atproto-migration-service/1.0";
+
+/// `fetch` in the browser, reqwest everywhere else
+pub fn default_transport() -> Box<dyn HttpTransport> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        Box::new(FetchTransport)
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .build()
+            .expect("Failed to create HTTP client");
+        Box::new(ReqwestTransport::with_client(client))
+    }
+}
+
+thread_local! {
+    static SHARED_TRANSPORT: Rc<MiddlewareStack> = Rc::new(
+        MiddlewareStack::new(default_transport())
+            .with(CircuitBreakerMiddleware::default())
+            .with(RateLimitMiddleware::default())
+            .with(TracingMiddleware),
+    );
+}
+
+/// Circuit breaker, rate limit and tracing over [`default_transport`], one
+/// stack for every client; put an [`AuthMiddleware`] in front of it for
+/// authenticated calls
+pub fn shared_transport() -> Rc<MiddlewareStack> {
+    SHARED_TRANSPORT.with(Rc::clone)
+}
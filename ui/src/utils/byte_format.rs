@@ -0,0 +1,129 @@
+//! Shared formatting for sizes and transfer rates shown to the user
+//!
+//! Progress displays, estimates, reports and log lines all print sizes through
+//! here, so one account never shows "48.2 MB" in one place and "50.5 MB" in
+//! another for the same bytes. Binary units (KiB, MiB, powers of 1024) are the
+//! default; the user can switch to decimal units (KB, MB, powers of 1000),
+//! which is what most operating systems and hosting plans quote. Precision
+//! adapts to the size: "4.27 MiB", "42.7 MiB", "427 MiB".
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Which unit system sizes are shown in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ByteUnits {
+    /// KiB, MiB, GiB: powers of 1024
+    #[default]
+    Binary,
+    /// KB, MB, GB: powers of 1000
+    Decimal,
+}
+
+impl ByteUnits {
+    fn base(self) -> f64 {
+        match self {
+            ByteUnits::Binary => 1024.0,
+            ByteUnits::Decimal => 1000.0,
+        }
+    }
+
+    fn labels(self) -> [&'static str; 5] {
+        match self {
+            ByteUnits::Binary => ["B", "KiB", "MiB", "GiB", "TiB"],
+            ByteUnits::Decimal => ["B", "KB", "MB", "GB", "TB"],
+        }
+    }
+}
+
+/// Set while the user has chosen decimal units
+static DECIMAL_UNITS: AtomicBool = AtomicBool::new(false);
+
+/// Unit system currently chosen for display
+pub fn byte_units() -> ByteUnits {
+    if DECIMAL_UNITS.load(Ordering::Relaxed) {
+        ByteUnits::Decimal
+    } else {
+        ByteUnits::Binary
+    }
+}
+
+pub fn set_byte_units(units: ByteUnits) {
+    DECIMAL_UNITS.store(units == ByteUnits::Decimal, Ordering::Relaxed);
+}
+
+/// Two decimals below 10, one below 100, none above
+fn with_adaptive_precision(value: f64, label: &str) -> String {
+    if value < 10.0 {
+        format!("{:.2} {}", value, label)
+    } else if value < 100.0 {
+        format!("{:.1} {}", value, label)
+    } else {
+        format!("{:.0} {}", value, label)
+    }
+}
+
+/// Format a size in the given units, e.g. "512 B" or "4.27 MiB"
+pub fn format_bytes_with(bytes: u64, units: ByteUnits) -> String {
+    let base = units.base();
+    let labels = units.labels();
+    if (bytes as f64) < base {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= base && unit < labels.len() - 1 {
+        value /= base;
+        unit += 1;
+    }
+    // 1023.9 KiB would print as "1024 KiB"; show it as the next unit instead
+    if value.round() >= base && unit < labels.len() - 1 {
+        value /= base;
+        unit += 1;
+    }
+    with_adaptive_precision(value, labels[unit])
+}
+
+/// Format a transfer rate in the given units, e.g. "1.50 MiB/s"
+pub fn format_throughput_with(bytes_per_sec: f64, units: ByteUnits) -> String {
+    format!(
+        "{}/s",
+        format_bytes_with(bytes_per_sec.max(0.0).round() as u64, units)
+    )
+}
+
+/// Format a size in the units the user chose
+pub fn format_size(bytes: u64) -> String {
+    format_bytes_with(bytes, byte_units())
+}
+
+/// Format a transfer rate in the units the user chose
+pub fn format_throughput(bytes_per_sec: f64) -> String {
+    format_throughput_with(bytes_per_sec, byte_units())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binary_and_decimal_sizes() {
+        assert_eq!(format_bytes_with(512, ByteUnits::Binary), "512 B");
+        assert_eq!(format_bytes_with(1024, ByteUnits::Binary), "1.00 KiB");
+        assert_eq!(format_bytes_with(1024, ByteUnits::Decimal), "1.02 KB");
+        assert_eq!(format_bytes_with(999, ByteUnits::Decimal), "999 B");
+        assert_eq!(format_bytes_with(4_480_000, ByteUnits::Binary), "4.27 MiB");
+        assert_eq!(format_bytes_with(44_800_000, ByteUnits::Binary), "42.7 MiB");
+        assert_eq!(format_bytes_with(448_000_000, ByteUnits::Binary), "427 MiB");
+        assert_eq!(format_bytes_with(1_048_575, ByteUnits::Binary), "1.00 MiB");
+        assert_eq!(
+            format_bytes_with(2_000_000_000, ByteUnits::Decimal),
+            "2.00 GB"
+        );
+        assert_eq!(
+            format_throughput_with(1_572_864.0, ByteUnits::Binary),
+            "1.50 MiB/s"
+        );
+        assert_eq!(format_throughput_with(-5.0, ByteUnits::Decimal), "0 B/s");
+    }
+}
@@ -4,63 +4,101 @@
 ///
 /// Some macros support optional dispatch parameter to capture messages in state.
 /// Use the _with_dispatch variants to also send messages to the application state.
+///
+/// Outside WASM (server-side rendering, native tests) output goes to stderr instead,
+/// since the browser console bindings panic without a JS runtime.
+///
+/// Levels are filtered at runtime through `utils::log_filter`; the message is only
+/// formatted when its level is enabled for the calling module, and `console_debug!`
+/// is rate limited per call site. The _with_dispatch variants always reach the
+/// application state, whatever the console filter says.
 #[macro_export]
 macro_rules! console_info {
     ($fmt:expr) => {
-        let timestamp = js_sys::Date::new_0().to_iso_string().as_string().unwrap_or_default();
-        gloo_console::info!(format!("[{}] {}", timestamp, $fmt))
+        $crate::utils::log_filter::write_filtered(
+            $crate::utils::console_macros::ConsoleLevel::Info,
+            module_path!(),
+            || format!("[{}] {}", $crate::utils::platform::now_iso8601(), $fmt),
+        )
     };
     ($fmt:expr, $($arg:tt)*) => {
-        let timestamp = js_sys::Date::new_0().to_iso_string().as_string().unwrap_or_default();
-        gloo_console::info!(format!("[{}] {}", timestamp, format!($fmt, $($arg)*)))
+        $crate::utils::log_filter::write_filtered(
+            $crate::utils::console_macros::ConsoleLevel::Info,
+            module_path!(),
+            || format!("[{}] {}", $crate::utils::platform::now_iso8601(), format!($fmt, $($arg)*)),
+        )
     };
 }
 
 #[macro_export]
 macro_rules! console_log {
     ($fmt:expr) => {
-        let timestamp = js_sys::Date::new_0().to_iso_string().as_string().unwrap_or_default();
-        gloo_console::log!(format!("[{}] {}", timestamp, $fmt))
+        $crate::utils::log_filter::write_filtered(
+            $crate::utils::console_macros::ConsoleLevel::Log,
+            module_path!(),
+            || format!("[{}] {}", $crate::utils::platform::now_iso8601(), $fmt),
+        )
     };
     ($fmt:expr, $($arg:tt)*) => {
-        let timestamp = js_sys::Date::new_0().to_iso_string().as_string().unwrap_or_default();
-        gloo_console::log!(format!("[{}] {}", timestamp, format!($fmt, $($arg)*)))
+        $crate::utils::log_filter::write_filtered(
+            $crate::utils::console_macros::ConsoleLevel::Log,
+            module_path!(),
+            || format!("[{}] {}", $crate::utils::platform::now_iso8601(), format!($fmt, $($arg)*)),
+        )
     };
 }
 
 #[macro_export]
 macro_rules! console_warn {
     ($fmt:expr) => {
-        let timestamp = js_sys::Date::new_0().to_iso_string().as_string().unwrap_or_default();
-        gloo_console::warn!(format!("[{}] {}", timestamp, $fmt))
+        $crate::utils::log_filter::write_filtered(
+            $crate::utils::console_macros::ConsoleLevel::Warn,
+            module_path!(),
+            || format!("[{}] {}", $crate::utils::platform::now_iso8601(), $fmt),
+        )
     };
     ($fmt:expr, $($arg:tt)*) => {
-        let timestamp = js_sys::Date::new_0().to_iso_string().as_string().unwrap_or_default();
-        gloo_console::warn!(format!("[{}] {}", timestamp, format!($fmt, $($arg)*)))
+        $crate::utils::log_filter::write_filtered(
+            $crate::utils::console_macros::ConsoleLevel::Warn,
+            module_path!(),
+            || format!("[{}] {}", $crate::utils::platform::now_iso8601(), format!($fmt, $($arg)*)),
+        )
     };
 }
 
 #[macro_export]
 macro_rules! console_error {
     ($fmt:expr) => {
-        let timestamp = js_sys::Date::new_0().to_iso_string().as_string().unwrap_or_default();
-        gloo_console::error!(format!("[{}] {}", timestamp, $fmt))
+        $crate::utils::log_filter::write_filtered(
+            $crate::utils::console_macros::ConsoleLevel::Error,
+            module_path!(),
+            || format!("[{}] {}", $crate::utils::platform::now_iso8601(), $fmt),
+        )
     };
     ($fmt:expr, $($arg:tt)*) => {
-        let timestamp = js_sys::Date::new_0().to_iso_string().as_string().unwrap_or_default();
-        gloo_console::error!(format!("[{}] {}", timestamp, format!($fmt, $($arg)*)))
+        $crate::utils::log_filter::write_filtered(
+            $crate::utils::console_macros::ConsoleLevel::Error,
+            module_path!(),
+            || format!("[{}] {}", $crate::utils::platform::now_iso8601(), format!($fmt, $($arg)*)),
+        )
     };
 }
 
 #[macro_export]
 macro_rules! console_debug {
     ($fmt:expr) => {
-        let timestamp = js_sys::Date::new_0().to_iso_string().as_string().unwrap_or_default();
-        gloo_console::debug!(format!("[{}] {}", timestamp, $fmt))
+        $crate::utils::log_filter::write_debug(
+            concat!(module_path!(), ":", line!()),
+            module_path!(),
+            || format!("[{}] {}", $crate::utils::platform::now_iso8601(), $fmt),
+        )
     };
     ($fmt:expr, $($arg:tt)*) => {
-        let timestamp = js_sys::Date::new_0().to_iso_string().as_string().unwrap_or_default();
-        gloo_console::debug!(format!("[{}] {}", timestamp, format!($fmt, $($arg)*)))
+        $crate::utils::log_filter::write_debug(
+            concat!(module_path!(), ":", line!()),
+            module_path!(),
+            || format!("[{}] {}", $crate::utils::platform::now_iso8601(), format!($fmt, $($arg)*)),
+        )
     };
 }
 
@@ -69,64 +107,246 @@ macro_rules! console_debug {
 #[macro_export]
 macro_rules! console_log_with_dispatch {
     ($dispatch:expr, $fmt:expr) => {
-        gloo_console::log!($fmt);
-        $dispatch.call($crate::migration::MigrationAction::AddConsoleMessage(format!("[LOG] {}", $fmt)));
+        if $crate::utils::log_filter::log_enabled(
+            $crate::utils::console_macros::ConsoleLevel::Log,
+            module_path!(),
+        ) {
+            $crate::utils::console_macros::write_console(
+                $crate::utils::console_macros::ConsoleLevel::Log,
+                &format!("{}", $fmt),
+            );
+        }
+        $dispatch.call($crate::migration::MigrationAction::AddConsoleMessage(
+            $crate::migration::ConsoleEntry::new(
+                $crate::utils::console_macros::ConsoleLevel::Log,
+                module_path!(),
+                format!("{}", $fmt),
+            ),
+        ));
     };
     ($dispatch:expr, $fmt:expr, $($arg:tt)*) => {
         let formatted = format!($fmt, $($arg)*);
-        gloo_console::log!(&formatted);
-        $dispatch.call($crate::migration::MigrationAction::AddConsoleMessage(format!("[LOG] {}", formatted)));
+        if $crate::utils::log_filter::log_enabled(
+            $crate::utils::console_macros::ConsoleLevel::Log,
+            module_path!(),
+        ) {
+            $crate::utils::console_macros::write_console(
+                $crate::utils::console_macros::ConsoleLevel::Log,
+                &formatted,
+            );
+        }
+        $dispatch.call($crate::migration::MigrationAction::AddConsoleMessage(
+            $crate::migration::ConsoleEntry::new(
+                $crate::utils::console_macros::ConsoleLevel::Log,
+                module_path!(),
+                formatted,
+            ),
+        ));
     };
 }
 
 #[macro_export]
 macro_rules! console_debug_with_dispatch {
     ($dispatch:expr, $fmt:expr) => {
-        gloo_console::debug!($fmt);
-        $dispatch.call($crate::migration::MigrationAction::AddConsoleMessage(format!("[DEBUG] {}", $fmt)));
+        if $crate::utils::log_filter::log_enabled(
+            $crate::utils::console_macros::ConsoleLevel::Debug,
+            module_path!(),
+        ) {
+            $crate::utils::console_macros::write_console(
+                $crate::utils::console_macros::ConsoleLevel::Debug,
+                &format!("{}", $fmt),
+            );
+        }
+        $dispatch.call($crate::migration::MigrationAction::AddConsoleMessage(
+            $crate::migration::ConsoleEntry::new(
+                $crate::utils::console_macros::ConsoleLevel::Debug,
+                module_path!(),
+                format!("{}", $fmt),
+            ),
+        ));
     };
     ($dispatch:expr, $fmt:expr, $($arg:tt)*) => {
         let formatted = format!($fmt, $($arg)*);
-        gloo_console::debug!(&formatted);
-        $dispatch.call($crate::migration::MigrationAction::AddConsoleMessage(format!("[DEBUG] {}", formatted)));
+        if $crate::utils::log_filter::log_enabled(
+            $crate::utils::console_macros::ConsoleLevel::Debug,
+            module_path!(),
+        ) {
+            $crate::utils::console_macros::write_console(
+                $crate::utils::console_macros::ConsoleLevel::Debug,
+                &formatted,
+            );
+        }
+        $dispatch.call($crate::migration::MigrationAction::AddConsoleMessage(
+            $crate::migration::ConsoleEntry::new(
+                $crate::utils::console_macros::ConsoleLevel::Debug,
+                module_path!(),
+                formatted,
+            ),
+        ));
     };
 }
 
 #[macro_export]
 macro_rules! console_info_with_dispatch {
     ($dispatch:expr, $fmt:expr) => {
-        gloo_console::info!($fmt);
-        $dispatch.call($crate::migration::MigrationAction::AddConsoleMessage(format!("[INFO] {}", $fmt)));
+        if $crate::utils::log_filter::log_enabled(
+            $crate::utils::console_macros::ConsoleLevel::Info,
+            module_path!(),
+        ) {
+            $crate::utils::console_macros::write_console(
+                $crate::utils::console_macros::ConsoleLevel::Info,
+                &format!("{}", $fmt),
+            );
+        }
+        $dispatch.call($crate::migration::MigrationAction::AddConsoleMessage(
+            $crate::migration::ConsoleEntry::new(
+                $crate::utils::console_macros::ConsoleLevel::Info,
+                module_path!(),
+                format!("{}", $fmt),
+            ),
+        ));
     };
     ($dispatch:expr, $fmt:expr, $($arg:tt)*) => {
         let formatted = format!($fmt, $($arg)*);
-        gloo_console::info!(&formatted);
-        $dispatch.call($crate::migration::MigrationAction::AddConsoleMessage(format!("[INFO] {}", formatted)));
+        if $crate::utils::log_filter::log_enabled(
+            $crate::utils::console_macros::ConsoleLevel::Info,
+            module_path!(),
+        ) {
+            $crate::utils::console_macros::write_console(
+                $crate::utils::console_macros::ConsoleLevel::Info,
+                &formatted,
+            );
+        }
+        $dispatch.call($crate::migration::MigrationAction::AddConsoleMessage(
+            $crate::migration::ConsoleEntry::new(
+                $crate::utils::console_macros::ConsoleLevel::Info,
+                module_path!(),
+                formatted,
+            ),
+        ));
     };
 }
 
 #[macro_export]
 macro_rules! console_warn_with_dispatch {
     ($dispatch:expr, $fmt:expr) => {
-        gloo_console::warn!($fmt);
-        $dispatch.call($crate::migration::MigrationAction::AddConsoleMessage(format!("[WARN] {}", $fmt)));
+        if $crate::utils::log_filter::log_enabled(
+            $crate::utils::console_macros::ConsoleLevel::Warn,
+            module_path!(),
+        ) {
+            $crate::utils::console_macros::write_console(
+                $crate::utils::console_macros::ConsoleLevel::Warn,
+                &format!("{}", $fmt),
+            );
+        }
+        $dispatch.call($crate::migration::MigrationAction::AddConsoleMessage(
+            $crate::migration::ConsoleEntry::new(
+                $crate::utils::console_macros::ConsoleLevel::Warn,
+                module_path!(),
+                format!("{}", $fmt),
+            ),
+        ));
     };
     ($dispatch:expr, $fmt:expr, $($arg:tt)*) => {
         let formatted = format!($fmt, $($arg)*);
-        gloo_console::warn!(&formatted);
-        $dispatch.call($crate::migration::MigrationAction::AddConsoleMessage(format!("[WARN] {}", formatted)));
+        if $crate::utils::log_filter::log_enabled(
+            $crate::utils::console_macros::ConsoleLevel::Warn,
+            module_path!(),
+        ) {
+            $crate::utils::console_macros::write_console(
+                $crate::utils::console_macros::ConsoleLevel::Warn,
+                &formatted,
+            );
+        }
+        $dispatch.call($crate::migration::MigrationAction::AddConsoleMessage(
+            $crate::migration::ConsoleEntry::new(
+                $crate::utils::console_macros::ConsoleLevel::Warn,
+                module_path!(),
+                formatted,
+            ),
+        ));
     };
 }
 
 #[macro_export]
 macro_rules! console_error_with_dispatch {
     ($dispatch:expr, $fmt:expr) => {
-        gloo_console::error!($fmt);
-        $dispatch.call($crate::migration::MigrationAction::AddConsoleMessage(format!("[ERROR] {}", $fmt)));
+        if $crate::utils::log_filter::log_enabled(
+            $crate::utils::console_macros::ConsoleLevel::Error,
+            module_path!(),
+        ) {
+            $crate::utils::console_macros::write_console(
+                $crate::utils::console_macros::ConsoleLevel::Error,
+                &format!("{}", $fmt),
+            );
+        }
+        $dispatch.call($crate::migration::MigrationAction::AddConsoleMessage(
+            $crate::migration::ConsoleEntry::new(
+                $crate::utils::console_macros::ConsoleLevel::Error,
+                module_path!(),
+                format!("{}", $fmt),
+            ),
+        ));
     };
     ($dispatch:expr, $fmt:expr, $($arg:tt)*) => {
         let formatted = format!($fmt, $($arg)*);
-        gloo_console::error!(&formatted);
-        $dispatch.call($crate::migration::MigrationAction::AddConsoleMessage(format!("[ERROR] {}", formatted)));
+        if $crate::utils::log_filter::log_enabled(
+            $crate::utils::console_macros::ConsoleLevel::Error,
+            module_path!(),
+        ) {
+            $crate::utils::console_macros::write_console(
+                $crate::utils::console_macros::ConsoleLevel::Error,
+                &formatted,
+            );
+        }
+        $dispatch.call($crate::migration::MigrationAction::AddConsoleMessage(
+            $crate::migration::ConsoleEntry::new(
+                $crate::utils::console_macros::ConsoleLevel::Error,
+                module_path!(),
+                formatted,
+            ),
+        ));
     };
 }
+
+/// Severity used to pick the console method, ordered from least to most severe
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+pub enum ConsoleLevel {
+    Debug,
+    Log,
+    Info,
+    Warn,
+    Error,
+}
+
+impl ConsoleLevel {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConsoleLevel::Debug => "DEBUG",
+            ConsoleLevel::Log => "LOG",
+            ConsoleLevel::Info => "INFO",
+            ConsoleLevel::Warn => "WARN",
+            ConsoleLevel::Error => "ERROR",
+        }
+    }
+}
+
+/// Write an already formatted message to the browser console (stderr outside WASM)
+#[cfg(target_arch = "wasm32")]
+pub fn write_console(level: ConsoleLevel, message: &str) {
+    match level {
+        ConsoleLevel::Log => gloo_console::log!(message),
+        ConsoleLevel::Debug => gloo_console::debug!(message),
+        ConsoleLevel::Info => gloo_console::info!(message),
+        ConsoleLevel::Warn => gloo_console::warn!(message),
+        ConsoleLevel::Error => gloo_console::error!(message),
+    }
+}
+
+/// Write an already formatted message to the browser console (stderr outside WASM)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_console(level: ConsoleLevel, message: &str) {
+    eprintln!("{:?}: {}", level, message);
+}
@@ -0,0 +1,281 @@
+//! Handle normalization for internationalized and uncommon domains
+//!
+//! Handles are DNS names, so `bücher.example`, `xn--bcher-kva.example` and
+//! `Bücher.Example.` all name the same account. Everything is reduced to the
+//! lowercase ASCII (punycode) form before it is checked or resolved, and turned
+//! back into Unicode only for display. The syntax check follows the atproto
+//! handle rules rather than a TLD list, so new gTLDs and IDN TLDs pass as long
+//! as they are well formed.
+//!
+//! Identifiers are often pasted from profiles, emails or chat, and arrive with
+//! surrounding spaces, a non-breaking space or zero-width characters, an `@`
+//! or a trailing dot. Every place an identity or PDS URL is entered goes
+//! through [`normalize_identifier`] or [`normalize_pds_url`] first, so those
+//! stray characters never reach resolution, where they would only show up as
+//! a confusing lookup failure.
+
+/// Longest handle the atproto spec allows
+const MAX_HANDLE_LEN: usize = 253;
+
+/// Longest single DNS label
+const MAX_LABEL_LEN: usize = 63;
+
+/// Why an ASCII handle fails the atproto syntax rules, if it does
+fn syntax_error(handle: &str) -> Option<String> {
+    if handle.len() > MAX_HANDLE_LEN {
+        return Some(format!(
+            "Handle is longer than {} characters",
+            MAX_HANDLE_LEN
+        ));
+    }
+
+    let labels: Vec<&str> = handle.split('.').collect();
+    if labels.len() < 2 {
+        return Some("Handle needs a domain, e.g. alice.bsky.social".to_string());
+    }
+    for label in &labels {
+        if label.is_empty() {
+            return Some("Handle has an empty segment between dots".to_string());
+        }
+        if label.len() > MAX_LABEL_LEN {
+            return Some(format!(
+                "Handle segment '{}' is longer than {} characters",
+                label, MAX_LABEL_LEN
+            ));
+        }
+        if label.starts_with('-') || label.ends_with('-') {
+            return Some(format!(
+                "Handle segment '{}' can't start or end with a hyphen",
+                label
+            ));
+        }
+        if !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Some(format!(
+                "Handle segment '{}' contains characters that aren't allowed",
+                label
+            ));
+        }
+    }
+    // Numeric TLDs would make IPv4 addresses look like handles
+    if labels
+        .last()
+        .is_some_and(|tld| tld.starts_with(|c: char| c.is_ascii_digit()))
+    {
+        return Some("Handle can't end in a numeric top-level domain".to_string());
+    }
+    None
+}
+
+/// Characters that are invisible when pasted but break a lookup
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00AD}' | '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{FEFF}'
+    )
+}
+
+/// Drop invisible characters and surrounding whitespace, including non-breaking spaces
+fn strip_pasted_noise(input: &str) -> String {
+    input
+        .chars()
+        .filter(|c| !is_invisible(*c))
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// Strip what commonly surrounds a pasted handle: whitespace, invisible
+/// characters, a leading `@` and trailing dots
+pub fn clean_identifier_input(input: &str) -> String {
+    let cleaned = strip_pasted_noise(input);
+    let cleaned = cleaned.strip_prefix('@').unwrap_or(&cleaned).trim_start();
+    cleaned.trim_end_matches('.').to_string()
+}
+
+/// Reduce user input to the canonical lowercase ASCII handle
+///
+/// Accepts pasted noise (see [`clean_identifier_input`]) and Unicode labels,
+/// which are converted to their `xn--` form.
+pub fn normalize_handle(input: &str) -> Result<String, String> {
+    let trimmed = clean_identifier_input(input);
+    if trimmed.is_empty() {
+        return Err("Handle is empty".to_string());
+    }
+
+    let ascii = idna::domain_to_ascii(&trimmed)
+        .map_err(|_| format!("'{}' is not a valid domain name", trimmed))?;
+    match syntax_error(&ascii) {
+        Some(error) => Err(error),
+        None => Ok(ascii),
+    }
+}
+
+/// Canonical form of a DID, or `None` if the input isn't one
+///
+/// The `did:` prefix and method are case-insensitive when typed; `did:plc`
+/// identifiers are lowercase base32 and `did:web` ones are domain names, so
+/// both are lowercased too.
+pub fn normalize_did(input: &str) -> Option<String> {
+    let cleaned = strip_pasted_noise(input);
+    let (scheme, rest) = cleaned.split_once(':')?;
+    let (method, identifier) = rest.split_once(':')?;
+    if !scheme.eq_ignore_ascii_case("did") || method.is_empty() || identifier.is_empty() {
+        return None;
+    }
+    let method = method.to_ascii_lowercase();
+    let identifier = match method.as_str() {
+        "plc" | "web" => identifier.to_ascii_lowercase(),
+        _ => identifier.to_string(),
+    };
+    Some(format!("did:{}:{}", method, identifier))
+}
+
+/// Canonical form of a handle or DID as typed into any identity field
+///
+/// Input that is neither a DID nor a well-formed handle is only cleaned, so
+/// validation still reports on what the user meant to enter.
+pub fn normalize_identifier(input: &str) -> String {
+    if let Some(did) = normalize_did(input) {
+        return did;
+    }
+    normalize_handle(input).unwrap_or_else(|_| clean_identifier_input(input))
+}
+
+/// Canonical PDS URL: `https://` added when no scheme was typed, host
+/// lowercased and trailing slashes removed
+pub fn normalize_pds_url(input: &str) -> String {
+    let cleaned = strip_pasted_noise(input);
+    if cleaned.is_empty() {
+        return cleaned;
+    }
+    let with_scheme = if cleaned.contains("://") {
+        cleaned.clone()
+    } else {
+        format!("https://{}", cleaned)
+    };
+    match reqwest::Url::parse(&with_scheme) {
+        Ok(url) => url.as_str().trim_end_matches('/').to_string(),
+        Err(_) => cleaned.trim_end_matches('/').to_string(),
+    }
+}
+
+/// Whether the input normalizes to a syntactically valid handle
+pub fn is_valid_handle_syntax(input: &str) -> bool {
+    normalize_handle(input).is_ok()
+}
+
+/// Unicode form of a handle for display; ASCII handles come back unchanged
+pub fn display_handle(handle: &str) -> String {
+    let (unicode, result) = idna::domain_to_unicode(handle);
+    if result.is_ok() {
+        unicode
+    } else {
+        handle.to_string()
+    }
+}
+
+/// The other spelling of an internationalized handle, if it has one
+///
+/// Returns the punycode form for Unicode input and the Unicode form for
+/// punycode input, so the UI can show users both.
+pub fn alternate_handle_form(input: &str) -> Option<String> {
+    let ascii = normalize_handle(input).ok()?;
+    let unicode = display_handle(&ascii);
+    if ascii == unicode {
+        return None;
+    }
+    let typed = clean_identifier_input(input);
+    if typed.eq_ignore_ascii_case(&ascii) {
+        Some(unicode)
+    } else {
+        Some(ascii)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalizes_idn_and_trailing_dots() {
+        assert_eq!(
+            normalize_handle("@Alice.BSKY.social.").unwrap(),
+            "alice.bsky.social"
+        );
+        assert_eq!(
+            normalize_handle("bücher.example").unwrap(),
+            "xn--bcher-kva.example"
+        );
+        assert_eq!(
+            normalize_handle("xn--bcher-kva.example").unwrap(),
+            "xn--bcher-kva.example"
+        );
+        // IDN and long gTLDs are fine
+        assert!(is_valid_handle_syntax("alice.xn--p1ai"));
+        assert!(is_valid_handle_syntax("alice.пример.рф"));
+        assert!(is_valid_handle_syntax("alice.photography"));
+
+        assert!(!is_valid_handle_syntax("alice"));
+        assert!(!is_valid_handle_syntax("alice..example"));
+        assert!(!is_valid_handle_syntax("-alice.example"));
+        assert!(!is_valid_handle_syntax("alice_b.example"));
+        assert!(!is_valid_handle_syntax("10.0.0.1"));
+        assert!(!is_valid_handle_syntax("invalid@handle.com"));
+    }
+
+    #[test]
+    fn test_normalizes_pasted_identifiers() {
+        assert_eq!(
+            normalize_identifier(" @alice.bsky.social.. "),
+            "alice.bsky.social"
+        );
+        assert_eq!(
+            normalize_identifier("\u{200B}alice.bsky.social\u{00A0}"),
+            "alice.bsky.social"
+        );
+        assert_eq!(
+            normalize_identifier("\u{FEFF}@ Alice.Example"),
+            "alice.example"
+        );
+        assert_eq!(
+            normalize_identifier("  DID:PLC:ABCDEF234567  "),
+            "did:plc:abcdef234567"
+        );
+        assert_eq!(
+            normalize_identifier("did:web:Alice.Example"),
+            "did:web:alice.example"
+        );
+        // Not a handle: cleaned but otherwise left for validation to report
+        assert_eq!(normalize_identifier(" @alice "), "alice");
+        assert_eq!(normalize_did("alice.example"), None);
+        assert_eq!(normalize_did("did:plc:"), None);
+
+        assert_eq!(
+            normalize_pds_url(" PDS.Example.com/ "),
+            "https://pds.example.com"
+        );
+        assert_eq!(
+            normalize_pds_url("https://pds.example.com:2583//"),
+            "https://pds.example.com:2583"
+        );
+        assert_eq!(
+            normalize_pds_url("http://localhost:2583"),
+            "http://localhost:2583"
+        );
+        assert_eq!(normalize_pds_url("  "), "");
+    }
+
+    #[test]
+    fn test_alternate_form_shows_the_other_spelling() {
+        assert_eq!(display_handle("xn--bcher-kva.example"), "bücher.example");
+        assert_eq!(
+            alternate_handle_form("bücher.example").as_deref(),
+            Some("xn--bcher-kva.example")
+        );
+        assert_eq!(
+            alternate_handle_form("xn--bcher-kva.example").as_deref(),
+            Some("bücher.example")
+        );
+        assert_eq!(alternate_handle_form("alice.bsky.social"), None);
+    }
+}
@@ -2,6 +2,7 @@ use crate::console_info;
 use crate::migration::storage::LocalStorageManager;
 use crate::migration::types::PdsDescribeResponse;
 use crate::migration::{MigrationAction, MigrationState};
+use crate::utils::handle::{display_handle, is_valid_handle_syntax};
 use dioxus::prelude::EventHandler;
 
 #[cfg(feature = "web")]
@@ -39,9 +40,13 @@ impl MigrationState {
             }
         } else if original.contains('.') && !original.starts_with("did:") {
             // Handle is a fully qualified domain name (FQDN) resolved via DNS TXT record
-            // Transform torrho.com -> torrho_com.blacksky.app
-            let underscore_handle = original.replace('.', "_");
-            format!("{}{}", underscore_handle, suggested_domain)
+            // Transform torrho.com -> torrho-com.blacksky.app (underscores aren't valid in handles)
+            let prefix = fqdn_to_prefix(original);
+            if is_valid_username_prefix(&prefix) {
+                format!("{}{}", prefix, suggested_domain)
+            } else {
+                format!("your_username{}", suggested_domain)
+            }
         } else {
             // Fallback for other cases (DID, etc.)
             format!("your_username{}", suggested_domain)
@@ -301,19 +306,41 @@ impl MigrationState {
     }
 }
 
+/// Collapse a custom domain into a single handle label, keeping IDN labels readable
+///
+/// `torrho.com` becomes `torrho-com` and `xn--bcher-kva.ch` becomes `bücher-ch`,
+/// which the new PDS receives in punycode once the full handle is normalized.
+fn fqdn_to_prefix(handle: &str) -> String {
+    display_handle(handle.trim_end_matches('.'))
+        .split('.')
+        .filter(|label| !label.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
 /// Validate if a string looks like a valid username prefix
 fn is_valid_username_prefix(prefix: &str) -> bool {
-    if prefix.is_empty() || prefix.len() < 2 || prefix.len() > 50 {
+    let length = prefix.chars().count();
+    if !(2..=50).contains(&length) || prefix.contains('.') {
         return false;
     }
 
-    // Must start with alphanumeric
-    if !prefix.chars().next().unwrap().is_alphanumeric() {
-        return false;
-    }
+    // Must form a valid first handle label; Unicode letters are fine since they
+    // map to punycode, but underscores and other punctuation are not
+    is_valid_handle_syntax(&format!("{}.example", prefix))
+}
 
-    // Allow alphanumeric plus common username characters
-    prefix
-        .chars()
-        .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fqdn_prefixes_are_valid_handle_labels() {
+        assert_eq!(fqdn_to_prefix("torrho.com"), "torrho-com");
+        assert_eq!(fqdn_to_prefix("xn--bcher-kva.ch."), "bücher-ch");
+        assert!(is_valid_username_prefix("torrho-com"));
+        assert!(is_valid_username_prefix("bücher-ch"));
+        assert!(!is_valid_username_prefix("torrho_com"));
+        assert!(!is_valid_username_prefix("-torrho"));
+    }
 }
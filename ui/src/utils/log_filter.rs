@@ -0,0 +1,255 @@
+//! Runtime log levels for the console macros
+//!
+//! Every `console_*!` call asks [`log_enabled`] before it formats anything, so
+//! a disabled level costs a lock and a comparison rather than a `format!` and a
+//! trip into the browser console. The filter is written like `RUST_LOG`: a
+//! default level followed by per-module overrides, e.g.
+//! `info,services::streaming=debug,services::client=warn`. Module paths may
+//! leave out the crate name, and the longest matching override wins.
+//!
+//! `console_debug!` is also rate limited per call site: each line may print
+//! [`set_debug_rate_limit`] messages a second, and the first message of the
+//! next second says how many were dropped. Per-chunk logs in the transfer
+//! loops otherwise flood the console and slow large migrations down.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Mutex, RwLock};
+
+use crate::utils::console_macros::{write_console, ConsoleLevel};
+
+/// Default level plus per-module overrides
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFilter {
+    pub default: ConsoleLevel,
+    /// Module path prefixes and their levels, in the order they were given
+    pub modules: Vec<(String, ConsoleLevel)>,
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        Self::new(ConsoleLevel::Info)
+    }
+}
+
+/// Level names accepted in a filter, least severe first
+pub const LEVEL_NAMES: [&str; 5] = ["debug", "log", "info", "warn", "error"];
+
+fn parse_level(name: &str) -> Result<ConsoleLevel, String> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "debug" | "trace" => Ok(ConsoleLevel::Debug),
+        "log" => Ok(ConsoleLevel::Log),
+        "info" => Ok(ConsoleLevel::Info),
+        "warn" | "warning" => Ok(ConsoleLevel::Warn),
+        "error" => Ok(ConsoleLevel::Error),
+        other => Err(format!(
+            "Unknown log level \"{}\"; use one of {}",
+            other,
+            LEVEL_NAMES.join(", ")
+        )),
+    }
+}
+
+/// Whether `prefix` names `module` or one of its parents
+fn module_matches(prefix: &str, module: &str) -> bool {
+    let matches = |path: &str| {
+        path.strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+    };
+    matches(module)
+        || module
+            .split_once("::")
+            .is_some_and(|(_, path)| matches(path))
+}
+
+impl LogFilter {
+    pub const fn new(default: ConsoleLevel) -> Self {
+        Self {
+            default,
+            modules: Vec::new(),
+        }
+    }
+
+    /// Parse `level` and `module=level` entries separated by commas
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut filter = Self::default();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            match entry.split_once('=') {
+                Some((module, level)) => {
+                    let module = module.trim().trim_matches(':');
+                    if module.is_empty() {
+                        return Err(format!("\"{}\" names no module", entry));
+                    }
+                    filter
+                        .modules
+                        .push((module.to_string(), parse_level(level)?));
+                }
+                None => filter.default = parse_level(entry)?,
+            }
+        }
+        Ok(filter)
+    }
+
+    /// Lowest level printed for `module`
+    pub fn level_for(&self, module: &str) -> ConsoleLevel {
+        self.modules
+            .iter()
+            .filter(|(prefix, _)| module_matches(prefix, module))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    pub fn allows(&self, level: ConsoleLevel, module: &str) -> bool {
+        level >= self.level_for(module)
+    }
+}
+
+impl fmt::Display for LogFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.default.label().to_ascii_lowercase())?;
+        for (module, level) in &self.modules {
+            write!(f, ",{}={}", module, level.label().to_ascii_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+/// Counts debug messages per call site within one-second windows
+#[derive(Debug, Default)]
+pub struct DebugRateLimiter {
+    windows: BTreeMap<&'static str, DebugWindow>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct DebugWindow {
+    started_ms: u64,
+    printed: u32,
+    suppressed: u32,
+}
+
+impl DebugRateLimiter {
+    pub const fn new() -> Self {
+        Self {
+            windows: BTreeMap::new(),
+        }
+    }
+
+    /// `None` drops the message; `Some(n)` prints it, after `n` were dropped
+    pub fn check(&mut self, callsite: &'static str, now_ms: u64, per_second: u32) -> Option<u32> {
+        let window = self.windows.entry(callsite).or_insert(DebugWindow {
+            started_ms: now_ms,
+            printed: 0,
+            suppressed: 0,
+        });
+        let mut dropped = 0;
+        if now_ms.saturating_sub(window.started_ms) >= 1000 {
+            dropped = window.suppressed;
+            *window = DebugWindow {
+                started_ms: now_ms,
+                printed: 0,
+                suppressed: 0,
+            };
+        }
+        if window.printed < per_second {
+            window.printed += 1;
+            Some(dropped)
+        } else {
+            window.suppressed += 1;
+            None
+        }
+    }
+}
+
+static FILTER: RwLock<LogFilter> = RwLock::new(LogFilter::new(ConsoleLevel::Info));
+static DEBUG_LOGS_PER_SECOND: AtomicU32 = AtomicU32::new(20);
+static DEBUG_LIMITER: Mutex<DebugRateLimiter> = Mutex::new(DebugRateLimiter::new());
+
+/// Filter currently applied by the console macros
+pub fn log_filter() -> LogFilter {
+    FILTER.read().map(|f| f.clone()).unwrap_or_default()
+}
+
+pub fn set_log_filter(filter: LogFilter) {
+    if let Ok(mut current) = FILTER.write() {
+        *current = filter;
+    }
+}
+
+/// Debug messages each call site may print per second
+pub fn set_debug_rate_limit(per_second: u32) {
+    DEBUG_LOGS_PER_SECOND.store(per_second.max(1), Ordering::Relaxed);
+}
+
+/// Whether a message at `level` from `module` should be printed
+pub fn log_enabled(level: ConsoleLevel, module: &str) -> bool {
+    FILTER
+        .read()
+        .map(|filter| filter.allows(level, module))
+        .unwrap_or(true)
+}
+
+/// Print a message if the filter lets it through; formatting is skipped otherwise
+pub fn write_filtered(level: ConsoleLevel, module: &str, message: impl FnOnce() -> String) {
+    if log_enabled(level, module) {
+        write_console(level, &message());
+    }
+}
+
+/// [`write_filtered`] for debug messages, rate limited per call site
+pub fn write_debug(callsite: &'static str, module: &str, message: impl FnOnce() -> String) {
+    if !log_enabled(ConsoleLevel::Debug, module) {
+        return;
+    }
+    let now = crate::utils::platform::now_ms() as u64;
+    let per_second = DEBUG_LOGS_PER_SECOND.load(Ordering::Relaxed);
+    let verdict = match DEBUG_LIMITER.lock() {
+        Ok(mut limiter) => limiter.check(callsite, now, per_second),
+        Err(_) => Some(0),
+    };
+    match verdict {
+        Some(0) => write_console(ConsoleLevel::Debug, &message()),
+        Some(dropped) => write_console(
+            ConsoleLevel::Debug,
+            &format!("{} ({} similar messages dropped)", message(), dropped),
+        ),
+        None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_overrides_and_debug_rate_limit() {
+        let filter =
+            LogFilter::parse("warn, services::streaming=debug, ui::services=error").unwrap();
+        assert_eq!(filter.default, ConsoleLevel::Warn);
+        assert!(filter.allows(ConsoleLevel::Debug, "ui::services::streaming::orchestrator"));
+        assert!(!filter.allows(ConsoleLevel::Warn, "ui::services::client::api"));
+        assert!(filter.allows(ConsoleLevel::Warn, "ui::migration::logic"));
+        assert!(!filter.allows(ConsoleLevel::Info, "ui::migration::logic"));
+        // A prefix only matches whole path segments
+        assert!(!filter.allows(ConsoleLevel::Debug, "ui::services::streaming_extra"));
+        assert_eq!(
+            filter.to_string(),
+            "warn,services::streaming=debug,ui::services=error"
+        );
+        assert_eq!(LogFilter::parse(&filter.to_string()), Ok(filter));
+        assert_eq!(LogFilter::parse(""), Ok(LogFilter::default()));
+        assert!(LogFilter::parse("loud").is_err());
+        assert!(LogFilter::parse("=debug").is_err());
+
+        let mut limiter = DebugRateLimiter::new();
+        assert_eq!(limiter.check("a:1", 1_000, 2), Some(0));
+        assert_eq!(limiter.check("a:1", 1_100, 2), Some(0));
+        assert_eq!(limiter.check("a:1", 1_200, 2), None);
+        assert_eq!(limiter.check("a:1", 1_300, 2), None);
+        // Other call sites have their own budget
+        assert_eq!(limiter.check("b:2", 1_300, 2), Some(0));
+        // The next window reports what the last one dropped
+        assert_eq!(limiter.check("a:1", 2_000, 2), Some(2));
+        assert_eq!(limiter.check("a:1", 2_100, 2), Some(0));
+    }
+}
@@ -0,0 +1,232 @@
+//! Spotting handles and domains that only look like what the user expects
+//!
+//! Unicode domains render as ordinary text, so `bsky.sоcial` with a Cyrillic
+//! `о` is indistinguishable from the real thing, and a migration pointed at a
+//! lookalike PDS hands the account to whoever runs it. Wherever a handle or
+//! PDS host is shown, the punycode spelling is disclosed next to the Unicode
+//! one and the checks here flag labels that mix scripts, consist of
+//! characters that imitate Latin letters, or contain emoji. The rules are a
+//! small subset of Unicode TR39 covering the scripts lookalike domains are
+//! actually built from; they warn, they never block.
+
+use crate::utils::handle::display_handle;
+
+/// Scripts whose letters imitate each other
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Script {
+    Latin,
+    Greek,
+    Cyrillic,
+    Armenian,
+    Cherokee,
+}
+
+impl Script {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Script::Latin => "Latin",
+            Script::Greek => "Greek",
+            Script::Cyrillic => "Cyrillic",
+            Script::Armenian => "Armenian",
+            Script::Cherokee => "Cherokee",
+        }
+    }
+
+    fn of(c: char) -> Option<Script> {
+        match c as u32 {
+            0x41..=0x5A | 0x61..=0x7A | 0xC0..=0x24F | 0x1E00..=0x1EFF => Some(Script::Latin),
+            0x370..=0x3FF | 0x1F00..=0x1FFF => Some(Script::Greek),
+            0x400..=0x52F | 0x2DE0..=0x2DFF | 0xA640..=0xA69F => Some(Script::Cyrillic),
+            0x530..=0x58F => Some(Script::Armenian),
+            0x13A0..=0x13FF | 0xAB70..=0xABBF => Some(Script::Cherokee),
+            _ => None,
+        }
+    }
+}
+
+/// Something about a domain the user should look at twice
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DomainWarning {
+    /// One label uses letters from more than one script
+    MixedScripts { label: String, scripts: Vec<Script> },
+    /// Non-Latin letters that spell a Latin-looking name
+    Lookalike { label: String, looks_like: String },
+    /// Emoji render differently, or not at all, across apps and fonts
+    Emoji { label: String },
+}
+
+impl DomainWarning {
+    pub fn message(&self) -> String {
+        match self {
+            DomainWarning::MixedScripts { label, scripts } => format!(
+                "\"{}\" mixes {} letters, a common trick for imitating another domain",
+                label,
+                scripts
+                    .iter()
+                    .map(Script::name)
+                    .collect::<Vec<_>>()
+                    .join(" and ")
+            ),
+            DomainWarning::Lookalike { label, looks_like } => format!(
+                "\"{}\" looks like \"{}\" but is spelled with different characters",
+                label, looks_like
+            ),
+            DomainWarning::Emoji { label } => format!(
+                "\"{}\" contains emoji, which some apps show differently or only as punycode",
+                label
+            ),
+        }
+    }
+}
+
+/// Latin letter a character is commonly mistaken for
+fn latin_lookalike(c: char) -> Option<char> {
+    let latin = match c {
+        // Cyrillic
+        'а' => 'a',
+        'с' => 'c',
+        'ԁ' => 'd',
+        'е' | 'ё' => 'e',
+        'һ' => 'h',
+        'і' | 'ӏ' => 'i',
+        'ј' => 'j',
+        'о' => 'o',
+        'р' => 'p',
+        'ԛ' => 'q',
+        'ѕ' => 's',
+        'ѵ' => 'v',
+        'ԝ' => 'w',
+        'х' => 'x',
+        'у' => 'y',
+        // Greek
+        'α' => 'a',
+        'ι' => 'i',
+        'κ' => 'k',
+        'ν' => 'v',
+        'ο' => 'o',
+        'ρ' => 'p',
+        'υ' => 'u',
+        'χ' => 'x',
+        // Armenian
+        'օ' => 'o',
+        'ս' => 'u',
+        'հ' => 'h',
+        'ո' => 'n',
+        'զ' => 'q',
+        _ => return None,
+    };
+    Some(latin)
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32, 0x2600..=0x27BF | 0x1F000..=0x1FAFF)
+}
+
+/// What to warn about in a handle or host, given in Unicode or punycode
+pub fn domain_warnings(domain: &str) -> Vec<DomainWarning> {
+    let unicode = display_handle(domain);
+    let mut warnings = Vec::new();
+    for label in unicode.split('.').filter(|label| !label.is_ascii()) {
+        let mut scripts: Vec<Script> = label.chars().filter_map(Script::of).collect();
+        scripts.sort();
+        scripts.dedup();
+        if scripts.len() > 1 {
+            warnings.push(DomainWarning::MixedScripts {
+                label: label.to_string(),
+                scripts,
+            });
+        }
+
+        let looks_like: Option<String> = label
+            .chars()
+            .map(|c| {
+                if c.is_ascii() {
+                    Some(c)
+                } else {
+                    latin_lookalike(c)
+                }
+            })
+            .collect();
+        if let Some(looks_like) = looks_like {
+            warnings.push(DomainWarning::Lookalike {
+                label: label.to_string(),
+                looks_like,
+            });
+        }
+
+        if label.chars().any(is_emoji) {
+            warnings.push(DomainWarning::Emoji {
+                label: label.to_string(),
+            });
+        }
+    }
+    warnings
+}
+
+/// Host of a PDS URL as typed, for the same checks as a handle
+pub fn url_host(url: &str) -> Option<String> {
+    let with_scheme = if url.contains("://") {
+        url.trim().to_string()
+    } else {
+        format!("https://{}", url.trim())
+    };
+    reqwest::Url::parse(&with_scheme)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domain_warnings() {
+        assert!(domain_warnings("alice.bsky.social").is_empty());
+        assert!(domain_warnings("bücher.example").is_empty());
+        assert!(domain_warnings("alice.пример.рф").is_empty());
+
+        // Cyrillic "о" in an otherwise Latin label, given as punycode
+        let mixed =
+            domain_warnings(&crate::utils::handle::normalize_handle("bsky.sоcial").unwrap());
+        assert_eq!(
+            mixed,
+            vec![
+                DomainWarning::MixedScripts {
+                    label: "sоcial".to_string(),
+                    scripts: vec![Script::Latin, Script::Cyrillic],
+                },
+                DomainWarning::Lookalike {
+                    label: "sоcial".to_string(),
+                    looks_like: "social".to_string(),
+                },
+            ]
+        );
+
+        assert_eq!(
+            domain_warnings("рауpаl.example")[1].message(),
+            "\"рауpаl\" looks like \"paypal\" but is spelled with different characters"
+        );
+        // Entirely Cyrillic, so not mixed, but it spells a Latin word
+        assert_eq!(
+            domain_warnings("аре.example"),
+            vec![DomainWarning::Lookalike {
+                label: "аре".to_string(),
+                looks_like: "ape".to_string(),
+            }]
+        );
+
+        assert!(matches!(
+            domain_warnings("🦋.example")[..],
+            [DomainWarning::Emoji { .. }]
+        ));
+
+        assert_eq!(
+            url_host("https://xn--sb-0ka.example:2583/xrpc").as_deref(),
+            Some("xn--sb-0ka.example")
+        );
+        assert_eq!(
+            url_host("pds.example.com").as_deref(),
+            Some("pds.example.com")
+        );
+    }
+}
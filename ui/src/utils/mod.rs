@@ -2,19 +2,33 @@
 //!
 //! This module provides utility functions and macros used throughout the application:
 //!
+//! - **byte_format**: Sizes and transfer rates in binary or decimal units for display
 //! - **console_macros**: WASM-compatible logging macros for browser console output
+//! - **handle**: Handle normalization (IDN/punycode, trailing dots) and syntax checks
 //! - **handle_suggestions**: ATProto handle validation and suggestion utilities
-//! - **platform**: Platform detection and WASM environment helpers
+//! - **log_filter**: Runtime log levels, per-module overrides and debug rate limiting
+//! - **lookalike**: Mixed-script, lookalike and emoji warnings for displayed domains
+//! - **qr**: Minimal QR code encoder for showing payloads to another device's camera
+//! - **platform**: Platform detection plus time, random and storage access with non-WASM fallbacks
+//! - **progress_text**: Progress as sentences for live regions and the text-only display
 //! - **serialization**: JSON serialization utilities for WASM compatibility
+//! - **time_format**: Durations, relative times and local wall-clock times for display
 //! - **validation**: Form validation and data validation utilities
 //!
 //! These utilities are designed to work consistently across server-side and WASM
 //! deployment targets.
 
+pub mod byte_format;
 pub mod console_macros;
+pub mod handle;
 pub mod handle_suggestions;
+pub mod log_filter;
+pub mod lookalike;
 pub mod platform;
+pub mod progress_text;
+pub mod qr;
 pub mod serialization;
+pub mod time_format;
 pub mod validation;
 
 pub use platform::*;
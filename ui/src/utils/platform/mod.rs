@@ -8,10 +8,54 @@
 //! - Mobile/desktop platform detection  
 //! - PWA/Home Screen App detection
 //! - Storage persistence detection
+//! - Time, randomness and storage access with non-WASM fallbacks (`time`, `random`,
+//!   `storage`) so components can be prerendered server-side without a JS runtime
+
+pub mod random;
+pub mod storage;
+pub mod time;
+
+pub use random::*;
+pub use storage::*;
+pub use time::*;
 
 use crate::{console_debug, console_warn};
 use web_sys::window;
 
+/// Whether a browser `window` is available
+///
+/// Always false outside WASM: calling into `web_sys`/`js_sys` there panics, so every
+/// browser probe in this module checks this first and falls back to a neutral answer.
+pub fn has_browser_window() -> bool {
+    #[cfg(target_arch = "wasm32")]
+    {
+        window().is_some()
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        false
+    }
+}
+
+/// Current page URL, or `None` outside a browser
+pub fn current_page_url() -> Option<String> {
+    if !has_browser_window() {
+        return None;
+    }
+    window().and_then(|w| w.location().href().ok())
+}
+
+/// Current URL fragment without the leading `#`, if any
+pub fn current_url_fragment() -> Option<String> {
+    if !has_browser_window() {
+        return None;
+    }
+    window()
+        .and_then(|w| w.location().hash().ok())
+        .map(|hash| hash.trim_start_matches('#').to_string())
+        .filter(|fragment| !fragment.is_empty())
+}
+
 /// Supported browser types with specific storage characteristics
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum BrowserType {
@@ -52,6 +96,10 @@ pub enum AppInstallState {
 
 /// Detect the current browser type based on user agent
 pub fn detect_browser() -> BrowserType {
+    if !has_browser_window() {
+        return BrowserType::Unknown;
+    }
+
     let user_agent = window()
         .and_then(|w| w.navigator().user_agent().ok())
         .unwrap_or_default();
@@ -72,6 +120,10 @@ pub fn detect_browser() -> BrowserType {
 
 /// Check if running on a mobile platform
 pub fn is_mobile_platform() -> bool {
+    if !has_browser_window() {
+        return false;
+    }
+
     // Check if mobile device using user agent
     let is_mobile = js_sys::eval(
         r#"/iPhone|iPad|iPod|Android|webOS|BlackBerry|IEMobile|Opera Mini/i.test(navigator.userAgent)"#
@@ -86,6 +138,10 @@ pub fn is_mobile_platform() -> bool {
 
 /// Check if running as a PWA/Home Screen App
 pub fn is_home_screen_app() -> bool {
+    if !has_browser_window() {
+        return false;
+    }
+
     // Check if running in standalone mode (PWA)
     // This works for both iOS home screen apps and installed PWAs
     let is_pwa = js_sys::eval(
@@ -103,6 +159,10 @@ pub fn is_home_screen_app() -> bool {
 
 /// Get comprehensive app installation state
 pub fn get_app_install_state() -> AppInstallState {
+    if !has_browser_window() {
+        return AppInstallState::BrowserTab;
+    }
+
     let is_pwa = is_home_screen_app();
 
     // Check if there's a UTM parameter indicating installation
@@ -228,4 +288,35 @@ mod tests {
         assert!(min >= 128 * 1024 * 1024); // At least 128MB
         assert!(max <= 4096 * 1024 * 1024); // No more than 4GB (WASM32 limit)
     }
+
+    #[test]
+    fn test_non_wasm_fallbacks() {
+        assert!(!has_browser_window());
+        assert_eq!(detect_browser(), BrowserType::Unknown);
+        assert_eq!(get_app_install_state(), AppInstallState::BrowserTab);
+
+        assert!(now_secs() > 1_600_000_000);
+        assert_eq!(now_iso8601().len(), "2024-01-01T00:00:00.000Z".len());
+
+        let value = random_f64();
+        assert!((0.0..1.0).contains(&value));
+        assert_eq!(random_hex(16).len(), 32);
+
+        storage_set(StorageArea::Session, "platform_test", vec![1, 2, 3]).unwrap();
+        let stored: Vec<u8> = storage_get(StorageArea::Session, "platform_test").unwrap();
+        assert_eq!(stored, vec![1, 2, 3]);
+        assert!(storage_get::<Vec<u8>>(StorageArea::Local, "platform_test").is_err());
+        storage_delete(StorageArea::Session, "platform_test");
+        assert!(storage_get::<Vec<u8>>(StorageArea::Session, "platform_test").is_err());
+    }
+
+    #[test]
+    fn test_format_iso8601() {
+        assert_eq!(format_iso8601(0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(format_iso8601(951_782_400_123), "2000-02-29T00:00:00.123Z");
+        assert_eq!(
+            format_iso8601(1_718_461_845_678),
+            "2024-06-15T14:30:45.678Z"
+        );
+    }
 }
@@ -0,0 +1,27 @@
+//! Randomness helpers backed by `getrandom`
+//!
+//! `getrandom` uses `crypto.getRandomValues` in the browser and the OS source elsewhere,
+//! so these are safe to call from render code during server-side rendering.
+
+/// Fill the buffer with cryptographically secure random bytes
+pub fn fill_random(buf: &mut [u8]) {
+    if let Err(e) = getrandom::getrandom(buf) {
+        // Only reachable if the platform has no entropy source at all
+        panic!("No secure random source available: {}", e);
+    }
+}
+
+/// Random float in `[0, 1)`, a drop-in for `Math.random()`
+pub fn random_f64() -> f64 {
+    let mut buf = [0u8; 8];
+    fill_random(&mut buf);
+    // Keep the top 53 bits so every value is exactly representable
+    (u64::from_le_bytes(buf) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Random lowercase hex string built from `byte_len` random bytes
+pub fn random_hex(byte_len: usize) -> String {
+    let mut buf = vec![0u8; byte_len];
+    fill_random(&mut buf);
+    buf.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,323 @@
+//! Key/value storage that degrades to an in-memory map outside the browser
+//!
+//! In WASM this wraps `gloo_storage` (`localStorage`/`sessionStorage`). During
+//! server-side rendering and native tests there is no Web Storage, so values are
+//! kept in a process-wide map instead of panicking on a missing `window`.
+//!
+//! With the `desktop` feature the native map stands in for `localStorage` in a
+//! desktop shell: the `Local` area is loaded from and written back to
+//! `local_storage.json` in `desktop_data_dir()`, so PLC progress and the
+//! checklist survive restarts. `Session` stays in memory, as in a tab.
+//!
+//! Access and refresh tokens never reach the file. They stay in memory while
+//! the app runs and are stripped from every value written to disk, so a saved
+//! session no longer deserializes after a restart and the user logs in again.
+
+use gloo_storage::errors::StorageError;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Which Web Storage area a value lives in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StorageArea {
+    /// `localStorage` - survives browser restarts
+    Local,
+    /// `sessionStorage` - cleared when the tab closes
+    Session,
+}
+
+#[cfg(target_arch = "wasm32")]
+mod backend {
+    use super::*;
+    use gloo_storage::{LocalStorage, SessionStorage, Storage};
+
+    pub fn get<T: DeserializeOwned>(area: StorageArea, key: &str) -> Result<T, StorageError> {
+        match area {
+            StorageArea::Local => LocalStorage::get(key),
+            StorageArea::Session => SessionStorage::get(key),
+        }
+    }
+
+    pub fn set<T: Serialize>(area: StorageArea, key: &str, value: T) -> Result<(), StorageError> {
+        match area {
+            StorageArea::Local => LocalStorage::set(key, value),
+            StorageArea::Session => SessionStorage::set(key, value),
+        }
+    }
+
+    pub fn delete(area: StorageArea, key: &str) {
+        match area {
+            StorageArea::Local => LocalStorage::delete(key),
+            StorageArea::Session => SessionStorage::delete(key),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod backend {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::{Mutex, OnceLock};
+
+    type Store = Mutex<HashMap<(StorageArea, String), String>>;
+
+    fn store() -> &'static Store {
+        static STORE: OnceLock<Store> = OnceLock::new();
+        STORE.get_or_init(|| Mutex::new(persist::load()))
+    }
+
+    pub fn get<T: DeserializeOwned>(area: StorageArea, key: &str) -> Result<T, StorageError> {
+        let raw = store()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&(area, key.to_string()))
+            .cloned()
+            .ok_or_else(|| StorageError::KeyNotFound(key.to_string()))?;
+        serde_json::from_str(&raw).map_err(StorageError::SerdeError)
+    }
+
+    pub fn set<T: Serialize>(area: StorageArea, key: &str, value: T) -> Result<(), StorageError> {
+        let raw = serde_json::to_string(&value).map_err(StorageError::SerdeError)?;
+        let mut store = store().lock().unwrap_or_else(|e| e.into_inner());
+        store.insert((area, key.to_string()), raw);
+        if area == StorageArea::Local {
+            persist::save(&store);
+        }
+        Ok(())
+    }
+
+    pub fn delete(area: StorageArea, key: &str) {
+        let mut store = store().lock().unwrap_or_else(|e| e.into_inner());
+        if store.remove(&(area, key.to_string())).is_some() && area == StorageArea::Local {
+            persist::save(&store);
+        }
+    }
+}
+
+/// Writes the `Local` area to disk for the desktop app
+#[cfg(all(not(target_arch = "wasm32"), feature = "desktop"))]
+mod persist {
+    use super::*;
+    use std::collections::HashMap;
+
+    pub fn load() -> HashMap<(StorageArea, String), String> {
+        desktop_data_dir()
+            .map(|dir| file::load(&dir.join(file::LOCAL_STORAGE_FILE)))
+            .unwrap_or_default()
+    }
+
+    pub fn save(store: &HashMap<(StorageArea, String), String>) {
+        let Some(dir) = desktop_data_dir() else {
+            return;
+        };
+        if let Err(e) = file::save(&dir.join(file::LOCAL_STORAGE_FILE), store) {
+            tracing::warn!("Could not save local storage to {}: {}", dir.display(), e);
+        }
+    }
+}
+
+/// Browsers keep the data themselves; native builds without `desktop` forget it
+#[cfg(all(not(target_arch = "wasm32"), not(feature = "desktop")))]
+mod persist {
+    use super::*;
+    use std::collections::HashMap;
+
+    pub fn load() -> HashMap<(StorageArea, String), String> {
+        HashMap::new()
+    }
+
+    pub fn save(_store: &HashMap<(StorageArea, String), String>) {}
+}
+
+/// `Local` entries as one JSON object of key to serialized value
+#[cfg(all(not(target_arch = "wasm32"), any(test, feature = "desktop")))]
+mod file {
+    use super::*;
+    use std::collections::{BTreeMap, HashMap};
+    use std::path::Path;
+
+    pub const LOCAL_STORAGE_FILE: &str = "local_storage.json";
+
+    /// Session fields that hold bearer tokens, in both naming styles used
+    const CREDENTIAL_FIELDS: &[&str] = &["accessJwt", "refreshJwt", "access_jwt", "refresh_jwt"];
+
+    /// Drop credential fields anywhere in a value, including JSON stored as a
+    /// string (sessions are kept as serialized strings). Returns whether
+    /// anything was removed.
+    fn strip_credentials(value: &mut serde_json::Value) -> bool {
+        use serde_json::Value;
+        match value {
+            Value::Object(map) => {
+                let mut stripped = false;
+                for field in CREDENTIAL_FIELDS {
+                    stripped |= map.remove(*field).is_some();
+                }
+                for nested in map.values_mut() {
+                    stripped |= strip_credentials(nested);
+                }
+                stripped
+            }
+            Value::Array(items) => items
+                .iter_mut()
+                .fold(false, |stripped, item| strip_credentials(item) | stripped),
+            Value::String(raw) => {
+                let Ok(mut inner) = serde_json::from_str::<Value>(raw) else {
+                    return false;
+                };
+                if !strip_credentials(&mut inner) {
+                    return false;
+                }
+                *raw = inner.to_string();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// A stored value as it may be written to disk
+    pub fn without_credentials(raw: &str) -> String {
+        let Ok(mut value) = serde_json::from_str::<serde_json::Value>(raw) else {
+            return raw.to_string();
+        };
+        if strip_credentials(&mut value) {
+            value.to_string()
+        } else {
+            raw.to_string()
+        }
+    }
+
+    pub fn load(path: &Path) -> HashMap<(StorageArea, String), String> {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|raw| serde_json::from_str::<BTreeMap<String, String>>(&raw).ok())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(key, value)| ((StorageArea::Local, key), value))
+            .collect()
+    }
+
+    /// Replace the file through a temporary one so a crash never leaves half of it
+    pub fn save(
+        path: &Path,
+        store: &HashMap<(StorageArea, String), String>,
+    ) -> std::io::Result<()> {
+        let local: BTreeMap<&str, String> = store
+            .iter()
+            .filter(|((area, _), _)| *area == StorageArea::Local)
+            .map(|((_, key), value)| (key.as_str(), without_credentials(value)))
+            .collect();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let temporary = path.with_extension("json.tmp");
+        std::fs::write(&temporary, serde_json::to_vec_pretty(&local)?)?;
+        std::fs::rename(&temporary, path)
+    }
+}
+
+/// Directory the desktop app keeps its data in
+///
+/// `TEKTITE_DATA_DIR` if set, otherwise the platform's per-user data directory
+/// (`$XDG_DATA_HOME` or `~/.local/share`, `~/Library/Application Support`,
+/// `%APPDATA%`) with a `tektite` subdirectory.
+#[cfg(all(not(target_arch = "wasm32"), feature = "desktop"))]
+pub fn desktop_data_dir() -> Option<std::path::PathBuf> {
+    use std::env::var_os;
+    use std::path::PathBuf;
+
+    if let Some(dir) = var_os("TEKTITE_DATA_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    let base = if cfg!(target_os = "windows") {
+        var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+    };
+    base.map(|dir| dir.join("tektite"))
+}
+
+/// Read and deserialize a value
+pub fn storage_get<T: DeserializeOwned>(area: StorageArea, key: &str) -> Result<T, StorageError> {
+    backend::get(area, key)
+}
+
+/// Serialize and store a value
+pub fn storage_set<T: Serialize>(
+    area: StorageArea,
+    key: &str,
+    value: T,
+) -> Result<(), StorageError> {
+    backend::set(area, key, value)
+}
+
+/// Remove a value; missing keys are ignored
+pub fn storage_delete(area: StorageArea, key: &str) {
+    backend::delete(area, key)
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_local_area_file_round_trip() {
+        let dir = std::env::temp_dir().join(format!("tektite-storage-{}", std::process::id()));
+        let path = dir.join(file::LOCAL_STORAGE_FILE);
+        let mut store = HashMap::new();
+        store.insert(
+            (StorageArea::Local, "new_pds_session".to_string()),
+            "{\"did\":\"did:plc:abc\"}".to_string(),
+        );
+        store.insert(
+            (StorageArea::Session, "captcha_state".to_string()),
+            "\"abc\"".to_string(),
+        );
+
+        file::save(&path, &store).unwrap();
+        let loaded = file::load(&path);
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(
+            loaded.get(&(StorageArea::Local, "new_pds_session".to_string())),
+            store.get(&(StorageArea::Local, "new_pds_session".to_string()))
+        );
+        assert!(file::load(&dir.join("missing.json")).is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_tokens_never_reach_the_file() {
+        let dir = std::env::temp_dir().join(format!("tektite-tokens-{}", std::process::id()));
+        let path = dir.join(file::LOCAL_STORAGE_FILE);
+        let session = serde_json::json!({
+            "did": "did:plc:abc",
+            "accessJwt": "secret-access",
+            "refreshJwt": "secret-refresh",
+        });
+        let mut store = HashMap::new();
+        // Sessions are stored as a JSON string inside the value
+        store.insert(
+            (StorageArea::Local, "old_pds_session".to_string()),
+            serde_json::to_string(&session.to_string()).unwrap(),
+        );
+        store.insert(
+            (StorageArea::Local, "new_pds_session".to_string()),
+            r#"{"did":"did:plc:abc","access_jwt":"secret-access","refresh_jwt":"secret-refresh"}"#
+                .to_string(),
+        );
+
+        file::save(&path, &store).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(!written.contains("secret"));
+        assert!(written.contains("did:plc:abc"));
+        let loaded = file::load(&path);
+        let old: String =
+            serde_json::from_str(&loaded[&(StorageArea::Local, "old_pds_session".to_string())])
+                .unwrap();
+        assert_eq!(old, r#"{"did":"did:plc:abc"}"#);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
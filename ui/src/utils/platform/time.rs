@@ -0,0 +1,85 @@
+//! Wall-clock helpers that work in the browser, the desktop shell and during
+//! server-side rendering
+//!
+//! In WASM these read `js_sys::Date`; elsewhere they fall back to `SystemTime` so
+//! components and reducers that stamp events can run without a JS runtime.
+
+/// Milliseconds since the Unix epoch
+#[cfg(target_arch = "wasm32")]
+pub fn now_ms() -> f64 {
+    js_sys::Date::now()
+}
+
+/// Milliseconds since the Unix epoch, whole like `Date.now()`
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now_ms() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as f64)
+        .unwrap_or(0.0)
+}
+
+/// Wait `delay_ms` milliseconds
+///
+/// gloo-timers schedules through `setTimeout`, which panics without a JS
+/// runtime, so every timer goes through here: gloo-timers in WASM, tokio in the
+/// desktop build and tests.
+pub async fn sleep_ms(delay_ms: u64) {
+    #[cfg(target_arch = "wasm32")]
+    gloo_timers::future::TimeoutFuture::new(delay_ms.min(u32::MAX as u64) as u32).await;
+    #[cfg(not(target_arch = "wasm32"))]
+    tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
+}
+
+/// Whole seconds since the Unix epoch
+pub fn now_secs() -> u64 {
+    (now_ms() / 1000.0) as u64
+}
+
+/// Current time as an ISO 8601 UTC string, e.g. `2024-05-01T12:34:56.789Z`
+#[cfg(target_arch = "wasm32")]
+pub fn now_iso8601() -> String {
+    js_sys::Date::new_0()
+        .to_iso_string()
+        .as_string()
+        .unwrap_or_default()
+}
+
+/// Current time as an ISO 8601 UTC string, e.g. `2024-05-01T12:34:56.789Z`
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now_iso8601() -> String {
+    format_iso8601(now_ms() as u64)
+}
+
+/// Format epoch milliseconds the same way `Date.prototype.toISOString` does
+pub fn format_iso8601(epoch_ms: u64) -> String {
+    let secs = epoch_ms / 1000;
+    let millis = epoch_ms % 1000;
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let time_of_day = secs % 86_400;
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        time_of_day / 3600,
+        (time_of_day % 3600) / 60,
+        time_of_day % 60,
+        millis
+    )
+}
+
+/// Convert days since 1970-01-01 to a (year, month, day) civil date
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
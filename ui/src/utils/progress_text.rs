@@ -0,0 +1,86 @@
+//! Progress as words, for screen readers and the text-only progress display
+//!
+//! Progress bars say nothing to a screen reader, and a live region that
+//! repeated every tick would talk over everything else on the page. The live
+//! region text therefore only changes when the transfer crosses another
+//! [`ANNOUNCE_STEP_PERCENT`], while the text-only display, which is read on
+//! demand, spells out the exact counts.
+
+use crate::utils::byte_format::format_size;
+
+/// Granularity of polite progress announcements
+pub const ANNOUNCE_STEP_PERCENT: u64 = 10;
+
+/// Text for a polite live region, stable between announcement steps
+pub fn progress_announcement(what: &str, processed: u64, total: u64) -> String {
+    if total == 0 {
+        format!("{} starting", what)
+    } else if processed >= total {
+        format!("{} complete", what)
+    } else {
+        let step = processed * 100 / total / ANNOUNCE_STEP_PERCENT * ANNOUNCE_STEP_PERCENT;
+        format!("{} {}% complete", what, step)
+    }
+}
+
+/// Exact progress as one sentence, e.g. "120 of 300 blobs transferred (40.0%), 12.00 MB of 30.00 MB"
+pub fn progress_sentence(
+    items: &str,
+    processed: u64,
+    total: u64,
+    processed_bytes: u64,
+    total_bytes: u64,
+) -> String {
+    if total == 0 {
+        return format!("Counting {}…", items);
+    }
+    let percent = processed as f64 / total as f64 * 100.0;
+    let mut sentence = format!(
+        "{} of {} {} transferred ({:.1}%)",
+        processed, total, items, percent
+    );
+    if total_bytes > 0 {
+        sentence.push_str(&format!(
+            ", {} of {}",
+            format_size(processed_bytes),
+            format_size(total_bytes)
+        ));
+    }
+    sentence
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_announcements_only_change_per_step() {
+        assert_eq!(
+            progress_announcement("Blob transfer", 0, 0),
+            "Blob transfer starting"
+        );
+        assert_eq!(
+            progress_announcement("Blob transfer", 120, 300),
+            "Blob transfer 40% complete"
+        );
+        assert_eq!(
+            progress_announcement("Blob transfer", 120, 300),
+            progress_announcement("Blob transfer", 149, 300)
+        );
+        assert_eq!(
+            progress_announcement("Blob transfer", 299, 300),
+            "Blob transfer 90% complete"
+        );
+        assert_eq!(
+            progress_announcement("Blob transfer", 300, 300),
+            "Blob transfer complete"
+        );
+
+        assert_eq!(progress_sentence("blobs", 0, 0, 0, 0), "Counting blobs…");
+        assert_eq!(
+            progress_sentence("blobs", 120, 300, 0, 0),
+            "120 of 300 blobs transferred (40.0%)"
+        );
+        assert!(progress_sentence("blobs", 1, 2, 1024, 2048).ends_with(", 1.00 KiB of 2.00 KiB"));
+    }
+}
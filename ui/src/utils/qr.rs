@@ -0,0 +1,557 @@
+//! Minimal QR code encoder for showing short payloads on screen
+//!
+//! Encodes bytes in byte mode at error correction level L, picking the smallest
+//! version (1-40) that fits and the mask with the lowest penalty score. That is
+//! all a screen-to-camera transfer needs; level L keeps the symbol as small as
+//! possible since a screen doesn't get scratched.
+
+use base64::Engine;
+
+/// Error correction codewords per block at level L, indexed by version
+const ECC_CODEWORDS_PER_BLOCK: [usize; 41] = [
+    0, 7, 10, 15, 20, 26, 18, 20, 24, 30, 18, 20, 24, 26, 30, 22, 24, 28, 30, 28, 28, 28, 28, 30,
+    30, 26, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+];
+
+/// Error correction blocks at level L, indexed by version
+const ECC_BLOCKS: [usize; 41] = [
+    0, 1, 1, 1, 1, 1, 2, 2, 2, 2, 4, 4, 4, 4, 4, 6, 6, 6, 6, 7, 8, 8, 9, 9, 10, 12, 12, 12, 13, 14,
+    15, 16, 17, 18, 19, 19, 20, 21, 22, 24, 25,
+];
+
+/// Format information bits identifying level L
+const ECL_L_FORMAT_BITS: u32 = 1;
+
+/// Light modules around the symbol that scanners need to find it
+const QUIET_ZONE: usize = 4;
+
+/// An encoded symbol; `true` modules are dark
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QrCode {
+    version: usize,
+    size: usize,
+    mask: u8,
+    modules: Vec<bool>,
+    is_function: Vec<bool>,
+}
+
+impl QrCode {
+    /// Encode bytes, or `None` if they exceed version 40 at level L (2953 bytes)
+    pub fn encode(data: &[u8]) -> Option<Self> {
+        let version = (1..=40).find(|&v| data_capacity_bits(v) >= segment_bits(v, data.len()))?;
+        let codewords = add_ecc_and_interleave(version, &data_codewords(version, data));
+
+        let mut qr = Self::blank(version);
+        qr.draw_function_patterns();
+        qr.draw_codewords(&codewords);
+
+        let mut best: Option<(u32, QrCode)> = None;
+        for mask in 0..8 {
+            let mut candidate = qr.clone();
+            candidate.apply_mask(mask);
+            candidate.draw_format_bits(mask);
+            let penalty = candidate.penalty();
+            if best.as_ref().is_none_or(|(lowest, _)| penalty < *lowest) {
+                best = Some((penalty, candidate));
+            }
+        }
+        best.map(|(_, qr)| qr)
+    }
+
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    /// Modules per side, without the quiet zone
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn mask(&self) -> u8 {
+        self.mask
+    }
+
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+
+    /// SVG image of the symbol with its quiet zone, one unit per module
+    pub fn to_svg(&self) -> String {
+        let extent = self.size + 2 * QUIET_ZONE;
+        let mut path = String::new();
+        for y in 0..self.size {
+            for x in 0..self.size {
+                if self.is_dark(x, y) {
+                    path.push_str(&format!("M{},{}h1v1h-1z", x + QUIET_ZONE, y + QUIET_ZONE));
+                }
+            }
+        }
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {extent} {extent}\" shape-rendering=\"crispEdges\"><rect width=\"{extent}\" height=\"{extent}\" fill=\"#fff\"/><path d=\"{path}\" fill=\"#000\"/></svg>"
+        )
+    }
+
+    /// The SVG as a `data:` URL for an `img` element
+    pub fn to_svg_data_url(&self) -> String {
+        format!(
+            "data:image/svg+xml;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(self.to_svg())
+        )
+    }
+
+    fn blank(version: usize) -> Self {
+        let size = version * 4 + 17;
+        Self {
+            version,
+            size,
+            mask: 0,
+            modules: vec![false; size * size],
+            is_function: vec![false; size * size],
+        }
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        let index = y * self.size + x;
+        self.modules[index] = dark;
+        self.is_function[index] = true;
+    }
+
+    fn draw_function_patterns(&mut self) {
+        let size = self.size;
+        for i in 0..size {
+            self.set_function(6, i, i % 2 == 0);
+            self.set_function(i, 6, i % 2 == 0);
+        }
+
+        for (cx, cy) in [(3, 3), (size - 4, 3), (3, size - 4)] {
+            self.draw_finder(cx, cy);
+        }
+
+        let positions = alignment_positions(self.version);
+        let last = positions.len().saturating_sub(1);
+        for (i, &cx) in positions.iter().enumerate() {
+            for (j, &cy) in positions.iter().enumerate() {
+                // Skip the three corners taken by finder patterns
+                let corner = (i == 0 || i == last) && (j == 0 || j == last);
+                if corner && !(i == last && j == last) {
+                    continue;
+                }
+                for dy in -2i32..=2 {
+                    for dx in -2i32..=2 {
+                        let x = (cx as i32 + dx) as usize;
+                        let y = (cy as i32 + dy) as usize;
+                        self.set_function(x, y, dx.abs().max(dy.abs()) != 1);
+                    }
+                }
+            }
+        }
+
+        // Reserve the format areas; the real bits go in once the mask is chosen
+        self.draw_format_bits(0);
+        self.draw_version_bits();
+    }
+
+    fn draw_finder(&mut self, cx: usize, cy: usize) {
+        for dy in -4i32..=4 {
+            for dx in -4i32..=4 {
+                let x = cx as i32 + dx;
+                let y = cy as i32 + dy;
+                if (0..self.size as i32).contains(&x) && (0..self.size as i32).contains(&y) {
+                    let distance = dx.abs().max(dy.abs());
+                    self.set_function(x as usize, y as usize, distance != 2 && distance != 4);
+                }
+            }
+        }
+    }
+
+    fn draw_format_bits(&mut self, mask: u8) {
+        let bits = format_bits(mask);
+        let bit = |i: u32| (bits >> i) & 1 != 0;
+        let size = self.size;
+
+        for i in 0..=5 {
+            self.set_function(8, i, bit(i as u32));
+        }
+        self.set_function(8, 7, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function(14 - i, 8, bit(i as u32));
+        }
+
+        for i in 0..8 {
+            self.set_function(size - 1 - i, 8, bit(i as u32));
+        }
+        for i in 8..15 {
+            self.set_function(8, size - 15 + i, bit(i as u32));
+        }
+        self.set_function(8, size - 8, true);
+    }
+
+    fn draw_version_bits(&mut self) {
+        if self.version < 7 {
+            return;
+        }
+        let mut remainder = self.version as u32;
+        for _ in 0..12 {
+            remainder = (remainder << 1) ^ ((remainder >> 11) * 0x1F25);
+        }
+        let bits = (self.version as u32) << 12 | remainder;
+        for i in 0..18 {
+            let dark = (bits >> i) & 1 != 0;
+            let a = self.size - 11 + i % 3;
+            let b = i / 3;
+            self.set_function(a, b, dark);
+            self.set_function(b, a, dark);
+        }
+    }
+
+    fn draw_codewords(&mut self, codewords: &[u8]) {
+        let size = self.size;
+        let total_bits = codewords.len() * 8;
+        let mut bit_index = 0;
+        let mut right = size as i32 - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            for vertical in 0..size {
+                for j in 0..2 {
+                    let x = (right - j) as usize;
+                    let upward = (right + 1) & 2 == 0;
+                    let y = if upward {
+                        size - 1 - vertical
+                    } else {
+                        vertical
+                    };
+                    let index = y * size + x;
+                    if !self.is_function[index] && bit_index < total_bits {
+                        self.modules[index] =
+                            (codewords[bit_index >> 3] >> (7 - (bit_index & 7))) & 1 != 0;
+                        bit_index += 1;
+                    }
+                }
+            }
+            right -= 2;
+        }
+    }
+
+    fn apply_mask(&mut self, mask: u8) {
+        self.mask = mask;
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                let index = y * self.size + x;
+                if invert && !self.is_function[index] {
+                    self.modules[index] = !self.modules[index];
+                }
+            }
+        }
+    }
+
+    /// Penalty score from the specification; lower scans more reliably
+    fn penalty(&self) -> u32 {
+        let size = self.size;
+        let mut penalty = 0;
+        let finder_like: [bool; 11] = [
+            true, false, true, true, true, false, true, false, false, false, false,
+        ];
+
+        for horizontal in [true, false] {
+            for a in 0..size {
+                let line: Vec<bool> = (0..size)
+                    .map(|b| {
+                        if horizontal {
+                            self.is_dark(b, a)
+                        } else {
+                            self.is_dark(a, b)
+                        }
+                    })
+                    .collect();
+
+                let mut run = 1;
+                for b in 1..=size {
+                    if b < size && line[b] == line[b - 1] {
+                        run += 1;
+                        continue;
+                    }
+                    if run >= 5 {
+                        penalty += 3 + (run as u32 - 5);
+                    }
+                    run = 1;
+                }
+
+                for window in line.windows(finder_like.len()) {
+                    if window == finder_like || window.iter().rev().eq(finder_like.iter()) {
+                        penalty += 40;
+                    }
+                }
+            }
+        }
+
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let color = self.is_dark(x, y);
+                if color == self.is_dark(x + 1, y)
+                    && color == self.is_dark(x, y + 1)
+                    && color == self.is_dark(x + 1, y + 1)
+                {
+                    penalty += 3;
+                }
+            }
+        }
+
+        let total = (size * size) as i64;
+        let dark = self.modules.iter().filter(|dark| **dark).count() as i64;
+        let k = ((dark * 20 - total * 10).abs() + total - 1) / total - 1;
+        penalty + 10 * k as u32
+    }
+}
+
+fn format_bits(mask: u8) -> u32 {
+    let data = ECL_L_FORMAT_BITS << 3 | mask as u32;
+    let mut remainder = data;
+    for _ in 0..10 {
+        remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+    }
+    (data << 10 | remainder) ^ 0x5412
+}
+
+fn alignment_positions(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return Vec::new();
+    }
+    let count = version / 7 + 2;
+    let step = if version == 32 {
+        26
+    } else {
+        (version * 4 + count * 2 + 1) / (count * 2 - 2) * 2
+    };
+    let mut positions = vec![6];
+    let mut position = version * 4 + 17 - 7;
+    for _ in 0..count - 1 {
+        positions.insert(1, position);
+        position -= step;
+    }
+    positions
+}
+
+fn raw_data_modules(version: usize) -> usize {
+    let mut result = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let alignments = version / 7 + 2;
+        result -= (25 * alignments - 10) * alignments - 55;
+        if version >= 7 {
+            result -= 36;
+        }
+    }
+    result
+}
+
+fn data_codewords_len(version: usize) -> usize {
+    raw_data_modules(version) / 8 - ECC_CODEWORDS_PER_BLOCK[version] * ECC_BLOCKS[version]
+}
+
+fn data_capacity_bits(version: usize) -> usize {
+    data_codewords_len(version) * 8
+}
+
+/// Bits taken by a byte-mode segment: mode indicator, length and data
+fn segment_bits(version: usize, len: usize) -> usize {
+    let count_bits = if version <= 9 { 8 } else { 16 };
+    if len >= 1 << count_bits {
+        return usize::MAX;
+    }
+    4 + count_bits + len * 8
+}
+
+fn data_codewords(version: usize, data: &[u8]) -> Vec<u8> {
+    let mut bits: Vec<bool> = Vec::new();
+    let mut push = |value: u32, count: usize| {
+        for i in (0..count).rev() {
+            bits.push((value >> i) & 1 != 0);
+        }
+    };
+    push(0b0100, 4);
+    push(data.len() as u32, if version <= 9 { 8 } else { 16 });
+    for byte in data {
+        push(*byte as u32, 8);
+    }
+
+    let capacity = data_capacity_bits(version);
+    let terminator = (capacity - bits.len()).min(4);
+    bits.extend(std::iter::repeat_n(false, terminator));
+    let padding = (8 - bits.len() % 8) % 8;
+    bits.extend(std::iter::repeat_n(false, padding));
+
+    let mut codewords: Vec<u8> = bits
+        .chunks(8)
+        .map(|byte| byte.iter().fold(0u8, |acc, bit| acc << 1 | *bit as u8))
+        .collect();
+    for pad in [0xEC, 0x11].into_iter().cycle() {
+        if codewords.len() >= data_codewords_len(version) {
+            break;
+        }
+        codewords.push(pad);
+    }
+    codewords
+}
+
+fn add_ecc_and_interleave(version: usize, data: &[u8]) -> Vec<u8> {
+    let blocks = ECC_BLOCKS[version];
+    let ecc_len = ECC_CODEWORDS_PER_BLOCK[version];
+    let raw_codewords = raw_data_modules(version) / 8;
+    let short_blocks = blocks - raw_codewords % blocks;
+    let short_block_len = raw_codewords / blocks;
+
+    let divisor = reed_solomon_divisor(ecc_len);
+    let mut split: Vec<Vec<u8>> = Vec::with_capacity(blocks);
+    let mut offset = 0;
+    for i in 0..blocks {
+        let data_len = short_block_len - ecc_len + usize::from(i >= short_blocks);
+        let mut block = data[offset..offset + data_len].to_vec();
+        offset += data_len;
+        let ecc = reed_solomon_remainder(&block, &divisor);
+        if i < short_blocks {
+            // Placeholder so every block has the same length while interleaving
+            block.push(0);
+        }
+        block.extend(ecc);
+        split.push(block);
+    }
+
+    let mut result = Vec::with_capacity(raw_codewords);
+    for i in 0..=short_block_len {
+        for (j, block) in split.iter().enumerate() {
+            if i != short_block_len - ecc_len || j >= short_blocks {
+                result.push(block[i]);
+            }
+        }
+    }
+    result
+}
+
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut z: u32 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x11D);
+        z ^= ((y as u32 >> i) & 1) * x as u32;
+    }
+    z as u8
+}
+
+fn reed_solomon_divisor(degree: usize) -> Vec<u8> {
+    let mut result = vec![0u8; degree];
+    result[degree - 1] = 1;
+    let mut root = 1u8;
+    for _ in 0..degree {
+        for j in 0..degree {
+            result[j] = gf_multiply(result[j], root);
+            if j + 1 < degree {
+                result[j] ^= result[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    result
+}
+
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut result = vec![0u8; divisor.len()];
+    for byte in data {
+        let factor = byte ^ result.remove(0);
+        result.push(0);
+        for (coefficient, remainder) in divisor.iter().zip(result.iter_mut()) {
+            *remainder ^= gf_multiply(*coefficient, factor);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capacity_matches_specification() {
+        // Byte-mode capacities at level L from the QR specification
+        for (version, bytes) in [(1, 17), (2, 32), (10, 271), (25, 1273), (40, 2953)] {
+            let fits = |len| data_capacity_bits(version) >= segment_bits(version, len);
+            assert!(
+                fits(bytes),
+                "version {} should hold {} bytes",
+                version,
+                bytes
+            );
+            assert!(!fits(bytes + 1), "version {} holds only {}", version, bytes);
+        }
+        assert_eq!(QrCode::encode(b"hello").unwrap().version(), 1);
+        assert_eq!(QrCode::encode(&[b'a'; 2953]).unwrap().version(), 40);
+        assert!(QrCode::encode(&[b'a'; 2954]).is_none());
+    }
+
+    #[test]
+    fn test_reed_solomon_codewords_are_divisible_by_generator() {
+        let data: Vec<u8> = (0..19u8).map(|i| i.wrapping_mul(37)).collect();
+        let divisor = reed_solomon_divisor(7);
+        let mut codeword = data.clone();
+        codeword.extend(reed_solomon_remainder(&data, &divisor));
+
+        // Every root of the generator, α^0..α^6, is a root of the codeword
+        let mut root = 1u8;
+        for _ in 0..7 {
+            let value = codeword
+                .iter()
+                .fold(0u8, |acc, byte| gf_multiply(acc, root) ^ byte);
+            assert_eq!(value, 0);
+            root = gf_multiply(root, 0x02);
+        }
+    }
+
+    #[test]
+    fn test_symbol_layout() {
+        let qr = QrCode::encode(&[b'x'; 400]).unwrap();
+        assert_eq!(qr.version(), 13);
+        assert_eq!(qr.size(), 69);
+
+        // Finder pattern in each of the three corners, with its light separator
+        let size = qr.size();
+        for (x0, y0) in [(0, 0), (size - 7, 0), (0, size - 7)] {
+            assert!(qr.is_dark(x0, y0) && qr.is_dark(x0 + 6, y0 + 6));
+            assert!(!qr.is_dark(x0 + 1, y0 + 1) && qr.is_dark(x0 + 3, y0 + 3));
+        }
+        assert!(!qr.is_dark(7, 7));
+        assert!(qr.is_dark(8, size - 8));
+
+        // Format bits read back as level L with the chosen mask
+        let read: u32 = (0..15).fold(0, |bits, i| {
+            let (x, y) = match i {
+                0..=5 => (8, i),
+                6 => (8, 7),
+                7 => (8, 8),
+                8 => (7, 8),
+                _ => (14 - i, 8),
+            };
+            bits | (qr.is_dark(x, y) as u32) << i
+        });
+        assert_eq!(read, format_bits(qr.mask()));
+        assert_eq!(
+            (read ^ 0x5412) >> 10,
+            ECL_L_FORMAT_BITS << 3 | qr.mask() as u32
+        );
+
+        assert!(qr.to_svg().starts_with("<svg"));
+        assert!(qr
+            .to_svg_data_url()
+            .starts_with("data:image/svg+xml;base64,"));
+    }
+}
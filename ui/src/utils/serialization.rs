@@ -293,8 +293,7 @@ mod chrono {
 
     impl DateTime {
         pub fn to_rfc3339(&self) -> String {
-            // Use js_sys::Date for WASM32 target
-            format!("{}", js_sys::Date::new_0().to_iso_string())
+            crate::utils::platform::now_iso8601()
         }
     }
 }
@@ -0,0 +1,135 @@
+//! Shared formatting for timestamps and durations shown to the user
+//!
+//! Everything the UI prints about time goes through here: precise durations
+//! for the timeline ("12.3s"), rough ones for estimates ("about 3 hours"),
+//! relative times ("about 5 minutes ago") and wall-clock times in the
+//! browser's time zone. The offset-taking functions are pure so they can be
+//! tested natively; the `local` variants ask the browser for its offset, and
+//! fall back to UTC outside WASM.
+
+use crate::utils::platform::format_iso8601;
+
+/// Format a duration as "850ms", "12.3s" or "4m 05s"
+pub fn format_duration_ms(ms: u64) -> String {
+    if ms < 1000 {
+        format!("{}ms", ms)
+    } else if ms < 60_000 {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    } else {
+        let secs = ms / 1000;
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    }
+}
+
+/// Rough duration, e.g. `about 3 hours` or `about 12 minutes`
+pub fn humanize_duration(secs: u64) -> String {
+    match secs {
+        0..=59 => "under a minute".to_string(),
+        60..=5399 => format!("about {} minutes", secs.div_ceil(60)),
+        5400..=129_599 => format!("about {} hours", (secs as f64 / 3600.0).round() as u64),
+        _ => format!("about {} days", (secs as f64 / 86_400.0).round() as u64),
+    }
+}
+
+/// `at_ms` relative to `now_ms`: "just now", "about 5 minutes ago" or "in about 2 hours"
+pub fn format_relative(at_ms: u64, now_ms: u64) -> String {
+    if at_ms.abs_diff(now_ms) < 60_000 {
+        return if at_ms > now_ms {
+            "in under a minute".to_string()
+        } else {
+            "just now".to_string()
+        };
+    }
+    if at_ms > now_ms {
+        format!("in {}", humanize_duration((at_ms - now_ms) / 1000))
+    } else {
+        format!("{} ago", humanize_duration((now_ms - at_ms) / 1000))
+    }
+}
+
+/// Minutes the browser's time zone is ahead of UTC at `epoch_ms`
+#[cfg(target_arch = "wasm32")]
+pub fn local_utc_offset_minutes(epoch_ms: u64) -> i32 {
+    let date = js_sys::Date::new(&wasm_bindgen::JsValue::from_f64(epoch_ms as f64));
+    -(date.get_timezone_offset() as i32)
+}
+
+/// Minutes the browser's time zone is ahead of UTC; UTC outside the browser
+#[cfg(not(target_arch = "wasm32"))]
+pub fn local_utc_offset_minutes(_epoch_ms: u64) -> i32 {
+    0
+}
+
+/// `UTC`, `UTC+2` or `UTC-5:30`
+pub fn format_utc_offset(offset_minutes: i32) -> String {
+    if offset_minutes == 0 {
+        return "UTC".to_string();
+    }
+    let sign = if offset_minutes > 0 { '+' } else { '-' };
+    let minutes = offset_minutes.unsigned_abs();
+    match minutes % 60 {
+        0 => format!("UTC{}{}", sign, minutes / 60),
+        rest => format!("UTC{}{}:{:02}", sign, minutes / 60, rest),
+    }
+}
+
+/// ISO 8601 digits of `epoch_ms` shifted into a time zone
+fn shifted(epoch_ms: u64, offset_minutes: i32) -> String {
+    let shifted = epoch_ms as i64 + offset_minutes as i64 * 60_000;
+    format_iso8601(shifted.max(0) as u64)
+}
+
+/// `HH:MM:SS` at the given offset from UTC
+pub fn format_clock(epoch_ms: u64, offset_minutes: i32) -> String {
+    shifted(epoch_ms, offset_minutes)[11..19].to_string()
+}
+
+/// `YYYY-MM-DD HH:MM (UTC+2)` at the given offset from UTC
+pub fn format_datetime(epoch_ms: u64, offset_minutes: i32) -> String {
+    let iso = shifted(epoch_ms, offset_minutes);
+    format!(
+        "{} {} ({})",
+        &iso[..10],
+        &iso[11..16],
+        format_utc_offset(offset_minutes)
+    )
+}
+
+/// `HH:MM:SS` in the browser's time zone
+pub fn format_local_clock(epoch_ms: u64) -> String {
+    format_clock(epoch_ms, local_utc_offset_minutes(epoch_ms))
+}
+
+/// `YYYY-MM-DD HH:MM (UTC+2)` in the browser's time zone
+pub fn format_local_datetime(epoch_ms: u64) -> String {
+    format_datetime(epoch_ms, local_utc_offset_minutes(epoch_ms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_formatting() {
+        assert_eq!(format_duration_ms(850), "850ms");
+        assert_eq!(format_duration_ms(12_340), "12.3s");
+        assert_eq!(format_duration_ms(245_000), "4m 05s");
+
+        assert_eq!(humanize_duration(30), "under a minute");
+        assert_eq!(humanize_duration(700), "about 12 minutes");
+        assert_eq!(humanize_duration(3 * 3600), "about 3 hours");
+        assert_eq!(humanize_duration(3 * 86_400), "about 3 days");
+
+        let now = 1_718_461_845_678;
+        assert_eq!(format_relative(now - 5_000, now), "just now");
+        assert_eq!(format_relative(now - 300_000, now), "about 5 minutes ago");
+        assert_eq!(format_relative(now + 7_200_000, now), "in about 2 hours");
+
+        // 2024-06-15T14:30:45.678Z
+        assert_eq!(format_clock(now, 0), "14:30:45");
+        assert_eq!(format_clock(now, 120), "16:30:45");
+        assert_eq!(format_datetime(now, 0), "2024-06-15 14:30 (UTC)");
+        assert_eq!(format_datetime(now, -330), "2024-06-15 09:00 (UTC-5:30)");
+        assert_eq!(format_datetime(now, 600), "2024-06-16 00:30 (UTC+10)");
+    }
+}
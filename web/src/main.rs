@@ -1,5 +1,7 @@
 use dioxus::prelude::*;
-use ui::MigrationService;
+#[cfg(feature = "demo-mode")]
+use ui::DemoMode;
+use ui::{MigrationService, SelfTest};
 
 const FAVICON: Asset = asset!("/assets/favicon.png");
 const MAIN_CSS: Asset = asset!("/assets/main.css");
@@ -26,6 +28,13 @@ fn App() -> Element {
 enum Route {
     #[route("/")]
     Home {},
+    // Not linked from the app; operators open it to check a deployment
+    #[route("/selftest")]
+    SelfTestPage {},
+    // Migrates a throwaway account between two test PDSes
+    #[cfg(feature = "demo-mode")]
+    #[route("/demo")]
+    DemoPage {},
 }
 
 #[component]
@@ -36,3 +45,18 @@ fn Home() -> Element {
         }
     }
 }
+
+#[component]
+fn SelfTestPage() -> Element {
+    rsx! {
+        SelfTest {}
+    }
+}
+
+#[cfg(feature = "demo-mode")]
+#[component]
+fn DemoPage() -> Element {
+    rsx! {
+        DemoMode {}
+    }
+}